@@ -6,8 +6,14 @@ use aws_sdk_cloudwatchlogs::{
     Client as CWClient,
 };
 use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::future::Future;
 use tracing::{debug, info, warn};
 
+/// Most events `fetch_log_events` returns from one call, not counting
+/// skipped ones; a call that reaches it may have left newer events behind
+pub const MAX_EVENTS_PER_FETCH: usize = 10_000;
+
 /// CloudWatch Logs client wrapper
 pub struct CloudWatchClient {
     client: CWClient,
@@ -50,7 +56,8 @@ impl CloudWatchClient {
     }
 
     /// Fetch log events from a log group using filter pattern
-    /// Returns events from all log streams in the group
+    /// Returns events from all log streams in the group, except those whose
+    /// ids are in `skip_ids`
     pub async fn fetch_log_events(
         &self,
         log_group: &str,
@@ -58,6 +65,7 @@ impl CloudWatchClient {
         end_time: i64,
         filter_pattern: Option<&str>,
         limit: Option<i32>,
+        skip_ids: &HashSet<String>,
     ) -> Result<Vec<LogEvent>> {
         debug!(
             "Fetching logs from group: {} (start: {}, end: {}, filter: {:?})",
@@ -79,45 +87,29 @@ impl CloudWatchClient {
             request = request.limit(lim);
         }
 
-        let mut events = Vec::new();
-        let mut next_token: Option<String> = None;
-
-        // Handle pagination
-        loop {
+        let events = drain_pages(log_group, |next_token| {
             let mut req = request.clone();
-
             if let Some(token) = next_token {
                 req = req.next_token(token);
             }
 
-            let response = req
-                .send()
-                .await
-                .context("Failed to fetch log events from CloudWatch")?;
-
-            if let Some(log_events) = response.events {
-                for event in log_events {
-                    if let Some(log_event) = Self::convert_filtered_event(log_group, event) {
-                        events.push(log_event);
-                    }
-                }
-            }
-
-            // Check for more pages
-            next_token = response.next_token;
-            if next_token.is_none() {
-                break;
+            async move {
+                let response = req
+                    .send()
+                    .await
+                    .context("Failed to fetch log events from CloudWatch")?;
+
+                let events = response
+                    .events
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|event| Self::convert_filtered_event(log_group, event))
+                    .filter(|event| !skip_ids.contains(&event.event_id))
+                    .collect();
+                Ok((events, response.next_token))
             }
-
-            // Safety limit: don't fetch more than 10,000 events in one call
-            if events.len() >= 10_000 {
-                warn!(
-                    "Reached safety limit of 10,000 events for log group: {}",
-                    log_group
-                );
-                break;
-            }
-        }
+        })
+        .await?;
 
         info!(
             "Fetched {} events from log group: {}",
@@ -155,20 +147,29 @@ impl CloudWatchClient {
             request = request.limit(lim);
         }
 
-        let response = request
-            .send()
-            .await
-            .context("Failed to fetch log events from stream")?;
-
-        let mut events = Vec::new();
+        // The forward token of the last page is the one that was sent
+        let events = drain_pages(log_group, |next_token| {
+            let mut req = request.clone();
+            if let Some(token) = next_token {
+                req = req.next_token(token);
+            }
 
-        if let Some(log_events) = response.events {
-            for event in log_events {
-                if let Some(log_event) = Self::convert_output_event(log_group, log_stream, event) {
-                    events.push(log_event);
-                }
+            async move {
+                let response = req
+                    .send()
+                    .await
+                    .context("Failed to fetch log events from stream")?;
+
+                let events = response
+                    .events
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|event| Self::convert_output_event(log_group, log_stream, event))
+                    .collect();
+                Ok((events, response.next_forward_token))
             }
-        }
+        })
+        .await?;
 
         debug!(
             "Fetched {} events from stream: {}/{}",
@@ -231,6 +232,40 @@ impl CloudWatchClient {
     }
 }
 
+/// Collect events page by page until there is no next token, the same token
+/// comes back, or `MAX_EVENTS_PER_FETCH` is reached. `fetch_page` gets the
+/// previous page's token and returns the page's events and next token.
+async fn drain_pages<F, Fut>(log_group: &str, mut fetch_page: F) -> Result<Vec<LogEvent>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<(Vec<LogEvent>, Option<String>)>>,
+{
+    let mut events = Vec::new();
+    let mut next_token: Option<String> = None;
+
+    loop {
+        let (page, token) = fetch_page(next_token.clone()).await?;
+        events.extend(page);
+
+        // Check for more pages
+        if token.is_none() || token == next_token {
+            break;
+        }
+        next_token = token;
+
+        // Safety limit: don't fetch more than MAX_EVENTS_PER_FETCH events in one call
+        if events.len() >= MAX_EVENTS_PER_FETCH {
+            warn!(
+                "Reached safety limit of {} events for log group: {}",
+                MAX_EVENTS_PER_FETCH, log_group
+            );
+            break;
+        }
+    }
+
+    Ok(events)
+}
+
 impl LogEvent {
     /// Convert CloudWatch timestamp (milliseconds) to DateTime<Utc>
     pub fn to_datetime(&self) -> DateTime<Utc> {
@@ -255,4 +290,58 @@ mod tests {
         let dt = event.to_datetime();
         assert!(dt.timestamp() > 0);
     }
+
+    fn events(ids: std::ops::Range<usize>) -> Vec<LogEvent> {
+        ids.map(|i| LogEvent {
+            log_group: "/test".to_string(),
+            log_stream: "stream-1".to_string(),
+            event_id: i.to_string(),
+            message: format!("event {}", i),
+            timestamp: 1700000000000 + i as i64,
+        })
+        .collect()
+    }
+
+    /// Drain `pages` through `drain_pages`, returning the events and the
+    /// token each page was requested with
+    async fn drain(pages: Vec<(Vec<LogEvent>, Option<&str>)>) -> (Vec<LogEvent>, Vec<Option<String>>) {
+        let mut pages = pages.into_iter();
+        let mut requested = Vec::new();
+        let events = drain_pages("/test", |token| {
+            requested.push(token);
+            let (page, next) = pages.next().expect("fetched past the last page");
+            async move { Ok((page, next.map(String::from))) }
+        })
+        .await
+        .unwrap();
+        (events, requested)
+    }
+
+    #[tokio::test]
+    async fn test_all_pages_are_fetched() {
+        // Filtered fetches can return empty pages before the last one
+        let (fetched, requested) = drain(vec![
+            (events(0..3), Some("page-2")),
+            (vec![], Some("page-3")),
+            (events(3..5), None),
+        ])
+        .await;
+        assert_eq!(fetched.iter().map(|e| e.event_id.as_str()).collect::<Vec<_>>(), ["0", "1", "2", "3", "4"]);
+        assert_eq!(requested, vec![None, Some("page-2".to_string()), Some("page-3".to_string())]);
+
+        // Stream fetches end when the forward token comes back unchanged
+        let (fetched, requested) = drain(vec![
+            (events(0..2), Some("f-1")),
+            (events(2..3), Some("f-2")),
+            (vec![], Some("f-2")),
+        ])
+        .await;
+        assert_eq!(fetched.len(), 3);
+        assert_eq!(requested.len(), 3);
+
+        // Paging stops at the safety limit
+        let (fetched, requested) = drain(vec![(events(0..MAX_EVENTS_PER_FETCH), Some("page-2"))]).await;
+        assert_eq!(fetched.len(), MAX_EVENTS_PER_FETCH);
+        assert_eq!(requested.len(), 1);
+    }
 }
@@ -49,6 +49,16 @@ pub struct VectaDBConfig {
     /// Request timeout in seconds (default: 30)
     #[serde(default = "default_timeout")]
     pub timeout_secs: u64,
+
+    /// Times a batch is retried after a connection error or 5xx response
+    /// (default: 2)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, doubled for each
+    /// retry after it (default: 1000)
+    #[serde(default = "default_retry_backoff")]
+    pub retry_backoff_ms: u64,
 }
 
 /// Log group configuration
@@ -109,15 +119,29 @@ pub enum ParserType {
     LangChain,
     /// Built-in LlamaIndex parser
     LlamaIndex,
+    /// Built-in AWS Bedrock model invocation log parser
+    Bedrock,
+    /// Built-in OpenAI ChatCompletion request/response log parser
+    OpenAI,
 }
 
 /// Agent behavior settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentSettings {
-    /// Polling interval in seconds (default: 10)
+    /// Polling interval in seconds (default: 10). Each log group's interval
+    /// adapts between the min and max below depending on its activity; an
+    /// interval outside them widens the range.
     #[serde(default = "default_poll_interval")]
     pub poll_interval_secs: u64,
 
+    /// Shortest interval a busy log group is polled at (default: 1)
+    #[serde(default = "default_min_poll_interval")]
+    pub min_poll_interval_secs: u64,
+
+    /// Longest interval a quiet log group backs off to (default: 60)
+    #[serde(default = "default_max_poll_interval")]
+    pub max_poll_interval_secs: u64,
+
     /// Lookback window in seconds for first poll (default: 300 = 5 minutes)
     #[serde(default = "default_lookback")]
     pub lookback_secs: u64,
@@ -129,15 +153,51 @@ pub struct AgentSettings {
     /// Generate embeddings for events (default: true)
     #[serde(default = "default_true")]
     pub generate_embeddings: bool,
+
+    /// Log groups polled at the same time (default: 4)
+    #[serde(default = "default_max_concurrent_groups")]
+    pub max_concurrent_groups: usize,
+
+    /// Longest wait at startup for VectaDB to become healthy (default: 60)
+    #[serde(default = "default_max_startup_wait")]
+    pub max_startup_wait_secs: u64,
+
+    /// Seconds between VectaDB health checks while running; polling pauses
+    /// while VectaDB is unhealthy (default: 30)
+    #[serde(default = "default_health_check_interval")]
+    pub health_check_interval_secs: u64,
+
+    /// File the last poll time of each log group is saved to on shutdown and
+    /// resumed from on start (default: none, start from the lookback window)
+    #[serde(default)]
+    pub checkpoint_path: Option<String>,
+
+    /// Parse and log events without ingesting them or advancing poll times
+    /// (default: false; also set by `--dry-run` or `DRY_RUN=true`)
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Address to serve Prometheus metrics on at `/metrics`, e.g.
+    /// "0.0.0.0:9464" (default: none, metrics are only logged)
+    #[serde(default)]
+    pub metrics_addr: Option<String>,
 }
 
 impl Default for AgentSettings {
     fn default() -> Self {
         Self {
             poll_interval_secs: default_poll_interval(),
+            min_poll_interval_secs: default_min_poll_interval(),
+            max_poll_interval_secs: default_max_poll_interval(),
             lookback_secs: default_lookback(),
             auto_create_traces: true,
             generate_embeddings: true,
+            max_concurrent_groups: default_max_concurrent_groups(),
+            max_startup_wait_secs: default_max_startup_wait(),
+            health_check_interval_secs: default_health_check_interval(),
+            checkpoint_path: None,
+            dry_run: false,
+            metrics_addr: None,
         }
     }
 }
@@ -151,14 +211,42 @@ fn default_timeout() -> u64 {
     30
 }
 
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_retry_backoff() -> u64 {
+    1000
+}
+
 fn default_poll_interval() -> u64 {
     10
 }
 
+fn default_min_poll_interval() -> u64 {
+    1
+}
+
+fn default_max_poll_interval() -> u64 {
+    60
+}
+
 fn default_lookback() -> u64 {
     300 // 5 minutes
 }
 
+fn default_max_concurrent_groups() -> usize {
+    4
+}
+
+fn default_max_startup_wait() -> u64 {
+    60
+}
+
+fn default_health_check_interval() -> u64 {
+    30
+}
+
 fn default_priority() -> u32 {
     100
 }
@@ -193,6 +281,18 @@ impl AgentConfig {
             anyhow::bail!("VectaDB endpoint cannot be empty");
         }
 
+        if self.agent.min_poll_interval_secs == 0 {
+            anyhow::bail!("min_poll_interval_secs must be at least 1");
+        }
+
+        if self.agent.max_concurrent_groups == 0 {
+            anyhow::bail!("max_concurrent_groups must be at least 1");
+        }
+
+        if self.agent.health_check_interval_secs == 0 {
+            anyhow::bail!("health_check_interval_secs must be at least 1");
+        }
+
         // Validate log groups
         if self.log_groups.is_empty() {
             anyhow::bail!("At least one log group must be configured");
@@ -231,9 +331,14 @@ mod tests {
     fn test_default_settings() {
         let settings = AgentSettings::default();
         assert_eq!(settings.poll_interval_secs, 10);
+        assert_eq!(settings.min_poll_interval_secs, 1);
+        assert_eq!(settings.max_poll_interval_secs, 60);
         assert_eq!(settings.lookback_secs, 300);
         assert!(settings.auto_create_traces);
         assert!(settings.generate_embeddings);
+        assert_eq!(settings.max_concurrent_groups, 4);
+        assert_eq!(settings.max_startup_wait_secs, 60);
+        assert_eq!(settings.health_check_interval_secs, 30);
     }
 
     #[test]
@@ -248,6 +353,8 @@ mod tests {
                 api_key: None,
                 batch_size: 100,
                 timeout_secs: 30,
+                max_retries: 2,
+                retry_backoff_ms: 1000,
             },
             log_groups: vec![LogGroupConfig {
                 name: "/aws/lambda/test".to_string(),
@@ -273,6 +380,8 @@ mod tests {
                 api_key: None,
                 batch_size: 100,
                 timeout_secs: 30,
+                max_retries: 2,
+                retry_backoff_ms: 1000,
             },
             log_groups: vec![],
             agent: AgentSettings::default(),
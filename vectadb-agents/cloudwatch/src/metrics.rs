@@ -0,0 +1,277 @@
+// Agent metrics: a summary logged every poll cycle and Prometheus counters
+// per log group, optionally served on `/metrics`
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::poller::{PollOutcome, PollReport};
+
+/// Totals for one poll cycle
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CycleSummary {
+    /// Log groups polled
+    pub groups: usize,
+    /// Log groups whose fetch or ingestion failed
+    pub failed_groups: usize,
+    pub fetched: usize,
+    pub unparsed: usize,
+    pub ingested: usize,
+    pub rejected: usize,
+}
+
+impl CycleSummary {
+    pub fn from_reports<'a>(reports: impl IntoIterator<Item = &'a PollReport>) -> Self {
+        let mut summary = Self::default();
+        for report in reports {
+            summary.groups += 1;
+            if report.outcome == PollOutcome::Failed {
+                summary.failed_groups += 1;
+            }
+            summary.fetched += report.fetched;
+            summary.unparsed += report.unparsed;
+            summary.ingested += report.ingested;
+            summary.rejected += report.rejected;
+        }
+        summary
+    }
+
+    /// Fraction of fetched events no parser rule matched
+    pub fn parse_failure_rate(&self) -> f64 {
+        if self.fetched == 0 {
+            0.0
+        } else {
+            self.unparsed as f64 / self.fetched as f64
+        }
+    }
+}
+
+/// Running totals for one log group
+#[derive(Debug, Clone, Default)]
+struct GroupMetrics {
+    polls: u64,
+    failed_polls: u64,
+    fetched: u64,
+    unparsed: u64,
+    ingested: u64,
+    rejected: u64,
+    /// When events were last ingested (ms since epoch)
+    last_ingest: Option<i64>,
+    /// Time the group has been polled up to (ms since epoch)
+    polled_up_to: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    cycles: u64,
+    groups: BTreeMap<String, GroupMetrics>,
+}
+
+/// Metrics since the agent started. Kept in memory only.
+#[derive(Debug, Default)]
+pub struct AgentMetrics {
+    totals: Mutex<Totals>,
+}
+
+impl AgentMetrics {
+    /// Count a finished poll cycle
+    pub fn record_cycle(&self) {
+        self.totals.lock().unwrap().cycles += 1;
+    }
+
+    /// Add one poll of `log_group` at `now`, which has been polled up to
+    /// `polled_up_to`
+    pub fn record_poll(&self, log_group: &str, report: &PollReport, polled_up_to: Option<i64>, now: i64) {
+        let mut totals = self.totals.lock().unwrap();
+        let group = totals.groups.entry(log_group.to_string()).or_default();
+        group.polls += 1;
+        if report.outcome == PollOutcome::Failed {
+            group.failed_polls += 1;
+        }
+        group.fetched += report.fetched as u64;
+        group.unparsed += report.unparsed as u64;
+        group.ingested += report.ingested as u64;
+        group.rejected += report.rejected as u64;
+        if report.ingested > 0 {
+            group.last_ingest = Some(now);
+        }
+        group.polled_up_to = polled_up_to;
+    }
+
+    /// The metrics in the Prometheus text format, with ages relative to `now`
+    pub fn render(&self, now: i64) -> String {
+        let totals = self.totals.lock().unwrap();
+        let mut out = String::new();
+
+        metric_header(&mut out, "vectadb_agent_cycles_total", "counter", "Poll cycles completed");
+        let _ = writeln!(out, "vectadb_agent_cycles_total {}", totals.cycles);
+
+        let groups = &totals.groups;
+        let counter = |out: &mut String, name: &str, help: &str, value: fn(&GroupMetrics) -> u64| {
+            group_samples(out, groups, &format!("vectadb_agent_{}_total", name), "counter", help, |g| {
+                Some(value(g).to_string())
+            })
+        };
+        counter(&mut out, "polls", "Polls of the log group", |g| g.polls);
+        counter(&mut out, "poll_failures", "Polls whose fetch or ingestion failed", |g| g.failed_polls);
+        counter(&mut out, "events_fetched", "Events fetched from CloudWatch", |g| g.fetched);
+        counter(&mut out, "events_unparsed", "Fetched events no parser rule matched", |g| g.unparsed);
+        counter(&mut out, "events_ingested", "Events VectaDB accepted", |g| g.ingested);
+        counter(&mut out, "events_rejected", "Events VectaDB rejected", |g| g.rejected);
+
+        // Groups that have never got that far have no sample
+        let seconds_since = |since: Option<i64>| since.map(|since| ((now - since).max(0) as f64 / 1000.0).to_string());
+        group_samples(
+            &mut out,
+            groups,
+            "vectadb_agent_seconds_since_last_ingest",
+            "gauge",
+            "Seconds since events from the log group were last ingested",
+            |g| seconds_since(g.last_ingest),
+        );
+        group_samples(
+            &mut out,
+            groups,
+            "vectadb_agent_lag_seconds",
+            "gauge",
+            "Seconds the log group has not been polled up to yet",
+            |g| seconds_since(g.polled_up_to),
+        );
+
+        out
+    }
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// One sample per log group, skipping groups `value` has none for
+fn group_samples(
+    out: &mut String,
+    groups: &BTreeMap<String, GroupMetrics>,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl Fn(&GroupMetrics) -> Option<String>,
+) {
+    metric_header(out, name, kind, help);
+    for (log_group, group) in groups {
+        if let Some(value) = value(group) {
+            let _ = writeln!(out, "{}{{log_group=\"{}\"}} {}", name, escape_label(log_group), value);
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Answer `GET /metrics` on `addr` for the life of the agent. Anything else
+/// gets a 404; this is a scrape endpoint, not a general HTTP server.
+pub async fn serve(addr: &str, metrics: Arc<AgentMetrics>) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics endpoint to {}", addr))?;
+    info!("Serving metrics on http://{}/metrics", addr);
+
+    loop {
+        let (mut socket, _) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to accept metrics connection: {}", e);
+                continue;
+            }
+        };
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let Ok(read) = socket.read(&mut request).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&request[..read]);
+            let request_line = request.split_whitespace().take(2).collect::<Vec<_>>();
+
+            let response = if request_line == ["GET", "/metrics"] {
+                let body = metrics.render(chrono::Utc::now().timestamp_millis());
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(outcome: PollOutcome, fetched: usize, unparsed: usize, ingested: usize) -> PollReport {
+        PollReport {
+            outcome,
+            fetched,
+            unparsed,
+            ingested,
+            rejected: fetched - ingested,
+        }
+    }
+
+    #[test]
+    fn test_cycle_summary_and_metrics() {
+        let reports = vec![
+            ("/aws/lambda/agent".to_string(), report(PollOutcome::Events, 8, 2, 7)),
+            ("/aws/lambda/quiet".to_string(), report(PollOutcome::Empty, 0, 0, 0)),
+            ("/aws/lambda/down".to_string(), report(PollOutcome::Failed, 0, 0, 0)),
+        ];
+
+        let summary = CycleSummary::from_reports(reports.iter().map(|(_, report)| report));
+        assert_eq!(
+            summary,
+            CycleSummary {
+                groups: 3,
+                failed_groups: 1,
+                fetched: 8,
+                unparsed: 2,
+                ingested: 7,
+                rejected: 1,
+            }
+        );
+        assert_eq!(summary.parse_failure_rate(), 0.25);
+        assert_eq!(CycleSummary::default().parse_failure_rate(), 0.0);
+
+        let metrics = AgentMetrics::default();
+        for (log_group, report) in &reports {
+            let polled_up_to = (report.outcome != PollOutcome::Failed).then_some(10_000);
+            metrics.record_poll(log_group, report, polled_up_to, 10_000);
+        }
+        metrics.record_cycle();
+
+        let rendered = metrics.render(25_000);
+        assert!(rendered.contains("vectadb_agent_cycles_total 1\n"));
+        assert!(rendered.contains("# TYPE vectadb_agent_events_ingested_total counter\n"));
+        assert!(rendered.contains("vectadb_agent_events_ingested_total{log_group=\"/aws/lambda/agent\"} 7\n"));
+        assert!(rendered.contains("vectadb_agent_events_unparsed_total{log_group=\"/aws/lambda/agent\"} 2\n"));
+        assert!(rendered.contains("vectadb_agent_poll_failures_total{log_group=\"/aws/lambda/down\"} 1\n"));
+        assert!(rendered.contains("vectadb_agent_seconds_since_last_ingest{log_group=\"/aws/lambda/agent\"} 15\n"));
+        assert!(rendered.contains("vectadb_agent_lag_seconds{log_group=\"/aws/lambda/quiet\"} 15\n"));
+        // Never ingested, never polled up to anything: no sample
+        assert!(!rendered.contains("vectadb_agent_seconds_since_last_ingest{log_group=\"/aws/lambda/quiet\"}"));
+        assert!(!rendered.contains("vectadb_agent_lag_seconds{log_group=\"/aws/lambda/down\"}"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
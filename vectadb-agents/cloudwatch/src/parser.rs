@@ -8,7 +8,7 @@ use crate::cloudwatch_client::LogEvent;
 use crate::config::{LogGroupConfig, ParserRule, ParserType};
 use crate::vectadb_client::EventIngestionRequest;
 
-/// Log parser with built-in patterns for LangChain, LlamaIndex, etc.
+/// Log parser with built-in patterns for LangChain, LlamaIndex, Bedrock, OpenAI, etc.
 pub struct LogParser {
     /// Built-in regex patterns
     built_in_patterns: BuiltInPatterns,
@@ -27,6 +27,8 @@ struct BuiltInPatterns {
 
     // Generic patterns
     generic_error: Regex,
+    log_level: Regex,
+    timestamp: Regex,
 
     // ID extraction patterns (for resilient trace detection)
     request_id: Regex,
@@ -67,6 +69,16 @@ impl BuiltInPatterns {
                 r"(?i)(?P<level>ERROR|FATAL|EXCEPTION).*?(?P<message>.*?)(?:at|in|from)?\s*(?P<location>.*)"
             ).unwrap(),
 
+            // Any other severity keyword
+            log_level: Regex::new(
+                r"\b(?P<level>(?i:TRACE|DEBUG|INFO|WARN(?:ING)?|CRITICAL))\b"
+            ).unwrap(),
+
+            // ISO-8601-like date with optional time, fraction and offset
+            timestamp: Regex::new(
+                r"(?P<date>\d{4}-\d{2}-\d{2})(?:[T ](?P<time>\d{2}:\d{2}:\d{2})(?:[.,](?P<fraction>\d+))?(?P<offset>Z|[+-]\d{2}:?\d{2})?)?"
+            ).unwrap(),
+
             // ID extraction patterns with multiple fallbacks
             request_id: Regex::new(
                 r"(?i)(?:request[-_]?id|req[-_]?id|trace[-_]?id)[:\s]+(?P<id>[a-zA-Z0-9\-]+)"
@@ -103,6 +115,43 @@ impl BuiltInPatterns {
             .and_then(|caps| caps.name("id"))
             .map(|m| m.as_str().to_string())
     }
+
+    /// Severity of a log line, upper-cased; error severities take precedence
+    fn extract_level(&self, message: &str) -> Option<String> {
+        self.generic_error
+            .captures(message)
+            .or_else(|| self.log_level.captures(message))
+            .and_then(|caps| caps.name("level"))
+            .map(|m| m.as_str().to_uppercase())
+    }
+
+    /// First timestamp in a log line. Times without an offset are taken as
+    /// UTC, and a bare date as midnight UTC.
+    fn extract_timestamp(&self, message: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        let caps = self.timestamp.captures(message)?;
+        let date = chrono::NaiveDate::parse_from_str(&caps["date"], "%Y-%m-%d").ok()?;
+
+        let Some(time) = caps.name("time") else {
+            return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+        };
+        let mut text = format!("{}T{}", date, time.as_str());
+        if let Some(fraction) = caps.name("fraction") {
+            text.push('.');
+            text.push_str(fraction.as_str());
+        }
+
+        match caps.name("offset") {
+            Some(offset) => {
+                text.push_str(offset.as_str());
+                chrono::DateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f%#z")
+                    .ok()
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+            }
+            None => chrono::NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|dt| dt.and_utc()),
+        }
+    }
 }
 
 impl LogParser {
@@ -151,6 +200,8 @@ impl LogParser {
             ParserType::Regex => self.try_parse_regex(event, parser, config),
             ParserType::LangChain => self.try_parse_langchain(event, parser, config),
             ParserType::LlamaIndex => self.try_parse_llamaindex(event, parser, config),
+            ParserType::Bedrock => self.try_parse_bedrock(event, parser, config),
+            ParserType::OpenAI => self.try_parse_openai(event, parser, config),
         }
     }
 
@@ -202,7 +253,7 @@ impl LogParser {
 
         let captures = regex.captures(&event.message)?;
 
-        let properties = self.build_event_from_captures(&captures, parser);
+        let properties = self.build_event_from_captures(&regex, &captures, parser);
 
         Some(self.build_event(event, config, properties, parser.event_type.as_deref()))
     }
@@ -332,17 +383,281 @@ impl LogParser {
         None
     }
 
+    /// Try to parse as a Bedrock model invocation log entry
+    fn try_parse_bedrock(
+        &self,
+        event: &LogEvent,
+        parser: &ParserRule,
+        config: &LogGroupConfig,
+    ) -> Option<EventIngestionRequest> {
+        let log: JsonValue = serde_json::from_str(&event.message).ok()?;
+        if log["schemaType"].as_str() != Some("ModelInvocationLog") {
+            return None;
+        }
+
+        let mut properties = serde_json::Map::new();
+        properties.insert("framework".to_string(), serde_json::json!("bedrock"));
+
+        for (source_key, target_key) in [
+            ("requestId", "request_id"),
+            ("operation", "operation"),
+            ("modelId", "model_id"),
+            ("accountId", "account_id"),
+            ("region", "aws_region"),
+            ("inferenceRegion", "inference_region"),
+            ("errorCode", "error_code"),
+        ] {
+            if let Some(value) = log[source_key].as_str() {
+                properties.insert(target_key.to_string(), serde_json::json!(value));
+            }
+        }
+
+        let error_code = log["errorCode"].as_str();
+        if let Some(code) = error_code {
+            properties.insert(
+                "throttled".to_string(),
+                serde_json::json!(code == "ThrottlingException"),
+            );
+        }
+
+        // Streaming operations only log token counts, Converse also logs usage
+        let body = &log["output"]["outputBodyJson"];
+        let input_tokens = body["usage"]["inputTokens"]
+            .as_u64()
+            .or_else(|| log["input"]["inputTokenCount"].as_u64());
+        let output_tokens = body["usage"]["outputTokens"]
+            .as_u64()
+            .or_else(|| log["output"]["outputTokenCount"].as_u64());
+        if let Some(tokens) = input_tokens {
+            properties.insert("input_tokens".to_string(), serde_json::json!(tokens));
+        }
+        if let Some(tokens) = output_tokens {
+            properties.insert("output_tokens".to_string(), serde_json::json!(tokens));
+        }
+        if let (Some(input), Some(output)) = (input_tokens, output_tokens) {
+            properties.insert("total_tokens".to_string(), serde_json::json!(input + output));
+        }
+        if let Some(latency) = body["metrics"]["latencyMs"].as_u64() {
+            properties.insert("latency_ms".to_string(), serde_json::json!(latency));
+        }
+        if let Some(reason) = body["stopReason"].as_str() {
+            properties.insert("stop_reason".to_string(), serde_json::json!(reason));
+        }
+
+        // The last user message is the prompt; the assistant's reply holds
+        // its text and any tool calls
+        let prompt = log["input"]["inputBodyJson"]["messages"]
+            .as_array()
+            .and_then(|messages| messages.iter().rev().find(|m| m["role"] == "user"))
+            .map(|message| content_text(&message["content"]))
+            .filter(|text| !text.is_empty());
+        if let Some(prompt) = prompt {
+            properties.insert("prompt".to_string(), serde_json::json!(prompt));
+        }
+
+        let content = &body["output"]["message"]["content"];
+        let response = content_text(content);
+        if !response.is_empty() {
+            properties.insert("response".to_string(), serde_json::json!(response));
+        }
+
+        let tool_calls: Vec<JsonValue> = content
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.get("toolUse"))
+            .map(|tool_use| {
+                serde_json::json!({
+                    "tool_use_id": tool_use["toolUseId"],
+                    "name": tool_use["name"],
+                    "input": tool_use["input"],
+                })
+            })
+            .collect();
+
+        let event_type = if error_code.is_some() {
+            "bedrock_error"
+        } else if !tool_calls.is_empty() {
+            "bedrock_tool_use"
+        } else {
+            "bedrock_invocation"
+        };
+        if !tool_calls.is_empty() {
+            properties.insert("tool_calls".to_string(), JsonValue::Array(tool_calls));
+        }
+
+        let mut parsed = self.build_event(
+            event,
+            config,
+            JsonValue::Object(properties),
+            Some(parser.event_type.as_deref().unwrap_or(event_type)),
+        );
+
+        // The request ID groups an invocation's events when nothing else does
+        if parsed.session_id.is_none() {
+            parsed.session_id = log["requestId"].as_str().map(String::from);
+        }
+
+        Some(parsed)
+    }
+
+    /// Try to parse as a logged OpenAI ChatCompletion: a response or
+    /// streaming chunk, a request, or a `{"request": ..., "response": ...}` pair
+    fn try_parse_openai(
+        &self,
+        event: &LogEvent,
+        parser: &ParserRule,
+        config: &LogGroupConfig,
+    ) -> Option<EventIngestionRequest> {
+        let log: JsonValue = serde_json::from_str(&event.message).ok()?;
+        let is_response = |value: &JsonValue| {
+            matches!(
+                value["object"].as_str(),
+                Some("chat.completion") | Some("chat.completion.chunk")
+            )
+        };
+        let is_request = |value: &JsonValue| value["messages"].is_array() && value["model"].is_string();
+
+        let (request, response) = if is_response(&log) {
+            (None, Some(&log))
+        } else if is_request(&log) {
+            (Some(&log), None)
+        } else if is_request(&log["request"]) || is_response(&log["response"]) {
+            (
+                Some(&log["request"]).filter(|r| is_request(r)),
+                Some(&log["response"]).filter(|r| r.is_object()),
+            )
+        } else {
+            return None;
+        };
+
+        let mut properties = serde_json::Map::new();
+        properties.insert("framework".to_string(), serde_json::json!("openai"));
+
+        let model = response
+            .and_then(|r| r["model"].as_str())
+            .or_else(|| request.and_then(|r| r["model"].as_str()));
+        if let Some(model) = model {
+            properties.insert("model_id".to_string(), serde_json::json!(model));
+        }
+
+        if let Some(request) = request {
+            let messages: Vec<JsonValue> = request["messages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|message| {
+                    serde_json::json!({
+                        "role": message["role"],
+                        "content": message_text(&message["content"]),
+                    })
+                })
+                .collect();
+            let prompt = messages
+                .iter()
+                .rev()
+                .find(|m| m["role"] == "user")
+                .map(|m| m["content"].clone());
+            if let Some(prompt) = prompt {
+                properties.insert("prompt".to_string(), prompt);
+            }
+            properties.insert("messages".to_string(), JsonValue::Array(messages));
+        }
+
+        let mut tool_calls = Vec::new();
+        let mut finish_reason = None;
+        let mut error = None;
+        if let Some(response) = response {
+            let streaming = response["object"] == "chat.completion.chunk";
+            properties.insert("streaming".to_string(), serde_json::json!(streaming));
+            if let Some(id) = response["id"].as_str() {
+                properties.insert("completion_id".to_string(), serde_json::json!(id));
+            }
+
+            // Full responses carry a `message`, streaming chunks a `delta`
+            let choice = &response["choices"][0];
+            let message = if streaming { &choice["delta"] } else { &choice["message"] };
+            let text = message_text(&message["content"]);
+            if !text.is_empty() {
+                properties.insert("response".to_string(), serde_json::json!(text));
+            }
+            for call in message["tool_calls"].as_array().into_iter().flatten() {
+                let arguments = &call["function"]["arguments"];
+                // Arguments are a JSON-encoded string; chunks may hold a fragment
+                let arguments = arguments
+                    .as_str()
+                    .and_then(|a| serde_json::from_str(a).ok())
+                    .unwrap_or_else(|| arguments.clone());
+                tool_calls.push(serde_json::json!({
+                    "id": call["id"],
+                    "name": call["function"]["name"],
+                    "arguments": arguments,
+                }));
+            }
+
+            finish_reason = choice["finish_reason"].as_str();
+            if let Some(reason) = finish_reason {
+                properties.insert("finish_reason".to_string(), serde_json::json!(reason));
+            }
+
+            // Streams report usage on their last chunk only, if at all
+            let usage = &response["usage"];
+            for (source_key, target_key) in [
+                ("prompt_tokens", "input_tokens"),
+                ("completion_tokens", "output_tokens"),
+                ("total_tokens", "total_tokens"),
+            ] {
+                if let Some(tokens) = usage[source_key].as_u64() {
+                    properties.insert(target_key.to_string(), serde_json::json!(tokens));
+                }
+            }
+
+            error = response["error"]["message"].as_str();
+            if let Some(message) = error {
+                properties.insert("error".to_string(), serde_json::json!(message));
+            }
+        }
+
+        let event_type = if error.is_some() {
+            "openai_error"
+        } else if !tool_calls.is_empty() || finish_reason == Some("tool_calls") {
+            "openai_tool_use"
+        } else if response.is_some() {
+            "openai_completion"
+        } else {
+            "openai_request"
+        };
+        if !tool_calls.is_empty() {
+            properties.insert("tool_calls".to_string(), JsonValue::Array(tool_calls));
+        }
+
+        let mut parsed = self.build_event(
+            event,
+            config,
+            JsonValue::Object(properties),
+            Some(parser.event_type.as_deref().unwrap_or(event_type)),
+        );
+
+        // Chunks of one stream share the completion ID
+        if parsed.session_id.is_none() {
+            parsed.session_id = response.and_then(|r| r["id"].as_str()).map(String::from);
+        }
+
+        Some(parsed)
+    }
+
     /// Build event properties from regex captures
     fn build_event_from_captures(
         &self,
+        regex: &Regex,
         captures: &regex::Captures,
         parser: &ParserRule,
     ) -> JsonValue {
         let mut properties = serde_json::Map::new();
 
-        // Extract all named capture groups
-        for (name, _) in captures.iter().skip(1).enumerate() {
-            if let Some(matched) = captures.name(&name.to_string()) {
+        // Extract all named capture groups that participated in the match
+        for name in regex.capture_names().flatten() {
+            if let Some(matched) = captures.name(name) {
                 properties.insert(name.to_string(), serde_json::json!(matched.as_str()));
             }
         }
@@ -369,12 +684,21 @@ impl LogParser {
         event: &LogEvent,
         config: &LogGroupConfig,
     ) -> EventIngestionRequest {
-        let properties = serde_json::json!({
-            "message": event.message,
-            "raw_log": true,
-        });
+        let mut properties = serde_json::Map::new();
+        properties.insert("message".to_string(), serde_json::json!(event.message));
+        properties.insert("raw_log".to_string(), serde_json::json!(true));
+
+        if let Some(level) = self.built_in_patterns.extract_level(&event.message) {
+            properties.insert("level".to_string(), serde_json::json!(level));
+        }
+        if let Some(timestamp) = self.built_in_patterns.extract_timestamp(&event.message) {
+            properties.insert(
+                "parsed_timestamp".to_string(),
+                serde_json::json!(timestamp.to_rfc3339()),
+            );
+        }
 
-        self.build_event(event, config, properties, None)
+        self.build_event(event, config, JsonValue::Object(properties), None)
     }
 
     /// Build final EventIngestionRequest with ID extraction
@@ -409,6 +733,31 @@ impl LogParser {
     }
 }
 
+/// Join the `text` blocks of a Bedrock message's content
+fn content_text(content: &JsonValue) -> String {
+    content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of an OpenAI message's content: a plain string, or the `text` of
+/// each part when content is a list of parts
+fn message_text(content: &JsonValue) -> String {
+    match content {
+        JsonValue::String(text) => text.clone(),
+        JsonValue::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
 impl Default for LogParser {
     fn default() -> Self {
         Self::new()
@@ -479,4 +828,295 @@ mod tests {
         assert_eq!(parsed.event_type, Some("tool_call".to_string()));
         assert_eq!(parsed.agent_id, Some("langchain-agent".to_string()));
     }
+
+    #[test]
+    fn test_regex_named_groups() {
+        let parser = LogParser::new();
+        let config = LogGroupConfig {
+            name: "/test".to_string(),
+            agent_id: None,
+            parsers: vec![ParserRule {
+                name: "levels".to_string(),
+                parser_type: ParserType::Regex,
+                pattern: Some(r"^(?P<level>[A-Z]+):\s+(?P<msg>.*?)(?:\s+\((?P<code>\d+)\))?$".to_string()),
+                field_mapping: HashMap::from([("msg".to_string(), "message".to_string())]),
+                event_type: Some("log".to_string()),
+                priority: 10,
+            }],
+            filter_pattern: None,
+        };
+
+        let parsed = parser.parse(&log_event("ERROR: connection refused"), &config);
+        assert_eq!(parsed.event_type, Some("log".to_string()));
+        assert_eq!(parsed.properties["level"], "ERROR");
+        assert_eq!(parsed.properties["msg"], "connection refused");
+        assert_eq!(parsed.properties["message"], "connection refused");
+        // Optional groups that didn't match are left out
+        assert!(parsed.properties.get("code").is_none());
+    }
+
+    #[test]
+    fn test_fallback_extracts_level_and_timestamp() {
+        let parser = LogParser::new();
+        let config = LogGroupConfig {
+            name: "/test".to_string(),
+            agent_id: None,
+            parsers: vec![],
+            filter_pattern: None,
+        };
+
+        let parsed = parser.parse(&log_event("2024-01-01 ERROR something broke"), &config);
+        assert_eq!(parsed.event_type, None);
+        assert_eq!(parsed.properties["message"], "2024-01-01 ERROR something broke");
+        assert_eq!(parsed.properties["raw_log"], true);
+        assert_eq!(parsed.properties["level"], "ERROR");
+        assert_eq!(parsed.properties["parsed_timestamp"], "2024-01-01T00:00:00+00:00");
+
+        let parsed = parser.parse(
+            &log_event("[2024-03-05 14:22:07,123+02:00] warning: disk at 91%"),
+            &config,
+        );
+        assert_eq!(parsed.properties["level"], "WARNING");
+        assert_eq!(parsed.properties["parsed_timestamp"], "2024-03-05T12:22:07.123+00:00");
+
+        let parsed = parser.parse(&log_event("worker started"), &config);
+        assert!(parsed.properties.get("level").is_none());
+        assert!(parsed.properties.get("parsed_timestamp").is_none());
+    }
+
+    fn bedrock_config() -> LogGroupConfig {
+        LogGroupConfig {
+            name: "/aws/bedrock/agent/invocations".to_string(),
+            agent_id: Some("bedrock-agent".to_string()),
+            parsers: vec![ParserRule {
+                name: "bedrock".to_string(),
+                parser_type: ParserType::Bedrock,
+                pattern: None,
+                field_mapping: HashMap::new(),
+                event_type: None,
+                priority: 10,
+            }],
+            filter_pattern: None,
+        }
+    }
+
+    fn json_event(log: JsonValue) -> LogEvent {
+        log_event(&log.to_string())
+    }
+
+    fn log_event(message: &str) -> LogEvent {
+        LogEvent {
+            log_group: "/test".to_string(),
+            log_stream: "stream-1".to_string(),
+            event_id: "1".to_string(),
+            message: message.to_string(),
+            timestamp: 1765933796000,
+        }
+    }
+
+    #[test]
+    fn test_bedrock_invocation() {
+        let parser = LogParser::new();
+        let event = json_event(serde_json::json!({
+            "timestamp": "2025-12-17T01:09:56Z",
+            "accountId": "123456789012",
+            "region": "us-east-1",
+            "requestId": "8d8903c3-e948-4c21-b4d9-6029f785f196",
+            "operation": "ConverseStream",
+            "modelId": "global.anthropic.claude-haiku-4-5-20251001-v1:0",
+            "input": {
+                "inputContentType": "application/json",
+                "inputBodyJson": {
+                    "messages": [
+                        { "role": "user", "content": [{ "text": "What immunizations does patient PAT001 need?" }] }
+                    ],
+                    "system": [{ "text": "You are a helpful healthcare virtual assistant." }]
+                },
+                "inputTokenCount": 1023
+            },
+            "output": {
+                "outputContentType": "application/json",
+                "outputBodyJson": {
+                    "output": {
+                        "message": {
+                            "role": "assistant",
+                            "content": [
+                                { "text": "I'll search the immunization records for PAT001." },
+                                {
+                                    "toolUse": {
+                                        "toolUseId": "tooluse_50nrLqI5R2ChSyM6RY4NFw",
+                                        "name": "LocalFHIRAPI___searchImmunization",
+                                        "type": "tool_use",
+                                        "input": { "search_value": "PAT001" }
+                                    }
+                                }
+                            ]
+                        }
+                    },
+                    "stopReason": "tool_use",
+                    "metrics": { "latencyMs": 858 },
+                    "usage": { "inputTokens": 1023, "outputTokens": 89, "totalTokens": 1112 }
+                },
+                "outputTokenCount": 89
+            },
+            "inferenceRegion": "us-east-2",
+            "schemaType": "ModelInvocationLog",
+            "schemaVersion": "1.0"
+        }));
+
+        let parsed = parser.parse(&event, &bedrock_config());
+        assert_eq!(parsed.event_type, Some("bedrock_tool_use".to_string()));
+        assert_eq!(parsed.agent_id, Some("bedrock-agent".to_string()));
+        assert_eq!(parsed.session_id, Some("8d8903c3-e948-4c21-b4d9-6029f785f196".to_string()));
+
+        let properties = &parsed.properties;
+        assert_eq!(properties["framework"], "bedrock");
+        assert_eq!(properties["model_id"], "global.anthropic.claude-haiku-4-5-20251001-v1:0");
+        assert_eq!(properties["operation"], "ConverseStream");
+        assert_eq!(properties["inference_region"], "us-east-2");
+        assert_eq!(properties["input_tokens"], 1023);
+        assert_eq!(properties["output_tokens"], 89);
+        assert_eq!(properties["total_tokens"], 1112);
+        assert_eq!(properties["latency_ms"], 858);
+        assert_eq!(properties["stop_reason"], "tool_use");
+        assert_eq!(properties["prompt"], "What immunizations does patient PAT001 need?");
+        assert_eq!(properties["response"], "I'll search the immunization records for PAT001.");
+        assert_eq!(
+            properties["tool_calls"],
+            serde_json::json!([{
+                "tool_use_id": "tooluse_50nrLqI5R2ChSyM6RY4NFw",
+                "name": "LocalFHIRAPI___searchImmunization",
+                "input": { "search_value": "PAT001" }
+            }])
+        );
+        assert!(properties.get("error_code").is_none());
+    }
+
+    #[test]
+    fn test_bedrock_throttling_error() {
+        let parser = LogParser::new();
+        let event = json_event(serde_json::json!({
+            "timestamp": "2025-12-17T01:10:02Z",
+            "requestId": "78d218c4-1971-42e5-b4f3-da908c8887a1",
+            "operation": "ConverseStream",
+            "modelId": "global.anthropic.claude-haiku-4-5-20251001-v1:0",
+            "errorCode": "ThrottlingException",
+            "schemaType": "ModelInvocationLog",
+            "schemaVersion": "1.0"
+        }));
+
+        let parsed = parser.parse(&event, &bedrock_config());
+        assert_eq!(parsed.event_type, Some("bedrock_error".to_string()));
+        assert_eq!(parsed.properties["error_code"], "ThrottlingException");
+        assert_eq!(parsed.properties["throttled"], true);
+        assert!(parsed.properties.get("input_tokens").is_none());
+
+        // Other JSON falls through to the fallback event
+        let other = json_event(serde_json::json!({ "level": "INFO", "message": "started" }));
+        assert_eq!(parser.parse(&other, &bedrock_config()).properties["raw_log"], true);
+    }
+
+    fn openai_config() -> LogGroupConfig {
+        LogGroupConfig {
+            name: "/app/openai".to_string(),
+            agent_id: Some("support-bot".to_string()),
+            parsers: vec![ParserRule {
+                name: "openai".to_string(),
+                parser_type: ParserType::OpenAI,
+                pattern: None,
+                field_mapping: HashMap::new(),
+                event_type: None,
+                priority: 10,
+            }],
+            filter_pattern: None,
+        }
+    }
+
+    #[test]
+    fn test_openai_full_response() {
+        let parser = LogParser::new();
+        let event = json_event(serde_json::json!({
+            "request": {
+                "model": "gpt-4o",
+                "messages": [
+                    { "role": "system", "content": "You are a support assistant." },
+                    { "role": "user", "content": [{ "type": "text", "text": "How do I reset my password?" }] }
+                ]
+            },
+            "response": {
+                "id": "chatcmpl-9abc",
+                "object": "chat.completion",
+                "model": "gpt-4o-2024-08-06",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "Open Settings and choose Reset password." },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 42, "completion_tokens": 9, "total_tokens": 51 }
+            }
+        }));
+
+        let parsed = parser.parse(&event, &openai_config());
+        assert_eq!(parsed.event_type, Some("openai_completion".to_string()));
+        assert_eq!(parsed.session_id, Some("chatcmpl-9abc".to_string()));
+
+        let properties = &parsed.properties;
+        assert_eq!(properties["framework"], "openai");
+        assert_eq!(properties["model_id"], "gpt-4o-2024-08-06");
+        assert_eq!(properties["streaming"], false);
+        assert_eq!(properties["messages"][0]["role"], "system");
+        assert_eq!(properties["prompt"], "How do I reset my password?");
+        assert_eq!(properties["response"], "Open Settings and choose Reset password.");
+        assert_eq!(properties["finish_reason"], "stop");
+        assert_eq!(properties["input_tokens"], 42);
+        assert_eq!(properties["output_tokens"], 9);
+        assert_eq!(properties["total_tokens"], 51);
+        assert!(properties.get("tool_calls").is_none());
+    }
+
+    #[test]
+    fn test_openai_tool_calls() {
+        let parser = LogParser::new();
+        let event = json_event(serde_json::json!({
+            "id": "chatcmpl-9def",
+            "object": "chat.completion",
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_abc123",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\":\"Lisbon\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 80, "completion_tokens": 17, "total_tokens": 97 }
+        }));
+
+        let parsed = parser.parse(&event, &openai_config());
+        assert_eq!(parsed.event_type, Some("openai_tool_use".to_string()));
+        assert_eq!(
+            parsed.properties["tool_calls"],
+            serde_json::json!([{ "id": "call_abc123", "name": "get_weather", "arguments": { "city": "Lisbon" } }])
+        );
+        assert_eq!(parsed.properties["finish_reason"], "tool_calls");
+        assert!(parsed.properties.get("response").is_none());
+
+        // A streaming chunk carries its text in a delta
+        let chunk = json_event(serde_json::json!({
+            "id": "chatcmpl-9ghi",
+            "object": "chat.completion.chunk",
+            "model": "gpt-4o-mini",
+            "choices": [{ "index": 0, "delta": { "content": "Hel" }, "finish_reason": null }]
+        }));
+        let parsed = parser.parse(&chunk, &openai_config());
+        assert_eq!(parsed.event_type, Some("openai_completion".to_string()));
+        assert_eq!(parsed.properties["streaming"], true);
+        assert_eq!(parsed.properties["response"], "Hel");
+        assert_eq!(parsed.session_id, Some("chatcmpl-9ghi".to_string()));
+    }
 }
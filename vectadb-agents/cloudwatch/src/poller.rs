@@ -0,0 +1,1073 @@
+// Poll loop: fetch, parse and ingest new events of each configured log group
+
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::cloudwatch_client::{CloudWatchClient, LogEvent, MAX_EVENTS_PER_FETCH};
+use crate::config::{AgentConfig, AgentSettings, LogGroupConfig};
+use crate::metrics::{AgentMetrics, CycleSummary};
+use crate::parser::LogParser;
+use crate::vectadb_client::{BulkEventIngestionResponse, EventIngestionRequest, HealthResponse, VectaDBClient};
+
+/// Where log events are fetched from. Events whose ids are in `seen` are
+/// skipped and don't count toward `MAX_EVENTS_PER_FETCH`.
+pub trait LogFetcher {
+    async fn fetch_log_events(
+        &self,
+        log_group: &str,
+        start_time: i64,
+        end_time: i64,
+        filter_pattern: Option<&str>,
+        seen: &HashSet<String>,
+    ) -> Result<Vec<LogEvent>>;
+}
+
+/// Where parsed events are sent
+pub trait EventSink {
+    async fn ingest_events_bulk(
+        &self,
+        events: Vec<EventIngestionRequest>,
+        auto_create_traces: bool,
+        generate_embeddings: bool,
+    ) -> Result<BulkEventIngestionResponse>;
+
+    async fn health_check(&self) -> Result<HealthResponse>;
+}
+
+impl LogFetcher for CloudWatchClient {
+    async fn fetch_log_events(
+        &self,
+        log_group: &str,
+        start_time: i64,
+        end_time: i64,
+        filter_pattern: Option<&str>,
+        seen: &HashSet<String>,
+    ) -> Result<Vec<LogEvent>> {
+        CloudWatchClient::fetch_log_events(self, log_group, start_time, end_time, filter_pattern, None, seen).await
+    }
+}
+
+impl EventSink for VectaDBClient {
+    async fn ingest_events_bulk(
+        &self,
+        events: Vec<EventIngestionRequest>,
+        auto_create_traces: bool,
+        generate_embeddings: bool,
+    ) -> Result<BulkEventIngestionResponse> {
+        VectaDBClient::ingest_events_bulk(self, events, auto_create_traces, generate_embeddings).await
+    }
+
+    async fn health_check(&self) -> Result<HealthResponse> {
+        VectaDBClient::health_check(self).await
+    }
+}
+
+/// First delay between health checks while VectaDB is unhealthy, doubled up
+/// to the health check interval
+const HEALTH_CHECK_BACKOFF: Duration = Duration::from_millis(250);
+
+/// What polling a log group found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollOutcome {
+    /// Fetching or ingesting failed; the group is retried from the same point
+    Failed,
+    /// No new events
+    Empty,
+    /// New events, all ingested
+    Events,
+    /// The fetch limit was reached, so newer events may be waiting. Holds the
+    /// newest fetched event's timestamp, which the next poll resumes from,
+    /// skipping the events already fetched at it.
+    Full(i64),
+    /// Dry run: this many events were parsed and logged, none ingested
+    DryRun(usize),
+}
+
+/// What polling one log group did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollReport {
+    pub outcome: PollOutcome,
+    /// Events fetched from CloudWatch, all of which are parsed
+    pub fetched: usize,
+    /// Fetched events no parser rule matched, sent as raw logs
+    pub unparsed: usize,
+    /// Events VectaDB accepted
+    pub ingested: usize,
+    /// Events VectaDB rejected
+    pub rejected: usize,
+}
+
+impl PollReport {
+    fn new(outcome: PollOutcome) -> Self {
+        Self {
+            outcome,
+            fetched: 0,
+            unparsed: 0,
+            ingested: 0,
+            rejected: 0,
+        }
+    }
+}
+
+/// When a log group is polled next
+#[derive(Debug, Clone, Copy)]
+struct GroupSchedule {
+    interval: Duration,
+    /// Milliseconds since epoch
+    next_poll: i64,
+}
+
+/// Events ingested at a log group's last poll time. CloudWatch's start time
+/// is inclusive, so the next fetch returns them again and skips them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Boundary {
+    /// Milliseconds since epoch
+    timestamp: i64,
+    event_ids: HashSet<String>,
+}
+
+/// Agent state for tracking last poll time per log group
+#[derive(Debug, Clone)]
+pub struct AgentState {
+    /// Last poll timestamp (milliseconds since epoch) per log group
+    last_poll_times: HashMap<String, i64>,
+    /// Events already ingested at the last poll time, per log group
+    boundaries: HashMap<String, Boundary>,
+    /// Poll interval and next poll time per log group
+    schedules: HashMap<String, GroupSchedule>,
+}
+
+/// What a checkpoint file holds
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    last_poll_times: HashMap<String, i64>,
+    #[serde(default)]
+    boundaries: HashMap<String, Boundary>,
+}
+
+impl AgentState {
+    pub fn new() -> Self {
+        Self {
+            last_poll_times: HashMap::new(),
+            boundaries: HashMap::new(),
+            schedules: HashMap::new(),
+        }
+    }
+
+    /// State saved by `save`, or a fresh state when `path` doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let checkpoint: Checkpoint = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid checkpoint file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Checkpoint::default(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read checkpoint {}", path.display()))
+            }
+        };
+
+        Ok(Self {
+            last_poll_times: checkpoint.last_poll_times,
+            boundaries: checkpoint.boundaries,
+            schedules: HashMap::new(),
+        })
+    }
+
+    /// Write the last poll times and the events seen at them to `path`. The file is replaced in one
+    /// rename, so a crash mid-write leaves the previous checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let checkpoint = Checkpoint {
+            last_poll_times: self.last_poll_times.clone(),
+            boundaries: self.boundaries.clone(),
+        };
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&checkpoint)?)
+            .with_context(|| format!("Failed to write checkpoint {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace checkpoint {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Get last poll time for log group, or calculate initial lookback
+    pub fn get_last_poll_time(&self, log_group: &str, lookback_secs: u64) -> i64 {
+        self.last_poll_times
+            .get(log_group)
+            .copied()
+            .unwrap_or_else(|| {
+                // First poll: look back N seconds
+                let now = chrono::Utc::now().timestamp_millis();
+                now - (lookback_secs as i64 * 1000)
+            })
+    }
+
+    /// Time the log group has been polled up to, if it has been
+    pub fn last_poll_time(&self, log_group: &str) -> Option<i64> {
+        self.last_poll_times.get(log_group).copied()
+    }
+
+    /// Ids of the events already ingested at the log group's last poll time
+    pub fn seen_at_last_poll(&self, log_group: &str) -> HashSet<String> {
+        self.boundaries
+            .get(log_group)
+            .filter(|boundary| self.last_poll_times.get(log_group) == Some(&boundary.timestamp))
+            .map(|boundary| boundary.event_ids.clone())
+            .unwrap_or_default()
+    }
+
+    /// Update last poll time for log group
+    pub fn update_last_poll_time(&mut self, log_group: &str, timestamp: i64) {
+        self.last_poll_times.insert(log_group.to_string(), timestamp);
+    }
+
+    /// Whether the log group is due to be polled; never-polled groups are
+    pub fn is_due(&self, log_group: &str, now: i64) -> bool {
+        self.schedules
+            .get(log_group)
+            .is_none_or(|schedule| schedule.next_poll <= now)
+    }
+
+    /// Current poll interval of the log group
+    pub fn interval(&self, log_group: &str, settings: &AgentSettings) -> Duration {
+        self.schedules
+            .get(log_group)
+            .map_or(Duration::from_secs(settings.poll_interval_secs), |schedule| schedule.interval)
+    }
+
+    /// Record a poll of the log group at `now`: advance its last poll time
+    /// unless it failed, remembering `seen`, the ids of the events ingested
+    /// at that time, and schedule its next poll
+    pub fn record_poll(
+        &mut self,
+        log_group: &str,
+        outcome: PollOutcome,
+        seen: HashSet<String>,
+        settings: &AgentSettings,
+        now: i64,
+    ) {
+        let polled_up_to = match outcome {
+            PollOutcome::Failed | PollOutcome::DryRun(_) => None,
+            PollOutcome::Empty | PollOutcome::Events => Some(now),
+            PollOutcome::Full(newest) => Some(newest),
+        };
+        if let Some(timestamp) = polled_up_to {
+            self.update_last_poll_time(log_group, timestamp);
+
+            // A full fetch can end on the same millisecond again, so the
+            // events seen there accumulate until the group moves past it
+            let boundary = self.boundaries.entry(log_group.to_string()).or_default();
+            if boundary.timestamp != timestamp {
+                boundary.timestamp = timestamp;
+                boundary.event_ids.clear();
+            }
+            boundary.event_ids.extend(seen);
+            if boundary.event_ids.is_empty() {
+                self.boundaries.remove(log_group);
+            }
+        }
+
+        let interval = next_interval(self.interval(log_group, settings), outcome, settings);
+        self.schedules.insert(
+            log_group.to_string(),
+            GroupSchedule {
+                interval,
+                next_poll: now + interval.as_millis() as i64,
+            },
+        );
+    }
+
+    /// How long until any of `log_groups` is due
+    pub fn time_until_next_poll<'a>(&self, log_groups: impl IntoIterator<Item = &'a str>, now: i64) -> Duration {
+        let next_poll = log_groups
+            .into_iter()
+            .map(|log_group| self.schedules.get(log_group).map_or(now, |s| s.next_poll))
+            .min()
+            .unwrap_or(now);
+        Duration::from_millis(next_poll.saturating_sub(now).max(0) as u64)
+    }
+}
+
+/// Poll interval after `outcome`: halved after a full fetch to catch up,
+/// doubled after an empty one to back off, and reset to `poll_interval_secs`
+/// after a normal one, within the configured bounds
+fn next_interval(current: Duration, outcome: PollOutcome, settings: &AgentSettings) -> Duration {
+    let base = Duration::from_secs(settings.poll_interval_secs);
+    let min = Duration::from_secs(settings.min_poll_interval_secs).min(base);
+    let max = Duration::from_secs(settings.max_poll_interval_secs).max(base);
+
+    let next = match outcome {
+        PollOutcome::Full(_) => current / 2,
+        PollOutcome::Empty => current * 2,
+        PollOutcome::Events | PollOutcome::DryRun(_) => base,
+        PollOutcome::Failed => current,
+    };
+    next.clamp(min, max)
+}
+
+/// Poll log groups as they come due until `shutdown` turns true, recording
+/// each cycle in `metrics`. Polling starts once VectaDB is healthy, failing
+/// if it isn't within `max_startup_wait_secs`, and pauses whenever a later
+/// health check fails. A poll cycle in progress is finished first; then the
+/// state is saved to `checkpoint_path`, if configured.
+pub async fn run<F: LogFetcher, S: EventSink>(
+    fetcher: &F,
+    sink: &S,
+    parser: &LogParser,
+    config: &AgentConfig,
+    state: &mut AgentState,
+    metrics: &AgentMetrics,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    // A dry run never sends anything to VectaDB
+    let check_health = !config.agent.dry_run;
+    let health_check_interval = Duration::from_secs(config.agent.health_check_interval_secs);
+
+    if check_health {
+        info!("Checking VectaDB health...");
+        let max_wait = Duration::from_secs(config.agent.max_startup_wait_secs);
+        match wait_until_healthy(sink, Some(max_wait), health_check_interval, &mut shutdown)
+            .await
+            .context("VectaDB is not available")?
+        {
+            Some(health) => info!("VectaDB is healthy: {} v{}", health.status, health.version),
+            None => return Ok(()),
+        }
+    }
+    let mut last_health_check = Instant::now();
+
+    while !*shutdown.borrow() {
+        if check_health && last_health_check.elapsed() >= health_check_interval {
+            if let Err(e) = sink.health_check().await {
+                warn!("VectaDB is unhealthy, pausing polling: {:#}", e);
+                match wait_until_healthy(sink, None, health_check_interval, &mut shutdown).await? {
+                    Some(_) => info!("VectaDB is healthy again, resuming polling"),
+                    None => break,
+                }
+            }
+            last_health_check = Instant::now();
+        }
+
+        info!("Starting poll cycle...");
+
+        let reports = poll_cycle(fetcher, sink, parser, config, state).await;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        for (log_group, report) in &reports {
+            metrics.record_poll(log_group, report, state.last_poll_time(log_group), now);
+        }
+        metrics.record_cycle();
+        let summary = CycleSummary::from_reports(reports.iter().map(|(_, report)| report));
+
+        // Wait until the next log group is due
+        let wait = state.time_until_next_poll(
+            config.log_groups.iter().map(|group| group.name.as_str()),
+            now,
+        );
+        info!(
+            "Poll cycle complete: {} group(s) polled ({} failed), {} events fetched, {} unparsed ({:.1}%), {} ingested, {} rejected; next poll in {:?}",
+            summary.groups,
+            summary.failed_groups,
+            summary.fetched,
+            summary.unparsed,
+            summary.parse_failure_rate() * 100.0,
+            summary.ingested,
+            summary.rejected,
+            wait
+        );
+
+        tokio::select! {
+            biased;
+            // Also taken when the sender is gone, as nothing can stop the loop then
+            _ = shutdown.changed() => break,
+            _ = tokio::time::sleep(wait) => {}
+        }
+    }
+
+    if let Some(path) = &config.agent.checkpoint_path {
+        state.save(path)?;
+        info!("Saved checkpoint to {}", path);
+    }
+    Ok(())
+}
+
+/// Check VectaDB health until it passes, backing off up to `max_backoff`
+/// between checks. Fails once `max_wait` has passed, if given. Returns `None`
+/// if shutdown was requested first.
+pub async fn wait_until_healthy<S: EventSink>(
+    sink: &S,
+    max_wait: Option<Duration>,
+    max_backoff: Duration,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<Option<HealthResponse>> {
+    let started = Instant::now();
+    let mut backoff = HEALTH_CHECK_BACKOFF.min(max_backoff);
+
+    loop {
+        let error = match sink.health_check().await {
+            Ok(health) => return Ok(Some(health)),
+            Err(e) => e,
+        };
+
+        let waited = started.elapsed();
+        let delay = match max_wait {
+            Some(max_wait) if waited >= max_wait => {
+                return Err(error.context(format!("Still unhealthy after {:?}", waited)));
+            }
+            Some(max_wait) => backoff.min(max_wait - waited),
+            None => backoff,
+        };
+        warn!("VectaDB health check failed, retrying in {:?}: {:#}", delay, error);
+
+        tokio::select! {
+            biased;
+            _ = shutdown.changed() => return Ok(None),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        backoff = (backoff * 2).min(max_backoff);
+    }
+}
+
+/// Poll every log group that is due, up to `max_concurrent_groups` at a
+/// time, and report what each poll did. A group's last poll time only
+/// advances when its events were ingested, so failed groups are retried
+/// from the same point next time.
+pub async fn poll_cycle<F: LogFetcher, S: EventSink>(
+    fetcher: &F,
+    sink: &S,
+    parser: &LogParser,
+    config: &AgentConfig,
+    state: &mut AgentState,
+) -> Vec<(String, PollReport)> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let settings = &config.agent;
+
+    // Start times are read up front; state is only written once every poll is done
+    let polls: Vec<_> = config
+        .log_groups
+        .iter()
+        .filter(|group| state.is_due(&group.name, now))
+        .map(|group| {
+            let start_time = state.get_last_poll_time(&group.name, settings.lookback_secs);
+            (group, start_time, state.seen_at_last_poll(&group.name))
+        })
+        .collect();
+
+    let polled: Vec<(String, PollReport, HashSet<String>)> = stream::iter(polls)
+        .map(|(group, start_time, seen)| async move {
+            let (report, seen) =
+                poll_log_group(fetcher, sink, parser, group, settings, start_time, now, &seen).await;
+            (group.name.clone(), report, seen)
+        })
+        .buffer_unordered(settings.max_concurrent_groups.max(1))
+        .collect()
+        .await;
+
+    polled
+        .into_iter()
+        .map(|(log_group, report, seen)| {
+            state.record_poll(&log_group, report.outcome, seen, settings, now);
+            (log_group, report)
+        })
+        .collect()
+}
+
+/// Fetch, parse and ingest one log group's events between `start_time` and
+/// `end_time`, skipping the `seen` ones. Also returns the ids of the fetched
+/// events at the time the next poll resumes from.
+#[allow(clippy::too_many_arguments)]
+async fn poll_log_group<F: LogFetcher, S: EventSink>(
+    fetcher: &F,
+    sink: &S,
+    parser: &LogParser,
+    log_group_config: &LogGroupConfig,
+    settings: &AgentSettings,
+    start_time: i64,
+    end_time: i64,
+    seen: &HashSet<String>,
+) -> (PollReport, HashSet<String>) {
+    let log_group = &log_group_config.name;
+
+    info!(
+        "Polling log group: {} (start: {}, end: {})",
+        log_group, start_time, end_time
+    );
+
+    // Fetch log events from CloudWatch
+    let log_events = match fetcher
+        .fetch_log_events(
+            log_group,
+            start_time,
+            end_time,
+            log_group_config.filter_pattern.as_deref(),
+            seen,
+        )
+        .await
+    {
+        Ok(events) => events,
+        Err(e) => {
+            error!("Failed to fetch logs from {}: {}", log_group, e);
+            return (PollReport::new(PollOutcome::Failed), HashSet::new());
+        }
+    };
+
+    if log_events.is_empty() {
+        info!("No new events in log group: {}", log_group);
+        return (PollReport::new(PollOutcome::Empty), HashSet::new());
+    }
+
+    let mut report = PollReport::new(match log_events.iter().map(|event| event.timestamp).max() {
+        Some(newest) if log_events.len() >= MAX_EVENTS_PER_FETCH => PollOutcome::Full(newest),
+        _ => PollOutcome::Events,
+    });
+    report.fetched = log_events.len();
+
+    let resume_from = match report.outcome {
+        PollOutcome::Full(newest) => newest,
+        _ => end_time,
+    };
+    let seen_at_resume: HashSet<String> = log_events
+        .iter()
+        .filter(|event| event.timestamp == resume_from)
+        .map(|event| event.event_id.clone())
+        .collect();
+
+    info!(
+        "Fetched {} events from log group: {}",
+        log_events.len(),
+        log_group
+    );
+
+    // Parse log events
+    let parsed_events: Vec<_> = log_events
+        .iter()
+        .map(|event| parser.parse(event, log_group_config))
+        .collect();
+
+    // Events no rule matched come back as fallback events
+    report.unparsed = parsed_events
+        .iter()
+        .filter(|event| event.properties["raw_log"] == true)
+        .count();
+    info!("Parsed {} events ({} unparsed)", parsed_events.len(), report.unparsed);
+
+    if settings.dry_run {
+        for event in &parsed_events {
+            info!(
+                "Dry run: would ingest {} event (type: {:?}, agent: {:?}, session: {:?}): {}",
+                log_group, event.event_type, event.agent_id, event.session_id, event.properties
+            );
+        }
+        report.outcome = PollOutcome::DryRun(parsed_events.len());
+        return (report, seen_at_resume);
+    }
+
+    // Send to VectaDB in bulk
+    match sink
+        .ingest_events_bulk(
+            parsed_events,
+            settings.auto_create_traces,
+            settings.generate_embeddings,
+        )
+        .await
+    {
+        Ok(response) => {
+            info!(
+                "Ingestion complete: {} succeeded, {} failed, {} trace(s)",
+                response.ingested,
+                response.failed,
+                response.trace_ids.len()
+            );
+
+            if !response.errors.is_empty() {
+                warn!("Ingestion errors: {:?}", response.errors);
+            }
+            report.ingested = response.ingested;
+            report.rejected = response.failed;
+            (report, seen_at_resume)
+        }
+        Err(e) => {
+            error!("Failed to ingest events for {}: {}", log_group, e);
+            // Don't update last_poll_time so we retry next cycle
+            report.outcome = PollOutcome::Failed;
+            (report, HashSet::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AwsConfig, VectaDBConfig};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Returns one event per fetch, after a delay, and tracks how many
+    /// fetches overlap. Fails for groups named "/fetch-fails".
+    #[derive(Default)]
+    struct MockCloudWatch {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    impl LogFetcher for MockCloudWatch {
+        async fn fetch_log_events(
+            &self,
+            log_group: &str,
+            _start_time: i64,
+            end_time: i64,
+            _filter_pattern: Option<&str>,
+            _seen: &HashSet<String>,
+        ) -> Result<Vec<LogEvent>> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if log_group == "/fetch-fails" {
+                anyhow::bail!("access denied");
+            }
+            Ok(vec![LogEvent {
+                log_group: log_group.to_string(),
+                log_stream: "stream-1".to_string(),
+                event_id: "1".to_string(),
+                message: "INFO request handled".to_string(),
+                timestamp: end_time,
+            }])
+        }
+    }
+
+    /// Records the log group of every ingested event. Fails for events of
+    /// "/ingest-fails", and the first `unhealthy_checks` health checks.
+    #[derive(Default)]
+    struct MockVectaDB {
+        calls: AtomicUsize,
+        ingested: Mutex<Vec<String>>,
+        /// Health checks left to fail
+        unhealthy_checks: AtomicUsize,
+    }
+
+    impl EventSink for MockVectaDB {
+        async fn ingest_events_bulk(
+            &self,
+            events: Vec<EventIngestionRequest>,
+            _auto_create_traces: bool,
+            _generate_embeddings: bool,
+        ) -> Result<BulkEventIngestionResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let groups: Vec<String> = events
+                .iter()
+                .filter_map(|e| e.source.as_ref().map(|s| s.log_group.clone()))
+                .collect();
+            if groups.iter().any(|g| g == "/ingest-fails") {
+                anyhow::bail!("VectaDB unavailable");
+            }
+
+            let ingested = events.len();
+            self.ingested.lock().unwrap().extend(groups);
+            Ok(BulkEventIngestionResponse {
+                ingested,
+                failed: 0,
+                trace_ids: vec![],
+                errors: vec![],
+            })
+        }
+
+        async fn health_check(&self) -> Result<HealthResponse> {
+            if self
+                .unhealthy_checks
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+                .is_ok()
+            {
+                anyhow::bail!("connection refused");
+            }
+            Ok(HealthResponse {
+                status: "healthy".to_string(),
+                version: "test".to_string(),
+                ontology_loaded: true,
+            })
+        }
+    }
+
+    /// A VectaDB that answers successive bulk requests with `statuses`, then
+    /// 200, accepting one event per request. Returns its endpoint and a
+    /// count of requests received.
+    async fn mock_vectadb_server(statuses: Vec<u16>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                // Read the whole request before answering
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length || read == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                let n = received.fetch_add(1, Ordering::SeqCst);
+                let status = statuses.get(n).copied().unwrap_or(200);
+                let body = if status == 200 {
+                    r#"{"ingested":1,"failed":0,"trace_ids":[],"errors":[]}"#
+                } else {
+                    "unavailable"
+                };
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (endpoint, requests)
+    }
+
+    fn config(groups: &[&str], max_concurrent_groups: usize) -> AgentConfig {
+        AgentConfig {
+            aws: AwsConfig {
+                region: "us-east-1".to_string(),
+                profile: None,
+            },
+            vectadb: VectaDBConfig {
+                endpoint: "http://localhost:8080".to_string(),
+                api_key: None,
+                batch_size: 100,
+                timeout_secs: 30,
+                max_retries: 2,
+                retry_backoff_ms: 1000,
+            },
+            log_groups: groups
+                .iter()
+                .map(|name| LogGroupConfig {
+                    name: name.to_string(),
+                    agent_id: None,
+                    parsers: vec![],
+                    filter_pattern: None,
+                })
+                .collect(),
+            agent: AgentSettings {
+                max_concurrent_groups,
+                ..AgentSettings::default()
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_groups_are_polled_concurrently() {
+        let config = config(&["/a", "/b", "/c", "/d", "/e", "/f"], 3);
+        let cloudwatch = MockCloudWatch::default();
+        let vectadb = MockVectaDB::default();
+        let mut state = AgentState::new();
+
+        let before = chrono::Utc::now().timestamp_millis();
+        poll_cycle(&cloudwatch, &vectadb, &LogParser::new(), &config, &mut state).await;
+
+        assert_eq!(cloudwatch.max_in_flight.load(Ordering::SeqCst), 3);
+        let mut ingested = vectadb.ingested.lock().unwrap().clone();
+        ingested.sort();
+        assert_eq!(ingested, vec!["/a", "/b", "/c", "/d", "/e", "/f"]);
+        for group in ["/a", "/b", "/c", "/d", "/e", "/f"] {
+            assert!(state.last_poll_times[group] >= before);
+        }
+
+        // No group is due again until its interval has passed
+        poll_cycle(&cloudwatch, &vectadb, &LogParser::new(), &config, &mut state).await;
+        assert_eq!(vectadb.ingested.lock().unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_cycle_and_saves_checkpoint() {
+        let checkpoint = std::env::temp_dir().join(format!(
+            "vectadb-agent-checkpoint-{}-{}.json",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let mut config = config(&["/a", "/b"], 2);
+        config.agent.checkpoint_path = Some(checkpoint.to_string_lossy().into_owned());
+
+        let cloudwatch = MockCloudWatch::default();
+        let vectadb = MockVectaDB::default();
+        let parser = LogParser::new();
+        let metrics = AgentMetrics::default();
+        let mut state = AgentState::new();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        // Shutdown arrives while the first cycle's fetches are in flight
+        let stop = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            shutdown_tx.send(true).unwrap();
+        };
+        let (result, ()) = tokio::join!(
+            run(&cloudwatch, &vectadb, &parser, &config, &mut state, &metrics, shutdown_rx),
+            stop
+        );
+        result.unwrap();
+
+        // The cycle was finished rather than cut short
+        assert_eq!(vectadb.ingested.lock().unwrap().len(), 2);
+        assert!(metrics.render(chrono::Utc::now().timestamp_millis()).contains("vectadb_agent_cycles_total 1\n"));
+
+        let restored = AgentState::load(&checkpoint).unwrap();
+        std::fs::remove_file(&checkpoint).unwrap();
+        assert_eq!(restored.last_poll_times, state.last_poll_times);
+        assert_eq!(restored.last_poll_times.len(), 2);
+        assert_eq!(restored.boundaries, state.boundaries);
+
+        // A missing checkpoint starts fresh
+        assert!(AgentState::load(&checkpoint).unwrap().last_poll_times.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_polling_waits_for_vectadb_to_become_healthy() {
+        let mut config = config(&["/a"], 1);
+        config.agent.health_check_interval_secs = 1;
+        let cloudwatch = MockCloudWatch::default();
+        let vectadb = MockVectaDB {
+            unhealthy_checks: AtomicUsize::new(2),
+            ..Default::default()
+        };
+        let parser = LogParser::new();
+        let metrics = AgentMetrics::default();
+        let mut state = AgentState::new();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        // Checks back off 250ms, then 500ms; the first poll follows
+        let stop = async {
+            tokio::time::sleep(Duration::from_millis(1_500)).await;
+            shutdown_tx.send(true).unwrap();
+        };
+        let (result, ()) = tokio::join!(
+            run(&cloudwatch, &vectadb, &parser, &config, &mut state, &metrics, shutdown_rx),
+            stop
+        );
+        result.unwrap();
+
+        assert_eq!(vectadb.unhealthy_checks.load(Ordering::SeqCst), 0);
+        assert_eq!(*vectadb.ingested.lock().unwrap(), vec!["/a"]);
+
+        // A VectaDB that never comes up fails startup once the wait is over
+        config.agent.max_startup_wait_secs = 0;
+        let vectadb = MockVectaDB {
+            unhealthy_checks: AtomicUsize::new(1),
+            ..Default::default()
+        };
+        let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+        let result = run(&cloudwatch, &vectadb, &parser, &config, &mut AgentState::new(), &metrics, shutdown_rx).await;
+        assert!(result.is_err());
+        assert!(vectadb.ingested.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_parses_without_ingesting() {
+        let mut config = config(&["/a", "/b"], 2);
+        config.agent.dry_run = true;
+        let cloudwatch = MockCloudWatch::default();
+        let vectadb = MockVectaDB::default();
+        let parser = LogParser::new();
+
+        let (report, _) = poll_log_group(
+            &cloudwatch,
+            &vectadb,
+            &parser,
+            &config.log_groups[0],
+            &config.agent,
+            0,
+            1_000,
+            &HashSet::new(),
+        )
+        .await;
+        assert_eq!(report.outcome, PollOutcome::DryRun(1));
+        assert_eq!(report.fetched, 1);
+
+        let mut state = AgentState::new();
+        poll_cycle(&cloudwatch, &vectadb, &parser, &config, &mut state).await;
+        assert_eq!(vectadb.calls.load(Ordering::SeqCst), 0);
+        // Nothing was ingested, so the next poll fetches the same window
+        assert!(state.last_poll_times.is_empty());
+        assert_eq!(state.interval("/a", &config.agent), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_poll_interval_adapts_to_activity() {
+        let settings = AgentSettings::default();
+        let mut state = AgentState::new();
+        let now = 1_700_000_000_000;
+        assert!(state.is_due("/busy", now));
+
+        // A full fetch resumes from its newest event, and sooner
+        state.record_poll("/busy", PollOutcome::Full(now - 500), HashSet::new(), &settings, now);
+        assert_eq!(state.last_poll_times["/busy"], now - 500);
+        assert_eq!(state.interval("/busy", &settings), Duration::from_secs(5));
+        assert!(!state.is_due("/busy", now + 4_999));
+        assert!(state.is_due("/busy", now + 5_000));
+
+        for _ in 0..5 {
+            state.record_poll("/busy", PollOutcome::Full(now), HashSet::new(), &settings, now);
+        }
+        assert_eq!(state.interval("/busy", &settings), Duration::from_secs(1));
+
+        state.record_poll("/busy", PollOutcome::Events, HashSet::new(), &settings, now);
+        assert_eq!(state.interval("/busy", &settings), Duration::from_secs(10));
+
+        // Empty polls back off up to the maximum
+        let mut intervals = Vec::new();
+        for _ in 0..4 {
+            state.record_poll("/quiet", PollOutcome::Empty, HashSet::new(), &settings, now);
+            intervals.push(state.interval("/quiet", &settings).as_secs());
+        }
+        assert_eq!(intervals, vec![20, 40, 60, 60]);
+
+        state.record_poll("/quiet", PollOutcome::Failed, HashSet::new(), &settings, now);
+        assert_eq!(state.interval("/quiet", &settings), Duration::from_secs(60));
+        assert_eq!(
+            state.time_until_next_poll(["/busy", "/quiet"], now),
+            Duration::from_secs(10)
+        );
+        assert_eq!(state.time_until_next_poll(["/busy", "/new"], now), Duration::ZERO);
+    }
+
+    /// Serves `events` like CloudWatch: those in the window, oldest first,
+    /// up to the fetch limit
+    struct LogStore {
+        events: Vec<LogEvent>,
+    }
+
+    impl LogFetcher for LogStore {
+        async fn fetch_log_events(
+            &self,
+            _log_group: &str,
+            start_time: i64,
+            end_time: i64,
+            _filter_pattern: Option<&str>,
+            seen: &HashSet<String>,
+        ) -> Result<Vec<LogEvent>> {
+            Ok(self
+                .events
+                .iter()
+                .filter(|event| (start_time..=end_time).contains(&event.timestamp))
+                .filter(|event| !seen.contains(&event.event_id))
+                .take(MAX_EVENTS_PER_FETCH)
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_fetches_resume_past_seen_events() {
+        // More events than one fetch returns, all in the same millisecond
+        let events = (0..MAX_EVENTS_PER_FETCH + 5)
+            .map(|i| LogEvent {
+                log_group: "/a".to_string(),
+                log_stream: "stream-1".to_string(),
+                event_id: i.to_string(),
+                message: "INFO request handled".to_string(),
+                timestamp: 1_000,
+            })
+            .collect();
+        let cloudwatch = LogStore { events };
+        let vectadb = MockVectaDB::default();
+        let config = config(&["/a"], 1);
+        let mut state = AgentState::new();
+        state.update_last_poll_time("/a", 1_000);
+
+        let reports = poll_cycle(&cloudwatch, &vectadb, &LogParser::new(), &config, &mut state).await;
+        assert_eq!(reports[0].1.outcome, PollOutcome::Full(1_000));
+        assert_eq!(state.last_poll_times["/a"], 1_000);
+        assert_eq!(state.seen_at_last_poll("/a").len(), MAX_EVENTS_PER_FETCH);
+
+        // The next poll starts at the same millisecond but only fetches the rest
+        state.schedules.clear();
+        let reports = poll_cycle(&cloudwatch, &vectadb, &LogParser::new(), &config, &mut state).await;
+        assert_eq!(reports[0].1.outcome, PollOutcome::Events);
+        assert_eq!(reports[0].1.fetched, 5);
+        assert_eq!(vectadb.ingested.lock().unwrap().len(), MAX_EVENTS_PER_FETCH + 5);
+        assert!(state.last_poll_times["/a"] > 1_000);
+        assert!(state.seen_at_last_poll("/a").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_groups_keep_their_poll_time() {
+        let config = config(&["/ok", "/fetch-fails", "/ingest-fails"], 8);
+        let cloudwatch = MockCloudWatch::default();
+        let vectadb = MockVectaDB::default();
+        let mut state = AgentState::new();
+        state.update_last_poll_time("/fetch-fails", 1_000);
+        state.update_last_poll_time("/ingest-fails", 2_000);
+
+        poll_cycle(&cloudwatch, &vectadb, &LogParser::new(), &config, &mut state).await;
+
+        assert_eq!(cloudwatch.max_in_flight.load(Ordering::SeqCst), 3);
+        assert!(state.last_poll_times["/ok"] > 2_000);
+        assert_eq!(state.last_poll_times["/fetch-fails"], 1_000);
+        assert_eq!(state.last_poll_times["/ingest-fails"], 2_000);
+        assert_eq!(*vectadb.ingested.lock().unwrap(), vec!["/ok"]);
+    }
+
+    #[tokio::test]
+    async fn test_transient_ingest_errors_are_retried_within_one_cycle() {
+        let (endpoint, requests) = mock_vectadb_server(vec![503]).await;
+        let mut config = config(&["/a"], 1);
+        config.vectadb.endpoint = endpoint;
+        config.vectadb.retry_backoff_ms = 10;
+        let vectadb = VectaDBClient::new(&config.vectadb).unwrap();
+        let mut state = AgentState::new();
+
+        let reports = poll_cycle(&MockCloudWatch::default(), &vectadb, &LogParser::new(), &config, &mut state).await;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(reports[0].1.outcome, PollOutcome::Events);
+        assert_eq!(reports[0].1.ingested, 1);
+        assert!(state.last_poll_times.contains_key("/a"));
+
+        // Once retries run out the window is left for the next cycle
+        let (endpoint, requests) = mock_vectadb_server(vec![503, 502, 500]).await;
+        config.vectadb.endpoint = endpoint;
+        let vectadb = VectaDBClient::new(&config.vectadb).unwrap();
+        let mut state = AgentState::new();
+
+        let reports = poll_cycle(&MockCloudWatch::default(), &vectadb, &LogParser::new(), &config, &mut state).await;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        assert_eq!(reports[0].1.outcome, PollOutcome::Failed);
+        assert!(state.last_poll_times.is_empty());
+
+        // A rejected batch is not retried
+        let (endpoint, requests) = mock_vectadb_server(vec![400]).await;
+        config.vectadb.endpoint = endpoint;
+        let vectadb = VectaDBClient::new(&config.vectadb).unwrap();
+        let mut state = AgentState::new();
+
+        let reports = poll_cycle(&MockCloudWatch::default(), &vectadb, &LogParser::new(), &config, &mut state).await;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(reports[0].1.rejected, 1);
+    }
+}
@@ -1,7 +1,7 @@
 // VectaDB API client for event ingestion
 
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
@@ -14,6 +14,16 @@ pub struct VectaDBClient {
     endpoint: String,
     api_key: Option<String>,
     batch_size: usize,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+/// A request VectaDB answered with an error status
+#[derive(Debug, thiserror::Error)]
+#[error("Bulk ingestion failed: {status} - {body}")]
+struct StatusError {
+    status: StatusCode,
+    body: String,
 }
 
 /// Event ingestion request matching VectaDB API schema
@@ -101,6 +111,8 @@ impl VectaDBClient {
             endpoint: config.endpoint.clone(),
             api_key: config.api_key.clone(),
             batch_size: config.batch_size,
+            max_retries: config.max_retries,
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
         })
     }
 
@@ -131,7 +143,11 @@ impl VectaDBClient {
         Ok(health)
     }
 
-    /// Ingest events in bulk with automatic batching and retry
+    /// Ingest events in bulk with automatic batching and retry. Connection
+    /// errors and 5xx responses are retried `max_retries` times with
+    /// exponential backoff; if a batch still fails, the whole call fails so
+    /// the poll window is retried. A batch VectaDB rejects outright is
+    /// counted as failed and the rest are still sent.
     pub async fn ingest_events_bulk(
         &self,
         events: Vec<EventIngestionRequest>,
@@ -174,73 +190,66 @@ impl VectaDBClient {
                 },
             };
 
-            // Retry logic: try up to 3 times
-            let mut attempts = 0;
-            let max_attempts = 3;
-            let mut last_error = None;
-
-            while attempts < max_attempts {
-                attempts += 1;
-
+            let mut attempt = 0;
+            let result = loop {
                 match self.send_bulk_request(&url, &request).await {
-                    Ok(response) => {
-                        total_ingested += response.ingested;
-                        total_failed += response.failed;
-
-                        // Merge trace IDs (deduplicate)
-                        for trace_id in response.trace_ids {
-                            if !all_trace_ids.contains(&trace_id) {
-                                all_trace_ids.push(trace_id);
-                            }
-                        }
-
-                        // Adjust error indices for global batch
-                        for mut error in response.errors {
-                            error.index += batch_idx * self.batch_size;
-                            all_errors.push(error);
-                        }
-
-                        break; // Success
-                    }
-                    Err(e) => {
+                    Err(e) if attempt < self.max_retries && is_transient(&e) => {
+                        let delay = self.retry_backoff * 2u32.saturating_pow(attempt);
+                        attempt += 1;
                         warn!(
-                            "Batch {} attempt {}/{} failed: {}",
+                            "Batch {} failed, retry {}/{} in {:?}: {}",
                             batch_idx + 1,
-                            attempts,
-                            max_attempts,
+                            attempt,
+                            self.max_retries,
+                            delay,
                             e
                         );
-                        last_error = Some(e);
+                        tokio::time::sleep(delay).await;
+                    }
+                    result => break result,
+                }
+            };
 
-                        if attempts < max_attempts {
-                            // Exponential backoff: 1s, 2s, 4s
-                            let delay = Duration::from_secs(2u64.pow(attempts - 1));
-                            tokio::time::sleep(delay).await;
+            match result {
+                Ok(response) => {
+                    total_ingested += response.ingested;
+                    total_failed += response.failed;
+
+                    // Merge trace IDs (deduplicate)
+                    for trace_id in response.trace_ids {
+                        if !all_trace_ids.contains(&trace_id) {
+                            all_trace_ids.push(trace_id);
                         }
                     }
-                }
-            }
 
-            // If all retries failed, mark entire batch as failed
-            if attempts >= max_attempts {
-                error!(
-                    "Batch {} failed after {} attempts: {:?}",
-                    batch_idx + 1,
-                    max_attempts,
-                    last_error
-                );
-                total_failed += batch.len();
-
-                // Add error for each event in failed batch
-                for i in 0..batch.len() {
-                    all_errors.push(IngestionError {
-                        index: batch_idx * self.batch_size + i,
-                        error: format!(
-                            "Batch failed after {} retries: {}",
-                            max_attempts,
-                            last_error.as_ref().map(|e| e.to_string()).unwrap_or_default()
-                        ),
-                    });
+                    // Adjust error indices for global batch
+                    for mut error in response.errors {
+                        error.index += batch_idx * self.batch_size;
+                        all_errors.push(error);
+                    }
+                }
+                // VectaDB is unreachable or failing; retrying later may work
+                Err(e) if is_transient(&e) => {
+                    error!(
+                        "Batch {} failed after {} retries: {}",
+                        batch_idx + 1,
+                        attempt,
+                        e
+                    );
+                    return Err(e.context(format!("Batch {} failed after {} retries", batch_idx + 1, attempt)));
+                }
+                // VectaDB rejected the batch itself; sending it again won't help
+                Err(e) => {
+                    error!("Batch {} rejected: {}", batch_idx + 1, e);
+                    total_failed += batch.len();
+
+                    // Add error for each event in failed batch
+                    for i in 0..batch.len() {
+                        all_errors.push(IngestionError {
+                            index: batch_idx * self.batch_size + i,
+                            error: format!("Batch rejected: {}", e),
+                        });
+                    }
                 }
             }
         }
@@ -275,7 +284,7 @@ impl VectaDBClient {
 
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("Bulk ingestion failed: {} - {}", status, body);
+            return Err(StatusError { status, body }.into());
         }
 
         let bulk_response: BulkEventIngestionResponse = response
@@ -299,6 +308,20 @@ impl VectaDBClient {
     }
 }
 
+/// Whether a failed request may succeed if sent again: VectaDB couldn't be
+/// reached, timed out, or answered 5xx or 429
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_connect() || e.is_timeout();
+        }
+        if let Some(e) = cause.downcast_ref::<StatusError>() {
+            return e.status.is_server_error() || e.status == StatusCode::TOO_MANY_REQUESTS;
+        }
+        false
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,6 +334,8 @@ mod tests {
             api_key: None,
             batch_size: 100,
             timeout_secs: 30,
+            max_retries: 2,
+            retry_backoff_ms: 1000,
         };
 
         let client = VectaDBClient::new(&config);
@@ -0,0 +1,11 @@
+// Generate the gRPC server and client from proto/vectadb.proto
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the bundled protoc unless one is configured
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    println!("cargo:rerun-if-changed=proto/vectadb.proto");
+    tonic_build::compile_protos("proto/vectadb.proto")?;
+    Ok(())
+}
@@ -9,38 +9,12 @@ pub mod analyzer;
 pub use metrics::{MetricsCollector, QueryMetrics, PerformanceMetrics};
 pub use aggregator::{MetricsAggregator, TimeWindow};
 pub use analyzer::{QueryAnalyzer, AnomalyDetector};
+pub use crate::config::AnalyticsConfig;
 
 use serde::{Deserialize, Serialize};
 // Duration reserved for future time window configurations
 // use std::time::Duration;
 
-/// Analytics configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AnalyticsConfig {
-    /// Enable metrics collection
-    pub enabled: bool,
-
-    /// Metrics retention period
-    pub retention_days: u32,
-
-    /// Sampling rate (0.0 to 1.0)
-    pub sampling_rate: f64,
-
-    /// Anomaly detection threshold
-    pub anomaly_threshold: f64,
-}
-
-impl Default for AnalyticsConfig {
-    fn default() -> Self {
-        Self {
-            enabled: true,
-            retention_days: 30,
-            sampling_rate: 1.0,
-            anomaly_threshold: 2.0, // 2 standard deviations
-        }
-    }
-}
-
 /// Metric data point
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricPoint {
@@ -0,0 +1,209 @@
+// Live tail of ingested agent events over WebSocket
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+
+use super::handlers::AppState;
+use super::types::{EventStreamParams, StreamMessage, StreamedEvent};
+use crate::db::{EventRecord, SurrealDBClient};
+
+/// Events buffered per subscriber before slow clients start missing events
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Wait before resubscribing after a live query drops, doubling up to the max
+const RESUBSCRIBE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RESUBSCRIBE_BACKOFF: Duration = Duration::from_secs(30);
+
+type LiveEvents = BoxStream<'static, anyhow::Result<EventRecord>>;
+
+/// Fan-out of newly ingested events to connected stream clients.
+/// Only used when SurrealDB is reached over HTTP, which has no live queries.
+#[derive(Clone)]
+pub struct EventBroadcaster {
+    sender: broadcast::Sender<Arc<StreamedEvent>>,
+}
+
+impl EventBroadcaster {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event; a no-op when nobody is listening
+    pub fn publish(&self, event: StreamedEvent) {
+        let _ = self.sender.send(Arc::new(event));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<StreamedEvent>> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBroadcaster {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Upgrade to a WebSocket that streams events matching the filters.
+/// Over a WebSocket SurrealDB connection events come from a LIVE query, so
+/// writes from every VectaDB instance are seen; otherwise from this process.
+pub async fn stream_events(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<EventStreamParams>,
+) -> Response {
+    // Subscribe before the handshake completes so no event is missed after it
+    match state.surreal.filter(|surreal| surreal.supports_live_queries()) {
+        Some(surreal) => {
+            let subscription = match surreal.subscribe_events(&params.event_filter()).await {
+                Ok(events) => Some(events),
+                Err(e) => {
+                    warn!("Failed to subscribe to live events: {}", e);
+                    None
+                }
+            };
+            ws.on_upgrade(move |socket| forward_live_events(socket, surreal, subscription, params))
+        }
+        None => {
+            let receiver = state.events.subscribe();
+            ws.on_upgrade(move |socket| forward_events(socket, receiver, params))
+        }
+    }
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<Arc<StreamedEvent>>,
+    params: EventStreamParams,
+) {
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let message = match received {
+                    Ok(event) if params.matches(&event) => StreamMessage::Event((*event).clone()),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => StreamMessage::Lagged { skipped },
+                    Err(RecvError::Closed) => break,
+                };
+
+                if !send(&mut socket, &message).await {
+                    break;
+                }
+            }
+            incoming = socket.recv() => if client_closed(incoming) {
+                break;
+            },
+        }
+    }
+
+    debug!("Event stream client disconnected");
+}
+
+/// Forward a database live subscription, resubscribing whenever it drops
+async fn forward_live_events(
+    mut socket: WebSocket,
+    surreal: Arc<SurrealDBClient>,
+    mut subscription: Option<LiveEvents>,
+    params: EventStreamParams,
+) {
+    let filter = params.event_filter();
+    let mut backoff = RESUBSCRIBE_BACKOFF;
+    let mut resubscribing = subscription.is_none();
+
+    'client: loop {
+        let mut events = match subscription.take() {
+            Some(events) => events,
+            None => match surreal.subscribe_events(&filter).await {
+                Ok(events) => {
+                    backoff = RESUBSCRIBE_BACKOFF;
+                    events
+                }
+                Err(e) => {
+                    warn!("Failed to resubscribe to live events: {}", e);
+                    if !wait_or_closed(&mut socket, backoff).await {
+                        break 'client;
+                    }
+                    backoff = (backoff * 2).min(MAX_RESUBSCRIBE_BACKOFF);
+                    continue;
+                }
+            },
+        };
+        if resubscribing && !send(&mut socket, &StreamMessage::Reconnected).await {
+            break;
+        }
+
+        loop {
+            tokio::select! {
+                next = events.next() => match next {
+                    Some(Ok(record)) => {
+                        let message = StreamMessage::Event(StreamedEvent::from(record));
+                        if !send(&mut socket, &message).await {
+                            break 'client;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        warn!("Live event subscription failed: {}", e);
+                        break;
+                    }
+                    None => {
+                        debug!("Live event subscription ended");
+                        break;
+                    }
+                },
+                incoming = socket.recv() => if client_closed(incoming) {
+                    break 'client;
+                },
+            }
+        }
+
+        resubscribing = true;
+        if !wait_or_closed(&mut socket, backoff).await {
+            break;
+        }
+    }
+
+    debug!("Event stream client disconnected");
+}
+
+/// Send a stream message; false once the client is gone
+async fn send(socket: &mut WebSocket, message: &StreamMessage) -> bool {
+    let text = match serde_json::to_string(message) {
+        Ok(text) => text,
+        Err(e) => {
+            debug!("Failed to encode stream message: {}", e);
+            return true;
+        }
+    };
+    socket.send(Message::Text(text)).await.is_ok()
+}
+
+/// Clients only need to send pings; anything else is ignored
+fn client_closed(incoming: Option<Result<Message, axum::Error>>) -> bool {
+    matches!(incoming, Some(Ok(Message::Close(_))) | Some(Err(_)) | None)
+}
+
+/// Sleep for `delay` unless the client disconnects first; false if it did
+async fn wait_or_closed(socket: &mut WebSocket, delay: Duration) -> bool {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return true,
+            incoming = socket.recv() => if client_closed(incoming) {
+                return false;
+            },
+        }
+    }
+}
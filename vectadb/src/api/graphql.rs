@@ -0,0 +1,263 @@
+// GraphQL endpoint: entities and their relations, fetched in one round trip
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Enum, Json as GraphQLJson, Object, Schema};
+use axum::{extract::State, Json};
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+
+use super::handlers::AppState;
+use super::types::RelationDirection;
+use crate::db::{Entity, Relation, SurrealDBClient};
+
+/// Items a list field returns when the query sets no `limit`
+const DEFAULT_LIST_LIMIT: usize = 100;
+
+pub type VectaSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema, rejecting queries nested deeper than `max_depth` fields
+pub fn schema(max_depth: usize) -> VectaSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(max_depth)
+        .finish()
+}
+
+/// Run a GraphQL query. Errors are reported in the response body, as GraphQL
+/// clients expect, so the status is always 200.
+pub async fn graphql(
+    State(state): State<AppState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let schema = schema(state.limits.max_graphql_depth);
+    Json(schema.execute(request.data(state)).await)
+}
+
+fn database<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a SurrealDBClient> {
+    ctx.data::<AppState>()?
+        .surreal
+        .as_deref()
+        .ok_or_else(|| "Database not connected".into())
+}
+
+/// `limit`, or the default, capped at the batch size limit
+fn list_limit(ctx: &Context<'_>, limit: Option<usize>) -> async_graphql::Result<usize> {
+    let max = ctx.data::<AppState>()?.limits.max_batch_size;
+    Ok(limit.unwrap_or(DEFAULT_LIST_LIMIT).min(max))
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+#[graphql(remote = "RelationDirection")]
+enum Direction {
+    Outgoing,
+    Incoming,
+    Both,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A live entity by ID
+    async fn entity(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<GraphQLEntity>> {
+        let entity = database(ctx)?.get_entity(&id).await?;
+        Ok(entity.map(GraphQLEntity))
+    }
+
+    /// Live entities of a type, in ID order
+    async fn entities(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(name = "type")] entity_type: String,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<GraphQLEntity>> {
+        let limit = list_limit(ctx, limit)?;
+        let entities = database(ctx)?.get_entities_page(&entity_type, None, limit).await?;
+        Ok(entities.into_iter().map(GraphQLEntity).collect())
+    }
+}
+
+struct GraphQLEntity(Entity);
+
+#[Object(name = "Entity")]
+impl GraphQLEntity {
+    async fn id(&self) -> String {
+        self.0.id_string()
+    }
+
+    async fn entity_type(&self) -> &str {
+        &self.0.entity_type
+    }
+
+    async fn properties(&self) -> GraphQLJson<&HashMap<String, JsonValue>> {
+        GraphQLJson(&self.0.properties)
+    }
+
+    async fn created_at(&self) -> String {
+        self.0.created_at.to_string()
+    }
+
+    async fn updated_at(&self) -> String {
+        self.0.updated_at.to_string()
+    }
+
+    async fn version(&self) -> u64 {
+        self.0.version
+    }
+
+    /// Relations from (outgoing), to (incoming) or touching (both) the entity
+    async fn relations(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "Direction::Both")] direction: Direction,
+        relation_type: Option<String>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<GraphQLRelation>> {
+        let relations = self.edges(ctx, direction, relation_type.as_deref(), limit).await?;
+        Ok(relations.into_iter().map(|(relation, _)| GraphQLRelation(relation)).collect())
+    }
+
+    /// Live entities at the other end of the entity's relations
+    async fn neighbors(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "Direction::Both")] direction: Direction,
+        relation_type: Option<String>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<GraphQLEntity>> {
+        let mut seen = HashSet::new();
+        let ids: Vec<String> = self
+            .edges(ctx, direction, relation_type.as_deref(), limit)
+            .await?
+            .into_iter()
+            .map(|(_, connected_id)| connected_id)
+            .filter(|id| seen.insert(id.clone()))
+            .collect();
+
+        let mut found: HashMap<String, Entity> = database(ctx)?
+            .get_entities_by_ids(&ids, false)
+            .await?
+            .into_iter()
+            .map(|entity| (entity.id_string(), entity))
+            .collect();
+        Ok(ids.iter().filter_map(|id| found.remove(id)).map(GraphQLEntity).collect())
+    }
+}
+
+impl GraphQLEntity {
+    /// The entity's relations, each with the ID of the entity at its other end
+    async fn edges(
+        &self,
+        ctx: &Context<'_>,
+        direction: Direction,
+        relation_type: Option<&str>,
+        limit: Option<usize>,
+    ) -> async_graphql::Result<Vec<(Relation, String)>> {
+        let surreal = database(ctx)?;
+        let limit = list_limit(ctx, limit)?;
+        let id = self.0.id_string();
+        let direction = RelationDirection::from(direction);
+        let mut edges = Vec::new();
+
+        if direction != RelationDirection::Incoming {
+            let outgoing = surreal.get_outgoing_relations(&id, relation_type).await?;
+            edges.extend(outgoing.into_iter().map(|r| {
+                let target = r.target_id.clone();
+                (r, target)
+            }));
+        }
+        if direction != RelationDirection::Outgoing {
+            let incoming = surreal.get_incoming_relations(&id, relation_type).await?;
+            edges.extend(incoming.into_iter().map(|r| {
+                let source = r.source_id.clone();
+                (r, source)
+            }));
+        }
+
+        edges.truncate(limit);
+        Ok(edges)
+    }
+}
+
+struct GraphQLRelation(Relation);
+
+#[Object(name = "Relation")]
+impl GraphQLRelation {
+    async fn id(&self) -> String {
+        self.0.id_string()
+    }
+
+    async fn relation_type(&self) -> &str {
+        &self.0.relation_type
+    }
+
+    async fn source_id(&self) -> &str {
+        &self.0.source_id
+    }
+
+    async fn target_id(&self) -> &str {
+        &self.0.target_id
+    }
+
+    async fn properties(&self) -> GraphQLJson<&HashMap<String, JsonValue>> {
+        GraphQLJson(&self.0.properties)
+    }
+
+    async fn created_at(&self) -> String {
+        self.0.created_at.to_string()
+    }
+
+    /// The source entity, unless it has been deleted
+    async fn source(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<GraphQLEntity>> {
+        Ok(database(ctx)?.get_entity(&self.0.source_id).await?.map(GraphQLEntity))
+    }
+
+    /// The target entity, unless it has been deleted
+    async fn target(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<GraphQLEntity>> {
+        Ok(database(ctx)?.get_entity(&self.0.target_id).await?.map(GraphQLEntity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LimitsConfig;
+
+    async fn execute(state: AppState, query: &str) -> serde_json::Value {
+        let schema = schema(state.limits.max_graphql_depth);
+        let response = schema.execute(async_graphql::Request::new(query).data(state)).await;
+        serde_json::to_value(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_depth_limit() {
+        let mut state = AppState::new();
+        state.limits = LimitsConfig {
+            max_graphql_depth: 4,
+            ..Default::default()
+        };
+
+        let shallow = execute(state.clone(), "{ entity(id: \"a\") { relations { target { id } } } }").await;
+        // Within the limit, so it fails for want of a database instead
+        assert_eq!(shallow["errors"][0]["message"], "Database not connected");
+
+        let deep = execute(
+            state,
+            "{ entity(id: \"a\") { relations { target { relations { id } } } } }",
+        )
+        .await;
+        assert_eq!(deep["errors"][0]["message"], "Query is nested too deep.");
+        assert!(deep["data"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_default_depth_allows_introspection() {
+        let response = execute(
+            AppState::new(),
+            "{ __schema { types { name fields { name type { kind ofType { kind ofType { kind ofType { kind ofType { name } } } } } } } } }",
+        )
+        .await;
+        assert!(response.get("errors").is_none(), "{}", response);
+        let types = response["data"]["__schema"]["types"].as_array().unwrap();
+        assert!(types.iter().any(|t| t["name"] == "Entity"));
+        assert!(types.iter().any(|t| t["name"] == "Relation"));
+    }
+}
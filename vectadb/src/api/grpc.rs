@@ -0,0 +1,213 @@
+// gRPC interface: client-streaming event ingestion and hybrid queries,
+// served on its own port by the same handlers as the REST API
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use std::future::Future;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status, Streaming};
+
+use super::audit::Actor;
+use super::handlers::{self, AppState};
+use super::types::{BulkEventIngestionRequest, ErrorResponse, EventIngestionRequest, IngestionOptions};
+use crate::query::{HybridQuery, ScoredResult};
+
+pub mod proto {
+    tonic::include_proto!("vectadb.v1");
+}
+
+use proto::vecta_db_server::{VectaDb, VectaDbServer};
+use proto::{Event, IngestError, IngestSummary, QueryRequest, QueryResponse, ScoredEntity};
+
+pub struct GrpcService {
+    state: AppState,
+}
+
+impl GrpcService {
+    pub fn new(state: AppState) -> VectaDbServer<Self> {
+        VectaDbServer::new(Self { state })
+    }
+}
+
+/// Serve gRPC on `listener` until `shutdown` completes
+pub async fn serve(
+    state: AppState,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(GrpcService::new(state))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+}
+
+#[tonic::async_trait]
+impl VectaDb for GrpcService {
+    /// Ingest the stream in batches of `MAX_BATCH_SIZE`, each stored as by
+    /// `POST /api/v1/events/batch`. Malformed events are reported as failures
+    /// without ending the stream.
+    async fn ingest_events(
+        &self,
+        request: Request<Streaming<Event>>,
+    ) -> Result<Response<IngestSummary>, Status> {
+        let batch_size = self.state.limits.max_batch_size.max(1);
+        let actor = Actor::from_addr(request.remote_addr());
+        let mut batches = request.into_inner().enumerate().chunks(batch_size);
+        let mut summary = IngestSummary::default();
+
+        while let Some(batch) = batches.next().await {
+            let mut indices = Vec::with_capacity(batch.len());
+            let mut events = Vec::with_capacity(batch.len());
+            for (index, event) in batch {
+                match EventIngestionRequest::try_from(event?) {
+                    Ok(event) => {
+                        indices.push(index);
+                        events.push(event);
+                    }
+                    Err(error) => {
+                        summary.failed += 1;
+                        summary.errors.push(IngestError { index: index as u64, error });
+                    }
+                }
+            }
+            if events.is_empty() {
+                continue;
+            }
+
+            let bulk = BulkEventIngestionRequest { events, options: stream_options() };
+            let Json(response) = handlers::ingest_events_bulk(State(self.state.clone()), actor.clone(), Json(bulk))
+                .await
+                .map_err(status)?;
+
+            summary.ingested += response.ingested as u64;
+            summary.failed += response.failed as u64;
+            summary.sampled_out += response.sampled_out as u64;
+            for trace_id in response.trace_ids {
+                if !summary.trace_ids.contains(&trace_id) {
+                    summary.trace_ids.push(trace_id);
+                }
+            }
+            summary.errors.extend(response.errors.into_iter().map(|e| IngestError {
+                index: indices[e.index] as u64,
+                error: e.error,
+            }));
+        }
+
+        Ok(Response::new(summary))
+    }
+
+    async fn query(&self, request: Request<QueryRequest>) -> Result<Response<QueryResponse>, Status> {
+        let query: HybridQuery = serde_json::from_str(&request.into_inner().query_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid query: {}", e)))?;
+
+        let Json(result) = handlers::hybrid_query(State(self.state.clone()), Json(query))
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(QueryResponse {
+            results: result.results.into_iter().map(ScoredEntity::from).collect(),
+            total_count: result.total_count as u64,
+            metadata_json: serde_json::to_string(&result.metadata).unwrap_or_default(),
+        }))
+    }
+}
+
+/// Options for streamed events: the bulk endpoint's defaults
+fn stream_options() -> IngestionOptions {
+    IngestionOptions {
+        auto_create_traces: true,
+        generate_embeddings: true,
+        ..Default::default()
+    }
+}
+
+impl TryFrom<Event> for EventIngestionRequest {
+    type Error = String;
+
+    fn try_from(event: Event) -> Result<Self, Self::Error> {
+        let timestamp = DateTime::<Utc>::from_timestamp_millis(event.timestamp_ms)
+            .ok_or_else(|| format!("Invalid timestamp: {}", event.timestamp_ms))?;
+        let properties = if event.properties_json.is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_str(&event.properties_json).map_err(|e| format!("Invalid properties: {}", e))?
+        };
+
+        Ok(EventIngestionRequest {
+            trace_id: event.trace_id,
+            timestamp,
+            event_type: event.event_type,
+            agent_id: event.agent_id,
+            session_id: event.session_id,
+            properties,
+            source: None,
+        })
+    }
+}
+
+impl From<ScoredResult> for ScoredEntity {
+    fn from(result: ScoredResult) -> Self {
+        Self {
+            id: result.entity.id_string(),
+            entity_type: result.entity.entity_type,
+            properties_json: serde_json::to_string(&result.entity.properties).unwrap_or_default(),
+            score: result.score,
+            source: format!("{:?}", result.source),
+            explanation: result.explanation,
+        }
+    }
+}
+
+/// The gRPC equivalent of a handler's HTTP error
+fn status((code, Json(body)): (StatusCode, Json<ErrorResponse>)) -> Status {
+    let message = format!("{}: {}", body.error, body.message);
+    match code {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Status::invalid_argument(message),
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        StatusCode::CONFLICT => Status::already_exists(message),
+        StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS => Status::resource_exhausted(message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::unavailable(message),
+        _ => Status::internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_conversion() {
+        let event = Event {
+            trace_id: Some("trace-1".to_string()),
+            timestamp_ms: 1_700_000_000_000,
+            event_type: Some("tool_call".to_string()),
+            properties_json: r#"{"tool": "search"}"#.to_string(),
+            ..Default::default()
+        };
+        let request = EventIngestionRequest::try_from(event).unwrap();
+        assert_eq!(request.trace_id.as_deref(), Some("trace-1"));
+        assert_eq!(request.timestamp.timestamp_millis(), 1_700_000_000_000);
+        assert_eq!(request.properties["tool"], "search");
+
+        let empty = EventIngestionRequest::try_from(Event::default()).unwrap();
+        assert_eq!(empty.properties, serde_json::json!({}));
+
+        let invalid = Event { properties_json: "{".to_string(), ..Default::default() };
+        assert!(EventIngestionRequest::try_from(invalid).unwrap_err().starts_with("Invalid properties"));
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        let unavailable = status((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("Unavailable", "Database not connected")),
+        ));
+        assert_eq!(unavailable.code(), tonic::Code::Unavailable);
+        assert_eq!(unavailable.message(), "Unavailable: Database not connected");
+
+        let too_large = status((StatusCode::PAYLOAD_TOO_LARGE, Json(ErrorResponse::new("BatchTooLarge", ""))));
+        assert_eq!(too_large.code(), tonic::Code::ResourceExhausted);
+    }
+}
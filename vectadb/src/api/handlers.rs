@@ -1,19 +1,58 @@
 // API handlers for ontology and entity operations
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use std::collections::HashMap;
+use futures::{Stream, StreamExt, TryStreamExt};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use crate::db::{Entity, QdrantClient, Relation, SurrealDBClient};
+use std::time::Duration;
+use surrealdb::sql::Thing;
+use tokio::sync::{mpsc, RwLock};
+use tracing::Instrument;
+
+use crate::config::{CorsConfig, IdempotencyConfig, LimitsConfig, PricingConfig, QueryConfig};
+use crate::db::{
+    bucket_start, is_valid_vector_name, zero_filled_buckets, AuditOperation, CollectionMetaCache,
+    Entity, EventFilter,
+    ExportTable, Histogram, ModelTokenUsage, QdrantClient, Relation, SurrealDBClient, TimeRange, VectorPoint,
+    EVENTS_COLLECTION,
+};
+use crate::db::surrealdb_client::{is_field_path, is_plain_identifier};
 use crate::embeddings::EmbeddingManager;
+use crate::error::VectaDBError;
 use crate::intelligence::OntologyReasoner;
-use crate::ontology::{OntologyLoader, OntologyValidator};
-use crate::query::QueryCoordinator;
+use crate::models::{
+    sort_chain, Agent, AgentWithRelations, CreateAgentRequest, CreateLogRequest, CreateTaskRequest,
+    CreateThoughtRequest, EmbeddingMetadata, Log, Task, TaskWithRelations, Thought, AGENT_ENTITY_TYPE,
+    COLLABORATES_RELATION, EXECUTES_RELATION, FOLLOWS_RELATION, LOG_ENTITY_TYPE, TASK_ENTITY_TYPE,
+    THOUGHT_ENTITY_TYPE,
+};
+use crate::ontology::relation_type::RelationType;
+use crate::ontology::template::render_embedding_template;
+use crate::ontology::validator::ValidationError;
+use crate::ontology::{
+    diff_schemas, entity_json_schema, merge_schemas, OntologyLoader, OntologySchema, OntologyValidator, SchemaDiff,
+    SchemaPatch,
+};
+use crate::query::{QueryCoordinator, TraversalDirection};
+use super::audit::{property_changes, Actor, AuditLogger};
+use super::event_stream::EventBroadcaster;
+use super::idempotency::IdempotencyStore;
+use super::pagination::{keyset_page, PageCursor};
+use super::rate_limit::RateLimiter;
+use super::timeout::RequestTimeouts;
+use super::sampling::TraceSampler;
+use super::tenancy::TenantRegistry;
 use super::types::*;
 
 /// Application state with database clients
@@ -24,6 +63,25 @@ pub struct AppState {
     pub qdrant: Option<Arc<QdrantClient>>,
     pub embedding_service: Option<Arc<EmbeddingManager>>,
     pub query_coordinator: Option<Arc<QueryCoordinator>>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub limits: LimitsConfig,
+    /// Default and maximum result limits for query, search and list endpoints
+    pub query: QueryConfig,
+    /// Token prices for cost estimates
+    pub pricing: PricingConfig,
+    /// Which traces' events are stored at ingestion
+    pub sampler: Arc<TraceSampler>,
+    pub cors: CorsConfig,
+    pub events: EventBroadcaster,
+    /// Per-tenant states when multi-tenancy is enabled
+    pub tenants: Option<Arc<TenantRegistry>>,
+    /// Responses of recent create requests, keyed by `Idempotency-Key`
+    pub idempotency: Arc<IdempotencyStore>,
+    pub timeouts: RequestTimeouts,
+    /// Records creates, updates and deletes when audit logging is enabled
+    pub audit: Option<AuditLogger>,
+    /// Which provider and model built each vector collection
+    pub collection_meta: Arc<CollectionMetaCache>,
 }
 
 impl AppState {
@@ -34,6 +92,18 @@ impl AppState {
             qdrant: None,
             embedding_service: None,
             query_coordinator: None,
+            rate_limiter: None,
+            limits: LimitsConfig::default(),
+            query: QueryConfig::default(),
+            pricing: PricingConfig::default(),
+            sampler: Arc::new(TraceSampler::default()),
+            cors: CorsConfig::default(),
+            events: EventBroadcaster::default(),
+            tenants: None,
+            idempotency: default_idempotency_store(),
+            timeouts: RequestTimeouts::default(),
+            audit: None,
+            collection_meta: Arc::new(CollectionMetaCache::default()),
         }
     }
 
@@ -43,11 +113,13 @@ impl AppState {
         qdrant: Arc<QdrantClient>,
         embedding_service: Arc<EmbeddingManager>,
     ) -> Self {
+        let collection_meta = Arc::new(CollectionMetaCache::default());
         let query_coordinator = Arc::new(QueryCoordinator::new(
             surreal.clone(),
             qdrant.clone(),
             reasoner.clone(),
             embedding_service.clone(),
+            collection_meta.clone(),
         ));
 
         Self {
@@ -56,10 +128,157 @@ impl AppState {
             qdrant: Some(qdrant),
             embedding_service: Some(embedding_service),
             query_coordinator: Some(query_coordinator),
+            rate_limiter: None,
+            limits: LimitsConfig::default(),
+            query: QueryConfig::default(),
+            pricing: PricingConfig::default(),
+            sampler: Arc::new(TraceSampler::default()),
+            cors: CorsConfig::default(),
+            events: EventBroadcaster::default(),
+            tenants: None,
+            idempotency: default_idempotency_store(),
+            timeouts: RequestTimeouts::default(),
+            audit: None,
+            collection_meta,
+        }
+    }
+
+    /// Cache hybrid query results for `ttl`; zero disables the cache
+    pub fn set_query_cache_ttl(&mut self, ttl: Duration) {
+        if let (Some(surreal), Some(qdrant), Some(embedding_service)) =
+            (&self.surreal, &self.qdrant, &self.embedding_service)
+        {
+            self.query_coordinator = Some(Arc::new(
+                QueryCoordinator::new(
+                    surreal.clone(),
+                    qdrant.clone(),
+                    self.reasoner.clone(),
+                    embedding_service.clone(),
+                    self.collection_meta.clone(),
+                )
+                .with_cache_ttl(ttl),
+            ));
+        }
+    }
+
+    /// Queue an audit record of a change made by `actor`, if audit logging is on
+    fn audit(
+        &self,
+        actor: &Actor,
+        operation: AuditOperation,
+        target_type: &str,
+        target_id: &str,
+        changes: serde_json::Value,
+    ) {
+        if let Some(audit) = &self.audit {
+            audit.record(actor.record(operation, target_type, target_id, changes));
+        }
+    }
+}
+
+fn default_idempotency_store() -> Arc<IdempotencyStore> {
+    Arc::new(IdempotencyStore::new(Duration::from_secs(
+        IdempotencyConfig::default().ttl_secs,
+    )))
+}
+
+/// Load the ontology schema persisted in SurrealDB into `reasoner`, if there is one
+pub async fn load_persisted_schema(
+    surreal: &SurrealDBClient,
+    reasoner: &RwLock<Option<OntologyReasoner>>,
+) {
+    match surreal.get_schema().await {
+        Ok(Some(schema)) => {
+            tracing::info!("Loaded ontology schema from database");
+            let loaded = OntologyReasoner::new(schema);
+            loaded.materialize();
+            *reasoner.write().await = Some(loaded);
+            tracing::info!("Ontology reasoner initialized with persisted schema");
+        }
+        Ok(None) => {
+            tracing::info!("No ontology schema found in database");
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load schema from database: {}", e);
+        }
+    }
+}
+
+/// Reconnect SurrealDB whenever it is down: with backoff until the first
+/// connection succeeds (then load the persisted schema), and after any
+/// failed health check later on
+pub async fn keep_surreal_connected(
+    surreal: Arc<SurrealDBClient>,
+    reasoner: Arc<RwLock<Option<OntologyReasoner>>>,
+    mut load_schema: bool,
+) {
+    const HEALTH_INTERVAL: Duration = Duration::from_secs(10);
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+    let mut delay = if load_schema { Duration::from_secs(1) } else { HEALTH_INTERVAL };
+
+    loop {
+        tokio::time::sleep(delay).await;
+
+        if surreal.is_connected() && surreal.health_check().await.unwrap_or(false) {
+            delay = HEALTH_INTERVAL;
+            continue;
+        }
+
+        match surreal.reconnect().await {
+            Ok(()) => {
+                tracing::info!("SurrealDB reconnected");
+                if load_schema {
+                    load_persisted_schema(&surreal, &reasoner).await;
+                    load_schema = false;
+                }
+                delay = HEALTH_INTERVAL;
+            }
+            Err(e) => {
+                tracing::warn!("SurrealDB still unavailable: {:#}", e);
+                delay = (delay * 2).clamp(Duration::from_secs(1), MAX_BACKOFF);
+            }
         }
     }
 }
 
+/// Create the Qdrant collection of every entity type in the loaded ontology
+/// that doesn't have one yet, so first inserts don't pay for (or race on)
+/// collection creation. Returns how many collections were created.
+pub async fn warm_collections(state: &AppState) -> usize {
+    let (Some(surreal), Some(qdrant), Some(embedding_service)) =
+        (&state.surreal, &state.qdrant, &state.embedding_service)
+    else {
+        return 0;
+    };
+    let entity_types: Vec<String> = match state.reasoner.read().await.as_ref() {
+        Some(reasoner) => reasoner.schema().entity_types.keys().cloned().collect(),
+        None => return 0,
+    };
+
+    let mut created = 0;
+    for entity_type in entity_types {
+        let dimension = embedding_service.dimension_for(&entity_type);
+        match qdrant.ensure_collection(&entity_type, dimension as u64).await {
+            Ok(true) => {
+                record_collection_meta(
+                    &state.collection_meta,
+                    surreal,
+                    embedding_service,
+                    &entity_type,
+                    dimension,
+                )
+                .await;
+                created += 1;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to pre-create collection for {}: {}", entity_type, e),
+        }
+    }
+
+    tracing::info!("Warmed up {} Qdrant collections from the ontology", created);
+    created
+}
+
 // ============================================================================
 // Health & Status
 // ============================================================================
@@ -89,77 +308,308 @@ pub async fn health_check(
 // Ontology Management
 // ============================================================================
 
+/// Most invalid entities listed in an existing-data report
+const MAX_INVALID_SAMPLES: usize = 5;
+
 pub async fn upload_schema(
     State(state): State<AppState>,
+    actor: Actor,
+    Query(params): Query<UploadSchemaParams>,
     Json(request): Json<UploadSchemaRequest>,
 ) -> Result<Json<UploadSchemaResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Parse schema based on format
-    let schema = match request.format {
-        SchemaFormat::Json => OntologyLoader::from_json_str(&request.schema),
-        SchemaFormat::Yaml => OntologyLoader::from_yaml_str(&request.schema),
-    }
-    .map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("InvalidSchema", e.to_string())),
-        )
-    })?;
+    let schema = parse_schema(&request)?;
 
     let namespace = schema.namespace.clone();
     let version = schema.version.clone();
 
+    let existing_data = if params.validate_existing_data || params.strict {
+        let report = validate_existing_data(&state, &schema).await?;
+        if params.strict && report.invalid_entities > 0 {
+            let examples: Vec<String> = report
+                .samples
+                .iter()
+                .map(|s| format!("{} '{}': {}", s.entity_type, s.id, s.errors.join("; ")))
+                .collect();
+            return Err(VectaDBError::Conflict(format!(
+                "{} of {} stored entities would fail validation against the new schema (e.g. {})",
+                report.invalid_entities,
+                report.checked_entities,
+                examples.join(", ")
+            ))
+            .with_code("ExistingDataInvalid")
+            .into());
+        }
+        Some(report)
+    } else {
+        None
+    };
+
     // Persist schema to SurrealDB if available
     if let Some(surreal) = &state.surreal {
         surreal
             .store_schema(&schema)
             .await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("DatabaseError", format!("Failed to persist schema: {}", e))),
-                )
-            })?;
+            .map_err(|e| VectaDBError::Database(format!("Failed to persist schema: {}", e)))?;
     }
 
-    // Create new reasoner with schema
+    // Create new reasoner with schema, expanding every type before it
+    // replaces the old one (and its cached expansions)
     let reasoner = OntologyReasoner::new(schema);
+    reasoner.materialize();
 
     // Update state
     let mut state_reasoner = state.reasoner.write().await;
     *state_reasoner = Some(reasoner);
 
+    state.audit(
+        &actor,
+        AuditOperation::Update,
+        "schema",
+        &namespace,
+        serde_json::json!({ "version": version }),
+    );
+
     Ok(Json(UploadSchemaResponse {
         success: true,
         message: "Ontology schema uploaded successfully".to_string(),
         namespace,
         version,
+        existing_data,
+    }))
+}
+
+/// Validate stored entities of the types `schema` would change against it
+async fn validate_existing_data(
+    state: &AppState,
+    schema: &OntologySchema,
+) -> Result<ExistingDataReport, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let checked_types = {
+        let reasoner = state.reasoner.read().await;
+        affected_entity_types(reasoner.as_ref().map(|r| r.schema()), schema)
+    };
+
+    let validator = OntologyValidator::new(schema.clone());
+    let mut report = ExistingDataReport::default();
+    for entity_type in &checked_types {
+        let entities = surreal
+            .query_entities(entity_type, false)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to query entities: {}", e)))?;
+        check_existing_entities(&validator, &entities, &mut report);
+    }
+    report.checked_types = checked_types;
+
+    Ok(report)
+}
+
+/// Entity types whose stored entities may validate differently under
+/// `candidate`: changed and removed types plus subtypes inheriting the
+/// changes, or every type when no schema is loaded yet
+fn affected_entity_types(
+    current: Option<&OntologySchema>,
+    candidate: &OntologySchema,
+) -> Vec<String> {
+    let mut types: Vec<String> = match current {
+        None => candidate.entity_types.keys().cloned().collect(),
+        Some(current) => {
+            let diff = diff_schemas(current, candidate);
+            let mut types = diff.removed_entity_types;
+            for change in diff.modified_entity_types {
+                types.extend(candidate.get_subtypes(&change.id));
+            }
+            types
+        }
+    };
+    types.sort();
+    types.dedup();
+    types
+}
+
+/// Count the entities failing `validator` into `report`
+fn check_existing_entities(
+    validator: &OntologyValidator,
+    entities: &[Entity],
+    report: &mut ExistingDataReport,
+) {
+    for entity in entities {
+        report.checked_entities += 1;
+        if let Err(errors) = validator.validate_entity(&entity.entity_type, &entity.properties) {
+            report.invalid_entities += 1;
+            if report.samples.len() < MAX_INVALID_SAMPLES {
+                report.samples.push(InvalidEntitySample {
+                    id: entity.id_string(),
+                    entity_type: entity.entity_type.clone(),
+                    errors: errors.iter().map(|e| e.to_string()).collect(),
+                });
+            }
+        }
+    }
+}
+
+/// Parse an uploaded schema based on its format
+fn parse_schema(
+    request: &UploadSchemaRequest,
+) -> Result<OntologySchema, (StatusCode, Json<ErrorResponse>)> {
+    match request.format {
+        SchemaFormat::Json => OntologyLoader::from_json_str(&request.schema),
+        SchemaFormat::Yaml => OntologyLoader::from_yaml_str(&request.schema),
+    }
+    .map_err(|e| VectaDBError::BadRequest(e.to_string()).with_code("InvalidSchema").into())
+}
+
+fn parse_schema_patch(
+    request: &UploadSchemaRequest,
+) -> Result<SchemaPatch, (StatusCode, Json<ErrorResponse>)> {
+    let patch = match request.format {
+        SchemaFormat::Json => serde_json::from_str(&request.schema).map_err(|e| e.to_string()),
+        SchemaFormat::Yaml => serde_yaml::from_str(&request.schema).map_err(|e| e.to_string()),
+    };
+    patch.map_err(|e| {
+        VectaDBError::BadRequest(format!("Failed to parse schema patch: {}", e))
+            .with_code("InvalidSchema")
+            .into()
+    })
+}
+
+/// Merge a partial schema into the loaded one. Types and rules the patch
+/// doesn't mention are kept.
+pub async fn patch_schema(
+    State(state): State<AppState>,
+    actor: Actor,
+    Query(params): Query<PatchSchemaParams>,
+    Json(request): Json<UploadSchemaRequest>,
+) -> Result<Json<PatchSchemaResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let patch = parse_schema_patch(&request)?;
+
+    // Held across the merge so concurrent patches can't drop each other's changes
+    let mut reasoner = state.reasoner.write().await;
+
+    let current = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let (schema, changes) = merge_schemas(current.schema(), patch, params.overwrite).map_err(|conflicts| {
+        VectaDBError::Conflict(format!("{} (pass overwrite=true to replace them)", conflicts.join("; ")))
+            .with_code("SchemaConflict")
+    })?;
+
+    schema
+        .validate()
+        .map_err(|e| {
+            VectaDBError::BadRequest(format!("Merged schema is invalid: {}", e))
+                .with_code("InvalidSchema")
+        })?;
+
+    if let Some(surreal) = &state.surreal {
+        surreal
+            .store_schema(&schema)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to persist schema: {}", e)))?;
+    }
+
+    let namespace = schema.namespace.clone();
+    let version = schema.version.clone();
+
+    let merged = OntologyReasoner::new(schema);
+    merged.materialize();
+    *reasoner = Some(merged);
+
+    state.audit(
+        &actor,
+        AuditOperation::Update,
+        "schema",
+        &namespace,
+        serde_json::json!({ "version": version, "changes": changes }),
+    );
+
+    Ok(Json(PatchSchemaResponse {
+        success: true,
+        message: "Ontology schema patched successfully".to_string(),
+        namespace,
+        version,
+        changes,
     }))
 }
 
+/// Compare a candidate schema against the loaded one without applying it
+pub async fn diff_schema(
+    State(state): State<AppState>,
+    Json(request): Json<UploadSchemaRequest>,
+) -> Result<Json<SchemaDiff>, (StatusCode, Json<ErrorResponse>)> {
+    let candidate = parse_schema(&request)?;
+
+    let mut diff = {
+        let reasoner = state.reasoner.read().await;
+        let reasoner = reasoner
+            .as_ref()
+            .ok_or_else(no_schema)?;
+        diff_schemas(reasoner.schema(), &candidate)
+    };
+
+    // Removing a type only breaks things if stored data still uses it
+    if let Some(surreal) = &state.surreal {
+        for type_id in diff.removed_entity_types.clone() {
+            let count = surreal.count_entities(&type_id).await.map_err(|e| {
+                VectaDBError::Database(format!("Failed to count entities: {}", e))
+            })?;
+            if count > 0 {
+                diff.flag_removed_type_in_use("entity", &type_id, count);
+            }
+        }
+        for relation_id in diff.removed_relation_types.clone() {
+            let count = surreal.count_relations(&relation_id).await.map_err(|e| {
+                VectaDBError::Database(format!("Failed to count relations: {}", e))
+            })?;
+            if count > 0 {
+                diff.flag_removed_type_in_use("relation", &relation_id, count);
+            }
+        }
+    }
+
+    Ok(Json(diff))
+}
+
+/// Download the loaded schema as JSON, or as YAML that `upload_schema`
+/// accepts back unchanged
 pub async fn get_schema(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    Query(params): Query<GetSchemaParams>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let format = params.format.unwrap_or_else(|| {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        if accept.contains("yaml") {
+            SchemaFormat::Yaml
+        } else {
+            SchemaFormat::Json
+        }
+    });
+
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
     let schema = reasoner.schema();
-    let json = serde_json::to_value(schema).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse::new("SerializationError", e.to_string())),
-        )
-    })?;
-
-    Ok(Json(json))
+    match format {
+        SchemaFormat::Json => {
+            let json = serde_json::to_value(schema).map_err(VectaDBError::from)?;
+            Ok(Json(json).into_response())
+        }
+        SchemaFormat::Yaml => {
+            let yaml = OntologyLoader::to_yaml_str(schema)?;
+            Ok(([(header::CONTENT_TYPE, "application/yaml")], yaml).into_response())
+        }
+    }
 }
 
 pub async fn get_entity_type(
@@ -168,26 +618,15 @@ pub async fn get_entity_type(
 ) -> Result<Json<GetEntityTypeResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
     let schema = reasoner.schema();
-    let entity_type = schema.entity_types.get(&type_id).ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "TypeNotFound",
-                format!("Entity type '{}' not found", type_id),
-            )),
-        )
-    })?;
+    let entity_type = schema
+        .entity_types
+        .get(&type_id)
+        .ok_or_else(|| type_not_found(&type_id))?;
 
     let properties = entity_type
         .properties
@@ -216,33 +655,41 @@ pub async fn get_entity_type(
     }))
 }
 
+/// JSON Schema of an entity type's properties, inherited ones included
+pub async fn get_entity_type_json_schema(
+    State(state): State<AppState>,
+    axum::extract::Path(type_id): axum::extract::Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let reasoner = state.reasoner.read().await;
+
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let schema = reasoner.schema();
+    let entity_type = schema
+        .entity_types
+        .get(&type_id)
+        .ok_or_else(|| type_not_found(&type_id))?;
+
+    Ok(Json(entity_json_schema(schema, entity_type)))
+}
+
 pub async fn get_subtypes(
     State(state): State<AppState>,
     axum::extract::Path(type_id): axum::extract::Path<String>,
 ) -> Result<Json<GetSubtypesResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
     let schema = reasoner.schema();
 
     // Check if type exists
     if !schema.entity_types.contains_key(&type_id) {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "TypeNotFound",
-                format!("Entity type '{}' not found", type_id),
-            )),
-        ));
+        return Err(type_not_found(&type_id).into());
     }
 
     let subtypes = schema.get_subtypes(&type_id);
@@ -253,44 +700,163 @@ pub async fn get_subtypes(
     }))
 }
 
+pub async fn get_supertypes(
+    State(state): State<AppState>,
+    axum::extract::Path(type_id): axum::extract::Path<String>,
+) -> Result<Json<GetSupertypesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let reasoner = state.reasoner.read().await;
+
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let schema = reasoner.schema();
+
+    // Check if type exists
+    if !schema.entity_types.contains_key(&type_id) {
+        return Err(type_not_found(&type_id).into());
+    }
+
+    let supertypes = schema.get_ancestors(&type_id);
+
+    Ok(Json(GetSupertypesResponse {
+        type_id,
+        supertypes,
+    }))
+}
+
+pub async fn list_relation_types(
+    State(state): State<AppState>,
+) -> Result<Json<ListRelationTypesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let reasoner = state.reasoner.read().await;
+
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let mut relation_types: Vec<GetRelationTypeResponse> = reasoner
+        .schema()
+        .relation_types
+        .values()
+        .map(relation_type_response)
+        .collect();
+    relation_types.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(Json(ListRelationTypesResponse {
+        count: relation_types.len(),
+        relation_types,
+    }))
+}
+
+pub async fn get_relation_type(
+    State(state): State<AppState>,
+    axum::extract::Path(relation_id): axum::extract::Path<String>,
+) -> Result<Json<GetRelationTypeResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let reasoner = state.reasoner.read().await;
+
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let relation_type = reasoner
+        .schema()
+        .relation_types
+        .get(&relation_id)
+        .ok_or_else(|| {
+            VectaDBError::NotFound(format!("Relation type '{}' not found", relation_id))
+                .with_code("TypeNotFound")
+        })?;
+
+    Ok(Json(relation_type_response(relation_type)))
+}
+
+fn relation_type_response(relation_type: &RelationType) -> GetRelationTypeResponse {
+    GetRelationTypeResponse {
+        id: relation_type.id.clone(),
+        label: relation_type.label.clone(),
+        domain: relation_type.domain.clone(),
+        range: relation_type.range.clone(),
+        inverse: relation_type.inverse.clone(),
+        transitive: relation_type.transitive,
+        symmetric: relation_type.symmetric,
+        functional: relation_type.functional,
+        reflexive: relation_type.reflexive,
+    }
+}
+
 // ============================================================================
 // Entity Validation
 // ============================================================================
 
 pub async fn validate_entity(
     State(state): State<AppState>,
-    Json(request): Json<ValidateEntityRequest>,
+    Json(mut request): Json<ValidateEntityRequest>,
 ) -> Result<Json<ValidateEntityResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    // Judge the entity as creation would store it
+    if let Some(entity_type) = reasoner.schema().entity_types.get(&request.entity_type) {
+        entity_type.apply_defaults(reasoner.schema(), &mut request.properties);
+        entity_type.apply_derived(reasoner.schema(), &mut request.properties);
+    }
 
     let validator = OntologyValidator::new(reasoner.schema().clone());
 
-    match validator.validate_entity(&request.entity_type, &request.properties) {
-        Ok(()) => Ok(Json(ValidateEntityResponse {
-            valid: true,
-            errors: vec![],
-        })),
-        Err(errors) => Ok(Json(ValidateEntityResponse {
-            valid: false,
-            errors: errors
-                .into_iter()
-                .map(|e| ValidationErrorInfo {
-                    error_type: format!("{:?}", e).split('(').next().unwrap_or("Error").to_string(),
-                    message: e.to_string(),
-                })
-                .collect(),
-        })),
+    let mut errors: Vec<ValidationErrorInfo> =
+        match validator.validate_entity(&request.entity_type, &request.properties) {
+            Ok(()) => vec![],
+            Err(errors) => errors.iter().map(validation_error_info).collect(),
+        };
+
+    if request.check_data {
+        let surreal = state
+            .surreal
+            .as_ref()
+            .ok_or_else(database_unavailable)?;
+        errors.extend(unique_violations(surreal, reasoner.schema(), &request).await?);
+    }
+
+    Ok(Json(ValidateEntityResponse {
+        valid: errors.is_empty(),
+        errors,
+    }))
+}
+
+/// Unique properties of the request whose value a stored entity of the same type already has
+async fn unique_violations(
+    surreal: &SurrealDBClient,
+    schema: &OntologySchema,
+    request: &ValidateEntityRequest,
+) -> Result<Vec<ValidationErrorInfo>, VectaDBError> {
+    let Some(entity_type) = schema.entity_types.get(&request.entity_type) else {
+        return Ok(vec![]);
+    };
+
+    let mut violations = Vec::new();
+    for property in entity_type.get_all_properties(schema).iter().filter(|p| p.unique) {
+        let value = match request.properties.get(&property.name) {
+            Some(value) if !value.is_null() => value,
+            _ => continue,
+        };
+        let holders = surreal
+            .entity_ids_with_property_value(&request.entity_type, &property.name, value)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to check unique property: {}", e)))?;
+        if let Some(holder) = holders.iter().find(|id| Some(id.as_str()) != request.entity_id.as_deref()) {
+            violations.push(ValidationErrorInfo {
+                error_type: "UniqueViolation".to_string(),
+                message: format!(
+                    "Property '{}' value {} is already used by entity '{}'",
+                    property.name, value, holder
+                ),
+            });
+        }
     }
+    Ok(violations)
 }
 
 pub async fn validate_relation(
@@ -299,31 +865,71 @@ pub async fn validate_relation(
 ) -> Result<Json<ValidateRelationResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
     let validator = OntologyValidator::new(reasoner.schema().clone());
 
-    match validator.validate_relation(
+    let mut errors = Vec::new();
+    if let Err(e) = validator.validate_relation(
         &request.relation_type,
         &request.source_type,
         &request.target_type,
     ) {
-        Ok(()) => Ok(Json(ValidateRelationResponse {
-            valid: true,
-            error: None,
-        })),
-        Err(e) => Ok(Json(ValidateRelationResponse {
-            valid: false,
-            error: Some(e.to_string()),
-        })),
+        errors.push(validation_error_info(&e));
+    }
+
+    if request.check_data {
+        let source_id = request.source_id.as_deref().ok_or_else(|| {
+            VectaDBError::Validation("source_id is required with check_data".to_string())
+        })?;
+        let surreal = state
+            .surreal
+            .as_ref()
+            .ok_or_else(database_unavailable)?;
+
+        if let Some(relation_type) = reasoner.schema().relation_types.get(&request.relation_type) {
+            if relation_type.functional {
+                let existing = surreal
+                    .get_outgoing_relations(source_id, Some(&request.relation_type))
+                    .await
+                    .map_err(|e| VectaDBError::Database(format!("Failed to get relations: {}", e)))?;
+                errors.extend(functional_violation(relation_type, &existing, request.target_id.as_deref()));
+            }
+        }
+    }
+
+    Ok(Json(ValidateRelationResponse {
+        valid: errors.is_empty(),
+        error: errors.first().map(|e| e.message.clone()),
+        errors,
+    }))
+}
+
+/// A functional relation allows one target per source, so any stored
+/// relation of the type to another target is a violation
+fn functional_violation(
+    relation_type: &RelationType,
+    existing: &[Relation],
+    target_id: Option<&str>,
+) -> Option<ValidationErrorInfo> {
+    let conflict = existing
+        .iter()
+        .find(|relation| Some(relation.target_id.as_str()) != target_id)?;
+    Some(ValidationErrorInfo {
+        error_type: "FunctionalViolation".to_string(),
+        message: format!(
+            "Relation '{}' is functional and '{}' already relates to '{}'",
+            relation_type.id, conflict.source_id, conflict.target_id
+        ),
+    })
+}
+
+fn validation_error_info(error: &ValidationError) -> ValidationErrorInfo {
+    ValidationErrorInfo {
+        error_type: format!("{:?}", error).split(['(', ' ']).next().unwrap_or("Error").to_string(),
+        message: error.to_string(),
     }
 }
 
@@ -337,22 +943,13 @@ pub async fn expand_query(
 ) -> Result<Json<ExpandQueryResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
-    let expanded = reasoner.expand_query(&request.entity_type).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new("QueryExpansionError", e.to_string())),
-        )
-    })?;
+    let expanded = reasoner
+        .expand_query(&request.entity_type)
+        .map_err(|e| VectaDBError::BadRequest(e.to_string()).with_code("QueryExpansionError"))?;
 
     let inferred_relations = if request.include_inferred_relations {
         expanded
@@ -377,21 +974,39 @@ pub async fn expand_query(
     }))
 }
 
-pub async fn get_compatible_relations(
+/// Expanded types of every entity type in the loaded schema, served from the
+/// reasoner's cache
+pub async fn get_materialized_expansions(
     State(state): State<AppState>,
-    Json(request): Json<GetCompatibleRelationsRequest>,
-) -> Result<Json<GetCompatibleRelationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<MaterializedExpansionsResponse>, (StatusCode, Json<ErrorResponse>)> {
     let reasoner = state.reasoner.read().await;
 
-    let reasoner = reasoner.as_ref().ok_or_else(|| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse::new(
-                "NoSchema",
-                "No ontology schema loaded",
-            )),
-        )
-    })?;
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
+
+    let expansions = reasoner
+        .materialize()
+        .into_iter()
+        .map(|(entity_type, expanded)| (entity_type, expanded.expanded_types))
+        .collect();
+
+    Ok(Json(MaterializedExpansionsResponse {
+        namespace: reasoner.schema().namespace.clone(),
+        version: reasoner.schema().version.clone(),
+        expansions,
+    }))
+}
+
+pub async fn get_compatible_relations(
+    State(state): State<AppState>,
+    Json(request): Json<GetCompatibleRelationsRequest>,
+) -> Result<Json<GetCompatibleRelationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let reasoner = state.reasoner.read().await;
+
+    let reasoner = reasoner
+        .as_ref()
+        .ok_or_else(no_schema)?;
 
     let compatible_relations = reasoner.get_compatible_relations(
         &request.source_type,
@@ -409,70 +1024,105 @@ pub async fn get_compatible_relations(
 // Entity CRUD
 // ============================================================================
 
+/// Create an entity. Repeats carrying the same `Idempotency-Key` return the original entity.
 pub async fn create_entity(
     State(state): State<AppState>,
+    actor: Actor,
+    headers: HeaderMap,
     Json(request): Json<CreateEntityRequest>,
+) -> Result<Json<CreateEntityResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .idempotency
+        .clone()
+        .once("entities", &headers, request, |request| insert_entity(state, &actor, request))
+        .await
+}
+
+async fn insert_entity(
+    state: AppState,
+    actor: &Actor,
+    mut request: CreateEntityRequest,
 ) -> Result<Json<CreateEntityResponse>, (StatusCode, Json<ErrorResponse>)> {
     // Check if databases are available
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let qdrant = state.qdrant.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Vector database not connected",
-            )),
-        )
-    })?;
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
 
-    let embedding_service = state.embedding_service.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "ServiceNotAvailable",
-                "Embedding service not available",
-            )),
-        )
-    })?;
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
 
-    // Validate entity against ontology if loaded
+    // Fill in property defaults and derived properties, then validate entity
+    // against ontology if loaded
     let reasoner = state.reasoner.read().await;
     if let Some(ref r) = *reasoner {
+        if let Some(entity_type) = r.schema().entity_types.get(&request.entity_type) {
+            entity_type.apply_defaults(r.schema(), &mut request.properties);
+            entity_type.apply_derived(r.schema(), &mut request.properties);
+        }
         let validator = OntologyValidator::new(r.schema().clone());
         validator
             .validate_entity(&request.entity_type, &request.properties)
             .map_err(|errors| {
                 let error_messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new(
-                        "ValidationError",
-                        format!("Entity validation failed: {}", error_messages.join("; ")),
-                    )),
-                )
+                VectaDBError::Validation(format!("Entity validation failed: {}", error_messages.join("; ")))
             })?;
     }
+    let text_spec = EmbeddingTextSpec::for_type(reasoner.as_ref().map(|r| r.schema()), &request.entity_type);
+    let schema_version = reasoner.as_ref().map(|r| r.schema().version.clone());
     drop(reasoner);
 
+    // Each named vector embeds one of the entity's properties
+    let mut named_texts = Vec::with_capacity(request.named_vectors.len());
+    for (name, property) in &request.named_vectors {
+        if !is_valid_vector_name(name) {
+            return Err(VectaDBError::Validation(format!(
+                "Invalid vector name '{}': use letters, digits, '_' and '-', other than 'sparse'",
+                name
+            ))
+            .into());
+        }
+        let text = match request.properties.get(property) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(value) => value.to_string(),
+            None => {
+                return Err(VectaDBError::Validation(format!(
+                    "Named vector '{}' embeds missing property '{}'",
+                    name, property
+                ))
+                .into())
+            }
+        };
+        named_texts.push((name.clone(), text));
+    }
+
+    if !request.sparse_vector.is_empty() {
+        check_sparse_vector(qdrant, &request.entity_type, &request.sparse_vector).await?;
+    }
+    if !named_texts.is_empty() {
+        let names = named_texts.iter().map(|(name, _)| name.clone()).collect();
+        check_named_vectors(qdrant, &request.entity_type, &names).await?;
+    }
+
     // Create entity
-    let mut entity = Entity::new(request.entity_type.clone(), request.properties);
+    let mut entity = Entity::new(request.entity_type.clone(), request.properties)
+        .with_sparse_vector(request.sparse_vector)
+        .with_schema_version(schema_version);
     if let Some(metadata) = request.metadata {
         entity = entity.with_metadata(metadata);
     }
 
     // Generate embedding from text properties
-    let text_content = extract_text_from_properties(&entity.properties);
+    let text_content = text_spec.render(&entity.properties);
     if !text_content.is_empty() {
-        match embedding_service.embed(&text_content).await {
+        match embedding_service.embed_for(&entity.entity_type, &text_content).await {
             Ok(embedding) => {
                 entity = entity.with_embedding(embedding);
             }
@@ -481,47 +1131,25 @@ pub async fn create_entity(
             }
         }
     }
+    // Sparse vectors are stored on the point holding the main embedding
+    if !entity.sparse_vector.is_empty() && entity.embedding.is_none() {
+        return Err(VectaDBError::Validation(format!(
+            "sparse_vector needs a dense embedding, but none was generated for this '{}' entity; give it text properties to embed",
+            entity.entity_type
+        ))
+        .into());
+    }
 
-    // Store in SurrealDB
-    let entity_id = surreal
-        .create_entity(&entity)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to create entity: {}", e),
-                )),
-            )
+    for (name, text) in named_texts {
+        let embedding = embedding_service.embed_for(&entity.entity_type, &text).await.map_err(|e| {
+            VectaDBError::Embedding(format!("Failed to embed named vector '{}': {}", name, e))
         })?;
-
-    // Store embedding in Qdrant if present
-    if let Some(ref embedding) = entity.embedding {
-        // Ensure collection exists
-        if !qdrant
-            .collection_exists(&entity.entity_type)
-            .await
-            .unwrap_or(false)
-        {
-            qdrant
-                .create_collection(&entity.entity_type, embedding.len() as u64)
-                .await
-                .map_err(|e| {
-                    tracing::warn!("Failed to create Qdrant collection: {}", e);
-                })
-                .ok();
-        }
-
-        qdrant
-            .upsert_embedding(&entity.entity_type, &entity_id, embedding.clone())
-            .await
-            .map_err(|e| {
-                tracing::warn!("Failed to store embedding: {}", e);
-            })
-            .ok();
+        entity = entity.with_named_embedding(name, embedding);
     }
 
+    let entity_id = persist_entity(&state.collection_meta, surreal, qdrant, embedding_service, &entity).await?;
+    state.audit(actor, AuditOperation::Create, "entity", &entity_id, created_changes(&entity));
+
     Ok(Json(CreateEntityResponse {
         id: entity_id,
         entity_type: entity.entity_type,
@@ -529,856 +1157,4006 @@ pub async fn create_entity(
     }))
 }
 
+/// Reject empty terms and non-finite weights, and sparse vectors for a
+/// collection created without room for them
+async fn check_sparse_vector(
+    qdrant: &QdrantClient,
+    entity_type: &str,
+    terms: &HashMap<String, f32>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if let Some((term, _)) = terms.iter().find(|(term, weight)| term.is_empty() || !weight.is_finite()) {
+        return Err(VectaDBError::Validation(format!(
+            "Invalid sparse vector term '{}': terms must be non-empty with finite weights",
+            term
+        ))
+        .into());
+    }
+
+    let supports_sparse = qdrant
+        .collection_supports_sparse(entity_type)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to check collection: {}", e)))?;
+    if supports_sparse == Some(false) {
+        return Err(VectaDBError::Validation(format!(
+            "Collection for '{}' was created without sparse vectors; recreate it with POST /api/v1/admin/reindex?entity_type={}&force=true",
+            entity_type, entity_type
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Reject named vectors missing from an existing collection: Qdrant fixes a
+/// collection's vector names when it is created
+async fn check_named_vectors(
+    qdrant: &QdrantClient,
+    entity_type: &str,
+    names: &BTreeSet<String>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let declared = qdrant
+        .collection_vector_names(entity_type)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to check collection: {}", e)))?;
+    // A collection created by this entity declares all of its names
+    let Some(declared) = declared else {
+        return Ok(());
+    };
+
+    let missing: Vec<&str> = names.difference(&declared).map(String::as_str).collect();
+    if !missing.is_empty() {
+        return Err(VectaDBError::Validation(format!(
+            "Collection for '{}' was created without named vector {}; recreate it with POST /api/v1/admin/reindex?entity_type={}&force=true&vector_names={}",
+            entity_type,
+            missing.join(", "),
+            entity_type,
+            missing.join(",")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Store `entity` in SurrealDB and its embedding, if any, in Qdrant. An entity
+/// whose embedding doesn't fit its collection is rolled back.
+async fn persist_entity(
+    collection_meta: &CollectionMetaCache,
+    surreal: &SurrealDBClient,
+    qdrant: &QdrantClient,
+    embedding_service: &EmbeddingManager,
+    entity: &Entity,
+) -> Result<String, (StatusCode, Json<ErrorResponse>)> {
+    // Store in SurrealDB
+    let entity_id = surreal
+        .create_entity(entity)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to create entity: {}", e)))?;
+
+    // Store the main embedding, the sparse vector and any named vectors as one Qdrant point
+    let Some(dimension) = entity
+        .embedding
+        .iter()
+        .chain(entity.named_embeddings.values())
+        .map(|embedding| embedding.len())
+        .next()
+    else {
+        return Ok(entity_id);
+    };
+
+    // Ensure collection exists
+    let vector_names: BTreeSet<String> = entity.named_embeddings.keys().cloned().collect();
+    match qdrant
+        .ensure_collection_with_named(&entity.entity_type, dimension as u64, &vector_names)
+        .await
+    {
+        Ok(true) => {
+            record_collection_meta(
+                collection_meta,
+                surreal,
+                embedding_service,
+                &entity.entity_type,
+                dimension,
+            )
+            .await;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::warn!("Failed to create Qdrant collection: {}", e);
+        }
+    }
+
+    if let Err(e) = qdrant
+        .upsert_vectors(
+            &entity.entity_type,
+            &entity_id,
+            entity.embedding.clone(),
+            &entity.sparse_vector,
+            &entity.named_embeddings,
+            entity.created_at.0,
+        )
+        .await
+    {
+        if let Some(response) = dimension_mismatch(&e) {
+            // Don't leave behind an entity that can never be found by similarity search
+            tracing::error!("Rejected embedding for entity {}: {}", entity_id, e);
+            if let Err(purge_err) = surreal.purge_entity(&entity_id).await {
+                tracing::warn!("Failed to roll back entity {}: {}", entity_id, purge_err);
+            }
+            return Err(response);
+        }
+        tracing::warn!("Failed to store embedding: {}", e);
+    }
+
+    Ok(entity_id)
+}
+
 pub async fn get_entity(
     State(state): State<AppState>,
     Path(entity_id): Path<String>,
+    Query(params): Query<GetEntityParams>,
 ) -> Result<Json<EntityResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let entity = surreal
-        .get_entity(&entity_id)
+    let entity = if params.include_deleted {
+        surreal.get_entity_including_deleted(&entity_id).await
+    } else {
+        surreal.get_entity(&entity_id).await
+    };
+
+    let entity = entity
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
+
+    let validation_errors = if params.validate {
+        let reasoner = state.reasoner.read().await;
+        Some(reasoner.as_ref().map(|r| read_validation_errors(r.schema(), &entity)).unwrap_or_default())
+    } else {
+        None
+    };
+
+    let mut response = entity_response(entity);
+    response.validation_errors = validation_errors;
+    Ok(Json(response))
+}
+
+/// Where a stored entity breaks `schema`. Entities last written under another
+/// ontology version aren't checked: they were valid when written, and reading
+/// them shouldn't fail because the ontology has since evolved.
+fn read_validation_errors(schema: &OntologySchema, entity: &Entity) -> Vec<String> {
+    if entity.schema_version.as_deref() != Some(schema.version.as_str()) {
+        return Vec::new();
+    }
+    match OntologyValidator::new(schema.clone()).validate_entity(&entity.entity_type, &entity.properties) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+/// Fetch many entities by ID in one database round trip
+pub async fn batch_get_entities(
+    State(state): State<AppState>,
+    Json(request): Json<BatchGetEntitiesRequest>,
+) -> Result<Json<BatchGetEntitiesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    check_batch_size(request.ids.len(), state.limits.max_batch_size)?;
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let mut seen = HashSet::new();
+    let ids: Vec<String> = request.ids.into_iter().filter(|id| seen.insert(id.clone())).collect();
+
+    let mut found: HashMap<String, Entity> = surreal
+        .get_entities_by_ids(&ids, request.include_deleted)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get entity: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "EntityNotFound",
-                    format!("Entity '{}' not found", entity_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entities: {}", e)))?
+        .into_iter()
+        .map(|entity| (entity.id_string(), entity))
+        .collect();
+
+    let mut entities = Vec::with_capacity(found.len());
+    let mut missing = Vec::new();
+    for id in ids {
+        match found.remove(&id) {
+            Some(entity) => entities.push(entity_response(entity)),
+            None => missing.push(id),
+        }
+    }
+
+    Ok(Json(BatchGetEntitiesResponse { entities, missing }))
+}
 
-    Ok(Json(EntityResponse {
+fn entity_response(entity: Entity) -> EntityResponse {
+    EntityResponse {
         id: entity.id_string(),
         entity_type: entity.entity_type,
         properties: entity.properties,
         embedding: entity.embedding,
+        named_embeddings: entity.named_embeddings,
+        sparse_vector: entity.sparse_vector,
         created_at: entity.created_at.to_string(),
         updated_at: entity.updated_at.to_string(),
         metadata: entity.metadata,
-    }))
+        deleted_at: entity.deleted_at.map(|d| d.to_string()),
+        version: entity.version,
+        schema_version: entity.schema_version,
+        validation_errors: None,
+    }
 }
 
 pub async fn update_entity(
     State(state): State<AppState>,
+    actor: Actor,
     Path(entity_id): Path<String>,
     Json(request): Json<UpdateEntityRequest>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
     // Get existing entity
     let mut entity = surreal
         .get_entity(&entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get entity: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "EntityNotFound",
-                    format!("Entity '{}' not found", entity_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
+
+    if entity.version != request.version {
+        return Err(version_conflict(&entity_id, request.version).into());
+    }
 
     // Update properties
-    entity.properties = request.properties;
-    entity.updated_at = surrealdb::sql::Datetime::default();
+    let before = std::mem::replace(&mut entity.properties, request.properties);
+    let updated = write_entity_update(&state, surreal, &entity_id, entity).await?;
+    state.audit(&actor, AuditOperation::Update, "entity", &entity_id, updated_changes(&before, &updated));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Recompute derived properties of an entity read at `entity.version`,
+/// validate it if an ontology is loaded and write it back, unless another
+/// update landed in between
+async fn write_entity_update(
+    state: &AppState,
+    surreal: &SurrealDBClient,
+    entity_id: &str,
+    mut entity: Entity,
+) -> Result<Entity, (StatusCode, Json<ErrorResponse>)> {
+    let expected_version = entity.version;
 
-    // Validate if ontology is loaded
     let reasoner = state.reasoner.read().await;
     if let Some(ref r) = *reasoner {
+        if let Some(entity_type) = r.schema().entity_types.get(&entity.entity_type) {
+            entity_type.apply_derived(r.schema(), &mut entity.properties);
+        }
         let validator = OntologyValidator::new(r.schema().clone());
         validator
             .validate_entity(&entity.entity_type, &entity.properties)
             .map_err(|errors| {
                 let error_messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new(
-                        "ValidationError",
-                        format!("Entity validation failed: {}", error_messages.join("; ")),
-                    )),
-                )
+                VectaDBError::Validation(format!("Entity validation failed: {}", error_messages.join("; ")))
             })?;
+        // Valid under the current schema now, whichever it was created under
+        entity.schema_version = Some(r.schema().version.clone());
     }
     drop(reasoner);
 
-    // Update in database
-    surreal
-        .update_entity(&entity_id, &entity)
+    // Conditional write: fails if another update landed since we read
+    let updated = surreal
+        .update_entity(entity_id, &entity, expected_version)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to update entity: {}", e),
-                )),
+        .map_err(|e| VectaDBError::Database(format!("Failed to update entity: {}", e)))?
+        .ok_or_else(|| version_conflict(entity_id, expected_version))?;
+
+    Ok(updated)
+}
+
+/// Create an entity, or replace the properties of the live entity of the same
+/// type that has the same natural key value
+pub async fn upsert_entity(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(request): Json<UpsertEntityRequest>,
+) -> Result<Json<UpsertEntityResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .clone()
+        .ok_or_else(database_unavailable)?;
+
+    let key = match request.key.clone() {
+        Some(key) => key,
+        None => natural_key(&state, &request.entity_type).await?,
+    };
+    if !is_plain_identifier(&key) {
+        return Err(VectaDBError::Validation(format!(
+            "Invalid upsert key '{}': use letters, digits and '_'",
+            key
+        ))
+        .into());
+    }
+    let value = match request.properties.get(&key) {
+        Some(value) if !value.is_null() => value.clone(),
+        _ => {
+            return Err(VectaDBError::Validation(format!(
+                "Upsert key property '{}' is missing",
+                key
+            ))
+            .into())
+        }
+    };
+
+    let matches = surreal
+        .entity_ids_with_property_value(&request.entity_type, &key, &value)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to look up entity by key: {}", e)))?;
+
+    match matches.as_slice() {
+        [] => {
+            let entity_type = request.entity_type.clone();
+            let Json(created) = insert_entity(
+                state,
+                &actor,
+                CreateEntityRequest {
+                    entity_type: request.entity_type,
+                    properties: request.properties,
+                    metadata: request.metadata,
+                    named_vectors: request.named_vectors,
+                    sparse_vector: request.sparse_vector,
+                },
             )
-        })?;
+            .await?;
+            Ok(Json(UpsertEntityResponse {
+                id: created.id,
+                entity_type,
+                key,
+                created: true,
+                version: 1,
+            }))
+        }
+        [entity_id] => {
+            // Updates don't touch Qdrant, so vectors would be silently dropped
+            if !request.sparse_vector.is_empty() || !request.named_vectors.is_empty() {
+                return Err(VectaDBError::BadRequest(format!(
+                    "Entity '{}' already exists; sparse_vector and named_vectors are only accepted when an upsert creates the entity",
+                    entity_id
+                ))
+                .into());
+            }
+            let mut entity = surreal
+                .get_entity(entity_id)
+                .await
+                .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+                .ok_or_else(|| {
+                    VectaDBError::Conflict(format!("Entity '{}' was deleted concurrently", entity_id))
+                        .with_code("VersionConflict")
+                })?;
+            let before = std::mem::replace(&mut entity.properties, request.properties);
+            if let Some(metadata) = request.metadata {
+                entity.metadata = metadata;
+            }
+            let updated = write_entity_update(&state, &surreal, entity_id, entity).await?;
+            state.audit(&actor, AuditOperation::Update, "entity", entity_id, updated_changes(&before, &updated));
+            Ok(Json(UpsertEntityResponse {
+                id: entity_id.clone(),
+                entity_type: updated.entity_type,
+                key,
+                created: false,
+                version: updated.version,
+            }))
+        }
+        _ => Err(VectaDBError::Conflict(format!(
+            "{} live '{}' entities have {} = {}",
+            matches.len(),
+            request.entity_type,
+            key,
+            value
+        ))
+        .with_code("AmbiguousKey")
+        .into()),
+    }
+}
 
-    Ok(StatusCode::NO_CONTENT)
+/// The natural key of an entity type: its one property marked unique
+async fn natural_key(state: &AppState, entity_type: &str) -> Result<String, VectaDBError> {
+    let reasoner = state.reasoner.read().await;
+    let unique: Vec<String> = reasoner
+        .as_ref()
+        .and_then(|r| {
+            let schema = r.schema();
+            schema.entity_types.get(entity_type).map(|t| {
+                t.get_all_properties(schema)
+                    .into_iter()
+                    .filter(|p| p.unique)
+                    .map(|p| p.name)
+                    .collect()
+            })
+        })
+        .unwrap_or_default();
+
+    match unique.as_slice() {
+        [key] => Ok(key.clone()),
+        [] => Err(VectaDBError::Validation(format!(
+            "'{}' has no unique property to upsert by; pass `key`",
+            entity_type
+        ))),
+        _ => Err(VectaDBError::Validation(format!(
+            "'{}' has several unique properties ({}); pass `key`",
+            entity_type,
+            unique.join(", ")
+        ))),
+    }
+}
+
+/// Errors shared by many handlers, keeping the specific `error` codes clients match on
+fn database_unavailable() -> VectaDBError {
+    VectaDBError::Unavailable("Database not connected".to_string())
+        .with_code("DatabaseNotAvailable")
+}
+
+fn vector_store_unavailable() -> VectaDBError {
+    VectaDBError::Unavailable("Vector database not connected".to_string())
+        .with_code("DatabaseNotAvailable")
+}
+
+fn no_schema() -> VectaDBError {
+    VectaDBError::NotFound("No ontology schema loaded".to_string()).with_code("NoSchema")
+}
+
+fn entity_not_found(entity_id: &str) -> VectaDBError {
+    VectaDBError::NotFound(format!("Entity '{}' not found", entity_id)).with_code("EntityNotFound")
+}
+
+fn type_not_found(type_id: &str) -> VectaDBError {
+    VectaDBError::NotFound(format!("Entity type '{}' not found", type_id)).with_code("TypeNotFound")
+}
+
+fn relation_not_found(relation_id: &str) -> VectaDBError {
+    VectaDBError::NotFound(format!("Relation '{}' not found", relation_id))
+        .with_code("RelationNotFound")
+}
+
+fn version_conflict(entity_id: &str, expected: u64) -> VectaDBError {
+    VectaDBError::Conflict(format!(
+        "Entity '{}' was modified concurrently (expected version {})",
+        entity_id, expected
+    ))
+    .with_code("VersionConflict")
 }
 
 pub async fn delete_entity(
     State(state): State<AppState>,
+    actor: Actor,
     Path(entity_id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let qdrant = state.qdrant.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Vector database not connected",
-            )),
-        )
-    })?;
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
 
     // Get entity to find its type
     let entity = surreal
         .get_entity(&entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get entity: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "EntityNotFound",
-                    format!("Entity '{}' not found", entity_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
 
-    // Delete from SurrealDB
+    // Tombstone in SurrealDB; the row is kept for audit history
     surreal
         .delete_entity(&entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to delete entity: {}", e),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to delete entity: {}", e)))?;
 
-    // Delete from Qdrant (if it exists)
-    qdrant
-        .delete_embedding(&entity.entity_type, &entity_id)
-        .await
-        .ok();
+    // Delete from Qdrant (if it exists) so it drops out of similarity search
+    delete_entity_vectors(qdrant, &entity, &entity_id).await;
+
+    state.audit(
+        &actor,
+        AuditOperation::Delete,
+        "entity",
+        &entity_id,
+        serde_json::json!({ "entity_type": entity.entity_type }),
+    );
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-// ============================================================================
-// Relation CRUD
-// ============================================================================
-
-pub async fn create_relation(
+/// Permanently remove an entity, including soft-deleted ones
+pub async fn purge_entity(
     State(state): State<AppState>,
-    Json(request): Json<CreateRelationRequest>,
-) -> Result<Json<CreateRelationResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    actor: Actor,
+    Path(entity_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    // Verify source and target entities exist
-    let source_entity = surreal
-        .get_entity(&request.source_id)
+    let entity = surreal
+        .get_entity_including_deleted(&entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get source entity: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "EntityNotFound",
-                    format!("Source entity '{}' not found", request.source_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
 
-    let target_entity = surreal
-        .get_entity(&request.target_id)
+    surreal
+        .purge_entity(&entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get target entity: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "EntityNotFound",
-                    format!("Target entity '{}' not found", request.target_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Failed to purge entity: {}", e)))?;
 
-    // Validate relation if ontology is loaded
-    let reasoner = state.reasoner.read().await;
-    if let Some(ref r) = *reasoner {
-        let validator = OntologyValidator::new(r.schema().clone());
-        validator
-            .validate_relation(
-                &request.relation_type,
-                &source_entity.entity_type,
-                &target_entity.entity_type,
-            )
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new(
-                        "ValidationError",
-                        format!("Relation validation failed: {}", e),
-                    )),
-                )
-            })?;
+    if let Some(ref qdrant) = state.qdrant {
+        delete_entity_vectors(qdrant, &entity, &entity_id).await;
     }
-    drop(reasoner);
 
-    // Create relation
-    let relation = Relation::new(
-        request.relation_type.clone(),
-        request.source_id.clone(),
-        request.target_id.clone(),
-        request.properties,
+    state.audit(
+        &actor,
+        AuditOperation::Purge,
+        "entity",
+        &entity_id,
+        serde_json::json!({ "entity_type": entity.entity_type }),
     );
 
-    let relation_id = surreal
-        .create_relation(&relation)
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Best-effort removal of an entity's point, which holds its main and named vectors
+async fn delete_entity_vectors(qdrant: &QdrantClient, entity: &Entity, entity_id: &str) {
+    qdrant
+        .delete_embedding(&entity.entity_type, entity_id)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to create relation: {}", e),
-                )),
-            )
-        })?;
+        .ok();
+}
 
-    Ok(Json(CreateRelationResponse {
-        id: relation_id,
-        relation_type: relation.relation_type,
-        source_id: relation.source_id,
-        target_id: relation.target_id,
-        created_at: relation.created_at.to_string(),
-    }))
+/// Audit summary of a created entity: its type and property names
+fn created_changes(entity: &Entity) -> serde_json::Value {
+    let mut properties: Vec<&String> = entity.properties.keys().collect();
+    properties.sort();
+    serde_json::json!({ "entity_type": entity.entity_type, "properties": properties })
 }
 
-pub async fn get_relation(
+/// Audit summary of an update: the properties touched and the new version
+fn updated_changes(before: &HashMap<String, serde_json::Value>, updated: &Entity) -> serde_json::Value {
+    let mut changes = property_changes(before, &updated.properties);
+    changes["entity_type"] = serde_json::json!(updated.entity_type);
+    changes["version"] = serde_json::json!(updated.version);
+    changes
+}
+
+/// Find the nearest neighbours of an existing entity by its stored embedding
+pub async fn get_similar_entities(
     State(state): State<AppState>,
-    Path(relation_id): Path<String>,
-) -> Result<Json<RelationResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
+    Path(entity_id): Path<String>,
+    Query(params): Query<SimilarEntitiesParams>,
+) -> Result<Json<SimilarEntitiesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let entity = surreal
+        .get_entity(&entity_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
+
+    let embedding = entity.embedding.clone().ok_or_else(|| {
+        VectaDBError::Conflict(format!(
+            "Entity '{}' has no stored embedding; create it with embedding generation enabled or reindex its type",
+            entity_id
+        ))
+        .with_code("EntityNotSearchable")
     })?;
 
-    let relation = surreal
-        .get_relation(&relation_id)
+    let limit = state.query.limit(params.limit);
+
+    // One extra hit, since the entity itself is normally its own nearest neighbour
+    let hits = qdrant
+        .search_similar_with_scores(&entity.entity_type, embedding, limit + 1, &TimeRange::default())
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get relation: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "RelationNotFound",
-                    format!("Relation '{}' not found", relation_id),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Vector search failed: {}", e)))?;
 
-    Ok(Json(RelationResponse {
-        id: relation.id_string(),
-        relation_type: relation.relation_type,
-        source_id: relation.source_id,
-        target_id: relation.target_id,
-        properties: relation.properties,
-        created_at: relation.created_at.to_string(),
+    let mut results = Vec::with_capacity(limit);
+    for (hit_id, score) in hits {
+        if hit_id == entity_id {
+            continue;
+        }
+        // Skip vectors whose entity has since been deleted
+        let neighbour = surreal
+            .get_entity(&hit_id)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?;
+        if let Some(neighbour) = neighbour {
+            results.push(SimilarEntity {
+                entity: entity_response(neighbour),
+                score,
+            });
+        }
+        if results.len() == limit {
+            break;
+        }
+    }
+
+    let count = results.len();
+    Ok(Json(SimilarEntitiesResponse {
+        entity_id,
+        results,
+        count,
+        limit,
     }))
 }
 
-pub async fn delete_relation(
+/// Find entities by keyword in their full-text indexed properties
+pub async fn text_search_entities(
     State(state): State<AppState>,
-    Path(relation_id): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    Json(request): Json<TextSearchRequest>,
+) -> Result<Json<TextSearchResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    // Verify relation exists
-    surreal
-        .get_relation(&relation_id)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to get relation: {}", e),
-                )),
-            )
-        })?
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse::new(
-                    "RelationNotFound",
-                    format!("Relation '{}' not found", relation_id),
-                )),
-            )
-        })?;
+    if request.query.trim().is_empty() {
+        return Err(VectaDBError::BadRequest("Query must not be empty".to_string())
+            .with_code("InvalidQuery")
+            .into());
+    }
 
-    surreal
-        .delete_relation(&relation_id)
+    let searchable = {
+        let reasoner = state.reasoner.read().await;
+        searchable_properties(reasoner.as_ref().map(|r| r.schema()), request.entity_type.as_deref())
+    };
+    let properties = if request.properties.is_empty() {
+        searchable
+    } else {
+        if let Some(name) = request.properties.iter().find(|name| !searchable.contains(*name)) {
+            return Err(VectaDBError::BadRequest(format!(
+                "Property '{}' is not marked searchable in the ontology",
+                name
+            ))
+            .with_code("PropertyNotSearchable")
+            .into());
+        }
+        let mut seen = HashSet::new();
+        request.properties.into_iter().filter(|name| seen.insert(name.clone())).collect()
+    };
+    if properties.is_empty() {
+        return Err(VectaDBError::BadRequest("No properties are marked searchable in the ontology".to_string())
+            .with_code("NoSearchableProperties")
+            .into());
+    }
+
+    let limit = state.query.limit(request.limit);
+    let hits = surreal
+        .text_search_entities(&request.query, &properties, request.entity_type.as_deref(), limit)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to delete relation: {}", e),
-                )),
-            )
-        })?;
+        .map_err(|e| VectaDBError::Database(format!("Full-text search failed: {}", e)))?;
 
-    Ok(StatusCode::NO_CONTENT)
+    let results: Vec<TextSearchHit> = hits
+        .into_iter()
+        .map(|(entity, score)| TextSearchHit { entity: entity_response(entity), score })
+        .collect();
+    let count = results.len();
+    Ok(Json(TextSearchResponse {
+        results,
+        count,
+        properties,
+        limit,
+    }))
+}
+
+/// Properties marked searchable, on `entity_type` (inherited ones included)
+/// or on any type when it is `None`
+fn searchable_properties(schema: Option<&OntologySchema>, entity_type: Option<&str>) -> Vec<String> {
+    let Some(schema) = schema else {
+        return Vec::new();
+    };
+    let properties = match entity_type {
+        Some(entity_type) => schema
+            .entity_types
+            .get(entity_type)
+            .map(|t| t.get_all_properties(schema))
+            .unwrap_or_default(),
+        None => schema.entity_types.values().flat_map(|t| t.properties.clone()).collect(),
+    };
+    let names: std::collections::BTreeSet<String> =
+        properties.into_iter().filter(|p| p.searchable).map(|p| p.name).collect();
+    names.into_iter().collect()
 }
 
 // ============================================================================
-// Hybrid Query
+// Logs
 // ============================================================================
 
-pub async fn hybrid_query(
+/// Store an agent log. Logs are entities of type `Log` whose message is
+/// embedded for semantic search.
+pub async fn create_log(
     State(state): State<AppState>,
-    Json(request): Json<HybridQuery>,
-) -> Result<Json<QueryResult>, (StatusCode, Json<ErrorResponse>)> {
-    let coordinator = state.query_coordinator.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "ServiceNotAvailable",
-                "Query coordinator not available",
-            )),
-        )
-    })?;
+    actor: Actor,
+    Json(request): Json<CreateLogRequest>,
+) -> Result<Json<CreateLogResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let result = coordinator
-        .execute(&request)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "QueryError",
-                    format!("Query execution failed: {}", e),
-                )),
-            )
-        })?;
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
 
-    Ok(Json(result))
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
+
+    if request.message.trim().is_empty() {
+        return Err(VectaDBError::Validation("Log message cannot be empty".to_string()).into());
+    }
+
+    let log = Log::from_request(request);
+    let properties = log.to_properties();
+    let schema_version = validate_if_defined(&state, LOG_ENTITY_TYPE, &properties).await?;
+
+    let entity = record_entity(
+        embedding_service,
+        LOG_ENTITY_TYPE,
+        &log.id,
+        properties,
+        schema_version,
+        &log.to_searchable_text(),
+    )
+    .await;
+    let id = persist_entity(&state.collection_meta, surreal, qdrant, embedding_service, &entity).await?;
+    state.audit(&actor, AuditOperation::Create, "entity", &id, created_changes(&entity));
+
+    Ok(Json(CreateLogResponse {
+        id,
+        level: log.level,
+        timestamp: log.timestamp,
+    }))
+}
+
+/// Fetch a log by ID
+pub async fn get_log(
+    State(state): State<AppState>,
+    Path(log_id): Path<String>,
+) -> Result<Json<Log>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let entity = typed_entity(surreal, LOG_ENTITY_TYPE, &log_id).await?;
+    let log = Log::from_properties(&log_id, &entity.properties)
+        .ok_or_else(|| VectaDBError::NotFound(format!("Log '{}' not found", log_id)))?;
+
+    Ok(Json(log))
+}
+
+/// Validate `properties` against `entity_type` if the loaded ontology defines
+/// that type; models stored as entities are free-form otherwise. Returns the
+/// version of the ontology they were validated against, if any.
+async fn validate_if_defined(
+    state: &AppState,
+    entity_type: &str,
+    properties: &HashMap<String, serde_json::Value>,
+) -> Result<Option<String>, VectaDBError> {
+    let reasoner = state.reasoner.read().await;
+    if let Some(ref r) = *reasoner {
+        if r.schema().entity_types.contains_key(entity_type) {
+            let validator = OntologyValidator::new(r.schema().clone());
+            validator
+                .validate_entity(entity_type, properties)
+                .map_err(|errors| {
+                    let error_messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    VectaDBError::Validation(format!(
+                        "{} validation failed: {}",
+                        entity_type,
+                        error_messages.join("; ")
+                    ))
+                })?;
+            return Ok(Some(r.schema().version.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Entity storing a model record under its own ID, embedded from `text`
+async fn record_entity(
+    embedding_service: &EmbeddingManager,
+    entity_type: &str,
+    id: &str,
+    properties: HashMap<String, serde_json::Value>,
+    schema_version: Option<String>,
+    text: &str,
+) -> Entity {
+    let mut entity = Entity::new(entity_type.to_string(), properties).with_schema_version(schema_version);
+    entity.id = Thing::from(("entity".to_string(), id.to_string()));
+    match embedding_service.embed_for(entity_type, text).await {
+        Ok(embedding) => entity.with_embedding(embedding),
+        Err(e) => {
+            tracing::warn!("Failed to generate {} embedding: {}", entity_type, e);
+            entity
+        }
+    }
 }
 
 // ============================================================================
-// Event Ingestion (Phase 5)
+// Reasoning chains
 // ============================================================================
 
-/// Ingest a single event
-pub async fn ingest_event(
+/// Add a thought to a trace's reasoning chain. The thought is embedded for
+/// similarity search and linked to the previous step with a `follows` relation.
+pub async fn create_thought(
     State(state): State<AppState>,
-    Json(request): Json<EventIngestionRequest>,
-) -> Result<Json<EventIngestionResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    actor: Actor,
+    Path(trace_id): Path<String>,
+    Json(request): Json<CreateThoughtRequest>,
+) -> Result<Json<CreateThoughtResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let embedding_service = state.embedding_service.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "ServiceNotAvailable",
-                "Embedding service not available",
-            )),
-        )
-    })?;
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
 
-    // Get or create trace
-    let trace_id = if let Some(ref tid) = request.trace_id {
-        tid.clone()
-    } else if let Some(ref sid) = request.session_id {
-        get_or_create_trace_by_session(&state, sid, request.agent_id.as_deref())
-            .await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new(
-                        "TraceError",
-                        format!("Failed to get/create trace: {}", e),
-                    )),
-                )
-            })?
-    } else {
-        // No trace_id or session_id - create a new trace
-        create_trace_for_session(&state, "default", request.agent_id.as_deref())
-            .await
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new(
-                        "TraceError",
-                        format!("Failed to create trace: {}", e),
-                    )),
-                )
-            })?
-    };
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
 
-    // Create event entity
-    let event_id = create_event_entity(surreal, &request, &trace_id)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "DatabaseError",
-                    format!("Failed to create event: {}", e),
-                )),
-            )
-        })?;
+    if request.content.trim().is_empty() {
+        return Err(VectaDBError::Validation("Thought content cannot be empty".to_string()).into());
+    }
 
-    // Generate and store embedding if properties contain text
-    let text_content = extract_text_from_json(&request.properties);
-    if !text_content.is_empty() {
-        if let Ok(embedding) = embedding_service.embed(&text_content).await {
-            store_event_vector(
-                state.qdrant.as_ref().unwrap(),
-                &event_id,
-                embedding,
-            )
+    // New thoughts follow the latest step of the chain
+    let previous = reasoning_chain(surreal, &trace_id).await?.pop();
+
+    let mut thought = Thought::from_request(request, trace_id.clone());
+    if thought.sequence.is_none() {
+        thought.sequence = Some(previous.as_ref().and_then(|p| p.sequence).unwrap_or(0) + 1);
+    }
+    let properties = thought.to_properties();
+    let schema_version = validate_if_defined(&state, THOUGHT_ENTITY_TYPE, &properties).await?;
+
+    let entity = record_entity(
+        embedding_service,
+        THOUGHT_ENTITY_TYPE,
+        &thought.id,
+        properties,
+        schema_version,
+        &thought.to_searchable_text(),
+    )
+    .await;
+    let id = persist_entity(&state.collection_meta, surreal, qdrant, embedding_service, &entity).await?;
+    state.audit(&actor, AuditOperation::Create, "entity", &id, created_changes(&entity));
+
+    if let Some(ref previous) = previous {
+        let link = Relation::new(
+            FOLLOWS_RELATION.to_string(),
+            id.clone(),
+            previous.id.clone(),
+            HashMap::new(),
+        );
+        let link_id = surreal
+            .create_relation(&link)
             .await
-            .ok(); // Log but don't fail on vector storage error
-        }
+            .map_err(|e| VectaDBError::Database(format!("Failed to link thought: {}", e)))?;
+        state.audit(&actor, AuditOperation::Create, "relation", &link_id, relation_changes(&link));
     }
 
-    Ok(Json(EventIngestionResponse {
-        event_id,
+    Ok(Json(CreateThoughtResponse {
+        id,
         trace_id,
-        created_at: request.timestamp,
+        sequence: thought.sequence,
+        follows: previous.map(|p| p.id),
+        timestamp: thought.timestamp,
     }))
 }
 
-/// Ingest events in bulk
-pub async fn ingest_events_bulk(
+/// Get a trace's reasoning chain in order
+pub async fn get_reasoning_chain(
     State(state): State<AppState>,
-    Json(request): Json<BulkEventIngestionRequest>,
-) -> Result<Json<BulkEventIngestionResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let surreal = state.surreal.as_ref().ok_or_else(|| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(ErrorResponse::new(
-                "DatabaseNotAvailable",
-                "Database not connected",
-            )),
-        )
-    })?;
+    Path(trace_id): Path<String>,
+) -> Result<Json<ReasoningChainResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
 
-    let embedding_service = state.embedding_service.as_ref();
+    let thoughts = reasoning_chain(surreal, &trace_id).await?;
 
-    let mut ingested = 0;
-    let mut failed = 0;
-    let mut trace_ids = Vec::new();
-    let mut errors = Vec::new();
+    Ok(Json(ReasoningChainResponse {
+        trace_id,
+        count: thoughts.len(),
+        thoughts,
+    }))
+}
 
-    for (index, event_request) in request.events.iter().enumerate() {
-        // Get or create trace
-        let trace_id_result = if let Some(ref tid) = event_request.trace_id {
-            Ok(tid.clone())
-        } else if let Some(ref sid) = event_request.session_id {
-            if request.options.auto_create_traces {
-                get_or_create_trace_by_session(&state, sid, event_request.agent_id.as_deref()).await
-            } else {
-                Err(anyhow::anyhow!("Trace not found and auto-create disabled"))
-            }
-        } else {
-            // No trace_id or session_id
-            if request.options.auto_create_traces {
-                create_trace_for_session(&state, "default", event_request.agent_id.as_deref()).await
-            } else {
-                Err(anyhow::anyhow!("No trace specified and auto-create disabled"))
-            }
-        };
+/// The thoughts recorded for a trace, in chain order
+async fn reasoning_chain(surreal: &SurrealDBClient, trace_id: &str) -> Result<Vec<Thought>, VectaDBError> {
+    let entities = surreal
+        .query_entities_by_property(THOUGHT_ENTITY_TYPE, "trace_id", trace_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to load reasoning chain: {}", e)))?;
 
-        let trace_id = match trace_id_result {
-            Ok(tid) => tid,
-            Err(e) => {
-                failed += 1;
-                errors.push(IngestionError {
-                    index,
-                    error: format!("Failed to get/create trace: {}", e),
-                });
-                continue;
-            }
-        };
+    let mut thoughts = records(&entities, Thought::from_properties);
+    sort_chain(&mut thoughts);
+    Ok(thoughts)
+}
 
-        // Create event entity
-        match create_event_entity(surreal, event_request, &trace_id).await {
-            Ok(event_id) => {
-                // Generate and store embedding if requested
-                if request.options.generate_embeddings {
-                    if let Some(embedding_svc) = embedding_service {
-                        let text_content = extract_text_from_json(&event_request.properties);
-                        if !text_content.is_empty() {
-                            if let Ok(embedding) = embedding_svc.embed(&text_content).await {
-                                if let Some(qdrant) = state.qdrant.as_ref() {
-                                    store_event_vector(qdrant, &event_id, embedding)
-                                        .await
-                                        .ok(); // Don't fail on vector storage error
-                                }
-                            }
-                        }
-                    }
-                }
+/// A live entity of `entity_type`; entities of other types are reported as not found
+async fn typed_entity(surreal: &SurrealDBClient, entity_type: &str, id: &str) -> Result<Entity, VectaDBError> {
+    surreal
+        .get_entity(id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get {}: {}", entity_type, e)))?
+        .filter(|entity| entity.entity_type == entity_type)
+        .ok_or_else(|| VectaDBError::NotFound(format!("{} '{}' not found", entity_type, id)))
+}
+
+/// Models rebuilt from stored entities; entities that don't parse are skipped
+fn records<T>(entities: &[Entity], from_properties: fn(&str, &HashMap<String, serde_json::Value>) -> Option<T>) -> Vec<T> {
+    entities
+        .iter()
+        .filter_map(|entity| from_properties(&entity.id_string(), &entity.properties))
+        .collect()
+}
+
+// ============================================================================
+// Agents
+// ============================================================================
+
+pub async fn create_agent(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(request): Json<CreateAgentRequest>,
+) -> Result<Json<Agent>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
+
+    if request.role.trim().is_empty() {
+        return Err(VectaDBError::Validation("Agent role cannot be empty".to_string()).into());
+    }
+
+    let agent = Agent::from_request(request);
+    let properties = agent.to_properties();
+    let schema_version = validate_if_defined(&state, AGENT_ENTITY_TYPE, &properties).await?;
+
+    let entity = record_entity(
+        embedding_service,
+        AGENT_ENTITY_TYPE,
+        &agent.id,
+        properties,
+        schema_version,
+        &agent.to_searchable_text(),
+    )
+    .await;
+    let id = persist_entity(&state.collection_meta, surreal, qdrant, embedding_service, &entity).await?;
+    state.audit(&actor, AuditOperation::Create, "entity", &id, created_changes(&entity));
+
+    Ok(Json(agent))
+}
+
+/// List agents, optionally with one role
+pub async fn list_agents(
+    State(state): State<AppState>,
+    Query(params): Query<ListAgentsParams>,
+) -> Result<Json<ListAgentsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let entities = match params.role {
+        Some(ref role) => surreal.query_entities_by_property(AGENT_ENTITY_TYPE, "role", role).await,
+        None => surreal.query_entities(AGENT_ENTITY_TYPE, false).await,
+    }
+    .map_err(|e| VectaDBError::Database(format!("Failed to list agents: {}", e)))?;
+
+    let cursor = params.cursor.as_deref().map(PageCursor::decode).transpose()?;
+    let limit = state.query.limit(params.limit);
+    let (agents, next_cursor) = keyset_page(
+        records(&entities, Agent::from_properties),
+        |agent| PageCursor::at(agent.created_at, &agent.id),
+        cursor.as_ref(),
+        params.offset.unwrap_or(0),
+        limit,
+    );
+
+    Ok(Json(ListAgentsResponse {
+        count: agents.len(),
+        agents,
+        limit,
+        next_cursor,
+    }))
+}
+
+pub async fn get_agent(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<Agent>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    Ok(Json(load_agent(surreal, &agent_id).await?))
+}
+
+/// An agent with its tasks, thoughts, logs, traces and collaborators
+pub async fn get_agent_relations(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentWithRelations>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let agent = load_agent(surreal, &agent_id).await?;
+
+    let owned = |entity_type: &'static str| surreal.query_entities_by_property(entity_type, "agent_id", &agent_id);
+    let tasks = owned(TASK_ENTITY_TYPE)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get agent tasks: {}", e)))?;
+    let thoughts = owned(THOUGHT_ENTITY_TYPE)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get agent thoughts: {}", e)))?;
+    let logs = owned(LOG_ENTITY_TYPE)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get agent logs: {}", e)))?;
+
+    let mut tasks = records(&tasks, Task::from_properties);
+    tasks.sort_by_key(|task| task.created_at);
+    let mut thoughts = records(&thoughts, Thought::from_properties);
+    thoughts.sort_by_key(|thought| thought.timestamp);
+    let mut logs = records(&logs, Log::from_properties);
+    logs.sort_by_key(|log| log.timestamp);
+
+    let trace_ids = surreal
+        .trace_ids_for_agent(&agent_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get agent traces: {}", e)))?;
+
+    // collaborates_with is symmetric, so it may have been recorded either way round
+    let outgoing = surreal
+        .get_outgoing_relations(&agent_id, Some(COLLABORATES_RELATION))
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get collaborators: {}", e)))?;
+    let incoming = surreal
+        .get_incoming_relations(&agent_id, Some(COLLABORATES_RELATION))
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get collaborators: {}", e)))?;
+    let mut collaborator_ids: Vec<String> = outgoing
+        .into_iter()
+        .map(|r| r.target_id)
+        .chain(incoming.into_iter().map(|r| r.source_id))
+        .filter(|id| *id != agent_id)
+        .collect();
+    collaborator_ids.sort();
+    collaborator_ids.dedup();
+
+    let mut collaborators = Vec::new();
+    for id in collaborator_ids {
+        match load_agent(surreal, &id).await {
+            Ok(collaborator) => collaborators.push(collaborator),
+            Err(VectaDBError::NotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(Json(AgentWithRelations {
+        agent,
+        tasks,
+        thoughts,
+        logs,
+        trace_ids,
+        collaborators,
+    }))
+}
+
+async fn load_agent(surreal: &SurrealDBClient, agent_id: &str) -> Result<Agent, VectaDBError> {
+    let entity = typed_entity(surreal, AGENT_ENTITY_TYPE, agent_id).await?;
+    Agent::from_properties(agent_id, &entity.properties)
+        .ok_or_else(|| VectaDBError::NotFound(format!("Agent '{}' not found", agent_id)))
+}
+
+// ============================================================================
+// Tasks
+// ============================================================================
+
+/// Create a task. If `agent_id` names an Agent entity, the agent is linked
+/// to the task with an `executes` relation.
+pub async fn create_task(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(request): Json<CreateTaskRequest>,
+) -> Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
+
+    if request.name.trim().is_empty() {
+        return Err(VectaDBError::Validation("Task name cannot be empty".to_string()).into());
+    }
+    if request.status.is_terminal() {
+        return Err(VectaDBError::Validation(format!(
+            "Tasks must start PENDING or RUNNING, not {:?}",
+            request.status
+        ))
+        .into());
+    }
+
+    let task = Task::from_request(request);
+    let properties = task.to_properties();
+    let schema_version = validate_if_defined(&state, TASK_ENTITY_TYPE, &properties).await?;
+
+    let entity = record_entity(
+        embedding_service,
+        TASK_ENTITY_TYPE,
+        &task.id,
+        properties,
+        schema_version,
+        &task.to_searchable_text(),
+    )
+    .await;
+    let id = persist_entity(&state.collection_meta, surreal, qdrant, embedding_service, &entity).await?;
+    state.audit(&actor, AuditOperation::Create, "entity", &id, created_changes(&entity));
+
+    let agent = surreal
+        .get_entity(&task.agent_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get agent: {}", e)))?;
+    match agent {
+        Some(agent) if agent.entity_type == AGENT_ENTITY_TYPE => {
+            let link = Relation::new(EXECUTES_RELATION.to_string(), task.agent_id.clone(), id, HashMap::new());
+            let link_id = surreal
+                .create_relation(&link)
+                .await
+                .map_err(|e| VectaDBError::Database(format!("Failed to link task to agent: {}", e)))?;
+            state.audit(&actor, AuditOperation::Create, "relation", &link_id, relation_changes(&link));
+        }
+        _ => tracing::debug!("Task {} has no Agent entity '{}' to link", task.id, task.agent_id),
+    }
+
+    Ok(Json(task))
+}
+
+/// List tasks, optionally for one agent or in one status
+pub async fn list_tasks(
+    State(state): State<AppState>,
+    Query(params): Query<ListTasksParams>,
+) -> Result<Json<ListTasksResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let entities = match params.agent_id {
+        Some(ref agent_id) => surreal.query_entities_by_property(TASK_ENTITY_TYPE, "agent_id", agent_id).await,
+        None => surreal.query_entities(TASK_ENTITY_TYPE, false).await,
+    }
+    .map_err(|e| VectaDBError::Database(format!("Failed to list tasks: {}", e)))?;
+
+    let tasks: Vec<Task> = records(&entities, Task::from_properties)
+        .into_iter()
+        .filter(|task| params.status.is_none_or(|status| task.status == status))
+        .collect();
+
+    let cursor = params.cursor.as_deref().map(PageCursor::decode).transpose()?;
+    let limit = state.query.limit(params.limit);
+    let (tasks, next_cursor) = keyset_page(
+        tasks,
+        |task| PageCursor::at(task.created_at, &task.id),
+        cursor.as_ref(),
+        params.offset.unwrap_or(0),
+        limit,
+    );
+
+    Ok(Json(ListTasksResponse {
+        count: tasks.len(),
+        tasks,
+        limit,
+        next_cursor,
+    }))
+}
+
+pub async fn get_task(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let (_, task) = load_task(surreal, &task_id).await?;
+    Ok(Json(task))
+}
+
+/// Move a task to a new status. Only pending → running → completed/failed
+/// (or cancelled before finishing) is allowed; anything else is a conflict.
+pub async fn update_task_status(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(task_id): Path<String>,
+    Json(request): Json<UpdateTaskStatusRequest>,
+) -> Result<Json<Task>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let (mut entity, mut task) = load_task(surreal, &task_id).await?;
+    let from = task.status;
+    if !task.transition_to(request.status) {
+        return Err(VectaDBError::Conflict(format!(
+            "Task '{}' cannot move from {:?} to {:?}",
+            task_id, from, request.status
+        ))
+        .with_code("InvalidTransition")
+        .into());
+    }
+
+    // Conditional write, so two racing transitions can't both succeed
+    let before = std::mem::replace(&mut entity.properties, task.to_properties());
+    let updated = surreal
+        .update_entity(&task_id, &entity, entity.version)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to update task: {}", e)))?
+        .ok_or_else(|| version_conflict(&task_id, entity.version))?;
+    state.audit(&actor, AuditOperation::Update, "entity", &task_id, updated_changes(&before, &updated));
+
+    Ok(Json(task))
+}
+
+pub async fn delete_task(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(task_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    typed_entity(surreal, TASK_ENTITY_TYPE, &task_id).await?;
+    delete_entity(State(state), actor, Path(task_id)).await
+}
+
+/// A task with the agent executing it and the thoughts and logs recorded for it
+pub async fn get_task_relations(
+    State(state): State<AppState>,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskWithRelations>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let (_, task) = load_task(surreal, &task_id).await?;
+
+    let executors = surreal
+        .get_incoming_relations(&task_id, Some(EXECUTES_RELATION))
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get task relations: {}", e)))?;
+    let executed_by = match executors.first() {
+        Some(relation) => surreal
+            .get_entity(&relation.source_id)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to get agent: {}", e)))?
+            .filter(|entity| entity.entity_type == AGENT_ENTITY_TYPE)
+            .and_then(|entity| Agent::from_properties(&relation.source_id, &entity.properties)),
+        None => None,
+    };
+
+    let thoughts = surreal
+        .query_entities_by_property(THOUGHT_ENTITY_TYPE, "task_id", &task_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get task thoughts: {}", e)))?;
+    let mut thoughts = records(&thoughts, Thought::from_properties);
+    sort_chain(&mut thoughts);
+
+    let logs = surreal
+        .query_entities_by_property(LOG_ENTITY_TYPE, "task_id", &task_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get task logs: {}", e)))?;
+    let mut logs = records(&logs, Log::from_properties);
+    logs.sort_by_key(|log| log.timestamp);
+
+    Ok(Json(TaskWithRelations {
+        task,
+        executed_by,
+        thoughts,
+        logs,
+    }))
+}
+
+/// A task and the entity storing it
+async fn load_task(surreal: &SurrealDBClient, task_id: &str) -> Result<(Entity, Task), VectaDBError> {
+    let entity = typed_entity(surreal, TASK_ENTITY_TYPE, task_id).await?;
+    let task = Task::from_properties(task_id, &entity.properties)
+        .ok_or_else(|| VectaDBError::NotFound(format!("Task '{}' not found", task_id)))?;
+    Ok((entity, task))
+}
+
+// ============================================================================
+// Relation CRUD
+// ============================================================================
+
+/// Create a relation. Repeats carrying the same `Idempotency-Key` return the original relation.
+pub async fn create_relation(
+    State(state): State<AppState>,
+    actor: Actor,
+    headers: HeaderMap,
+    Json(request): Json<CreateRelationRequest>,
+) -> Result<Json<CreateRelationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .idempotency
+        .clone()
+        .once("relations", &headers, request, |request| insert_relation(state, &actor, request))
+        .await
+}
+
+async fn insert_relation(
+    state: AppState,
+    actor: &Actor,
+    request: CreateRelationRequest,
+) -> Result<Json<CreateRelationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    // Verify source and target entities exist
+    let source_entity = surreal
+        .get_entity(&request.source_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get source entity: {}", e)))?
+        .ok_or_else(|| {
+            VectaDBError::NotFound(format!("Source entity '{}' not found", request.source_id))
+                .with_code("EntityNotFound")
+        })?;
+
+    let target_entity = surreal
+        .get_entity(&request.target_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get target entity: {}", e)))?
+        .ok_or_else(|| {
+            VectaDBError::NotFound(format!("Target entity '{}' not found", request.target_id))
+                .with_code("EntityNotFound")
+        })?;
+
+    // Validate relation if ontology is loaded
+    let reasoner = state.reasoner.read().await;
+    let mut relation_type = None;
+    let mut inverse = None;
+    if let Some(ref r) = *reasoner {
+        let validator = OntologyValidator::new(r.schema().clone());
+        validator
+            .validate_relation(
+                &request.relation_type,
+                &source_entity.entity_type,
+                &target_entity.entity_type,
+            )
+            .map_err(|e| VectaDBError::Validation(format!("Relation validation failed: {}", e)))?;
+        relation_type = r.schema().relation_types.get(&request.relation_type).cloned();
+
+        if request.materialize_inverse {
+            inverse = r.schema().inverse_of(&request.relation_type).cloned();
+            if let Some(ref inverse) = inverse {
+                validator
+                    .validate_relation(&inverse.id, &target_entity.entity_type, &source_entity.entity_type)
+                    .map_err(|e| {
+                        VectaDBError::Validation(format!("Inverse relation validation failed: {}", e))
+                    })?;
+            }
+        }
+    }
+    drop(reasoner);
+
+    // Functional types are checked inside the write transaction, so concurrent creates cannot both pass
+    let mut functional = Vec::new();
+    if let Some(relation_type) = relation_type.as_ref().filter(|t| t.functional) {
+        functional.push((relation_type.id.as_str(), request.source_id.as_str(), request.target_id.as_str()));
+    }
+    if let Some(inverse) = inverse.as_ref().filter(|t| t.functional) {
+        functional.push((inverse.id.as_str(), request.target_id.as_str(), request.source_id.as_str()));
+    }
+
+    // Create relation
+    let relation = Relation::new(
+        request.relation_type.clone(),
+        request.source_id.clone(),
+        request.target_id.clone(),
+        request.properties,
+    );
+    let relation_id = relation.id_string();
+
+    let mut implied = Vec::new();
+    if let Some(inverse) = &inverse {
+        implied.push((inverse.id.as_str(), &request.target_id, &request.source_id));
+    }
+    if let Some(relation_type) = relation_type.as_ref().filter(|t| t.reflexive && !t.functional) {
+        if request.materialize_reflexive {
+            implied.push((relation_type.id.as_str(), &request.source_id, &request.source_id));
+            implied.push((relation_type.id.as_str(), &request.target_id, &request.target_id));
+        }
+    }
+    let implied: Vec<Relation> = implied
+        .into_iter()
+        .map(|(relation_type, source_id, target_id)| {
+            Relation::new(relation_type.to_string(), source_id.clone(), target_id.clone(), HashMap::new())
+        })
+        .collect();
+
+    // The relation and those it implies are stored together or not at all
+    let implied_relation_ids = surreal
+        .create_relation_with_implied(&relation, &functional, &implied)
+        .await
+        .map_err(|e| match e.downcast::<VectaDBError>() {
+            Ok(e @ VectaDBError::Conflict(_)) => e.with_code("FunctionalViolation"),
+            Ok(e) => e,
+            Err(e) => VectaDBError::Database(format!("Failed to create relation: {}", e)),
+        })?;
+
+    state.audit(actor, AuditOperation::Create, "relation", &relation_id, relation_changes(&relation));
+
+    Ok(Json(CreateRelationResponse {
+        id: relation_id,
+        relation_type: relation.relation_type,
+        source_id: relation.source_id,
+        target_id: relation.target_id,
+        created_at: relation.created_at.to_string(),
+        implied_relation_ids,
+    }))
+}
+
+pub async fn get_relation(
+    State(state): State<AppState>,
+    Path(relation_id): Path<String>,
+) -> Result<Json<RelationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let relation = surreal
+        .get_relation(&relation_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get relation: {}", e)))?
+        .ok_or_else(|| relation_not_found(&relation_id))?;
+
+    Ok(Json(relation_response(relation)))
+}
+
+/// List an entity's direct relations, optionally by direction and type
+pub async fn get_entity_relations(
+    State(state): State<AppState>,
+    Path(entity_id): Path<String>,
+    Query(params): Query<EntityRelationsParams>,
+) -> Result<Json<EntityRelationsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    surreal
+        .get_entity(&entity_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
+
+    let relation_type = params.relation_type.as_deref();
+    let mut relations = Vec::new();
+
+    if params.direction != RelationDirection::Incoming {
+        let outgoing = surreal
+            .get_outgoing_relations(&entity_id, relation_type)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to get relations: {}", e)))?;
+        relations.extend(outgoing.into_iter().map(|relation| EntityEdge {
+            connected_entity_id: relation.target_id.clone(),
+            direction: RelationDirection::Outgoing,
+            relation: relation_response(relation),
+        }));
+    }
+
+    if params.direction != RelationDirection::Outgoing {
+        let incoming = surreal
+            .get_incoming_relations(&entity_id, relation_type)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to get relations: {}", e)))?;
+        relations.extend(incoming.into_iter().map(|relation| EntityEdge {
+            connected_entity_id: relation.source_id.clone(),
+            direction: RelationDirection::Incoming,
+            relation: relation_response(relation),
+        }));
+    }
+
+    Ok(Json(EntityRelationsResponse {
+        entity_id,
+        count: relations.len(),
+        relations,
+    }))
+}
+
+/// Extract the entities and relations within `depth` hops of an entity
+pub async fn get_entity_subgraph(
+    State(state): State<AppState>,
+    Path(entity_id): Path<String>,
+    Query(params): Query<SubgraphParams>,
+) -> Result<Json<SubgraphResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let coordinator = state
+        .query_coordinator
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Query coordinator not available".to_string()))?;
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    surreal
+        .get_entity(&entity_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get entity: {}", e)))?
+        .ok_or_else(|| entity_not_found(&entity_id))?;
+
+    let max_nodes = params
+        .max_nodes
+        .unwrap_or(state.limits.max_subgraph_nodes)
+        .clamp(1, state.limits.max_subgraph_nodes.max(1));
+    let direction = match params.direction {
+        RelationDirection::Outgoing => TraversalDirection::Outgoing,
+        RelationDirection::Incoming => TraversalDirection::Incoming,
+        RelationDirection::Both => TraversalDirection::Both,
+    };
+
+    let subgraph = coordinator
+        .subgraph(
+            &entity_id,
+            params.depth,
+            direction,
+            params.relation_type.as_deref(),
+            max_nodes,
+        )
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to extract subgraph: {}", e)))?;
+
+    Ok(Json(SubgraphResponse {
+        nodes: subgraph.nodes.into_iter().map(entity_response).collect(),
+        edges: subgraph.edges.into_iter().map(relation_response).collect(),
+        truncated: subgraph.truncated,
+    }))
+}
+
+fn relation_response(relation: Relation) -> RelationResponse {
+    RelationResponse {
+        id: relation.id_string(),
+        relation_type: relation.relation_type,
+        source_id: relation.source_id,
+        target_id: relation.target_id,
+        properties: relation.properties,
+        created_at: relation.created_at.to_string(),
+    }
+}
+
+pub async fn delete_relation(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(relation_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    // Verify relation exists
+    let relation = surreal
+        .get_relation(&relation_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to get relation: {}", e)))?
+        .ok_or_else(|| relation_not_found(&relation_id))?;
+
+    surreal
+        .delete_relation(&relation_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to delete relation: {}", e)))?;
+
+    // The inverse relation no longer holds either
+    let inverse = state
+        .reasoner
+        .read()
+        .await
+        .as_ref()
+        .and_then(|r| r.schema().inverse_of(&relation.relation_type).map(|t| t.id.clone()));
+    if let Some(inverse) = inverse {
+        let reverse = surreal
+            .get_outgoing_relations(&relation.target_id, Some(&inverse))
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to get relations: {}", e)))?;
+        for reverse in reverse.iter().filter(|r| r.target_id == relation.source_id) {
+            surreal
+                .delete_relation(&reverse.id_string())
+                .await
+                .map_err(|e| VectaDBError::Database(format!("Failed to delete inverse relation: {}", e)))?;
+        }
+    }
+
+    state.audit(&actor, AuditOperation::Delete, "relation", &relation_id, relation_changes(&relation));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Audit summary of a relation: its type and endpoints
+fn relation_changes(relation: &Relation) -> serde_json::Value {
+    serde_json::json!({
+        "relation_type": relation.relation_type,
+        "source_id": relation.source_id,
+        "target_id": relation.target_id,
+    })
+}
+
+// ============================================================================
+// Hybrid Query
+// ============================================================================
+
+pub async fn hybrid_query(
+    State(state): State<AppState>,
+    Json(mut request): Json<HybridQuery>,
+) -> Result<Json<QueryResult>, (StatusCode, Json<ErrorResponse>)> {
+    let coordinator = state
+        .query_coordinator
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Query coordinator not available".to_string()))?;
+    request.apply_limits(&state.query);
+
+    let result = coordinator.execute(&request).await.map_err(query_error)?;
+
+    Ok(Json(result))
+}
+
+/// Stream hybrid query results as server-sent events
+pub async fn query_stream(
+    State(state): State<AppState>,
+    Json(mut request): Json<HybridQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ErrorResponse>)> {
+    let coordinator = state
+        .query_coordinator
+        .clone()
+        .ok_or_else(|| VectaDBError::Unavailable("Query coordinator not available".to_string()))?;
+    request.apply_limits(&state.query);
+
+    let (sender, receiver) = mpsc::channel(32);
+    // Keep the request's span, so the query's log lines carry its id
+    let task = tokio::spawn(
+        async move { coordinator.execute_streaming(&request, sender).await }.in_current_span(),
+    );
+    let done = async move {
+        task.await
+            .unwrap_or_else(|e| Err(anyhow::anyhow!("Query task failed: {}", e)))
+    };
+
+    Ok(Sse::new(query_event_stream(receiver, done)).keep_alive(KeepAlive::default()))
+}
+
+/// One `result` event per streamed result, then a terminal `metadata` event
+/// (or `error` if the query failed part way)
+fn query_event_stream(
+    receiver: mpsc::Receiver<ScoredResult>,
+    done: impl Future<Output = anyhow::Result<StreamSummary>> + Send + 'static,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let results = futures::stream::unfold(receiver, |mut receiver| async move {
+        let result = receiver.recv().await?;
+        Some((sse_event("result", &result), receiver))
+    });
+
+    let tail = futures::stream::once(async move {
+        match done.await {
+            Ok(summary) => sse_event("metadata", &summary),
+            Err(e) => {
+                let (_, Json(body)) = query_error(e);
+                sse_event("error", &body)
+            }
+        }
+    });
+
+    results.chain(tail).map(Ok)
+}
+
+fn sse_event(name: &str, data: &impl serde::Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+fn query_error(e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    match e.downcast_ref::<VectaDBError>() {
+        Some(VectaDBError::BadRequest(msg)) => {
+            VectaDBError::BadRequest(msg.clone()).with_code("InvalidQuery").into()
+        }
+        _ => VectaDBError::Internal(format!("Query execution failed: {}", e))
+            .with_code("QueryError")
+            .into(),
+    }
+}
+
+// ============================================================================
+// Event Ingestion (Phase 5)
+// ============================================================================
+
+/// Ingest a single event. Repeats carrying the same `Idempotency-Key` return the original event.
+pub async fn ingest_event(
+    State(state): State<AppState>,
+    actor: Actor,
+    headers: HeaderMap,
+    Json(request): Json<EventIngestionRequest>,
+) -> Result<Json<EventIngestionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .idempotency
+        .clone()
+        .once("events", &headers, request, |request| record_event(state, actor, request))
+        .await
+}
+
+async fn record_event(
+    state: AppState,
+    actor: Actor,
+    request: EventIngestionRequest,
+) -> Result<Json<EventIngestionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
+
+    if sampled_out(&state, &request) {
+        return Ok(Json(EventIngestionResponse {
+            event_id: None,
+            trace_id: request.trace_id,
+            created_at: request.timestamp,
+            sampled_out: true,
+        }));
+    }
+
+    // Embed first, so an embedding the collection can't hold is rejected
+    // before anything is written and a retry doesn't duplicate the event
+    let text_content = extract_text_from_json(&request.properties);
+    let embedding = match (&state.qdrant, text_content.is_empty()) {
+        (Some(qdrant), false) => match embedding_service.embed_for(EVENTS_COLLECTION, &text_content).await {
+            Ok(embedding) => {
+                if let Err(e) = qdrant.check_vector_size(EVENTS_COLLECTION, embedding.len()).await {
+                    if let Some(response) = dimension_mismatch(&e) {
+                        tracing::error!("Rejected embedding for event: {}", e);
+                        return Err(response);
+                    }
+                }
+                Some((qdrant, embedding))
+            }
+            Err(_) => None,
+        },
+        _ => None,
+    };
+
+    // Get or create trace
+    let trace_id = if let Some(ref tid) = request.trace_id {
+        tid.clone()
+    } else if let Some(ref sid) = request.session_id {
+        get_or_create_trace_by_session(&state, sid, request.agent_id.as_deref())
+            .await
+            .map_err(|e| {
+                VectaDBError::Internal(format!("Failed to get/create trace: {}", e))
+                    .with_code("TraceError")
+            })?
+    } else {
+        // No trace_id or session_id - create a new trace
+        create_trace_for_session(&state, "default", request.agent_id.as_deref())
+            .await
+            .map_err(|e| {
+                VectaDBError::Internal(format!("Failed to create trace: {}", e))
+                    .with_code("TraceError")
+            })?
+    };
+
+    // Create event entity
+    let event_id = create_event_entity(surreal, &request, &trace_id)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to create event: {}", e)))?;
+
+    if let Some((qdrant, embedding)) = embedding {
+        if let Err(e) =
+            store_event_vector(
+                &state.collection_meta,
+                qdrant,
+                surreal,
+                embedding_service,
+                &event_id,
+                request.timestamp,
+                embedding,
+            )
+            .await
+        {
+            match dimension_mismatch(&e) {
+                Some(response) => {
+                    // The collection changed since the check; don't keep an event the client will retry
+                    tracing::error!("Rejected embedding for event {}: {}", event_id, e);
+                    if let Err(delete_err) = surreal.delete_events(std::slice::from_ref(&event_id)).await {
+                        tracing::warn!("Failed to roll back event {}: {}", event_id, delete_err);
+                    }
+                    return Err(response);
+                }
+                // Log but don't fail on vector storage error
+                None => tracing::warn!("Failed to store event embedding: {}", e),
+            }
+        }
+    }
+
+    state.audit(&actor, AuditOperation::Create, "event", &event_id, event_changes(&request, &trace_id));
+    state
+        .events
+        .publish(StreamedEvent::from_request(&event_id, &trace_id, &request));
+
+    Ok(Json(EventIngestionResponse {
+        event_id: Some(event_id),
+        trace_id: Some(trace_id),
+        created_at: request.timestamp,
+        sampled_out: false,
+    }))
+}
+
+/// Audit summary of an ingested event: its type, trace and property names
+fn event_changes(request: &EventIngestionRequest, trace_id: &str) -> serde_json::Value {
+    let mut properties: Vec<&String> = request
+        .properties
+        .as_object()
+        .map(|properties| properties.keys().collect())
+        .unwrap_or_default();
+    properties.sort();
+    serde_json::json!({ "event_type": request.event_type, "trace_id": trace_id, "properties": properties })
+}
+
+/// Whether sampling keeps the event out of storage, counting it if so.
+/// Events naming neither a trace nor a session start a trace of their own
+/// and are always kept.
+fn sampled_out(state: &AppState, request: &EventIngestionRequest) -> bool {
+    let Some(key) = request.trace_id.as_deref().or(request.session_id.as_deref()) else {
+        return false;
+    };
+    if state.sampler.keeps(key) {
+        return false;
+    }
+    state.sampler.record_dropped(
+        request.agent_id.as_deref(),
+        request.event_type.as_deref().unwrap_or("unknown"),
+    )
+    ;
+    true
+}
+
+/// Ingest events in bulk
+pub async fn ingest_events_bulk(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(request): Json<BulkEventIngestionRequest>,
+) -> Result<Json<BulkEventIngestionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    check_batch_size(request.events.len(), state.limits.max_batch_size)?;
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let mut ingested = 0;
+    let mut failed = 0;
+    let mut sampled = 0;
+    let mut trace_ids = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, event_request) in request.events.iter().enumerate() {
+        if sampled_out(&state, event_request) {
+            sampled += 1;
+            continue;
+        }
+        match ingest_one_event(&state, &actor, surreal, event_request, &request.options).await {
+            Ok(outcome) => {
+                if let Some(warning) = outcome.warning {
+                    errors.push(IngestionError {
+                        index,
+                        error: warning,
+                        event: None,
+                        dead_letter_id: None,
+                    });
+                }
+                ingested += 1;
+                if !trace_ids.contains(&outcome.trace_id) {
+                    trace_ids.push(outcome.trace_id);
+                }
+            }
+            Err(error) => {
+                failed += 1;
+                let dead_letter_id = if request.options.dead_letter {
+                    dead_letter_event(surreal, event_request, &error).await
+                } else {
+                    None
+                };
+                errors.push(IngestionError {
+                    index,
+                    error,
+                    event: request.options.return_failed_events.then(|| event_request.clone()),
+                    dead_letter_id,
+                });
+            }
+        }
+    }
+
+    Ok(Json(BulkEventIngestionResponse {
+        ingested,
+        failed,
+        sampled_out: sampled,
+        trace_ids,
+        errors,
+    }))
+}
+
+/// An event that was stored
+struct IngestedEvent {
+    trace_id: String,
+    /// A problem that did not stop the event being stored
+    warning: Option<String>,
+}
+
+/// Resolve an event's trace, store it and embed it. Errors are reported per
+/// event, so they come back as messages.
+async fn ingest_one_event(
+    state: &AppState,
+    actor: &Actor,
+    surreal: &SurrealDBClient,
+    event_request: &EventIngestionRequest,
+    options: &IngestionOptions,
+) -> Result<IngestedEvent, String> {
+    // Get or create trace
+    let trace_id_result = if let Some(ref tid) = event_request.trace_id {
+        Ok(tid.clone())
+    } else if let Some(ref sid) = event_request.session_id {
+        if options.auto_create_traces {
+            get_or_create_trace_by_session(state, sid, event_request.agent_id.as_deref()).await
+        } else {
+            Err(anyhow::anyhow!("Trace not found and auto-create disabled"))
+        }
+    } else {
+        // No trace_id or session_id
+        if options.auto_create_traces {
+            create_trace_for_session(state, "default", event_request.agent_id.as_deref()).await
+        } else {
+            Err(anyhow::anyhow!("No trace specified and auto-create disabled"))
+        }
+    };
+    let trace_id = trace_id_result.map_err(|e| format!("Failed to get/create trace: {}", e))?;
+
+    // Create event entity
+    let event_id = create_event_entity(surreal, event_request, &trace_id)
+        .await
+        .map_err(|e| format!("Failed to create event: {}", e))?;
+    state.audit(actor, AuditOperation::Create, "event", &event_id, event_changes(event_request, &trace_id));
+    state
+        .events
+        .publish(StreamedEvent::from_request(&event_id, &trace_id, event_request));
+
+    let mut warning = None;
+
+    // Generate and store embedding if requested
+    if options.generate_embeddings {
+        if let Some(embedding_svc) = state.embedding_service.as_ref() {
+            let text_content = extract_text_from_json(&event_request.properties);
+            if !text_content.is_empty() {
+                if let Ok(embedding) = embedding_svc.embed_for(EVENTS_COLLECTION, &text_content).await {
+                    if let Some(qdrant) = state.qdrant.as_ref() {
+                        // Don't fail on vector storage error, but report mismatches
+                        let stored = store_event_vector(
+                            &state.collection_meta,
+                            qdrant,
+                            surreal,
+                            embedding_svc,
+                            &event_id,
+                            event_request.timestamp,
+                            embedding,
+                        )
+                        .await;
+                        if let Err(e) = stored {
+                            if dimension_mismatch(&e).is_some() {
+                                tracing::error!("Rejected embedding for event {}: {}", event_id, e);
+                                warning = Some(format!("Event stored without embedding: {}", e));
+                            } else {
+                                tracing::warn!("Failed to store event embedding: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(IngestedEvent { trace_id, warning })
+}
+
+/// Keep a failed event for replay, returning its dead letter ID
+async fn dead_letter_event(
+    surreal: &SurrealDBClient,
+    event_request: &EventIngestionRequest,
+    error: &str,
+) -> Option<String> {
+    let event = match serde_json::to_value(event_request) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!("Failed to serialize dead letter: {}", e);
+            return None;
+        }
+    };
+    match surreal.store_dead_letter(&event, error).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            tracing::warn!("Failed to store dead letter: {}", e);
+            None
+        }
+    }
+}
+
+/// List stored events, newest first
+pub async fn list_events(
+    State(state): State<AppState>,
+    Query(params): Query<ListEventsParams>,
+) -> Result<Json<ListEventsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let filter = EventFilter {
+        agent_id: params.agent_id,
+        trace_id: params.trace_id,
+        event_type: params.event_type,
+        time_range: TimeRange::new(params.start_time, params.end_time),
+    };
+    filter.time_range.validate()?;
+    let cursor = params.cursor.as_deref().map(PageCursor::decode).transpose()?;
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let limit = state.query.limit(params.limit);
+
+    let events = surreal
+        .list_events(
+            &filter,
+            cursor.as_ref().map(|c| (c.timestamp.as_str(), c.id.as_str())),
+            params.offset.unwrap_or(0),
+            limit,
+        )
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list events: {}", e)))?;
+
+    // A full page may have more behind it; resume from the stored sort key of its last event
+    let next_cursor = if events.len() == limit {
+        events.last().and_then(|last| {
+            Some(PageCursor::new(last.get("timestamp")?.as_str()?, last.get("id")?.as_str()?).encode())
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(ListEventsResponse {
+        count: events.len(),
+        events,
+        limit,
+        next_cursor,
+    }))
+}
+
+/// Get or create trace by session_id with resilient detection
+async fn get_or_create_trace_by_session(
+    state: &AppState,
+    session_id: &str,
+    agent_id: Option<&str>,
+) -> Result<String, anyhow::Error> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Database not available"))?;
+
+    // Strategy 1: Try exact session_id match first
+    #[derive(Debug, serde::Deserialize)]
+    struct TraceRecord {
+        id: String,
+        #[allow(dead_code)] // Reserved for future time-based filtering
+        start_time: Option<String>,
+    }
+
+    let query = format!(
+        "SELECT id, start_time FROM agent_trace WHERE session_id = '{}' ORDER BY start_time DESC LIMIT 1",
+        session_id.replace('\'', "\\'")
+    );
+
+    let mut result = surreal.db().query(query).await?;
+    let traces: Vec<TraceRecord> = result.take(0).unwrap_or_default();
+
+    if let Some(trace) = traces.first() {
+        tracing::debug!("Found trace by session_id: {}", trace.id);
+        return Ok(trace.id.clone());
+    }
+
+    // Strategy 2: If agent_id provided, check for recent trace (within 1 hour)
+    if let Some(aid) = agent_id {
+        let query = format!(
+            "SELECT id, start_time FROM agent_trace WHERE agent_id = '{}' AND status = 'running' AND start_time > time::now() - 1h ORDER BY start_time DESC LIMIT 1",
+            aid.replace('\'', "\\'")
+        );
+
+        let mut result = surreal.db().query(query).await?;
+        let traces: Vec<TraceRecord> = result.take(0).unwrap_or_default();
+
+        if let Some(trace) = traces.first() {
+            tracing::debug!("Found trace by agent_id: {}", trace.id);
+            return Ok(trace.id.clone());
+        }
+    }
+
+    // Strategy 3: Create new trace
+    tracing::info!("Creating new trace for session_id: {}", session_id);
+    create_trace_for_session(state, session_id, agent_id).await
+}
+
+/// Create a new trace for a session
+async fn create_trace_for_session(
+    state: &AppState,
+    session_id: &str,
+    agent_id: Option<&str>,
+) -> Result<String, anyhow::Error> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Database not available"))?;
+
+    let trace_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+
+    let agent_id_str = agent_id.map(|s| format!("'{}'", s.replace('\'', "\\'")))
+        .unwrap_or_else(|| "NONE".to_string());
+
+    let query = format!(
+        "CREATE agent_trace CONTENT {{
+            id: '{}',
+            session_id: '{}',
+            agent_id: {},
+            status: 'running',
+            start_time: '{}',
+            created_at: '{}',
+            updated_at: '{}'
+        }}",
+        trace_id,
+        session_id.replace('\'', "\\'"),
+        agent_id_str,
+        now.to_rfc3339(),
+        now.to_rfc3339(),
+        now.to_rfc3339()
+    );
+
+    surreal.db().query(query).await?;
+
+    Ok(trace_id)
+}
+
+/// Create event entity in SurrealDB
+async fn create_event_entity(
+    surreal: &SurrealDBClient,
+    request: &EventIngestionRequest,
+    trace_id: &str,
+) -> Result<String, anyhow::Error> {
+    let event_id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+
+    // Build event properties as JSON
+    let mut event_data = serde_json::json!({
+        "id": event_id,
+        "trace_id": trace_id,
+        "timestamp": request.timestamp.to_rfc3339(),
+        "properties": request.properties,
+        "created_at": now.to_rfc3339(),
+        "updated_at": now.to_rfc3339(),
+    });
+
+    // Add optional fields
+    if let Some(ref event_type) = request.event_type {
+        event_data["event_type"] = serde_json::json!(event_type);
+    }
+    if let Some(ref agent_id) = request.agent_id {
+        event_data["agent_id"] = serde_json::json!(agent_id);
+    }
+    if let Some(ref session_id) = request.session_id {
+        event_data["session_id"] = serde_json::json!(session_id);
+    }
+    if let Some(ref source) = request.source {
+        event_data["source"] = serde_json::json!(source);
+    }
+
+    let query = format!("CREATE agent_event CONTENT {}", event_data);
+
+    surreal.db().query(query).await?;
+
+    // Create relation from trace to event
+    let trace_record_id = format!("agent_trace:`{}`", trace_id);
+    let event_record_id = format!("agent_event:`{}`", event_id);
+
+    let relation_query = format!(
+        "RELATE {}->contains->{} CONTENT {{
+            created_at: '{}'
+        }}",
+        trace_record_id,
+        event_record_id,
+        now.to_rfc3339()
+    );
+
+    surreal.db().query(relation_query).await?;
+
+    Ok(event_id)
+}
+
+/// Map an embedding dimension mismatch from the vector store to an error response
+fn dimension_mismatch(err: &anyhow::Error) -> Option<(StatusCode, Json<ErrorResponse>)> {
+    match err.downcast_ref::<VectaDBError>() {
+        Some(&VectaDBError::EmbeddingDimensionMismatch { expected, got }) => {
+            Some(VectaDBError::EmbeddingDimensionMismatch { expected, got }.into())
+        }
+        _ => None,
+    }
+}
+
+/// Store event embedding in Qdrant
+async fn store_event_vector(
+    collection_meta: &CollectionMetaCache,
+    qdrant: &QdrantClient,
+    surreal: &SurrealDBClient,
+    embedding_service: &EmbeddingManager,
+    event_id: &str,
+    timestamp: chrono::DateTime<chrono::Utc>,
+    embedding: Vec<f32>,
+) -> Result<(), anyhow::Error> {
+    // Ensure collection exists
+    if qdrant
+        .ensure_collection(EVENTS_COLLECTION, embedding.len() as u64)
+        .await?
+    {
+        record_collection_meta(
+            collection_meta,
+            surreal,
+            embedding_service,
+            EVENTS_COLLECTION,
+            embedding.len(),
+        )
+        .await;
+    }
+
+    // Store embedding
+    qdrant
+        .upsert_embedding(EVENTS_COLLECTION, event_id, embedding, timestamp)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Analytics
+// ============================================================================
+
+/// Aggregate event statistics, optionally for a single agent
+pub async fn get_analytics(
+    State(state): State<AppState>,
+    Query(params): Query<AnalyticsParams>,
+) -> Result<Json<AnalyticsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let analytics = surreal
+        .get_event_analytics(params.agent_id.as_deref())
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to aggregate analytics: {}", e)))?;
+
+    let error_rate = analytics.error_rate();
+    let sampler = &state.sampler;
+    let sampled_out = sampler.is_sampling().then(|| sampler.dropped(params.agent_id.as_deref()));
+
+    Ok(Json(AnalyticsResponse {
+        agent_id: params.agent_id,
+        total_events: analytics.total_events,
+        events_by_type: analytics.events_by_type,
+        error_count: analytics.error_count,
+        error_rate,
+        token_usage: analytics.token_usage,
+        avg_latency_ms: analytics.avg_latency_ms,
+        sampling_rate: sampler.is_sampling().then(|| sampler.rate()),
+        sampled_out,
+    }))
+}
+
+/// Count records per distinct value of a field, e.g. events per `event_type`
+pub async fn get_facets(
+    State(state): State<AppState>,
+    Query(params): Query<FacetsParams>,
+) -> Result<Json<FacetsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_field_path(&params.field) {
+        return Err(VectaDBError::Validation(format!("Invalid field '{}'", params.field)).into());
+    }
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let limit = state.query.limit(params.limit);
+    let (values, distinct_count) = surreal
+        .facet_counts(params.table, &params.field, limit)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to count '{}' values: {}", params.field, e)))?;
+
+    Ok(Json(FacetsResponse {
+        table: params.table,
+        field: params.field,
+        values,
+        distinct_count,
+        limit,
+    }))
+}
+
+const MAX_TIMESERIES_BUCKETS: i64 = 1000;
+
+/// Count records per fixed time interval, e.g. events per hour for an
+/// activity chart. Intervals without records are returned with a zero count.
+pub async fn get_timeseries(
+    State(state): State<AppState>,
+    Query(params): Query<TimeseriesParams>,
+) -> Result<Json<TimeseriesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    TimeRange::new(Some(params.start), Some(params.end)).validate()?;
+    let interval_secs = parse_interval(&params.interval).ok_or_else(|| {
+        VectaDBError::Validation(format!(
+            "Invalid interval '{}'; use a number and one of s, m, h, d or w, e.g. 1h",
+            params.interval
+        ))
+    })?;
+    let span = params.end.timestamp() - bucket_start(params.start, interval_secs);
+    if span / interval_secs >= MAX_TIMESERIES_BUCKETS {
+        return Err(VectaDBError::Validation(format!(
+            "Range spans more than {} intervals; use a longer interval or a shorter range",
+            MAX_TIMESERIES_BUCKETS
+        ))
+        .into());
+    }
+    if let Some(property) = &params.property {
+        if !is_plain_identifier(property) {
+            return Err(VectaDBError::Validation(format!("Invalid property name '{}'", property)).into());
+        }
+    }
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let found = surreal
+        .time_buckets(params.table, params.start, params.end, interval_secs, params.property.as_deref())
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to bucket records: {}", e)))?;
+    let buckets = zero_filled_buckets(params.start, params.end, interval_secs, found);
+    let total = buckets.iter().map(|bucket| bucket.count).sum();
+
+    Ok(Json(TimeseriesResponse {
+        table: params.table,
+        interval_secs,
+        property: params.property,
+        buckets,
+        total,
+    }))
+}
+
+/// Seconds in an interval such as `30s`, `15m`, `1h`, `1d` or `1w`
+fn parse_interval(interval: &str) -> Option<i64> {
+    let unit = interval.chars().last()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 604_800,
+        _ => return None,
+    };
+    let count: i64 = interval[..interval.len() - 1].parse().ok()?;
+    (count > 0).then(|| count.checked_mul(seconds)).flatten()
+}
+
+/// Estimate LLM spend from event token counts and the configured prices
+pub async fn get_cost(
+    State(state): State<AppState>,
+    Query(params): Query<CostParams>,
+) -> Result<Json<CostResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let filter = EventFilter {
+        agent_id: params.agent_id.clone(),
+        trace_id: params.trace_id.clone(),
+        event_type: None,
+        time_range: TimeRange::new(params.start_time, params.end_time),
+    };
+    filter.time_range.validate()?;
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let usage = surreal
+        .token_usage_by_model(&filter)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to sum token usage: {}", e)))?;
+
+    let mut report = cost_report(usage, &state.pricing);
+    report.agent_id = params.agent_id;
+    report.trace_id = params.trace_id;
+    Ok(Json(report))
+}
+
+/// Price each model's token usage
+fn cost_report(usage: Vec<ModelTokenUsage>, pricing: &PricingConfig) -> CostResponse {
+    let mut report = CostResponse {
+        agent_id: None,
+        trace_id: None,
+        total_cost: 0.0,
+        models: Vec::with_capacity(usage.len()),
+        unpriced_events: 0,
+        events_without_tokens: 0,
+    };
+
+    for model in usage {
+        let price = model.model_id.as_ref().and_then(|id| pricing.models.get(id));
+        let cost = price.map(|price| price.cost(model.input_tokens, model.output_tokens));
+        match cost {
+            Some(cost) => report.total_cost += cost,
+            None => report.unpriced_events += model.events,
+        }
+        report.events_without_tokens += model.events_without_tokens;
+        report.models.push(ModelCost {
+            model_id: model.model_id.unwrap_or_else(|| "unknown".to_string()),
+            events: model.events,
+            input_tokens: model.input_tokens,
+            output_tokens: model.output_tokens,
+            cost,
+        });
+    }
+
+    report.models.sort_by(|a, b| {
+        b.cost
+            .unwrap_or(-1.0)
+            .total_cmp(&a.cost.unwrap_or(-1.0))
+            .then_with(|| a.model_id.cmp(&b.model_id))
+    });
+    report
+}
+
+const DEFAULT_HISTOGRAM_BUCKETS: usize = 10;
+const MAX_HISTOGRAM_BUCKETS: usize = 1000;
+
+/// Histogram of a numeric property over events matching the filters
+pub async fn event_histogram(
+    State(state): State<AppState>,
+    Json(request): Json<HistogramRequest>,
+) -> Result<Json<HistogramResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let filter = EventFilter {
+        agent_id: request.agent_id,
+        trace_id: request.trace_id,
+        event_type: request.event_type,
+        time_range: TimeRange::new(request.start_time, request.end_time),
+    };
+    filter.time_range.validate()?;
+
+    if !is_plain_identifier(&request.property) {
+        return Err(VectaDBError::Validation(format!("Invalid property name '{}'", request.property)).into());
+    }
+    if let Some(edges) = &request.buckets {
+        if edges.len() < 2 || edges.len() > MAX_HISTOGRAM_BUCKETS + 1 {
+            return Err(VectaDBError::Validation(format!(
+                "buckets needs between 2 and {} edges",
+                MAX_HISTOGRAM_BUCKETS + 1
+            ))
+            .into());
+        }
+        if edges.iter().any(|edge| !edge.is_finite()) || edges.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(VectaDBError::Validation("buckets must be finite and strictly increasing".to_string()).into());
+        }
+    }
+    let bucket_count = request.bucket_count.unwrap_or(DEFAULT_HISTOGRAM_BUCKETS);
+    if !(1..=MAX_HISTOGRAM_BUCKETS).contains(&bucket_count) {
+        return Err(VectaDBError::Validation(format!(
+            "bucket_count must be between 1 and {}",
+            MAX_HISTOGRAM_BUCKETS
+        ))
+        .into());
+    }
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let values = surreal
+        .event_property_values(&filter, &request.property)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to collect '{}' values: {}", request.property, e)))?;
+
+    let histogram = match &request.buckets {
+        Some(edges) => Histogram::with_edges(&values, edges),
+        None => Histogram::equal_width(&values, bucket_count),
+    };
+
+    Ok(Json(HistogramResponse {
+        property: request.property,
+        histogram,
+    }))
+}
+
+const DEFAULT_DUPLICATE_SCAN: usize = 1000;
+const MAX_DUPLICATE_SCAN: usize = 5000;
+
+/// Cluster near-duplicate entities of one type by embedding similarity.
+/// Comparison is pairwise over the scanned vectors, so the scan is capped.
+pub async fn find_duplicates(
+    State(state): State<AppState>,
+    Query(params): Query<DuplicatesParams>,
+) -> Result<Json<DuplicatesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !(params.threshold > 0.0 && params.threshold <= 1.0) {
+        return Err(VectaDBError::Validation(format!(
+            "threshold must be in (0, 1], got {}",
+            params.threshold
+        ))
+        .into());
+    }
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let max_scan = params
+        .max_scan
+        .unwrap_or(DEFAULT_DUPLICATE_SCAN)
+        .clamp(1, MAX_DUPLICATE_SCAN);
+    let scan_error = |e: anyhow::Error| {
+        VectaDBError::Database(format!("Failed to scan {}: {}", params.entity_type, e))
+    };
+
+    let mut vectors = Vec::new();
+    let mut truncated = false;
+    if qdrant.collection_exists(&params.entity_type).await.map_err(scan_error)? {
+        let mut batches = Box::pin(qdrant.scroll(
+            &params.entity_type,
+            SCROLL_BATCH_SIZE.min(max_scan),
+            true,
+        ));
+        'scan: while let Some(batch) = batches.try_next().await.map_err(scan_error)? {
+            // Vectors left behind by deleted entities aren't duplicates of anything
+            let orphans: HashSet<String> = orphaned(surreal, &params.entity_type, &batch)
+                .await
+                .map_err(scan_error)?
+                .into_iter()
+                .map(|p| p.id.clone())
+                .collect();
+
+            for point in batch {
+                if orphans.contains(&point.id) {
+                    continue;
+                }
+                let Some(ref vector) = point.vector else {
+                    continue;
+                };
+                if vectors.len() == max_scan {
+                    truncated = true;
+                    break 'scan;
+                }
+                vectors.push((point.entity_id().to_string(), vector.clone()));
+            }
+        }
+    }
+
+    let clusters = near_duplicate_clusters(&vectors, params.threshold);
+
+    Ok(Json(DuplicatesResponse {
+        entity_type: params.entity_type,
+        threshold: params.threshold,
+        scanned: vectors.len(),
+        truncated,
+        clusters,
+    }))
+}
+
+/// Group ids whose vectors have cosine similarity of at least `threshold`,
+/// transitively. Only groups of two or more are returned, largest first.
+fn near_duplicate_clusters(vectors: &[(String, Vec<f32>)], threshold: f32) -> Vec<Vec<String>> {
+    let normalized: Vec<Vec<f32>> = vectors
+        .iter()
+        .map(|(_, vector)| {
+            let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm == 0.0 {
+                vector.clone()
+            } else {
+                vector.iter().map(|x| x / norm).collect()
+            }
+        })
+        .collect();
+
+    // Union-find over scan positions
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..vectors.len()).collect();
+    for i in 0..normalized.len() {
+        for j in (i + 1)..normalized.len() {
+            let similarity: f32 = normalized[i].iter().zip(&normalized[j]).map(|(a, b)| a * b).sum();
+            if similarity >= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                if a != b {
+                    parent[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut order = Vec::new();
+    for (i, (id, _)) in vectors.iter().enumerate() {
+        let r = root(&mut parent, i);
+        groups
+            .entry(r)
+            .or_insert_with(|| {
+                order.push(r);
+                Vec::new()
+            })
+            .push(id.clone());
+    }
+
+    let mut clusters: Vec<Vec<String>> = order
+        .into_iter()
+        .filter_map(|r| groups.remove(&r))
+        .filter(|ids| ids.len() > 1)
+        .collect();
+    clusters.sort_by_key(|ids| std::cmp::Reverse(ids.len()));
+    clusters
+}
+
+// ============================================================================
+// Export Handlers
+// ============================================================================
+
+const DEFAULT_EXPORT_BATCH_SIZE: usize = 500;
+const MAX_EXPORT_BATCH_SIZE: usize = 5000;
+
+/// Paging state carried between export chunks
+struct ExportState {
+    surreal: Arc<SurrealDBClient>,
+    cursor: Option<String>,
+    done: bool,
+}
+
+/// Stream a whole table as NDJSON, reading SurrealDB one chunk at a time
+pub async fn export_table(
+    State(state): State<AppState>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .clone()
+        .ok_or_else(database_unavailable)?;
+
+    let table = params.table;
+    let batch_size = params
+        .batch_size
+        .unwrap_or(DEFAULT_EXPORT_BATCH_SIZE)
+        .clamp(1, MAX_EXPORT_BATCH_SIZE);
+
+    let initial = ExportState {
+        surreal,
+        cursor: None,
+        done: false,
+    };
+
+    let stream = futures::stream::unfold(initial, move |mut st| async move {
+        if st.done {
+            return None;
+        }
+
+        let rows = match st
+            .surreal
+            .export_chunk(table, st.cursor.as_deref(), batch_size)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                // Headers are already sent, so the best we can do is abort the body
+                st.done = true;
+                return Some((Err(std::io::Error::other(e.to_string())), st));
+            }
+        };
+
+        if rows.is_empty() {
+            return None;
+        }
+
+        st.cursor = rows
+            .last()
+            .and_then(|row| row.get("id"))
+            .and_then(|id| id.as_str())
+            .map(String::from);
+        st.done = rows.len() < batch_size || st.cursor.is_none();
+
+        Some((Ok(encode_ndjson(table, rows)), st))
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// Encode a chunk of records as NDJSON lines
+fn encode_ndjson(table: ExportTable, rows: Vec<serde_json::Value>) -> Bytes {
+    let mut buf = Vec::new();
+    for record in rows {
+        let line = ExportRecord { table, record };
+        if serde_json::to_writer(&mut buf, &line).is_ok() {
+            buf.push(b'\n');
+        }
+    }
+    Bytes::from(buf)
+}
+
+// ============================================================================
+// Import Handlers
+// ============================================================================
+
+/// Cap on per-line errors echoed back in the import summary
+const MAX_IMPORT_ERRORS: usize = 100;
+
+/// Ingest an NDJSON body in the export format, upserting records with their original ids
+pub async fn import_records(
+    State(state): State<AppState>,
+    actor: Actor,
+    Query(params): Query<ImportParams>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<ImportResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let max_import = state.limits.max_import_bytes;
+    let max_line = state.limits.max_body_bytes;
+    let too_large = || VectaDBError::PayloadTooLarge(format!("Import body exceeds {} bytes", max_import));
+
+    // Refuse a declared oversize body before touching the database
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > max_import) {
+        return Err(too_large().into());
+    }
+
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let validator = if params.validate {
+        let reasoner = state.reasoner.read().await;
+        let reasoner = reasoner
+            .as_ref()
+            .ok_or_else(no_schema)?;
+        Some(OntologyValidator::new(reasoner.schema().clone()))
+    } else {
+        None
+    };
+
+    let mut response = ImportResponse::default();
+    let mut stream = body.into_data_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut line_no = 0;
+    let mut received = 0usize;
+
+    // Process complete lines as chunks arrive rather than buffering the whole body
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| {
+            VectaDBError::BadRequest(format!("Failed to read request body: {}", e))
+                .with_code("InvalidBody")
+        })?;
+        received += chunk.len();
+        if received > max_import {
+            return Err(too_large().into());
+        }
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            line_no += 1;
+            import_line(&state, &actor, surreal, validator.as_ref(), &line, line_no, &mut response).await;
+        }
+
+        // Whatever is left has no newline yet, so a single line may not outgrow the JSON body limit
+        if buffer.len() > max_line {
+            return Err(VectaDBError::PayloadTooLarge(format!(
+                "Import line {} exceeds {} bytes",
+                line_no + 1,
+                max_line
+            ))
+            .into());
+        }
+    }
+
+    if !buffer.is_empty() {
+        line_no += 1;
+        import_line(&state, &actor, surreal, validator.as_ref(), &buffer, line_no, &mut response).await;
+    }
+
+    Ok(Json(response))
+}
+
+/// Import a single NDJSON line, recording the outcome in the summary and
+/// the audit log
+async fn import_line(
+    state: &AppState,
+    actor: &Actor,
+    surreal: &SurrealDBClient,
+    validator: Option<&OntologyValidator>,
+    line: &[u8],
+    line_no: usize,
+    response: &mut ImportResponse,
+) {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return;
+    }
+
+    let result = async {
+        let record: ExportRecord =
+            serde_json::from_slice(line).map_err(|e| format!("Invalid record: {}", e))?;
+
+        if let Some(validator) = validator {
+            validate_import_record(surreal, validator, &record).await?;
+        }
+
+        let created = surreal
+            .import_record(record.table, &record.record)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>((record, created))
+    }
+    .await;
+
+    match result {
+        Ok((record, created)) => {
+            let operation = if created {
+                response.created += 1;
+                AuditOperation::Create
+            } else {
+                response.updated += 1;
+                AuditOperation::Update
+            };
+            let target_type = match record.table {
+                ExportTable::Entity => "entity",
+                ExportTable::Relation => "relation",
+                ExportTable::AgentEvent => "event",
+                ExportTable::AgentTrace => "trace",
+            };
+            let id = record.record.get("id").and_then(|id| id.as_str()).unwrap_or_default();
+            state.audit(actor, operation, target_type, id, serde_json::json!({ "imported": true }));
+        }
+        Err(error) => {
+            response.failed += 1;
+            if response.errors.len() < MAX_IMPORT_ERRORS {
+                response.errors.push(ImportError { line: line_no, error });
+            }
+        }
+    }
+}
+
+/// Check an imported entity or relation against the ontology
+async fn validate_import_record(
+    surreal: &SurrealDBClient,
+    validator: &OntologyValidator,
+    record: &ExportRecord,
+) -> Result<(), String> {
+    let field = |name: &str| record.record.get(name).and_then(|v| v.as_str());
+
+    match record.table {
+        ExportTable::Entity => {
+            let entity_type = field("entity_type").ok_or("Entity is missing entity_type")?;
+            let properties: HashMap<String, serde_json::Value> = match record.record.get("properties") {
+                Some(props) => serde_json::from_value(props.clone())
+                    .map_err(|e| format!("Invalid properties: {}", e))?,
+                None => HashMap::new(),
+            };
+
+            validator
+                .validate_entity(entity_type, &properties)
+                .map_err(|errors| {
+                    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    format!("Entity validation failed: {}", messages.join("; "))
+                })
+        }
+        ExportTable::Relation => {
+            let relation_type = field("relation_type").ok_or("Relation is missing relation_type")?;
+            let source_id = field("source_id").ok_or("Relation is missing source_id")?;
+            let target_id = field("target_id").ok_or("Relation is missing target_id")?;
+
+            let source = surreal
+                .get_entity(source_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Source entity '{}' not found", source_id))?;
+            let target = surreal
+                .get_entity(target_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Target entity '{}' not found", target_id))?;
+
+            validator
+                .validate_relation(relation_type, &source.entity_type, &target.entity_type)
+                .map_err(|e| e.to_string())
+        }
+        ExportTable::AgentEvent | ExportTable::AgentTrace => Ok(()),
+    }
+}
+
+// ============================================================================
+// Admin Handlers
+// ============================================================================
+
+const DEFAULT_REINDEX_BATCH_SIZE: usize = 64;
+const MAX_REINDEX_BATCH_SIZE: usize = 1000;
+
+/// Regenerate embeddings for every entity of a type with the current provider
+pub async fn reindex_entities(
+    State(state): State<AppState>,
+    Query(params): Query<ReindexParams>,
+) -> Result<Json<ReindexResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let embedding_service = state
+        .embedding_service
+        .as_ref()
+        .ok_or_else(|| VectaDBError::Unavailable("Embedding service not available".to_string()))?;
+
+    let entity_type = params.entity_type.clone();
+    let dimension = embedding_service.dimension_for(&entity_type) as u64;
+    let text_spec = {
+        let reasoner = state.reasoner.read().await;
+        EmbeddingTextSpec::for_type(reasoner.as_ref().map(|r| r.schema()), &entity_type)
+    };
+    let vector_error = |e: anyhow::Error| {
+        VectaDBError::Database(format!("Failed to prepare collection: {}", e))
+            .with_code("VectorDatabaseError")
+    };
+
+    // Named vectors stored on the type's entities or requested, which the collection must declare
+    let mut vector_names = surreal
+        .named_vector_names(&entity_type)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to read named vectors: {}", e)))?;
+    for name in params.vector_names.iter().flat_map(|names| names.split(',')).map(str::trim) {
+        if !is_valid_vector_name(name) {
+            return Err(VectaDBError::Validation(format!(
+                "Invalid vector name '{}': use letters, digits, '_' and '-', other than 'sparse'",
+                name
+            ))
+            .into());
+        }
+        vector_names.insert(name.to_string());
+    }
+    let supports_sparse = qdrant.collection_supports_sparse(&entity_type).await.map_err(vector_error)?;
+    let declared_names = qdrant.collection_vector_names(&entity_type).await.map_err(vector_error)?;
+    let outdated_layout = supports_sparse == Some(false)
+        || declared_names.is_some_and(|declared| !declared.is_superset(&vector_names));
+
+    // Make sure the collection matches the provider before writing anything
+    let mut collection_recreated = false;
+    match qdrant.collection_dimension(&entity_type).await.map_err(vector_error)? {
+        Some(existing) if existing != dimension => {
+            if !params.force {
+                return Err(VectaDBError::Conflict(format!(
+                    "Collection for '{}' has dimension {} but provider '{}' produces {}; pass force=true to recreate it",
+                    entity_type,
+                    existing,
+                    embedding_service.provider_for(&entity_type),
+                    dimension
+                ))
+                .with_code("DimensionMismatch")
+                .into());
+            }
+            qdrant.delete_collection(&entity_type).await.map_err(vector_error)?;
+            qdrant
+                .ensure_collection_with_named(&entity_type, dimension, &vector_names)
+                .await
+                .map_err(vector_error)?;
+            collection_recreated = true;
+        }
+        // Collections created before sparse vector support, or without the
+        // named vectors entities carry, are rebuilt with them on request
+        Some(_) if params.force && outdated_layout => {
+            qdrant.delete_collection(&entity_type).await.map_err(vector_error)?;
+            qdrant
+                .ensure_collection_with_named(&entity_type, dimension, &vector_names)
+                .await
+                .map_err(vector_error)?;
+            collection_recreated = true;
+        }
+        Some(_) => {}
+        None => {
+            qdrant
+                .ensure_collection_with_named(&entity_type, dimension, &vector_names)
+                .await
+                .map_err(vector_error)?;
+            collection_recreated = true;
+        }
+    }
+    let declared = qdrant
+        .collection_vector_names(&entity_type)
+        .await
+        .map_err(vector_error)?
+        .unwrap_or_default();
+
+    record_collection_meta(
+        &state.collection_meta,
+        surreal,
+        embedding_service,
+        &entity_type,
+        dimension as usize,
+    )
+    .await;
+
+    let batch_size = params
+        .batch_size
+        .unwrap_or(DEFAULT_REINDEX_BATCH_SIZE)
+        .clamp(1, MAX_REINDEX_BATCH_SIZE);
+
+    let mut response = ReindexResponse {
+        entity_type: entity_type.clone(),
+        provider: embedding_service.provider_for(&entity_type).to_string(),
+        dimension,
+        collection_recreated,
+        processed: 0,
+        skipped: 0,
+        failed: 0,
+        completed: false,
+        next_cursor: params.cursor.clone(),
+        error: None,
+    };
+
+    loop {
+        let visited = response.processed + response.skipped + response.failed;
+        let page_size = match params.limit {
+            Some(limit) if visited >= limit => break,
+            Some(limit) => batch_size.min(limit - visited),
+            None => batch_size,
+        };
+
+        let entities = surreal
+            .get_entities_page(&entity_type, response.next_cursor.as_deref(), page_size)
+            .await
+            .map_err(|e| VectaDBError::Database(format!("Failed to read entities: {}", e)))?;
+
+        if entities.is_empty() {
+            response.completed = true;
+            break;
+        }
+
+        let mut ids = Vec::new();
+        let mut texts = Vec::new();
+        for entity in &entities {
+            let text = text_spec.render(&entity.properties);
+            if text.is_empty() {
+                response.skipped += 1;
+            } else {
+                // Named vectors are written back as stored, where they still fit the collection
+                let named: HashMap<String, Vec<f32>> = entity
+                    .named_embeddings
+                    .iter()
+                    .filter(|(name, vector)| declared.contains(*name) && vector.len() as u64 == dimension)
+                    .map(|(name, vector)| (name.clone(), vector.clone()))
+                    .collect();
+                ids.push((entity.id_string(), entity.created_at.0, &entity.sparse_vector, named));
+                texts.push(text);
+            }
+        }
+
+        // A provider failure stops the run; the cursor still points at the last finished batch
+        let vectors = match embedding_service.embed_batch_for(&entity_type, &texts).await {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                tracing::warn!("Re-index of {} stopped: {}", entity_type, e);
+                response.error = Some(format!("Failed to generate embeddings: {}", e));
+                break;
+            }
+        };
+
+        for ((id, created_at, sparse_terms, named), vector) in ids.iter().zip(vectors) {
+            let stored = match qdrant
+                .upsert_vectors(&entity_type, id, Some(vector.clone()), sparse_terms, named, *created_at)
+                .await
+            {
+                Ok(()) => surreal.set_entity_embedding(id, vector).await,
+                Err(e) => Err(e),
+            };
+            match stored {
+                Ok(()) => response.processed += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to re-index entity {}: {}", id, e);
+                    response.failed += 1;
+                }
+            }
+        }
+
+        response.next_cursor = entities.last().map(|e| e.id_string());
+        tracing::info!(
+            "Re-indexed {} entities of type {} so far",
+            response.processed,
+            entity_type
+        );
+
+        if entities.len() < page_size {
+            response.completed = true;
+            break;
+        }
+    }
+
+    if response.completed {
+        response.next_cursor = None;
+    }
+
+    Ok(Json(response))
+}
+
+/// Points read per Qdrant scroll request when scanning collections
+const SCROLL_BATCH_SIZE: usize = 256;
+
+/// Compare every vector collection against SurrealDB, reporting orphaned points
+pub async fn collection_stats(
+    State(state): State<AppState>,
+) -> Result<Json<CollectionStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let entity_types = qdrant
+        .list_entity_collections()
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list collections: {}", e)))?;
+
+    let mut collections = Vec::with_capacity(entity_types.len());
+    for entity_type in entity_types {
+        let scan_error =
+            |e: anyhow::Error| VectaDBError::Database(format!("Failed to scan {}: {}", entity_type, e));
+
+        let entity_count = if entity_type == EVENTS_COLLECTION {
+            surreal.count_events().await
+        } else {
+            surreal.count_entities(&entity_type).await
+        }
+        .map_err(scan_error)?;
+        let mut point_count = 0u64;
+        let mut orphaned_points = 0u64;
+
+        let mut batches = Box::pin(qdrant.scroll(&entity_type, SCROLL_BATCH_SIZE, false));
+        while let Some(batch) = batches.try_next().await.map_err(scan_error)? {
+            point_count += batch.len() as u64;
+            let orphans = orphaned(surreal, &entity_type, &batch).await.map_err(scan_error)?;
+            orphaned_points += orphans.len() as u64;
+        }
+
+        let missing_vectors = entity_count.saturating_sub(point_count - orphaned_points);
+        collections.push(CollectionStats {
+            entity_type,
+            point_count,
+            entity_count,
+            orphaned_points,
+            missing_vectors,
+            consistent: orphaned_points == 0 && missing_vectors == 0,
+        });
+    }
+
+    Ok(Json(CollectionStatsResponse { collections }))
+}
+
+/// Delete vectors whose entity no longer exists in SurrealDB
+pub async fn gc_vectors(
+    State(state): State<AppState>,
+    Query(params): Query<GcVectorsParams>,
+) -> Result<Json<GcVectorsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let qdrant = state
+        .qdrant
+        .as_ref()
+        .ok_or_else(vector_store_unavailable)?;
+
+    let entity_types = qdrant
+        .list_entity_collections()
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list collections: {}", e)))?
+        .into_iter()
+        .filter(|t| params.entity_type.is_none() || params.entity_type.as_ref() == Some(t))
+        .collect::<Vec<_>>();
+
+    let mut response = GcVectorsResponse {
+        dry_run: params.dry_run,
+        scanned: 0,
+        removed: 0,
+        collections: Vec::with_capacity(entity_types.len()),
+    };
+
+    for entity_type in entity_types {
+        let gc_error =
+            |e: anyhow::Error| VectaDBError::Database(format!("Failed to clean {}: {}", entity_type, e));
+
+        let mut report = GcCollectionReport {
+            entity_type: entity_type.clone(),
+            scanned: 0,
+            removed: 0,
+        };
+
+        // Orphans in a batch sit before the scroll offset, so deleting them doesn't skip points
+        let mut batches = Box::pin(qdrant.scroll(&entity_type, SCROLL_BATCH_SIZE, false));
+        while let Some(batch) = batches.try_next().await.map_err(gc_error)? {
+            report.scanned += batch.len() as u64;
+
+            let orphans: Vec<String> = orphaned(surreal, &entity_type, &batch)
+                .await
+                .map_err(gc_error)?
+                .into_iter()
+                .map(|p| p.id.clone())
+                .collect();
+            if !params.dry_run {
+                qdrant.delete_embeddings(&entity_type, &orphans).await.map_err(gc_error)?;
+            }
+            report.removed += orphans.len() as u64;
+        }
+
+        if report.removed > 0 {
+            tracing::info!(
+                "{} {} orphaned vectors from {}",
+                if params.dry_run { "Found" } else { "Removed" },
+                report.removed,
+                entity_type
+            );
+        }
+        response.scanned += report.scanned;
+        response.removed += report.removed;
+        response.collections.push(report);
+    }
+
+    Ok(Json(response))
+}
+
+/// Points in a batch whose entity (or event) no longer exists
+async fn orphaned<'a>(
+    surreal: &SurrealDBClient,
+    entity_type: &str,
+    points: &'a [VectorPoint],
+) -> anyhow::Result<Vec<&'a VectorPoint>> {
+    let ids: Vec<String> = points.iter().map(|p| p.entity_id().to_string()).collect();
+    let existing = if entity_type == EVENTS_COLLECTION {
+        surreal.existing_event_ids(&ids).await?
+    } else {
+        surreal.existing_entity_ids(&ids).await?
+    };
+    Ok(points.iter().filter(|p| !existing.contains(p.entity_id())).collect())
+}
+
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 100;
+const MAX_DEAD_LETTER_LIMIT: usize = 1000;
+
+/// List events kept after failing bulk ingestion, oldest first
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(params): Query<DeadLetterParams>,
+) -> Result<Json<ListDeadLettersResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let limit = dead_letter_limit(&params);
+    let dead_letters = surreal
+        .list_dead_letters(limit)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list dead letters: {}", e)))?;
+
+    Ok(Json(ListDeadLettersResponse {
+        count: dead_letters.len(),
+        dead_letters,
+    }))
+}
+
+/// Re-ingest dead-lettered events, oldest first. Replayed events leave the
+/// store; events that fail again stay with their error and attempt count
+/// updated. Traces are auto-created and embeddings generated as with the
+/// default ingestion options.
+pub async fn replay_dead_letters(
+    State(state): State<AppState>,
+    actor: Actor,
+    Query(params): Query<DeadLetterParams>,
+) -> Result<Json<ReplayDeadLettersResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let surreal = state
+        .surreal
+        .as_ref()
+        .ok_or_else(database_unavailable)?;
+
+    let limit = dead_letter_limit(&params);
+    let dead_letters = surreal
+        .list_dead_letters(limit)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list dead letters: {}", e)))?;
+
+    let options = IngestionOptions {
+        auto_create_traces: true,
+        generate_embeddings: true,
+        ..IngestionOptions::default()
+    };
+    let mut response = ReplayDeadLettersResponse {
+        replayed: 0,
+        failed: 0,
+        errors: Vec::new(),
+    };
+
+    for letter in dead_letters {
+        let outcome = match serde_json::from_value::<EventIngestionRequest>(letter.event) {
+            Ok(event_request) => ingest_one_event(&state, &actor, surreal, &event_request, &options)
+                .await
+                .map(|_| ()),
+            Err(e) => Err(format!("Invalid event payload: {}", e)),
+        };
 
-                ingested += 1;
-                if !trace_ids.contains(&trace_id) {
-                    trace_ids.push(trace_id);
+        match outcome {
+            Ok(()) => {
+                response.replayed += 1;
+                if let Err(e) = surreal.delete_dead_letter(&letter.id).await {
+                    tracing::warn!("Failed to remove replayed dead letter {}: {}", letter.id, e);
                 }
             }
-            Err(e) => {
-                failed += 1;
-                errors.push(IngestionError {
-                    index,
-                    error: format!("Failed to create event: {}", e),
+            Err(error) => {
+                response.failed += 1;
+                if let Err(e) = surreal.record_dead_letter_failure(&letter.id, &error).await {
+                    tracing::warn!("Failed to update dead letter {}: {}", letter.id, e);
+                }
+                response.errors.push(DeadLetterError {
+                    id: letter.id,
+                    error,
                 });
             }
         }
     }
 
-    Ok(Json(BulkEventIngestionResponse {
-        ingested,
-        failed,
-        trace_ids,
-        errors,
-    }))
+    Ok(Json(response))
 }
 
-/// Get or create trace by session_id with resilient detection
-async fn get_or_create_trace_by_session(
-    state: &AppState,
-    session_id: &str,
-    agent_id: Option<&str>,
-) -> Result<String, anyhow::Error> {
+fn dead_letter_limit(params: &DeadLetterParams) -> usize {
+    params
+        .limit
+        .unwrap_or(DEFAULT_DEAD_LETTER_LIMIT)
+        .clamp(1, MAX_DEAD_LETTER_LIMIT)
+}
+
+// ============================================================================
+// Audit Log
+// ============================================================================
+
+/// Recorded creates, updates and deletes, most recent first
+pub async fn list_audit_records(
+    State(state): State<AppState>,
+    Query(params): Query<AuditParams>,
+) -> Result<Json<AuditResponse>, (StatusCode, Json<ErrorResponse>)> {
     let surreal = state
         .surreal
         .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Database not available"))?;
+        .ok_or_else(database_unavailable)?;
 
-    // Strategy 1: Try exact session_id match first
-    #[derive(Debug, serde::Deserialize)]
-    struct TraceRecord {
-        id: String,
-        #[allow(dead_code)] // Reserved for future time-based filtering
-        start_time: Option<String>,
-    }
+    let limit = state.query.limit(params.limit);
+    let records = surreal
+        .list_audit_records(params.target_id.as_deref(), limit)
+        .await
+        .map_err(|e| VectaDBError::Database(format!("Failed to list audit records: {}", e)))?;
 
-    let query = format!(
-        "SELECT id, start_time FROM agent_trace WHERE session_id = '{}' ORDER BY start_time DESC LIMIT 1",
-        session_id.replace('\'', "\\'")
-    );
+    Ok(Json(AuditResponse {
+        count: records.len(),
+        records,
+        limit,
+    }))
+}
 
-    let mut result = surreal.db().query(query).await?;
-    let traces: Vec<TraceRecord> = result.take(0).unwrap_or_default();
+// ============================================================================
+// Helper Functions
+// ============================================================================
 
-    if let Some(trace) = traces.first() {
-        tracing::debug!("Found trace by session_id: {}", trace.id);
-        return Ok(trace.id.clone());
+/// Reject bulk requests with more items than the configured maximum
+fn check_batch_size(len: usize, max: usize) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if len > max {
+        return Err(VectaDBError::PayloadTooLarge(format!(
+            "Batch of {} items exceeds the maximum of {}",
+            len, max
+        ))
+        .with_code("BatchTooLarge")
+        .into());
     }
+    Ok(())
+}
 
-    // Strategy 2: If agent_id provided, check for recent trace (within 1 hour)
-    if let Some(aid) = agent_id {
-        let query = format!(
-            "SELECT id, start_time FROM agent_trace WHERE agent_id = '{}' AND status = 'running' AND start_time > time::now() - 1h ORDER BY start_time DESC LIMIT 1",
-            aid.replace('\'', "\\'")
-        );
+/// Record which provider/model produced the vectors in the collection of
+/// `entity_type`. Called only when a collection is created or re-indexed.
+async fn record_collection_meta(
+    cache: &CollectionMetaCache,
+    surreal: &SurrealDBClient,
+    embedding_service: &EmbeddingManager,
+    entity_type: &str,
+    dimension: usize,
+) {
+    let meta = EmbeddingMetadata::for_collection(
+        entity_type,
+        embedding_service.provider_for(entity_type),
+        embedding_service.model_for(entity_type),
+        dimension,
+    );
+    if let Err(e) = cache.store(surreal, meta).await {
+        tracing::warn!("Failed to record metadata for collection {}: {}", entity_type, e);
+    }
+}
 
-        let mut result = surreal.db().query(query).await?;
-        let traces: Vec<TraceRecord> = result.take(0).unwrap_or_default();
+/// How an entity type's properties become its embedding text, per the
+/// loaded ontology
+#[derive(Debug, Default)]
+struct EmbeddingTextSpec {
+    /// Template from the type's metadata, which takes precedence
+    template: Option<String>,
+    /// Properties marked embeddable, or None to embed all of them
+    fields: Option<HashSet<String>>,
+}
 
-        if let Some(trace) = traces.first() {
-            tracing::debug!("Found trace by agent_id: {}", trace.id);
-            return Ok(trace.id.clone());
+impl EmbeddingTextSpec {
+    fn for_type(schema: Option<&OntologySchema>, entity_type: &str) -> Self {
+        let Some((schema, definition)) =
+            schema.and_then(|schema| Some((schema, schema.entity_types.get(entity_type)?)))
+        else {
+            return Self::default();
+        };
+
+        let fields: HashSet<String> = definition.embeddable_properties(schema).into_iter().collect();
+        Self {
+            template: definition.embedding_template(schema).map(str::to_string),
+            fields: (!fields.is_empty()).then_some(fields),
         }
     }
 
-    // Strategy 3: Create new trace
-    tracing::info!("Creating new trace for session_id: {}", session_id);
-    create_trace_for_session(state, session_id, agent_id).await
+    fn render(&self, properties: &HashMap<String, serde_json::Value>) -> String {
+        match &self.template {
+            Some(template) => render_embedding_template(template, properties),
+            None => extract_text_from_properties(properties, self.fields.as_ref()),
+        }
+    }
 }
 
-/// Create a new trace for a session
-async fn create_trace_for_session(
-    state: &AppState,
-    session_id: &str,
-    agent_id: Option<&str>,
-) -> Result<String, anyhow::Error> {
-    let surreal = state
-        .surreal
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("Database not available"))?;
-
-    let trace_id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now();
+/// How deep nested objects and arrays are followed for embedding text
+const MAX_TEXT_DEPTH: usize = 8;
 
-    let agent_id_str = agent_id.map(|s| format!("'{}'", s.replace('\'', "\\'")))
-        .unwrap_or_else(|| "NONE".to_string());
+/// Extract text content from entity properties for embedding generation,
+/// limited to `fields` when given
+fn extract_text_from_properties(
+    properties: &HashMap<String, serde_json::Value>,
+    fields: Option<&HashSet<String>>,
+) -> String {
+    let mut text_parts = Vec::new();
 
-    let query = format!(
-        "CREATE agent_trace CONTENT {{
-            id: '{}',
-            session_id: '{}',
-            agent_id: {},
-            status: 'running',
-            start_time: '{}',
-            created_at: '{}',
-            updated_at: '{}'
-        }}",
-        trace_id,
-        session_id.replace('\'', "\\'"),
-        agent_id_str,
-        now.to_rfc3339(),
-        now.to_rfc3339(),
-        now.to_rfc3339()
-    );
+    for (key, value) in properties {
+        if fields.is_some_and(|fields| !fields.contains(key)) {
+            continue;
+        }
+        collect_text(key, value, 0, &mut text_parts);
+    }
 
-    surreal.db().query(query).await?;
+    text_parts.join(". ")
+}
 
-    Ok(trace_id)
+/// Extract text content from JSON value for embedding generation
+fn extract_text_from_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        _ => {
+            let mut text_parts = Vec::new();
+            collect_text("", value, 0, &mut text_parts);
+            text_parts.join(". ")
+        }
+    }
 }
 
-/// Create event entity in SurrealDB
-async fn create_event_entity(
-    surreal: &SurrealDBClient,
-    request: &EventIngestionRequest,
-    trace_id: &str,
-) -> Result<String, anyhow::Error> {
-    let event_id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now();
+/// Append `value`'s scalar leaves to `text_parts` as `path: value`, where
+/// the path joins the keys leading to the leaf with dots. Array elements
+/// share their array's path, and scalar arrays become one comma-separated
+/// part. Nothing below `MAX_TEXT_DEPTH` levels is included.
+fn collect_text(path: &str, value: &serde_json::Value, depth: usize, text_parts: &mut Vec<String>) {
+    let scalar = |value: &serde_json::Value| match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
 
-    // Build event properties as JSON
-    let mut event_data = serde_json::json!({
-        "id": event_id,
-        "trace_id": trace_id,
-        "timestamp": request.timestamp.to_rfc3339(),
-        "properties": request.properties,
-        "created_at": now.to_rfc3339(),
-        "updated_at": now.to_rfc3339(),
-    });
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Object(map) => {
+            if depth >= MAX_TEXT_DEPTH {
+                return;
+            }
+            for (key, val) in map {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                collect_text(&path, val, depth + 1, text_parts);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            if depth >= MAX_TEXT_DEPTH {
+                return;
+            }
+            let scalars: Vec<String> = items.iter().filter_map(scalar).collect();
+            if !scalars.is_empty() {
+                let joined = scalars.join(", ");
+                text_parts.push(if path.is_empty() { joined } else { format!("{}: {}", path, joined) });
+            }
+            for item in items.iter().filter(|item| item.is_object() || item.is_array()) {
+                collect_text(path, item, depth + 1, text_parts);
+            }
+        }
+        leaf => {
+            if let Some(text) = scalar(leaf) {
+                text_parts.push(if path.is_empty() { text } else { format!("{}: {}", path, text) });
+            }
+        }
+    }
+}
 
-    // Add optional fields
-    if let Some(ref event_type) = request.event_type {
-        event_data["event_type"] = serde_json::json!(event_type);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelPrice;
+    use crate::query::{QueryMetadata, ResultSource};
+
+    fn scored(name: &str, score: f32) -> ScoredResult {
+        let mut properties = HashMap::new();
+        properties.insert("name".to_string(), serde_json::json!(name));
+        ScoredResult {
+            entity: Entity::new("Agent".to_string(), properties),
+            score,
+            source: ResultSource::Vector,
+            explanation: None,
+        }
     }
-    if let Some(ref agent_id) = request.agent_id {
-        event_data["agent_id"] = serde_json::json!(agent_id);
+
+    /// Parse an SSE body into (event, data) pairs
+    fn parse_sse(body: &str) -> Vec<(String, serde_json::Value)> {
+        body.split("\n\n")
+            .filter_map(|block| {
+                let event = block.lines().find_map(|l| l.strip_prefix("event: "))?;
+                let data = block.lines().find_map(|l| l.strip_prefix("data: "))?;
+                Some((event.to_string(), serde_json::from_str(data).unwrap()))
+            })
+            .collect()
     }
-    if let Some(ref session_id) = request.session_id {
-        event_data["session_id"] = serde_json::json!(session_id);
+
+    #[tokio::test]
+    async fn test_query_stream_emits_results_then_metadata() {
+        let (sender, receiver) = mpsc::channel(4);
+        let producer = tokio::spawn(async move {
+            for (name, score) in [("alpha", 0.9), ("beta", 0.7)] {
+                sender.send(scored(name, score)).await.unwrap();
+            }
+            let metadata = QueryMetadata {
+                execution_time_ms: 0,
+                vector_count: Some(2),
+                graph_count: None,
+                searched_types: Some(vec!["Agent".to_string()]),
+                traversed_relations: None,
+                cache_hit: false,
+                limit: None,
+                explanation: None,
+                extra: HashMap::new(),
+            };
+            Ok(StreamSummary::new(2, metadata, std::time::Instant::now()))
+        });
+        let done = async move { producer.await.unwrap() };
+
+        let response = Sse::new(query_event_stream(receiver, done)).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let events = parse_sse(std::str::from_utf8(&body).unwrap());
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].0, "result");
+        assert_eq!(events[0].1["entity"]["properties"]["name"], "alpha");
+        assert_eq!(events[1].0, "result");
+        assert_eq!(events[1].1["score"].as_f64().unwrap() as f32, 0.7);
+        assert_eq!(events[2].0, "metadata");
+        assert_eq!(events[2].1["total_count"], 2);
+        assert_eq!(events[2].1["metadata"]["vector_count"], 2);
     }
-    if let Some(ref source) = request.source {
-        event_data["source"] = serde_json::json!(source);
+
+    #[tokio::test]
+    async fn test_query_stream_reports_failure_as_error_event() {
+        let (sender, receiver) = mpsc::channel::<ScoredResult>(1);
+        drop(sender);
+        let done = async { Err(anyhow::anyhow!("qdrant unreachable")) };
+
+        let response = Sse::new(query_event_stream(receiver, done)).into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let events = parse_sse(std::str::from_utf8(&body).unwrap());
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "error");
+        assert_eq!(events[0].1["error"], "QueryError");
     }
 
-    let query = format!("CREATE agent_event CONTENT {}", event_data);
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("30s"), Some(30));
+        assert_eq!(parse_interval("15m"), Some(900));
+        assert_eq!(parse_interval("1h"), Some(3600));
+        assert_eq!(parse_interval("2d"), Some(172_800));
+        assert_eq!(parse_interval("1w"), Some(604_800));
+        assert_eq!(parse_interval("0h"), None);
+        assert_eq!(parse_interval("-1h"), None);
+        assert_eq!(parse_interval("h"), None);
+        assert_eq!(parse_interval("1y"), None);
+        assert_eq!(parse_interval(""), None);
+    }
 
-    surreal.db().query(query).await?;
+    #[test]
+    fn test_searchable_properties() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
 
-    // Create relation from trace to event
-    let trace_record_id = format!("agent_trace:`{}`", trace_id);
-    let event_record_id = format!("agent_event:`{}`", event_id);
+        let mut schema = OntologySchema::new("search://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Log".to_string(), "Log".to_string())
+                .with_property(PropertyDefinition::new("message".to_string(), PropertyType::String).searchable())
+                .with_property(PropertyDefinition::new("level".to_string(), PropertyType::String)),
+        );
+        schema.add_entity_type(
+            EntityType::new("ToolLog".to_string(), "Tool log".to_string())
+                .with_parent("Log".to_string())
+                .with_property(PropertyDefinition::new("output".to_string(), PropertyType::String).searchable()),
+        );
+        schema.add_entity_type(
+            EntityType::new("Note".to_string(), "Note".to_string())
+                .with_property(PropertyDefinition::new("body".to_string(), PropertyType::String).searchable()),
+        );
 
-    let relation_query = format!(
-        "RELATE {}->contains->{} CONTENT {{
-            created_at: '{}'
-        }}",
-        trace_record_id,
-        event_record_id,
-        now.to_rfc3339()
-    );
+        assert_eq!(searchable_properties(Some(&schema), Some("ToolLog")), vec!["message", "output"]);
+        assert_eq!(searchable_properties(Some(&schema), Some("Log")), vec!["message"]);
+        assert_eq!(searchable_properties(Some(&schema), None), vec!["body", "message", "output"]);
+        assert!(searchable_properties(Some(&schema), Some("Unknown")).is_empty());
+        assert!(searchable_properties(None, None).is_empty());
+    }
 
-    surreal.db().query(relation_query).await?;
+    #[test]
+    fn test_read_validation_skips_entities_from_older_schema_versions() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
 
-    Ok(event_id)
-}
+        let mut v2 = OntologySchema::new("evolve://test".to_string(), "2.0.0".to_string());
+        v2.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string())
+                .with_property(PropertyDefinition::new("owner".to_string(), PropertyType::String).required()),
+        );
 
-/// Store event embedding in Qdrant
-async fn store_event_vector(
-    qdrant: &QdrantClient,
-    event_id: &str,
-    embedding: Vec<f32>,
-) -> Result<(), anyhow::Error> {
-    const EVENTS_COLLECTION: &str = "agent_events";
+        let tagged = |version: &str| {
+            Entity::new("Agent".to_string(), HashMap::new()).with_schema_version(Some(version.to_string()))
+        };
 
-    // Ensure collection exists
-    if !qdrant.collection_exists(EVENTS_COLLECTION).await? {
-        qdrant
-            .create_collection(EVENTS_COLLECTION, embedding.len() as u64)
-            .await?;
+        // Written under v1, before `owner` was required
+        let old = tagged("1.0.0");
+        assert!(read_validation_errors(&v2, &old).is_empty());
+        let untagged = Entity::new("Agent".to_string(), HashMap::new());
+        assert!(read_validation_errors(&v2, &untagged).is_empty());
+
+        // Claimed to be written under v2, so held to it
+        let current = tagged("2.0.0");
+        let errors = read_validation_errors(&v2, &current);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("owner"));
     }
 
-    // Store embedding
-    qdrant
-        .upsert_embedding(EVENTS_COLLECTION, event_id, embedding)
-        .await?;
+    #[test]
+    fn test_new_required_property_reports_invalid_existing_entities() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
 
-    Ok(())
-}
+        let mut current = OntologySchema::new("migration://test".to_string(), "1.0.0".to_string());
+        current.add_entity_type(EntityType::new("Agent".to_string(), "Agent".to_string()));
+        current.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        current.add_entity_type(EntityType::new("Task".to_string(), "Task".to_string()));
 
-// ============================================================================
-// Helper Functions
-// ============================================================================
+        let mut candidate = current.clone();
+        candidate.entity_types.get_mut("Agent").unwrap().properties.push(
+            PropertyDefinition::new("owner".to_string(), PropertyType::String).required(),
+        );
 
-/// Extract text content from entity properties for embedding generation
-fn extract_text_from_properties(properties: &HashMap<String, serde_json::Value>) -> String {
-    let mut text_parts = Vec::new();
+        // LLMAgent inherits the new requirement; Task is untouched
+        let affected = affected_entity_types(Some(&current), &candidate);
+        assert_eq!(affected, vec!["Agent", "LLMAgent"]);
 
-    for (key, value) in properties {
-        match value {
-            serde_json::Value::String(s) => {
-                text_parts.push(format!("{}: {}", key, s));
-            }
-            serde_json::Value::Number(n) => {
-                text_parts.push(format!("{}: {}", key, n));
-            }
-            serde_json::Value::Bool(b) => {
-                text_parts.push(format!("{}: {}", key, b));
+        let entity = |entity_type: &str, owner: Option<&str>| {
+            let mut properties = HashMap::new();
+            if let Some(owner) = owner {
+                properties.insert("owner".to_string(), serde_json::json!(owner));
             }
-            _ => {}
+            Entity::new(entity_type.to_string(), properties)
+        };
+        let stored = vec![
+            entity("Agent", Some("ops")),
+            entity("Agent", None),
+            entity("LLMAgent", None),
+        ];
+
+        let validator = OntologyValidator::new(candidate);
+        let mut report = ExistingDataReport::default();
+        check_existing_entities(&validator, &stored, &mut report);
+
+        assert_eq!(report.checked_entities, 3);
+        assert_eq!(report.invalid_entities, 2);
+        assert_eq!(report.samples.len(), 2);
+        assert_eq!(report.samples[1].entity_type, "LLMAgent");
+        assert!(report.samples[0].errors[0].contains("owner"));
+    }
+
+
+    #[test]
+    fn test_embedding_text_respects_embeddable_properties() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+
+        let mut schema = OntologySchema::new("embed://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Log".to_string(), "Log".to_string())
+                .with_property(PropertyDefinition::new("id".to_string(), PropertyType::String))
+                .with_property(PropertyDefinition::new("timestamp".to_string(), PropertyType::DateTime))
+                .with_property(
+                    PropertyDefinition::new("message".to_string(), PropertyType::String).embeddable(),
+                ),
+        );
+        schema.add_entity_type(EntityType::new("Task".to_string(), "Task".to_string()));
+
+        let mut properties = HashMap::new();
+        properties.insert("id".to_string(), serde_json::json!("log-8f3a2c"));
+        properties.insert("timestamp".to_string(), serde_json::json!("2026-01-07T12:00:00Z"));
+        properties.insert("message".to_string(), serde_json::json!("Connection refused"));
+
+        let spec = EmbeddingTextSpec::for_type(Some(&schema), "Log");
+        assert_eq!(spec.render(&properties), "message: Connection refused");
+
+        // Without annotations or an ontology every scalar property is embedded
+        for spec in [
+            EmbeddingTextSpec::for_type(Some(&schema), "Task"),
+            EmbeddingTextSpec::for_type(None, "Log"),
+        ] {
+            assert!(spec.fields.is_none());
+            let text = spec.render(&properties);
+            assert!(text.contains("log-8f3a2c") && text.contains("Connection refused"));
         }
     }
 
-    text_parts.join(". ")
-}
+    #[test]
+    fn test_embedding_text_uses_inherited_template() {
+        use crate::ontology::entity_type::EntityType;
+
+        let mut schema = OntologySchema::new("embed://test".to_string(), "1.0.0".to_string());
+        let mut message = EntityType::new("Message".to_string(), "Message".to_string());
+        message.metadata = serde_json::json!({ "embedding_template": "{role|user}: {content}" });
+        schema.add_entity_type(message);
+        schema.add_entity_type(
+            EntityType::new("ChatMessage".to_string(), "Chat Message".to_string())
+                .with_parent("Message".to_string()),
+        );
 
-/// Extract text content from JSON value for embedding generation
-fn extract_text_from_json(value: &serde_json::Value) -> String {
-    match value {
-        serde_json::Value::String(s) => s.clone(),
-        serde_json::Value::Object(map) => {
-            let mut text_parts = Vec::new();
-            for (key, val) in map {
-                match val {
-                    serde_json::Value::String(s) => {
-                        text_parts.push(format!("{}: {}", key, s));
-                    }
-                    serde_json::Value::Number(n) => {
-                        text_parts.push(format!("{}: {}", key, n));
-                    }
-                    serde_json::Value::Bool(b) => {
-                        text_parts.push(format!("{}: {}", key, b));
-                    }
-                    _ => {}
-                }
-            }
-            text_parts.join(". ")
+        let mut properties = HashMap::new();
+        properties.insert("content".to_string(), serde_json::json!("Summarize the incident"));
+        properties.insert("id".to_string(), serde_json::json!("msg-1"));
+
+        let spec = EmbeddingTextSpec::for_type(Some(&schema), "ChatMessage");
+        assert_eq!(spec.render(&properties), "user: Summarize the incident");
+    }
+
+    #[test]
+    fn test_embedding_text_includes_nested_values() {
+        let payload = serde_json::json!({
+            "tool": "web_search",
+            "input": {
+                "query": "rust async runtimes",
+                "filters": { "language": "en", "max_results": 5 }
+            },
+            "output": {
+                "results": [
+                    { "title": "Tokio tutorial", "tags": ["async", "runtime"] },
+                    { "title": "async-std book" }
+                ]
+            },
+            "error": null
+        });
+
+        let text = extract_text_from_json(&payload);
+        assert!(text.contains("tool: web_search"));
+        assert!(text.contains("input.query: rust async runtimes"));
+        assert!(text.contains("input.filters.max_results: 5"));
+        assert!(text.contains("output.results.title: Tokio tutorial"));
+        assert!(text.contains("output.results.title: async-std book"));
+        assert!(text.contains("output.results.tags: async, runtime"));
+        assert!(!text.contains("null"));
+
+        let properties: HashMap<String, serde_json::Value> =
+            serde_json::from_value(payload).unwrap();
+        let text = extract_text_from_properties(&properties, None);
+        assert!(text.contains("input.filters.language: en"));
+
+        // Nothing is taken from below the depth cap
+        let mut deep = serde_json::json!("buried");
+        for _ in 0..=MAX_TEXT_DEPTH {
+            deep = serde_json::json!({ "next": deep });
         }
-        _ => String::new(),
+        assert_eq!(extract_text_from_json(&deep), "");
+        assert!(extract_text_from_json(&deep["next"]).ends_with(": buried"));
+    }
+
+    #[test]
+    fn test_near_duplicates_cluster_together() {
+        let vectors = vec![
+            ("log-1".to_string(), vec![1.0, 0.02, 0.0]),
+            ("log-2".to_string(), vec![0.0, 1.0, 0.0]),
+            ("log-3".to_string(), vec![2.0, 0.0, 0.01]),
+            ("log-4".to_string(), vec![0.99, 0.03, 0.0]),
+            ("log-5".to_string(), vec![0.0, 0.0, 1.0]),
+            ("log-6".to_string(), vec![0.0, 0.01, 1.0]),
+        ];
+
+        let clusters = near_duplicate_clusters(&vectors, 0.97);
+        assert_eq!(
+            clusters,
+            vec![
+                vec!["log-1".to_string(), "log-3".to_string(), "log-4".to_string()],
+                vec!["log-5".to_string(), "log-6".to_string()],
+            ]
+        );
+
+        // At a threshold of 1.0 only exact directions match
+        assert!(near_duplicate_clusters(&vectors, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_cost_report_prices_each_model() {
+        let pricing = PricingConfig {
+            models: HashMap::from([
+                ("haiku".to_string(), ModelPrice { input_per_1k: 0.25, output_per_1k: 1.25 }),
+                ("sonnet".to_string(), ModelPrice { input_per_1k: 3.0, output_per_1k: 15.0 }),
+            ]),
+        };
+        let usage = |model_id: Option<&str>, events, without_tokens, input_tokens, output_tokens| ModelTokenUsage {
+            model_id: model_id.map(str::to_string),
+            events,
+            events_without_tokens: without_tokens,
+            input_tokens,
+            output_tokens,
+        };
+
+        let report = cost_report(
+            vec![
+                usage(Some("haiku"), 3, 1, 4000, 2000),
+                usage(Some("sonnet"), 2, 0, 1000, 500),
+                usage(Some("titan"), 4, 0, 8000, 0),
+                usage(None, 2, 2, 0, 0),
+            ],
+            &pricing,
+        );
+
+        // haiku: 4 * 0.25 + 2 * 1.25 = 3.5; sonnet: 1 * 3 + 0.5 * 15 = 10.5
+        assert!((report.total_cost - 14.0).abs() < 1e-9);
+        let costs: Vec<(&str, Option<f64>)> = report.models.iter().map(|m| (m.model_id.as_str(), m.cost)).collect();
+        assert_eq!(
+            costs,
+            vec![("sonnet", Some(10.5)), ("haiku", Some(3.5)), ("titan", None), ("unknown", None)]
+        );
+        assert_eq!(report.unpriced_events, 6);
+        assert_eq!(report.events_without_tokens, 3);
+    }
+
+    #[test]
+    fn test_functional_violation_allows_one_target() {
+        let assigned_to = RelationType::new(
+            "assigned_to".to_string(),
+            "Assigned to".to_string(),
+            "Task".to_string(),
+            "Agent".to_string(),
+        )
+        .functional();
+        let existing = vec![Relation::new(
+            "assigned_to".to_string(),
+            "task-1".to_string(),
+            "agent-1".to_string(),
+            HashMap::new(),
+        )];
+
+        // The first relation from a source is fine, and so is repeating it
+        assert!(functional_violation(&assigned_to, &[], Some("agent-1")).is_none());
+        assert!(functional_violation(&assigned_to, &existing, Some("agent-1")).is_none());
+
+        let violation = functional_violation(&assigned_to, &existing, Some("agent-2")).unwrap();
+        assert_eq!(violation.error_type, "FunctionalViolation");
+        assert!(violation.message.contains("agent-1"));
+        assert!(functional_violation(&assigned_to, &existing, None).is_some());
     }
 }
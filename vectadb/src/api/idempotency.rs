@@ -0,0 +1,259 @@
+// Idempotent creates: a retried request with the same Idempotency-Key gets the original response
+
+use axum::http::HeaderMap;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::error::VectaDBError;
+use crate::ttl_map::TtlMap;
+
+/// Header carrying the client-chosen key
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Most keys held at once; beyond this, new keys are not remembered
+const MAX_ENTRIES: usize = 10_000;
+
+type Outcome = Arc<dyn Any + Send + Sync>;
+
+struct Entry {
+    outcome: Arc<OnceCell<Outcome>>,
+    /// Hash of the request body the key was first used with
+    fingerprint: u64,
+}
+
+/// Remembers the response of each keyed create for `ttl`
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: Mutex<TtlMap<Entry>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(TtlMap::new(ttl, MAX_ENTRIES)),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Run `create` on `request` at most once per `Idempotency-Key` within `scope`.
+    ///
+    /// Without a key, `create` always runs. With one, a repeat returns the
+    /// first successful result; concurrent repeats wait for the first attempt
+    /// instead of racing it. Reusing a key with a different request body is
+    /// rejected with 422. Failures are not remembered, so a retry after an
+    /// error runs `create` again.
+    pub async fn once<B, T, E, F, Fut>(&self, scope: &str, headers: &HeaderMap, request: B, create: F) -> Result<T, E>
+    where
+        B: Serialize,
+        T: Clone + Send + Sync + 'static,
+        E: From<VectaDBError>,
+        F: FnOnce(B) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let Some(key) = idempotency_key(headers)? else {
+            return create(request).await;
+        };
+
+        let fingerprint = fingerprint(&request)?;
+        let Some(cell) = self.cell(format!("{}:{}", scope, key), fingerprint, Instant::now())? else {
+            return create(request).await;
+        };
+        let outcome = cell
+            .get_or_try_init(|| async { create(request).await.map(|result| Arc::new(result) as Outcome) })
+            .await?;
+
+        outcome.downcast_ref::<T>().cloned().ok_or_else(|| {
+            VectaDBError::Internal(format!("Idempotency key '{}' holds a different response type", key)).into()
+        })
+    }
+
+    /// The slot for `key`, or `None` if the store is full and the key cannot be remembered
+    fn cell(&self, key: String, fingerprint: u64, now: Instant) -> Result<Option<Arc<OnceCell<Outcome>>>, VectaDBError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(entry) = entries.get(&key, now) {
+            if entry.fingerprint != fingerprint {
+                return Err(VectaDBError::Unprocessable(
+                    "Idempotency-Key was already used with a different request body".to_string(),
+                ));
+            }
+            return Ok(Some(entry.outcome.clone()));
+        }
+
+        let outcome = Arc::new(OnceCell::new());
+        let entry = Entry {
+            outcome: outcome.clone(),
+            fingerprint,
+        };
+        Ok(entries.insert(key, entry, now).then_some(outcome))
+    }
+}
+
+/// Hash of the request body with object keys sorted, so bodies that differ
+/// only in key order (or `HashMap` iteration order) hash the same
+fn fingerprint<B: Serialize>(request: &B) -> Result<u64, VectaDBError> {
+    let mut canonical = serde_json::to_value(request)?;
+    canonical.sort_all_objects();
+
+    let mut hasher = DefaultHasher::new();
+    canonical.to_string().hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+/// The request's idempotency key, if it sent one
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, VectaDBError> {
+    let Some(value) = headers.get(IDEMPOTENCY_HEADER) else {
+        return Ok(None);
+    };
+
+    let key = value
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| VectaDBError::BadRequest("Idempotency-Key must be visible ASCII".to_string()))?;
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(VectaDBError::BadRequest(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_KEY_LEN
+        )));
+    }
+
+    Ok(Some(key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn keyed(key: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_HEADER, HeaderValue::from_str(key).unwrap());
+        headers
+    }
+
+    fn body(name: &str) -> serde_json::Value {
+        serde_json::json!({ "entity_type": "Agent", "properties": { "name": name } })
+    }
+
+    /// Simulated create that hands out a new id on every call
+    async fn create(counter: &AtomicUsize) -> Result<String, VectaDBError> {
+        Ok(format!("entity-{}", counter.fetch_add(1, Ordering::SeqCst) + 1))
+    }
+
+    #[tokio::test]
+    async fn test_same_key_creates_once() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let counter = AtomicUsize::new(0);
+
+        let first = store.once("entities", &keyed("retry-1"), body("a"), |_| create(&counter)).await.unwrap();
+        let second = store.once("entities", &keyed("retry-1"), body("a"), |_| create(&counter)).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        // Other keys, other scopes and unkeyed requests all create
+        let other = store.once("entities", &keyed("retry-2"), body("a"), |_| create(&counter)).await.unwrap();
+        let relation = store.once("relations", &keyed("retry-1"), body("a"), |_| create(&counter)).await.unwrap();
+        let unkeyed = store.once("entities", &HeaderMap::new(), body("a"), |_| create(&counter)).await.unwrap();
+        assert_ne!(other, first);
+        assert_ne!(relation, first);
+        assert_ne!(unkeyed, first);
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_repeats_wait_for_first() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let counter = AtomicUsize::new(0);
+        let headers = keyed("concurrent");
+
+        let slow_create = |_: serde_json::Value| async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            create(&counter).await
+        };
+        let (a, b) = tokio::join!(
+            store.once("entities", &headers, body("a"), slow_create),
+            store.once("entities", &headers, body("a"), slow_create)
+        );
+        assert_eq!(a.unwrap(), b.unwrap());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_and_expired_keys_retry() {
+        let store = IdempotencyStore::new(Duration::ZERO);
+        let counter = AtomicUsize::new(0);
+        let headers = keyed("flaky");
+
+        let failed: Result<String, VectaDBError> = store
+            .once("entities", &headers, body("a"), |_| async { Err(VectaDBError::Database("down".into())) })
+            .await;
+        assert!(failed.is_err());
+
+        // With a zero TTL every request finds the key expired
+        let first = store.once("entities", &headers, body("a"), |_| create(&counter)).await.unwrap();
+        let second = store.once("entities", &headers, body("a"), |_| create(&counter)).await.unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_different_body_is_rejected() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let counter = AtomicUsize::new(0);
+        let headers = keyed("reused");
+
+        store.once("entities", &headers, body("a"), |_| create(&counter)).await.unwrap();
+        let reused: Result<String, VectaDBError> =
+            store.once("entities", &headers, body("b"), |_| create(&counter)).await;
+        assert!(matches!(reused, Err(VectaDBError::Unprocessable(_))));
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_key_order() {
+        let properties: HashMap<String, u32> = (0..32).map(|i| (format!("k{}", i), i)).collect();
+        let mut reversed = serde_json::Map::new();
+        for i in (0..32).rev() {
+            reversed.insert(format!("k{}", i), serde_json::json!(i));
+        }
+
+        assert_eq!(fingerprint(&properties).unwrap(), fingerprint(&reversed).unwrap());
+        assert_ne!(fingerprint(&body("a")).unwrap(), fingerprint(&body("b")).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_full_store_runs_without_remembering() {
+        let store = IdempotencyStore::new(Duration::from_secs(60));
+        let counter = AtomicUsize::new(0);
+        for i in 0..MAX_ENTRIES {
+            store.once("entities", &keyed(&format!("fill-{}", i)), body("a"), |_| create(&counter)).await.unwrap();
+        }
+
+        // A new key past the bound still creates, but is not remembered
+        let headers = keyed("overflow");
+        let first = store.once("entities", &headers, body("a"), |_| create(&counter)).await.unwrap();
+        let second = store.once("entities", &headers, body("a"), |_| create(&counter)).await.unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_idempotency_key_validation() {
+        assert_eq!(idempotency_key(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(idempotency_key(&keyed(" abc ")).unwrap(), Some("abc".to_string()));
+        assert!(idempotency_key(&keyed(" ")).is_err());
+        assert!(idempotency_key(&keyed(&"k".repeat(MAX_KEY_LEN + 1))).is_err());
+    }
+}
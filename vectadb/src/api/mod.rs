@@ -4,4 +4,15 @@
 pub mod routes;
 pub mod handlers;
 pub mod types;
-
+pub mod rate_limit;
+pub mod audit;
+pub mod request_id;
+pub mod timeout;
+pub mod event_stream;
+pub mod tenancy;
+pub mod idempotency;
+pub mod pagination;
+pub mod sampling;
+pub mod openapi;
+pub mod graphql;
+pub mod grpc;
@@ -0,0 +1,577 @@
+// OpenAPI 3 description of the REST API, served on `/openapi.json`
+
+use axum::Json;
+use utoipa::openapi::path::{OperationBuilder, ParameterBuilder, ParameterIn, PathItemType};
+use utoipa::openapi::request_body::RequestBodyBuilder;
+use utoipa::openapi::{
+    Content, ObjectBuilder, OpenApi as OpenApiDocument, PathItem, PathsBuilder, Ref, RefOr, Required,
+    ResponseBuilder, Schema, SchemaType,
+};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use super::types::*;
+use crate::db::{
+    AuditOperation, AuditRecord, DeadLetter, Entity, ExportTable, FacetCount, Histogram, HistogramBucket, TimeBucket,
+    TokenUsage,
+};
+use crate::models::{
+    Agent, AgentWithRelations, CreateAgentRequest, CreateLogRequest, CreateTaskRequest, CreateThoughtRequest, Log,
+    LogLevel, Task, TaskStatus, TaskWithRelations, Thought,
+};
+use crate::ontology::diff::{EntityTypeChange, PropertyChange, RelationTypeChange};
+use crate::ontology::{SchemaDiff, SchemaMerge};
+use crate::query::{
+    CombinedQuery, GraphQuery, HybridQuery, MergeStrategy, QueryExplanation, QueryMetadata, QueryResult,
+    ResultSource, ScoredResult, StreamSummary, TraversalDirection, VectorQuery,
+};
+
+use super::sampling::SampledOut;
+
+const JSON: &str = "application/json";
+const NDJSON: &str = "application/x-ndjson";
+
+/// Schemas and document metadata; the operations come from `operations`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "VectaDB API", description = "Observability database for LLM agents"),
+    components(schemas(
+        UploadSchemaRequest, SchemaFormat, UploadSchemaResponse, PatchSchemaResponse, ExistingDataReport,
+        InvalidEntitySample, GetEntityTypeResponse, PropertyInfo, GetSubtypesResponse, GetSupertypesResponse,
+        GetRelationTypeResponse, ListRelationTypesResponse, ValidateEntityRequest, ValidateEntityResponse,
+        ValidationErrorInfo, ValidateRelationRequest, ValidateRelationResponse, ExpandQueryRequest,
+        ExpandQueryResponse, MaterializedExpansionsResponse, InferredRelationInfo, GetCompatibleRelationsRequest,
+        GetCompatibleRelationsResponse, HealthResponse, ErrorResponse, CreateEntityRequest, CreateEntityResponse,
+        UpsertEntityRequest, UpsertEntityResponse, UpdateEntityRequest, EntityResponse, BatchGetEntitiesRequest,
+        BatchGetEntitiesResponse, SimilarEntity, SimilarEntitiesResponse, TextSearchRequest, TextSearchHit,
+        TextSearchResponse, CreateRelationRequest,
+        CreateRelationResponse, CreateLogResponse, CreateThoughtResponse, ReasoningChainResponse,
+        ListAgentsResponse, ListTasksResponse, UpdateTaskStatusRequest, RelationResponse, RelationDirection,
+        EntityEdge, EntityRelationsResponse, SubgraphResponse, EventIngestionRequest, ListEventsResponse,
+        StreamedEvent, StreamMessage, LogSource, BulkEventIngestionRequest, IngestionOptions,
+        EventIngestionResponse, BulkEventIngestionResponse, IngestionError, ListDeadLettersResponse,
+        ReplayDeadLettersResponse, DeadLetterError, AnalyticsResponse, ModelCost, CostResponse, HistogramRequest,
+        HistogramResponse, FacetsResponse, TimeseriesResponse, DuplicatesResponse, ExportRecord, ImportResponse,
+        ImportError, ReindexResponse, CollectionStats, CollectionStatsResponse, GcCollectionReport,
+        GcVectorsResponse, AuditResponse,
+        Agent, AgentWithRelations, CreateAgentRequest, Task, TaskStatus, TaskWithRelations, CreateTaskRequest,
+        Log, LogLevel, CreateLogRequest, Thought, CreateThoughtRequest,
+        HybridQuery, VectorQuery, GraphQuery, CombinedQuery, TraversalDirection, MergeStrategy, QueryResult,
+        ScoredResult, ResultSource, QueryMetadata, QueryExplanation, StreamSummary,
+        Entity, DeadLetter, TokenUsage, Histogram, HistogramBucket, FacetCount, TimeBucket, ExportTable, SampledOut,
+        SchemaDiff, EntityTypeChange, PropertyChange, RelationTypeChange, SchemaMerge, AuditRecord, AuditOperation,
+    ))
+)]
+struct ApiDoc;
+
+/// The API description. Every route in `api_routes` has an operation here.
+pub fn openapi() -> OpenApiDocument {
+    let mut paths = PathsBuilder::new();
+    for (method, path, operation) in operations() {
+        paths = paths.path(openapi_path(path), PathItem::new(method, operation.build(path)));
+    }
+
+    let mut document = ApiDoc::openapi();
+    document.paths = paths.build();
+    document
+}
+
+/// Serve the API description
+pub async fn get_openapi() -> Json<OpenApiDocument> {
+    Json(openapi())
+}
+
+fn operations() -> Vec<(PathItemType, &'static str, Operation)> {
+    use PathItemType::{Delete, Get, Patch, Post, Put};
+
+    vec![
+        (Get, "/health", Operation::new("health", "Service health and loaded ontology").ok::<HealthResponse>()),
+        (Get, "/openapi.json", Operation::new("health", "This document").ok_object()),
+
+        // Ontology management
+        (Post, "/api/v1/ontology/schema", Operation::new("ontology", "Upload an ontology schema")
+            .params::<UploadSchemaParams>()
+            .body::<UploadSchemaRequest>()
+            .ok::<UploadSchemaResponse>()
+            .errors(&[400, 409])),
+        (Get, "/api/v1/ontology/schema", Operation::new("ontology", "Download the loaded schema as JSON or YAML")
+            .params::<GetSchemaParams>()
+            .ok_object()
+            .errors(&[404])),
+        (Patch, "/api/v1/ontology/schema", Operation::new("ontology", "Merge a partial schema into the loaded one")
+            .params::<PatchSchemaParams>()
+            .body::<UploadSchemaRequest>()
+            .ok::<PatchSchemaResponse>()
+            .errors(&[400, 404, 409])),
+        (Post, "/api/v1/ontology/diff", Operation::new("ontology", "Compare a candidate schema with the loaded one")
+            .body::<UploadSchemaRequest>()
+            .ok::<SchemaDiff>()
+            .errors(&[400, 404])),
+        (Get, "/api/v1/ontology/types/:type_id", Operation::new("ontology", "Get an entity type")
+            .ok::<GetEntityTypeResponse>()
+            .errors(&[404])),
+        (Get, "/api/v1/ontology/types/:type_id/json-schema", Operation::new("ontology", "JSON Schema for entities of a type")
+            .ok_object()
+            .errors(&[404])),
+        (Get, "/api/v1/ontology/types/:type_id/subtypes", Operation::new("ontology", "Subtypes of an entity type")
+            .ok::<GetSubtypesResponse>()
+            .errors(&[404])),
+        (Get, "/api/v1/ontology/types/:type_id/supertypes", Operation::new("ontology", "Supertypes of an entity type")
+            .ok::<GetSupertypesResponse>()
+            .errors(&[404])),
+        (Get, "/api/v1/ontology/relation-types", Operation::new("ontology", "List relation types")
+            .ok::<ListRelationTypesResponse>()
+            .errors(&[404])),
+        (Get, "/api/v1/ontology/relation-types/:relation_id", Operation::new("ontology", "Get a relation type")
+            .ok::<GetRelationTypeResponse>()
+            .errors(&[404])),
+
+        // Validation
+        (Post, "/api/v1/validate/entity", Operation::new("validation", "Validate an entity against the ontology")
+            .body::<ValidateEntityRequest>()
+            .ok::<ValidateEntityResponse>()
+            .errors(&[404])),
+        (Post, "/api/v1/validate/relation", Operation::new("validation", "Validate a relation against the ontology")
+            .body::<ValidateRelationRequest>()
+            .ok::<ValidateRelationResponse>()
+            .errors(&[400, 404])),
+
+        // Query expansion
+        (Post, "/api/v1/query/expand", Operation::new("query", "Expand an entity type to its subtypes and inferred relations")
+            .body::<ExpandQueryRequest>()
+            .ok::<ExpandQueryResponse>()
+            .errors(&[400, 404])),
+        (Get, "/api/v1/query/expansions", Operation::new("query", "Precomputed type expansions")
+            .ok::<MaterializedExpansionsResponse>()
+            .errors(&[404])),
+        (Post, "/api/v1/query/compatible_relations", Operation::new("query", "Relation types allowed between two entity types")
+            .body::<GetCompatibleRelationsRequest>()
+            .ok::<GetCompatibleRelationsResponse>()
+            .errors(&[404])),
+
+        // Entities
+        (Post, "/api/v1/entities", Operation::new("entities", "Create an entity")
+            .body::<CreateEntityRequest>()
+            .ok::<CreateEntityResponse>()
+            .errors(&[400, 409])),
+        (Put, "/api/v1/entities", Operation::new("entities", "Create or update an entity by natural key")
+            .body::<UpsertEntityRequest>()
+            .ok::<UpsertEntityResponse>()
+            .errors(&[400, 409])),
+        (Post, "/api/v1/entities/batch-get", Operation::new("entities", "Get several entities by ID")
+            .body::<BatchGetEntitiesRequest>()
+            .ok::<BatchGetEntitiesResponse>()
+            .errors(&[400])),
+        (Post, "/api/v1/entities/text-search", Operation::new("entities", "Find entities by keyword in searchable properties")
+            .body::<TextSearchRequest>()
+            .ok::<TextSearchResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/entities/:id", Operation::new("entities", "Get an entity")
+            .params::<GetEntityParams>()
+            .ok::<EntityResponse>()
+            .errors(&[404])),
+        (Put, "/api/v1/entities/:id", Operation::new("entities", "Update an entity's properties")
+            .body::<UpdateEntityRequest>()
+            .no_content()
+            .errors(&[400, 404, 409])),
+        (Delete, "/api/v1/entities/:id", Operation::new("entities", "Soft-delete an entity")
+            .no_content()
+            .errors(&[404])),
+        (Delete, "/api/v1/entities/:id/purge", Operation::new("entities", "Permanently delete an entity and its relations")
+            .no_content()
+            .errors(&[404])),
+        (Get, "/api/v1/entities/:id/relations", Operation::new("entities", "Relations of an entity")
+            .params::<EntityRelationsParams>()
+            .ok::<EntityRelationsResponse>()
+            .errors(&[404])),
+        (Get, "/api/v1/entities/:id/subgraph", Operation::new("entities", "Entities and relations around an entity")
+            .params::<SubgraphParams>()
+            .ok::<SubgraphResponse>()
+            .errors(&[400, 404])),
+        (Get, "/api/v1/entities/:id/similar", Operation::new("entities", "Entities whose embeddings are nearest an entity's")
+            .params::<SimilarEntitiesParams>()
+            .ok::<SimilarEntitiesResponse>()
+            .errors(&[404, 409])),
+
+        // Logs
+        (Post, "/api/v1/logs", Operation::new("agents", "Record a log line")
+            .body::<CreateLogRequest>()
+            .ok::<CreateLogResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/logs/:id", Operation::new("agents", "Get a log line")
+            .ok::<Log>()
+            .errors(&[400, 404])),
+
+        // Agents
+        (Post, "/api/v1/agents", Operation::new("agents", "Register an agent")
+            .body::<CreateAgentRequest>()
+            .ok::<Agent>()
+            .errors(&[400])),
+        (Get, "/api/v1/agents", Operation::new("agents", "List agents")
+            .params::<ListAgentsParams>()
+            .ok::<ListAgentsResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/agents/:id", Operation::new("agents", "Get an agent")
+            .ok::<Agent>()
+            .errors(&[404])),
+        (Get, "/api/v1/agents/:id/relations", Operation::new("agents", "An agent with its tasks, thoughts, logs and collaborators")
+            .ok::<AgentWithRelations>()
+            .errors(&[404])),
+
+        // Tasks
+        (Post, "/api/v1/tasks", Operation::new("agents", "Create a task")
+            .body::<CreateTaskRequest>()
+            .ok::<Task>()
+            .errors(&[400])),
+        (Get, "/api/v1/tasks", Operation::new("agents", "List tasks")
+            .params::<ListTasksParams>()
+            .ok::<ListTasksResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/tasks/:id", Operation::new("agents", "Get a task")
+            .ok::<Task>()
+            .errors(&[404])),
+        (Delete, "/api/v1/tasks/:id", Operation::new("agents", "Delete a task")
+            .no_content()
+            .errors(&[404])),
+        (Put, "/api/v1/tasks/:id/status", Operation::new("agents", "Move a task to another status")
+            .body::<UpdateTaskStatusRequest>()
+            .ok::<Task>()
+            .errors(&[404, 409])),
+        (Get, "/api/v1/tasks/:id/relations", Operation::new("agents", "A task with its agent, thoughts and logs")
+            .ok::<TaskWithRelations>()
+            .errors(&[404])),
+
+        // Reasoning chains
+        (Post, "/api/v1/traces/:trace_id/thoughts", Operation::new("agents", "Add a thought to a trace")
+            .body::<CreateThoughtRequest>()
+            .ok::<CreateThoughtResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/traces/:trace_id/thoughts", Operation::new("agents", "The thoughts of a trace in order")
+            .ok::<ReasoningChainResponse>()
+            .errors(&[404])),
+
+        // Relations
+        (Post, "/api/v1/relations", Operation::new("relations", "Create a relation")
+            .body::<CreateRelationRequest>()
+            .ok::<CreateRelationResponse>()
+            .errors(&[400, 404, 409])),
+        (Get, "/api/v1/relations/:id", Operation::new("relations", "Get a relation")
+            .ok::<RelationResponse>()
+            .errors(&[404])),
+        (Delete, "/api/v1/relations/:id", Operation::new("relations", "Delete a relation")
+            .no_content()
+            .errors(&[404])),
+
+        // Hybrid queries
+        (Post, "/api/v1/query/hybrid", Operation::new("query", "Run a vector, graph or combined query")
+            .body::<HybridQuery>()
+            .ok::<QueryResult>()
+            .errors(&[400])),
+        (Post, "/api/v1/query/stream", Operation::new("query", "Run a query, streaming results as server-sent events")
+            .body::<HybridQuery>()
+            .ok_stream(
+                "text/event-stream",
+                "`result` events carrying a ScoredResult each, then a `summary` event carrying a StreamSummary",
+                string_schema(),
+            )
+            .errors(&[400])),
+
+        // GraphQL
+        (Post, "/graphql", Operation::new("query", "Run a GraphQL query over entities and relations")
+            .body_as(JSON, object_schema())
+            .ok_object()),
+
+        // Events
+        (Post, "/api/v1/events", Operation::new("events", "Ingest an agent event")
+            .body::<EventIngestionRequest>()
+            .ok::<EventIngestionResponse>()
+            .errors(&[400, 409])),
+        (Get, "/api/v1/events", Operation::new("events", "List events, newest first")
+            .params::<ListEventsParams>()
+            .ok::<ListEventsResponse>()
+            .errors(&[400])),
+        (Post, "/api/v1/events/batch", Operation::new("events", "Ingest a batch of agent events")
+            .body::<BulkEventIngestionRequest>()
+            .ok::<BulkEventIngestionResponse>()
+            .errors(&[400, 413])),
+        (Get, "/api/v1/events/stream", Operation::new("events", "Subscribe to ingested events over a WebSocket")
+            .params::<EventStreamParams>()
+            .switching_protocols("Upgraded to a WebSocket carrying StreamMessage frames")),
+
+        // Analytics
+        (Get, "/api/v1/analytics", Operation::new("analytics", "Event counts, errors and token usage")
+            .params::<AnalyticsParams>()
+            .ok::<AnalyticsResponse>()),
+        (Get, "/api/analytics", Operation::new("analytics", "Same as /api/v1/analytics")
+            .params::<AnalyticsParams>()
+            .ok::<AnalyticsResponse>()),
+        (Post, "/api/v1/analytics/duplicates", Operation::new("analytics", "Find near-duplicate events")
+            .params::<DuplicatesParams>()
+            .ok::<DuplicatesResponse>()
+            .errors(&[400])),
+        (Post, "/api/v1/analytics/histogram", Operation::new("analytics", "Distribution of a numeric event property")
+            .body::<HistogramRequest>()
+            .ok::<HistogramResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/analytics/facets", Operation::new("analytics", "Record counts per distinct value of a field")
+            .params::<FacetsParams>()
+            .ok::<FacetsResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/analytics/timeseries", Operation::new("analytics", "Record counts per time interval")
+            .params::<TimeseriesParams>()
+            .ok::<TimeseriesResponse>()
+            .errors(&[400])),
+        (Get, "/api/v1/analytics/cost", Operation::new("analytics", "Token cost per model")
+            .params::<CostParams>()
+            .ok::<CostResponse>()
+            .errors(&[400])),
+
+        // Export / import
+        (Get, "/api/v1/export", Operation::new("admin", "Export a table as NDJSON")
+            .params::<ExportParams>()
+            .ok_stream(NDJSON, "One record per line", schema_ref::<ExportRecord>())
+            .errors(&[400])),
+        (Post, "/api/v1/import", Operation::new("admin", "Import NDJSON records from an export")
+            .params::<ImportParams>()
+            .body_as(NDJSON, schema_ref::<ExportRecord>())
+            .ok::<ImportResponse>()
+            .errors(&[400])),
+
+        // Admin
+        (Post, "/api/v1/admin/reindex", Operation::new("admin", "Re-embed stored entities")
+            .params::<ReindexParams>()
+            .ok::<ReindexResponse>()
+            .errors(&[409])),
+        (Get, "/api/v1/admin/collections", Operation::new("admin", "Vector collection statistics")
+            .ok::<CollectionStatsResponse>()),
+        (Post, "/api/v1/admin/gc-vectors", Operation::new("admin", "Delete vectors whose entities are gone")
+            .params::<GcVectorsParams>()
+            .ok::<GcVectorsResponse>()),
+        (Get, "/api/v1/admin/dead-letters", Operation::new("admin", "List events that failed ingestion")
+            .params::<DeadLetterParams>()
+            .ok::<ListDeadLettersResponse>()),
+        (Post, "/api/v1/admin/replay-dead-letters", Operation::new("admin", "Retry ingesting failed events")
+            .params::<DeadLetterParams>()
+            .ok::<ReplayDeadLettersResponse>()
+            .errors(&[413])),
+
+        // Audit log
+        (Get, "/api/v1/audit", Operation::new("audit", "Recorded creates, updates and deletes")
+            .params::<AuditParams>()
+            .ok::<AuditResponse>()),
+    ]
+}
+
+/// An operation under construction. Path parameters are added from the path
+/// when it is built, and every operation documents `ErrorResponse` as its
+/// default response.
+struct Operation {
+    builder: OperationBuilder,
+    errors: &'static [u16],
+}
+
+impl Operation {
+    fn new(tag: &str, summary: &str) -> Self {
+        Self {
+            builder: OperationBuilder::new().tag(tag).summary(Some(summary)),
+            errors: &[],
+        }
+    }
+
+    fn params<P: IntoParams>(mut self) -> Self {
+        self.builder = self.builder.parameters(Some(P::into_params(|| Some(ParameterIn::Query))));
+        self
+    }
+
+    fn body<T: ToSchema<'static>>(self) -> Self {
+        self.body_as(JSON, schema_ref::<T>())
+    }
+
+    fn body_as(mut self, content_type: &str, schema: RefOr<Schema>) -> Self {
+        let body = RequestBodyBuilder::new()
+            .content(content_type, Content::new(schema))
+            .required(Some(Required::True))
+            .build();
+        self.builder = self.builder.request_body(Some(body));
+        self
+    }
+
+    fn ok<T: ToSchema<'static>>(self) -> Self {
+        self.ok_stream(JSON, "Success", schema_ref::<T>())
+    }
+
+    /// A JSON object response with no fixed shape
+    fn ok_object(self) -> Self {
+        self.ok_stream(JSON, "Success", object_schema())
+    }
+
+    fn ok_stream(mut self, content_type: &str, description: &str, schema: RefOr<Schema>) -> Self {
+        let response = ResponseBuilder::new()
+            .description(description)
+            .content(content_type, Content::new(schema))
+            .build();
+        self.builder = self.builder.response("200", response);
+        self
+    }
+
+    fn no_content(mut self) -> Self {
+        self.builder = self.builder.response("204", ResponseBuilder::new().description("Success").build());
+        self
+    }
+
+    fn switching_protocols(mut self, description: &str) -> Self {
+        self.builder = self.builder.response("101", ResponseBuilder::new().description(description).build());
+        self
+    }
+
+    fn errors(mut self, statuses: &'static [u16]) -> Self {
+        self.errors = statuses;
+        self
+    }
+
+    fn build(self, path: &str) -> utoipa::openapi::path::Operation {
+        let mut builder = self.builder;
+        for name in path.split('/').filter_map(|segment| segment.strip_prefix(':')) {
+            let parameter = ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Path)
+                .required(Required::True)
+                .schema(Some(string_schema()))
+                .build();
+            builder = builder.parameter(parameter);
+        }
+
+        for status in self.errors {
+            let reason = axum::http::StatusCode::from_u16(*status)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or("Error");
+            builder = builder.response(status.to_string(), error_response(reason));
+        }
+        builder.response("default", error_response("Error")).build()
+    }
+}
+
+fn error_response(description: &str) -> utoipa::openapi::Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(JSON, Content::new(schema_ref::<ErrorResponse>()))
+        .build()
+}
+
+fn schema_ref<T: ToSchema<'static>>() -> RefOr<Schema> {
+    Ref::from_schema_name(T::schema().0).into()
+}
+
+fn object_schema() -> RefOr<Schema> {
+    ObjectBuilder::new().schema_type(SchemaType::Object).into()
+}
+
+fn string_schema() -> RefOr<Schema> {
+    ObjectBuilder::new().schema_type(SchemaType::String).into()
+}
+
+/// Axum's `:param` path syntax in OpenAPI's `{param}` form
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn document() -> serde_json::Value {
+        serde_json::to_value(openapi()).unwrap()
+    }
+
+    #[test]
+    fn test_document_parses_as_openapi() {
+        let document: openapiv3::OpenAPI = serde_json::from_value(document()).unwrap();
+        assert!(document.openapi.starts_with("3."));
+        assert_eq!(document.info.title, "VectaDB API");
+
+        for path in [
+            "/health",
+            "/api/v1/entities",
+            "/api/v1/entities/{id}",
+            "/api/v1/relations",
+            "/api/v1/query/hybrid",
+            "/api/v1/events",
+            "/api/v1/ontology/schema",
+        ] {
+            assert!(document.paths.paths.contains_key(path), "{} is missing", path);
+        }
+
+        let entity = document.paths.paths["/api/v1/entities/{id}"].as_item().unwrap();
+        let get = entity.get.as_ref().unwrap();
+        assert_eq!(get.parameters.len(), 2, "the id and include_deleted");
+        assert!(get.responses.responses.contains_key(&openapiv3::StatusCode::Code(404)));
+        assert!(get.responses.default.is_some());
+        assert!(entity.delete.as_ref().unwrap().responses.responses.contains_key(&openapiv3::StatusCode::Code(204)));
+    }
+
+    #[test]
+    fn test_every_schema_reference_resolves() {
+        fn collect_refs(value: &serde_json::Value, refs: &mut BTreeSet<String>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    if let Some(serde_json::Value::String(target)) = map.get("$ref") {
+                        refs.insert(target.clone());
+                    }
+                    map.values().for_each(|value| collect_refs(value, refs));
+                }
+                serde_json::Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+                _ => {}
+            }
+        }
+
+        let document = document();
+        let mut refs = BTreeSet::new();
+        collect_refs(&document, &mut refs);
+        assert!(refs.contains("#/components/schemas/HybridQuery"));
+
+        let missing: Vec<_> = refs
+            .iter()
+            .filter(|target| {
+                let name = target.strip_prefix("#/components/schemas/").unwrap();
+                document["components"]["schemas"].get(name).is_none()
+            })
+            .collect();
+        assert!(missing.is_empty(), "not components: {:?}", missing);
+    }
+
+    #[test]
+    fn test_operations_match_routes() {
+        // Every `.route("path", method(handler))` in the router
+        let routes = include_str!("routes.rs");
+        let pattern = regex::Regex::new(r#"\.route\("([^"]+)",\s*(get|post|put|patch|delete)\("#).unwrap();
+        let routed: BTreeSet<(String, String)> = pattern
+            .captures_iter(routes.split("mod tests").next().unwrap())
+            .map(|c| (c[2].to_string(), openapi_path(&c[1])))
+            .collect();
+        assert!(routed.len() > 50);
+
+        let document = document();
+        let documented: BTreeSet<(String, String)> = document["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object().unwrap().keys().map(move |method| (method.clone(), path.clone()))
+            })
+            .collect();
+        assert_eq!(routed, documented);
+    }
+
+    #[test]
+    fn test_openapi_path() {
+        assert_eq!(openapi_path("/api/v1/traces/:trace_id/thoughts"), "/api/v1/traces/{trace_id}/thoughts");
+        assert_eq!(openapi_path("/health"), "/health");
+    }
+}
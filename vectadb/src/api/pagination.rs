@@ -0,0 +1,147 @@
+// Opaque keyset cursors for list endpoints
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::VectaDBError;
+
+/// Sort key of the last row of a page. Rows are ordered by `(timestamp, id)`,
+/// the field order, so a page resumes right after this key however many rows
+/// were inserted meanwhile, unlike an offset.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PageCursor {
+    #[serde(rename = "t")]
+    pub timestamp: String,
+    #[serde(rename = "i")]
+    pub id: String,
+}
+
+impl PageCursor {
+    pub fn new(timestamp: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            timestamp: timestamp.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Cursor for a row keyed by a datetime, formatted so string order is time order
+    pub fn at(timestamp: DateTime<Utc>, id: impl Into<String>) -> Self {
+        Self::new(sort_timestamp(timestamp), id)
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Parse a `next_cursor` handed out earlier
+    pub fn decode(cursor: &str) -> Result<Self, VectaDBError> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| VectaDBError::Validation(format!("Invalid cursor '{}'", cursor)))
+    }
+}
+
+/// Fixed-width RFC 3339 in UTC, so timestamps compare correctly as strings
+pub fn sort_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Page through rows held in memory, in ascending `(timestamp, id)` order.
+/// Rows after `cursor` are skipped by `offset`, then at most `limit` are kept.
+/// `next_cursor` is set when more rows follow the page.
+pub fn keyset_page<T>(
+    mut rows: Vec<T>,
+    key: impl Fn(&T) -> PageCursor,
+    cursor: Option<&PageCursor>,
+    offset: usize,
+    limit: usize,
+) -> (Vec<T>, Option<String>) {
+    rows.sort_by_cached_key(|row| key(row));
+    let start = match cursor {
+        Some(cursor) => rows.partition_point(|row| key(row) <= *cursor),
+        None => 0,
+    };
+
+    let mut page: Vec<T> = rows.into_iter().skip(start + offset).collect();
+    let more = page.len() > limit;
+    page.truncate(limit);
+
+    let next_cursor = if more {
+        page.last().map(|row| key(row).encode())
+    } else {
+        None
+    };
+    (page, next_cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Row {
+        id: String,
+        created_at: DateTime<Utc>,
+    }
+
+    fn row(id: &str, second: i64) -> Row {
+        Row {
+            id: id.to_string(),
+            created_at: DateTime::from_timestamp(1_700_000_000 + second, 0).unwrap(),
+        }
+    }
+
+    fn key(row: &Row) -> PageCursor {
+        PageCursor::at(row.created_at, &row.id)
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = PageCursor::new("2026-01-07T12:00:00.000000000Z", "event-1");
+        assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(matches!(PageCursor::decode("not a cursor"), Err(VectaDBError::Validation(_))));
+    }
+
+    #[test]
+    fn test_paging_survives_inserts_mid_iteration() {
+        // Two rows share a timestamp, so the id breaks the tie
+        let mut rows = vec![row("a", 1), row("c", 2), row("b", 2), row("d", 3), row("e", 4)];
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<PageCursor> = None;
+        loop {
+            let (page, next) = keyset_page(rows.clone(), key, cursor.as_ref(), 0, 2);
+            seen.extend(page.iter().map(|r| r.id.clone()));
+
+            // Rows land both before and after the read position between pages
+            if seen.len() == 2 {
+                rows.push(row("early", 0));
+                rows.push(row("late", 5));
+            }
+
+            match next {
+                Some(next) => cursor = Some(PageCursor::decode(&next).unwrap()),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen, vec!["a", "b", "c", "d", "e", "late"]);
+    }
+
+    #[test]
+    fn test_offset_and_limit_fallback() {
+        let rows = vec![row("a", 1), row("b", 2), row("c", 3)];
+
+        let (page, next) = keyset_page(rows.clone(), key, None, 1, 1);
+        assert_eq!(page, vec![row("b", 2)]);
+        assert!(next.is_some());
+
+        let (page, next) = keyset_page(rows, key, None, 0, 3);
+        assert_eq!(page.len(), 3);
+        assert!(next.is_none());
+    }
+}
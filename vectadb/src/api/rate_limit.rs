@@ -0,0 +1,204 @@
+// Per-client rate limiting (token bucket) for the API
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::RateLimitConfig;
+use super::types::ErrorResponse;
+
+/// Token bucket for a single client
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Keyed token-bucket limiter shared by all requests
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    max_keys: usize,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            rate: config.requests_per_second.max(f64::MIN_POSITIVE),
+            burst: config.burst.max(1) as f64,
+            max_keys: config.max_keys.max(1),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for `key`. On rejection, returns how long until one is available.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if !buckets.contains_key(key) && buckets.len() >= self.max_keys {
+            self.evict(&mut buckets, now);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            last_refill: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+
+    /// Make room for a new key. Buckets that have refilled completely are
+    /// indistinguishable from fresh ones, so drop those first; if the map is
+    /// still full, drop the least recently used bucket.
+    fn evict(&self, buckets: &mut HashMap<String, Bucket>, now: Instant) {
+        let refill_secs = self.burst / self.rate;
+        buckets.retain(|_, b| now.saturating_duration_since(b.last_refill).as_secs_f64() < refill_secs);
+
+        if buckets.len() >= self.max_keys {
+            let oldest = buckets
+                .iter()
+                .min_by_key(|(_, b)| b.last_refill)
+                .map(|(k, _)| k.clone());
+            if let Some(key) = oldest {
+                buckets.remove(&key);
+            }
+        }
+    }
+}
+
+/// Identify the caller by its IP. API key headers are not validated by any
+/// auth layer yet, so keying on them would let a client rotate keys to
+/// bypass the limit and flood the bucket map.
+fn client_key(request: &Request) -> String {
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Middleware rejecting requests over the limit with 429 and `Retry-After`
+pub async fn enforce(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Liveness probes are never throttled
+    if request.uri().path() == "/health" {
+        return next.run(request).await;
+    }
+
+    let key = client_key(&request);
+    match limiter.check(&key) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::debug!("Rate limit exceeded for {}", key);
+
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(ErrorResponse::new(
+                    "RateLimited",
+                    format!("Rate limit exceeded; retry after {} seconds", retry_after),
+                )),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(rps: f64, burst: u32, max_keys: usize) -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig {
+            enabled: true,
+            requests_per_second: rps,
+            burst,
+            max_keys,
+        })
+    }
+
+    #[test]
+    fn test_burst_then_reject() {
+        let limiter = limiter(1.0, 3, 100);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at("a", now).is_ok());
+        }
+        let wait = limiter.check_at("a", now).unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+
+        // Other clients have their own bucket
+        assert!(limiter.check_at("b", now).is_ok());
+    }
+
+    #[test]
+    fn test_recovers_after_window() {
+        let limiter = limiter(2.0, 1, 100);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("a", now).is_ok());
+        assert!(limiter.check_at("a", now).is_err());
+        assert!(limiter.check_at("a", now + Duration::from_millis(200)).is_err());
+        assert!(limiter.check_at("a", now + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn test_client_key_ignores_api_key_headers() {
+        let request = |api_key: &str| {
+            let mut request = axum::http::Request::builder()
+                .header("x-api-key", api_key)
+                .header(header::AUTHORIZATION, format!("Bearer {}", api_key))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 7], 4000))));
+            request
+        };
+
+        assert_eq!(client_key(&request("key-1")), "ip:10.0.0.7");
+        assert_eq!(client_key(&request("key-1")), client_key(&request("key-2")));
+    }
+
+    #[test]
+    fn test_tracked_keys_are_bounded() {
+        let limiter = limiter(1.0, 5, 3);
+        let now = Instant::now();
+
+        for i in 0..10 {
+            limiter.check_at(&format!("client-{}", i), now).unwrap();
+        }
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert!(buckets.contains_key("client-9"));
+    }
+}
@@ -0,0 +1,80 @@
+// Request IDs: every request runs in a tracing span carrying its id, so all
+// log lines it causes can be correlated
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of the request being handled, available as a request extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// The client's `X-Request-ID` if it is usable, otherwise a new UUID
+fn request_id(request: &Request) -> String {
+    request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Middleware running the request in a span with its id, and echoing the id
+/// in the `X-Request-ID` response header
+pub async fn propagate(mut request: Request, next: Next) -> Response {
+    let id = request_id(&request);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(id: Option<&str>) -> Request {
+        let mut builder = axum::http::Request::builder().uri("/health");
+        if let Some(id) = id {
+            builder = builder.header("x-request-id", id);
+        }
+        builder.body(axum::body::Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_request_id_kept_or_generated() {
+        assert_eq!(request_id(&request_with(Some("req-42"))), "req-42");
+
+        let generated = request_id(&request_with(None));
+        assert!(uuid::Uuid::parse_str(&generated).is_ok());
+
+        // Ids that would be unsafe in logs or too long are replaced
+        let spaced = request_id(&request_with(Some("two words")));
+        assert!(uuid::Uuid::parse_str(&spaced).is_ok());
+        let long = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+        assert_ne!(request_id(&request_with(Some(&long))), long);
+    }
+}
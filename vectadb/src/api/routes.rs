@@ -1,12 +1,24 @@
 // API routes configuration
 
 use axum::{
-    routing::{delete, get, post, put},
+    extract::DefaultBodyLimit,
+    http::{HeaderName, HeaderValue, Method},
+    middleware,
+    routing::{delete, get, patch, post, put},
     Router,
 };
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
 
+use crate::config::CorsConfig;
+
+use super::event_stream;
+use super::graphql;
 use super::handlers::{self, AppState};
+use super::openapi;
+use super::rate_limit;
+use super::request_id;
+use super::tenancy;
+use super::timeout;
 
 /// Create the main API router (without database dependencies)
 pub fn create_router() -> Router {
@@ -16,15 +28,52 @@ pub fn create_router() -> Router {
 
 /// Create API router with custom state (for database integration)
 pub fn create_router_with_state(state: AppState) -> Router {
+    // With multi-tenancy on, requests are routed to tenant-scoped copies of the routes
+    let router = match state.tenants.clone() {
+        Some(registry) => tenancy::tenant_router(registry),
+        None => api_routes().with_state(state.clone()),
+    };
+
+    // Innermost, so only handler time counts towards the timeout
+    let router = router.layer(middleware::from_fn_with_state(state.timeouts, timeout::enforce));
+
+    // Rate limiting sits inside CORS so rejections still carry CORS headers
+    let router = match state.rate_limiter.clone() {
+        Some(limiter) => router.layer(middleware::from_fn_with_state(limiter, rate_limit::enforce)),
+        None => router,
+    };
+
+    // Cap JSON request bodies; streaming import reads its body directly and enforces its own limits
+    let router = router.layer(DefaultBodyLimit::max(state.limits.max_body_bytes));
+
+    // Cross-origin access is off unless origins are configured
+    let router = match cors_layer(&state.cors) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+
+    // Outermost, so every response and log line of a request carries its id
+    router.layer(middleware::from_fn(request_id::propagate))
+}
+
+/// All API routes, without middleware or state
+pub(crate) fn api_routes() -> Router<AppState> {
     Router::new()
         // Health check
         .route("/health", get(handlers::health_check))
+        .route("/openapi.json", get(openapi::get_openapi))
 
         // Ontology management
         .route("/api/v1/ontology/schema", post(handlers::upload_schema))
         .route("/api/v1/ontology/schema", get(handlers::get_schema))
+        .route("/api/v1/ontology/schema", patch(handlers::patch_schema))
+        .route("/api/v1/ontology/diff", post(handlers::diff_schema))
         .route("/api/v1/ontology/types/:type_id", get(handlers::get_entity_type))
+        .route("/api/v1/ontology/types/:type_id/json-schema", get(handlers::get_entity_type_json_schema))
         .route("/api/v1/ontology/types/:type_id/subtypes", get(handlers::get_subtypes))
+        .route("/api/v1/ontology/types/:type_id/supertypes", get(handlers::get_supertypes))
+        .route("/api/v1/ontology/relation-types", get(handlers::list_relation_types))
+        .route("/api/v1/ontology/relation-types/:relation_id", get(handlers::get_relation_type))
 
         // Entity validation
         .route("/api/v1/validate/entity", post(handlers::validate_entity))
@@ -32,13 +81,43 @@ pub fn create_router_with_state(state: AppState) -> Router {
 
         // Query expansion
         .route("/api/v1/query/expand", post(handlers::expand_query))
+        .route("/api/v1/query/expansions", get(handlers::get_materialized_expansions))
         .route("/api/v1/query/compatible_relations", post(handlers::get_compatible_relations))
 
         // Entity CRUD
         .route("/api/v1/entities", post(handlers::create_entity))
+        .route("/api/v1/entities", put(handlers::upsert_entity))
+        .route("/api/v1/entities/batch-get", post(handlers::batch_get_entities))
+        .route("/api/v1/entities/text-search", post(handlers::text_search_entities))
         .route("/api/v1/entities/:id", get(handlers::get_entity))
         .route("/api/v1/entities/:id", put(handlers::update_entity))
         .route("/api/v1/entities/:id", delete(handlers::delete_entity))
+        .route("/api/v1/entities/:id/purge", delete(handlers::purge_entity))
+        .route("/api/v1/entities/:id/relations", get(handlers::get_entity_relations))
+        .route("/api/v1/entities/:id/subgraph", get(handlers::get_entity_subgraph))
+        .route("/api/v1/entities/:id/similar", get(handlers::get_similar_entities))
+
+        // Logs
+        .route("/api/v1/logs", post(handlers::create_log))
+        .route("/api/v1/logs/:id", get(handlers::get_log))
+
+        // Agents
+        .route("/api/v1/agents", post(handlers::create_agent))
+        .route("/api/v1/agents", get(handlers::list_agents))
+        .route("/api/v1/agents/:id", get(handlers::get_agent))
+        .route("/api/v1/agents/:id/relations", get(handlers::get_agent_relations))
+
+        // Tasks
+        .route("/api/v1/tasks", post(handlers::create_task))
+        .route("/api/v1/tasks", get(handlers::list_tasks))
+        .route("/api/v1/tasks/:id", get(handlers::get_task))
+        .route("/api/v1/tasks/:id", delete(handlers::delete_task))
+        .route("/api/v1/tasks/:id/status", put(handlers::update_task_status))
+        .route("/api/v1/tasks/:id/relations", get(handlers::get_task_relations))
+
+        // Reasoning chains
+        .route("/api/v1/traces/:trace_id/thoughts", post(handlers::create_thought))
+        .route("/api/v1/traces/:trace_id/thoughts", get(handlers::get_reasoning_chain))
 
         // Relation CRUD
         .route("/api/v1/relations", post(handlers::create_relation))
@@ -47,16 +126,83 @@ pub fn create_router_with_state(state: AppState) -> Router {
 
         // Hybrid queries
         .route("/api/v1/query/hybrid", post(handlers::hybrid_query))
+        .route("/api/v1/query/stream", post(handlers::query_stream))
+
+        // GraphQL
+        .route("/graphql", post(graphql::graphql))
 
         // Event ingestion (Phase 5)
         .route("/api/v1/events", post(handlers::ingest_event))
+        .route("/api/v1/events", get(handlers::list_events))
         .route("/api/v1/events/batch", post(handlers::ingest_events_bulk))
+        .route("/api/v1/events/stream", get(event_stream::stream_events))
+
+        // Analytics
+        .route("/api/v1/analytics", get(handlers::get_analytics))
+        .route("/api/analytics", get(handlers::get_analytics))
+        .route("/api/v1/analytics/duplicates", post(handlers::find_duplicates))
+        .route("/api/v1/analytics/histogram", post(handlers::event_histogram))
+        .route("/api/v1/analytics/facets", get(handlers::get_facets))
+        .route("/api/v1/analytics/timeseries", get(handlers::get_timeseries))
+        .route("/api/v1/analytics/cost", get(handlers::get_cost))
+
+        // Export / import
+        .route("/api/v1/export", get(handlers::export_table))
+        .route("/api/v1/import", post(handlers::import_records))
+
+        // Admin
+        .route("/api/v1/admin/reindex", post(handlers::reindex_entities))
+        .route("/api/v1/admin/collections", get(handlers::collection_stats))
+        .route("/api/v1/admin/gc-vectors", post(handlers::gc_vectors))
+        .route("/api/v1/admin/dead-letters", get(handlers::list_dead_letters))
+        .route("/api/v1/admin/replay-dead-letters", post(handlers::replay_dead_letters))
 
-        // Add CORS middleware
-        .layer(CorsLayer::permissive())
+        // Audit log
+        .route("/api/v1/audit", get(handlers::list_audit_records))
+}
+
+/// Build the CORS layer from config, or None when no origins are allowed
+fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let origins = if config.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|o| o.parse::<HeaderValue>().ok()),
+        )
+    };
 
-        // Add state
-        .with_state(state)
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|m| m.to_uppercase().parse().ok())
+        .collect();
+
+    let headers = if config.allowed_headers.iter().any(|h| h == "*") {
+        AllowHeaders::any()
+    } else {
+        AllowHeaders::list(
+            config
+                .allowed_headers
+                .iter()
+                .filter_map(|h| h.parse::<HeaderName>().ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .expose_headers([request_id::REQUEST_ID_HEADER.clone()])
+            .allow_credentials(config.allow_credentials),
+    )
 }
 
 #[cfg(test)]
@@ -78,6 +224,33 @@ mod tests {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_response_carries_request_id() {
+        let app = create_router();
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = response.headers().get("x-request-id").unwrap().to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+        // A client's id is echoed back, also on error responses
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/ontology/schema")
+                    .header("x-request-id", "client-req-7")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "client-req-7");
+    }
+
     #[tokio::test]
     async fn test_get_schema_not_loaded() {
         let app = create_router();
@@ -94,4 +267,3055 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[tokio::test]
+    async fn test_endpoints_without_database() {
+        let app = create_router();
+
+        let cases = [
+            ("GET", "/api/analytics?agent_id=agent-1", ""),
+            ("POST", "/api/v1/import", "{\"table\":\"entity\",\"record\":{\"id\":\"a\"}}\n"),
+            ("DELETE", "/api/v1/entities/abc/purge", ""),
+            ("POST", "/api/v1/entities/text-search", r#"{"query": "timeout"}"#),
+            ("POST", "/api/v1/admin/reindex?entity_type=Log", ""),
+            ("POST", "/api/v1/admin/replay-dead-letters", ""),
+            ("GET", "/api/v1/audit?target_id=abc", ""),
+            ("GET", "/api/v1/admin/collections", ""),
+            ("POST", "/api/v1/logs", r#"{"agent_id": "agent-1", "message": "disk almost full"}"#),
+        ];
+        for (method, uri, body) in cases {
+            let mut request = Request::builder().method(method).uri(uri);
+            if !body.is_empty() {
+                request = request.header("content-type", "application/json");
+            }
+            let response = app.clone().oneshot(request.body(Body::from(body)).unwrap()).await.unwrap();
+
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{} {}", method, uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_rejects_unknown_table() {
+        let app = create_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/export?table=users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_returns_429() {
+        use crate::config::RateLimitConfig;
+        use std::sync::Arc;
+
+        let mut state = AppState::new();
+        state.rate_limiter = Some(Arc::new(rate_limit::RateLimiter::new(&RateLimitConfig {
+            enabled: true,
+            requests_per_second: 1.0,
+            burst: 2,
+            max_keys: 100,
+        })));
+        let app = create_router_with_state(state);
+
+        let request = |api_key: &str, ip: [u8; 4]| {
+            let mut request = Request::builder()
+                .uri("/api/v1/ontology/schema")
+                .header("x-api-key", api_key)
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(std::net::SocketAddr::from((ip, 4000))));
+            request
+        };
+
+        // Rotating the unauthenticated API key header does not earn a fresh bucket
+        for key in ["key-1", "key-2"] {
+            let response = app.clone().oneshot(request(key, [10, 0, 0, 1])).await.unwrap();
+            assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        let response = app.clone().oneshot(request("key-3", [10, 0, 0, 1])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "1");
+
+        // A different client IP is unaffected, and health checks are never limited
+        let response = app.clone().oneshot(request("key-1", [10, 0, 0, 2])).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let health = Request::builder().uri("/health").body(Body::empty()).unwrap();
+        let response = app.oneshot(health).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn bulk_events_request(count: usize) -> Request<Body> {
+        let events: Vec<_> = (0..count)
+            .map(|i| serde_json::json!({"timestamp": "2026-01-01T00:00:00Z", "event_type": "test", "properties": {"i": i}}))
+            .collect();
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/events/batch")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "events": events }).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bulk_batch_size_limit() {
+        let mut state = AppState::new();
+        state.limits.max_batch_size = 5;
+        let app = create_router_with_state(state);
+
+        let response = app.clone().oneshot(bulk_events_request(6)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Within the limit the request gets past the check (and fails only for lack of a database)
+        let response = app.oneshot(bulk_events_request(5)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_body_size_limit() {
+        let mut state = AppState::new();
+        state.limits.max_body_bytes = 1024;
+        let app = create_router_with_state(state);
+
+        let response = app.clone().oneshot(bulk_events_request(100)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = app.oneshot(bulk_events_request(1)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_declared_oversize_body() {
+        let mut state = AppState::new();
+        state.limits.max_import_bytes = 16;
+        let app = create_router_with_state(state);
+
+        let body = "{\"table\":\"entity\",\"record\":{\"id\":\"a\"}}\n";
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/import")
+                    .header("content-length", body.len())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_import_rejects_overlong_line() {
+        use crate::db::SurrealDBClient;
+        use std::sync::Arc;
+
+        let mut state = AppState::new();
+        state.surreal = Some(Arc::new(SurrealDBClient::new(&test_config()).await.unwrap()));
+        state.limits.max_body_bytes = 64;
+        let app = create_router_with_state(state);
+
+        // No newline ever arrives, so the line must be cut off at the limit rather than buffered
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/import")
+                    .body(Body::from("x".repeat(1024)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    fn preflight(origin: &str) -> Request<Body> {
+        Request::builder()
+            .method("OPTIONS")
+            .uri("/api/v1/entities")
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "content-type")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_for_configured_origin() {
+        let mut state = AppState::new();
+        state.cors.allowed_origins = vec!["https://dashboard.example.com".to_string()];
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(preflight("https://dashboard.example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://dashboard.example.com"
+        );
+        let methods = headers["access-control-allow-methods"].to_str().unwrap();
+        assert!(methods.contains("POST"));
+        assert!(methods.contains("DELETE"));
+        let allowed_headers = headers["access-control-allow-headers"].to_str().unwrap();
+        assert!(allowed_headers.contains("content-type"));
+
+        // Other origins are not echoed back
+        let response = app.oneshot(preflight("https://evil.example.com")).await.unwrap();
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_disabled_by_default() {
+        let app = create_router();
+
+        let response = app.oneshot(preflight("https://dashboard.example.com")).await.unwrap();
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_delivers_filtered_events() {
+        use super::super::types::StreamedEvent;
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let state = AppState::new();
+        let events = state.events.clone();
+        let app = create_router_with_state(state);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let url = format!("ws://{}/api/v1/events/stream?agent_id=agent-1", addr);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let event = |id: &str, agent_id: &str| StreamedEvent {
+            id: id.to_string(),
+            trace_id: "trace-1".to_string(),
+            timestamp: chrono::Utc::now(),
+            event_type: Some("tool_call".to_string()),
+            agent_id: Some(agent_id.to_string()),
+            session_id: None,
+            properties: serde_json::json!({ "tool": "search" }),
+        };
+        events.publish(event("event-1", "agent-2"));
+        events.publish(event("event-2", "agent-1"));
+
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("no event received")
+            .unwrap()
+            .unwrap();
+        let Message::Text(text) = message else {
+            panic!("expected text message, got {:?}", message);
+        };
+        let body: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(body["type"], "event");
+        assert_eq!(body["id"], "event-2");
+        assert_eq!(body["agent_id"], "agent-1");
+    }
+
+    #[tokio::test]
+    async fn test_list_events_rejects_inverted_time_range() {
+        let app = create_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/events?start_time=2025-01-15T12:00:00Z&end_time=2025-01-15T11:00:00Z")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_facets_reject_invalid_field() {
+        let app = create_router();
+
+        for uri in [
+            "/api/v1/analytics/facets?table=agent_event&field=event_type;DELETE%20agent_event",
+            "/api/v1/analytics/facets?table=agent_event&field=properties..model_id",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeseries_rejects_invalid_interval_and_range() {
+        let app = create_router();
+
+        for uri in [
+            "/api/v1/analytics/timeseries?interval=1y&start=2026-01-07T00:00:00Z&end=2026-01-08T00:00:00Z",
+            "/api/v1/analytics/timeseries?interval=1h&start=2026-01-08T00:00:00Z&end=2026-01-07T00:00:00Z",
+            "/api/v1/analytics/timeseries?interval=1s&start=2026-01-07T00:00:00Z&end=2026-01-08T00:00:00Z",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_histogram_rejects_unordered_buckets() {
+        let app = create_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/analytics/histogram")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"property": "latency_ms", "buckets": [0, 500, 100]}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_list_events_rejects_malformed_cursor() {
+        let app = create_router();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/events?cursor=not-a-cursor")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_entity_relations_directions_and_type() {
+        use crate::db::{Entity, Relation, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let entity = Entity::new("EdgeTest".to_string(), HashMap::new());
+            ids.push(surreal.create_entity(&entity).await.unwrap());
+        }
+        let (a, b, c) = (&ids[0], &ids[1], &ids[2]);
+        for (rel_type, source, target) in [("executes", a, b), ("owns", a, c), ("owns", c, a)] {
+            let relation = Relation::new(rel_type.to_string(), source.clone(), target.clone(), HashMap::new());
+            surreal.create_relation(&relation).await.unwrap();
+        }
+
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+
+        let list = |query: &str| {
+            let app = app.clone();
+            let uri = format!("/api/v1/entities/{}/relations{}", a, query);
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+        let connected = |body: &serde_json::Value| {
+            let mut ids: Vec<String> = body["relations"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["connected_entity_id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut ids: Vec<String>| {
+            ids.sort();
+            ids
+        };
+
+        let outgoing = list("?direction=outgoing").await;
+        assert_eq!(connected(&outgoing), sorted(vec![b.clone(), c.clone()]));
+        assert!(outgoing["relations"].as_array().unwrap().iter().all(|r| r["direction"] == "outgoing"));
+
+        let incoming = list("?direction=incoming").await;
+        assert_eq!(connected(&incoming), vec![c.clone()]);
+        assert_eq!(incoming["relations"][0]["direction"], "incoming");
+
+        let both = list("").await;
+        assert_eq!(both["count"], 3);
+
+        let executes = list("?direction=both&type=executes").await;
+        assert_eq!(connected(&executes), vec![b.clone()]);
+        assert_eq!(executes["relations"][0]["relation_type"], "executes");
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB and Qdrant running
+    async fn test_gc_vectors_removes_orphans() {
+        use crate::db::{Entity, QdrantClient, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use surrealdb::sql::Thing;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+
+        let _ = qdrant.delete_collection("GcTest").await;
+        qdrant.create_collection("GcTest", 4).await.unwrap();
+
+        // One vector backed by an entity, one whose entity was never stored
+        let mut entity = Entity::new("GcTest".to_string(), HashMap::new());
+        let live_id = uuid::Uuid::new_v4().to_string();
+        entity.id = Thing::from(("entity".to_string(), live_id.clone()));
+        surreal.create_entity(&entity).await.unwrap();
+        let orphan_id = uuid::Uuid::new_v4().to_string();
+        for id in [&live_id, &orphan_id] {
+            qdrant
+                .upsert_embedding("GcTest", id, vec![0.1, 0.2, 0.3, 0.4], chrono::Utc::now())
+                .await
+                .unwrap();
+        }
+
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        state.qdrant = Some(qdrant.clone());
+        let app = create_router_with_state(state);
+
+        let gc = |query: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(format!("/api/v1/admin/gc-vectors?entity_type=GcTest{}", query))
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let dry_run = gc("&dry_run=true").await;
+        assert_eq!(dry_run["scanned"], 2);
+        assert_eq!(dry_run["removed"], 1);
+
+        let collected = gc("").await;
+        assert_eq!(collected["collections"][0]["entity_type"], "GcTest");
+        assert_eq!(collected["removed"], 1);
+
+        let remaining: Vec<String> = {
+            use futures::TryStreamExt;
+            let batches: Vec<Vec<crate::db::VectorPoint>> =
+                qdrant.scroll("GcTest", 10, false).try_collect().await.unwrap();
+            batches.into_iter().flatten().map(|p| p.entity_id().to_string()).collect()
+        };
+        assert_eq!(remaining, vec![live_id.clone()]);
+
+        let again = gc("").await;
+        assert_eq!(again["removed"], 0);
+
+        // Cleanup
+        let _ = surreal.purge_entity(&live_id).await;
+        let _ = qdrant.delete_collection("GcTest").await;
+    }
+
+    fn test_config() -> crate::config::DatabaseConfig {
+        use crate::config::{DatabaseConfig, QdrantConfig, SurrealDBConfig};
+
+        DatabaseConfig {
+            surrealdb: SurrealDBConfig {
+                endpoint: "ws://localhost:8000".to_string(),
+                namespace: "test".to_string(),
+                database: "test".to_string(),
+                username: "root".to_string(),
+                password: "root".to_string(),
+                pool_size: 1,
+            },
+            qdrant: QdrantConfig {
+                url: "http://localhost:6333".to_string(),
+                api_key: None,
+                collection_prefix: "test_".to_string(),
+                warmup_collections: false,
+                collection: Default::default(),
+            },
+        }
+    }
+
+    fn test_embedding_config() -> crate::config::EmbeddingConfig {
+        crate::config::EmbeddingConfig {
+            model: "all-MiniLM-L6-v2".to_string(),
+            dim: 384,
+            provider: "local".to_string(),
+            plugin_config_dir: "./config/embeddings".to_string(),
+            fallback_to_local: false,
+            fallback_providers: Vec::new(),
+            type_providers: Default::default(),
+            verify_dim: true,
+            max_concurrent_requests: 8,
+            max_input_chars: 16_000,
+            long_text_strategy: crate::config::LongTextStrategy::Truncate,
+            normalize: false,
+        }
+    }
+
+    fn tenant_app(base: AppState) -> Router {
+        use std::sync::Arc;
+
+        let mut state = base.clone();
+        let allowed = ["tenant-a", "tenant-b", "iso-a", "iso-b"];
+        state.tenants = Some(Arc::new(tenancy::TenantRegistry::new(
+            base,
+            test_config(),
+            allowed.iter().map(|t| t.to_string()).collect(),
+            None,
+        )));
+        create_router_with_state(state)
+    }
+
+    #[tokio::test]
+    async fn test_tenancy_requires_tenant_header() {
+        let app = tenant_app(AppState::new());
+
+        let missing = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/v1/ontology/schema").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::BAD_REQUEST);
+
+        let invalid = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/ontology/schema")
+                    .header(tenancy::TENANT_HEADER, "../other")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        // Tenants off the allow-list are never set up
+        let unknown = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/ontology/schema")
+                    .header(tenancy::TENANT_HEADER, "tenant-z")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+        // Health checks and the API description are not tenant-scoped
+        let health = app
+            .clone()
+            .oneshot(Request::builder().uri("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(health.status(), StatusCode::OK);
+
+        let openapi = app
+            .oneshot(Request::builder().uri("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(openapi.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_tenants_have_separate_ontologies() {
+        let app = tenant_app(AppState::new());
+        let schema = serde_json::json!({
+            "namespace": "tenant://a",
+            "version": "1.0.0",
+            "entity_types": {},
+            "relation_types": {},
+            "rules": []
+        });
+
+        let upload = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/ontology/schema")
+                    .header("content-type", "application/json")
+                    .header(tenancy::TENANT_HEADER, "tenant-a")
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "schema": schema.to_string(),
+                            "format": "json"
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(upload.status(), StatusCode::OK);
+
+        let get_schema = |tenant: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri("/api/v1/ontology/schema")
+                    .header(tenancy::TENANT_HEADER, tenant)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        assert_eq!(get_schema("tenant-a").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(get_schema("tenant-b").await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_relation_type_introspection() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+
+        let app = create_router();
+        let get = |uri: &'static str| {
+            app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        };
+
+        // Nothing to introspect before a schema is loaded
+        let response = get("/api/v1/ontology/relation-types").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut schema = OntologySchema::new("relations://test".to_string(), "1.0.0".to_string());
+        for id in ["Agent", "Task"] {
+            schema.add_entity_type(EntityType::new(id.to_string(), id.to_string()));
+        }
+        let mut executes = RelationType::new(
+            "executes".to_string(),
+            "Executes".to_string(),
+            "Agent".to_string(),
+            "Task".to_string(),
+        );
+        executes.inverse = Some("executed_by".to_string());
+        executes.functional = true;
+        let executed_by = RelationType::new(
+            "executed_by".to_string(),
+            "Executed by".to_string(),
+            "Task".to_string(),
+            "Agent".to_string(),
+        );
+        let mut delegates = RelationType::new(
+            "delegates".to_string(),
+            "Delegates".to_string(),
+            "Agent".to_string(),
+            "Agent".to_string(),
+        );
+        delegates.transitive = true;
+        for relation_type in [executes, executed_by, delegates] {
+            schema.add_relation_type(relation_type);
+        }
+        let schema = serde_json::to_string(&schema).unwrap();
+
+        let upload = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/ontology/schema")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "schema": schema, "format": "json" }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(upload.status(), StatusCode::OK);
+
+        let response = get("/api/v1/ontology/relation-types/executes").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let executes: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(executes["label"], "Executes");
+        assert_eq!(executes["domain"], "Agent");
+        assert_eq!(executes["range"], "Task");
+        assert_eq!(executes["inverse"], "executed_by");
+        assert_eq!(executes["functional"], true);
+        assert_eq!(executes["transitive"], false);
+
+        let response = get("/api/v1/ontology/relation-types").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(listed["count"], 3);
+        assert_eq!(listed["relation_types"][0]["id"], "delegates");
+        assert_eq!(listed["relation_types"][0]["transitive"], true);
+
+        let response = get("/api/v1/ontology/relation-types/unknown").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_supertypes_follow_parent_chain() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::OntologySchema;
+
+        let mut schema = OntologySchema::new("supertypes://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(EntityType::new("Agent".to_string(), "Agent".to_string()));
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string()).with_parent("Agent".to_string()),
+        );
+        schema.add_entity_type(
+            EntityType::new("ChatAgent".to_string(), "Chat Agent".to_string())
+                .with_parent("LLMAgent".to_string()),
+        );
+
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        let app = create_router_with_state(state);
+        let supertypes = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+                (status, body["supertypes"].clone())
+            }
+        };
+
+        let (status, chain) = supertypes("/api/v1/ontology/types/ChatAgent/supertypes").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(chain, serde_json::json!(["LLMAgent", "Agent"]));
+
+        let (status, chain) = supertypes("/api/v1/ontology/types/Agent/supertypes").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(chain, serde_json::json!([]));
+
+        let (status, _) = supertypes("/api/v1/ontology/types/Unknown/supertypes").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_entity_type_json_schema_route() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+
+        let mut schema = OntologySchema::new("routes://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string())
+                .with_property(PropertyDefinition::new("name".to_string(), PropertyType::String).required()),
+        );
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string()).with_parent("Agent".to_string()),
+        );
+
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/api/v1/ontology/types/LLMAgent/json-schema").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(document["title"], "LLM Agent");
+        assert_eq!(document["properties"]["name"]["type"], "string");
+        assert_eq!(document["required"], serde_json::json!(["name"]));
+
+        let response = app
+            .oneshot(Request::builder().uri("/api/v1/ontology/types/Unknown/json-schema").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_graphql_entity_with_outgoing_relations() {
+        use crate::db::{Entity, Relation, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let surreal = Arc::new(SurrealDBClient::new(&test_config()).await.unwrap());
+
+        let mut ids = Vec::new();
+        for name in ["planner", "search", "summarize"] {
+            let properties = HashMap::from([("name".to_string(), serde_json::json!(name))]);
+            ids.push(surreal.create_entity(&Entity::new("GraphQLTest".to_string(), properties)).await.unwrap());
+        }
+        let (a, b, c) = (&ids[0], &ids[1], &ids[2]);
+        for (source, target) in [(a, b), (a, c), (c, a)] {
+            let relation = Relation::new("uses".to_string(), source.clone(), target.clone(), HashMap::new());
+            surreal.create_relation(&relation).await.unwrap();
+        }
+
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        let query = format!(
+            "{{ entity(id: \"{}\") {{ id properties relations(direction: OUTGOING) {{ relationType target {{ id properties }} }} }} }}",
+            a
+        );
+        let response = create_router_with_state(state)
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/graphql")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::json!({ "query": query }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(body.get("errors").is_none(), "{}", body);
+
+        let entity = &body["data"]["entity"];
+        assert_eq!(entity["id"], a.as_str());
+        assert_eq!(entity["properties"]["name"], "planner");
+        let mut targets: Vec<(String, String)> = entity["relations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                assert_eq!(r["relationType"], "uses");
+                let target = &r["target"];
+                (target["id"].as_str().unwrap().to_string(), target["properties"]["name"].as_str().unwrap().to_string())
+            })
+            .collect();
+        targets.sort();
+        let mut expected = vec![(b.clone(), "search".to_string()), (c.clone(), "summarize".to_string())];
+        expected.sort();
+        assert_eq!(targets, expected);
+
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openapi_route() {
+        let response = create_router()
+            .oneshot(Request::builder().uri("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document: openapiv3::OpenAPI = serde_json::from_slice(&bytes).unwrap();
+        for path in ["/api/v1/entities", "/api/v1/query/hybrid", "/api/v1/events", "/openapi.json"] {
+            assert!(document.paths.paths.contains_key(path), "{} is missing", path);
+        }
+        assert!(document.components.unwrap().schemas.contains_key("CreateEntityRequest"));
+    }
+
+    #[tokio::test]
+    async fn test_diff_schema_leaves_loaded_schema_in_place() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::OntologySchema;
+
+        let mut current = OntologySchema::new("diff://test".to_string(), "1.0.0".to_string());
+        for id in ["Agent", "Task"] {
+            current.add_entity_type(EntityType::new(id.to_string(), id.to_string()));
+        }
+        let mut candidate = current.clone();
+        candidate.version = "2.0.0".to_string();
+        candidate.entity_types.remove("Task");
+        candidate.add_entity_type(EntityType::new("Tool".to_string(), "Tool".to_string()));
+
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(current));
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/ontology/diff")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "schema": serde_json::to_string(&candidate).unwrap(),
+                            "format": "json",
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let diff: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(diff["added_entity_types"], serde_json::json!(["Tool"]));
+        assert_eq!(diff["removed_entity_types"], serde_json::json!(["Task"]));
+        // No stored data references Task
+        assert_eq!(diff["breaking"], false);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/ontology/types/Task")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_validate_entity_applies_property_defaults() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+
+        let mut schema = OntologySchema::new("defaults://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Task".to_string(), "Task".to_string()).with_property(
+                PropertyDefinition::new("status".to_string(), PropertyType::String)
+                    .required()
+                    .with_default(serde_json::json!("pending")),
+            ),
+        );
+
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        let app = create_router_with_state(state);
+
+        let validate = |properties: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/validate/entity")
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                serde_json::json!({ "entity_type": "Task", "properties": properties })
+                                    .to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        // The default satisfies the required property
+        assert_eq!(validate(serde_json::json!({})).await["valid"], true);
+
+        // An explicit value is validated as sent, not replaced by the default
+        let result = validate(serde_json::json!({ "status": 7 })).await;
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["errors"][0]["error_type"], "PropertyTypeMismatch");
+    }
+
+    #[tokio::test]
+    async fn test_patch_schema_merges_into_loaded_schema() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::OntologySchema;
+
+        let mut current = OntologySchema::new("patch://test".to_string(), "1.0.0".to_string());
+        for id in ["Agent", "Task"] {
+            current.add_entity_type(EntityType::new(id.to_string(), id.to_string()));
+        }
+
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(current));
+        let app = create_router_with_state(state);
+
+        let patch = |uri: &'static str, schema: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("PATCH")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                serde_json::json!({
+                                    "schema": schema.to_string(),
+                                    "format": "json",
+                                })
+                                .to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+            }
+        };
+        let type_status = |type_id: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .uri(format!("/api/v1/ontology/types/{}", type_id))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        // A new subtype of an existing type
+        let tool_agent = serde_json::to_value(
+            EntityType::new("ToolAgent".to_string(), "Tool Agent".to_string())
+                .with_parent("Agent".to_string()),
+        )
+        .unwrap();
+        let (status, body) = patch(
+            "/api/v1/ontology/schema",
+            serde_json::json!({ "version": "1.1.0", "entity_types": { "ToolAgent": tool_agent } }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["version"], "1.1.0");
+        assert_eq!(body["added_entity_types"], serde_json::json!(["ToolAgent"]));
+        for type_id in ["Agent", "Task", "ToolAgent"] {
+            assert_eq!(type_status(type_id).await, StatusCode::OK, "{}", type_id);
+        }
+
+        // Redefining Task is rejected, then allowed with overwrite
+        let job = serde_json::to_value(EntityType::new("Task".to_string(), "Job".to_string())).unwrap();
+        let redefine = serde_json::json!({ "entity_types": { "Task": job } });
+        let (status, body) = patch("/api/v1/ontology/schema", redefine.clone()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "SchemaConflict");
+        let (status, body) = patch("/api/v1/ontology/schema?overwrite=true", redefine).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["updated_entity_types"], serde_json::json!(["Task"]));
+        assert_eq!(type_status("ToolAgent").await, StatusCode::OK);
+
+        // A patch leaving the merged schema invalid changes nothing
+        let orphan = serde_json::to_value(
+            EntityType::new("Orphan".to_string(), "Orphan".to_string())
+                .with_parent("Missing".to_string()),
+        )
+        .unwrap();
+        let (status, _) = patch(
+            "/api/v1/ontology/schema",
+            serde_json::json!({ "entity_types": { "Orphan": orphan } }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(type_status("Orphan").await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_schema_yaml_download_round_trips() {
+        use crate::ontology::OntologyLoader;
+
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+
+        let mut schema = OntologySchema::new("roundtrip://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string()).with_property(
+                PropertyDefinition::new("name".to_string(), PropertyType::String).required(),
+            ),
+        );
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        schema.add_entity_type(EntityType::new("Task".to_string(), "Task".to_string()));
+        schema.add_relation_type(RelationType::new(
+            "executes".to_string(),
+            "executes".to_string(),
+            "Agent".to_string(),
+            "Task".to_string(),
+        ));
+        let yaml = OntologyLoader::to_yaml_str(&schema).unwrap();
+
+        let app = create_router_with_state(AppState::new());
+
+        let upload = |schema: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/ontology/schema")
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                serde_json::json!({ "schema": schema, "format": "yaml" }).to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+            }
+        };
+        let download = |uri: &'static str, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .header("accept", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let content_type = response.headers()[axum::http::header::CONTENT_TYPE].to_str().unwrap().to_string();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (content_type, String::from_utf8(bytes.to_vec()).unwrap())
+            }
+        };
+
+        upload(yaml).await;
+        let original = serde_json::to_value(&schema).unwrap();
+
+        let (content_type, downloaded) = download("/api/v1/ontology/schema?format=yaml", "*/*").await;
+        assert_eq!(content_type, "application/yaml");
+        let parsed = OntologyLoader::from_yaml_str(&downloaded).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), original);
+
+        // Re-uploading the download changes nothing
+        upload(downloaded.clone()).await;
+        let (_, again) = download("/api/v1/ontology/schema", "application/yaml").await;
+        let reparsed = OntologyLoader::from_yaml_str(&again).unwrap();
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), original);
+
+        let (content_type, json) = download("/api/v1/ontology/schema", "application/json").await;
+        assert!(content_type.starts_with("application/json"));
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json, original);
+    }
+
+    #[tokio::test]
+    async fn test_schema_upload_replaces_materialized_expansions() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::OntologySchema;
+
+        let app = create_router_with_state(AppState::new());
+
+        let upload = |schema: OntologySchema| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/ontology/schema")
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                serde_json::json!({
+                                    "schema": serde_json::to_string(&schema).unwrap(),
+                                    "format": "json",
+                                })
+                                .to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+            }
+        };
+        let agent_expansion = || {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri("/api/v1/query/expansions")
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+                let mut types: Vec<String> =
+                    serde_json::from_value(body["expansions"]["Agent"].clone()).unwrap();
+                types.sort();
+                types
+            }
+        };
+
+        let mut schema = OntologySchema::new("expansions://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(EntityType::new("Agent".to_string(), "Agent".to_string()));
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        upload(schema.clone()).await;
+        assert_eq!(agent_expansion().await, ["Agent", "LLMAgent"]);
+
+        schema.version = "2.0.0".to_string();
+        schema.add_entity_type(
+            EntityType::new("HumanAgent".to_string(), "Human Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        upload(schema).await;
+        assert_eq!(agent_expansion().await, ["Agent", "HumanAgent", "LLMAgent"]);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_tenant_entities_are_isolated() {
+        use crate::db::{Entity, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let database = test_config();
+        let mut base = AppState::new();
+        base.surreal = Some(Arc::new(SurrealDBClient::new(&database).await.unwrap()));
+        let app = tenant_app(base);
+
+        // Write straight into tenant A's database, named <database>_<tenant>
+        let mut tenant_a = database.clone();
+        tenant_a.surrealdb.database = format!("{}_iso-a", database.surrealdb.database);
+        let surreal_a = SurrealDBClient::new(&tenant_a).await.unwrap();
+        let id = surreal_a
+            .create_entity(&Entity::new("Note".to_string(), HashMap::new()))
+            .await
+            .unwrap();
+
+        let get = |tenant: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/entities/{}", id))
+                    .header(tenancy::TENANT_HEADER, tenant)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        assert_eq!(get("iso-a").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(get("iso-b").await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        // Without tenancy the entity is not in the shared database either
+        assert!(SurrealDBClient::new(&database).await.unwrap().get_entity(&id).await.unwrap().is_none());
+
+        // Cleanup
+        let _ = surreal_a.purge_entity(&id).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_failed_events_are_dead_lettered_and_replayed() {
+        use crate::db::SurrealDBClient;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+        let send = |method: &'static str, uri: &'static str, body: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method(method)
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        // Without a trace and with auto-creation off, the event fails
+        let properties = serde_json::json!({ "message": "tool call timed out", "attempt": 3 });
+        let batch = serde_json::json!({
+            "events": [{ "timestamp": "2026-01-07T12:00:00Z", "properties": properties }],
+            "options": {
+                "auto_create_traces": false,
+                "generate_embeddings": false,
+                "return_failed_events": true,
+                "dead_letter": true,
+            },
+        });
+        let ingested = send("POST", "/api/v1/events/batch", batch.to_string()).await;
+        assert_eq!(ingested["failed"], 1);
+        let error = &ingested["errors"][0];
+        assert_eq!(error["event"]["properties"], properties);
+        let dead_letter_id = error["dead_letter_id"].as_str().unwrap().to_string();
+
+        let listed = send("GET", "/api/v1/admin/dead-letters?limit=1000", String::new()).await;
+        let stored = listed["dead_letters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|letter| letter["id"] == dead_letter_id.as_str())
+            .expect("failed event is dead-lettered");
+        assert_eq!(stored["event"]["properties"], properties);
+
+        // Replay auto-creates the trace, so the event goes through
+        let replayed = send("POST", "/api/v1/admin/replay-dead-letters?limit=1000", String::new()).await;
+        assert!(replayed["replayed"].as_u64().unwrap() >= 1);
+
+        let listed = send("GET", "/api/v1/admin/dead-letters?limit=1000", String::new()).await;
+        assert!(listed["dead_letters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|letter| letter["id"] != dead_letter_id.as_str()));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_idempotent_create_relation() {
+        use crate::db::{Entity, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let source = surreal
+            .create_entity(&Entity::new("IdemTest".to_string(), HashMap::new()))
+            .await
+            .unwrap();
+        let target = surreal
+            .create_entity(&Entity::new("IdemTest".to_string(), HashMap::new()))
+            .await
+            .unwrap();
+
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+
+        let body = serde_json::json!({
+            "relation_type": "links",
+            "source_id": source,
+            "target_id": target,
+        })
+        .to_string();
+        let create = || {
+            let app = app.clone();
+            let body = body.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/relations")
+                            .header("content-type", "application/json")
+                            .header(crate::api::idempotency::IDEMPOTENCY_HEADER, "link-once")
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let first = create().await;
+        let second = create().await;
+        assert_eq!(first["id"], second["id"]);
+        assert_eq!(first["created_at"], second["created_at"]);
+        assert_eq!(surreal.get_outgoing_relations(&source, None).await.unwrap().len(), 1);
+
+        // Cleanup
+        for id in [&source, &target] {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_relation_against_data_requires_source() {
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+
+        let mut schema = OntologySchema::new("validate://test".to_string(), "1.0.0".to_string());
+        for id in ["Task", "Agent"] {
+            schema.add_entity_type(EntityType::new(id.to_string(), id.to_string()));
+        }
+        schema.add_relation_type(
+            RelationType::new(
+                "assigned_to".to_string(),
+                "Assigned to".to_string(),
+                "Task".to_string(),
+                "Agent".to_string(),
+            )
+            .functional(),
+        );
+        let state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        let app = create_router_with_state(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/validate/relation")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"relation_type": "assigned_to", "source_type": "Task", "target_type": "Agent", "check_data": true}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_text_search_finds_entities_by_keyword() {
+        use crate::db::{Entity, SurrealDBClient};
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut schema = OntologySchema::new("search://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("SearchLog".to_string(), "Log".to_string())
+                .with_property(PropertyDefinition::new("message".to_string(), PropertyType::String).searchable()),
+        );
+        surreal.store_schema(&schema).await.unwrap();
+
+        let marker = format!("marker{}", uuid::Uuid::new_v4().simple());
+        let mut ids = Vec::new();
+        for message in [
+            format!("Connection TIMEOUT while calling {}", marker),
+            format!("{} finished without errors", marker),
+            "Unrelated message".to_string(),
+        ] {
+            let entity = Entity::new(
+                "SearchLog".to_string(),
+                HashMap::from([("message".to_string(), serde_json::json!(message))]),
+            );
+            ids.push(surreal.create_entity(&entity).await.unwrap());
+        }
+
+        let mut state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+        let search = |body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/entities/text-search")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+
+        // Keywords match case-insensitively, and both entities carry the marker
+        let (status, result) = search(serde_json::json!({"query": "timeout", "entity_type": "SearchLog"})).await;
+        assert_eq!(status, StatusCode::OK);
+        let found: Vec<&str> = result["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["entity"]["id"].as_str().unwrap())
+            .collect();
+        assert!(found.contains(&ids[0].as_str()));
+        assert!(!found.contains(&ids[2].as_str()));
+
+        let (_, result) = search(serde_json::json!({"query": marker, "properties": ["message"]})).await;
+        assert_eq!(result["count"], 2);
+        assert!(result["results"][0]["score"].as_f64().unwrap() > 0.0);
+
+        let (status, result) = search(serde_json::json!({"query": "timeout", "properties": ["level"]})).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(result["error"], "PropertyNotSearchable");
+
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_validate_against_data_flags_functional_and_unique_violations() {
+        use crate::db::{Entity, SurrealDBClient};
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut schema = OntologySchema::new("validate://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("ValidateTask".to_string(), "Task".to_string())
+                .with_property(PropertyDefinition::new("key".to_string(), PropertyType::String).unique()),
+        );
+        schema.add_entity_type(EntityType::new("ValidateAgent".to_string(), "Agent".to_string()));
+        schema.add_relation_type(
+            RelationType::new(
+                "validate_assigned_to".to_string(),
+                "Assigned to".to_string(),
+                "ValidateTask".to_string(),
+                "ValidateAgent".to_string(),
+            )
+            .functional(),
+        );
+
+        let key = format!("TASK-{}", uuid::Uuid::new_v4());
+        let task = surreal
+            .create_entity(&Entity::new(
+                "ValidateTask".to_string(),
+                HashMap::from([("key".to_string(), serde_json::json!(key))]),
+            ))
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for _ in 0..2 {
+            let agent = Entity::new("ValidateAgent".to_string(), HashMap::new());
+            agents.push(surreal.create_entity(&agent).await.unwrap());
+        }
+
+        let mut state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert!(response.status().is_success());
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+        let assign = |target: &str| {
+            serde_json::json!({
+                "relation_type": "validate_assigned_to",
+                "source_type": "ValidateTask",
+                "target_type": "ValidateAgent",
+                "check_data": true,
+                "source_id": task,
+                "target_id": target,
+            })
+        };
+
+        // Creating the functional relation once validates; a second target does not
+        assert_eq!(post("/api/v1/validate/relation", assign(&agents[0])).await["valid"], true);
+        post(
+            "/api/v1/relations",
+            serde_json::json!({
+                "relation_type": "validate_assigned_to",
+                "source_id": task,
+                "target_id": agents[0],
+            }),
+        )
+        .await;
+        let second = post("/api/v1/validate/relation", assign(&agents[1])).await;
+        assert_eq!(second["valid"], false);
+        assert_eq!(second["errors"][0]["error_type"], "FunctionalViolation");
+
+        // Without check_data only the schema is consulted
+        let mut schema_only = assign(&agents[1]);
+        schema_only["check_data"] = serde_json::json!(false);
+        assert_eq!(post("/api/v1/validate/relation", schema_only).await["valid"], true);
+
+        // A second task may not reuse the key, but the task itself may keep it
+        let duplicate = serde_json::json!({
+            "entity_type": "ValidateTask",
+            "properties": {"key": key},
+            "check_data": true,
+        });
+        let result = post("/api/v1/validate/entity", duplicate.clone()).await;
+        assert_eq!(result["valid"], false);
+        assert_eq!(result["errors"][0]["error_type"], "UniqueViolation");
+        let mut update = duplicate;
+        update["entity_id"] = serde_json::json!(task);
+        assert_eq!(post("/api/v1/validate/entity", update).await["valid"], true);
+
+        // Cleanup
+        for id in std::iter::once(&task).chain(&agents) {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_create_relation_enforces_functional_and_reflexive_types() {
+        use crate::db::{Entity, SurrealDBClient};
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut schema = OntologySchema::new("semantics://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(EntityType::new("SemanticsTest".to_string(), "Node".to_string()));
+        let relation = |id: &str| {
+            RelationType::new(
+                id.to_string(),
+                id.to_string(),
+                "SemanticsTest".to_string(),
+                "SemanticsTest".to_string(),
+            )
+        };
+        schema.add_relation_type(relation("semantics_owned_by").functional());
+        schema.add_relation_type(relation("semantics_similar").reflexive());
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let entity = Entity::new("SemanticsTest".to_string(), HashMap::new());
+            ids.push(surreal.create_entity(&entity).await.unwrap());
+        }
+        let (a, b, c) = (&ids[0], &ids[1], &ids[2]);
+
+        let mut state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+        let create = |body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/relations")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+
+        // A functional relation takes one target per source
+        let owned_by = |target: &str| {
+            serde_json::json!({"relation_type": "semantics_owned_by", "source_id": a, "target_id": target})
+        };
+        assert_eq!(create(owned_by(b)).await.0, StatusCode::OK);
+        let (status, body) = create(owned_by(c)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"], "FunctionalViolation");
+        assert_eq!(surreal.get_outgoing_relations(a, Some("semantics_owned_by")).await.unwrap().len(), 1);
+
+        // Reflexive self-links are created on request, once per entity
+        let similar = serde_json::json!({
+            "relation_type": "semantics_similar",
+            "source_id": a,
+            "target_id": c,
+            "materialize_reflexive": true,
+        });
+        let (status, body) = create(similar.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["implied_relation_ids"].as_array().unwrap().len(), 2);
+        for id in [a, c] {
+            let similar = surreal.get_outgoing_relations(id, Some("semantics_similar")).await.unwrap();
+            assert!(similar.iter().any(|relation| &relation.target_id == id));
+        }
+        let (_, body) = create(similar).await;
+        assert!(body.get("implied_relation_ids").is_none());
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_batch_get_returns_found_entities_and_missing_ids() {
+        use crate::db::{Entity, SurrealDBClient};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut ids = Vec::new();
+        for n in 0..3 {
+            let mut properties = HashMap::new();
+            properties.insert("n".to_string(), serde_json::json!(n));
+            ids.push(
+                surreal
+                    .create_entity(&Entity::new("BatchGetItem".to_string(), properties))
+                    .await
+                    .unwrap(),
+            );
+        }
+        surreal.delete_entity(&ids[2]).await.unwrap();
+
+        let mut state = AppState::new();
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+
+        let batch_get = |body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri("/api/v1/entities/batch-get")
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        let result = batch_get(serde_json::json!({
+            "ids": [ids[1], "does-not-exist", ids[0], ids[2], ids[1]],
+        }))
+        .await;
+        // Request order, duplicates collapsed, deleted entities reported missing
+        let found: Vec<&str> = result["entities"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(found, vec![ids[1].as_str(), ids[0].as_str()]);
+        assert_eq!(result["entities"][0]["properties"]["n"], 1);
+        assert_eq!(result["missing"], serde_json::json!(["does-not-exist", ids[2]]));
+
+        let result = batch_get(serde_json::json!({ "ids": [ids[2]], "include_deleted": true })).await;
+        assert_eq!(result["entities"][0]["id"], ids[2].as_str());
+        assert_eq!(result["missing"], serde_json::json!([]));
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_get_rejects_oversized_batch() {
+        let mut state = AppState::new();
+        state.limits.max_batch_size = 2;
+        let app = create_router_with_state(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/entities/batch-get")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::json!({ "ids": ["a", "b", "c"] }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_inverse_relation_is_created_and_deleted_with_forward() {
+        use crate::db::{Entity, SurrealDBClient};
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::relation_type::RelationType;
+        use crate::ontology::OntologySchema;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+
+        let mut schema = OntologySchema::new("inverse://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(EntityType::new("InverseAgent".to_string(), "Agent".to_string()));
+        schema.add_entity_type(EntityType::new("InverseTask".to_string(), "Task".to_string()));
+        schema.add_relation_type(
+            RelationType::new(
+                "inverse_executes".to_string(),
+                "executes".to_string(),
+                "InverseAgent".to_string(),
+                "InverseTask".to_string(),
+            )
+            .with_inverse("inverse_executed_by".to_string()),
+        );
+        schema.add_relation_type(RelationType::new(
+            "inverse_executed_by".to_string(),
+            "executed by".to_string(),
+            "InverseTask".to_string(),
+            "InverseAgent".to_string(),
+        ));
+
+        let agent = surreal
+            .create_entity(&Entity::new("InverseAgent".to_string(), HashMap::new()))
+            .await
+            .unwrap();
+        let task = surreal
+            .create_entity(&Entity::new("InverseTask".to_string(), HashMap::new()))
+            .await
+            .unwrap();
+
+        let mut state = AppState::new();
+        *state.reasoner.write().await = Some(crate::intelligence::OntologyReasoner::new(schema));
+        state.surreal = Some(surreal.clone());
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/relations")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "relation_type": "inverse_executes",
+                            "source_id": agent,
+                            "target_id": task,
+                            "materialize_inverse": true,
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created["implied_relation_ids"].as_array().unwrap().len(), 1);
+
+        // The task now leads back to its agent
+        let inverse = surreal.get_outgoing_relations(&task, Some("inverse_executed_by")).await.unwrap();
+        assert_eq!(inverse.len(), 1);
+        assert_eq!(inverse[0].target_id, agent);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/v1/relations/{}", created["id"].as_str().unwrap()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(surreal.get_outgoing_relations(&agent, Some("inverse_executes")).await.unwrap().is_empty());
+        assert!(surreal.get_outgoing_relations(&task, Some("inverse_executed_by")).await.unwrap().is_empty());
+
+        // Cleanup
+        for id in [&agent, &task] {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_create_and_get_log() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant, embeddings);
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/logs")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"agent_id": "agent-1", "task_id": "task-9", "level": "ERROR", "message": "Connection refused", "metadata": {"port": 5432}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+        assert_eq!(created["level"], "ERROR");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/logs/{}", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let log: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(log["id"], id.as_str());
+        assert_eq!(log["agent_id"], "agent-1");
+        assert_eq!(log["task_id"], "task-9");
+        assert_eq!(log["message"], "Connection refused");
+        assert_eq!(log["metadata"]["port"], 5432);
+        assert_eq!(log["timestamp"], created["timestamp"]);
+
+        // Cleanup
+        let _ = surreal.purge_entity(&id).await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_reasoning_chain_in_order() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant, embeddings);
+        let app = create_router_with_state(state);
+        let uri = format!("/api/v1/traces/{}/thoughts", uuid::Uuid::new_v4());
+
+        let mut created = Vec::new();
+        for content in ["Read the failing test", "The fixture is stale", "Regenerate the fixture"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(&uri)
+                        .header("content-type", "application/json")
+                        .body(Body::from(
+                            serde_json::json!({"agent_id": "agent-1", "content": content}).to_string(),
+                        ))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            created.push(serde_json::from_slice::<serde_json::Value>(&body).unwrap());
+        }
+
+        // Each step is numbered and follows the one before it
+        assert_eq!(created[0]["sequence"], 1);
+        assert!(created[0]["follows"].is_null());
+        assert_eq!(created[1]["follows"], created[0]["id"]);
+        assert_eq!(created[2]["follows"], created[1]["id"]);
+        assert_eq!(created[2]["sequence"], 3);
+        let ids: Vec<String> = created.iter().map(|c| c["id"].as_str().unwrap().to_string()).collect();
+        let follows = surreal.get_outgoing_relations(&ids[2], Some("follows")).await.unwrap();
+        assert_eq!(follows.len(), 1);
+
+        let response = app
+            .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let chain: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(chain["count"], 3);
+        let contents: Vec<&str> = chain["thoughts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["Read the failing test", "The fixture is stale", "Regenerate the fixture"]);
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_task_lifecycle() {
+        use crate::db::{Entity, QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant, embeddings);
+        let app = create_router_with_state(state);
+
+        let agent_properties: HashMap<String, serde_json::Value> = serde_json::from_value(serde_json::json!({
+            "role": "analyst",
+            "goal": "summarize earnings",
+            "created_at": chrono::Utc::now(),
+        }))
+        .unwrap();
+        let agent_id = surreal
+            .create_entity(&Entity::new("Agent".to_string(), agent_properties))
+            .await
+            .unwrap();
+
+        let send = |method: &'static str, uri: String, body: Option<serde_json::Value>| {
+            let app = app.clone();
+            async move {
+                let request = Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("content-type", "application/json");
+                let body = body.map(|b| Body::from(b.to_string())).unwrap_or_else(Body::empty);
+                let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+            }
+        };
+
+        let (status, task) = send(
+            "POST",
+            "/api/v1/tasks".to_string(),
+            Some(serde_json::json!({"agent_id": agent_id, "name": "summarize Q4"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(task["status"], "PENDING");
+        let task_id = task["id"].as_str().unwrap().to_string();
+        let status_uri = format!("/api/v1/tasks/{}/status", task_id);
+
+        let (status, running) = send("PUT", status_uri.clone(), Some(serde_json::json!({"status": "RUNNING"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(running["status"], "RUNNING");
+
+        // Running tasks can't go back to pending
+        let (status, error) = send("PUT", status_uri.clone(), Some(serde_json::json!({"status": "PENDING"}))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"], "InvalidTransition");
+
+        let (status, completed) = send("PUT", status_uri, Some(serde_json::json!({"status": "COMPLETED"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(completed["duration_ms"].is_number());
+
+        let (status, view) = send("GET", format!("/api/v1/tasks/{}/relations", task_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(view["status"], "COMPLETED");
+        assert_eq!(view["executed_by"]["id"], agent_id.as_str());
+        assert_eq!(view["executed_by"]["role"], "analyst");
+
+        // Cleanup
+        for id in [&task_id, &agent_id] {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_agent_relations_projection() {
+        use crate::db::{QdrantClient, Relation, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant, embeddings);
+        let app = create_router_with_state(state);
+
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let agent = post("/api/v1/agents", serde_json::json!({"role": "researcher", "goal": "find sources"})).await;
+        let agent_id = agent["id"].as_str().unwrap().to_string();
+        let peer = post("/api/v1/agents", serde_json::json!({"role": "writer", "goal": "draft the report"})).await;
+        let peer_id = peer["id"].as_str().unwrap().to_string();
+        let task = post("/api/v1/tasks", serde_json::json!({"agent_id": agent_id, "name": "collect citations"})).await;
+        let task_id = task["id"].as_str().unwrap().to_string();
+        surreal
+            .create_relation(&Relation::new(
+                "collaborates_with".to_string(),
+                peer_id.clone(),
+                agent_id.clone(),
+                HashMap::new(),
+            ))
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/agents/{}/relations", agent_id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let view: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(view["role"], "researcher");
+        assert_eq!(view["tasks"].as_array().unwrap().len(), 1);
+        assert_eq!(view["tasks"][0]["id"], task_id.as_str());
+        assert_eq!(view["collaborators"].as_array().unwrap().len(), 1);
+        assert_eq!(view["collaborators"][0]["id"], peer_id.as_str());
+
+        // The task is linked to its agent
+        let executes = surreal.get_outgoing_relations(&agent_id, Some("executes")).await.unwrap();
+        assert_eq!(executes.len(), 1);
+        assert_eq!(executes[0].target_id, task_id);
+
+        // Cleanup
+        for id in [&task_id, &agent_id, &peer_id] {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_warm_collections_creates_schema_collections() {
+        use crate::api::handlers::warm_collections;
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use crate::intelligence::OntologyReasoner;
+        use crate::ontology::entity_type::EntityType;
+        use crate::ontology::OntologySchema;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let mut config = test_config();
+        config.qdrant.warmup_collections = true;
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+
+        let types = ["WarmupPlanner", "WarmupExecutor"];
+        let mut schema = OntologySchema::new("warmup".to_string(), "1.0".to_string());
+        for entity_type in types {
+            let _ = qdrant.delete_collection(entity_type).await;
+            schema.add_entity_type(EntityType::new(entity_type.to_string(), entity_type.to_string()));
+        }
+        let reasoner = Arc::new(RwLock::new(Some(OntologyReasoner::new(schema))));
+        let state = AppState::with_databases(reasoner, surreal, qdrant.clone(), embeddings);
+
+        assert_eq!(warm_collections(&state).await, types.len());
+        for entity_type in types {
+            assert_eq!(qdrant.collection_dimension(entity_type).await.unwrap(), Some(384));
+        }
+
+        // Warming up again finds nothing left to create
+        assert_eq!(warm_collections(&state).await, 0);
+
+        for entity_type in types {
+            let _ = qdrant.delete_collection(entity_type).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_named_vectors_are_searched_independently() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let _ = qdrant.delete_collection("NamedToolCall").await;
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        // One call searched for weather, the other fetched a stock price
+        let mut ids = Vec::new();
+        for (input, output) in [
+            ("weather forecast for Lisbon", "stock price of ACME is 42 dollars"),
+            ("stock quote for ACME", "sunny with light rain in the evening"),
+        ] {
+            let created = post(
+                "/api/v1/entities",
+                serde_json::json!({
+                    "entity_type": "NamedToolCall",
+                    "properties": { "input": input, "output": output },
+                    "named_vectors": { "input": "input", "output": "output" },
+                }),
+            )
+            .await;
+            ids.push(created["id"].as_str().unwrap().to_string());
+        }
+
+        let stored = surreal.get_entity(&ids[0]).await.unwrap().unwrap();
+        assert_eq!(stored.named_embeddings.len(), 2);
+        // Both named vectors live on each entity's point in the type's collection
+        assert_eq!(
+            qdrant.collection_vector_names("NamedToolCall").await.unwrap(),
+            Some(["input".to_string(), "output".to_string()].into())
+        );
+        assert_eq!(qdrant.collection_dimension("NamedToolCall").await.unwrap(), Some(384));
+
+        let top = |vector_name: &'static str| {
+            post(
+                "/api/v1/query/hybrid",
+                serde_json::json!({
+                    "type": "Vector",
+                    "entity_type": "NamedToolCall",
+                    "query_text": "weather",
+                    "vector_name": vector_name,
+                    "limit": 1,
+                }),
+            )
+        };
+        // "weather" is the first call's input but the second call's output
+        let by_input = top("input").await;
+        assert_eq!(by_input["results"][0]["entity"]["properties"]["input"], "weather forecast for Lisbon");
+        let by_output = top("output").await;
+        assert_eq!(by_output["results"][0]["entity"]["properties"]["input"], "stock quote for ACME");
+
+        // A name the collection was created without is refused rather than silently dropped
+        let summarized = serde_json::json!({
+            "entity_type": "NamedToolCall",
+            "properties": { "input": "weather forecast for Porto", "output": "cloudy" },
+            "named_vectors": { "summary": "output" },
+        });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/entities")
+                    .header("content-type", "application/json")
+                    .body(Body::from(summarized.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Re-indexing with the new name declares it, after which the entity is accepted
+        let reindexed = post(
+            "/api/v1/admin/reindex?entity_type=NamedToolCall&force=true&vector_names=summary",
+            serde_json::json!({}),
+        )
+        .await;
+        assert_eq!(reindexed["collection_recreated"], true);
+        assert_eq!(
+            qdrant.collection_vector_names("NamedToolCall").await.unwrap(),
+            Some(["input".to_string(), "output".to_string(), "summary".to_string()].into())
+        );
+        let created = post("/api/v1/entities", summarized).await;
+        ids.push(created["id"].as_str().unwrap().to_string());
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+        let _ = qdrant.delete_collection("NamedToolCall").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_hybrid_search_fuses_dense_and_sparse_vectors() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let _ = qdrant.delete_collection("HybridIncident").await;
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        // Only the unrelated-sounding incident carries the error code as a term
+        let mut ids = Vec::new();
+        for (message, terms) in [
+            ("database connection refused", serde_json::json!({ "database": 1.0, "refused": 1.0 })),
+            ("database connection timed out", serde_json::json!({ "database": 1.0, "timeout": 1.0 })),
+            ("nightly report finished late", serde_json::json!({ "err_4021": 2.0, "report": 0.5 })),
+        ] {
+            let created = post(
+                "/api/v1/entities",
+                serde_json::json!({
+                    "entity_type": "HybridIncident",
+                    "properties": { "message": message },
+                    "sparse_vector": terms,
+                }),
+            )
+            .await;
+            ids.push(created["id"].as_str().unwrap().to_string());
+        }
+
+        let stored = surreal.get_entity(&ids[2]).await.unwrap().unwrap();
+        assert_eq!(stored.sparse_vector.get("err_4021"), Some(&2.0));
+        assert_eq!(qdrant.collection_supports_sparse("HybridIncident").await.unwrap(), Some(true));
+
+        let search = |sparse_vector: Option<serde_json::Value>| {
+            post(
+                "/api/v1/query/hybrid",
+                serde_json::json!({
+                    "type": "Vector",
+                    "entity_type": "HybridIncident",
+                    "query_text": "database connection error",
+                    "sparse_vector": sparse_vector,
+                    "limit": 2,
+                }),
+            )
+        };
+        let messages = |result: &serde_json::Value| -> Vec<String> {
+            result["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["entity"]["properties"]["message"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Dense search alone ranks the two database incidents first
+        let dense = messages(&search(None).await);
+        assert!(!dense.contains(&"nightly report finished late".to_string()), "{:?}", dense);
+
+        // The error code pulls the lexical match in beside the best semantic one
+        let hybrid = messages(&search(Some(serde_json::json!({ "err_4021": 1.0 }))).await);
+        assert_eq!(hybrid.len(), 2);
+        assert!(hybrid.contains(&"nightly report finished late".to_string()), "{:?}", hybrid);
+        assert!(hybrid.iter().any(|m| m.starts_with("database connection")), "{:?}", hybrid);
+
+        // Without text to embed there is no point to hold the sparse vector, so it is refused
+        let sparse_only = serde_json::json!({
+            "entity_type": "HybridIncident",
+            "properties": {},
+            "sparse_vector": { "err_4021": 1.0 },
+        });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/entities")
+                    .header("content-type", "application/json")
+                    .body(Body::from(sparse_only.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(error["message"].as_str().unwrap().contains("dense embedding"), "{}", error);
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+        let _ = qdrant.delete_collection("HybridIncident").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_derived_property_is_computed_on_create_and_update() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use crate::ontology::entity_type::{Derivation, EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+
+        let mut schema = OntologySchema::new("derived://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("DerivedNote".to_string(), "Note".to_string())
+                .with_property(PropertyDefinition::new("text".to_string(), PropertyType::String).embeddable())
+                .with_property(
+                    PropertyDefinition::new("text_length".to_string(), PropertyType::Number)
+                        .derived(Derivation::LengthOf("text".to_string())),
+                ),
+        );
+        let reasoner = Arc::new(RwLock::new(Some(crate::intelligence::OntologyReasoner::new(schema))));
+        let state = AppState::with_databases(reasoner, surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/entities")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "entity_type": "DerivedNote",
+                            "properties": { "text": "hello", "text_length": 1 },
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        // The client's value is replaced by the computed one
+        let stored = surreal.get_entity(&id).await.unwrap().unwrap();
+        assert_eq!(stored.properties["text_length"], 5);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("/api/v1/entities/{}", id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "properties": { "text": "hello world", "text_length": 5 },
+                            "version": stored.version,
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let updated = surreal.get_entity(&id).await.unwrap().unwrap();
+        assert_eq!(updated.properties["text_length"], 11);
+
+        // Cleanup
+        let _ = surreal.purge_entity(&id).await;
+        let _ = qdrant.delete_collection("DerivedNote").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_upsert_entity_creates_then_updates_by_key() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+
+        let mut schema = OntologySchema::new("upsert://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("UpsertRequest".to_string(), "Request".to_string())
+                .with_property(PropertyDefinition::new("request_id".to_string(), PropertyType::String).unique())
+                .with_property(PropertyDefinition::new("status".to_string(), PropertyType::String)),
+        );
+        let reasoner = Arc::new(RwLock::new(Some(crate::intelligence::OntologyReasoner::new(schema))));
+        let state = AppState::with_databases(reasoner, surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let upsert = |status: &'static str| {
+            let app = app.clone();
+            let request_id = request_id.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("PUT")
+                            .uri("/api/v1/entities")
+                            .header("content-type", "application/json")
+                            .body(Body::from(
+                                serde_json::json!({
+                                    "entity_type": "UpsertRequest",
+                                    "properties": { "request_id": request_id, "status": status },
+                                })
+                                .to_string(),
+                            ))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        let first = upsert("started").await;
+        assert_eq!(first["created"], true);
+        assert_eq!(first["key"], "request_id");
+
+        // The same key updates the entity instead of creating another
+        let second = upsert("finished").await;
+        assert_eq!(second["created"], false);
+        assert_eq!(second["id"], first["id"]);
+        assert_eq!(second["version"], 2);
+
+        let ids = surreal
+            .entity_ids_with_property_value("UpsertRequest", "request_id", &serde_json::json!(request_id))
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 1);
+        let stored = surreal.get_entity(&ids[0]).await.unwrap().unwrap();
+        assert_eq!(stored.properties["status"], "finished");
+
+        // Cleanup
+        let _ = surreal.purge_entity(&ids[0]).await;
+        let _ = qdrant.delete_collection("UpsertRequest").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_similar_entities_exclude_self_and_are_ranked() {
+        use crate::db::{Entity, QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::collections::HashMap;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let _ = qdrant.delete_collection("SimilarNote").await;
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+        let send = |method: &'static str, uri: String, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method(method)
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&bytes).unwrap())
+            }
+        };
+
+        let mut ids = Vec::new();
+        for text in [
+            "heavy rain and thunderstorms expected in Lisbon tonight",
+            "storms and heavy rainfall forecast for Lisbon this evening",
+            "quarterly revenue grew twelve percent year over year",
+            "the recipe needs two cups of flour and an egg",
+        ] {
+            let (status, created) = send(
+                "POST",
+                "/api/v1/entities".to_string(),
+                serde_json::json!({ "entity_type": "SimilarNote", "properties": { "text": text } }),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            ids.push(created["id"].as_str().unwrap().to_string());
+        }
+
+        let (status, similar) = send(
+            "GET",
+            format!("/api/v1/entities/{}/similar?limit=3", ids[0]),
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let results = similar["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r["entity"]["id"] != ids[0].as_str()));
+        assert_eq!(
+            results[0]["entity"]["properties"]["text"],
+            "storms and heavy rainfall forecast for Lisbon this evening"
+        );
+        let scores: Vec<f64> = results.iter().map(|r| r["score"].as_f64().unwrap()).collect();
+        assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        // An entity stored without an embedding can't be searched from
+        let mut properties = HashMap::new();
+        properties.insert("text".to_string(), serde_json::json!("no vector"));
+        let unembedded = surreal
+            .create_entity(&Entity::new("SimilarNote".to_string(), properties))
+            .await
+            .unwrap();
+        let (status, error) = send(
+            "GET",
+            format!("/api/v1/entities/{}/similar", unembedded),
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"], "EntityNotSearchable");
+
+        // Cleanup
+        for id in ids.iter().chain([&unembedded]) {
+            let _ = surreal.purge_entity(id).await;
+        }
+        let _ = qdrant.delete_collection("SimilarNote").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_near_identical_logs_cluster_as_duplicates() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let _ = qdrant.delete_collection("DuplicateLog").await;
+        let state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+
+        let mut ids = Vec::new();
+        for message in [
+            "Connection to payments API timed out after 30 seconds",
+            "Connection to payments API timed out after 30 seconds.",
+            "connection to payments api timed out after 30 seconds",
+            "User profile photo uploaded successfully",
+        ] {
+            let created = post(
+                "/api/v1/entities",
+                serde_json::json!({ "entity_type": "DuplicateLog", "properties": { "message": message } }),
+            )
+            .await;
+            ids.push(created["id"].as_str().unwrap().to_string());
+        }
+
+        let duplicates = post(
+            "/api/v1/analytics/duplicates?entity_type=DuplicateLog&threshold=0.95",
+            serde_json::Value::Null,
+        )
+        .await;
+        assert_eq!(duplicates["scanned"], 4);
+        assert_eq!(duplicates["truncated"], false);
+        let clusters = duplicates["clusters"].as_array().unwrap();
+        assert_eq!(clusters.len(), 1);
+        let mut cluster: Vec<&str> = clusters[0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|id| id.as_str().unwrap())
+            .collect();
+        cluster.sort();
+        let mut expected: Vec<&str> = ids[..3].iter().map(String::as_str).collect();
+        expected.sort();
+        assert_eq!(cluster, expected);
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+        let _ = qdrant.delete_collection("DuplicateLog").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_create_and_delete_are_audited() {
+        use crate::api::audit::AuditLogger;
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+        let mut state = AppState::with_databases(Arc::new(RwLock::new(None)), surreal.clone(), qdrant, embeddings);
+        state.audit = Some(AuditLogger::start(surreal.clone(), 16));
+        let app = create_router_with_state(state);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/entities")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "audit-test-key-123")
+                    .header("x-request-id", "audit-create")
+                    .body(Body::from(r#"{"entity_type": "AuditNote", "properties": {"text": "audited"}}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/v1/entities/{}", id))
+                    .header("x-api-key", "audit-test-key-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        // Records are written in the background, so poll until they all land
+        let audit_records = |target_id: String, expected: usize| {
+            let app = app.clone();
+            async move {
+                let mut records = Vec::new();
+                for _ in 0..50 {
+                    let response = app
+                        .clone()
+                        .oneshot(
+                            Request::builder()
+                                .uri(format!("/api/v1/audit?target_id={}", target_id))
+                                .body(Body::empty())
+                                .unwrap(),
+                        )
+                        .await
+                        .unwrap();
+                    assert_eq!(response.status(), StatusCode::OK);
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                    let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    records = listed["records"].as_array().unwrap().clone();
+                    if records.len() == expected {
+                        break;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                records
+            }
+        };
+
+        let records = audit_records(id.clone(), 2).await;
+        assert_eq!(records.len(), 2);
+        // Most recent first
+        assert_eq!(records[0]["operation"], "delete");
+        assert_eq!(records[1]["operation"], "create");
+        assert_eq!(records[1]["target_type"], "entity");
+        assert_eq!(records[1]["request_id"], "audit-create");
+        assert_eq!(records[1]["changes"]["entity_type"], "AuditNote");
+        assert_eq!(records[1]["changes"]["properties"], serde_json::json!(["text"]));
+        for record in &records {
+            assert_eq!(record["target_id"], id.as_str());
+            // API keys aren't authenticated, so they're neither trusted nor stored
+            assert!(record["principal"].is_null());
+        }
+
+        // Linking a new task to its agent is audited like any other relation
+        let post = |uri: &'static str, body: serde_json::Value| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .method("POST")
+                            .uri(uri)
+                            .header("content-type", "application/json")
+                            .body(Body::from(body.to_string()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+            }
+        };
+        let agent = post("/api/v1/agents", serde_json::json!({"role": "auditor", "goal": "check the books"})).await;
+        let agent_id = agent["id"].as_str().unwrap().to_string();
+        let task = post("/api/v1/tasks", serde_json::json!({"agent_id": agent_id, "name": "reconcile"})).await;
+        let task_id = task["id"].as_str().unwrap().to_string();
+
+        let links = surreal.get_outgoing_relations(&agent_id, Some("executes")).await.unwrap();
+        assert_eq!(links.len(), 1);
+        let link_id = links[0].id_string();
+        let records = audit_records(link_id.clone(), 1).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["operation"], "create");
+        assert_eq!(records[0]["target_type"], "relation");
+        assert_eq!(records[0]["target_id"], link_id.as_str());
+
+        // Cleanup
+        for id in [&id, &agent_id, &task_id] {
+            let _ = surreal.purge_entity(id).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_entity_from_older_schema_version_survives_evolution() {
+        use crate::db::{QdrantClient, SurrealDBClient};
+        use crate::embeddings::EmbeddingManager;
+        use crate::intelligence::OntologyReasoner;
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+        use crate::ontology::OntologySchema;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+
+        let config = test_config();
+        let surreal = Arc::new(SurrealDBClient::new(&config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&config.qdrant).await.unwrap());
+        let embeddings = Arc::new(EmbeddingManager::new(test_embedding_config()).await.unwrap());
+
+        let schema = |version: &str, owner: PropertyDefinition| {
+            let mut schema = OntologySchema::new("evolve://test".to_string(), version.to_string());
+            schema.add_entity_type(
+                EntityType::new("EvolvingAgent".to_string(), "Agent".to_string())
+                    .with_property(PropertyDefinition::new("name".to_string(), PropertyType::String))
+                    .with_property(owner),
+            );
+            OntologyReasoner::new(schema)
+        };
+        let owner = || PropertyDefinition::new("owner".to_string(), PropertyType::String);
+        let reasoner = Arc::new(RwLock::new(Some(schema("1.0.0", owner()))));
+        let state = AppState::with_databases(reasoner.clone(), surreal.clone(), qdrant.clone(), embeddings);
+        let app = create_router_with_state(state);
+
+        let create = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/entities")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"entity_type": "EvolvingAgent", "properties": {"name": "planner"}}"#))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(create()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        // v2 makes `owner` required, which the v1 entity lacks
+        *reasoner.write().await = Some(schema("2.0.0", owner().required()));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/v1/entities/{}?validate=true", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entity: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(entity["schema_version"], "1.0.0");
+        assert_eq!(entity["properties"]["name"], "planner");
+        assert_eq!(entity["validation_errors"], serde_json::json!([]));
+
+        // Writes are held to the current schema
+        let response = app.oneshot(create()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Cleanup
+        let _ = surreal.purge_entity(&id).await;
+        let _ = qdrant.delete_collection("EvolvingAgent").await;
+    }
 }
@@ -0,0 +1,146 @@
+// Ingestion sampling: keep a fixed fraction of traces and only count the rest
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+/// Events left out of storage by sampling
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct SampledOut {
+    pub events: u64,
+    pub events_by_type: HashMap<String, u64>,
+}
+
+/// Decides per trace whether its events are stored. The decision is a hash
+/// of the trace (or session) ID, so every event of a trace gets the same one,
+/// on every node and across restarts.
+pub struct TraceSampler {
+    rate: f64,
+    /// Dropped event counts keyed by agent ID. Kept in memory only.
+    dropped: Mutex<HashMap<Option<String>, SampledOut>>,
+}
+
+impl TraceSampler {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            dropped: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fraction of traces kept
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Whether any traces are dropped at all
+    pub fn is_sampling(&self) -> bool {
+        self.rate < 1.0
+    }
+
+    /// Whether events of the trace or session `key` are stored
+    pub fn keeps(&self, key: &str) -> bool {
+        !self.is_sampling() || sample_point(key) < self.rate
+    }
+
+    /// Count an event that was not stored
+    pub fn record_dropped(&self, agent_id: Option<&str>, event_type: &str) {
+        let mut dropped = self.dropped.lock().unwrap();
+        let counts = dropped.entry(agent_id.map(str::to_string)).or_default();
+        counts.events += 1;
+        *counts.events_by_type.entry(event_type.to_string()).or_default() += 1;
+    }
+
+    /// Events dropped for one agent, or for all agents when `agent_id` is `None`
+    pub fn dropped(&self, agent_id: Option<&str>) -> SampledOut {
+        let dropped = self.dropped.lock().unwrap();
+        let mut total = SampledOut::default();
+        for (agent, counts) in dropped.iter() {
+            if agent_id.is_some() && agent.as_deref() != agent_id {
+                continue;
+            }
+            total.events += counts.events;
+            for (event_type, count) in &counts.events_by_type {
+                *total.events_by_type.entry(event_type.clone()).or_default() += count;
+            }
+        }
+        total
+    }
+}
+
+impl Default for TraceSampler {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// Map a key uniformly onto [0, 1) with 64-bit FNV-1a, which unlike std's
+/// hasher is the same in every process
+fn sample_point(key: &str) -> f64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    // FNV mixes the low bits poorly for similar keys; fold the high half in
+    hash ^= hash >> 32;
+    hash = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_about_rate_of_traces() {
+        for rate in [0.1, 0.25, 0.5, 0.9] {
+            let sampler = TraceSampler::new(rate);
+            let traces = 20_000;
+            let kept = (0..traces)
+                .filter(|i| sampler.keeps(&format!("trace-{}", i)))
+                .count();
+            let fraction = kept as f64 / traces as f64;
+            assert!((fraction - rate).abs() < 0.02, "rate {} kept {}", rate, fraction);
+        }
+
+        assert!((0..1000).all(|i| TraceSampler::new(1.0).keeps(&format!("trace-{}", i))));
+        assert!((0..1000).all(|i| !TraceSampler::new(0.0).keeps(&format!("trace-{}", i))));
+    }
+
+    #[test]
+    fn test_decision_is_stable_per_trace() {
+        let first = TraceSampler::new(0.3);
+        let second = TraceSampler::new(0.3);
+        for i in 0..1000 {
+            let trace = uuid::Uuid::new_v4().to_string();
+            let keep = first.keeps(&trace);
+            assert_eq!(first.keeps(&trace), keep);
+            assert_eq!(second.keeps(&trace), keep, "trace {} ({})", trace, i);
+        }
+
+        // A trace kept at one rate is still kept at any higher rate
+        let trace = (0..).map(|i| format!("trace-{}", i)).find(|t| first.keeps(t)).unwrap();
+        assert!(TraceSampler::new(0.5).keeps(&trace));
+    }
+
+    #[test]
+    fn test_dropped_counts_per_agent() {
+        let sampler = TraceSampler::new(0.5);
+        sampler.record_dropped(Some("agent-1"), "llm_call");
+        sampler.record_dropped(Some("agent-1"), "llm_call");
+        sampler.record_dropped(Some("agent-2"), "tool_call");
+        sampler.record_dropped(None, "llm_call");
+
+        let agent = sampler.dropped(Some("agent-1"));
+        assert_eq!(agent.events, 2);
+        assert_eq!(agent.events_by_type["llm_call"], 2);
+
+        let all = sampler.dropped(None);
+        assert_eq!(all.events, 4);
+        assert_eq!(all.events_by_type["llm_call"], 3);
+        assert_eq!(all.events_by_type["tool_call"], 1);
+        assert_eq!(sampler.dropped(Some("agent-3")), SampledOut::default());
+    }
+}
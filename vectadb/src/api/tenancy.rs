@@ -0,0 +1,285 @@
+// Multi-tenant isolation: every request is served from a tenant-scoped copy of the app state
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+use tracing::info;
+
+use crate::config::DatabaseConfig;
+use crate::db::retention::{self, RetentionPolicy};
+use crate::db::{QdrantClient, SurrealDBClient};
+use crate::error::VectaDBError;
+use super::audit::AuditLogger;
+use super::event_stream::EventBroadcaster;
+use super::handlers::{self, AppState};
+use super::idempotency::IdempotencyStore;
+use super::openapi;
+use super::routes;
+use super::sampling::TraceSampler;
+
+/// Header carrying the tenant of a request
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+const MAX_TENANT_ID_LEN: usize = 64;
+
+/// Tenant-scoped routers, created on a tenant's first request.
+///
+/// Each tenant gets its own SurrealDB database (`<database>_<tenant>` in the
+/// configured namespace), its own Qdrant collection prefix
+/// (`<prefix><tenant>.`), ontology, and event stream. Limits, CORS, rate
+/// limiting and the embedding provider are shared. The tenant header is not
+/// authenticated, so only tenants on the configured allow-list are served.
+pub struct TenantRegistry {
+    base: AppState,
+    database: DatabaseConfig,
+    allowed: HashSet<String>,
+    retention: Option<RetentionPolicy>,
+    routers: RwLock<HashMap<String, Router>>,
+}
+
+impl TenantRegistry {
+    pub fn new(
+        base: AppState,
+        database: DatabaseConfig,
+        allowed_tenants: Vec<String>,
+        retention: Option<RetentionPolicy>,
+    ) -> Self {
+        Self {
+            base,
+            database,
+            allowed: allowed_tenants.into_iter().collect(),
+            retention,
+            routers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Router serving `tenant`, creating the tenant's state if needed
+    async fn router(&self, tenant: &str) -> Result<Router, VectaDBError> {
+        if let Some(router) = self.routers.read().await.get(tenant) {
+            return Ok(router.clone());
+        }
+        if !self.allowed.contains(tenant) {
+            return Err(VectaDBError::NotFound(format!("Unknown tenant '{}'", tenant)));
+        }
+
+        // Connecting and warming up can be slow, so it happens outside the
+        // lock; only this tenant's first requests wait for it
+        let state = self.tenant_state(tenant).await?;
+
+        let router = match self.routers.write().await.entry(tenant.to_string()) {
+            // Another request set the tenant up first; its state wins and ours is dropped
+            Entry::Occupied(existing) => return Ok(existing.get().clone()),
+            Entry::Vacant(slot) => slot.insert(routes::api_routes().with_state(state.clone())).clone(),
+        };
+        self.start_background_tasks(&state);
+        info!("Initialized tenant {}", tenant);
+        Ok(router)
+    }
+
+    /// Keep the tenant's database connected and enforce retention on it, as
+    /// is done for the shared database
+    fn start_background_tasks(&self, state: &AppState) {
+        let Some(surreal) = &state.surreal else {
+            return;
+        };
+        tokio::spawn(handlers::keep_surreal_connected(
+            surreal.clone(),
+            state.reasoner.clone(),
+            false,
+        ));
+        if let Some(policy) = &self.retention {
+            tokio::spawn(retention::enforce_retention(
+                surreal.clone(),
+                state.qdrant.clone(),
+                policy.clone(),
+            ));
+        }
+    }
+
+    /// Open tenant-scoped clients for whichever backends the base state has
+    async fn tenant_state(&self, tenant: &str) -> Result<AppState, VectaDBError> {
+        let mut database = self.database.clone();
+        database.surrealdb.database = format!("{}_{}", database.surrealdb.database, tenant);
+        database.qdrant.collection_prefix = collection_prefix(&database.qdrant.collection_prefix, tenant);
+
+        let surreal = match self.base.surreal {
+            Some(_) => Some(Arc::new(SurrealDBClient::new(&database).await.map_err(|e| {
+                VectaDBError::Unavailable(format!("Failed to open database for tenant '{}': {}", tenant, e))
+            })?)),
+            None => None,
+        };
+        let qdrant = match self.base.qdrant {
+            Some(_) => Some(Arc::new(QdrantClient::new(&database.qdrant).await.map_err(|e| {
+                VectaDBError::Unavailable(format!("Failed to open vector store for tenant '{}': {}", tenant, e))
+            })?)),
+            None => None,
+        };
+
+        let reasoner = Arc::new(RwLock::new(None));
+        if let Some(surreal) = &surreal {
+            handlers::load_persisted_schema(surreal, &reasoner).await;
+        }
+
+        let mut state = match (surreal, qdrant, self.base.embedding_service.clone()) {
+            (Some(surreal), Some(qdrant), Some(embedding_service)) => {
+                AppState::with_databases(reasoner, surreal, qdrant, embedding_service)
+            }
+            (surreal, qdrant, embedding_service) => {
+                let mut state = AppState::new();
+                state.reasoner = reasoner;
+                state.surreal = surreal;
+                state.qdrant = qdrant;
+                state.embedding_service = embedding_service;
+                state
+            }
+        };
+
+        state.rate_limiter = self.base.rate_limiter.clone();
+        state.limits = self.base.limits.clone();
+        state.query = self.base.query.clone();
+        state.pricing = self.base.pricing.clone();
+        state.sampler = Arc::new(TraceSampler::new(self.base.sampler.rate()));
+        state.cors = self.base.cors.clone();
+        state.events = EventBroadcaster::default();
+        state.idempotency = Arc::new(IdempotencyStore::new(self.base.idempotency.ttl()));
+        state.audit = match (&self.base.audit, &state.surreal) {
+            (Some(audit), Some(surreal)) => Some(AuditLogger::start(surreal.clone(), audit.queue_size())),
+            _ => None,
+        };
+        if let Some(coordinator) = &self.base.query_coordinator {
+            state.set_query_cache_ttl(coordinator.cache_ttl());
+        }
+        if self.database.qdrant.warmup_collections {
+            handlers::warm_collections(&state).await;
+        }
+        Ok(state)
+    }
+}
+
+/// Qdrant collection prefix of `tenant`. Tenant IDs never contain `.`, so
+/// ending the prefix with one keeps tenant `a` from also matching the
+/// collections of tenant `a_b`.
+fn collection_prefix(prefix: &str, tenant: &str) -> String {
+    format!("{}{}.", prefix, tenant)
+}
+
+/// Router that sends each request to its tenant's routes. Only `/health`
+/// and `/openapi.json` answer without a tenant.
+pub fn tenant_router(registry: Arc<TenantRegistry>) -> Router {
+    Router::new()
+        .route("/health", get(handlers::health_check).with_state(registry.base.clone()))
+        .route("/openapi.json", get(openapi::get_openapi))
+        .fallback(dispatch)
+        .with_state(registry)
+}
+
+async fn dispatch(State(registry): State<Arc<TenantRegistry>>, request: Request) -> Response {
+    let result = match tenant_id(request.headers()) {
+        Ok(tenant) => registry.router(&tenant).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(router) => router.oneshot(request).await.unwrap_or_else(|never| match never {}),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Read and validate the tenant header. IDs become part of database and
+/// collection names, so only ASCII letters, digits, `_` and `-` are allowed.
+fn tenant_id(headers: &HeaderMap) -> Result<String, VectaDBError> {
+    let tenant = headers
+        .get(TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| VectaDBError::BadRequest("Missing X-Tenant-ID header".to_string()))?;
+
+    let valid = tenant.len() <= MAX_TENANT_ID_LEN
+        && tenant
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(VectaDBError::BadRequest(format!(
+            "Invalid tenant ID '{}': use up to {} letters, digits, '_' or '-'",
+            tenant, MAX_TENANT_ID_LEN
+        )));
+    }
+
+    Ok(tenant.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(tenant: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TENANT_HEADER, HeaderValue::from_str(tenant).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_tenant_id_validation() {
+        assert_eq!(tenant_id(&headers("acme-corp_1")).unwrap(), "acme-corp_1");
+        assert_eq!(tenant_id(&headers(" acme ")).unwrap(), "acme");
+
+        assert!(matches!(tenant_id(&HeaderMap::new()), Err(VectaDBError::BadRequest(_))));
+        assert!(matches!(tenant_id(&headers("")), Err(VectaDBError::BadRequest(_))));
+        assert!(matches!(tenant_id(&headers("acme;DROP")), Err(VectaDBError::BadRequest(_))));
+        assert!(matches!(tenant_id(&headers("a.b")), Err(VectaDBError::BadRequest(_))));
+        assert!(tenant_id(&headers(&"a".repeat(MAX_TENANT_ID_LEN + 1))).is_err());
+    }
+
+    #[test]
+    fn test_tenant_collection_prefixes_do_not_overlap() {
+        use crate::db::qdrant_client::CollectionNaming;
+
+        let a = CollectionNaming::new(collection_prefix("vectadb_", "a"));
+        let a_b = CollectionNaming::new(collection_prefix("vectadb_", "a_b"));
+        assert_eq!(a.collection("Log"), "vectadb_a.Log");
+        assert_eq!(a.entity_type(&a_b.collection("Log")), None);
+        assert_eq!(a_b.entity_type(&a.collection("b_Log")), None);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_tenant_collections_are_listed_apart() {
+        use crate::config::QdrantConfig;
+
+        let client = |tenant: &str| {
+            let config = QdrantConfig {
+                url: "http://localhost:6333".to_string(),
+                api_key: None,
+                collection_prefix: collection_prefix("test_", tenant),
+                warmup_collections: false,
+                collection: Default::default(),
+            };
+            async move { QdrantClient::new(&config).await.unwrap() }
+        };
+        let a = client("a").await;
+        let a_b = client("a_b").await;
+
+        let _ = a.delete_collection("b_Log").await;
+        let _ = a_b.delete_collection("Log").await;
+        a.create_collection("b_Log", 4).await.unwrap();
+        a_b.create_collection("Log", 4).await.unwrap();
+
+        assert_eq!(a.list_entity_collections().await.unwrap(), vec!["b_Log".to_string()]);
+        assert_eq!(a_b.list_entity_collections().await.unwrap(), vec!["Log".to_string()]);
+
+        // Cleanup
+        let _ = a.delete_collection("b_Log").await;
+        let _ = a_b.delete_collection("Log").await;
+    }
+}
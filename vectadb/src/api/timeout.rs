@@ -0,0 +1,172 @@
+// Request timeouts: handlers that run too long are dropped and answered with 504
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::time::Duration;
+
+use crate::config::{ServerConfig, DEFAULT_LONG_REQUEST_TIMEOUT_SECS, DEFAULT_REQUEST_TIMEOUT_SECS};
+use super::types::ErrorResponse;
+
+/// Routes doing bulk work, which get the long-running timeout
+const LONG_RUNNING_ROUTES: &[&str] = &[
+    "/api/v1/events/batch",
+    "/api/v1/import",
+    "/api/v1/admin/reindex",
+    "/api/v1/admin/gc-vectors",
+    "/api/v1/admin/replay-dead-letters",
+];
+
+/// How long a request may take before its handler is dropped; `None` never times out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestTimeouts {
+    pub default: Option<Duration>,
+    /// For bulk ingest, import, reindex and other long-running routes
+    pub long_running: Option<Duration>,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            default: Some(Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS)),
+            long_running: Some(Duration::from_secs(DEFAULT_LONG_REQUEST_TIMEOUT_SECS)),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    /// Timeouts from config, where 0 seconds disables a timeout
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let secs = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        Self {
+            default: secs(config.request_timeout_secs),
+            long_running: secs(config.long_request_timeout_secs),
+        }
+    }
+
+    /// The timeout applying to requests for `path`
+    pub fn for_path(&self, path: &str) -> Option<Duration> {
+        if LONG_RUNNING_ROUTES.contains(&path) {
+            self.long_running
+        } else {
+            self.default
+        }
+    }
+}
+
+/// Middleware answering 504 when the handler outlives its timeout. The
+/// handler's future is dropped, cancelling any database or embedding call in
+/// flight. Only producing the response head is timed, so streamed bodies
+/// (event streams, exports) may run on.
+pub async fn enforce(
+    State(timeouts): State<RequestTimeouts>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(timeout) = timeouts.for_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path().to_string();
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request to {} timed out after {:?}", path, timeout);
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ErrorResponse::new(
+                    "RequestTimeout",
+                    format!("Request did not complete within {} seconds", timeout.as_secs_f64()),
+                )),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// Sets its flag when dropped, i.e. when the handler is cancelled or done
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn slow_app(timeouts: RequestTimeouts, delay: Duration, dropped: Arc<AtomicBool>) -> Router {
+        let handler = move || {
+            let dropped = dropped.clone();
+            async move {
+                let _flag = DropFlag(dropped);
+                tokio::time::sleep(delay).await;
+                "done"
+            }
+        };
+        Router::new()
+            .route("/slow", get(handler.clone()))
+            .route("/api/v1/admin/reindex", get(handler))
+            .layer(middleware::from_fn_with_state(timeouts, enforce))
+    }
+
+    async fn status(app: Router, uri: &str) -> StatusCode {
+        app.oneshot(axum::http::Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out_with_504() {
+        let timeouts = RequestTimeouts {
+            default: Some(Duration::from_millis(20)),
+            long_running: Some(Duration::from_secs(5)),
+        };
+        let dropped = Arc::new(AtomicBool::new(false));
+        let app = slow_app(timeouts, Duration::from_secs(10), dropped.clone());
+
+        let started = std::time::Instant::now();
+        assert_eq!(status(app, "/slow").await, StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // The handler was dropped rather than left running
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_long_running_routes_get_their_own_timeout() {
+        let timeouts = RequestTimeouts {
+            default: Some(Duration::from_millis(20)),
+            long_running: Some(Duration::from_secs(5)),
+        };
+        let app = slow_app(timeouts, Duration::from_millis(100), Arc::new(AtomicBool::new(false)));
+
+        assert_eq!(status(app.clone(), "/api/v1/admin/reindex").await, StatusCode::OK);
+        assert_eq!(status(app, "/slow").await, StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[test]
+    fn test_zero_disables_timeout() {
+        let config = ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+            grpc_enabled: false,
+            grpc_port: 50051,
+            request_timeout_secs: 0,
+            long_request_timeout_secs: 120,
+        };
+        let timeouts = RequestTimeouts::from_config(&config);
+        assert_eq!(timeouts.for_path("/api/v1/entities"), None);
+        assert_eq!(timeouts.for_path("/api/v1/import"), Some(Duration::from_secs(120)));
+    }
+}
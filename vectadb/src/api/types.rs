@@ -3,13 +3,19 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+
+use super::sampling::SampledOut;
+use crate::db::{AuditRecord, DeadLetter, ExportTable, FacetCount, Histogram, TimeBucket, TokenUsage};
+use crate::models::{Agent, LogLevel, Task, TaskStatus, Thought};
+use crate::ontology::SchemaMerge;
 
 // ============================================================================
 // Ontology Management
 // ============================================================================
 
 /// Upload ontology schema request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UploadSchemaRequest {
     /// Schema in JSON or YAML format
     pub schema: String,
@@ -18,24 +24,88 @@ pub struct UploadSchemaRequest {
     pub format: SchemaFormat,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SchemaFormat {
     Json,
     Yaml,
 }
 
+/// Schema download query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetSchemaParams {
+    /// "json" or "yaml"; defaults to the `Accept` header, then JSON
+    pub format: Option<SchemaFormat>,
+}
+
+/// Schema upload query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UploadSchemaParams {
+    /// Check stored entities of affected types against the new schema
+    #[serde(default)]
+    pub validate_existing_data: bool,
+    /// Reject the upload if any stored entity would fail validation;
+    /// implies `validate_existing_data`
+    #[serde(default)]
+    pub strict: bool,
+}
+
 /// Schema upload response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UploadSchemaResponse {
     pub success: bool,
     pub message: String,
     pub namespace: String,
     pub version: String,
+    /// Present when `validate_existing_data` was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub existing_data: Option<ExistingDataReport>,
+}
+
+/// Schema patch query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PatchSchemaParams {
+    /// Replace existing types and rules the patch redefines instead of rejecting it
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Schema patch response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PatchSchemaResponse {
+    pub success: bool,
+    pub message: String,
+    pub namespace: String,
+    pub version: String,
+    #[serde(flatten)]
+    pub changes: SchemaMerge,
+}
+
+/// Stored entities checked against an uploaded schema
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ExistingDataReport {
+    /// Entity types whose validation rules changed
+    pub checked_types: Vec<String>,
+    pub checked_entities: usize,
+    /// Entities that would fail validation under the new schema
+    pub invalid_entities: usize,
+    /// The first few invalid entities
+    pub samples: Vec<InvalidEntitySample>,
+}
+
+/// A stored entity that fails validation under a new schema
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InvalidEntitySample {
+    pub id: String,
+    pub entity_type: String,
+    pub errors: Vec<String>,
 }
 
 /// Get entity type response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetEntityTypeResponse {
     pub id: String,
     pub label: String,
@@ -44,7 +114,7 @@ pub struct GetEntityTypeResponse {
     pub constraints: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PropertyInfo {
     pub name: String,
     pub property_type: String,
@@ -54,49 +124,96 @@ pub struct PropertyInfo {
 }
 
 /// Get subtypes response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetSubtypesResponse {
     pub type_id: String,
     pub subtypes: Vec<String>,
 }
 
+/// Get supertypes response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GetSupertypesResponse {
+    pub type_id: String,
+    /// Ancestors, nearest parent first
+    pub supertypes: Vec<String>,
+}
+
+/// Get relation type response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GetRelationTypeResponse {
+    pub id: String,
+    pub label: String,
+    pub domain: String,
+    pub range: String,
+    pub inverse: Option<String>,
+    pub transitive: bool,
+    pub symmetric: bool,
+    pub functional: bool,
+    pub reflexive: bool,
+}
+
+/// List relation types response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListRelationTypesResponse {
+    pub relation_types: Vec<GetRelationTypeResponse>,
+    pub count: usize,
+}
+
 // ============================================================================
 // Entity Validation
 // ============================================================================
 
 /// Validate entity request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateEntityRequest {
     pub entity_type: String,
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
+    /// Also check unique properties against stored entities
+    #[serde(default)]
+    pub check_data: bool,
+    /// Entity being updated, which does not collide with itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
 }
 
 /// Validation response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateEntityResponse {
     pub valid: bool,
     pub errors: Vec<ValidationErrorInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidationErrorInfo {
     pub error_type: String,
     pub message: String,
 }
 
 /// Validate relation request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateRelationRequest {
     pub relation_type: String,
     pub source_type: String,
     pub target_type: String,
+    /// Also check functional relations against stored relations
+    #[serde(default)]
+    pub check_data: bool,
+    /// Source entity of the relation to create; required with `check_data`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
 }
 
 /// Validate relation response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ValidateRelationResponse {
     pub valid: bool,
+    /// The first of `errors`
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationErrorInfo>,
 }
 
 // ============================================================================
@@ -104,14 +221,14 @@ pub struct ValidateRelationResponse {
 // ============================================================================
 
 /// Expand query request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ExpandQueryRequest {
     pub entity_type: String,
     pub include_inferred_relations: bool,
 }
 
 /// Expand query response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ExpandQueryResponse {
     pub original_type: String,
     pub expanded_types: Vec<String>,
@@ -119,7 +236,15 @@ pub struct ExpandQueryResponse {
     pub metadata: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Materialized expansions response: the expanded types of every entity type
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MaterializedExpansionsResponse {
+    pub namespace: String,
+    pub version: String,
+    pub expansions: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct InferredRelationInfo {
     pub relation_type: String,
     pub source_type: String,
@@ -128,14 +253,14 @@ pub struct InferredRelationInfo {
 }
 
 /// Get compatible relations request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetCompatibleRelationsRequest {
     pub source_type: String,
     pub target_type: String,
 }
 
 /// Get compatible relations response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetCompatibleRelationsResponse {
     pub source_type: String,
     pub target_type: String,
@@ -147,7 +272,7 @@ pub struct GetCompatibleRelationsResponse {
 // ============================================================================
 
 /// Health check response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
@@ -161,7 +286,7 @@ pub struct HealthResponse {
 // ============================================================================
 
 /// Standard error response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
@@ -181,43 +306,192 @@ impl ErrorResponse {
 // ============================================================================
 
 /// Create entity request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateEntityRequest {
     pub entity_type: String,
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<HashMap<String, String>>,
+    /// Named vectors to store, each embedding one property: vector name -> property name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub named_vectors: HashMap<String, String>,
+    /// Term weights (e.g. SPLADE or BM25 output) stored as a sparse vector
+    /// next to the embedding, for hybrid search: term -> weight
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sparse_vector: HashMap<String, f32>,
 }
 
 /// Create entity response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateEntityResponse {
     pub id: String,
     pub entity_type: String,
     pub created_at: String,
 }
 
+/// Upsert entity request: create, or update the entity with the same key
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpsertEntityRequest {
+    pub entity_type: String,
+    #[schema(value_type = Object)]
+    pub properties: HashMap<String, JsonValue>,
+    /// Property identifying the entity; defaults to the type's one unique property
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+    /// Named vectors; only accepted when the upsert creates the entity
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub named_vectors: HashMap<String, String>,
+    /// Sparse vector term weights; only accepted when the upsert creates the entity
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sparse_vector: HashMap<String, f32>,
+}
+
+/// Upsert entity response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpsertEntityResponse {
+    pub id: String,
+    pub entity_type: String,
+    /// Property the entity was matched on
+    pub key: String,
+    /// Whether a new entity was created rather than an existing one updated
+    pub created: bool,
+    /// Version after the write
+    pub version: u64,
+}
+
 /// Update entity request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateEntityRequest {
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
+    /// Version the client last read; the update is rejected if it has changed
+    pub version: u64,
 }
 
 /// Entity response (for GET)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EntityResponse {
     pub id: String,
     pub entity_type: String,
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub named_embeddings: HashMap<String, Vec<f32>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sparse_vector: HashMap<String, f32>,
     pub created_at: String,
     pub updated_at: String,
     pub metadata: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+    /// Ontology version the entity was validated against when last written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
+    /// With `validate=true`, where the entity breaks the loaded ontology
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation_errors: Option<Vec<String>>,
+}
+
+/// Entity read query parameters
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetEntityParams {
+    /// Return the entity even if it has been soft-deleted
+    #[serde(default)]
+    pub include_deleted: bool,
+    /// Check the entity against the loaded ontology. Entities written under
+    /// an older ontology version are not checked.
+    #[serde(default)]
+    pub validate: bool,
+}
+
+/// Batch get request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchGetEntitiesRequest {
+    pub ids: Vec<String>,
+    /// Return soft-deleted entities too
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+/// Batch get response: found entities in request order, and the IDs not found
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchGetEntitiesResponse {
+    pub entities: Vec<EntityResponse>,
+    pub missing: Vec<String>,
+}
+
+/// Similar entities query parameters
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SimilarEntitiesParams {
+    /// Most neighbours to return (defaults to and is capped by the server's query limits)
+    pub limit: Option<usize>,
+}
+
+/// A neighbour of the requested entity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SimilarEntity {
+    pub entity: EntityResponse,
+    /// Vector similarity to the requested entity
+    pub score: f32,
+}
+
+/// Nearest neighbours of an entity, most similar first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SimilarEntitiesResponse {
+    pub entity_id: String,
+    pub results: Vec<SimilarEntity>,
+    pub count: usize,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
+}
+
+/// Full-text search request
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchRequest {
+    /// Keywords; entities matching any of them are returned, best match first
+    pub query: String,
+    /// Only search entities of this type
+    #[serde(default)]
+    pub entity_type: Option<String>,
+    /// Properties to search, each marked `searchable` in the ontology.
+    /// Defaults to every searchable property (of `entity_type`, if given).
+    #[serde(default)]
+    pub properties: Vec<String>,
+    /// Most results to return (defaults to and is capped by the server's query limits)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// An entity matching a full-text search
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchHit {
+    pub entity: EntityResponse,
+    /// BM25 relevance, summed over the matching properties
+    pub score: f32,
+}
+
+/// Full-text search results, most relevant first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TextSearchResponse {
+    pub results: Vec<TextSearchHit>,
+    pub count: usize,
+    /// Properties searched
+    pub properties: Vec<String>,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
 }
 
 /// List entities response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ListEntitiesResponse {
     pub entities: Vec<EntityResponse>,
     pub total: usize,
@@ -228,43 +502,210 @@ pub struct ListEntitiesResponse {
 // ============================================================================
 
 /// Create relation request
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateRelationRequest {
     pub relation_type: String,
     pub source_id: String,
     pub target_id: String,
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
+    /// For a reflexive relation type, also link the source and target to
+    /// themselves
+    #[serde(default)]
+    pub materialize_reflexive: bool,
+    /// If the relation type has an inverse, also create the inverse relation
+    /// from the target back to the source
+    #[serde(default)]
+    pub materialize_inverse: bool,
 }
 
 /// Create relation response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateRelationResponse {
     pub id: String,
     pub relation_type: String,
     pub source_id: String,
     pub target_id: String,
     pub created_at: String,
+    /// Relations the ontology implies that were created alongside this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub implied_relation_ids: Vec<String>,
+}
+
+/// Create log response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateLogResponse {
+    pub id: String,
+    pub level: LogLevel,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Create thought response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateThoughtResponse {
+    pub id: String,
+    pub trace_id: String,
+    pub sequence: Option<i32>,
+    /// Thought this one follows in the chain, if it isn't the first step
+    pub follows: Option<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reasoning chain of a trace, in order
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReasoningChainResponse {
+    pub trace_id: String,
+    pub thoughts: Vec<Thought>,
+    pub count: usize,
+}
+
+/// Query parameters for listing agents
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListAgentsParams {
+    /// Only agents with this role
+    pub role: Option<String>,
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Agents to skip (after the cursor, if any)
+    pub offset: Option<usize>,
+}
+
+/// List agents response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListAgentsResponse {
+    pub agents: Vec<Agent>,
+    pub count: usize,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Query parameters for listing tasks
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTasksParams {
+    /// Only tasks belonging to this agent
+    pub agent_id: Option<String>,
+    /// Only tasks in this status
+    pub status: Option<TaskStatus>,
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Tasks to skip (after the cursor, if any)
+    pub offset: Option<usize>,
+}
+
+/// List tasks response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListTasksResponse {
+    pub tasks: Vec<Task>,
+    pub count: usize,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Request to move a task to a new status
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTaskStatusRequest {
+    pub status: TaskStatus,
 }
 
 /// Relation response (for GET)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RelationResponse {
     pub id: String,
     pub relation_type: String,
     pub source_id: String,
     pub target_id: String,
+    #[schema(value_type = Object)]
     pub properties: HashMap<String, JsonValue>,
     pub created_at: String,
 }
 
+/// Which edges of an entity to list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationDirection {
+    Outgoing,
+    Incoming,
+    #[default]
+    Both,
+}
+
+/// Query parameters for listing an entity's relations
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EntityRelationsParams {
+    #[serde(default)]
+    pub direction: RelationDirection,
+    /// Only relations of this type
+    #[serde(rename = "type")]
+    pub relation_type: Option<String>,
+}
+
+/// One direct edge of an entity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EntityEdge {
+    #[serde(flatten)]
+    pub relation: RelationResponse,
+    /// `outgoing` if the entity is the source, `incoming` if it is the target
+    pub direction: RelationDirection,
+    /// The entity at the other end of the edge
+    pub connected_entity_id: String,
+}
+
+/// Direct edges of an entity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EntityRelationsResponse {
+    pub entity_id: String,
+    pub relations: Vec<EntityEdge>,
+    pub count: usize,
+}
+
+/// Query parameters for extracting an entity's neighborhood
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SubgraphParams {
+    /// Number of hops from the start entity
+    #[serde(default = "default_subgraph_depth")]
+    pub depth: usize,
+    #[serde(default)]
+    pub direction: RelationDirection,
+    /// Only follow relations of this type
+    #[serde(rename = "type")]
+    pub relation_type: Option<String>,
+    /// Node limit; capped by the server's configured maximum
+    pub max_nodes: Option<usize>,
+}
+
+fn default_subgraph_depth() -> usize {
+    2
+}
+
+/// Entities and relations around a start entity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubgraphResponse {
+    pub nodes: Vec<EntityResponse>,
+    pub edges: Vec<RelationResponse>,
+    /// True when the node limit was reached before the walk finished
+    pub truncated: bool,
+}
+
 // ============================================================================
 // Hybrid Query
 // ============================================================================
 
 /// Hybrid query request (re-export from query module)
 pub use crate::query::{
-    HybridQuery, QueryResult,
+    HybridQuery, QueryResult, ScoredResult, StreamSummary,
 };
 
 // ============================================================================
@@ -272,7 +713,7 @@ pub use crate::query::{
 // ============================================================================
 
 /// Event ingestion request - flexible schema for log-based events
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EventIngestionRequest {
     /// Optional: Link to existing trace
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -301,8 +742,121 @@ pub struct EventIngestionRequest {
     pub source: Option<LogSource>,
 }
 
+/// Query parameters for listing events
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListEventsParams {
+    pub agent_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub event_type: Option<String>,
+    /// Only events at or after this time
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only events before this time
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Events to skip (after the cursor, if any)
+    pub offset: Option<usize>,
+}
+
+/// Event list response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListEventsResponse {
+    pub events: Vec<serde_json::Value>,
+    pub count: usize,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Agent event as pushed to live stream subscribers
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamedEvent {
+    pub id: String,
+    pub trace_id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub properties: serde_json::Value,
+}
+
+impl StreamedEvent {
+    pub fn from_request(id: &str, trace_id: &str, request: &EventIngestionRequest) -> Self {
+        Self {
+            id: id.to_string(),
+            trace_id: trace_id.to_string(),
+            timestamp: request.timestamp,
+            event_type: request.event_type.clone(),
+            agent_id: request.agent_id.clone(),
+            session_id: request.session_id.clone(),
+            properties: request.properties.clone(),
+        }
+    }
+}
+
+impl From<crate::db::EventRecord> for StreamedEvent {
+    fn from(record: crate::db::EventRecord) -> Self {
+        Self {
+            id: record.id,
+            trace_id: record.trace_id,
+            timestamp: record.timestamp,
+            event_type: record.event_type,
+            agent_id: record.agent_id,
+            session_id: record.session_id,
+            properties: record.properties,
+        }
+    }
+}
+
+/// Message sent over the event stream WebSocket
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Event(StreamedEvent),
+    /// The client fell behind and this many events were dropped
+    Lagged { skipped: u64 },
+    /// The database subscription dropped and was re-established; events
+    /// created in between were not streamed
+    Reconnected,
+}
+
+/// Query parameters for the live event stream
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventStreamParams {
+    pub agent_id: Option<String>,
+    pub trace_id: Option<String>,
+}
+
+impl EventStreamParams {
+    pub fn matches(&self, event: &StreamedEvent) -> bool {
+        let agent_ok = self
+            .agent_id
+            .as_ref()
+            .is_none_or(|id| event.agent_id.as_ref() == Some(id));
+        let trace_ok = self.trace_id.as_ref().is_none_or(|id| &event.trace_id == id);
+        agent_ok && trace_ok
+    }
+
+    /// Filter for a database live subscription
+    pub fn event_filter(&self) -> crate::db::EventFilter {
+        crate::db::EventFilter {
+            agent_id: self.agent_id.clone(),
+            trace_id: self.trace_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Log source metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LogSource {
     /// Source system (cloudwatch, datadog, etc.)
     pub system: String,
@@ -318,7 +872,7 @@ pub struct LogSource {
 }
 
 /// Bulk event ingestion request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct BulkEventIngestionRequest {
     /// List of events to ingest
     pub events: Vec<EventIngestionRequest>,
@@ -329,7 +883,7 @@ pub struct BulkEventIngestionRequest {
 }
 
 /// Ingestion options
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, ToSchema)]
 pub struct IngestionOptions {
     /// Auto-create traces from session_id if not exists
     #[serde(default = "default_true")]
@@ -342,6 +896,14 @@ pub struct IngestionOptions {
     /// Extract event relationships (causality)
     #[serde(default)]
     pub extract_relationships: bool,
+
+    /// Echo each failed event's payload in its error, so it can be retried
+    #[serde(default)]
+    pub return_failed_events: bool,
+
+    /// Keep failed events in the dead letter store for later replay
+    #[serde(default)]
+    pub dead_letter: bool,
 }
 
 fn default_true() -> bool {
@@ -349,25 +911,429 @@ fn default_true() -> bool {
 }
 
 /// Event ingestion response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct EventIngestionResponse {
-    pub event_id: String,
-    pub trace_id: String,
+    /// Absent when sampling left the event out of storage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    /// Absent for a sampled-out event that only named a session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The event's trace is not sampled, so it was only counted
+    pub sampled_out: bool,
 }
 
 /// Bulk event ingestion response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BulkEventIngestionResponse {
     pub ingested: usize,
     pub failed: usize,
+    /// Events only counted because their trace is not sampled
+    pub sampled_out: usize,
     pub trace_ids: Vec<String>,
     pub errors: Vec<IngestionError>,
 }
 
 /// Ingestion error details
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct IngestionError {
     pub index: usize,
     pub error: String,
+    /// The failed event, with `return_failed_events`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<EventIngestionRequest>,
+    /// Where the failed event was kept, with `dead_letter`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter_id: Option<String>,
+}
+
+/// Dead letter list/replay query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterParams {
+    /// Most dead letters to return or replay, oldest first
+    pub limit: Option<usize>,
+}
+
+/// Dead letter list response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListDeadLettersResponse {
+    pub dead_letters: Vec<DeadLetter>,
+    pub count: usize,
+}
+
+/// Dead letter replay response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayDeadLettersResponse {
+    /// Events ingested and removed from the dead letter store
+    pub replayed: usize,
+    /// Events that failed again and stay in the store
+    pub failed: usize,
+    pub errors: Vec<DeadLetterError>,
+}
+
+/// A dead letter that failed to replay
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeadLetterError {
+    pub id: String,
+    pub error: String,
+}
+
+// ============================================================================
+// Audit Log
+// ============================================================================
+
+/// Audit log query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditParams {
+    /// Only records of changes to this entity, relation or schema namespace
+    pub target_id: Option<String>,
+    /// Most records to return, most recent first
+    pub limit: Option<usize>,
+}
+
+/// Audit log response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditResponse {
+    pub records: Vec<AuditRecord>,
+    pub count: usize,
+    pub limit: usize,
+}
+
+// ============================================================================
+// Analytics
+// ============================================================================
+
+/// Analytics query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyticsParams {
+    /// Restrict aggregation to a single agent
+    pub agent_id: Option<String>,
+}
+
+/// Aggregated event analytics response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyticsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    pub total_events: u64,
+    pub events_by_type: HashMap<String, u64>,
+    pub error_count: u64,
+    pub error_rate: f64,
+    pub token_usage: TokenUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_latency_ms: Option<f64>,
+    /// Fraction of traces stored, when ingestion sampling is on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling_rate: Option<f64>,
+    /// Events counted but not stored since startup; the totals above
+    /// cover stored events only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampled_out: Option<SampledOut>,
+}
+
+/// Cost estimate query parameters
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CostParams {
+    pub agent_id: Option<String>,
+    pub trace_id: Option<String>,
+    /// Only events at or after this time
+    #[serde(alias = "start")]
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only events before this time
+    #[serde(alias = "end")]
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Estimated spend of one model
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ModelCost {
+    /// `unknown` for events without a `model_id`
+    pub model_id: String,
+    pub events: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Absent when the model has no configured price
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+/// Estimated LLM spend, in the currency of the configured prices
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct CostResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Sum over priced models
+    pub total_cost: f64,
+    /// Most expensive first; unpriced models last
+    pub models: Vec<ModelCost>,
+    /// Events whose model has no configured price, so are not in `total_cost`
+    pub unpriced_events: u64,
+    /// Events reporting no token counts, which add nothing to the cost
+    pub events_without_tokens: u64,
+}
+
+/// Histogram request over a numeric event property
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct HistogramRequest {
+    /// Event property to bucket, e.g. `latency_ms` or `total_tokens`
+    pub property: String,
+    /// Bucket edges in increasing order; takes precedence over `bucket_count`
+    pub buckets: Option<Vec<f64>>,
+    /// Equal-width buckets between the smallest and largest value (default 10)
+    pub bucket_count: Option<usize>,
+    pub agent_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub event_type: Option<String>,
+    /// Only events at or after this time
+    pub start_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only events before this time
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Distribution of an event property
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistogramResponse {
+    pub property: String,
+    #[serde(flatten)]
+    pub histogram: Histogram,
+}
+
+/// Facet query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FacetsParams {
+    /// Table to aggregate: entity, relation, agent_event or agent_trace
+    pub table: ExportTable,
+    /// Field to group by, e.g. `event_type` or `properties.model_id`
+    pub field: String,
+    /// Most values to return (defaults to and is capped by the server's query limits)
+    pub limit: Option<usize>,
+}
+
+/// Distinct values of a field with their record counts, most common first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FacetsResponse {
+    pub table: ExportTable,
+    pub field: String,
+    pub values: Vec<FacetCount>,
+    /// Distinct values in all, including those beyond `limit`
+    pub distinct_count: u64,
+    /// Limit applied, after the server's default and maximum
+    pub limit: usize,
+}
+
+/// Time-series query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimeseriesParams {
+    /// Table to bucket (default agent_event): entity, relation, agent_event or agent_trace
+    #[serde(default = "default_timeseries_table")]
+    pub table: ExportTable,
+    /// Bucket width: a number and a unit among `s`, `m`, `h`, `d` and `w`, e.g. `15m` or `1h`
+    pub interval: String,
+    /// Start of the first bucket's interval (inclusive)
+    pub start: chrono::DateTime<chrono::Utc>,
+    /// End of the series (exclusive)
+    pub end: chrono::DateTime<chrono::Utc>,
+    /// Numeric property to sum and average per bucket, e.g. `latency_ms`
+    pub property: Option<String>,
+}
+
+fn default_timeseries_table() -> ExportTable {
+    ExportTable::AgentEvent
+}
+
+/// Record counts over consecutive fixed intervals, empty ones included
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TimeseriesResponse {
+    pub table: ExportTable,
+    /// Bucket width in seconds
+    pub interval_secs: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    pub buckets: Vec<TimeBucket>,
+    /// Records over all buckets
+    pub total: u64,
+}
+
+/// Near-duplicate detection query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DuplicatesParams {
+    pub entity_type: String,
+    /// Minimum cosine similarity for two entities to count as duplicates
+    #[serde(default = "default_duplicate_threshold")]
+    pub threshold: f32,
+    /// Most vectors to compare (default 1000, max 5000)
+    pub max_scan: Option<usize>,
+}
+
+fn default_duplicate_threshold() -> f32 {
+    0.97
+}
+
+/// Clusters of near-duplicate entity ids, largest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DuplicatesResponse {
+    pub entity_type: String,
+    pub threshold: f32,
+    /// Vectors compared
+    pub scanned: usize,
+    /// True when the collection held more vectors than were scanned
+    pub truncated: bool,
+    pub clusters: Vec<Vec<String>>,
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Export query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// Table to export: entity, relation, agent_event or agent_trace
+    pub table: ExportTable,
+    /// Records fetched from SurrealDB per chunk
+    pub batch_size: Option<usize>,
+}
+
+/// A single NDJSON export line
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExportRecord {
+    pub table: ExportTable,
+    #[schema(value_type = Object)]
+    pub record: JsonValue,
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// Import query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportParams {
+    /// Validate entities and relations against the loaded ontology
+    #[serde(default)]
+    pub validate: bool,
+}
+
+/// Import summary
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ImportError>,
+}
+
+/// A record that failed to import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportError {
+    /// 1-based line number in the NDJSON body
+    pub line: usize,
+    pub error: String,
+}
+
+// ============================================================================
+// Admin
+// ============================================================================
+
+/// Re-index query parameters
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReindexParams {
+    pub entity_type: String,
+    /// Recreate the collection if its dimension differs from the provider's,
+    /// or if it was created without room for sparse vectors
+    #[serde(default)]
+    pub force: bool,
+    /// Comma-separated named vectors to declare besides those the type's
+    /// entities carry, so entities can be created with them afterwards
+    pub vector_names: Option<String>,
+    /// Resume after this entity id (from a previous `next_cursor`)
+    pub cursor: Option<String>,
+    /// Entities embedded per batch
+    pub batch_size: Option<usize>,
+    /// Stop after this many entities; resume with `next_cursor`
+    pub limit: Option<usize>,
+}
+
+/// Re-index progress report
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReindexResponse {
+    pub entity_type: String,
+    pub provider: String,
+    pub dimension: u64,
+    pub collection_recreated: bool,
+    pub processed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// True once every entity of the type has been visited
+    pub completed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Point and entity counts for one vector collection
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollectionStats {
+    pub entity_type: String,
+    /// Points stored in the Qdrant collection
+    pub point_count: u64,
+    /// Live entities of this type in SurrealDB
+    pub entity_count: u64,
+    /// Points with no live entity behind them
+    pub orphaned_points: u64,
+    /// Live entities without a point, e.g. ones with no embeddable text
+    pub missing_vectors: u64,
+    /// True when every point has an entity and every entity has a point
+    pub consistent: bool,
+}
+
+/// Consistency report across all vector collections
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollectionStatsResponse {
+    pub collections: Vec<CollectionStats>,
+}
+
+/// Orphaned-vector cleanup query parameters
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GcVectorsParams {
+    /// Only clean this entity type's collection
+    pub entity_type: Option<String>,
+    /// Report orphans without deleting them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Cleanup result for one collection
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GcCollectionReport {
+    pub entity_type: String,
+    /// Points examined
+    pub scanned: u64,
+    /// Orphaned points deleted (or found, in a dry run)
+    pub removed: u64,
+}
+
+/// Orphaned-vector cleanup result
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GcVectorsResponse {
+    pub dry_run: bool,
+    pub scanned: u64,
+    pub removed: u64,
+    pub collections: Vec<GcCollectionReport>,
 }
@@ -1,5 +1,7 @@
 use crate::error::{Result, VectaDBError};
-use serde::Deserialize;
+use axum::http::{HeaderName, HeaderValue, Method};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
 #[derive(Debug, Clone, Deserialize)]
@@ -9,6 +11,15 @@ pub struct Config {
     pub embedding: EmbeddingConfig,
     pub api: ApiConfig,
     pub similarity: SimilarityConfig,
+    pub rate_limit: RateLimitConfig,
+    pub limits: LimitsConfig,
+    pub cors: CorsConfig,
+    pub tenancy: TenancyConfig,
+    pub idempotency: IdempotencyConfig,
+    pub audit: AuditConfig,
+    pub query: QueryConfig,
+    pub pricing: PricingConfig,
+    pub analytics: AnalyticsConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -21,15 +32,33 @@ pub struct DatabaseConfig {
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Serve the gRPC interface alongside the REST API
+    pub grpc_enabled: bool,
+    pub grpc_port: u16,
+    /// Seconds a request may take before it is answered with 504; 0 disables
+    pub request_timeout_secs: u64,
+    /// The same for bulk ingest, import, reindex and other long-running routes
+    pub long_request_timeout_secs: u64,
 }
 
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_LONG_REQUEST_TIMEOUT_SECS: u64 = 600;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SurrealDBConfig {
+    /// `ws://` or `wss://` connects over WebSocket; `http://`, `https://` or a bare `host:port` over HTTP
     pub endpoint: String,
     pub namespace: String,
     pub database: String,
     pub username: String,
     pub password: String,
+    /// Authenticated connections used round-robin
+    #[serde(default = "default_pool_size")]
+    pub pool_size: usize,
+}
+
+fn default_pool_size() -> usize {
+    4
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -37,6 +66,70 @@ pub struct QdrantConfig {
     pub url: String,
     pub api_key: Option<String>,
     pub collection_prefix: String,
+    /// Create a collection for every ontology entity type at startup
+    #[serde(default)]
+    pub warmup_collections: bool,
+    /// Storage settings for collections created from now on
+    #[serde(default)]
+    pub collection: CollectionSettings,
+}
+
+/// How VectaDB creates Qdrant collections. Existing collections keep the
+/// settings they were created with until they are re-created.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CollectionSettings {
+    /// Compress the vectors searched in RAM
+    #[serde(default)]
+    pub quantization: Quantization,
+    /// With quantization, keep the original vectors on disk and only the
+    /// quantized ones in RAM. Top results are rescored from the originals.
+    #[serde(default)]
+    pub quantization_originals_on_disk: bool,
+    /// Keep vectors on disk (memory-mapped) instead of RAM. Cold data stays
+    /// cheap to host, searches that miss the page cache read from disk.
+    #[serde(default)]
+    pub on_disk_vectors: bool,
+    /// Keep payloads on disk and load them only when results are returned
+    #[serde(default)]
+    pub on_disk_payload: bool,
+    /// HNSW edges per node; Qdrant's default (16) when unset
+    #[serde(default)]
+    pub hnsw_m: Option<u64>,
+    /// HNSW candidates considered while building the index; Qdrant's
+    /// default (100) when unset
+    #[serde(default)]
+    pub hnsw_ef_construct: Option<u64>,
+}
+
+/// Vector quantization: less memory per vector, at some cost in recall
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    /// Full-precision float32 vectors
+    #[default]
+    None,
+    /// int8 components: 4x smaller, with a recall loss of around 1%
+    Scalar,
+    /// Product quantization at 16x compression: much smaller, noticeably
+    /// lower recall and slower indexing
+    Product,
+    /// One bit per component: 32x smaller; only suits high-dimensional
+    /// models (1024+ dimensions) trained for it
+    Binary,
+}
+
+impl std::str::FromStr for Quantization {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "scalar" => Ok(Self::Scalar),
+            "product" => Ok(Self::Product),
+            "binary" => Ok(Self::Binary),
+            other => Err(format!("expected 'none', 'scalar', 'product' or 'binary', got '{}'", other)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -49,12 +142,72 @@ pub struct EmbeddingConfig {
     pub plugin_config_dir: String,
     #[serde(default)]
     pub fallback_to_local: bool,
+    /// Providers tried in order when the primary fails; all must produce
+    /// `dim`-dimensional vectors
+    #[serde(default)]
+    pub fallback_providers: Vec<String>,
+    /// Entity types embedded by a provider other than `provider`, e.g. code
+    /// snippets by a code model
+    #[serde(default)]
+    pub type_providers: HashMap<String, String>,
+    /// Probe-encode at startup and fail if the vectors don't have `dim` dimensions
+    #[serde(default = "default_verify_dim")]
+    pub verify_dim: bool,
+    /// Provider calls allowed in flight at once, however many requests are embedding
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Longest text, in characters, sent to the provider in one input; 0 for no limit
+    #[serde(default = "default_max_input_chars")]
+    pub max_input_chars: usize,
+    /// What to do with text longer than `max_input_chars`
+    #[serde(default)]
+    pub long_text_strategy: LongTextStrategy,
+    /// Scale every vector to unit length before it is stored or searched
+    /// with, for providers that don't return normalized embeddings
+    #[serde(default)]
+    pub normalize: bool,
+}
+
+/// How text over `max_input_chars` is embedded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LongTextStrategy {
+    /// Embed only the leading `max_input_chars` characters
+    #[default]
+    Truncate,
+    /// Embed every `max_input_chars` chunk and average the vectors, weighted
+    /// by chunk length
+    AverageChunks,
+}
+
+impl std::str::FromStr for LongTextStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "truncate" => Ok(Self::Truncate),
+            "average_chunks" => Ok(Self::AverageChunks),
+            other => Err(format!("expected 'truncate' or 'average_chunks', got '{}'", other)),
+        }
+    }
 }
 
 fn default_embedding_provider() -> String {
     "local".to_string()
 }
 
+fn default_verify_dim() -> bool {
+    true
+}
+
+fn default_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_max_input_chars() -> usize {
+    16_000
+}
+
 fn default_plugin_config_dir() -> String {
     "./config/embeddings".to_string()
 }
@@ -71,6 +224,233 @@ pub struct SimilarityConfig {
     pub limit: usize,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    /// Maximum request body size in bytes for JSON endpoints, and line length for import
+    pub max_body_bytes: usize,
+    /// Maximum body size in bytes for the streaming import endpoint
+    pub max_import_bytes: usize,
+    /// Maximum number of items accepted by a single bulk request
+    pub max_batch_size: usize,
+    /// Maximum number of entities returned by a subgraph extraction
+    pub max_subgraph_nodes: usize,
+    /// Maximum nesting of fields in a GraphQL query
+    pub max_graphql_depth: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 10 * 1024 * 1024,
+            max_import_bytes: 1024 * 1024 * 1024,
+            max_batch_size: 1000,
+            max_subgraph_nodes: 500,
+            max_graphql_depth: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API; empty disables cross-origin access, `*` allows any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: split_list(DEFAULT_CORS_METHODS),
+            allowed_headers: split_list(DEFAULT_CORS_HEADERS),
+            allow_credentials: false,
+        }
+    }
+}
+
+const DEFAULT_CORS_METHODS: &str = "GET,POST,PUT,PATCH,DELETE,OPTIONS";
+const DEFAULT_CORS_HEADERS: &str = "content-type,authorization,x-api-key,x-tenant-id,idempotency-key,x-request-id";
+
+/// Parse `Type=provider` pairs, e.g. `CodeSnippet=openai,Document=cohere`
+fn parse_type_providers(value: &str) -> Result<HashMap<String, String>> {
+    let mut routes = HashMap::new();
+    for entry in split_list(value) {
+        let invalid = || VectaDBError::Config(format!("Invalid EMBEDDING_TYPE_PROVIDERS entry '{}'", entry));
+        let (entity_type, provider) = entry.split_once('=').ok_or_else(invalid)?;
+        let (entity_type, provider) = (entity_type.trim(), provider.trim());
+        if entity_type.is_empty() || provider.is_empty() {
+            return Err(invalid());
+        }
+        routes.insert(entity_type.to_string(), provider.to_string());
+    }
+    Ok(routes)
+}
+
+/// Split a comma-separated setting into trimmed, non-empty items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Price of a model's tokens, per 1000
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_1k + output_tokens as f64 * self.output_per_1k) / 1000.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PricingConfig {
+    /// Token prices keyed by the `model_id` events report
+    pub models: HashMap<String, ModelPrice>,
+}
+
+impl PricingConfig {
+    /// Parse `model_id=input_per_1k:output_per_1k` entries, comma-separated
+    fn parse(value: &str) -> Result<Self> {
+        let mut models = HashMap::new();
+        for entry in split_list(value) {
+            let invalid = || VectaDBError::Config(format!("Invalid MODEL_PRICING entry '{}'", entry));
+            let (model_id, prices) = entry.rsplit_once('=').ok_or_else(invalid)?;
+            let (input, output) = prices.split_once(':').ok_or_else(invalid)?;
+            let price = ModelPrice {
+                input_per_1k: input.trim().parse().map_err(|_| invalid())?,
+                output_per_1k: output.trim().parse().map_err(|_| invalid())?,
+            };
+            if model_id.trim().is_empty() || price.input_per_1k < 0.0 || price.output_per_1k < 0.0 {
+                return Err(invalid());
+            }
+            models.insert(model_id.trim().to_string(), price);
+        }
+        Ok(Self { models })
+    }
+}
+
+/// Analytics configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Enable metrics collection
+    pub enabled: bool,
+
+    /// Events and traces older than this many days are purged (0 keeps them forever)
+    pub retention_days: u32,
+
+    /// Seconds between retention runs
+    pub retention_interval_secs: u64,
+
+    /// Records deleted per batch during a retention run
+    pub retention_batch_size: usize,
+
+    /// Sampling rate (0.0 to 1.0): the fraction of traces whose events are
+    /// stored at ingestion. Events of other traces are only counted.
+    pub sampling_rate: f64,
+
+    /// Anomaly detection threshold
+    pub anomaly_threshold: f64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: 30,
+            retention_interval_secs: 3600,
+            retention_batch_size: 500,
+            sampling_rate: 1.0,
+            anomaly_threshold: 2.0, // 2 standard deviations
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Sustained requests per second allowed per client
+    pub requests_per_second: f64,
+    /// Requests a client may make in a burst above the sustained rate
+    pub burst: u32,
+    /// Maximum number of clients tracked before old entries are evicted
+    pub max_keys: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenancyConfig {
+    /// Require an `X-Tenant-ID` header and scope all data to that tenant
+    pub enabled: bool,
+    /// Tenant IDs that may be served; the tenant header is not authenticated,
+    /// so requests for any other tenant are refused
+    pub allowed_tenants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    /// Seconds a create request's `Idempotency-Key` is remembered
+    pub ttl_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { ttl_secs: 3600 }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditConfig {
+    /// Record every create, update and delete in the `audit` table
+    pub enabled: bool,
+    /// Audit records waiting to be written before new ones are dropped
+    pub queue_size: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            queue_size: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryConfig {
+    /// Seconds a hybrid query result is cached (0 disables the cache)
+    pub query_cache_ttl_secs: u64,
+    /// Results returned by query, search and list endpoints when no `limit` is given
+    pub default_limit: usize,
+    /// Ceiling on any requested `limit`
+    pub max_limit: usize,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            query_cache_ttl_secs: 0,
+            default_limit: 10,
+            max_limit: 1000,
+        }
+    }
+}
+
+impl QueryConfig {
+    /// The limit to apply for a requested one: the default when absent,
+    /// otherwise clamped to between 1 and the maximum
+    pub fn limit(&self, requested: Option<usize>) -> usize {
+        let max = self.max_limit.max(1);
+        requested.unwrap_or(self.default_limit).clamp(1, max)
+    }
+}
+
 impl Config {
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
@@ -82,6 +462,22 @@ impl Config {
                     .unwrap_or_else(|_| "8080".to_string())
                     .parse()
                     .map_err(|e| VectaDBError::Config(format!("Invalid SERVER_PORT: {}", e)))?,
+                grpc_enabled: env::var("GRPC_ENABLED")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                grpc_port: env::var("GRPC_PORT")
+                    .unwrap_or_else(|_| "50051".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid GRPC_PORT: {}", e)))?,
+                request_timeout_secs: env::var("REQUEST_TIMEOUT_SECS")
+                    .unwrap_or_else(|_| DEFAULT_REQUEST_TIMEOUT_SECS.to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid REQUEST_TIMEOUT_SECS: {}", e)))?,
+                long_request_timeout_secs: env::var("LONG_REQUEST_TIMEOUT_SECS")
+                    .unwrap_or_else(|_| DEFAULT_LONG_REQUEST_TIMEOUT_SECS.to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid LONG_REQUEST_TIMEOUT_SECS: {}", e)))?,
             },
             database: DatabaseConfig {
                 surrealdb: SurrealDBConfig {
@@ -95,6 +491,10 @@ impl Config {
                         .unwrap_or_else(|_| "root".to_string()),
                     password: env::var("SURREAL_PASS")
                         .unwrap_or_else(|_| "root".to_string()),
+                    pool_size: env::var("SURREAL_POOL_SIZE")
+                        .unwrap_or_else(|_| default_pool_size().to_string())
+                        .parse()
+                        .map_err(|e| VectaDBError::Config(format!("Invalid SURREAL_POOL_SIZE: {}", e)))?,
                 },
                 qdrant: QdrantConfig {
                     url: env::var("QDRANT_URL")
@@ -102,6 +502,38 @@ impl Config {
                     api_key: env::var("QDRANT_API_KEY").ok(),
                     collection_prefix: env::var("QDRANT_COLLECTION_PREFIX")
                         .unwrap_or_else(|_| "vectadb_".to_string()),
+                    warmup_collections: env::var("QDRANT_WARMUP_COLLECTIONS")
+                        .unwrap_or_else(|_| "false".to_string())
+                        .parse()
+                        .unwrap_or(false),
+                    collection: CollectionSettings {
+                        quantization: env::var("QDRANT_QUANTIZATION")
+                            .unwrap_or_else(|_| "none".to_string())
+                            .parse()
+                            .map_err(|e| VectaDBError::Config(format!("Invalid QDRANT_QUANTIZATION: {}", e)))?,
+                        quantization_originals_on_disk: env::var("QDRANT_QUANTIZATION_ORIGINALS_ON_DISK")
+                            .unwrap_or_else(|_| "false".to_string())
+                            .parse()
+                            .unwrap_or(false),
+                        on_disk_vectors: env::var("QDRANT_ON_DISK_VECTORS")
+                            .unwrap_or_else(|_| "false".to_string())
+                            .parse()
+                            .unwrap_or(false),
+                        on_disk_payload: env::var("QDRANT_ON_DISK_PAYLOAD")
+                            .unwrap_or_else(|_| "false".to_string())
+                            .parse()
+                            .unwrap_or(false),
+                        hnsw_m: env::var("QDRANT_HNSW_M")
+                            .ok()
+                            .map(|v| v.parse())
+                            .transpose()
+                            .map_err(|e| VectaDBError::Config(format!("Invalid QDRANT_HNSW_M: {}", e)))?,
+                        hnsw_ef_construct: env::var("QDRANT_HNSW_EF_CONSTRUCT")
+                            .ok()
+                            .map(|v| v.parse())
+                            .transpose()
+                            .map_err(|e| VectaDBError::Config(format!("Invalid QDRANT_HNSW_EF_CONSTRUCT: {}", e)))?,
+                    },
                 },
             },
             embedding: EmbeddingConfig {
@@ -119,6 +551,30 @@ impl Config {
                     .unwrap_or_else(|_| "false".to_string())
                     .parse()
                     .unwrap_or(false),
+                fallback_providers: split_list(&env::var("EMBEDDING_FALLBACK_PROVIDERS").unwrap_or_default()),
+                type_providers: parse_type_providers(&env::var("EMBEDDING_TYPE_PROVIDERS").unwrap_or_default())?,
+                verify_dim: env::var("EMBEDDING_VERIFY_DIM")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                max_concurrent_requests: env::var("EMBEDDING_MAX_CONCURRENT_REQUESTS")
+                    .unwrap_or_else(|_| default_max_concurrent_requests().to_string())
+                    .parse()
+                    .map_err(|e| {
+                        VectaDBError::Config(format!("Invalid EMBEDDING_MAX_CONCURRENT_REQUESTS: {}", e))
+                    })?,
+                max_input_chars: env::var("EMBEDDING_MAX_INPUT_CHARS")
+                    .unwrap_or_else(|_| default_max_input_chars().to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid EMBEDDING_MAX_INPUT_CHARS: {}", e)))?,
+                long_text_strategy: env::var("EMBEDDING_LONG_TEXT_STRATEGY")
+                    .unwrap_or_else(|_| "truncate".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid EMBEDDING_LONG_TEXT_STRATEGY: {}", e)))?,
+                normalize: env::var("EMBEDDING_NORMALIZE")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid EMBEDDING_NORMALIZE: {}", e)))?,
             },
             api: ApiConfig {
                 key: env::var("API_KEY")
@@ -136,6 +592,250 @@ impl Config {
                     .parse()
                     .map_err(|e| VectaDBError::Config(format!("Invalid SIMILARITY_LIMIT: {}", e)))?,
             },
+            rate_limit: RateLimitConfig {
+                enabled: env::var("RATE_LIMIT_ENABLED")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .unwrap_or(true),
+                requests_per_second: env::var("RATE_LIMIT_RPS")
+                    .unwrap_or_else(|_| "100".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid RATE_LIMIT_RPS: {}", e)))?,
+                burst: env::var("RATE_LIMIT_BURST")
+                    .unwrap_or_else(|_| "200".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid RATE_LIMIT_BURST: {}", e)))?,
+                max_keys: env::var("RATE_LIMIT_MAX_KEYS")
+                    .unwrap_or_else(|_| "10000".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid RATE_LIMIT_MAX_KEYS: {}", e)))?,
+            },
+            limits: LimitsConfig {
+                max_body_bytes: env::var("MAX_BODY_BYTES")
+                    .unwrap_or_else(|_| "10485760".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid MAX_BODY_BYTES: {}", e)))?,
+                max_import_bytes: env::var("MAX_IMPORT_BYTES")
+                    .unwrap_or_else(|_| "1073741824".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid MAX_IMPORT_BYTES: {}", e)))?,
+                max_batch_size: env::var("MAX_BATCH_SIZE")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid MAX_BATCH_SIZE: {}", e)))?,
+                max_subgraph_nodes: env::var("MAX_SUBGRAPH_NODES")
+                    .unwrap_or_else(|_| "500".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid MAX_SUBGRAPH_NODES: {}", e)))?,
+                max_graphql_depth: env::var("MAX_GRAPHQL_DEPTH")
+                    .unwrap_or_else(|_| "12".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid MAX_GRAPHQL_DEPTH: {}", e)))?,
+            },
+            cors: CorsConfig::from_env()?,
+            pricing: PricingConfig::parse(&env::var("MODEL_PRICING").unwrap_or_default())?,
+            analytics: AnalyticsConfig::from_env()?,
+            tenancy: TenancyConfig::from_env()?,
+            idempotency: IdempotencyConfig {
+                ttl_secs: env::var("IDEMPOTENCY_TTL_SECS")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid IDEMPOTENCY_TTL_SECS: {}", e)))?,
+            },
+            audit: AuditConfig {
+                enabled: env::var("AUDIT_ENABLED")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .unwrap_or(false),
+                queue_size: env::var("AUDIT_QUEUE_SIZE")
+                    .unwrap_or_else(|_| "1024".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid AUDIT_QUEUE_SIZE: {}", e)))?,
+            },
+            query: QueryConfig {
+                query_cache_ttl_secs: env::var("QUERY_CACHE_TTL_SECS")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid QUERY_CACHE_TTL_SECS: {}", e)))?,
+                default_limit: env::var("QUERY_DEFAULT_LIMIT")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid QUERY_DEFAULT_LIMIT: {}", e)))?,
+                max_limit: env::var("QUERY_MAX_LIMIT")
+                    .unwrap_or_else(|_| "1000".to_string())
+                    .parse()
+                    .map_err(|e| VectaDBError::Config(format!("Invalid QUERY_MAX_LIMIT: {}", e)))?,
+            },
         })
     }
 }
+
+impl AnalyticsConfig {
+    fn from_env() -> Result<Self> {
+        let config = AnalyticsConfig {
+            enabled: env::var("ANALYTICS_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            retention_days: env::var("ANALYTICS_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid ANALYTICS_RETENTION_DAYS: {}", e)))?,
+            retention_interval_secs: env::var("ANALYTICS_RETENTION_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid ANALYTICS_RETENTION_INTERVAL_SECS: {}", e)))?,
+            retention_batch_size: env::var("ANALYTICS_RETENTION_BATCH_SIZE")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid ANALYTICS_RETENTION_BATCH_SIZE: {}", e)))?,
+            sampling_rate: env::var("ANALYTICS_SAMPLING_RATE")
+                .unwrap_or_else(|_| "1.0".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid ANALYTICS_SAMPLING_RATE: {}", e)))?,
+            anomaly_threshold: env::var("ANALYTICS_ANOMALY_THRESHOLD")
+                .unwrap_or_else(|_| "2.0".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid ANALYTICS_ANOMALY_THRESHOLD: {}", e)))?,
+        };
+
+        if !(0.0..=1.0).contains(&config.sampling_rate) {
+            return Err(VectaDBError::Config(
+                "ANALYTICS_SAMPLING_RATE must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        if config.retention_interval_secs == 0 || config.retention_batch_size == 0 {
+            return Err(VectaDBError::Config(
+                "ANALYTICS_RETENTION_INTERVAL_SECS and ANALYTICS_RETENTION_BATCH_SIZE must be positive".to_string(),
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+impl TenancyConfig {
+    fn from_env() -> Result<Self> {
+        let config = TenancyConfig {
+            enabled: env::var("MULTI_TENANCY_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            allowed_tenants: split_list(&env::var("ALLOWED_TENANTS").unwrap_or_default()),
+        };
+
+        if config.enabled && config.allowed_tenants.is_empty() {
+            return Err(VectaDBError::Config(
+                "MULTI_TENANCY_ENABLED requires ALLOWED_TENANTS to list the tenants to serve".to_string(),
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+impl CorsConfig {
+    fn from_env() -> Result<Self> {
+        let config = CorsConfig {
+            allowed_origins: split_list(&env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default()),
+            allowed_methods: split_list(
+                &env::var("CORS_ALLOWED_METHODS").unwrap_or_else(|_| DEFAULT_CORS_METHODS.to_string()),
+            ),
+            allowed_headers: split_list(
+                &env::var("CORS_ALLOWED_HEADERS").unwrap_or_else(|_| DEFAULT_CORS_HEADERS.to_string()),
+            ),
+            allow_credentials: env::var("CORS_ALLOW_CREDENTIALS")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|e| VectaDBError::Config(format!("Invalid CORS_ALLOW_CREDENTIALS: {}", e)))?,
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject settings the CORS layer would drop silently or panic on at startup
+    fn validate(&self) -> Result<()> {
+        // Browsers reject credentialed responses with wildcards, and tower-http panics on them
+        if self.allow_credentials && self.allowed_origins.iter().any(|o| o == "*") {
+            return Err(VectaDBError::Config(
+                "CORS_ALLOW_CREDENTIALS cannot be used with CORS_ALLOWED_ORIGINS=*".to_string(),
+            ));
+        }
+        if self.allow_credentials && self.allowed_headers.iter().any(|h| h == "*") {
+            return Err(VectaDBError::Config(
+                "CORS_ALLOW_CREDENTIALS cannot be used with CORS_ALLOWED_HEADERS=*".to_string(),
+            ));
+        }
+
+        for origin in self.allowed_origins.iter().filter(|o| *o != "*") {
+            origin
+                .parse::<HeaderValue>()
+                .map_err(|_| VectaDBError::Config(format!("Invalid CORS origin: {}", origin)))?;
+        }
+        for method in &self.allowed_methods {
+            method
+                .to_uppercase()
+                .parse::<Method>()
+                .map_err(|_| VectaDBError::Config(format!("Invalid CORS method: {}", method)))?;
+        }
+        for header in self.allowed_headers.iter().filter(|h| *h != "*") {
+            header
+                .parse::<HeaderName>()
+                .map_err(|_| VectaDBError::Config(format!("Invalid CORS header: {}", header)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_pricing() {
+        let pricing = PricingConfig::parse(
+            "anthropic.claude-3-haiku-20240307-v1:0=0.00025:0.00125, amazon.titan-text-express-v1 = 0.0002:0.0006",
+        )
+        .unwrap();
+        assert_eq!(
+            pricing.models["anthropic.claude-3-haiku-20240307-v1:0"],
+            ModelPrice { input_per_1k: 0.00025, output_per_1k: 0.00125 }
+        );
+        assert_eq!(pricing.models["amazon.titan-text-express-v1"].output_per_1k, 0.0006);
+
+        assert!(PricingConfig::parse("").unwrap().models.is_empty());
+        assert!(PricingConfig::parse("haiku=0.25").is_err());
+        assert!(PricingConfig::parse("haiku=-1:2").is_err());
+    }
+
+    #[test]
+    fn test_parse_type_providers() {
+        let routes = parse_type_providers("CodeSnippet=openai, Document = cohere").unwrap();
+        assert_eq!(routes["CodeSnippet"], "openai");
+        assert_eq!(routes["Document"], "cohere");
+
+        assert!(parse_type_providers("").unwrap().is_empty());
+        assert!(parse_type_providers("CodeSnippet").is_err());
+        assert!(parse_type_providers("=openai").is_err());
+    }
+
+    #[test]
+    fn test_cors_validation() {
+        let cors = |origins: &[&str], headers: &[&str], allow_credentials: bool| CorsConfig {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allowed_headers: headers.iter().map(|h| h.to_string()).collect(),
+            allow_credentials,
+            ..CorsConfig::default()
+        };
+
+        assert!(cors(&["https://app.example.com"], &["content-type"], true).validate().is_ok());
+        assert!(cors(&["*"], &["*"], false).validate().is_ok());
+
+        assert!(cors(&["*"], &["content-type"], true).validate().is_err());
+        assert!(cors(&["https://app.example.com"], &["*"], true).validate().is_err());
+        assert!(cors(&["https://app.example.com\n"], &["content-type"], false).validate().is_err());
+        assert!(cors(&["https://app.example.com"], &["bad header"], false).validate().is_err());
+    }
+}
@@ -0,0 +1,42 @@
+// Cached embedding provenance of vector collections
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::db::SurrealDBClient;
+use crate::models::EmbeddingMetadata;
+
+/// Collection metadata read through from SurrealDB. Metadata is only
+/// written when a collection is created or re-indexed, so entries (including
+/// "no metadata recorded") are kept until this cache writes them again.
+#[derive(Default)]
+pub struct CollectionMetaCache {
+    entries: RwLock<HashMap<String, Option<EmbeddingMetadata>>>,
+}
+
+impl CollectionMetaCache {
+    /// Metadata of `collection`, read from SurrealDB the first time only
+    pub async fn get(&self, surreal: &SurrealDBClient, collection: &str) -> Result<Option<EmbeddingMetadata>> {
+        if let Some(meta) = self.entries.read().unwrap_or_else(|e| e.into_inner()).get(collection) {
+            return Ok(meta.clone());
+        }
+
+        let meta = surreal.get_collection_meta(collection).await?;
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(collection.to_string(), meta.clone());
+        Ok(meta)
+    }
+
+    /// Persist `meta` and serve it from now on
+    pub async fn store(&self, surreal: &SurrealDBClient, meta: EmbeddingMetadata) -> Result<()> {
+        surreal.store_collection_meta(&meta).await?;
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(meta.id.clone(), Some(meta));
+        Ok(())
+    }
+}
@@ -3,7 +3,10 @@
 pub mod surrealdb_client;
 pub mod qdrant_client;
 pub mod types;
+pub mod retention;
+pub mod collection_meta;
 
 pub use surrealdb_client::SurrealDBClient;
-pub use qdrant_client::QdrantClient;
+pub use collection_meta::CollectionMetaCache;
+pub use qdrant_client::{is_valid_vector_name, QdrantClient, EVENTS_COLLECTION};
 pub use types::*;
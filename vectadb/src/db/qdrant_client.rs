@@ -2,19 +2,85 @@
 
 use anyhow::{Context, Result};
 use qdrant_client::Qdrant;
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream};
 use qdrant_client::qdrant::{
-    vectors_config::Config, CreateCollection, Distance, PointStruct, SearchPoints,
-    VectorParams, VectorsConfig,
+    point_id::PointIdOptions, quantization_config, vector_output::Vector, vectors_config::Config,
+    BinaryQuantization, CompressionRatio, Condition, CreateCollection, Distance, Filter, Fusion, HnswConfigDiff,
+    NamedVectors, PointId, PointStruct, PrefetchQuery, ProductQuantization, QuantizationConfig, QuantizationType,
+    Query, QueryPoints, Range, RetrievedPoint, ScalarQuantization, ScrollPoints, SearchPoints, SparseVectorConfig,
+    SparseVectorParams, VectorInput, VectorParams, VectorParamsMap, VectorsConfig,
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::RwLock;
 use tracing::{debug, info, warn};
 
-use crate::config::QdrantConfig;
+use crate::config::{CollectionSettings, Quantization, QdrantConfig};
+use crate::db::{TimeRange, VectorPoint};
+use crate::error::VectaDBError;
+
+/// Collection for agent event vectors, prefixed like any entity type.
+/// Its points are backed by `agent_event` records rather than entities.
+pub const EVENTS_COLLECTION: &str = "agent_events";
+
+/// Name of the sparse vector stored alongside an entity's unnamed dense vector
+pub const SPARSE_VECTOR_NAME: &str = "sparse";
+
+/// Whether `name` can name a vector: ASCII letters, digits, `_` and `-`,
+/// other than the sparse vector's name
+pub fn is_valid_vector_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != SPARSE_VECTOR_NAME
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Maps entity types to Qdrant collection names under a deployment-wide prefix,
+/// so deployments sharing one Qdrant instance never touch each other's collections
+#[derive(Debug, Clone)]
+pub struct CollectionNaming {
+    prefix: String,
+}
+
+impl CollectionNaming {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Collection holding the vectors of an entity type
+    pub fn collection(&self, entity_type: &str) -> String {
+        format!("{}{}", self.prefix, entity_type)
+    }
+
+    /// Entity type stored in a collection, or None if the collection has another prefix
+    pub fn entity_type<'a>(&self, collection: &'a str) -> Option<&'a str> {
+        collection.strip_prefix(&self.prefix)
+    }
+}
+
+/// Vector layout of an existing collection, fixed when the collection is created
+#[derive(Debug, Clone, PartialEq)]
+struct CollectionLayout {
+    /// Size of the unnamed dense vector, which named vectors share
+    dimension: Option<u64>,
+    /// Whether points can carry a sparse vector
+    sparse: bool,
+    /// Named dense vectors points can carry
+    named: BTreeSet<String>,
+}
 
 /// Qdrant client wrapper for vector operations
 pub struct QdrantClient {
     client: Qdrant,
-    collection_prefix: String,
+    naming: CollectionNaming,
+    settings: CollectionSettings,
+    /// Layouts of collections known to exist, so writes don't look them up
+    /// every time. Entries are dropped when this client creates or deletes
+    /// the collection, or an upsert into it fails.
+    layouts: RwLock<HashMap<String, CollectionLayout>>,
 }
 
 impl QdrantClient {
@@ -37,13 +103,15 @@ impl QdrantClient {
 
         Ok(Self {
             client,
-            collection_prefix: config.collection_prefix.clone(),
+            naming: CollectionNaming::new(config.collection_prefix.clone()),
+            settings: config.collection.clone(),
+            layouts: RwLock::new(HashMap::new()),
         })
     }
 
     /// Get collection name for an entity type
     fn collection_name(&self, entity_type: &str) -> String {
-        format!("{}{}", self.collection_prefix, entity_type)
+        self.naming.collection(entity_type)
     }
 
     /// Check if Qdrant is healthy
@@ -61,20 +129,44 @@ impl QdrantClient {
     // Collection Management
     // ============================================================================
 
-    /// Create a collection for an entity type
+    /// Create a collection for an entity type, unless it already exists
     pub async fn create_collection(
         &self,
         entity_type: &str,
         vector_size: u64,
     ) -> Result<()> {
+        self.ensure_collection(entity_type, vector_size).await.map(|_| ())
+    }
+
+    /// Create a collection for an entity type if it is missing, returning
+    /// whether this call created it. Safe to race: when concurrent callers
+    /// both try to create the collection, the loser's "already exists" error
+    /// counts as success, so exactly one caller sees `true`.
+    pub async fn ensure_collection(&self, entity_type: &str, vector_size: u64) -> Result<bool> {
+        self.ensure_collection_with_named(entity_type, vector_size, &BTreeSet::new())
+            .await
+    }
+
+    /// Like `ensure_collection`, also declaring named vectors of the same
+    /// size. Qdrant fixes a collection's vector names when it is created, so
+    /// they only apply when this call creates the collection.
+    pub async fn ensure_collection_with_named(
+        &self,
+        entity_type: &str,
+        vector_size: u64,
+        vector_names: &BTreeSet<String>,
+    ) -> Result<bool> {
         let collection_name = self.collection_name(entity_type);
-        debug!("Creating Qdrant collection: {}", collection_name);
+
+        if self.cached_layout(entity_type).is_some() {
+            return Ok(false);
+        }
 
         // Check if collection already exists
         match self.client.collection_exists(&collection_name).await {
             Ok(true) => {
                 debug!("Collection {} already exists", collection_name);
-                return Ok(());
+                return Ok(false);
             }
             Ok(false) => {}
             Err(e) => {
@@ -82,26 +174,22 @@ impl QdrantClient {
             }
         }
 
-        // Create collection with cosine distance
-        let create_collection = CreateCollection {
-            collection_name: collection_name.clone(),
-            vectors_config: Some(VectorsConfig {
-                config: Some(Config::Params(VectorParams {
-                    size: vector_size,
-                    distance: Distance::Cosine.into(),
-                    ..Default::default()
-                })),
-            }),
-            ..Default::default()
-        };
+        debug!("Creating Qdrant collection: {}", collection_name);
 
-        self.client
-            .create_collection(create_collection)
-            .await
-            .context(format!("Failed to create collection {}", collection_name))?;
+        let create_collection =
+            create_collection_request(&collection_name, vector_size, vector_names, &self.settings);
+        if let Err(e) = self.client.create_collection(create_collection).await {
+            // Another caller created it between the check and the create
+            if self.client.collection_exists(&collection_name).await.unwrap_or(false) {
+                debug!("Collection {} was created concurrently", collection_name);
+                return Ok(false);
+            }
+            return Err(e).context(format!("Failed to create collection {}", collection_name));
+        }
 
+        self.forget_layout(entity_type);
         info!("Created Qdrant collection: {}", collection_name);
-        Ok(())
+        Ok(true)
     }
 
     /// Delete a collection
@@ -113,6 +201,7 @@ impl QdrantClient {
             .delete_collection(collection_name.clone())
             .await
             .context(format!("Failed to delete collection {}", collection_name))?;
+        self.forget_layout(entity_type);
 
         info!("Deleted Qdrant collection: {}", collection_name);
         Ok(())
@@ -120,6 +209,10 @@ impl QdrantClient {
 
     /// Check if a collection exists
     pub async fn collection_exists(&self, entity_type: &str) -> Result<bool> {
+        if self.cached_layout(entity_type).is_some() {
+            return Ok(true);
+        }
+
         let collection_name = self.collection_name(entity_type);
         self.client
             .collection_exists(collection_name)
@@ -127,26 +220,208 @@ impl QdrantClient {
             .context("Failed to check collection existence")
     }
 
+    /// Get the vector dimension of an existing collection, or None if it does not exist
+    pub async fn collection_dimension(&self, entity_type: &str) -> Result<Option<u64>> {
+        Ok(self.collection_layout(entity_type).await?.and_then(|layout| layout.dimension))
+    }
+
+    /// Whether a collection can hold sparse vectors, or None if it does not
+    /// exist. Collections created before sparse vector support can't, and
+    /// have to be recreated.
+    pub async fn collection_supports_sparse(&self, entity_type: &str) -> Result<Option<bool>> {
+        Ok(self.collection_layout(entity_type).await?.map(|layout| layout.sparse))
+    }
+
+    /// Named vectors of a collection, or None if it does not exist
+    pub async fn collection_vector_names(&self, entity_type: &str) -> Result<Option<BTreeSet<String>>> {
+        Ok(self.collection_layout(entity_type).await?.map(|layout| layout.named))
+    }
+
+    /// Fail with a dimension mismatch if an existing collection can't hold vectors of `size`
+    pub async fn check_vector_size(&self, entity_type: &str, size: usize) -> Result<()> {
+        check_dimension(self.collection_dimension(entity_type).await?, size)?;
+        Ok(())
+    }
+
+    /// Layout of a collection, or None if it does not exist. Looked up in
+    /// Qdrant only the first time a collection is seen.
+    async fn collection_layout(&self, entity_type: &str) -> Result<Option<CollectionLayout>> {
+        if let Some(layout) = self.cached_layout(entity_type) {
+            return Ok(Some(layout));
+        }
+        if !self.collection_exists(entity_type).await? {
+            return Ok(None);
+        }
+
+        let collection_name = self.collection_name(entity_type);
+        let info = self
+            .client
+            .collection_info(collection_name.clone())
+            .await
+            .context(format!("Failed to get collection info for {}", collection_name))?;
+
+        let params = info.result.and_then(|r| r.config).and_then(|c| c.params);
+        let vectors = params
+            .as_ref()
+            .and_then(|p| p.vectors_config.as_ref())
+            .and_then(|v| v.config.as_ref());
+        // Collections with named vectors keep the unnamed one under ""
+        let (dimension, named) = match vectors {
+            Some(Config::Params(params)) => (Some(params.size), BTreeSet::new()),
+            Some(Config::ParamsMap(map)) => (
+                map.map.get("").map(|params| params.size),
+                map.map.keys().filter(|name| !name.is_empty()).cloned().collect(),
+            ),
+            None => (None, BTreeSet::new()),
+        };
+        let layout = CollectionLayout {
+            dimension,
+            sparse: params
+                .as_ref()
+                .and_then(|p| p.sparse_vectors_config.as_ref())
+                .is_some_and(|sparse| sparse.map.contains_key(SPARSE_VECTOR_NAME)),
+            named,
+        };
+
+        self.layouts
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(entity_type.to_string(), layout.clone());
+        Ok(Some(layout))
+    }
+
+    fn cached_layout(&self, entity_type: &str) -> Option<CollectionLayout> {
+        self.layouts
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(entity_type)
+            .cloned()
+    }
+
+    fn forget_layout(&self, entity_type: &str) {
+        self.layouts
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(entity_type);
+    }
+
+    /// Entity types that have a collection under this client's prefix
+    pub async fn list_entity_collections(&self) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .list_collections()
+            .await
+            .context("Failed to list collections")?;
+
+        let mut entity_types: Vec<String> = response
+            .collections
+            .into_iter()
+            .filter_map(|c| self.naming.entity_type(&c.name).map(str::to_string))
+            .collect();
+        entity_types.sort();
+        Ok(entity_types)
+    }
+
+    /// Iterate over every point in a collection, `batch_size` points at a time.
+    /// Payloads are always returned; vectors only when `with_vectors` is set.
+    pub fn scroll(
+        &self,
+        entity_type: &str,
+        batch_size: usize,
+        with_vectors: bool,
+    ) -> impl Stream<Item = Result<Vec<VectorPoint>>> + '_ {
+        let collection_name = self.collection_name(entity_type);
+        let limit = batch_size.clamp(1, u32::MAX as usize) as u32;
+
+        // State is the next page offset; None once the last page has been read
+        stream::try_unfold(Some(None::<PointId>), move |offset| {
+            let collection_name = collection_name.clone();
+            async move {
+                let Some(offset) = offset else {
+                    return Ok(None);
+                };
+
+                let response = self
+                    .client
+                    .scroll(ScrollPoints {
+                        collection_name: collection_name.clone(),
+                        offset,
+                        limit: Some(limit),
+                        with_payload: Some(true.into()),
+                        with_vectors: Some(with_vectors.into()),
+                        ..Default::default()
+                    })
+                    .await
+                    .context(format!("Failed to scroll collection {}", collection_name))?;
+
+                if response.result.is_empty() {
+                    return Ok(None);
+                }
+
+                debug!("Scrolled {} points from {}", response.result.len(), collection_name);
+                let points = response.result.into_iter().filter_map(vector_point).collect();
+                Ok(Some((points, response.next_page_offset.map(Some))))
+            }
+        })
+    }
+
     // ============================================================================
     // Vector Operations
     // ============================================================================
 
-    /// Upsert an embedding for an entity
+    /// Upsert an embedding for an entity, stamped with the time used by range filters
     pub async fn upsert_embedding(
         &self,
         entity_type: &str,
         entity_id: &str,
         embedding: Vec<f32>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        self.upsert_vectors(
+            entity_type,
+            entity_id,
+            Some(embedding),
+            &HashMap::new(),
+            &HashMap::new(),
+            timestamp,
+        )
+        .await
+    }
+
+    /// Upsert an entity's embedding together with its sparse vector of term
+    /// weights and its named vectors. All are written as one point, so a
+    /// point written without terms or a named vector loses the one it had.
+    pub async fn upsert_vectors(
+        &self,
+        entity_type: &str,
+        entity_id: &str,
+        embedding: Option<Vec<f32>>,
+        sparse_terms: &HashMap<String, f32>,
+        named: &HashMap<String, Vec<f32>>,
+        timestamp: DateTime<Utc>,
     ) -> Result<()> {
         let collection_name = self.collection_name(entity_type);
         debug!("Upserting embedding for entity {} in {}", entity_id, collection_name);
+        if embedding.is_none() && sparse_terms.is_empty() && named.is_empty() {
+            return Ok(());
+        }
 
-        // Ensure collection exists
-        if !self.collection_exists(entity_type).await? {
+        // Ensure collection exists and accepts vectors of this size
+        let Some(layout) = self.collection_layout(entity_type).await? else {
             return Err(anyhow::anyhow!(
                 "Collection {} does not exist. Create it first.",
                 collection_name
             ));
+        };
+        for vector in embedding.iter().chain(named.values()) {
+            check_dimension(layout.dimension, vector.len())?;
+        }
+        if let Some(name) = named.keys().find(|name| !layout.named.contains(*name)) {
+            return Err(VectaDBError::Validation(format!(
+                "Collection {} has no vector named '{}'",
+                collection_name, name
+            ))
+            .into());
         }
 
         // Create point with entity ID and embedding
@@ -155,14 +430,30 @@ impl QdrantClient {
 
         let mut payload_map: HashMap<String, QdrantValue> = HashMap::new();
         payload_map.insert("entity_id".to_string(), entity_id.to_string().into());
+        payload_map.insert(TIMESTAMP_FIELD.to_string(), timestamp.timestamp_millis().into());
 
         let payload: qdrant_client::Payload = payload_map.into();
 
-        let point = PointStruct::new(
-            entity_id.to_string(),
-            embedding,
-            payload,
-        );
+        let point = match embedding {
+            Some(embedding) if sparse_terms.is_empty() && named.is_empty() => {
+                PointStruct::new(entity_id.to_string(), embedding, payload)
+            }
+            embedding => {
+                let mut vectors = NamedVectors::default();
+                if let Some(embedding) = embedding {
+                    vectors = vectors.add_vector("", embedding);
+                }
+                if !sparse_terms.is_empty() {
+                    let (indices, values) = sparse_vector(sparse_terms);
+                    vectors = vectors
+                        .add_vector(SPARSE_VECTOR_NAME, qdrant_client::qdrant::Vector::new_sparse(indices, values));
+                }
+                for (name, vector) in named {
+                    vectors = vectors.add_vector(name.clone(), vector.clone());
+                }
+                PointStruct::new(entity_id.to_string(), vectors, payload)
+            }
+        };
 
         use qdrant_client::qdrant::UpsertPoints;
 
@@ -172,10 +463,11 @@ impl QdrantClient {
             ..Default::default()
         };
 
-        self.client
-            .upsert_points(upsert_request)
-            .await
-            .context("Failed to upsert embedding")?;
+        if let Err(e) = self.client.upsert_points(upsert_request).await {
+            // The collection may have been recreated elsewhere; look it up afresh next time
+            self.forget_layout(entity_type);
+            return Err(e).context("Failed to upsert embedding");
+        }
 
         debug!("Upserted embedding for entity {}", entity_id);
         Ok(())
@@ -183,8 +475,17 @@ impl QdrantClient {
 
     /// Delete an embedding
     pub async fn delete_embedding(&self, entity_type: &str, entity_id: &str) -> Result<()> {
+        self.delete_embeddings(entity_type, &[entity_id.to_string()]).await
+    }
+
+    /// Delete the embeddings of several entities in one request
+    pub async fn delete_embeddings(&self, entity_type: &str, entity_ids: &[String]) -> Result<()> {
+        if entity_ids.is_empty() {
+            return Ok(());
+        }
+
         let collection_name = self.collection_name(entity_type);
-        debug!("Deleting embedding for entity {} from {}", entity_id, collection_name);
+        debug!("Deleting {} embeddings from {}", entity_ids.len(), collection_name);
 
         use qdrant_client::qdrant::{PointsSelector, PointsIdsList, DeletePoints};
 
@@ -192,7 +493,7 @@ impl QdrantClient {
             points_selector_one_of: Some(
                 qdrant_client::qdrant::points_selector::PointsSelectorOneOf::Points(
                     PointsIdsList {
-                        ids: vec![entity_id.to_string().into()],
+                        ids: entity_ids.iter().map(|id| id.clone().into()).collect(),
                     },
                 ),
             ),
@@ -201,6 +502,7 @@ impl QdrantClient {
         let delete_request = DeletePoints {
             collection_name: collection_name.clone(),
             points: Some(points_selector),
+            wait: Some(true),
             ..Default::default()
         };
 
@@ -209,7 +511,7 @@ impl QdrantClient {
             .await
             .context("Failed to delete embedding")?;
 
-        debug!("Deleted embedding for entity {}", entity_id);
+        debug!("Deleted {} embeddings from {}", entity_ids.len(), collection_name);
         Ok(())
     }
 
@@ -273,6 +575,7 @@ impl QdrantClient {
         entity_type: &str,
         query_vector: Vec<f32>,
         limit: usize,
+        time_range: &TimeRange,
     ) -> Result<Vec<(String, f32)>> {
         let collection_name = self.collection_name(entity_type);
         debug!("Searching for similar entities with scores in {}", collection_name);
@@ -288,6 +591,7 @@ impl QdrantClient {
             vector: query_vector,
             limit: limit as u64,
             with_payload: Some(true.into()),
+            filter: time_filter(time_range),
             ..Default::default()
         };
 
@@ -301,15 +605,7 @@ impl QdrantClient {
             .result
             .into_iter()
             .filter_map(|point| {
-                let entity_id = point.id.and_then(|id| match id.point_id_options {
-                    Some(qdrant_client::qdrant::point_id::PointIdOptions::Uuid(uuid)) => {
-                        Some(uuid)
-                    }
-                    Some(qdrant_client::qdrant::point_id::PointIdOptions::Num(num)) => {
-                        Some(num.to_string())
-                    }
-                    None => None,
-                })?;
+                let entity_id = point.id.and_then(point_id_string)?;
 
                 Some((entity_id, point.score))
             })
@@ -319,6 +615,78 @@ impl QdrantClient {
         Ok(results)
     }
 
+    /// Search with a dense vector and a sparse vector of term weights at once,
+    /// fusing the two rankings by reciprocal rank fusion inside Qdrant. Each
+    /// side fetches `limit` candidates; scores are fusion scores, not similarities.
+    pub async fn search_hybrid(
+        &self,
+        entity_type: &str,
+        query_vector: Vec<f32>,
+        sparse_terms: &HashMap<String, f32>,
+        limit: usize,
+        time_range: &TimeRange,
+    ) -> Result<Vec<(String, f32)>> {
+        let collection_name = self.collection_name(entity_type);
+        debug!("Hybrid search in {}", collection_name);
+
+        // Ensure collection exists
+        if !self.collection_exists(entity_type).await? {
+            debug!("Collection {} does not exist, returning empty results", collection_name);
+            return Ok(vec![]);
+        }
+
+        let query_points = hybrid_query_request(&collection_name, query_vector, sparse_terms, limit, time_range);
+        let response = self
+            .client
+            .query(query_points)
+            .await
+            .context("Failed to run hybrid search")?;
+
+        let results: Vec<(String, f32)> = response
+            .result
+            .into_iter()
+            .filter_map(|point| Some((point.id.and_then(point_id_string)?, point.score)))
+            .collect();
+
+        debug!("Found {} entities by hybrid search", results.len());
+        Ok(results)
+    }
+
+    /// Search one of a collection's named vectors
+    pub async fn search_named_vector(
+        &self,
+        entity_type: &str,
+        vector_name: &str,
+        query_vector: Vec<f32>,
+        limit: usize,
+        time_range: &TimeRange,
+    ) -> Result<Vec<(String, f32)>> {
+        let collection_name = self.collection_name(entity_type);
+        debug!("Searching named vector {} in {}", vector_name, collection_name);
+
+        // Ensure collection exists
+        if !self.collection_exists(entity_type).await? {
+            debug!("Collection {} does not exist, returning empty results", collection_name);
+            return Ok(vec![]);
+        }
+
+        let query_points = named_query_request(&collection_name, vector_name, query_vector, limit, time_range);
+        let response = self
+            .client
+            .query(query_points)
+            .await
+            .context(format!("Failed to search named vector {}", vector_name))?;
+
+        let results: Vec<(String, f32)> = response
+            .result
+            .into_iter()
+            .filter_map(|point| Some((point.id.and_then(point_id_string)?, point.score)))
+            .collect();
+
+        debug!("Found {} entities by named vector {}", results.len(), vector_name);
+        Ok(results)
+    }
+
     /// Search across multiple entity types (for ontology-expanded queries)
     pub async fn search_similar_multi_type(
         &self,
@@ -347,6 +715,208 @@ impl QdrantClient {
     }
 }
 
+/// Payload field holding the point's timestamp in epoch milliseconds
+const TIMESTAMP_FIELD: &str = "timestamp";
+
+/// Payload range filter for a time window; points without a timestamp never match
+fn time_filter(range: &TimeRange) -> Option<Filter> {
+    if !range.is_bounded() {
+        return None;
+    }
+
+    let millis = |time: Option<DateTime<Utc>>| time.map(|t| t.timestamp_millis() as f64);
+    Some(Filter::must([Condition::range(
+        TIMESTAMP_FIELD,
+        Range {
+            gte: millis(range.start),
+            lt: millis(range.end),
+            ..Default::default()
+        },
+    )]))
+}
+
+/// Qdrant sparse vector for a map of term weights. Terms are hashed to indices
+/// with 32-bit FNV-1a, which is stable across restarts; terms whose hashes
+/// collide share an index and their weights add up.
+fn sparse_vector(terms: &HashMap<String, f32>) -> (Vec<u32>, Vec<f32>) {
+    let mut weights: BTreeMap<u32, f32> = BTreeMap::new();
+    for (term, weight) in terms {
+        *weights.entry(term_index(term)).or_default() += weight;
+    }
+    weights.into_iter().unzip()
+}
+
+/// Sparse vector index of a term
+fn term_index(term: &str) -> u32 {
+    term.bytes().fold(0x811c_9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Query prefetching dense and sparse candidates under the same time filter
+/// and fusing them with reciprocal rank fusion
+fn hybrid_query_request(
+    collection_name: &str,
+    query_vector: Vec<f32>,
+    sparse_terms: &HashMap<String, f32>,
+    limit: usize,
+    time_range: &TimeRange,
+) -> QueryPoints {
+    let (indices, values) = sparse_vector(sparse_terms);
+    let filter = time_filter(time_range);
+    let limit = Some(limit as u64);
+
+    QueryPoints {
+        collection_name: collection_name.to_string(),
+        prefetch: vec![
+            PrefetchQuery {
+                query: Some(Query::new_nearest(query_vector)),
+                filter: filter.clone(),
+                limit,
+                ..Default::default()
+            },
+            PrefetchQuery {
+                query: Some(Query::new_nearest(VectorInput::new_sparse(indices, values))),
+                using: Some(SPARSE_VECTOR_NAME.to_string()),
+                filter,
+                limit,
+                ..Default::default()
+            },
+        ],
+        query: Some(Query::new_fusion(Fusion::Rrf)),
+        limit,
+        with_payload: Some(true.into()),
+        ..Default::default()
+    }
+}
+
+/// Query for the nearest neighbours of `query_vector` among the points'
+/// `vector_name` vectors
+fn named_query_request(
+    collection_name: &str,
+    vector_name: &str,
+    query_vector: Vec<f32>,
+    limit: usize,
+    time_range: &TimeRange,
+) -> QueryPoints {
+    QueryPoints {
+        collection_name: collection_name.to_string(),
+        query: Some(Query::new_nearest(query_vector)),
+        using: Some(vector_name.to_string()),
+        filter: time_filter(time_range),
+        limit: Some(limit as u64),
+        with_payload: Some(true.into()),
+        ..Default::default()
+    }
+}
+
+/// Entity ID carried by a point ID
+fn point_id_string(id: PointId) -> Option<String> {
+    match id.point_id_options? {
+        PointIdOptions::Uuid(uuid) => Some(uuid),
+        PointIdOptions::Num(num) => Some(num.to_string()),
+    }
+}
+
+/// Convert a scrolled point, skipping points without an ID
+fn vector_point(point: RetrievedPoint) -> Option<VectorPoint> {
+    let vector = point.vectors.and_then(|v| v.get_vector()).and_then(|v| match v {
+        Vector::Dense(dense) => Some(dense.data),
+        _ => None,
+    });
+
+    Some(VectorPoint {
+        id: point_id_string(point.id?)?,
+        payload: point
+            .payload
+            .into_iter()
+            .map(|(key, value)| (key, value.into_json()))
+            .collect(),
+        vector,
+    })
+}
+
+/// Request creating a collection of `vector_size`-dimensional vectors compared
+/// by cosine distance, stored per `settings`, with room for a sparse vector.
+/// With `vector_names`, points hold those named vectors beside the unnamed one.
+fn create_collection_request(
+    collection_name: &str,
+    vector_size: u64,
+    vector_names: &BTreeSet<String>,
+    settings: &CollectionSettings,
+) -> CreateCollection {
+    let quantized = settings.quantization != Quantization::None;
+    // Quantized vectors must stay in RAM when the originals go to disk
+    let always_ram = (quantized && settings.quantization_originals_on_disk).then_some(true);
+    let vectors_on_disk = (settings.on_disk_vectors || always_ram.is_some()).then_some(true);
+
+    let quantization = match settings.quantization {
+        Quantization::None => None,
+        Quantization::Scalar => Some(quantization_config::Quantization::Scalar(ScalarQuantization {
+            r#type: QuantizationType::Int8.into(),
+            quantile: Some(0.99),
+            always_ram,
+        })),
+        Quantization::Product => Some(quantization_config::Quantization::Product(ProductQuantization {
+            compression: CompressionRatio::X16.into(),
+            always_ram,
+        })),
+        Quantization::Binary => Some(quantization_config::Quantization::Binary(BinaryQuantization {
+            always_ram,
+            ..Default::default()
+        })),
+    };
+
+    // Parameters left unset keep Qdrant's defaults
+    let hnsw_config = (settings.hnsw_m.is_some() || settings.hnsw_ef_construct.is_some()).then(|| {
+        HnswConfigDiff {
+            m: settings.hnsw_m,
+            ef_construct: settings.hnsw_ef_construct,
+            ..Default::default()
+        }
+    });
+
+    let params = VectorParams {
+        size: vector_size,
+        distance: Distance::Cosine.into(),
+        on_disk: vectors_on_disk,
+        ..Default::default()
+    };
+    let vectors = if vector_names.is_empty() {
+        Config::Params(params)
+    } else {
+        let names = std::iter::once("").chain(vector_names.iter().map(String::as_str));
+        Config::ParamsMap(VectorParamsMap {
+            map: names.map(|name| (name.to_string(), params.clone())).collect(),
+        })
+    };
+
+    CreateCollection {
+        collection_name: collection_name.to_string(),
+        vectors_config: Some(VectorsConfig { config: Some(vectors) }),
+        sparse_vectors_config: Some(SparseVectorConfig {
+            map: HashMap::from([(SPARSE_VECTOR_NAME.to_string(), SparseVectorParams::default())]),
+        }),
+        on_disk_payload: settings.on_disk_payload.then_some(true),
+        hnsw_config,
+        quantization_config: quantization.map(|quantization| QuantizationConfig {
+            quantization: Some(quantization),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Reject vectors that don't match the collection's configured size
+fn check_dimension(expected: Option<u64>, got: usize) -> std::result::Result<(), VectaDBError> {
+    match expected {
+        Some(expected) if expected as usize != got => Err(VectaDBError::EmbeddingDimensionMismatch {
+            expected: expected as usize,
+            got,
+        }),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -356,6 +926,8 @@ mod tests {
             url: "http://localhost:6333".to_string(),
             api_key: None,
             collection_prefix: "test_".to_string(),
+            warmup_collections: false,
+            collection: Default::default(),
         }
     }
 
@@ -389,8 +961,421 @@ mod tests {
         // Verify it exists
         let exists = client.collection_exists("TestEntity").await.unwrap();
         assert!(exists);
+        let dimension = client.collection_dimension("TestEntity").await.unwrap();
+        assert_eq!(dimension, Some(384));
 
         // Cleanup
         let _ = client.delete_collection("TestEntity").await;
     }
+
+    fn vector_params(request: &CreateCollection) -> &VectorParams {
+        match request.vectors_config.as_ref().and_then(|v| v.config.as_ref()) {
+            Some(Config::Params(params)) => params,
+            other => panic!("expected single vector params, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_create_collection_request_quantization() {
+        let plain = create_collection_request("test_Log", 384, &BTreeSet::new(), &CollectionSettings::default());
+        assert_eq!(plain.collection_name, "test_Log");
+        assert_eq!(vector_params(&plain).size, 384);
+        assert_eq!(vector_params(&plain).on_disk, None);
+        assert_eq!(plain.on_disk_payload, None);
+        assert!(plain.hnsw_config.is_none());
+        assert!(plain.quantization_config.is_none());
+        assert!(plain.sparse_vectors_config.unwrap().map.contains_key(SPARSE_VECTOR_NAME));
+
+        let settings = CollectionSettings {
+            quantization: Quantization::Scalar,
+            quantization_originals_on_disk: true,
+            ..Default::default()
+        };
+        let scalar = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings);
+        assert_eq!(vector_params(&scalar).on_disk, Some(true));
+        match scalar.quantization_config.and_then(|q| q.quantization) {
+            Some(quantization_config::Quantization::Scalar(scalar)) => {
+                assert_eq!(scalar.r#type, i32::from(QuantizationType::Int8));
+                assert_eq!(scalar.always_ram, Some(true));
+            }
+            other => panic!("expected scalar quantization, got {:?}", other),
+        }
+
+        // Originals only move to disk alongside quantization
+        let settings = CollectionSettings {
+            quantization: Quantization::None,
+            quantization_originals_on_disk: true,
+            ..Default::default()
+        };
+        let unquantized = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings);
+        assert_eq!(vector_params(&unquantized).on_disk, None);
+
+        let settings = CollectionSettings {
+            quantization: Quantization::Product,
+            ..Default::default()
+        };
+        let product = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings);
+        assert!(matches!(
+            product.quantization_config.and_then(|q| q.quantization),
+            Some(quantization_config::Quantization::Product(ProductQuantization { always_ram: None, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_create_collection_request_on_disk_storage() {
+        let settings = CollectionSettings {
+            on_disk_vectors: true,
+            on_disk_payload: true,
+            ..Default::default()
+        };
+        let request = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings);
+        assert_eq!(vector_params(&request).on_disk, Some(true));
+        assert_eq!(request.on_disk_payload, Some(true));
+        assert!(request.quantization_config.is_none());
+
+        let settings = CollectionSettings {
+            on_disk_payload: true,
+            ..Default::default()
+        };
+        let payload_only = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings);
+        assert_eq!(vector_params(&payload_only).on_disk, None);
+        assert_eq!(payload_only.on_disk_payload, Some(true));
+    }
+
+    #[test]
+    fn test_create_collection_request_hnsw() {
+        let settings = CollectionSettings {
+            hnsw_m: Some(32),
+            hnsw_ef_construct: Some(200),
+            ..Default::default()
+        };
+        let hnsw = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings).hnsw_config.unwrap();
+        assert_eq!(hnsw.m, Some(32));
+        assert_eq!(hnsw.ef_construct, Some(200));
+
+        // Unset parameters are left to Qdrant's defaults
+        let settings = CollectionSettings {
+            hnsw_ef_construct: Some(64),
+            ..Default::default()
+        };
+        let hnsw = create_collection_request("test_Log", 384, &BTreeSet::new(), &settings).hnsw_config.unwrap();
+        assert_eq!(hnsw.m, None);
+        assert_eq!(hnsw.ef_construct, Some(64));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_quantized_collection_is_created() {
+        let config = QdrantConfig {
+            collection: CollectionSettings {
+                quantization: Quantization::Scalar,
+                quantization_originals_on_disk: true,
+                ..Default::default()
+            },
+            ..test_config()
+        };
+        let client = QdrantClient::new(&config).await.unwrap();
+        let _ = client.delete_collection("QuantizedEntity").await;
+        client.create_collection("QuantizedEntity", 4).await.unwrap();
+
+        let info = client.client.collection_info("test_QuantizedEntity").await.unwrap();
+        let config = info.result.and_then(|r| r.config).unwrap();
+        match config.quantization_config.and_then(|q| q.quantization) {
+            Some(quantization_config::Quantization::Scalar(scalar)) => {
+                assert_eq!(scalar.r#type, i32::from(QuantizationType::Int8));
+                assert_eq!(scalar.always_ram, Some(true));
+            }
+            other => panic!("expected scalar quantization, got {:?}", other),
+        }
+        let on_disk = match config.params.and_then(|p| p.vectors_config).and_then(|v| v.config) {
+            Some(Config::Params(params)) => params.on_disk,
+            other => panic!("expected single vector params, got {:?}", other),
+        };
+        assert_eq!(on_disk, Some(true));
+
+        let _ = client.delete_collection("QuantizedEntity").await;
+    }
+
+    #[test]
+    fn test_time_filter() {
+        assert!(time_filter(&TimeRange::default()).is_none());
+
+        let start = "2025-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let filter = time_filter(&TimeRange::new(Some(start), None)).unwrap();
+        assert_eq!(filter.must.len(), 1);
+        assert_eq!(filter.must[0], Condition::range(
+            TIMESTAMP_FIELD,
+            Range {
+                gte: Some(start.timestamp_millis() as f64),
+                ..Default::default()
+            },
+        ));
+    }
+
+    #[test]
+    fn test_check_dimension() {
+        assert!(check_dimension(Some(384), 384).is_ok());
+        assert!(check_dimension(None, 384).is_ok());
+
+        match check_dimension(Some(384), 768) {
+            Err(VectaDBError::EmbeddingDimensionMismatch { expected, got }) => {
+                assert_eq!(expected, 384);
+                assert_eq!(got, 768);
+            }
+            other => panic!("expected dimension mismatch, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_upsert_dimension_mismatch_is_surfaced() {
+        let config = test_config();
+        let client = QdrantClient::new(&config).await.unwrap();
+
+        let _ = client.delete_collection("MismatchEntity").await;
+        client.create_collection("MismatchEntity", 4).await.unwrap();
+
+        let err = client
+            .upsert_embedding(
+                "MismatchEntity",
+                "00000000-0000-0000-0000-000000000001",
+                vec![0.1; 8],
+                Utc::now(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VectaDBError>(),
+            Some(VectaDBError::EmbeddingDimensionMismatch { expected: 4, got: 8 })
+        ));
+
+        // Cleanup
+        let _ = client.delete_collection("MismatchEntity").await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_scroll_spans_multiple_batches() {
+        use futures::TryStreamExt;
+        use std::collections::HashSet;
+
+        let config = test_config();
+        let client = QdrantClient::new(&config).await.unwrap();
+
+        let _ = client.delete_collection("ScrollEntity").await;
+        client.create_collection("ScrollEntity", 4).await.unwrap();
+
+        let ids: HashSet<String> = (1..=25)
+            .map(|n| format!("00000000-0000-0000-0000-{:012}", n))
+            .collect();
+        for id in &ids {
+            client
+                .upsert_embedding("ScrollEntity", id, vec![0.1, 0.2, 0.3, 0.4], Utc::now())
+                .await
+                .unwrap();
+        }
+
+        let batches: Vec<Vec<VectorPoint>> = client
+            .scroll("ScrollEntity", 10, true)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), vec![10, 10, 5]);
+
+        let points: Vec<&VectorPoint> = batches.iter().flatten().collect();
+        let scrolled: HashSet<String> = points.iter().map(|p| p.id.clone()).collect();
+        assert_eq!(scrolled, ids);
+        assert!(points.iter().all(|p| p.vector.as_ref().map(Vec::len) == Some(4)));
+        assert!(points.iter().all(|p| p.entity_id() == p.id));
+
+        // Cleanup
+        let _ = client.delete_collection("ScrollEntity").await;
+    }
+
+    #[test]
+    fn test_sparse_vector_hashes_terms_stably() {
+        let terms = HashMap::from([("timeout".to_string(), 1.5), ("database".to_string(), 0.5)]);
+        let (indices, values) = sparse_vector(&terms);
+
+        // Sorted by index, each term's weight at its hash
+        assert_eq!(indices.len(), 2);
+        assert!(indices[0] < indices[1]);
+        let weight = |term: &str| values[indices.iter().position(|&i| i == term_index(term)).unwrap()];
+        assert_eq!(weight("timeout"), 1.5);
+        assert_eq!(weight("database"), 0.5);
+
+        // FNV-1a reference values, so indices never change between releases
+        assert_eq!(term_index(""), 0x811c_9dc5);
+        assert_eq!(term_index("a"), 0xe40c_292c);
+    }
+
+    #[test]
+    fn test_hybrid_query_request_fuses_dense_and_sparse() {
+        let start = "2025-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let terms = HashMap::from([("timeout".to_string(), 1.0)]);
+        let request = hybrid_query_request(
+            "test_Log",
+            vec![0.1, 0.2],
+            &terms,
+            10,
+            &TimeRange::new(Some(start), None),
+        );
+
+        assert_eq!(request.query, Some(Query::new_fusion(Fusion::Rrf)));
+        assert_eq!(request.limit, Some(10));
+        assert_eq!(request.prefetch.len(), 2);
+        assert_eq!(request.prefetch[0].using, None);
+        assert_eq!(request.prefetch[1].using.as_deref(), Some(SPARSE_VECTOR_NAME));
+        assert!(request.prefetch.iter().all(|p| p.filter.is_some() && p.limit == Some(10)));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_hybrid_search_finds_lexical_match() {
+        let config = test_config();
+        let client = QdrantClient::new(&config).await.unwrap();
+
+        let _ = client.delete_collection("HybridEntity").await;
+        client.create_collection("HybridEntity", 4).await.unwrap();
+        assert_eq!(client.collection_supports_sparse("HybridEntity").await.unwrap(), Some(true));
+
+        // The lexical match is the dense vector's worst candidate
+        let lexical = "00000000-0000-0000-0000-000000000001";
+        let semantic = "00000000-0000-0000-0000-000000000002";
+        let unrelated = "00000000-0000-0000-0000-000000000003";
+        let terms = HashMap::from([("err_4021".to_string(), 2.0), ("timeout".to_string(), 0.5)]);
+        client
+            .upsert_vectors("HybridEntity", lexical, Some(vec![0.0, 0.0, 1.0, 0.0]), &terms, &HashMap::new(), Utc::now())
+            .await
+            .unwrap();
+        client
+            .upsert_embedding("HybridEntity", semantic, vec![1.0, 0.0, 0.0, 0.0], Utc::now())
+            .await
+            .unwrap();
+        let other_terms = HashMap::from([("cache".to_string(), 1.0)]);
+        client
+            .upsert_vectors(
+                "HybridEntity",
+                unrelated,
+                Some(vec![0.7, 0.7, 0.0, 0.0]),
+                &other_terms,
+                &HashMap::new(),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+
+        let dense_only = client
+            .search_similar_with_scores("HybridEntity", vec![1.0, 0.0, 0.0, 0.0], 2, &TimeRange::default())
+            .await
+            .unwrap();
+        assert!(dense_only.iter().all(|(id, _)| id != lexical));
+
+        let query_terms = HashMap::from([("err_4021".to_string(), 1.0)]);
+        let hybrid = client
+            .search_hybrid("HybridEntity", vec![1.0, 0.0, 0.0, 0.0], &query_terms, 2, &TimeRange::default())
+            .await
+            .unwrap();
+        let ids: Vec<&str> = hybrid.iter().map(|(id, _)| id.as_str()).collect();
+        assert!(ids.contains(&lexical), "{:?}", ids);
+        assert!(ids.contains(&semantic), "{:?}", ids);
+
+        // Cleanup
+        let _ = client.delete_collection("HybridEntity").await;
+    }
+
+    #[test]
+    fn test_collection_naming_applies_prefix() {
+        let naming = CollectionNaming::new("tenant1_");
+        assert_eq!(naming.collection("Log"), "tenant1_Log");
+        assert_eq!(naming.collection(EVENTS_COLLECTION), "tenant1_agent_events");
+        assert_eq!(naming.entity_type("tenant1_Log"), Some("Log"));
+        assert_eq!(naming.entity_type("tenant2_Log"), None);
+    }
+
+    #[test]
+    fn test_named_vectors_share_the_entity_collection() {
+        let names = BTreeSet::from(["input".to_string(), "output".to_string()]);
+        let request = create_collection_request("test_ToolCall", 384, &names, &CollectionSettings::default());
+        match request.vectors_config.and_then(|v| v.config) {
+            Some(Config::ParamsMap(map)) => {
+                let declared: BTreeSet<&str> = map.map.keys().map(String::as_str).collect();
+                assert_eq!(declared, BTreeSet::from(["", "input", "output"]));
+                assert!(map.map.values().all(|params| params.size == 384));
+            }
+            other => panic!("expected named vector params, got {:?}", other),
+        }
+
+        let request = named_query_request("test_ToolCall", "input", vec![0.1, 0.2], 5, &TimeRange::default());
+        assert_eq!(request.using.as_deref(), Some("input"));
+        assert_eq!(request.limit, Some(5));
+        assert!(request.filter.is_none());
+
+        assert!(is_valid_vector_name("tool_output-2"));
+        assert!(!is_valid_vector_name(""));
+        assert!(!is_valid_vector_name("in.put"));
+        assert!(!is_valid_vector_name(SPARSE_VECTOR_NAME));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_collections_are_created_under_prefix() {
+        let config = QdrantConfig {
+            collection_prefix: "tenant1_".to_string(),
+            ..test_config()
+        };
+        let client = QdrantClient::new(&config).await.unwrap();
+
+        for entity_type in ["Log", EVENTS_COLLECTION] {
+            let _ = client.delete_collection(entity_type).await;
+            client.create_collection(entity_type, 4).await.unwrap();
+        }
+
+        // Check the raw Qdrant names, bypassing the client's own prefixing
+        for name in ["tenant1_Log", "tenant1_agent_events"] {
+            assert!(client.client.collection_exists(name).await.unwrap(), "{} missing", name);
+        }
+        assert!(!client.client.collection_exists("Log").await.unwrap());
+
+        let listed = client.list_entity_collections().await.unwrap();
+        assert!(listed.contains(&"Log".to_string()));
+        assert!(listed.contains(&EVENTS_COLLECTION.to_string()));
+
+        // Cleanup
+        for entity_type in ["Log", EVENTS_COLLECTION] {
+            let _ = client.delete_collection(entity_type).await;
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Qdrant running
+    async fn test_concurrent_first_inserts_create_one_collection() {
+        let client = std::sync::Arc::new(QdrantClient::new(&test_config()).await.unwrap());
+        let _ = client.delete_collection("RaceEntity").await;
+
+        // Every insert of the brand-new type tries to create its collection
+        let inserts = (1..=16).map(|n| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let created = client.ensure_collection("RaceEntity", 4).await?;
+                let id = format!("00000000-0000-0000-0000-{:012}", n);
+                client.upsert_embedding("RaceEntity", &id, vec![0.5; 4], Utc::now()).await?;
+                Ok::<bool, anyhow::Error>(created)
+            })
+        });
+        let outcomes = futures::future::join_all(inserts).await;
+
+        let mut created = 0;
+        for outcome in outcomes {
+            if outcome.unwrap().unwrap() {
+                created += 1;
+            }
+        }
+        assert_eq!(created, 1);
+
+        let listed = client.list_entity_collections().await.unwrap();
+        assert_eq!(listed.iter().filter(|t| *t == "RaceEntity").count(), 1);
+
+        let _ = client.delete_collection("RaceEntity").await;
+    }
 }
@@ -0,0 +1,138 @@
+// Retention: purge events and traces older than the configured number of days
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::AnalyticsConfig;
+use super::qdrant_client::{QdrantClient, EVENTS_COLLECTION};
+use super::surrealdb_client::SurrealDBClient;
+
+/// How old data may get and how it is purged
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub max_age: chrono::Duration,
+    pub interval: Duration,
+    pub batch_size: usize,
+}
+
+impl RetentionPolicy {
+    /// The configured policy, or `None` when data is kept forever
+    pub fn from_config(config: &AnalyticsConfig) -> Option<Self> {
+        if config.retention_days == 0 {
+            return None;
+        }
+        Some(Self {
+            max_age: chrono::Duration::days(config.retention_days as i64),
+            interval: Duration::from_secs(config.retention_interval_secs),
+            batch_size: config.retention_batch_size.max(1),
+        })
+    }
+
+    /// Records timestamped before this are expired
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - self.max_age
+    }
+}
+
+/// What one retention run deleted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PurgeStats {
+    pub events: usize,
+    pub vectors: usize,
+    pub traces: usize,
+}
+
+/// Delete everything that expired as of `now`, a batch at a time. Event
+/// vectors are deleted before their rows, so a failed vector delete leaves
+/// the batch for the next run instead of orphaning vectors.
+pub async fn purge_expired(
+    surreal: &SurrealDBClient,
+    qdrant: Option<&QdrantClient>,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<PurgeStats> {
+    let cutoff = policy.cutoff(now);
+    let mut stats = PurgeStats::default();
+
+    let vectors = match qdrant {
+        Some(qdrant) if qdrant.collection_exists(EVENTS_COLLECTION).await? => Some(qdrant),
+        _ => None,
+    };
+
+    loop {
+        let ids = surreal.expired_event_ids(cutoff, policy.batch_size).await?;
+        if let Some(qdrant) = vectors {
+            qdrant.delete_embeddings(EVENTS_COLLECTION, &ids).await?;
+            stats.vectors += ids.len();
+        }
+        surreal.delete_events(&ids).await?;
+        stats.events += ids.len();
+
+        if ids.len() < policy.batch_size {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+
+    loop {
+        let deleted = surreal.delete_expired_traces(cutoff, policy.batch_size).await?;
+        stats.traces += deleted;
+
+        if deleted < policy.batch_size {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+
+    Ok(stats)
+}
+
+/// Purge expired data every `policy.interval`, for the life of the server
+pub async fn enforce_retention(
+    surreal: Arc<SurrealDBClient>,
+    qdrant: Option<Arc<QdrantClient>>,
+    policy: RetentionPolicy,
+) {
+    loop {
+        tokio::time::sleep(policy.interval).await;
+
+        match purge_expired(&surreal, qdrant.as_deref(), &policy, Utc::now()).await {
+            Ok(stats) if stats == PurgeStats::default() => {}
+            Ok(stats) => info!(
+                "Retention purged {} events, {} vectors and {} traces older than {} days",
+                stats.events,
+                stats.vectors,
+                stats.traces,
+                policy.max_age.num_days()
+            ),
+            Err(e) => warn!("Retention run failed: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_from_config() {
+        let config = AnalyticsConfig {
+            retention_days: 7,
+            retention_interval_secs: 60,
+            retention_batch_size: 100,
+            ..Default::default()
+        };
+        let policy = RetentionPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.interval, Duration::from_secs(60));
+        assert_eq!(policy.batch_size, 100);
+
+        let now: DateTime<Utc> = "2025-03-08T12:00:00Z".parse().unwrap();
+        assert_eq!(policy.cutoff(now), "2025-03-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        let forever = AnalyticsConfig { retention_days: 0, ..Default::default() };
+        assert!(RetentionPolicy::from_config(&forever).is_none());
+    }
+}
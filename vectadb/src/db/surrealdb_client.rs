@@ -1,23 +1,91 @@
 // SurrealDB client for graph and entity storage
 
 use anyhow::{Context, Result};
+use futures::stream::{BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
-use surrealdb::engine::remote::http::{Client, Http};
+use surrealdb::engine::any::{self, Any};
 use surrealdb::opt::auth::Root;
 use surrealdb::sql::Datetime;
-use surrealdb::Surreal;
-use std::sync::Arc;
+use surrealdb::{Action, Notification, Surreal};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
 use tracing::{debug, info, warn};
 
 use crate::config::DatabaseConfig;
+use crate::error::VectaDBError;
+use crate::models::EmbeddingMetadata;
+use crate::ontology::entity_type::PropertyDefinition;
 use crate::ontology::OntologySchema;
-use super::types::{Entity, Relation};
+use super::types::{
+    AuditRecord, DeadLetter, Entity, EventAnalytics, EventFilter, EventRecord, ExportTable, FacetCount,
+    ModelTokenUsage, Relation, TimeBucket, TokenUsage,
+};
+
+/// Remote engine used to talk to SurrealDB, chosen by the endpoint scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurrealEngine {
+    Http,
+    Https,
+    Ws,
+    Wss,
+}
+
+impl SurrealEngine {
+    /// Split an endpoint into its engine and address. A bare `host:port`
+    /// keeps the HTTP engine that was the only option before.
+    pub fn from_endpoint(endpoint: &str) -> Result<(Self, &str)> {
+        let Some((scheme, address)) = endpoint.split_once("://") else {
+            return Ok((Self::Http, endpoint));
+        };
+
+        let engine = match scheme.to_ascii_lowercase().as_str() {
+            "http" => Self::Http,
+            "https" => Self::Https,
+            "ws" => Self::Ws,
+            "wss" => Self::Wss,
+            _ => anyhow::bail!(
+                "Unsupported SurrealDB endpoint scheme '{}' (expected http, https, ws or wss)",
+                scheme
+            ),
+        };
+        if address.is_empty() {
+            anyhow::bail!("SurrealDB endpoint '{}' has no address", endpoint);
+        }
+
+        Ok((engine, address.trim_end_matches('/')))
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Https => "https",
+            Self::Ws => "ws",
+            Self::Wss => "wss",
+        }
+    }
+
+    /// Live queries need a persistent connection, which only WebSocket engines hold
+    pub fn supports_live_queries(&self) -> bool {
+        matches!(self, Self::Ws | Self::Wss)
+    }
+}
 
 /// SurrealDB client wrapper
 pub struct SurrealDBClient {
-    db: Arc<Surreal<Client>>,
+    /// Authenticated connections, handed out round-robin; replaced whole on reconnect
+    pool: RwLock<Vec<Surreal<Any>>>,
+    next: AtomicUsize,
+    engine: SurrealEngine,
     namespace: String,
     database: String,
+    /// Settings used to (re)open the pool
+    config: DatabaseConfig,
+    connected: AtomicBool,
+    /// Bumped on every successful reconnect, so concurrent failures reconnect once
+    generation: AtomicU64,
+    reconnecting: tokio::sync::Mutex<()>,
 }
 
 /// Stored ontology schema record
@@ -30,27 +98,126 @@ struct OntologyRecord {
 }
 
 impl SurrealDBClient {
-    /// Get reference to the underlying Surreal database connection
-    pub fn db(&self) -> &Surreal<Client> {
-        &self.db
+    /// Get the next pooled Surreal database connection
+    pub fn db(&self) -> Surreal<Any> {
+        let pool = self.pool.read().unwrap_or_else(|e| e.into_inner());
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % pool.len();
+        pool[index].clone()
     }
 
-    /// Create a new SurrealDB client and connect
+    /// Create a new SurrealDB client, open its connection pool and
+    /// initialize the schema
     pub async fn new(config: &DatabaseConfig) -> Result<Self> {
+        let client = Self::disconnected(config)?;
+        client.reconnect().await?;
+        Ok(client)
+    }
+
+    /// Create a client without connecting. Queries fail until `reconnect`
+    /// succeeds, so a database that is down at startup can be retried later.
+    pub fn disconnected(config: &DatabaseConfig) -> Result<Self> {
+        let (engine, _) = SurrealEngine::from_endpoint(&config.surrealdb.endpoint)?;
+
+        Ok(Self {
+            pool: RwLock::new(vec![Surreal::init()]),
+            next: AtomicUsize::new(0),
+            engine,
+            namespace: config.surrealdb.namespace.clone(),
+            database: config.surrealdb.database.clone(),
+            config: config.clone(),
+            connected: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+            reconnecting: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Whether the last connection attempt succeeded and no query has since
+    /// failed for lack of a connection
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+
+    /// Open a fresh connection pool, initialize the schema and swap the pool in
+    pub async fn reconnect(&self) -> Result<()> {
+        let _guard = self.reconnecting.lock().await;
+        self.open_pool().await
+    }
+
+    /// Reconnect unless another caller already did since `generation`
+    async fn reconnect_after(&self, generation: u64) -> Result<()> {
+        let _guard = self.reconnecting.lock().await;
+        if self.generation.load(Ordering::Acquire) != generation {
+            return Ok(());
+        }
+        self.connected.store(false, Ordering::Release);
+        self.open_pool().await
+    }
+
+    /// Callers hold `reconnecting`
+    async fn open_pool(&self) -> Result<()> {
+        let config = &self.config;
         info!("Connecting to SurrealDB at {}", config.surrealdb.endpoint);
-        debug!("Connection details - namespace: {}, database: {}",
-               config.surrealdb.namespace, config.surrealdb.database);
+        debug!("Connection details - namespace: {}, database: {}, pool size: {}",
+               config.surrealdb.namespace, config.surrealdb.database, config.surrealdb.pool_size);
+
+        let connections = (0..config.surrealdb.pool_size.max(1)).map(|_| Self::connect(config));
+        let result = match futures::future::try_join_all(connections).await {
+            Ok(pool) => {
+                info!(
+                    "Connected to SurrealDB: {}/{} ({} connections)",
+                    config.surrealdb.namespace,
+                    config.surrealdb.database,
+                    pool.len()
+                );
+                *self.pool.write().unwrap_or_else(|e| e.into_inner()) = pool;
+
+                // Initialize schema once, whatever the pool size
+                self.initialize_schema().await
+            }
+            Err(e) => Err(e),
+        };
+
+        if result.is_ok() {
+            self.generation.fetch_add(1, Ordering::AcqRel);
+        }
+        self.connected.store(result.is_ok(), Ordering::Release);
+        result
+    }
+
+    /// Run a query, and if it fails because the connection is gone,
+    /// reconnect and run it once more. Reads go through this; writes do not,
+    /// since a write may have been applied before the connection dropped.
+    pub async fn retry_on_disconnect<T, F, Fut>(&self, query: F) -> Result<T>
+    where
+        F: Fn(Surreal<Any>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let generation = self.generation.load(Ordering::Acquire);
+        retry_once(|| query(self.db()), || self.reconnect_after(generation)).await
+    }
+
+    /// Whether `subscribe_events` can be used on this connection
+    pub fn supports_live_queries(&self) -> bool {
+        self.engine.supports_live_queries()
+    }
 
+    /// Open one authenticated connection with the namespace and database selected
+    async fn connect(config: &DatabaseConfig) -> Result<Surreal<Any>> {
         // Connect to SurrealDB
-        debug!("Step 1: Establishing HTTP connection...");
-        let db = match Surreal::new::<Http>(&config.surrealdb.endpoint).await {
+        let (engine, address) = SurrealEngine::from_endpoint(&config.surrealdb.endpoint)?;
+        debug!("Step 1: Establishing {} connection...", engine.scheme());
+        let db = match any::connect(format!("{}://{}", engine.scheme(), address)).await {
             Ok(client) => {
-                debug!("Step 1: HTTP connection established successfully");
+                debug!("Step 1: {} connection established successfully", engine.scheme());
                 client
             }
             Err(e) => {
                 warn!("Step 1 failed with error: {:?}", e);
-                return Err(anyhow::anyhow!("Failed to establish HTTP connection to SurrealDB: {}", e));
+                return Err(anyhow::anyhow!(
+                    "Failed to establish {} connection to SurrealDB: {}",
+                    engine.scheme(),
+                    e
+                ));
             }
         };
 
@@ -73,21 +240,7 @@ impl SurrealDBClient {
             .context("Failed to select namespace/database")?;
         debug!("Step 3: Namespace and database selected successfully");
 
-        info!(
-            "Connected to SurrealDB: {}/{}",
-            config.surrealdb.namespace, config.surrealdb.database
-        );
-
-        let client = Self {
-            db: Arc::new(db),
-            namespace: config.surrealdb.namespace.clone(),
-            database: config.surrealdb.database.clone(),
-        };
-
-        // Initialize schema
-        client.initialize_schema().await?;
-
-        Ok(client)
+        Ok(db)
     }
 
     /// Initialize database schema
@@ -95,7 +248,7 @@ impl SurrealDBClient {
         debug!("Initializing SurrealDB schema");
 
         // Define ontology_schema table
-        self.db
+        self.db()
             .query(
                 "DEFINE TABLE IF NOT EXISTS ontology_schema SCHEMAFULL;
                  DEFINE FIELD IF NOT EXISTS namespace ON ontology_schema TYPE string;
@@ -108,22 +261,27 @@ impl SurrealDBClient {
             .context("Failed to define ontology_schema table")?;
 
         // Define entity table
-        self.db
+        self.db()
             .query(
                 "DEFINE TABLE IF NOT EXISTS entity SCHEMAFULL;
                  DEFINE FIELD IF NOT EXISTS entity_type ON entity TYPE string;
                  DEFINE FIELD IF NOT EXISTS properties ON entity FLEXIBLE TYPE object;
                  DEFINE FIELD IF NOT EXISTS embedding ON entity TYPE option<array>;
+                 DEFINE FIELD IF NOT EXISTS named_embeddings ON entity FLEXIBLE TYPE option<object>;
+                 DEFINE FIELD IF NOT EXISTS sparse_vector ON entity FLEXIBLE TYPE option<object>;
                  DEFINE FIELD IF NOT EXISTS metadata ON entity FLEXIBLE TYPE option<object>;
                  DEFINE FIELD IF NOT EXISTS created_at ON entity TYPE datetime DEFAULT time::now();
                  DEFINE FIELD IF NOT EXISTS updated_at ON entity TYPE datetime DEFAULT time::now();
+                 DEFINE FIELD IF NOT EXISTS deleted_at ON entity TYPE option<datetime>;
+                 DEFINE FIELD IF NOT EXISTS version ON entity TYPE int DEFAULT 0;
+                 DEFINE FIELD IF NOT EXISTS schema_version ON entity TYPE option<string>;
                  DEFINE INDEX IF NOT EXISTS idx_type ON entity COLUMNS entity_type;",
             )
             .await
             .context("Failed to define entity table")?;
 
         // Define relation table
-        self.db
+        self.db()
             .query(
                 "DEFINE TABLE IF NOT EXISTS relation SCHEMAFULL;
                  DEFINE FIELD IF NOT EXISTS relation_type ON relation TYPE string;
@@ -138,8 +296,16 @@ impl SurrealDBClient {
             .await
             .context("Failed to define relation table")?;
 
+        // Define collection_meta table (embedding provenance per vector collection)
+        self.db()
+            .query(
+                "DEFINE TABLE IF NOT EXISTS collection_meta SCHEMALESS;",
+            )
+            .await
+            .context("Failed to define collection_meta table")?;
+
         // Phase 5: Define agent_trace table
-        self.db
+        self.db()
             .query(
                 "DEFINE TABLE IF NOT EXISTS agent_trace SCHEMAFULL;
                  DEFINE FIELD IF NOT EXISTS id ON agent_trace TYPE string;
@@ -157,7 +323,7 @@ impl SurrealDBClient {
             .context("Failed to define agent_trace table")?;
 
         // Phase 5: Define agent_event table
-        self.db
+        self.db()
             .query(
                 "DEFINE TABLE IF NOT EXISTS agent_event SCHEMAFULL;
                  DEFINE FIELD IF NOT EXISTS id ON agent_event TYPE string;
@@ -177,13 +343,66 @@ impl SurrealDBClient {
             .await
             .context("Failed to define agent_event table")?;
 
+        // Events that failed ingestion, kept for replay
+        self.db()
+            .query(
+                "DEFINE TABLE IF NOT EXISTS dead_letter SCHEMAFULL;
+                 DEFINE FIELD IF NOT EXISTS event ON dead_letter FLEXIBLE TYPE object;
+                 DEFINE FIELD IF NOT EXISTS error ON dead_letter TYPE string;
+                 DEFINE FIELD IF NOT EXISTS attempts ON dead_letter TYPE int DEFAULT 0;
+                 DEFINE FIELD IF NOT EXISTS created_at ON dead_letter TYPE datetime DEFAULT time::now();
+                 DEFINE INDEX IF NOT EXISTS idx_created_at ON dead_letter COLUMNS created_at;",
+            )
+            .await
+            .context("Failed to define dead_letter table")?;
+
+        // Audit log of creates, updates and deletes
+        self.db()
+            .query(
+                "DEFINE TABLE IF NOT EXISTS audit SCHEMAFULL;
+                 DEFINE FIELD IF NOT EXISTS operation ON audit TYPE string;
+                 DEFINE FIELD IF NOT EXISTS target_type ON audit TYPE string;
+                 DEFINE FIELD IF NOT EXISTS target_id ON audit TYPE string;
+                 DEFINE FIELD IF NOT EXISTS principal ON audit TYPE option<string>;
+                 DEFINE FIELD IF NOT EXISTS request_id ON audit TYPE option<string>;
+                 DEFINE FIELD IF NOT EXISTS changes ON audit FLEXIBLE TYPE object;
+                 DEFINE FIELD IF NOT EXISTS created_at ON audit TYPE datetime DEFAULT time::now();
+                 DEFINE INDEX IF NOT EXISTS idx_target_id ON audit COLUMNS target_id;
+                 DEFINE INDEX IF NOT EXISTS idx_created_at ON audit COLUMNS created_at;",
+            )
+            .await
+            .context("Failed to define audit table")?;
+
+        // Indexes on entity properties the stored ontology marks as indexed
+        match self.get_schema().await {
+            Ok(Some(schema)) => self.define_property_indexes(&schema).await?,
+            Ok(None) => {}
+            Err(e) => warn!("Skipping property indexes, stored ontology unreadable: {:#}", e),
+        }
+
         debug!("SurrealDB schema initialized (including Phase 5 tables)");
         Ok(())
     }
 
+    /// Define an index for every property the ontology marks as indexed
+    async fn define_property_indexes(&self, schema: &OntologySchema) -> Result<()> {
+        let statements = property_index_statements(schema);
+        if statements.is_empty() {
+            return Ok(());
+        }
+        self.db()
+            .query(statements.join("\n"))
+            .await
+            .context("Failed to define entity property indexes")?
+            .check()
+            .context("Failed to define entity property indexes")?;
+        debug!("Defined {} entity property indexes", statements.len());
+        Ok(())
+    }
+
     /// Check if SurrealDB is healthy
     pub async fn health_check(&self) -> Result<bool> {
-        match self.db.health().await {
+        match self.db().health().await {
             Ok(_) => Ok(true),
             Err(e) => {
                 warn!("SurrealDB health check failed: {}", e);
@@ -211,14 +430,14 @@ impl SurrealDBClient {
         };
 
         // Use upsert to handle dotted namespaces and updates
-        match self.db
+        match self.db()
             .upsert::<Option<OntologyRecord>>(("ontology_schema", schema.namespace.clone()))
             .content(record)
             .await
         {
             Ok(_) => {
                 info!("Stored ontology schema: {}", schema.namespace);
-                Ok(())
+                self.define_property_indexes(schema).await
             }
             Err(e) => {
                 warn!("Failed to upsert ontology schema: {:?}", e);
@@ -233,10 +452,12 @@ impl SurrealDBClient {
 
         // Get the most recent schema
         let mut result = self
-            .db
-            .query("SELECT * FROM ontology_schema ORDER BY created_at DESC LIMIT 1")
-            .await
-            .context("Failed to query ontology schema")?;
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT * FROM ontology_schema ORDER BY created_at DESC LIMIT 1")
+                    .await
+                    .context("Failed to query ontology schema")
+            })
+            .await?;
 
         let records: Vec<OntologyRecord> = result.take(0)?;
 
@@ -262,17 +483,20 @@ impl SurrealDBClient {
 
         // Use SurrealDB query with bind parameters and explicit datetime values
         let query = format!(
-            "CREATE entity:⟨{}⟩ SET entity_type = $entity_type, properties = $properties, embedding = $embedding, metadata = $metadata, created_at = time::now(), updated_at = time::now()",
+            "CREATE entity:⟨{}⟩ SET entity_type = $entity_type, properties = $properties, embedding = $embedding, named_embeddings = $named_embeddings, sparse_vector = $sparse_vector, metadata = $metadata, schema_version = $schema_version, created_at = time::now(), updated_at = time::now(), version = 1",
             record_id_string
         );
 
         match self
-            .db
+            .db()
             .query(query)
             .bind(("entity_type", entity.entity_type.clone()))
             .bind(("properties", serde_json::to_value(&entity.properties)?))
             .bind(("embedding", entity.embedding.clone()))
+            .bind(("named_embeddings", serde_json::to_value(&entity.named_embeddings)?))
+            .bind(("sparse_vector", serde_json::to_value(&entity.sparse_vector)?))
             .bind(("metadata", serde_json::to_value(&entity.metadata)?))
+            .bind(("schema_version", entity.schema_version.clone()))
             .await
         {
             Ok(_) => {
@@ -286,60 +510,209 @@ impl SurrealDBClient {
         }
     }
 
-    /// Get an entity by ID
+    /// Get an entity by ID, hiding soft-deleted entities
     pub async fn get_entity(&self, id: &str) -> Result<Option<Entity>> {
+        let entity = self.get_entity_including_deleted(id).await?;
+        Ok(entity.filter(|e| !e.is_deleted()))
+    }
+
+    /// Get an entity by ID, including soft-deleted entities
+    pub async fn get_entity_including_deleted(&self, id: &str) -> Result<Option<Entity>> {
         debug!("Getting entity: {}", id);
 
         let entity: Option<Entity> = self
-            .db
-            .select(("entity", id))
-            .await
-            .context("Failed to get entity")?;
+            .retry_on_disconnect(|db| async move {
+                db.select(("entity", id)).await.context("Failed to get entity")
+            })
+            .await?;
 
         Ok(entity)
     }
 
-    /// Update an entity
-    pub async fn update_entity(&self, id: &str, entity: &Entity) -> Result<()> {
-        debug!("Updating entity: {}", id);
+    /// Entities among `ids` in one query, in no particular order. IDs with no
+    /// entity are skipped, as are soft-deleted entities unless `include_deleted`.
+    pub async fn get_entities_by_ids(&self, ids: &[String], include_deleted: bool) -> Result<Vec<Entity>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        debug!("Getting {} entities by ID", ids.len());
+
+        let things: Vec<surrealdb::sql::Thing> = ids
+            .iter()
+            .map(|id| surrealdb::sql::Thing::from(("entity".to_string(), id.clone())))
+            .collect();
+        let query = if include_deleted {
+            "SELECT * FROM entity WHERE id IN $ids"
+        } else {
+            "SELECT * FROM entity WHERE id IN $ids AND deleted_at = NONE"
+        };
 
-        let entity_clone = entity.clone();
-        let _: Option<Entity> = self
-            .db
-            .update(("entity", id))
-            .content(entity_clone)
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let things = things.clone();
+                async move { db.query(query).bind(("ids", things)).await.context("Failed to get entities") }
+            })
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
+    /// Live entities whose `properties` match `query` by full-text search,
+    /// best BM25 score first. Every property must be full-text indexed.
+    pub async fn text_search_entities(
+        &self,
+        query: &str,
+        properties: &[String],
+        entity_type: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<(Entity, f32)>> {
+        #[derive(Deserialize)]
+        struct Hit {
+            id: surrealdb::sql::Thing,
+            score: f32,
+        }
+
+        if let Some(name) = properties.iter().find(|name| !is_plain_identifier(name)) {
+            anyhow::bail!("Invalid property name '{}'", name);
+        }
+        if properties.is_empty() {
+            return Ok(Vec::new());
+        }
+        debug!("Full-text search over {:?} for '{}'", properties, query);
+
+        let (matches, scores): (Vec<String>, Vec<String>) = properties
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                (
+                    format!("properties.{name} @{i}@ $query"),
+                    format!("(search::score({i}) ?? 0)"),
+                )
+            })
+            .unzip();
+        let mut conditions = vec![format!("({})", matches.join(" OR ")), "deleted_at = NONE".to_string()];
+        if entity_type.is_some() {
+            conditions.push("entity_type = $entity_type".to_string());
+        }
+        let statement = format!(
+            "SELECT id, {} AS score FROM entity WHERE {} ORDER BY score DESC LIMIT $limit",
+            scores.join(" + "),
+            conditions.join(" AND ")
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let statement = statement.clone();
+                async move {
+                    db.query(statement)
+                        .bind(("query", query.to_string()))
+                        .bind(("entity_type", entity_type.map(str::to_string)))
+                        .bind(("limit", limit))
+                        .await
+                        .context("Failed to search entities")
+                }
+            })
+            .await?;
+        let hits: Vec<Hit> = result.take(0)?;
+
+        let ids: Vec<String> = hits.iter().map(|hit| hit.id.id.to_raw()).collect();
+        let mut entities: HashMap<surrealdb::sql::Thing, Entity> = self
+            .get_entities_by_ids(&ids, false)
+            .await?
+            .into_iter()
+            .map(|entity| (entity.id.clone(), entity))
+            .collect();
+
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| entities.remove(&hit.id).map(|entity| (entity, hit.score)))
+            .collect())
+    }
+
+    /// Update an entity's properties if its stored version still equals `expected_version`.
+    /// Returns the updated entity, or None if the version no longer matches (or the
+    /// entity is gone), in which case nothing is written.
+    pub async fn update_entity(
+        &self,
+        id: &str,
+        entity: &Entity,
+        expected_version: u64,
+    ) -> Result<Option<Entity>> {
+        debug!("Updating entity: {} (expected version {})", id, expected_version);
+
+        let mut result = self
+            .db()
+            .query(
+                "UPDATE type::thing('entity', $id) SET properties = $properties, metadata = $metadata, embedding = $embedding, schema_version = $schema_version, updated_at = time::now(), version = (version ?? 0) + 1 WHERE (version ?? 0) = $expected_version AND deleted_at = NONE RETURN AFTER",
+            )
+            .bind(("id", id.to_string()))
+            .bind(("properties", serde_json::to_value(&entity.properties)?))
+            .bind(("metadata", serde_json::to_value(&entity.metadata)?))
+            .bind(("embedding", entity.embedding.clone()))
+            .bind(("schema_version", entity.schema_version.clone()))
+            .bind(("expected_version", expected_version))
             .await
             .context("Failed to update entity")?;
 
-        debug!("Updated entity: {}", id);
-        Ok(())
+        let updated: Vec<Entity> = result.take(0).context("Failed to update entity")?;
+        let updated = updated.into_iter().next();
+
+        match &updated {
+            Some(e) => debug!("Updated entity: {} to version {}", id, e.version),
+            None => debug!("Entity {} not updated: version mismatch or missing", id),
+        }
+        Ok(updated)
     }
 
-    /// Delete an entity
+    /// Soft-delete an entity by setting its tombstone
     pub async fn delete_entity(&self, id: &str) -> Result<()> {
-        debug!("Deleting entity: {}", id);
+        debug!("Soft-deleting entity: {}", id);
+
+        self.db()
+            .query("UPDATE type::thing('entity', $id) SET deleted_at = time::now(), version = (version ?? 0) + 1 WHERE deleted_at = NONE")
+            .bind(("id", id.to_string()))
+            .await
+            .context("Failed to delete entity")?
+            .check()
+            .context("Failed to delete entity")?;
+
+        debug!("Soft-deleted entity: {}", id);
+        Ok(())
+    }
+
+    /// Permanently remove an entity, whether or not it is tombstoned
+    pub async fn purge_entity(&self, id: &str) -> Result<()> {
+        debug!("Purging entity: {}", id);
 
         let _: Option<Entity> = self
-            .db
+            .db()
             .delete(("entity", id))
             .await
-            .context("Failed to delete entity")?;
+            .context("Failed to purge entity")?;
 
-        debug!("Deleted entity: {}", id);
+        debug!("Purged entity: {}", id);
         Ok(())
     }
 
     /// Query entities by type
-    pub async fn query_entities(&self, entity_type: &str) -> Result<Vec<Entity>> {
+    pub async fn query_entities(&self, entity_type: &str, include_deleted: bool) -> Result<Vec<Entity>> {
         debug!("Querying entities of type: {}", entity_type);
 
-        let entity_type_owned = entity_type.to_string();
+        let query = if include_deleted {
+            "SELECT * FROM entity WHERE entity_type = $type"
+        } else {
+            "SELECT * FROM entity WHERE entity_type = $type AND deleted_at = NONE"
+        };
+
         let mut result = self
-            .db
-            .query("SELECT * FROM entity WHERE entity_type = $type")
-            .bind(("type", entity_type_owned))
-            .await
-            .context("Failed to query entities")?;
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("type", entity_type.to_string()))
+                    .await
+                    .context("Failed to query entities")
+            })
+            .await?;
 
         let entities: Vec<Entity> = result.take(0)?;
 
@@ -347,17 +720,137 @@ impl SurrealDBClient {
         Ok(entities)
     }
 
+    /// Query live entities of a type whose string property `property` equals `value`
+    pub async fn query_entities_by_property(
+        &self,
+        entity_type: &str,
+        property: &str,
+        value: &str,
+    ) -> Result<Vec<Entity>> {
+        debug!("Querying {} entities with {} = {}", entity_type, property, value);
+
+        // The property name is spliced into the query, so keep it to a plain identifier
+        if !is_plain_identifier(property) {
+            anyhow::bail!("Invalid property name '{}'", property);
+        }
+
+        let query = format!(
+            "SELECT * FROM entity WHERE entity_type = $type AND properties.{} = $value AND deleted_at = NONE",
+            property
+        );
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("type", entity_type.to_string()))
+                        .bind(("value", value.to_string()))
+                        .await
+                        .context("Failed to query entities by property")
+                }
+            })
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
+    /// IDs of live entities of a type whose property `property` equals `value`
+    pub async fn entity_ids_with_property_value(
+        &self,
+        entity_type: &str,
+        property: &str,
+        value: &serde_json::Value,
+    ) -> Result<Vec<String>> {
+        // The property name is spliced into the query, so keep it to a plain identifier
+        if !is_plain_identifier(property) {
+            anyhow::bail!("Invalid property name '{}'", property);
+        }
+
+        let query = format!(
+            "SELECT VALUE record::id(id) FROM entity WHERE entity_type = $type AND properties.{} = $value AND deleted_at = NONE",
+            property
+        );
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("type", entity_type.to_string()))
+                        .bind(("value", value.clone()))
+                        .await
+                        .context("Failed to query entities by property value")
+                }
+            })
+            .await?;
+
+        let ids: Vec<String> = result.take(0)?;
+        Ok(ids)
+    }
+
+    /// Live entities of `entity_types` whose ID is one of `ids` or whose
+    /// properties contain any of `terms` (lowercase) as a substring
+    pub async fn keyword_candidates(
+        &self,
+        entity_types: &[String],
+        ids: &[String],
+        terms: &[String],
+        limit: usize,
+    ) -> Result<Vec<Entity>> {
+        debug!("Keyword search over {:?} for {:?}", entity_types, terms);
+
+        let mut clauses = vec!["record::id(id) IN $ids".to_string()];
+        for i in 0..terms.len() {
+            clauses.push(format!("string::contains(string::lowercase(<string> properties), $term{})", i));
+        }
+        let query = format!(
+            "SELECT * FROM entity WHERE entity_type IN $types AND deleted_at = NONE AND ({}) LIMIT $limit",
+            clauses.join(" OR ")
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    let mut request = db
+                        .query(query)
+                        .bind(("types", entity_types.to_vec()))
+                        .bind(("ids", ids.to_vec()))
+                        .bind(("limit", limit));
+                    for (i, term) in terms.iter().enumerate() {
+                        request = request.bind((format!("term{}", i), term.clone()));
+                    }
+                    request.await.context("Failed to run keyword search")
+                }
+            })
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
     /// Query entities by type (including subtypes)
-    pub async fn query_entities_expanded(&self, entity_types: &[String]) -> Result<Vec<Entity>> {
+    pub async fn query_entities_expanded(
+        &self,
+        entity_types: &[String],
+        include_deleted: bool,
+    ) -> Result<Vec<Entity>> {
         debug!("Querying entities of types: {:?}", entity_types);
 
-        let types_owned = entity_types.to_vec();
+        let query = if include_deleted {
+            "SELECT * FROM entity WHERE entity_type IN $types"
+        } else {
+            "SELECT * FROM entity WHERE entity_type IN $types AND deleted_at = NONE"
+        };
+
         let mut result = self
-            .db
-            .query("SELECT * FROM entity WHERE entity_type IN $types")
-            .bind(("types", types_owned))
-            .await
-            .context("Failed to query entities")?;
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("types", entity_types.to_vec()))
+                    .await
+                    .context("Failed to query entities")
+            })
+            .await?;
 
         let entities: Vec<Entity> = result.take(0)?;
 
@@ -365,6 +858,209 @@ impl SurrealDBClient {
         Ok(entities)
     }
 
+    /// Names of the named vectors stored on live entities of a type
+    pub async fn named_vector_names(&self, entity_type: &str) -> Result<BTreeSet<String>> {
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT VALUE object::keys(named_embeddings) FROM entity WHERE entity_type = $entity_type AND named_embeddings != NONE AND deleted_at = NONE")
+                    .bind(("entity_type", entity_type.to_string()))
+                    .await
+                    .context("Failed to query named vectors")
+            })
+            .await?;
+
+        let names: Vec<Vec<String>> = result.take(0)?;
+        Ok(names.into_iter().flatten().collect())
+    }
+
+    /// Page through live entities of a type in id order, starting after `cursor`
+    pub async fn get_entities_page(
+        &self,
+        entity_type: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Entity>> {
+        debug!("Paging entities of type {} after {:?}", entity_type, cursor);
+
+        let query = if cursor.is_some() {
+            "SELECT * FROM entity WHERE entity_type = $type AND deleted_at = NONE AND id > type::thing('entity', $cursor) ORDER BY id LIMIT $limit"
+        } else {
+            "SELECT * FROM entity WHERE entity_type = $type AND deleted_at = NONE ORDER BY id LIMIT $limit"
+        };
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("type", entity_type.to_string()))
+                    .bind(("cursor", cursor.map(|c| c.to_string())))
+                    .bind(("limit", limit))
+                    .await
+                    .context("Failed to page entities")
+            })
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
+    /// Count live entities of a type
+    pub async fn count_entities(&self, entity_type: &str) -> Result<u64> {
+        self.count_records(
+            "SELECT count() AS count FROM entity WHERE entity_type = $type AND deleted_at = NONE GROUP ALL",
+            entity_type,
+        )
+        .await
+    }
+
+    /// Count relations of a type
+    pub async fn count_relations(&self, relation_type: &str) -> Result<u64> {
+        self.count_records(
+            "SELECT count() AS count FROM relation WHERE relation_type = $type GROUP ALL",
+            relation_type,
+        )
+        .await
+    }
+
+    /// Count stored agent events
+    pub async fn count_events(&self) -> Result<u64> {
+        self.count_records("SELECT count() AS count FROM agent_event GROUP ALL", "")
+            .await
+    }
+
+    async fn count_records(&self, query: &str, entity_type: &str) -> Result<u64> {
+        #[derive(Debug, Deserialize)]
+        struct CountRow {
+            count: u64,
+        }
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("type", entity_type.to_string()))
+                    .await
+                    .context("Failed to count records")
+            })
+            .await?;
+
+        let rows: Vec<CountRow> = result.take(0)?;
+        Ok(rows.first().map(|r| r.count).unwrap_or(0))
+    }
+
+    /// IDs of the traces run by `agent_id`, oldest first
+    pub async fn trace_ids_for_agent(&self, agent_id: &str) -> Result<Vec<String>> {
+        #[derive(Debug, Deserialize)]
+        struct TraceRow {
+            id: String,
+        }
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT record::id(id) AS id, start_time FROM agent_trace WHERE agent_id = $agent_id ORDER BY start_time")
+                    .bind(("agent_id", agent_id.to_string()))
+                    .await
+                    .context("Failed to query agent traces")
+            })
+            .await?;
+
+        let rows: Vec<TraceRow> = result.take(0)?;
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+
+    /// The subset of `ids` that belong to live entities
+    pub async fn existing_entity_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        self.existing_ids(
+            "SELECT VALUE record::id(id) FROM entity WHERE record::id(id) IN $ids AND deleted_at = NONE",
+            ids,
+        )
+        .await
+    }
+
+    /// The subset of `ids` that belong to stored agent events
+    pub async fn existing_event_ids(&self, ids: &[String]) -> Result<HashSet<String>> {
+        self.existing_ids(
+            "SELECT VALUE record::id(id) FROM agent_event WHERE record::id(id) IN $ids",
+            ids,
+        )
+        .await
+    }
+
+    async fn existing_ids(&self, query: &str, ids: &[String]) -> Result<HashSet<String>> {
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("ids", ids.to_vec()))
+                    .await
+                    .context("Failed to look up record ids")
+            })
+            .await?;
+
+        let existing: Vec<String> = result.take(0)?;
+        Ok(existing.into_iter().collect())
+    }
+
+    /// Replace the stored embedding of an entity.
+    /// Embeddings are derived data, so this does not bump the entity version.
+    pub async fn set_entity_embedding(&self, id: &str, embedding: Vec<f32>) -> Result<()> {
+        debug!("Setting embedding for entity: {}", id);
+
+        self.db()
+            .query("UPDATE type::thing('entity', $id) SET embedding = $embedding")
+            .bind(("id", id.to_string()))
+            .bind(("embedding", embedding))
+            .await
+            .context("Failed to set entity embedding")?
+            .check()
+            .context("Failed to set entity embedding")?;
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Collection Metadata
+    // ============================================================================
+
+    /// Record which provider, model and dimension produced a collection's vectors
+    pub async fn store_collection_meta(&self, meta: &EmbeddingMetadata) -> Result<()> {
+        debug!("Storing collection metadata for: {}", meta.id);
+
+        let mut content = serde_json::to_value(meta)?;
+        if let Some(obj) = content.as_object_mut() {
+            obj.remove("id");
+        }
+
+        self.db()
+            .query("UPSERT type::thing('collection_meta', $id) CONTENT $content")
+            .bind(("id", meta.id.clone()))
+            .bind(("content", content))
+            .await
+            .context("Failed to store collection metadata")?
+            .check()
+            .context("Failed to store collection metadata")?;
+
+        Ok(())
+    }
+
+    /// Get the recorded metadata for a collection, if any
+    pub async fn get_collection_meta(&self, collection: &str) -> Result<Option<EmbeddingMetadata>> {
+        debug!("Getting collection metadata for: {}", collection);
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT *, record::id(id) AS id FROM type::thing('collection_meta', $id)")
+                    .bind(("id", collection.to_string()))
+                    .await
+                    .context("Failed to get collection metadata")
+            })
+            .await?;
+
+        let meta: Vec<EmbeddingMetadata> = result.take(0)?;
+        Ok(meta.into_iter().next())
+    }
+
     // ============================================================================
     // Relation Operations
     // ============================================================================
@@ -386,7 +1082,7 @@ impl SurrealDBClient {
         );
 
         match self
-            .db
+            .db()
             .query(query)
             .bind(("relation_type", relation.relation_type.clone()))
             .bind(("source_id", relation.source_id.clone()))
@@ -405,15 +1101,107 @@ impl SurrealDBClient {
         }
     }
 
+    /// Create `relation` and the relations it implies in one transaction, so
+    /// either all of them are stored or none is.
+    ///
+    /// Each `(relation_type, source_id, target_id)` in `functional` is checked
+    /// inside the transaction: if the source already relates to another
+    /// target by that type, nothing is written and the error wraps
+    /// `VectaDBError::Conflict`. Implied relations that already exist are
+    /// skipped. Returns the IDs of the implied relations created.
+    pub async fn create_relation_with_implied(
+        &self,
+        relation: &Relation,
+        functional: &[(&str, &str, &str)],
+        implied: &[Relation],
+    ) -> Result<Vec<String>> {
+        debug!(
+            "Creating relation {} -> {} -> {} with {} implied",
+            relation.source_id, relation.relation_type, relation.target_id, implied.len()
+        );
+
+        let mut statements = vec!["BEGIN TRANSACTION".to_string()];
+        for i in 0..functional.len() {
+            statements.push(format!(
+                "LET $taken{i} = (SELECT VALUE target_id FROM relation WHERE relation_type = $ftype{i} AND source_id = $fsource{i} AND target_id != $ftarget{i})"
+            ));
+            statements.push(format!(
+                "IF array::len($taken{i}) > 0 {{ THROW string::concat($fmessage{i}, $taken{i}[0], \"'\") }}"
+            ));
+        }
+
+        // Same record IDs as `create_relation`
+        let writes: Vec<&Relation> = std::iter::once(relation).chain(implied).collect();
+        let create = |i: usize| {
+            format!(
+                "CREATE relation:⟨{}⟩ SET relation_type = $type{i}, source_id = $source{i}, target_id = $target{i}, properties = $properties{i}, created_at = time::now()",
+                writes[i].id_string()
+            )
+        };
+        statements.push(create(0));
+        for i in 1..writes.len() {
+            statements.push(format!(
+                "IF array::len((SELECT VALUE id FROM relation WHERE relation_type = $type{i} AND source_id = $source{i} AND target_id = $target{i})) = 0 {{ {} }}",
+                create(i)
+            ));
+        }
+        statements.push("COMMIT TRANSACTION".to_string());
+
+        let db = self.db();
+        let mut request = db.query(statements.join(";\n"));
+        for (i, (relation_type, source_id, target_id)) in functional.iter().enumerate() {
+            request = request
+                .bind((format!("ftype{}", i), relation_type.to_string()))
+                .bind((format!("fsource{}", i), source_id.to_string()))
+                .bind((format!("ftarget{}", i), target_id.to_string()))
+                .bind((
+                    format!("fmessage{}", i),
+                    format!("Relation '{}' is functional and '{}' already relates to '", relation_type, source_id),
+                ));
+        }
+        for (i, write) in writes.iter().enumerate() {
+            request = request
+                .bind((format!("type{}", i), write.relation_type.clone()))
+                .bind((format!("source{}", i), write.source_id.clone()))
+                .bind((format!("target{}", i), write.target_id.clone()))
+                .bind((format!("properties{}", i), serde_json::to_value(&write.properties)?));
+        }
+
+        let mut response = request.await.context("Failed to create relation")?;
+        let errors: Vec<String> = response.take_errors().into_values().map(|e| e.to_string()).collect();
+        if !errors.is_empty() {
+            // The other statements of the failed transaction report that they were not executed
+            if let Some(violation) = errors
+                .iter()
+                .filter(|message| message.contains("' is functional and '"))
+                .find_map(|message| message.find("Relation '").map(|start| message[start..].to_string()))
+            {
+                return Err(VectaDBError::Conflict(violation).into());
+            }
+            warn!("Failed to insert relation {}: {}", relation.relation_type, errors.join("; "));
+            return Err(anyhow::anyhow!("Failed to insert relation: {}", errors.join("; ")));
+        }
+
+        // Each implied create yields the new relation, or NONE when it was skipped.
+        // Transaction statements have no results, and each check holds two slots.
+        let first_implied = 2 * functional.len() + 1;
+        let mut created = Vec::new();
+        for i in 0..implied.len() {
+            let relation: Option<Relation> = response.take(first_implied + i)?;
+            created.extend(relation.map(|relation| relation.id_string()));
+        }
+        Ok(created)
+    }
+
     /// Get a relation by ID
     pub async fn get_relation(&self, id: &str) -> Result<Option<Relation>> {
         debug!("Getting relation: {}", id);
 
         let relation: Option<Relation> = self
-            .db
-            .select(("relation", id))
-            .await
-            .context("Failed to get relation")?;
+            .retry_on_disconnect(|db| async move {
+                db.select(("relation", id)).await.context("Failed to get relation")
+            })
+            .await?;
 
         Ok(relation)
     }
@@ -423,7 +1211,7 @@ impl SurrealDBClient {
         debug!("Deleting relation: {}", id);
 
         let _: Option<Relation> = self
-            .db
+            .db()
             .delete(("relation", id))
             .await
             .context("Failed to delete relation")?;
@@ -440,22 +1228,21 @@ impl SurrealDBClient {
     ) -> Result<Vec<Relation>> {
         debug!("Getting outgoing relations from: {}", entity_id);
 
-        let entity_id_owned = entity_id.to_string();
-
-        let mut result = if let Some(rel_type) = relation_type {
-            let rel_type_owned = rel_type.to_string();
-            self.db
-                .query("SELECT * FROM relation WHERE source_id = $entity_id AND relation_type = $rel_type")
-                .bind(("entity_id", entity_id_owned))
-                .bind(("rel_type", rel_type_owned))
-                .await
+        let query = if relation_type.is_some() {
+            "SELECT * FROM relation WHERE source_id = $entity_id AND relation_type = $rel_type"
         } else {
-            self.db
-                .query("SELECT * FROM relation WHERE source_id = $entity_id")
-                .bind(("entity_id", entity_id_owned))
-                .await
-        }
-        .context("Failed to query outgoing relations")?;
+            "SELECT * FROM relation WHERE source_id = $entity_id"
+        };
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("entity_id", entity_id.to_string()))
+                    .bind(("rel_type", relation_type.map(str::to_string)))
+                    .await
+                    .context("Failed to query outgoing relations")
+            })
+            .await?;
 
         let relations: Vec<Relation> = result.take(0)?;
 
@@ -471,22 +1258,21 @@ impl SurrealDBClient {
     ) -> Result<Vec<Relation>> {
         debug!("Getting incoming relations to: {}", entity_id);
 
-        let entity_id_owned = entity_id.to_string();
-
-        let mut result = if let Some(rel_type) = relation_type {
-            let rel_type_owned = rel_type.to_string();
-            self.db
-                .query("SELECT * FROM relation WHERE target_id = $entity_id AND relation_type = $rel_type")
-                .bind(("entity_id", entity_id_owned))
-                .bind(("rel_type", rel_type_owned))
-                .await
+        let query = if relation_type.is_some() {
+            "SELECT * FROM relation WHERE target_id = $entity_id AND relation_type = $rel_type"
         } else {
-            self.db
-                .query("SELECT * FROM relation WHERE target_id = $entity_id")
-                .bind(("entity_id", entity_id_owned))
-                .await
-        }
-        .context("Failed to query incoming relations")?;
+            "SELECT * FROM relation WHERE target_id = $entity_id"
+        };
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("entity_id", entity_id.to_string()))
+                    .bind(("rel_type", relation_type.map(str::to_string)))
+                    .await
+                    .context("Failed to query incoming relations")
+            })
+            .await?;
 
         let relations: Vec<Relation> = result.take(0)?;
 
@@ -533,7 +1319,7 @@ impl SurrealDBClient {
                     .await?;
 
                 for relation in relations {
-                    // Get target entity
+                    // Get target entity (tombstoned entities end the path)
                     if let Some(target) = self.get_entity(&relation.target_id).await? {
                         result.push(target.clone());
                         next_level.push(target.id_string());
@@ -551,12 +1337,806 @@ impl SurrealDBClient {
         debug!("Graph traversal found {} entities", result.len());
         Ok(result)
     }
+
+    // ============================================================================
+    // Export / Import
+    // ============================================================================
+
+    /// Read one chunk of records ordered by record id, starting after `cursor`.
+    /// Record ids are returned as plain strings in the `id` field.
+    pub async fn export_chunk(
+        &self,
+        table: ExportTable,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        debug!("Exporting {} records from {} after {:?}", limit, table.table_name(), cursor);
+
+        let query = if cursor.is_some() {
+            "SELECT *, record::id(id) AS id FROM type::table($table) WHERE id > type::thing($table, $cursor) ORDER BY id LIMIT $limit"
+        } else {
+            "SELECT *, record::id(id) AS id FROM type::table($table) ORDER BY id LIMIT $limit"
+        };
+
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(query)
+                    .bind(("table", table.table_name()))
+                    .bind(("cursor", cursor.map(|c| c.to_string())))
+                    .bind(("limit", limit))
+                    .await
+                    .context("Failed to read export chunk")
+            })
+            .await?;
+
+        let rows: Vec<serde_json::Value> = result.take(0)?;
+        Ok(rows)
+    }
+
+    /// Upsert one exported record, preserving its id.
+    /// Returns true if the record was created, false if an existing one was replaced.
+    pub async fn import_record(&self, table: ExportTable, record: &serde_json::Value) -> Result<bool> {
+        let mut content = record
+            .as_object()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Record must be a JSON object"))?;
+
+        let id = match content.remove("id") {
+            Some(serde_json::Value::String(id)) if !id.is_empty() => id,
+            _ => anyhow::bail!("Record is missing a string id"),
+        };
+
+        debug!("Importing {} record: {}", table.table_name(), id);
+
+        // Datetime columns come back from export as strings, so cast them explicitly
+        let upsert = match table {
+            ExportTable::Entity => {
+                "UPSERT type::thing($table, $id) SET entity_type = $content.entity_type, properties = $content.properties ?? {}, embedding = $content.embedding, metadata = $content.metadata, created_at = <datetime>($content.created_at ?? time::now()), updated_at = <datetime>($content.updated_at ?? time::now()), deleted_at = IF $content.deleted_at THEN <datetime>$content.deleted_at END, version = $content.version ?? 0"
+            }
+            ExportTable::Relation => {
+                "UPSERT type::thing($table, $id) SET relation_type = $content.relation_type, source_id = $content.source_id, target_id = $content.target_id, properties = $content.properties ?? {}, created_at = <datetime>($content.created_at ?? time::now())"
+            }
+            ExportTable::AgentEvent | ExportTable::AgentTrace => {
+                "UPSERT type::thing($table, $id) CONTENT $content"
+            }
+        };
+
+        let mut result = self
+            .db()
+            .query(format!("RETURN record::exists(type::thing($table, $id)); {}", upsert))
+            .bind(("table", table.table_name()))
+            .bind(("id", id.clone()))
+            .bind(("content", serde_json::Value::Object(content)))
+            .await
+            .context("Failed to import record")?;
+
+        let existed: Option<bool> = result.take(0)?;
+
+        // Surface per-statement errors from the upsert
+        let _: Vec<serde_json::Value> = result
+            .take(1)
+            .with_context(|| format!("Failed to upsert {} record {}", table.table_name(), id))?;
+
+        Ok(!existed.unwrap_or(false))
+    }
+
+    // ============================================================================
+    // Events
+    // ============================================================================
+
+    /// List agent events matching the filter, newest first (ties broken by id).
+    /// The time range applies to the event `timestamp` (start inclusive, end exclusive).
+    /// `before` is the stored `(timestamp, id)` of the last event already seen;
+    /// `offset` skips further events after that.
+    pub async fn list_events(
+        &self,
+        filter: &EventFilter,
+        before: Option<(&str, &str)>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        debug!("Listing events: {:?} before {:?}", filter, before);
+
+        let mut conditions = event_filter_conditions(filter);
+        // Same order as the ORDER BY, so the page continues exactly where the last one ended
+        if before.is_some() {
+            conditions.push("(timestamp < $before_ts OR (timestamp = $before_ts AND record::id(id) < $before_id))");
+        }
+
+        let scope = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let query = format!(
+            "SELECT *, record::id(id) AS id FROM agent_event {scope} ORDER BY timestamp DESC, id DESC LIMIT $limit START $offset"
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("agent_id", filter.agent_id.clone()))
+                        .bind(("trace_id", filter.trace_id.clone()))
+                        .bind(("event_type", filter.event_type.clone()))
+                        .bind(("start", filter.time_range.start.map(|t| t.to_rfc3339())))
+                        .bind(("end", filter.time_range.end.map(|t| t.to_rfc3339())))
+                        .bind(("before_ts", before.map(|(timestamp, _)| timestamp.to_string())))
+                        .bind(("before_id", before.map(|(_, id)| id.to_string())))
+                        .bind(("offset", offset))
+                        .bind(("limit", limit))
+                        .await
+                        .context("Failed to list events")
+                }
+            })
+            .await?;
+
+        let events: Vec<serde_json::Value> = result.take(0)?;
+
+        debug!("Found {} events", events.len());
+        Ok(events)
+    }
+
+    /// Subscribe to events created from now on that match the filter's agent,
+    /// trace and event type, using a LIVE SELECT. Writes from every VectaDB
+    /// instance sharing the database are seen. The stream ends when the
+    /// connection drops; callers resubscribe.
+    pub async fn subscribe_events(&self, filter: &EventFilter) -> Result<BoxStream<'static, Result<EventRecord>>> {
+        anyhow::ensure!(
+            self.supports_live_queries(),
+            "Live queries need a ws:// or wss:// SurrealDB endpoint, not {}://",
+            self.engine.scheme()
+        );
+        debug!("Subscribing to events: {:?}", filter);
+
+        let mut conditions = Vec::new();
+        if filter.agent_id.is_some() {
+            conditions.push("agent_id = $agent_id");
+        }
+        if filter.trace_id.is_some() {
+            conditions.push("trace_id = $trace_id");
+        }
+        if filter.event_type.is_some() {
+            conditions.push("event_type = $event_type");
+        }
+
+        let scope = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut result = self
+            .db()
+            .query(format!("LIVE SELECT *, record::id(id) AS id FROM agent_event {scope}"))
+            .bind(("agent_id", filter.agent_id.clone()))
+            .bind(("trace_id", filter.trace_id.clone()))
+            .bind(("event_type", filter.event_type.clone()))
+            .await
+            .context("Failed to start live event query")?;
+
+        let notifications = result
+            .stream::<Notification<EventRecord>>(0)
+            .context("Failed to stream live event query")?;
+
+        Ok(notifications
+            .filter_map(|notification| async move {
+                match notification {
+                    Ok(notification) if notification.action == Action::Create => Some(Ok(notification.data)),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(anyhow::Error::new(e).context("Live event query failed"))),
+                }
+            })
+            .boxed())
+    }
+
+    // ============================================================================
+    // Analytics
+    // ============================================================================
+
+    /// Token usage of events matching the filter, summed per `model_id`
+    pub async fn token_usage_by_model(&self, filter: &EventFilter) -> Result<Vec<ModelTokenUsage>> {
+        debug!("Summing token usage by model for {:?}", filter);
+
+        #[derive(Debug, Deserialize)]
+        struct UsageRow {
+            model_id: Option<String>,
+            events: u64,
+            events_without_tokens: u64,
+            input_tokens: f64,
+            output_tokens: f64,
+        }
+
+        let conditions = event_filter_conditions(filter);
+        let scope = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let query = format!(
+            "SELECT properties.model_id AS model_id, count() AS events,
+                count(properties.input_tokens = NONE AND properties.output_tokens = NONE) AS events_without_tokens,
+                math::sum(properties.input_tokens ?? 0) AS input_tokens,
+                math::sum(properties.output_tokens ?? 0) AS output_tokens
+             FROM agent_event {scope} GROUP BY model_id"
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("agent_id", filter.agent_id.clone()))
+                        .bind(("trace_id", filter.trace_id.clone()))
+                        .bind(("event_type", filter.event_type.clone()))
+                        .bind(("start", filter.time_range.start.map(|t| t.to_rfc3339())))
+                        .bind(("end", filter.time_range.end.map(|t| t.to_rfc3339())))
+                        .await
+                        .context("Failed to sum token usage by model")
+                }
+            })
+            .await?;
+
+        let rows: Vec<UsageRow> = result.take(0)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ModelTokenUsage {
+                model_id: row.model_id,
+                events: row.events,
+                events_without_tokens: row.events_without_tokens,
+                input_tokens: row.input_tokens as u64,
+                output_tokens: row.output_tokens as u64,
+            })
+            .collect())
+    }
+
+    /// Numeric values of the event property `property` over events matching
+    /// the filter. Events without the property, or with a non-numeric value,
+    /// are skipped.
+    pub async fn event_property_values(&self, filter: &EventFilter, property: &str) -> Result<Vec<f64>> {
+        debug!("Collecting event property {} for {:?}", property, filter);
+
+        // The property name is spliced into the query, so keep it to a plain identifier
+        if !is_plain_identifier(property) {
+            anyhow::bail!("Invalid property name '{}'", property);
+        }
+
+        let mut conditions: Vec<String> = event_filter_conditions(filter).into_iter().map(String::from).collect();
+        conditions.push(format!("type::is::number(properties.{property})"));
+        let query = format!(
+            "SELECT VALUE <float> properties.{property} FROM agent_event WHERE {}",
+            conditions.join(" AND ")
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("agent_id", filter.agent_id.clone()))
+                        .bind(("trace_id", filter.trace_id.clone()))
+                        .bind(("event_type", filter.event_type.clone()))
+                        .bind(("start", filter.time_range.start.map(|t| t.to_rfc3339())))
+                        .bind(("end", filter.time_range.end.map(|t| t.to_rfc3339())))
+                        .await
+                        .context("Failed to collect event property values")
+                }
+            })
+            .await?;
+
+        let values: Vec<f64> = result.take(0)?;
+        Ok(values)
+    }
+
+    /// Record counts per distinct value of `field`, a top-level or dotted
+    /// nested field such as `properties.model_id`, most common first. Also
+    /// returns how many distinct values there are in all.
+    pub async fn facet_counts(
+        &self,
+        table: ExportTable,
+        field: &str,
+        limit: usize,
+    ) -> Result<(Vec<FacetCount>, u64)> {
+        debug!("Counting {} values of {}", table.table_name(), field);
+
+        #[derive(Debug, Deserialize)]
+        struct CountRow {
+            count: u64,
+        }
+
+        // The field is spliced into the query, so keep it to dotted identifiers
+        if !is_field_path(field) {
+            anyhow::bail!("Invalid field '{}'", field);
+        }
+        // Soft-deleted entities are hidden like everywhere else
+        let scope = if table == ExportTable::Entity { "WHERE deleted_at = NONE" } else { "" };
+        let groups = format!(
+            "SELECT {field} AS value, count() AS count FROM {} {scope} GROUP BY value",
+            table.table_name()
+        );
+        let query = format!(
+            "SELECT value, count FROM ({groups}) ORDER BY count DESC, value LIMIT $limit;
+             SELECT count() AS count FROM ({groups}) GROUP ALL;"
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("limit", limit))
+                        .await
+                        .context("Failed to count field values")
+                }
+            })
+            .await?;
+
+        let facets: Vec<FacetCount> = result.take(0)?;
+        let distinct: Vec<CountRow> = result.take(1)?;
+        Ok((facets, distinct.first().map(|row| row.count).unwrap_or(0)))
+    }
+
+    /// Non-empty `interval_secs` buckets of the records in `[start, end)`,
+    /// keyed by bucket start in Unix seconds. With a `property`, buckets also
+    /// carry its sum and mean over the records where it is a number.
+    pub async fn time_buckets(
+        &self,
+        table: ExportTable,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+        interval_secs: i64,
+        property: Option<&str>,
+    ) -> Result<HashMap<i64, TimeBucket>> {
+        debug!("Bucketing {} by {}s from {} to {}", table.table_name(), interval_secs, start, end);
+
+        #[derive(Debug, Deserialize)]
+        struct CountRow {
+            bucket: f64,
+            count: u64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct StatsRow {
+            bucket: f64,
+            sum: f64,
+            avg: f64,
+        }
+
+        // The property name is spliced into the query, so keep it to a plain identifier
+        if let Some(property) = property.filter(|p| !is_plain_identifier(p)) {
+            anyhow::bail!("Invalid property name '{}'", property);
+        }
+
+        let time = format!("<datetime>{}", table.time_field());
+        let bucket = format!("math::floor(time::unix({time}) / $interval) * $interval AS bucket");
+        let mut conditions = vec![format!("{time} >= <datetime>$start"), format!("{time} < <datetime>$end")];
+        if table == ExportTable::Entity {
+            conditions.push("deleted_at = NONE".to_string());
+        }
+        let mut query = format!(
+            "SELECT {bucket}, count() AS count FROM {} WHERE {} GROUP BY bucket;",
+            table.table_name(),
+            conditions.join(" AND ")
+        );
+        if let Some(property) = property {
+            conditions.push(format!("type::is::number(properties.{property})"));
+            query.push_str(&format!(
+                "SELECT {bucket}, math::sum(<float> properties.{property}) AS sum, math::mean(<float> properties.{property}) AS avg FROM {} WHERE {} GROUP BY bucket;",
+                table.table_name(),
+                conditions.join(" AND ")
+            ));
+        }
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("interval", interval_secs))
+                        .bind(("start", start.to_rfc3339()))
+                        .bind(("end", end.to_rfc3339()))
+                        .await
+                        .context("Failed to bucket records by time")
+                }
+            })
+            .await?;
+
+        let counts: Vec<CountRow> = result.take(0)?;
+        let mut buckets: HashMap<i64, TimeBucket> = counts
+            .into_iter()
+            .filter_map(|row| {
+                let secs = row.bucket as i64;
+                let time = chrono::DateTime::from_timestamp(secs, 0)?;
+                Some((secs, TimeBucket { count: row.count, ..TimeBucket::empty(time) }))
+            })
+            .collect();
+        if property.is_some() {
+            let stats: Vec<StatsRow> = result.take(1)?;
+            for row in stats {
+                if let Some(bucket) = buckets.get_mut(&(row.bucket as i64)) {
+                    bucket.sum = Some(row.sum);
+                    bucket.avg = Some(row.avg);
+                }
+            }
+        }
+        Ok(buckets)
+    }
+
+    /// Aggregate event statistics, optionally scoped to a single agent.
+    /// All aggregation happens in SurrealDB; no event rows are loaded.
+    pub async fn get_event_analytics(&self, agent_id: Option<&str>) -> Result<EventAnalytics> {
+        debug!("Aggregating event analytics for agent: {:?}", agent_id);
+
+        #[derive(Debug, Deserialize)]
+        struct CountRow {
+            count: u64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct TypeCountRow {
+            event_type: Option<String>,
+            count: u64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct TokenRow {
+            input_tokens: f64,
+            output_tokens: f64,
+            total_tokens: f64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct LatencyRow {
+            avg_latency_ms: Option<f64>,
+        }
+
+        let (scope, scope_and) = if agent_id.is_some() {
+            ("WHERE agent_id = $agent_id", "WHERE agent_id = $agent_id AND")
+        } else {
+            ("", "WHERE")
+        };
+
+        let query = format!(
+            "SELECT count() AS count FROM agent_event {scope} GROUP ALL;
+             SELECT event_type, count() AS count FROM agent_event {scope} GROUP BY event_type;
+             SELECT count() AS count FROM agent_event {scope_and} (event_type = 'error' OR properties.level IN ['ERROR', 'CRITICAL', 'FATAL', 'error', 'critical', 'fatal']) GROUP ALL;
+             SELECT math::sum(properties.input_tokens ?? 0) AS input_tokens, math::sum(properties.output_tokens ?? 0) AS output_tokens, math::sum(properties.total_tokens ?? 0) AS total_tokens FROM agent_event {scope} GROUP ALL;
+             SELECT math::mean(properties.latency_ms) AS avg_latency_ms FROM agent_event {scope_and} properties.latency_ms != NONE GROUP ALL;"
+        );
+
+        let mut result = self
+            .retry_on_disconnect(|db| {
+                let query = query.clone();
+                async move {
+                    db.query(query)
+                        .bind(("agent_id", agent_id.map(|s| s.to_string())))
+                        .await
+                        .context("Failed to aggregate event analytics")
+                }
+            })
+            .await?;
+
+        let totals: Vec<CountRow> = result.take(0)?;
+        let by_type: Vec<TypeCountRow> = result.take(1)?;
+        let errors: Vec<CountRow> = result.take(2)?;
+        let tokens: Vec<TokenRow> = result.take(3)?;
+        let latency: Vec<LatencyRow> = result.take(4)?;
+
+        let token_usage = tokens
+            .first()
+            .map(|t| {
+                let input_tokens = t.input_tokens as u64;
+                let output_tokens = t.output_tokens as u64;
+                // Fall back to input + output when events don't report a total
+                let total_tokens = if t.total_tokens > 0.0 {
+                    t.total_tokens as u64
+                } else {
+                    input_tokens + output_tokens
+                };
+                TokenUsage {
+                    input_tokens,
+                    output_tokens,
+                    total_tokens,
+                }
+            })
+            .unwrap_or_default();
+
+        let analytics = EventAnalytics {
+            total_events: totals.first().map(|r| r.count).unwrap_or(0),
+            events_by_type: by_type
+                .into_iter()
+                .map(|r| (r.event_type.unwrap_or_else(|| "unknown".to_string()), r.count))
+                .collect(),
+            error_count: errors.first().map(|r| r.count).unwrap_or(0),
+            token_usage,
+            avg_latency_ms: latency.first().and_then(|r| r.avg_latency_ms),
+        };
+
+        debug!("Aggregated {} events", analytics.total_events);
+        Ok(analytics)
+    }
+
+    // ============================================================================
+    // Dead Letters
+    // ============================================================================
+
+    /// Keep an event that failed ingestion so it can be replayed later
+    pub async fn store_dead_letter(&self, event: &serde_json::Value, error: &str) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        debug!("Storing dead letter: {}", id);
+
+        self.db()
+            .query(
+                "CREATE type::thing('dead_letter', $id) SET event = $event, error = $error, attempts = 0, created_at = time::now()",
+            )
+            .bind(("id", id.clone()))
+            .bind(("event", event.clone()))
+            .bind(("error", error.to_string()))
+            .await
+            .context("Failed to store dead letter")?
+            .check()
+            .context("Failed to store dead letter")?;
+
+        Ok(id)
+    }
+
+    /// Oldest dead letters first
+    pub async fn list_dead_letters(&self, limit: usize) -> Result<Vec<DeadLetter>> {
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT *, record::id(id) AS id FROM dead_letter ORDER BY created_at ASC LIMIT $limit")
+                    .bind(("limit", limit))
+                    .await
+                    .context("Failed to list dead letters")
+            })
+            .await?;
+
+        let letters: Vec<DeadLetter> = result.take(0)?;
+        Ok(letters)
+    }
+
+    /// Record another failed replay of a dead letter
+    pub async fn record_dead_letter_failure(&self, id: &str, error: &str) -> Result<()> {
+        self.db()
+            .query("UPDATE type::thing('dead_letter', $id) SET error = $error, attempts += 1")
+            .bind(("id", id.to_string()))
+            .bind(("error", error.to_string()))
+            .await
+            .context("Failed to update dead letter")?
+            .check()
+            .context("Failed to update dead letter")?;
+
+        Ok(())
+    }
+
+    /// Remove a dead letter once it has been replayed
+    pub async fn delete_dead_letter(&self, id: &str) -> Result<()> {
+        self.db()
+            .query("DELETE type::thing('dead_letter', $id)")
+            .bind(("id", id.to_string()))
+            .await
+            .context("Failed to delete dead letter")?
+            .check()
+            .context("Failed to delete dead letter")?;
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Audit Log
+    // ============================================================================
+
+    /// Append a record to the audit log
+    pub async fn insert_audit_record(&self, record: &AuditRecord) -> Result<()> {
+        self.db()
+            .query(
+                "CREATE audit SET operation = $operation, target_type = $target_type, target_id = $target_id,
+                 principal = $principal, request_id = $request_id, changes = $changes,
+                 created_at = <datetime>$created_at",
+            )
+            .bind(("operation", serde_json::to_value(record.operation)?))
+            .bind(("target_type", record.target_type.clone()))
+            .bind(("target_id", record.target_id.clone()))
+            .bind(("principal", record.principal.clone()))
+            .bind(("request_id", record.request_id.clone()))
+            .bind(("changes", record.changes.clone()))
+            .bind(("created_at", record.created_at.to_raw()))
+            .await
+            .context("Failed to write audit record")?
+            .check()
+            .context("Failed to write audit record")?;
+
+        Ok(())
+    }
+
+    /// Most recent audit records first, optionally only those for one target
+    pub async fn list_audit_records(&self, target_id: Option<&str>, limit: usize) -> Result<Vec<AuditRecord>> {
+        let filter = if target_id.is_some() { "WHERE target_id = $target_id" } else { "" };
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query(format!(
+                    "SELECT * OMIT id FROM audit {} ORDER BY created_at DESC LIMIT $limit",
+                    filter
+                ))
+                .bind(("target_id", target_id.map(str::to_string)))
+                .bind(("limit", limit))
+                .await
+                .context("Failed to list audit records")
+            })
+            .await?;
+
+        let records: Vec<AuditRecord> = result.take(0)?;
+        Ok(records)
+    }
+
+    // ============================================================================
+    // Retention
+    // ============================================================================
+
+    /// IDs of up to `limit` events timestamped before `cutoff`
+    pub async fn expired_event_ids(&self, cutoff: chrono::DateTime<chrono::Utc>, limit: usize) -> Result<Vec<String>> {
+        let mut result = self
+            .retry_on_disconnect(|db| async move {
+                db.query("SELECT VALUE record::id(id) FROM agent_event WHERE <datetime>timestamp < <datetime>$cutoff LIMIT $limit")
+                    .bind(("cutoff", cutoff.to_rfc3339()))
+                    .bind(("limit", limit))
+                    .await
+                    .context("Failed to find expired events")
+            })
+            .await?;
+
+        let ids: Vec<String> = result.take(0)?;
+        Ok(ids)
+    }
+
+    /// Delete events by ID, along with their `contains` edges
+    pub async fn delete_events(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        debug!("Deleting {} events", ids.len());
+
+        self.db()
+            .query("FOR $id IN $ids { DELETE type::thing('agent_event', $id); }")
+            .bind(("ids", ids.to_vec()))
+            .await
+            .context("Failed to delete events")?
+            .check()
+            .context("Failed to delete events")?;
+
+        Ok(())
+    }
+
+    /// Delete up to `limit` traces started before `cutoff` that no longer
+    /// contain any events, returning how many were deleted
+    pub async fn delete_expired_traces(&self, cutoff: chrono::DateTime<chrono::Utc>, limit: usize) -> Result<usize> {
+        let mut result = self
+            .db()
+            .query(
+                "LET $ids = (SELECT VALUE record::id(id) FROM agent_trace WHERE <datetime>start_time < <datetime>$cutoff AND array::len((SELECT VALUE id FROM agent_event WHERE trace_id = record::id($parent.id) LIMIT 1)) = 0 LIMIT $limit);
+                 FOR $id IN $ids { DELETE type::thing('agent_trace', $id); };
+                 RETURN array::len($ids);",
+            )
+            .bind(("cutoff", cutoff.to_rfc3339()))
+            .bind(("limit", limit))
+            .await
+            .context("Failed to delete expired traces")?;
+
+        let deleted: Option<usize> = result.take(2)?;
+        Ok(deleted.unwrap_or(0))
+    }
+}
+
+/// WHERE conditions for an event filter, binding `$agent_id`, `$trace_id`,
+/// `$event_type`, `$start` and `$end`
+fn event_filter_conditions(filter: &EventFilter) -> Vec<&'static str> {
+    let mut conditions = Vec::new();
+    if filter.agent_id.is_some() {
+        conditions.push("agent_id = $agent_id");
+    }
+    if filter.trace_id.is_some() {
+        conditions.push("trace_id = $trace_id");
+    }
+    if filter.event_type.is_some() {
+        conditions.push("event_type = $event_type");
+    }
+    if filter.time_range.start.is_some() {
+        conditions.push("<datetime>timestamp >= <datetime>$start");
+    }
+    if filter.time_range.end.is_some() {
+        conditions.push("<datetime>timestamp < <datetime>$end");
+    }
+    conditions
+}
+
+/// Analyzer for full-text indexes: words split on whitespace, punctuation
+/// and letter/digit boundaries, matched case- and accent-insensitively
+const TEXT_ANALYZER: &str =
+    "DEFINE ANALYZER IF NOT EXISTS entity_text TOKENIZERS blank, class, punct FILTERS lowercase, ascii;";
+
+/// `DEFINE INDEX` statements for the properties `schema` marks as indexed or
+/// searchable, one per property name since all types share the entity table.
+/// Names that are not plain identifiers are skipped.
+fn property_index_statements(schema: &OntologySchema) -> Vec<String> {
+    let indexed = property_names(schema, |p| p.indexed).into_iter().map(|name| {
+        format!("DEFINE INDEX IF NOT EXISTS idx_prop_{name} ON entity COLUMNS properties.{name};")
+    });
+    let searchable = property_names(schema, |p| p.searchable);
+    let analyzer = (!searchable.is_empty()).then(|| TEXT_ANALYZER.to_string());
+    let text = searchable.into_iter().map(|name| {
+        format!(
+            "DEFINE INDEX IF NOT EXISTS idx_text_{name} ON entity FIELDS properties.{name} SEARCH ANALYZER entity_text BM25;"
+        )
+    });
+
+    indexed.chain(analyzer).chain(text).collect()
+}
+
+fn property_names(schema: &OntologySchema, pick: impl Fn(&PropertyDefinition) -> bool) -> BTreeSet<&str> {
+    let mut names = BTreeSet::new();
+    for property in schema.entity_types.values().flat_map(|t| &t.properties) {
+        if !pick(property) {
+            continue;
+        }
+        if !is_plain_identifier(&property.name) {
+            warn!("Not indexing property '{}': not a plain identifier", property.name);
+            continue;
+        }
+        names.insert(property.name.as_str());
+    }
+    names
+}
+
+/// Whether `path` is a field or dotted nested field made of plain identifiers
+pub(crate) fn is_field_path(path: &str) -> bool {
+    path.split('.').all(is_plain_identifier)
+}
+
+/// Whether `name` can be used unquoted as a SurrealQL field name
+pub(crate) fn is_plain_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Run `query`; on a connection error, `reconnect` and run it once more
+async fn retry_once<T, Q, QFut, R, RFut>(query: Q, reconnect: R) -> Result<T>
+where
+    Q: Fn() -> QFut,
+    QFut: Future<Output = Result<T>>,
+    R: FnOnce() -> RFut,
+    RFut: Future<Output = Result<()>>,
+{
+    match query().await {
+        Err(e) if is_connection_error(&e) => {
+            warn!("SurrealDB connection lost ({:#}), reconnecting", e);
+            reconnect().await.context("Failed to reconnect to SurrealDB")?;
+            query().await
+        }
+        result => result,
+    }
+}
+
+/// Whether an error means the connection is missing or broken, as opposed to
+/// a query the database rejected
+fn is_connection_error(error: &anyhow::Error) -> bool {
+    use surrealdb::error::Api;
+
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<surrealdb::Error>(),
+            Some(surrealdb::Error::Api(
+                Api::ConnectionUninitialised | Api::Http(_) | Api::Ws(_)
+            ))
+        )
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::SurrealDBConfig;
+    use std::collections::HashMap;
 
     fn test_config() -> DatabaseConfig {
         DatabaseConfig {
@@ -566,15 +2146,188 @@ mod tests {
                 database: "test".to_string(),
                 username: "root".to_string(),
                 password: "root".to_string(),
+                pool_size: 1,
             },
             qdrant: crate::config::QdrantConfig {
                 url: "http://localhost:6333".to_string(),
                 api_key: None,
                 collection_prefix: "test_".to_string(),
+                warmup_collections: false,
+                collection: Default::default(),
             },
         }
     }
 
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_subscribe_events_receives_created_event() {
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        assert!(client.supports_live_queries());
+
+        let filter = EventFilter {
+            trace_id: Some("live-test".to_string()),
+            ..Default::default()
+        };
+        let mut events = client.subscribe_events(&filter).await.unwrap();
+
+        let now = chrono::Utc::now().to_rfc3339();
+        for (id, trace_id) in [("live-other", "other-trace"), ("live-1", "live-test")] {
+            client
+                .db()
+                .query("CREATE agent_event CONTENT { id: $id, trace_id: $trace_id, timestamp: $now, event_type: 'tool_call', properties: { tool: 'search' }, created_at: $now, updated_at: $now }")
+                .bind(("id", id))
+                .bind(("trace_id", trace_id))
+                .bind(("now", now.clone()))
+                .await
+                .unwrap();
+        }
+
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), events.next())
+            .await
+            .expect("create notification within 5s")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.id, "live-1");
+        assert_eq!(event.trace_id, "live-test");
+        assert_eq!(event.event_type.as_deref(), Some("tool_call"));
+        assert_eq!(event.properties["tool"], "search");
+
+        // Cleanup
+        for id in ["live-other", "live-1"] {
+            let _: Option<serde_json::Value> = client.db().delete(("agent_event", id)).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_once_reconnects_after_transient_failure() {
+        let attempts = AtomicUsize::new(0);
+        let reconnects = AtomicUsize::new(0);
+
+        let result = retry_once(
+            || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(anyhow::Error::new(surrealdb::Error::from(
+                        surrealdb::error::Api::ConnectionUninitialised,
+                    ))
+                    .context("Failed to get entity"))
+                } else {
+                    Ok(42)
+                }
+            },
+            || async {
+                reconnects.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
+
+        // Errors from the query itself are not retried
+        attempts.store(0, Ordering::SeqCst);
+        let result: Result<()> = retry_once(
+            || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(anyhow::anyhow!("Parse error in query"))
+            },
+            || async {
+                reconnects.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(reconnects.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_disconnected_client_connects_on_first_query() {
+        let client = SurrealDBClient::disconnected(&test_config()).unwrap();
+        assert!(!client.is_connected());
+
+        // The first query hits an unconnected pool, reconnects and succeeds
+        assert!(client.get_entity("no-such-entity").await.unwrap().is_none());
+        assert!(client.is_connected());
+    }
+
+    #[test]
+    fn test_property_index_statements() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+
+        let mut schema = OntologySchema::new("index://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Task".to_string(), "Task".to_string())
+                .with_property(PropertyDefinition::new("status".to_string(), PropertyType::String).indexed())
+                .with_property(PropertyDefinition::new("agent_id".to_string(), PropertyType::String).indexed())
+                .with_property(PropertyDefinition::new("notes".to_string(), PropertyType::String)),
+        );
+        schema.add_entity_type(
+            EntityType::new("Run".to_string(), "Run".to_string())
+                .with_property(PropertyDefinition::new("status".to_string(), PropertyType::String).indexed())
+                .with_property(PropertyDefinition::new("bad name".to_string(), PropertyType::String).indexed()),
+        );
+
+        assert_eq!(
+            property_index_statements(&schema),
+            vec![
+                "DEFINE INDEX IF NOT EXISTS idx_prop_agent_id ON entity COLUMNS properties.agent_id;",
+                "DEFINE INDEX IF NOT EXISTS idx_prop_status ON entity COLUMNS properties.status;",
+            ]
+        );
+
+        // Searchable properties get a full-text index, after the shared analyzer
+        schema.add_entity_type(
+            EntityType::new("Note".to_string(), "Note".to_string())
+                .with_property(PropertyDefinition::new("body".to_string(), PropertyType::String).searchable()),
+        );
+        let statements = property_index_statements(&schema);
+        assert_eq!(statements.len(), 4);
+        assert_eq!(statements[2], TEXT_ANALYZER);
+        assert_eq!(
+            statements[3],
+            "DEFINE INDEX IF NOT EXISTS idx_text_body ON entity FIELDS properties.body SEARCH ANALYZER entity_text BM25;"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_store_schema_defines_property_indexes() {
+        use crate::ontology::entity_type::{EntityType, PropertyDefinition, PropertyType};
+
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        let mut schema = OntologySchema::new("index://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("IndexedTask".to_string(), "Task".to_string())
+                .with_property(PropertyDefinition::new("status".to_string(), PropertyType::String).indexed()),
+        );
+        client.store_schema(&schema).await.unwrap();
+
+        let mut result = client.db().query("INFO FOR TABLE entity").await.unwrap();
+        let info: Option<serde_json::Value> = result.take(0).unwrap();
+        assert!(info.unwrap()["indexes"].get("idx_prop_status").is_some());
+    }
+
+    #[test]
+    fn test_engine_from_endpoint_scheme() {
+        let cases = [
+            ("localhost:8000", SurrealEngine::Http, "localhost:8000"),
+            ("http://localhost:8000", SurrealEngine::Http, "localhost:8000"),
+            ("https://db.example.com", SurrealEngine::Https, "db.example.com"),
+            ("ws://localhost:8000", SurrealEngine::Ws, "localhost:8000"),
+            ("WSS://db.example.com:443/", SurrealEngine::Wss, "db.example.com:443"),
+        ];
+        for (endpoint, engine, address) in cases {
+            assert_eq!(SurrealEngine::from_endpoint(endpoint).unwrap(), (engine, address), "{}", endpoint);
+        }
+
+        assert!(SurrealEngine::from_endpoint("mem://").is_err());
+        assert!(SurrealEngine::from_endpoint("ws://").is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Requires SurrealDB running
     async fn test_connection() {
@@ -591,4 +2344,564 @@ mod tests {
         let healthy = client.health_check().await.unwrap();
         assert!(healthy);
     }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_concurrent_update_rejected() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        let entity = Entity::new("VersionTest".to_string(), HashMap::new());
+        let id = client.create_entity(&entity).await.unwrap();
+        let base = client.get_entity(&id).await.unwrap().unwrap();
+        assert_eq!(base.version, 1);
+
+        // Two writers start from the same version
+        let mut first = base.clone();
+        first.properties.insert("writer".to_string(), serde_json::json!("first"));
+        let mut second = base.clone();
+        second.properties.insert("writer".to_string(), serde_json::json!("second"));
+
+        let updated = client.update_entity(&id, &first, base.version).await.unwrap();
+        assert_eq!(updated.unwrap().version, 2);
+
+        let rejected = client.update_entity(&id, &second, base.version).await.unwrap();
+        assert!(rejected.is_none());
+
+        let stored = client.get_entity(&id).await.unwrap().unwrap();
+        assert_eq!(stored.version, 2);
+        assert_eq!(stored.properties.get("writer"), Some(&serde_json::json!("first")));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_entities_page_resumes_from_cursor() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        client.db().query("DELETE entity WHERE entity_type = 'PageTest'").await.unwrap();
+        for _ in 0..5 {
+            let entity = Entity::new("PageTest".to_string(), HashMap::new());
+            client.create_entity(&entity).await.unwrap();
+        }
+
+        let first = client.get_entities_page("PageTest", None, 3).await.unwrap();
+        assert_eq!(first.len(), 3);
+
+        let cursor = first.last().unwrap().id_string();
+        let rest = client.get_entities_page("PageTest", Some(&cursor), 3).await.unwrap();
+        assert_eq!(rest.len(), 2);
+        assert!(rest.iter().all(|e| first.iter().all(|f| f.id_string() != e.id_string())));
+
+        client.set_entity_embedding(&cursor, vec![0.5; 4]).await.unwrap();
+        let entity = client.get_entity(&cursor).await.unwrap().unwrap();
+        assert_eq!(entity.embedding, Some(vec![0.5; 4]));
+        assert_eq!(entity.version, 1);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_collection_meta_round_trip() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        let meta = EmbeddingMetadata::for_collection("MetaTest", "voyage", "voyage-3", 1024);
+        client.store_collection_meta(&meta).await.unwrap();
+
+        let stored = client.get_collection_meta("MetaTest").await.unwrap().unwrap();
+        assert_eq!(stored.id, "MetaTest");
+        assert_eq!(stored.provider, "voyage");
+        assert_eq!(stored.dimension, 1024);
+        assert!(stored.validate_dimension(1024).is_ok());
+        assert!(stored.validate_dimension(1536).is_err());
+
+        assert!(client.get_collection_meta("NoSuchCollection").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_soft_delete_and_purge() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        let entity = Entity::new("SoftDeleteTest".to_string(), HashMap::new());
+        let id = client.create_entity(&entity).await.unwrap();
+
+        client.delete_entity(&id).await.unwrap();
+
+        // Hidden from default reads
+        assert!(client.get_entity(&id).await.unwrap().is_none());
+        let live = client.query_entities("SoftDeleteTest", false).await.unwrap();
+        assert!(live.iter().all(|e| e.id_string() != id));
+
+        // Still readable when asked
+        let tombstoned = client.get_entity_including_deleted(&id).await.unwrap().unwrap();
+        assert!(tombstoned.is_deleted());
+        let all = client.query_entities("SoftDeleteTest", true).await.unwrap();
+        assert!(all.iter().any(|e| e.id_string() == id));
+
+        // Purge removes the row entirely
+        client.purge_entity(&id).await.unwrap();
+        assert!(client.get_entity_including_deleted(&id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_list_events_time_range() {
+        use crate::db::TimeRange;
+
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        // One event per hour from 10:00 to 14:00, under an agent unique to this run
+        let agent_id = format!("time-range-{}", nanoid::nanoid!());
+        for hour in 10..15 {
+            let timestamp = format!("2025-01-15T{}:00:00+00:00", hour);
+            let event = serde_json::json!({
+                "id": nanoid::nanoid!(),
+                "trace_id": "time-range-trace",
+                "timestamp": timestamp,
+                "agent_id": agent_id,
+                "properties": { "hour": hour },
+                "created_at": timestamp,
+                "updated_at": timestamp,
+            });
+            client.import_record(ExportTable::AgentEvent, &event).await.unwrap();
+        }
+
+        let filter = EventFilter {
+            agent_id: Some(agent_id),
+            time_range: TimeRange::new(
+                Some("2025-01-15T11:00:00Z".parse().unwrap()),
+                Some("2025-01-15T13:00:00Z".parse().unwrap()),
+            ),
+            ..Default::default()
+        };
+        let events = client.list_events(&filter, None, 0, 100).await.unwrap();
+
+        // 11:00 is included, 13:00 is not; newest first
+        let hours: Vec<i64> = events
+            .iter()
+            .map(|e| e["properties"]["hour"].as_i64().unwrap())
+            .collect();
+        assert_eq!(hours, vec![12, 11]);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_retention_purges_only_expired_events() {
+        use crate::db::retention::{purge_expired, RetentionPolicy};
+
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        let run = uuid::Uuid::new_v4();
+        let old_trace = format!("retention-old-{}", run);
+        let recent_trace = format!("retention-recent-{}", run);
+        let long_trace = format!("retention-long-{}", run);
+
+        // Timestamps long before any other test data, with the clock pinned just after them
+        for (trace_id, start_time, timestamp) in [
+            (&old_trace, "1990-01-01T00:00:00+00:00", "1990-01-01T00:00:00+00:00"),
+            (&recent_trace, "1990-03-25T00:00:00+00:00", "1990-03-25T00:00:00+00:00"),
+            // Started long ago but still has recent events
+            (&long_trace, "1990-01-01T00:00:00+00:00", "1990-03-25T00:00:00+00:00"),
+        ] {
+            client
+                .import_record(
+                    ExportTable::AgentTrace,
+                    &serde_json::json!({
+                        "id": trace_id,
+                        "session_id": trace_id,
+                        "status": "completed",
+                        "start_time": start_time,
+                        "created_at": start_time,
+                        "updated_at": timestamp,
+                    }),
+                )
+                .await
+                .unwrap();
+            for n in 0..3 {
+                client
+                    .import_record(
+                        ExportTable::AgentEvent,
+                        &serde_json::json!({
+                            "id": format!("{}-{}", trace_id, n),
+                            "trace_id": trace_id,
+                            "timestamp": timestamp,
+                            "properties": {},
+                            "created_at": timestamp,
+                            "updated_at": timestamp,
+                        }),
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let policy = RetentionPolicy {
+            max_age: chrono::Duration::days(30),
+            interval: std::time::Duration::from_secs(60),
+            batch_size: 2,
+        };
+        let now: chrono::DateTime<chrono::Utc> = "1990-04-01T00:00:00Z".parse().unwrap();
+        let stats = purge_expired(&client, None, &policy, now).await.unwrap();
+        assert_eq!(stats.events, 3);
+        assert_eq!(stats.traces, 1);
+
+        let events = |trace_id: &str| {
+            let filter = EventFilter {
+                trace_id: Some(trace_id.to_string()),
+                ..Default::default()
+            };
+            let client = &client;
+            async move { client.list_events(&filter, None, 0, 10).await.unwrap().len() }
+        };
+        assert_eq!(events(&old_trace).await, 0);
+        assert_eq!(events(&recent_trace).await, 3);
+        assert_eq!(events(&long_trace).await, 3);
+
+        // Nothing further has expired
+        let again = purge_expired(&client, None, &policy, now).await.unwrap();
+        assert_eq!(again, Default::default());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_event_cursor_paging_has_no_duplicates_or_gaps() {
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        let trace_id = format!("page-trace-{}", uuid::Uuid::new_v4());
+
+        // Five events, two sharing a timestamp
+        let create = |id: String, timestamp: &'static str| {
+            let trace_id = trace_id.clone();
+            let client = &client;
+            async move {
+                let event = serde_json::json!({
+                    "id": id,
+                    "trace_id": trace_id,
+                    "timestamp": timestamp,
+                    "properties": {},
+                    "created_at": timestamp,
+                    "updated_at": timestamp,
+                });
+                client.import_record(ExportTable::AgentEvent, &event).await.unwrap();
+            }
+        };
+        for (n, timestamp) in [
+            (1, "2025-01-15T10:00:00+00:00"),
+            (2, "2025-01-15T11:00:00+00:00"),
+            (3, "2025-01-15T11:00:00+00:00"),
+            (4, "2025-01-15T12:00:00+00:00"),
+            (5, "2025-01-15T13:00:00+00:00"),
+        ] {
+            create(format!("{}-{}", trace_id, n), timestamp).await;
+        }
+
+        let filter = EventFilter {
+            trace_id: Some(trace_id.clone()),
+            ..Default::default()
+        };
+        let mut seen: Vec<String> = Vec::new();
+        let mut before: Option<(String, String)> = None;
+        loop {
+            let page = client
+                .list_events(&filter, before.as_ref().map(|(t, i)| (t.as_str(), i.as_str())), 0, 2)
+                .await
+                .unwrap();
+            seen.extend(page.iter().map(|e| e["id"].as_str().unwrap().to_string()));
+
+            // A newer event arriving mid-iteration must not shift later pages
+            if seen.len() == 2 {
+                create(format!("{}-6", trace_id), "2025-01-15T14:00:00+00:00").await;
+            }
+
+            match page.last() {
+                Some(last) if page.len() == 2 => {
+                    before = Some((
+                        last["timestamp"].as_str().unwrap().to_string(),
+                        last["id"].as_str().unwrap().to_string(),
+                    ))
+                }
+                _ => break,
+            }
+        }
+
+        let expected: Vec<String> = [5, 4, 3, 2, 1].iter().map(|n| format!("{}-{}", trace_id, n)).collect();
+        assert_eq!(seen, expected);
+
+        // Cleanup
+        for n in 1..=6 {
+            let _: Option<serde_json::Value> = client
+                .db()
+                .delete(("agent_event", format!("{}-{}", trace_id, n)))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_export_chunks_cover_table() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        for i in 0..5 {
+            let mut properties = HashMap::new();
+            properties.insert("index".to_string(), serde_json::json!(i));
+            let entity = Entity::new("ExportTest".to_string(), properties);
+            client.create_entity(&entity).await.unwrap();
+        }
+
+        // Page through the table in small chunks and check every line parses
+        let mut cursor: Option<String> = None;
+        let mut seen = std::collections::HashSet::new();
+        loop {
+            let rows = client
+                .export_chunk(ExportTable::Entity, cursor.as_deref(), 2)
+                .await
+                .unwrap();
+            if rows.is_empty() {
+                break;
+            }
+            for row in &rows {
+                let line = serde_json::to_string(row).unwrap();
+                let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+                let id = parsed["id"].as_str().unwrap().to_string();
+                assert!(seen.insert(id), "record exported twice");
+            }
+            cursor = rows.last().and_then(|r| r["id"].as_str()).map(String::from);
+        }
+
+        assert!(seen.len() >= 5);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_export_import_round_trip() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+
+        let mut properties = HashMap::new();
+        properties.insert("name".to_string(), serde_json::json!("RoundTrip"));
+        let sample = Entity::new("RoundTripTest".to_string(), properties);
+        let sample_id = client.create_entity(&sample).await.unwrap();
+        for _ in 0..3 {
+            let entity = Entity::new("RoundTripTest".to_string(), HashMap::new());
+            client.create_entity(&entity).await.unwrap();
+        }
+
+        // Export everything
+        let mut exported = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let rows = client
+                .export_chunk(ExportTable::Entity, cursor.as_deref(), 100)
+                .await
+                .unwrap();
+            if rows.is_empty() {
+                break;
+            }
+            cursor = rows.last().and_then(|r| r["id"].as_str()).map(String::from);
+            exported.extend(rows);
+        }
+
+        // Wipe and re-import
+        client.db().query("DELETE entity").await.unwrap();
+        let mut created = 0;
+        for record in &exported {
+            if client.import_record(ExportTable::Entity, record).await.unwrap() {
+                created += 1;
+            }
+        }
+        assert_eq!(created, exported.len());
+
+        let restored = client.export_chunk(ExportTable::Entity, None, 10_000).await.unwrap();
+        assert_eq!(restored.len(), exported.len());
+
+        let entity = client.get_entity(&sample_id).await.unwrap().unwrap();
+        assert_eq!(entity.entity_type, "RoundTripTest");
+        assert_eq!(entity.properties.get("name"), Some(&serde_json::json!("RoundTrip")));
+
+        // Importing again updates in place
+        assert!(!client.import_record(ExportTable::Entity, &exported[0]).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_event_analytics() {
+        let config = test_config();
+        let client = SurrealDBClient::new(&config).await.unwrap();
+        let agent_id = format!("analytics-test-{}", nanoid::nanoid!(8));
+
+        let events = [
+            ("llm_call", serde_json::json!({"input_tokens": 100, "output_tokens": 50, "total_tokens": 150, "latency_ms": 200})),
+            ("llm_call", serde_json::json!({"input_tokens": 300, "output_tokens": 100, "total_tokens": 400, "latency_ms": 400})),
+            ("tool_call", serde_json::json!({"tool_name": "search"})),
+            ("error", serde_json::json!({"message": "timeout", "latency_ms": 600})),
+        ];
+
+        for (event_type, properties) in events {
+            let now = chrono::Utc::now().to_rfc3339();
+            client
+                .db()
+                .query("CREATE agent_event CONTENT { id: $id, trace_id: 'analytics-test', timestamp: $now, event_type: $event_type, agent_id: $agent_id, properties: $properties, created_at: $now, updated_at: $now }")
+                .bind(("id", uuid::Uuid::new_v4().to_string()))
+                .bind(("now", now))
+                .bind(("event_type", event_type.to_string()))
+                .bind(("agent_id", agent_id.clone()))
+                .bind(("properties", properties))
+                .await
+                .unwrap();
+        }
+
+        let analytics = client.get_event_analytics(Some(&agent_id)).await.unwrap();
+
+        assert_eq!(analytics.total_events, 4);
+        assert_eq!(analytics.events_by_type.get("llm_call"), Some(&2));
+        assert_eq!(analytics.events_by_type.get("tool_call"), Some(&1));
+        assert_eq!(analytics.error_count, 1);
+        assert_eq!(analytics.error_rate(), 0.25);
+        assert_eq!(analytics.token_usage.input_tokens, 400);
+        assert_eq!(analytics.token_usage.output_tokens, 150);
+        assert_eq!(analytics.token_usage.total_tokens, 550);
+        assert_eq!(analytics.avg_latency_ms, Some(400.0));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_facet_counts() {
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        let trace_id = format!("facets-test-{}", nanoid::nanoid!(8));
+
+        let events = [
+            ("llm_call", Some("gpt-4o")),
+            ("llm_call", Some("gpt-4o")),
+            ("llm_call", Some("claude")),
+            ("tool_call", None),
+            ("error", None),
+        ];
+        for (event_type, model_id) in events {
+            let now = chrono::Utc::now().to_rfc3339();
+            client
+                .db()
+                .query("CREATE agent_event CONTENT { id: $id, trace_id: $trace_id, timestamp: $now, event_type: $event_type, properties: { facet_model: $model_id }, created_at: $now, updated_at: $now }")
+                .bind(("id", uuid::Uuid::new_v4().to_string()))
+                .bind(("trace_id", trace_id.clone()))
+                .bind(("now", now))
+                .bind(("event_type", event_type.to_string()))
+                .bind(("model_id", model_id.map(str::to_string)))
+                .await
+                .unwrap();
+        }
+
+        // Only this test's events hold its trace id, once each
+        let (facets, distinct) = client.facet_counts(ExportTable::AgentEvent, "trace_id", 10_000).await.unwrap();
+        assert!(distinct >= 1);
+        let ours = facets.iter().find(|facet| facet.value == serde_json::json!(trace_id));
+        assert_eq!(ours.map(|facet| facet.count), Some(5));
+
+        let (by_type, _) = client.facet_counts(ExportTable::AgentEvent, "event_type", 10_000).await.unwrap();
+        assert!(by_type.windows(2).all(|pair| pair[0].count >= pair[1].count));
+        let count = |value: &str| by_type.iter().find(|f| f.value == serde_json::json!(value)).map(|f| f.count);
+        assert!(count("llm_call").unwrap() >= 3);
+        assert!(count("tool_call").unwrap() >= 1);
+
+        let (top, _) = client.facet_counts(ExportTable::AgentEvent, "event_type", 1).await.unwrap();
+        assert_eq!(top, by_type[..1]);
+
+        // Nested fields
+        let (facets, _) = client
+            .facet_counts(ExportTable::AgentEvent, "properties.facet_model", 10_000)
+            .await
+            .unwrap();
+        let count = |value: serde_json::Value| facets.iter().find(|f| f.value == value).map(|f| f.count);
+        assert_eq!(count(serde_json::json!("gpt-4o")), Some(2));
+        assert_eq!(count(serde_json::json!("claude")), Some(1));
+
+        let _ = client
+            .db()
+            .query("DELETE agent_event WHERE trace_id = $trace_id")
+            .bind(("trace_id", trace_id))
+            .await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_time_buckets_over_several_hours() {
+        let client = SurrealDBClient::new(&test_config()).await.unwrap();
+        let trace_id = format!("timeseries-test-{}", nanoid::nanoid!(8));
+        // Far in the past so no other test's events fall in the range
+        let base: chrono::DateTime<chrono::Utc> = "1991-06-01T10:00:00Z".parse().unwrap();
+
+        // Two events in the first hour, none in the second, three in the fourth
+        let events = [(5, Some(100)), (50, Some(300)), (185, None), (190, Some(20)), (230, Some(40))];
+        for (minutes, latency_ms) in events {
+            let timestamp = (base + chrono::Duration::minutes(minutes)).to_rfc3339();
+            client
+                .db()
+                .query("CREATE agent_event CONTENT { id: $id, trace_id: $trace_id, timestamp: $timestamp, properties: { latency_ms: $latency_ms }, created_at: $timestamp, updated_at: $timestamp }")
+                .bind(("id", uuid::Uuid::new_v4().to_string()))
+                .bind(("trace_id", trace_id.clone()))
+                .bind(("timestamp", timestamp))
+                .bind(("latency_ms", latency_ms))
+                .await
+                .unwrap();
+        }
+
+        let end = base + chrono::Duration::hours(4);
+        let found = client
+            .time_buckets(ExportTable::AgentEvent, base, end, 3600, Some("latency_ms"))
+            .await
+            .unwrap();
+        let buckets = crate::db::zero_filled_buckets(base, end, 3600, found);
+
+        let counts: Vec<u64> = buckets.iter().map(|bucket| bucket.count).collect();
+        assert_eq!(counts, vec![2, 0, 0, 3]);
+        assert_eq!(buckets[0].start, base);
+        assert_eq!(buckets[0].sum, Some(400.0));
+        assert_eq!(buckets[0].avg, Some(200.0));
+        assert_eq!(buckets[1].sum, None);
+        // The event without a latency is counted but not averaged
+        assert_eq!(buckets[3].avg, Some(30.0));
+
+        let _ = client
+            .db()
+            .query("DELETE agent_event WHERE trace_id = $trace_id")
+            .bind(("trace_id", trace_id))
+            .await;
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB running
+    async fn test_pooled_reads_run_concurrently() {
+        let mut config = test_config();
+        config.surrealdb.pool_size = 4;
+        let client = SurrealDBClient::new(&config).await.unwrap();
+        assert_eq!(client.pool.read().unwrap().len(), 4);
+
+        let entity = Entity::new("PoolTest".to_string(), HashMap::new());
+        let id = client.create_entity(&entity).await.unwrap();
+        const READS: usize = 64;
+
+        let start = std::time::Instant::now();
+        for _ in 0..READS {
+            client.get_entity(&id).await.unwrap().unwrap();
+        }
+        let sequential = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let reads = (0..READS).map(|_| client.get_entity(&id));
+        let entities = futures::future::try_join_all(reads).await.unwrap();
+        let concurrent = start.elapsed();
+
+        assert!(entities.iter().all(|e| e.as_ref().map(|e| e.id_string()) == Some(id.clone())));
+        assert!(
+            concurrent < sequential,
+            "{} concurrent reads took {:?}, sequential took {:?}",
+            READS,
+            concurrent,
+            sequential
+        );
+
+        client.purge_entity(&id).await.unwrap();
+    }
 }
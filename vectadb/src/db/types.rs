@@ -2,21 +2,44 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use chrono::{DateTime, Utc};
 use surrealdb::sql::{Datetime, Thing};
+use utoipa::ToSchema;
 
 /// Entity stored in the database
 /// Note: id is Thing type for proper SurrealDB deserialization
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Entity {
+    #[schema(value_type = String)]
     pub id: Thing,
     pub entity_type: String,
     pub properties: HashMap<String, serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+    /// Extra embeddings searched by name, e.g. a tool call's input and output
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub named_embeddings: HashMap<String, Vec<f32>>,
+    /// Term weights (SPLADE, BM25) stored as a sparse vector next to the
+    /// embedding, for hybrid search
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sparse_vector: HashMap<String, f32>,
+    #[schema(value_type = String, format = DateTime)]
     pub created_at: Datetime,
+    #[schema(value_type = String, format = DateTime)]
     pub updated_at: Datetime,
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     pub metadata: HashMap<String, String>,
+    /// Tombstone set by soft delete; tombstoned entities are hidden from reads by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub deleted_at: Option<Datetime>,
+    /// Incremented on every write; used for optimistic concurrency control
+    #[serde(default)]
+    pub version: u64,
+    /// Version of the ontology the entity was validated against when last
+    /// written; `None` if no ontology defined its type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
 }
 
 impl Entity {
@@ -27,9 +50,14 @@ impl Entity {
             entity_type,
             properties,
             embedding: None,
+            named_embeddings: HashMap::new(),
+            sparse_vector: HashMap::new(),
             created_at: Datetime::default(),
             updated_at: Datetime::default(),
             metadata: HashMap::new(),
+            deleted_at: None,
+            version: 1,
+            schema_version: None,
         }
     }
 
@@ -43,10 +71,30 @@ impl Entity {
         self
     }
 
+    pub fn with_named_embedding(mut self, name: impl Into<String>, embedding: Vec<f32>) -> Self {
+        self.named_embeddings.insert(name.into(), embedding);
+        self
+    }
+
+    pub fn with_sparse_vector(mut self, sparse_vector: HashMap<String, f32>) -> Self {
+        self.sparse_vector = sparse_vector;
+        self
+    }
+
     pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
         self.metadata = metadata;
         self
     }
+
+    pub fn with_schema_version(mut self, schema_version: Option<String>) -> Self {
+        self.schema_version = schema_version;
+        self
+    }
+
+    /// Whether the entity has been soft-deleted
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 /// Relation between entities
@@ -97,3 +145,438 @@ pub struct GraphPath {
     pub entities: Vec<Entity>,
     pub relations: Vec<Relation>,
 }
+
+/// A point read back from a vector collection
+#[derive(Debug, Clone)]
+pub struct VectorPoint {
+    /// Point ID, which is the ID of the entity it embeds
+    pub id: String,
+    pub payload: HashMap<String, serde_json::Value>,
+    /// Only present when vectors were requested
+    pub vector: Option<Vec<f32>>,
+}
+
+impl VectorPoint {
+    /// ID of the embedded entity as it was written, falling back to the point ID.
+    /// Qdrant normalizes UUID point IDs, so the payload copy is authoritative.
+    pub fn entity_id(&self) -> &str {
+        self.payload
+            .get("entity_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.id)
+    }
+}
+
+/// Time window filter; `start` is inclusive and `end` is exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeRange {
+    pub fn new(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+
+    /// Reject ranges that can never match anything
+    pub fn validate(&self) -> crate::error::Result<()> {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start >= end => Err(crate::error::VectaDBError::BadRequest(
+                "start_time must be before end_time".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        self.start.is_none_or(|start| time >= start) && self.end.is_none_or(|end| time < end)
+    }
+}
+
+/// Agent event as stored in the `agent_event` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub id: String,
+    pub trace_id: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub event_type: Option<String>,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub properties: serde_json::Value,
+}
+
+/// Filters for listing agent events
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub agent_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub event_type: Option<String>,
+    pub time_range: TimeRange,
+}
+
+/// Tables that can be exported and imported as NDJSON, or faceted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportTable {
+    Entity,
+    Relation,
+    AgentEvent,
+    AgentTrace,
+}
+
+impl ExportTable {
+    /// SurrealDB table name
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            ExportTable::Entity => "entity",
+            ExportTable::Relation => "relation",
+            ExportTable::AgentEvent => "agent_event",
+            ExportTable::AgentTrace => "agent_trace",
+        }
+    }
+
+    /// Field holding when a record happened or was created
+    pub fn time_field(&self) -> &'static str {
+        match self {
+            ExportTable::Entity | ExportTable::Relation => "created_at",
+            ExportTable::AgentEvent => "timestamp",
+            ExportTable::AgentTrace => "start_time",
+        }
+    }
+}
+
+/// An event that failed ingestion, kept for replay
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetter {
+    pub id: String,
+    /// The event as it was submitted
+    pub event: serde_json::Value,
+    /// Why it most recently failed
+    pub error: String,
+    /// Failed replays so far
+    #[serde(default)]
+    pub attempts: u64,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: Datetime,
+}
+
+/// What a mutating operation did to its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOperation {
+    Create,
+    Update,
+    /// Soft delete
+    Delete,
+    /// Permanent removal
+    Purge,
+}
+
+/// One create, update or delete, as kept in the audit log
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecord {
+    pub operation: AuditOperation,
+    /// Kind of record changed: `entity`, `relation` or `schema`
+    pub target_type: String,
+    /// Id of the entity or relation, or namespace of the schema
+    pub target_id: String,
+    /// Who made the change: the client IP, until requests are authenticated
+    pub principal: Option<String>,
+    /// `X-Request-ID` of the request that made the change
+    pub request_id: Option<String>,
+    /// Summary of the change, e.g. the property names added, removed and changed
+    pub changes: serde_json::Value,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: Datetime,
+}
+
+/// Token usage totals summed over events
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+/// Aggregated statistics over agent events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventAnalytics {
+    pub total_events: u64,
+    pub events_by_type: HashMap<String, u64>,
+    pub error_count: u64,
+    pub token_usage: TokenUsage,
+    /// Average latency over events that report `latency_ms`
+    pub avg_latency_ms: Option<f64>,
+}
+
+impl EventAnalytics {
+    /// Fraction of events that are errors (0.0 when there are no events)
+    pub fn error_rate(&self) -> f64 {
+        if self.total_events == 0 {
+            0.0
+        } else {
+            self.error_count as f64 / self.total_events as f64
+        }
+    }
+}
+
+/// Token usage summed over the events of one model
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelTokenUsage {
+    /// `None` for events that report no `model_id`
+    pub model_id: Option<String>,
+    pub events: u64,
+    /// Events reporting neither `input_tokens` nor `output_tokens`
+    pub events_without_tokens: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// A distinct value of a field and the records holding it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FacetCount {
+    /// `null` counts the records without the field
+    #[schema(value_type = Object)]
+    pub value: serde_json::Value,
+    pub count: u64,
+}
+
+/// Values counted between a lower (inclusive) and upper (exclusive) edge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct HistogramBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u64,
+}
+
+/// Distribution of a numeric event property over buckets
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct Histogram {
+    pub buckets: Vec<HistogramBucket>,
+    /// Values counted, including those outside every bucket
+    pub total: u64,
+    /// Values below the first bucket
+    pub below: u64,
+    /// Values above the last bucket
+    pub above: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl Histogram {
+    /// Bucket `values` between consecutive `edges`, which must be increasing.
+    /// The last bucket also holds values equal to its upper edge.
+    pub fn with_edges(values: &[f64], edges: &[f64]) -> Self {
+        let mut buckets: Vec<HistogramBucket> = edges
+            .windows(2)
+            .map(|pair| HistogramBucket {
+                lower: pair[0],
+                upper: pair[1],
+                count: 0,
+            })
+            .collect();
+        let mut histogram = Histogram {
+            total: values.len() as u64,
+            min: values.iter().copied().reduce(f64::min),
+            max: values.iter().copied().reduce(f64::max),
+            ..Default::default()
+        };
+
+        let (Some(&first), Some(&last)) = (edges.first(), edges.last()) else {
+            histogram.above = histogram.total;
+            return histogram;
+        };
+        for &value in values {
+            if value < first {
+                histogram.below += 1;
+            } else if value > last || buckets.is_empty() {
+                histogram.above += 1;
+            } else {
+                // Index of the first edge above the value, less one, is its bucket
+                let index = edges.partition_point(|&edge| edge <= value).saturating_sub(1);
+                let last = buckets.len() - 1;
+                buckets[index.min(last)].count += 1;
+            }
+        }
+
+        histogram.buckets = buckets;
+        histogram
+    }
+
+    /// `count` equal-width buckets from the smallest value to the largest
+    pub fn equal_width(values: &[f64], count: usize) -> Self {
+        let (Some(min), Some(max)) = (
+            values.iter().copied().reduce(f64::min),
+            values.iter().copied().reduce(f64::max),
+        ) else {
+            return Self::default();
+        };
+
+        let count = if min == max { 1 } else { count.max(1) };
+        let width = (max - min) / count as f64;
+        let mut edges: Vec<f64> = (0..count).map(|i| min + width * i as f64).collect();
+        // Exactly the max, so rounding never leaves it outside the last bucket
+        edges.push(max);
+        Self::with_edges(values, &edges)
+    }
+}
+
+/// Records in one fixed time interval, starting at `start`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct TimeBucket {
+    pub start: DateTime<Utc>,
+    pub count: u64,
+    /// Sum of the requested property over records that have it as a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sum: Option<f64>,
+    /// Mean of the requested property over records that have it as a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg: Option<f64>,
+}
+
+impl TimeBucket {
+    pub fn empty(start: DateTime<Utc>) -> Self {
+        Self { start, count: 0, sum: None, avg: None }
+    }
+}
+
+/// Start of the `interval_secs`-long interval holding `time`, counting
+/// intervals from the Unix epoch
+pub fn bucket_start(time: DateTime<Utc>, interval_secs: i64) -> i64 {
+    time.timestamp().div_euclid(interval_secs) * interval_secs
+}
+
+/// Every bucket from the one holding `start` up to `end` (exclusive), taking
+/// those in `found` (keyed by start in Unix seconds) and zero-filling the rest
+pub fn zero_filled_buckets(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    interval_secs: i64,
+    mut found: HashMap<i64, TimeBucket>,
+) -> Vec<TimeBucket> {
+    let mut buckets = Vec::new();
+    let mut bucket = bucket_start(start, interval_secs);
+    while let Some(bucket_time) = DateTime::from_timestamp(bucket, 0).filter(|time| *time < end) {
+        buckets.push(found.remove(&bucket).unwrap_or_else(|| TimeBucket::empty(bucket_time)));
+        bucket += interval_secs;
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_rate() {
+        let analytics = EventAnalytics {
+            total_events: 8,
+            error_count: 2,
+            ..Default::default()
+        };
+        assert_eq!(analytics.error_rate(), 0.25);
+    }
+
+    #[test]
+    fn test_histogram_with_edges() {
+        let latencies = [5.0, 40.0, 99.9, 100.0, 250.0, 499.0, 500.0, 1200.0, -1.0];
+        let histogram = Histogram::with_edges(&latencies, &[0.0, 100.0, 500.0, 1000.0]);
+
+        let counts: Vec<u64> = histogram.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![3, 3, 1]);
+        assert_eq!(histogram.below, 1);
+        assert_eq!(histogram.above, 1);
+        assert_eq!(histogram.total, latencies.len() as u64);
+        assert_eq!(histogram.min, Some(-1.0));
+        assert_eq!(histogram.max, Some(1200.0));
+
+        // The upper edge belongs to the last bucket
+        let histogram = Histogram::with_edges(&[1000.0], &[0.0, 100.0, 500.0, 1000.0]);
+        assert_eq!(histogram.buckets[2].count, 1);
+        assert_eq!(histogram.above, 0);
+    }
+
+    #[test]
+    fn test_histogram_equal_width() {
+        let latencies: Vec<f64> = (0..=100).map(|ms| ms as f64).collect();
+        let histogram = Histogram::equal_width(&latencies, 4);
+
+        let edges: Vec<(f64, f64)> = histogram.buckets.iter().map(|b| (b.lower, b.upper)).collect();
+        assert_eq!(edges, vec![(0.0, 25.0), (25.0, 50.0), (50.0, 75.0), (75.0, 100.0)]);
+        let counts: Vec<u64> = histogram.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![25, 25, 25, 26]);
+        assert_eq!(histogram.below + histogram.above, 0);
+
+        // Identical values fall in a single bucket, and no values mean no buckets
+        let histogram = Histogram::equal_width(&[7.0, 7.0], 10);
+        assert_eq!(histogram.buckets, vec![HistogramBucket { lower: 7.0, upper: 7.0, count: 2 }]);
+        assert_eq!(Histogram::equal_width(&[], 10), Histogram::default());
+    }
+
+    #[test]
+    fn test_zero_filled_buckets() {
+        use chrono::TimeZone;
+
+        let at = |hour: u32, minute: u32| chrono::Utc.with_ymd_and_hms(2026, 1, 7, hour, minute, 0).unwrap();
+        let found = HashMap::from([
+            (at(10, 0).timestamp(), TimeBucket { count: 3, ..TimeBucket::empty(at(10, 0)) }),
+            (at(12, 0).timestamp(), TimeBucket { count: 1, sum: Some(5.0), avg: Some(5.0), ..TimeBucket::empty(at(12, 0)) }),
+        ]);
+
+        // Starts are aligned to the hour; the end is exclusive
+        let buckets = zero_filled_buckets(at(10, 30), at(13, 0), 3600, found);
+        let starts: Vec<_> = buckets.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![at(10, 0), at(11, 0), at(12, 0)]);
+        let counts: Vec<u64> = buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![3, 0, 1]);
+        assert_eq!(buckets[1], TimeBucket::empty(at(11, 0)));
+        assert_eq!(buckets[2].avg, Some(5.0));
+
+        // A partial last interval is included
+        assert_eq!(zero_filled_buckets(at(10, 0), at(11, 15), 3600, HashMap::new()).len(), 2);
+    }
+
+    #[test]
+    fn test_export_table_names() {
+        let table: ExportTable = serde_json::from_str("\"agent_event\"").unwrap();
+        assert_eq!(table, ExportTable::AgentEvent);
+        assert_eq!(table.table_name(), "agent_event");
+        assert_eq!(ExportTable::Entity.table_name(), "entity");
+    }
+
+    #[test]
+    fn test_time_range_bounds() {
+        let start = "2025-01-15T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let end = "2025-01-15T11:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let range = TimeRange::new(Some(start), Some(end));
+
+        // Start is inclusive, end is exclusive
+        assert!(range.contains(start));
+        assert!(range.contains(end - chrono::Duration::seconds(1)));
+        assert!(!range.contains(end));
+        assert!(!range.contains(start - chrono::Duration::seconds(1)));
+
+        assert!(TimeRange::default().contains(end));
+        assert!(!TimeRange::default().is_bounded());
+        assert!(range.validate().is_ok());
+        assert!(TimeRange::new(Some(end), Some(start)).validate().is_err());
+        assert!(TimeRange::new(Some(start), Some(start)).validate().is_err());
+    }
+
+    #[test]
+    fn test_error_rate_no_events() {
+        let analytics = EventAnalytics::default();
+        assert_eq!(analytics.error_rate(), 0.0);
+    }
+}
@@ -1,18 +1,37 @@
 // Embedding manager - Unified interface over plugin system and local service
-use crate::config::EmbeddingConfig;
+use crate::config::{EmbeddingConfig, LongTextStrategy};
 use crate::embeddings::plugin::{EmbeddingPlugin, PluginConfig, PluginRegistry, ProviderConfig};
 use crate::embeddings::plugins::{CoherePlugin, HuggingFacePlugin, OpenAIPlugin, VoyagePlugin};
 use crate::embeddings::service::{EmbeddingModel, EmbeddingService};
 use crate::error::{Result, VectaDBError};
+use std::collections::HashMap;
 use std::fs;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tracing::{debug, info, warn};
 
+/// Chain entry for the local embedding service
+const LOCAL_PROVIDER: &str = "local";
+
+/// Provider and model an entity type is routed to
+#[derive(Debug, Clone)]
+struct Route {
+    provider: String,
+    model: String,
+}
+
 /// Embedding manager that handles both plugin-based and local embeddings
 pub struct EmbeddingManager {
     registry: Option<PluginRegistry>,
     local_service: Option<Arc<EmbeddingService>>,
     config: EmbeddingConfig,
+    model: String,
+    /// Providers tried in order: the primary, then its fallbacks
+    chain: Vec<String>,
+    /// Entity types embedded by a provider other than the primary
+    routes: HashMap<String, Route>,
+    /// Caps provider calls in flight at `max_concurrent_requests`
+    requests: Semaphore,
 }
 
 impl EmbeddingManager {
@@ -24,18 +43,63 @@ impl EmbeddingManager {
             registry: None,
             local_service: None,
             config: config.clone(),
+            model: config.model.clone(),
+            chain: Vec::new(),
+            routes: HashMap::new(),
+            requests: Semaphore::new(config.max_concurrent_requests.max(1)),
         };
 
         // Initialize based on provider
-        if config.provider == "local" {
+        if config.provider == LOCAL_PROVIDER {
             manager.init_local_service()?;
         } else {
             manager.init_plugin_system().await?;
         }
+        manager.chain.push(config.provider.clone());
+
+        for provider in &config.fallback_providers {
+            manager.add_fallback(provider).await?;
+        }
+
+        // Initialize local service as fallback if configured
+        if config.fallback_to_local && !manager.chain.iter().any(|p| p == LOCAL_PROVIDER) {
+            info!("Initializing local service as fallback");
+            // Don't fail if fallback init fails
+            if manager.init_local_service().is_ok() {
+                manager.check_fallback_dimension(LOCAL_PROVIDER)?;
+                manager.chain.push(LOCAL_PROVIDER.to_string());
+            }
+        }
+
+        for (entity_type, provider) in &config.type_providers {
+            manager.add_route(entity_type, provider).await?;
+        }
+
+        if config.verify_dim {
+            manager.verify_dimension().await?;
+        }
 
         Ok(manager)
     }
 
+    /// Embed a probe text and check the vector has the configured dimension,
+    /// so a wrong `EMBEDDING_DIM` fails at startup instead of at first insert
+    async fn verify_dimension(&self) -> Result<()> {
+        let probe = self.embed("dimension probe").await?;
+        if probe.len() != self.config.dim {
+            return Err(VectaDBError::Config(format!(
+                "EMBEDDING_DIM is {} but provider '{}' model '{}' produces {}-dimensional vectors",
+                self.config.dim,
+                self.config.provider,
+                self.model,
+                probe.len()
+            )));
+        }
+
+        debug!("Verified embedding dimension {}", probe.len());
+        Ok(())
+    }
+
     /// Initialize local embedding service
     fn init_local_service(&mut self) -> Result<()> {
         info!("Initializing local embedding service");
@@ -71,64 +135,120 @@ impl EmbeddingManager {
     async fn init_plugin_system(&mut self) -> Result<()> {
         info!("Initializing embedding plugin system");
 
-        let mut registry = PluginRegistry::new();
+        let provider = self.config.provider.clone();
+        let plugin_config = self.provider_config(&provider)?;
+        self.model = plugin_config.provider.model().to_string();
+        self.register_plugin(&provider, plugin_config).await?;
+        self.registry_mut().set_active(&provider)?;
 
-        // Load plugin configuration
-        let config_path = format!(
-            "{}/{}.yaml",
-            self.config.plugin_config_dir, self.config.provider
-        );
+        info!("Plugin '{}' initialized successfully", provider);
+        Ok(())
+    }
 
-        debug!("Loading plugin config from: {}", config_path);
+    /// Initialize a fallback provider and append it to the chain
+    async fn add_fallback(&mut self, provider: &str) -> Result<()> {
+        if self.chain.iter().any(|p| p == provider) {
+            warn!("Embedding provider '{}' is already in the fallback chain", provider);
+            return Ok(());
+        }
+
+        info!("Initializing fallback embedding provider: {}", provider);
+        self.init_provider(provider).await?;
+        self.check_fallback_dimension(provider)?;
+        self.chain.push(provider.to_string());
+        Ok(())
+    }
 
-        let plugin_config = self.load_plugin_config(&config_path)?;
+    /// Embed `entity_type` with `provider` instead of the primary. Routed
+    /// types use their provider alone, without the fallback chain.
+    async fn add_route(&mut self, entity_type: &str, provider: &str) -> Result<()> {
+        if provider == self.config.provider {
+            return Ok(());
+        }
 
-        // Create and initialize appropriate plugin
-        match self.config.provider.as_str() {
-            "openai" => {
-                let mut plugin = OpenAIPlugin::new();
-                plugin.initialize(plugin_config).await?;
-                registry.register(Box::new(plugin));
-            }
-            "cohere" => {
-                let mut plugin = CoherePlugin::new();
-                plugin.initialize(plugin_config).await?;
-                registry.register(Box::new(plugin));
-            }
-            "huggingface" => {
-                let mut plugin = HuggingFacePlugin::new();
-                plugin.initialize(plugin_config).await?;
-                registry.register(Box::new(plugin));
-            }
-            "voyage" => {
-                let mut plugin = VoyagePlugin::new();
-                plugin.initialize(plugin_config).await?;
-                registry.register(Box::new(plugin));
+        info!("Routing {} embeddings to provider: {}", entity_type, provider);
+        let model = self.init_provider(provider).await?;
+        self.routes.insert(
+            entity_type.to_string(),
+            Route {
+                provider: provider.to_string(),
+                model,
+            },
+        );
+        Ok(())
+    }
+
+    /// Initialize `provider` unless it already is, returning its model
+    async fn init_provider(&mut self, provider: &str) -> Result<String> {
+        if provider == LOCAL_PROVIDER {
+            if self.local_service.is_none() {
+                self.init_local_service()?;
             }
+            return Ok(self.config.model.clone());
+        }
+
+        let plugin_config = self.provider_config(provider)?;
+        let model = plugin_config.provider.model().to_string();
+        if self.registry.as_ref().and_then(|r| r.get(provider)).is_none() {
+            self.register_plugin(provider, plugin_config).await?;
+        }
+        Ok(model)
+    }
+
+    /// Vectors from every provider in the chain share one collection, so a
+    /// fallback must produce the same dimension as the primary
+    fn check_fallback_dimension(&self, provider: &str) -> Result<()> {
+        let dimension = self.provider_dimension(provider);
+        if dimension != Some(self.config.dim) {
+            return Err(VectaDBError::Config(format!(
+                "Fallback embedding provider '{}' produces {}-dimensional vectors but EMBEDDING_DIM is {}",
+                provider,
+                dimension.map_or("unknown".to_string(), |d| d.to_string()),
+                self.config.dim
+            )));
+        }
+        Ok(())
+    }
+
+    /// Load `<plugin_config_dir>/<provider>.yaml`
+    fn provider_config(&self, provider: &str) -> Result<PluginConfig> {
+        let config_path = format!("{}/{}.yaml", self.config.plugin_config_dir, provider);
+        debug!("Loading plugin config from: {}", config_path);
+        self.load_plugin_config(&config_path)
+    }
+
+    /// Create, initialize and register the plugin for `provider`
+    async fn register_plugin(&mut self, provider: &str, plugin_config: PluginConfig) -> Result<()> {
+        let mut plugin: Box<dyn EmbeddingPlugin> = match provider {
+            "openai" => Box::new(OpenAIPlugin::new()),
+            "cohere" => Box::new(CoherePlugin::new()),
+            "huggingface" => Box::new(HuggingFacePlugin::new()),
+            "voyage" => Box::new(VoyagePlugin::new()),
             _ => {
                 return Err(VectaDBError::Config(format!(
                     "Unknown embedding provider: {}",
-                    self.config.provider
+                    provider
                 )));
             }
-        }
-
-        registry.set_active(&self.config.provider)?;
-
-        info!(
-            "Plugin '{}' initialized successfully",
-            self.config.provider
-        );
+        };
+        plugin.initialize(plugin_config).await?;
+        self.registry_mut().register(plugin);
+        Ok(())
+    }
 
-        self.registry = Some(registry);
+    fn registry_mut(&mut self) -> &mut PluginRegistry {
+        self.registry.get_or_insert_with(PluginRegistry::new)
+    }
 
-        // Initialize local service as fallback if configured
-        if self.config.fallback_to_local {
-            info!("Initializing local service as fallback");
-            self.init_local_service().ok(); // Don't fail if fallback init fails
+    /// Dimension of a provider in the chain, if it is initialized
+    fn provider_dimension(&self, provider: &str) -> Option<usize> {
+        if provider == LOCAL_PROVIDER {
+            return self.local_service.as_ref().map(|service| service.dimension());
         }
-
-        Ok(())
+        self.registry
+            .as_ref()
+            .and_then(|registry| registry.get(provider))
+            .map(|plugin| plugin.dimension())
     }
 
     /// Load plugin configuration from YAML file
@@ -194,75 +314,198 @@ impl EmbeddingManager {
         Ok(())
     }
 
-    /// Generate embedding for a single text
+    /// Wait for a free provider slot
+    async fn acquire_request(&self) -> Result<tokio::sync::SemaphorePermit<'_>> {
+        self.requests
+            .acquire()
+            .await
+            .map_err(|e| VectaDBError::Embedding(format!("Embedding request limiter closed: {}", e)))
+    }
+
+    /// Generate embedding for a single text. Text over `max_input_chars` is
+    /// truncated or chunked per `long_text_strategy`.
     pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        // Try plugin first
-        if let Some(ref registry) = self.registry {
-            match registry.get_active()?.embed(text).await {
-                Ok(embedding) => return Ok(embedding),
-                Err(e) => {
-                    warn!("Plugin embedding failed: {}", e);
-                    if !self.config.fallback_to_local {
-                        return Err(e);
-                    }
+        self.embed_with_chain(&self.chain, text).await
+    }
+
+    /// Generate embeddings for multiple texts, each cut down like in `embed`
+    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch_with_chain(&self.chain, texts).await
+    }
+
+    /// Embed text of an entity type with the type's provider
+    pub async fn embed_for(&self, entity_type: &str, text: &str) -> Result<Vec<f32>> {
+        self.embed_with_chain(self.chain_for(entity_type), text).await
+    }
+
+    /// Embed texts of an entity type with the type's provider
+    pub async fn embed_batch_for(&self, entity_type: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embed_batch_with_chain(self.chain_for(entity_type), texts).await
+    }
+
+    /// Embed `text` once per provider the entity types are routed to, so a
+    /// query searching several types matches each with its own vectors.
+    /// Returns the vector for each type.
+    pub async fn embed_for_types(&self, entity_types: &[String], text: &str) -> Result<HashMap<String, Vec<f32>>> {
+        let mut by_provider: HashMap<&str, Vec<f32>> = HashMap::new();
+        let mut vectors = HashMap::with_capacity(entity_types.len());
+        for entity_type in entity_types {
+            let provider = self.provider_for(entity_type);
+            let vector = match by_provider.get(provider) {
+                Some(vector) => vector.clone(),
+                None => {
+                    let vector = self.embed_for(entity_type, text).await?;
+                    by_provider.insert(provider, vector.clone());
+                    vector
                 }
+            };
+            vectors.insert(entity_type.clone(), vector);
+        }
+        Ok(vectors)
+    }
+
+    /// Providers tried for an entity type
+    fn chain_for(&self, entity_type: &str) -> &[String] {
+        match self.routes.get(entity_type) {
+            Some(route) => std::slice::from_ref(&route.provider),
+            None => &self.chain,
+        }
+    }
+
+    async fn embed_with_chain(&self, chain: &[String], text: &str) -> Result<Vec<f32>> {
+        let inputs = self.split_input(text);
+        let embedding = match inputs.as_slice() {
+            [input] => self.embed_input(chain, input).await?,
+            _ => mean_pool(&inputs, &self.embed_inputs(chain, &inputs).await?),
+        };
+        Ok(self.finish(embedding))
+    }
+
+    async fn embed_batch_with_chain(&self, chain: &[String], texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let split: Vec<Vec<String>> = texts.iter().map(|text| self.split_input(text)).collect();
+        if split.iter().all(|inputs| inputs.len() == 1) {
+            let inputs: Vec<String> = split.into_iter().flatten().collect();
+            let embeddings = self.embed_inputs(chain, &inputs).await?;
+            return Ok(embeddings.into_iter().map(|e| self.finish(e)).collect());
+        }
+
+        // One provider batch for every chunk of every text, pooled per text after
+        let inputs: Vec<String> = split.iter().flatten().cloned().collect();
+        let embeddings = self.embed_inputs(chain, &inputs).await?;
+        let mut pooled = Vec::with_capacity(texts.len());
+        let mut offset = 0;
+        for chunks in &split {
+            pooled.push(self.finish(mean_pool(chunks, &embeddings[offset..offset + chunks.len()])));
+            offset += chunks.len();
+        }
+        Ok(pooled)
+    }
+
+    /// A provider's vector as it is stored and searched with
+    fn finish(&self, embedding: Vec<f32>) -> Vec<f32> {
+        if self.config.normalize {
+            l2_normalize(embedding)
+        } else {
+            embedding
+        }
+    }
+
+    /// The inputs `text` is embedded as: itself when it fits, otherwise its
+    /// leading `max_input_chars` characters or all its chunks of that size
+    fn split_input(&self, text: &str) -> Vec<String> {
+        let max = self.config.max_input_chars;
+        if max == 0 || text.chars().count() <= max {
+            return vec![text.to_string()];
+        }
+
+        match self.config.long_text_strategy {
+            LongTextStrategy::Truncate => vec![text.chars().take(max).collect()],
+            LongTextStrategy::AverageChunks => {
+                let chars: Vec<char> = text.chars().collect();
+                chars.chunks(max).map(|chunk| chunk.iter().collect()).collect()
             }
         }
+    }
 
-        // Fall back to local service
-        if let Some(ref service) = self.local_service {
-            debug!("Using local embedding service");
-            return service.encode(text);
+    /// Embed one provider input, trying each provider in the chain until
+    /// one succeeds
+    async fn embed_input(&self, chain: &[String], text: &str) -> Result<Vec<f32>> {
+        let _permit = self.acquire_request().await?;
+
+        let mut last_error = None;
+        for provider in chain {
+            match self.embed_with(provider, text).await {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) => {
+                    warn!("Embedding provider '{}' failed: {}", provider, e);
+                    last_error = Some(e);
+                }
+            }
         }
 
-        Err(VectaDBError::Embedding(
-            "No embedding service available".to_string(),
-        ))
+        Err(last_error.unwrap_or_else(|| {
+            VectaDBError::Embedding("No embedding service available".to_string())
+        }))
     }
 
-    /// Generate embeddings for multiple texts
-    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    /// Embed provider inputs in one batch, trying each provider in the chain
+    /// until one succeeds
+    async fn embed_inputs(&self, chain: &[String], texts: &[String]) -> Result<Vec<Vec<f32>>> {
         if texts.is_empty() {
             return Ok(vec![]);
         }
 
-        // Try plugin first
-        if let Some(ref registry) = self.registry {
-            match registry.get_active()?.embed_batch(texts).await {
+        let _permit = self.acquire_request().await?;
+
+        let mut last_error = None;
+        for provider in chain {
+            match self.embed_batch_with(provider, texts).await {
                 Ok(embeddings) => return Ok(embeddings),
                 Err(e) => {
-                    warn!("Plugin batch embedding failed: {}", e);
-                    if !self.config.fallback_to_local {
-                        return Err(e);
-                    }
+                    warn!("Embedding provider '{}' failed on a batch: {}", provider, e);
+                    last_error = Some(e);
                 }
             }
         }
 
-        // Fall back to local service
-        if let Some(ref service) = self.local_service {
+        Err(last_error.unwrap_or_else(|| {
+            VectaDBError::Embedding("No embedding service available".to_string())
+        }))
+    }
+
+    async fn embed_with(&self, provider: &str, text: &str) -> Result<Vec<f32>> {
+        if provider == LOCAL_PROVIDER {
+            debug!("Using local embedding service");
+            return self.local_service()?.encode(text);
+        }
+        self.plugin(provider)?.embed(text).await
+    }
+
+    async fn embed_batch_with(&self, provider: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if provider == LOCAL_PROVIDER {
             debug!("Using local embedding service for batch");
-            return service.encode_batch(texts);
+            return self.local_service()?.encode_batch(texts);
         }
+        self.plugin(provider)?.embed_batch(texts).await
+    }
 
-        Err(VectaDBError::Embedding(
-            "No embedding service available".to_string(),
-        ))
+    fn local_service(&self) -> Result<&EmbeddingService> {
+        self.local_service
+            .as_deref()
+            .ok_or_else(|| VectaDBError::Embedding("Local embedding service not initialized".to_string()))
+    }
+
+    fn plugin(&self, provider: &str) -> Result<&dyn EmbeddingPlugin> {
+        self.registry
+            .as_ref()
+            .and_then(|registry| registry.get(provider))
+            .ok_or_else(|| VectaDBError::Embedding(format!("Embedding provider '{}' not initialized", provider)))
     }
 
     /// Get embedding dimension
     pub fn dimension(&self) -> usize {
-        if let Some(ref registry) = self.registry {
-            if let Ok(plugin) = registry.get_active() {
-                return plugin.dimension();
-            }
-        }
-
-        if let Some(ref service) = self.local_service {
-            return service.dimension();
-        }
-
-        self.config.dim
+        self.provider_dimension(&self.config.provider)
+            .unwrap_or(self.config.dim)
     }
 
     /// Get current provider name
@@ -270,21 +513,47 @@ impl EmbeddingManager {
         &self.config.provider
     }
 
-    /// Check if manager is healthy
+    /// Get current model name
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Dimension of an entity type's embeddings
+    pub fn dimension_for(&self, entity_type: &str) -> usize {
+        match self.routes.get(entity_type) {
+            Some(route) => self.provider_dimension(&route.provider).unwrap_or(self.config.dim),
+            None => self.dimension(),
+        }
+    }
+
+    /// Provider embedding an entity type
+    pub fn provider_for(&self, entity_type: &str) -> &str {
+        self.routes
+            .get(entity_type)
+            .map_or(self.provider(), |route| route.provider.as_str())
+    }
+
+    /// Model embedding an entity type
+    pub fn model_for(&self, entity_type: &str) -> &str {
+        self.routes
+            .get(entity_type)
+            .map_or(self.model(), |route| route.model.as_str())
+    }
+
+    /// Check if manager is healthy: any provider in the chain can embed
     pub async fn health_check(&self) -> Result<bool> {
-        if let Some(ref registry) = self.registry {
-            match registry.get_active()?.health_check().await {
-                Ok(health) => return Ok(health.healthy),
-                Err(_) => {
-                    if self.local_service.is_some() {
-                        return Ok(true); // Fallback available
-                    }
-                    return Ok(false);
+        for provider in &self.chain {
+            if provider == LOCAL_PROVIDER {
+                return Ok(true);
+            }
+            if let Ok(health) = self.plugin(provider)?.health_check().await {
+                if health.healthy {
+                    return Ok(true);
                 }
             }
         }
 
-        Ok(self.local_service.is_some())
+        Ok(false)
     }
 
     /// Get usage statistics (if using plugin)
@@ -296,9 +565,48 @@ impl EmbeddingManager {
     }
 }
 
+/// Average of chunk embeddings, each weighted by its chunk's length so a short
+/// last chunk doesn't count as much as a full one
+fn mean_pool(chunks: &[String], embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let dim = embeddings.first().map_or(0, |e| e.len());
+    let mut pooled = vec![0.0f32; dim];
+    let mut total = 0.0f32;
+
+    for (chunk, embedding) in chunks.iter().zip(embeddings) {
+        let weight = chunk.chars().count() as f32;
+        for (sum, value) in pooled.iter_mut().zip(embedding) {
+            *sum += weight * value;
+        }
+        total += weight;
+    }
+
+    if total > 0.0 {
+        for value in &mut pooled {
+            *value /= total;
+        }
+    }
+    pooled
+}
+
+/// `embedding` scaled to unit length, so dot product and cosine similarity
+/// agree. A zero vector has no direction and is returned as is.
+fn l2_normalize(mut embedding: Vec<f32>) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 && norm.is_finite() {
+        for value in &mut embedding {
+            *value /= norm;
+        }
+    }
+    embedding
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::embeddings::plugin::{PluginHealth, PluginStats};
+    use futures::future::{BoxFuture, FutureExt};
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_local_manager_creation() {
@@ -308,6 +616,13 @@ mod tests {
             provider: "local".to_string(),
             plugin_config_dir: "./config/embeddings".to_string(),
             fallback_to_local: false,
+            fallback_providers: Vec::new(),
+            type_providers: HashMap::new(),
+            verify_dim: true,
+            max_concurrent_requests: 8,
+            max_input_chars: 16_000,
+            long_text_strategy: LongTextStrategy::Truncate,
+            normalize: false,
         };
 
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -318,4 +633,332 @@ mod tests {
         assert_eq!(manager.provider(), "local");
         assert_eq!(manager.dimension(), 384);
     }
+
+    type EmbedFn = Arc<dyn Fn(String) -> BoxFuture<'static, Result<Vec<f32>>> + Send + Sync>;
+
+    /// Plugin whose name, dimension and embedding are set by the test
+    struct MockPlugin {
+        name: &'static str,
+        dimension: usize,
+        embed: EmbedFn,
+    }
+
+    impl MockPlugin {
+        fn new<F, Fut>(name: &'static str, dimension: usize, embed: F) -> Self
+        where
+            F: Fn(String) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<Vec<f32>>> + Send + 'static,
+        {
+            Self {
+                name,
+                dimension,
+                embed: Arc::new(move |text| embed(text).boxed()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingPlugin for MockPlugin {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn version(&self) -> &'static str {
+            "0.0.0"
+        }
+
+        fn dimension(&self) -> usize {
+            self.dimension
+        }
+
+        fn max_batch_size(&self) -> usize {
+            16
+        }
+
+        async fn initialize(&mut self, _config: PluginConfig) -> Result<()> {
+            Ok(())
+        }
+
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            (self.embed)(text.to_string()).await
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let mut embeddings = Vec::with_capacity(texts.len());
+            for text in texts {
+                embeddings.push(self.embed(text).await?);
+            }
+            Ok(embeddings)
+        }
+
+        async fn health_check(&self) -> Result<PluginHealth> {
+            let start = std::time::Instant::now();
+            let result = self.embed("health check").await;
+            let latency_ms = Some(start.elapsed().as_millis() as u64);
+            Ok(match result {
+                Ok(_) => PluginHealth {
+                    healthy: true,
+                    message: Some("Mock is responsive".to_string()),
+                    latency_ms,
+                },
+                Err(e) => PluginHealth {
+                    healthy: false,
+                    message: Some(format!("Health check failed: {}", e)),
+                    latency_ms,
+                },
+            })
+        }
+
+        fn get_stats(&self) -> PluginStats {
+            Default::default()
+        }
+    }
+
+    /// Claims 1536 dimensions but really produces 768
+    fn misreporting_plugin() -> MockPlugin {
+        MockPlugin::new("misreporting", 1536, |_| async { Ok(vec![0.1; 768]) })
+    }
+
+    /// Rejects inputs over 100 characters, and otherwise embeds a text as
+    /// `[length, 1]`
+    fn input_limited_plugin() -> MockPlugin {
+        MockPlugin::new("limited", 2, |text| async move {
+            let len = text.chars().count();
+            if len > 100 {
+                return Err(VectaDBError::Embedding(format!("Input of {} characters is too long", len)));
+            }
+            Ok(vec![len as f32, 1.0])
+        })
+    }
+
+    /// Always down
+    fn failing_plugin() -> MockPlugin {
+        MockPlugin::new("failing", 2, |_| async {
+            Err(VectaDBError::Embedding("Rate limited".to_string()))
+        })
+    }
+
+    /// Embeds every text as `[0.1; 4]`, recording the peak number of
+    /// overlapping calls in the returned counter
+    fn tracking_plugin() -> (MockPlugin, Arc<AtomicUsize>) {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let recorded = peak.clone();
+        let plugin = MockPlugin::new("tracking", 4, move |_| {
+            let (in_flight, peak) = (in_flight.clone(), peak.clone());
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(vec![0.1; 4])
+            }
+        });
+        (plugin, recorded)
+    }
+
+    /// Manager whose only provider is `plugin`, with `overrides` applied to
+    /// a default config for it
+    fn test_manager(plugin: MockPlugin, overrides: impl FnOnce(&mut EmbeddingConfig)) -> EmbeddingManager {
+        let mut config = EmbeddingConfig {
+            model: format!("{}-v1", plugin.name),
+            dim: plugin.dimension,
+            provider: plugin.name.to_string(),
+            plugin_config_dir: "./config/embeddings".to_string(),
+            fallback_to_local: false,
+            fallback_providers: Vec::new(),
+            type_providers: HashMap::new(),
+            verify_dim: true,
+            max_concurrent_requests: 8,
+            max_input_chars: 16_000,
+            long_text_strategy: LongTextStrategy::Truncate,
+            normalize: false,
+        };
+        overrides(&mut config);
+
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(plugin));
+        registry.set_active(&config.provider).unwrap();
+
+        EmbeddingManager {
+            registry: Some(registry),
+            local_service: None,
+            model: config.model.clone(),
+            chain: vec![config.provider.clone()],
+            routes: HashMap::new(),
+            requests: Semaphore::new(config.max_concurrent_requests.max(1)),
+            config,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_health_check_reports_provider_state() {
+        let healthy = input_limited_plugin().health_check().await.unwrap();
+        assert!(healthy.healthy);
+
+        let down = failing_plugin().health_check().await.unwrap();
+        assert!(!down.healthy);
+        assert!(down.message.unwrap().contains("Rate limited"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_dimension_rejects_mismatched_provider() {
+        let mut manager = test_manager(misreporting_plugin(), |_| {});
+
+        let err = manager.verify_dimension().await.unwrap_err();
+        assert!(matches!(err, VectaDBError::Config(_)));
+        assert!(err.to_string().contains("1536"));
+        assert!(err.to_string().contains("768"));
+
+        manager.config.dim = 768;
+        assert!(manager.verify_dimension().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_long_text_is_truncated_or_averaged() {
+        let long = "é".repeat(250);
+
+        // Without a limit the provider rejects the input
+        let unlimited = test_manager(input_limited_plugin(), |config| config.max_input_chars = 0);
+        assert!(unlimited.embed(&long).await.is_err());
+
+        let truncating = test_manager(input_limited_plugin(), |config| config.max_input_chars = 100);
+        assert_eq!(truncating.embed(&long).await.unwrap(), vec![100.0, 1.0]);
+        assert_eq!(truncating.embed("short").await.unwrap(), vec![5.0, 1.0]);
+
+        // Chunks of 100, 100 and 50 characters, weighted by length
+        let averaging = test_manager(input_limited_plugin(), |config| {
+            config.max_input_chars = 100;
+            config.long_text_strategy = LongTextStrategy::AverageChunks;
+        });
+        assert_eq!(averaging.embed(&long).await.unwrap(), vec![90.0, 1.0]);
+
+        let batch = averaging
+            .embed_batch(&["short".to_string(), long.clone(), "x".repeat(100)])
+            .await
+            .unwrap();
+        assert_eq!(batch, vec![vec![5.0, 1.0], vec![90.0, 1.0], vec![100.0, 1.0]]);
+    }
+
+    fn norm(v: &[f32]) -> f32 {
+        v.iter().map(|x| x * x).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn test_l2_normalize() {
+        let normalized = l2_normalize(vec![3.0, 4.0]);
+        assert_eq!(normalized, vec![0.6, 0.8]);
+        assert!((norm(&normalized) - 1.0).abs() < 1e-6);
+        assert_eq!(l2_normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn test_normalize_scales_vectors_to_unit_length() {
+        let manager = test_manager(input_limited_plugin(), |config| {
+            config.max_input_chars = 100;
+            config.long_text_strategy = LongTextStrategy::AverageChunks;
+            config.normalize = true;
+        });
+
+        let short = manager.embed("short").await.unwrap();
+        let long = manager.embed(&"é".repeat(250)).await.unwrap();
+        assert!((norm(&short) - 1.0).abs() < 1e-6);
+        assert!((norm(&long) - 1.0).abs() < 1e-6);
+
+        // Batches, including pooled long text, match single embeds
+        let batch = manager
+            .embed_batch(&["short".to_string(), "é".repeat(250)])
+            .await
+            .unwrap();
+        assert_eq!(batch, vec![short.clone(), long.clone()]);
+
+        // Scores are unchanged: the dot product of normalized vectors is the
+        // cosine similarity of the raw ones
+        let (raw_short, raw_long) = ([5.0f32, 1.0], [90.0f32, 1.0]);
+        let cosine = raw_short.iter().zip(&raw_long).map(|(a, b)| a * b).sum::<f32>()
+            / (norm(&raw_short) * norm(&raw_long));
+        let dot = short.iter().zip(&long).map(|(a, b)| a * b).sum::<f32>();
+        assert!((dot - cosine).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider_used_when_primary_fails() {
+        let mut manager = test_manager(failing_plugin(), |config| config.max_input_chars = 0);
+        manager.registry.as_mut().unwrap().register(Box::new(input_limited_plugin()));
+
+        // Without a fallback the primary's error is returned
+        let err = manager.embed("hello").await.unwrap_err();
+        assert!(err.to_string().contains("Rate limited"));
+
+        manager.chain.push("limited".to_string());
+        assert_eq!(manager.embed("hello").await.unwrap(), vec![5.0, 1.0]);
+        let batch = manager
+            .embed_batch(&["hi".to_string(), "hello".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(batch, vec![vec![2.0, 1.0], vec![5.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_with_other_dimension_rejected() {
+        let mut manager = test_manager(input_limited_plugin(), |config| config.max_input_chars = 0);
+        manager.registry.as_mut().unwrap().register(Box::new(misreporting_plugin()));
+
+        assert!(manager.check_fallback_dimension("limited").is_ok());
+        let err = manager.check_fallback_dimension("misreporting").unwrap_err();
+        assert!(matches!(err, VectaDBError::Config(_)));
+        assert!(err.to_string().contains("1536"));
+        assert!(manager.check_fallback_dimension("cohere").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_types_routed_to_their_providers() {
+        // "limited" embeds as [length, 1]; "tracking" as [0.1; 4]
+        let mut manager = test_manager(input_limited_plugin(), |config| config.max_input_chars = 0);
+        manager.registry.as_mut().unwrap().register(Box::new(tracking_plugin().0));
+        manager.routes.insert(
+            "CodeSnippet".to_string(),
+            Route {
+                provider: "tracking".to_string(),
+                model: "tracking-v1".to_string(),
+            },
+        );
+
+        assert_eq!(manager.embed_for("CodeSnippet", "fn main").await.unwrap(), vec![0.1; 4]);
+        assert_eq!(manager.embed_for("Document", "fn main").await.unwrap(), vec![7.0, 1.0]);
+        let batch = manager
+            .embed_batch_for("CodeSnippet", &["a".to_string(), "bc".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(batch, vec![vec![0.1; 4], vec![0.1; 4]]);
+
+        assert_eq!(manager.provider_for("CodeSnippet"), "tracking");
+        assert_eq!(manager.model_for("CodeSnippet"), "tracking-v1");
+        assert_eq!(manager.dimension_for("CodeSnippet"), 4);
+        assert_eq!(manager.provider_for("Document"), "limited");
+        assert_eq!(manager.model_for("Document"), "limited-v1");
+        assert_eq!(manager.dimension_for("Document"), 2);
+
+        // A query over both types gets each type's own vector
+        let types = vec!["CodeSnippet".to_string(), "Document".to_string(), "Note".to_string()];
+        let vectors = manager.embed_for_types(&types, "hello").await.unwrap();
+        assert_eq!(vectors["CodeSnippet"], vec![0.1; 4]);
+        assert_eq!(vectors["Document"], vec![5.0, 1.0]);
+        assert_eq!(vectors["Note"], vec![5.0, 1.0]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_are_capped() {
+        let (plugin, peak) = tracking_plugin();
+        let manager = test_manager(plugin, |config| config.max_concurrent_requests = 3);
+
+        let texts: Vec<String> = (0..24).map(|i| format!("event {}", i)).collect();
+        let singles = futures::future::join_all(texts.iter().map(|text| manager.embed(text)));
+        let batches = futures::future::join_all(texts.chunks(4).map(|chunk| manager.embed_batch(chunk)));
+        let (singles, batches) = tokio::join!(singles, batches);
+
+        assert!(singles.iter().all(|result| result.is_ok()));
+        assert!(batches.iter().all(|result| result.is_ok()));
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
 }
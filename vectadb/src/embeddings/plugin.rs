@@ -104,6 +104,19 @@ pub enum ProviderConfig {
     },
 }
 
+impl ProviderConfig {
+    /// Model name configured for the provider
+    pub fn model(&self) -> &str {
+        match self {
+            ProviderConfig::OpenAI { model, .. }
+            | ProviderConfig::Cohere { model, .. }
+            | ProviderConfig::HuggingFace { model, .. }
+            | ProviderConfig::Voyage { model, .. }
+            | ProviderConfig::Local { model, .. } => model,
+        }
+    }
+}
+
 // Default values
 fn default_openai_base_url() -> String {
     "https://api.openai.com/v1".to_string()
@@ -183,7 +196,7 @@ impl PluginRegistry {
             self.active_plugin = Some(name.to_string());
             Ok(())
         } else {
-            Err(crate::error::VectaDBError::InvalidInput(
+            Err(crate::error::VectaDBError::BadRequest(
                 format!("Plugin '{}' not found", name),
             ))
         }
@@ -192,11 +205,11 @@ impl PluginRegistry {
     /// Get the active plugin
     pub fn get_active(&self) -> Result<&dyn EmbeddingPlugin> {
         let name = self.active_plugin.as_ref().ok_or_else(|| {
-            crate::error::VectaDBError::InvalidInput("No active plugin set".to_string())
+            crate::error::VectaDBError::BadRequest("No active plugin set".to_string())
         })?;
 
         self.plugins.get(name).map(|p| p.as_ref()).ok_or_else(|| {
-            crate::error::VectaDBError::InvalidInput(format!("Plugin '{}' not found", name))
+            crate::error::VectaDBError::BadRequest(format!("Plugin '{}' not found", name))
         })
     }
 
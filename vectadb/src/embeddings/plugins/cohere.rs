@@ -71,7 +71,7 @@ impl CoherePlugin {
         let config = self
             .config
             .as_ref()
-            .ok_or_else(|| VectaDBError::InvalidInput("Plugin not initialized".to_string()))?;
+            .ok_or_else(|| VectaDBError::BadRequest("Plugin not initialized".to_string()))?;
 
         let url = format!("{}/embed", config.base_url);
 
@@ -180,7 +180,7 @@ impl EmbeddingPlugin for CoherePlugin {
                 });
                 Ok(())
             }
-            _ => Err(VectaDBError::InvalidInput(
+            _ => Err(VectaDBError::BadRequest(
                 "Invalid provider config for Cohere plugin".to_string(),
             )),
         }
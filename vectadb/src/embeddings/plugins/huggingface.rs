@@ -53,7 +53,7 @@ impl HuggingFacePlugin {
         let config = self
             .config
             .as_ref()
-            .ok_or_else(|| VectaDBError::InvalidInput("Plugin not initialized".to_string()))?;
+            .ok_or_else(|| VectaDBError::BadRequest("Plugin not initialized".to_string()))?;
 
         let url = format!("{}/models/{}", config.base_url, config.model);
 
@@ -170,7 +170,7 @@ impl EmbeddingPlugin for HuggingFacePlugin {
                 });
                 Ok(())
             }
-            _ => Err(VectaDBError::InvalidInput(
+            _ => Err(VectaDBError::BadRequest(
                 "Invalid provider config for HuggingFace plugin".to_string(),
             )),
         }
@@ -74,7 +74,7 @@ impl OpenAIPlugin {
         let config = self
             .config
             .as_ref()
-            .ok_or_else(|| VectaDBError::InvalidInput("Plugin not initialized".to_string()))?;
+            .ok_or_else(|| VectaDBError::BadRequest("Plugin not initialized".to_string()))?;
 
         let url = format!("{}/embeddings", config.base_url);
 
@@ -176,7 +176,7 @@ impl EmbeddingPlugin for OpenAIPlugin {
                 });
                 Ok(())
             }
-            _ => Err(VectaDBError::InvalidInput(
+            _ => Err(VectaDBError::BadRequest(
                 "Invalid provider config for OpenAI plugin".to_string(),
             )),
         }
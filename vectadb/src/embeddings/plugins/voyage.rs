@@ -66,7 +66,7 @@ impl VoyagePlugin {
         let config = self
             .config
             .as_ref()
-            .ok_or_else(|| VectaDBError::InvalidInput("Plugin not initialized".to_string()))?;
+            .ok_or_else(|| VectaDBError::BadRequest("Plugin not initialized".to_string()))?;
 
         let url = format!("{}/embeddings", config.base_url);
 
@@ -170,7 +170,7 @@ impl EmbeddingPlugin for VoyagePlugin {
                 });
                 Ok(())
             }
-            _ => Err(VectaDBError::InvalidInput(
+            _ => Err(VectaDBError::BadRequest(
                 "Invalid provider config for Voyage plugin".to_string(),
             )),
         }
@@ -124,7 +124,7 @@ impl EmbeddingService {
     /// Calculate cosine similarity between two embeddings
     pub fn cosine_similarity(&self, a: &[f32], b: &[f32]) -> Result<f32> {
         if a.len() != b.len() {
-            return Err(VectaDBError::InvalidInput(
+            return Err(VectaDBError::BadRequest(
                 "Embeddings must have the same dimension".to_string(),
             ));
         }
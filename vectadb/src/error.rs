@@ -3,32 +3,48 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
 use thiserror::Error;
 
+use crate::api::types::ErrorResponse;
+
 /// VectaDB Result type
 pub type Result<T> = std::result::Result<T, VectaDBError>;
 
 /// Main error type for VectaDB
 #[derive(Error, Debug)]
 pub enum VectaDBError {
-    #[error("SurrealDB error: {0}")]
-    SurrealDB(String),
-
-    #[error("Qdrant error: {0}")]
-    Qdrant(String),
+    #[error("Database error: {0}")]
+    Database(String),
 
     #[error("Embedding error: {0}")]
     Embedding(String),
 
+    #[error("Embedding dimension mismatch: collection expects {expected}, got {got}")]
+    EmbeddingDimensionMismatch { expected: usize, got: usize },
+
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Validation failed: {0}")]
+    Validation(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
-    #[error("Invalid input: {0}")]
-    InvalidInput(String),
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Unprocessable request: {0}")]
+    Unprocessable(String),
 
     #[error("Authentication failed: {0}")]
     Unauthorized(String),
@@ -38,34 +54,110 @@ pub enum VectaDBError {
 
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    /// Another error answered with a more specific `error` code; see [`VectaDBError::with_code`]
+    #[error("{inner}")]
+    Coded {
+        code: &'static str,
+        inner: Box<VectaDBError>,
+    },
+}
+
+impl VectaDBError {
+    /// Keep this error's status but answer with `code` in the `error` field,
+    /// e.g. `EntityNotFound` rather than the generic `NotFound`
+    pub fn with_code(self, code: &'static str) -> Self {
+        VectaDBError::Coded {
+            code,
+            inner: Box::new(self),
+        }
+    }
+
+    /// HTTP status returned to API clients
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            VectaDBError::Validation(_) | VectaDBError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            VectaDBError::NotFound(_) => StatusCode::NOT_FOUND,
+            VectaDBError::Conflict(_) => StatusCode::CONFLICT,
+            VectaDBError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            VectaDBError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            VectaDBError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            VectaDBError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            VectaDBError::Database(_)
+            | VectaDBError::Embedding(_)
+            | VectaDBError::EmbeddingDimensionMismatch { .. }
+            | VectaDBError::Config(_)
+            | VectaDBError::Internal(_)
+            | VectaDBError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            VectaDBError::Coded { inner, .. } => inner.status_code(),
+        }
+    }
+
+    /// Machine-readable code used in the `error` field of responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            VectaDBError::Database(_) => "DatabaseError",
+            VectaDBError::Embedding(_) => "EmbeddingError",
+            VectaDBError::EmbeddingDimensionMismatch { .. } => "EmbeddingDimensionMismatch",
+            VectaDBError::Config(_) => "ConfigError",
+            VectaDBError::Validation(_) => "ValidationError",
+            VectaDBError::NotFound(_) => "NotFound",
+            VectaDBError::Unavailable(_) => "ServiceNotAvailable",
+            VectaDBError::BadRequest(_) => "BadRequest",
+            VectaDBError::Conflict(_) => "Conflict",
+            VectaDBError::PayloadTooLarge(_) => "PayloadTooLarge",
+            VectaDBError::Unprocessable(_) => "UnprocessableEntity",
+            VectaDBError::Unauthorized(_) => "Unauthorized",
+            VectaDBError::Internal(_) => "InternalError",
+            VectaDBError::Serialization(_) => "SerializationError",
+            VectaDBError::Coded { code, .. } => *code,
+        }
+    }
+
+    /// Human-readable message without the variant prefix
+    fn message(&self) -> String {
+        match self {
+            VectaDBError::Database(msg)
+            | VectaDBError::Embedding(msg)
+            | VectaDBError::Config(msg)
+            | VectaDBError::Validation(msg)
+            | VectaDBError::NotFound(msg)
+            | VectaDBError::Unavailable(msg)
+            | VectaDBError::BadRequest(msg)
+            | VectaDBError::Conflict(msg)
+            | VectaDBError::PayloadTooLarge(msg)
+            | VectaDBError::Unprocessable(msg)
+            | VectaDBError::Unauthorized(msg)
+            | VectaDBError::Internal(msg)
+            | VectaDBError::Serialization(msg) => msg.clone(),
+            VectaDBError::EmbeddingDimensionMismatch { .. } => {
+                format!("{}; re-index the collection or check the embedding model", self)
+            }
+            VectaDBError::Coded { inner, .. } => inner.message(),
+        }
+    }
 }
 
 impl IntoResponse for VectaDBError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            VectaDBError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            VectaDBError::InvalidInput(msg) => (StatusCode::BAD_REQUEST, msg),
-            VectaDBError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-            VectaDBError::SurrealDB(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", msg)),
-            VectaDBError::Qdrant(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Vector DB error: {}", msg)),
-            VectaDBError::Embedding(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Embedding error: {}", msg)),
-            VectaDBError::Config(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Config error: {}", msg)),
-            VectaDBError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            VectaDBError::Serialization(msg) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Serialization error: {}", msg)),
-        };
-
-        let body = Json(json!({
-            "error": error_message,
-        }));
-
-        (status, body).into_response()
+        <(StatusCode, Json<ErrorResponse>)>::from(self).into_response()
+    }
+}
+
+/// Lets handlers that return the `(StatusCode, Json<ErrorResponse>)` pair use `?` on VectaDB errors
+impl From<VectaDBError> for (StatusCode, Json<ErrorResponse>) {
+    fn from(err: VectaDBError) -> Self {
+        (
+            err.status_code(),
+            Json(ErrorResponse::new(err.code(), err.message())),
+        )
     }
 }
 
 // Conversion from common error types
 impl From<surrealdb::Error> for VectaDBError {
     fn from(err: surrealdb::Error) -> Self {
-        VectaDBError::SurrealDB(err.to_string())
+        VectaDBError::Database(err.to_string())
     }
 }
 
@@ -86,3 +178,70 @@ impl From<std::io::Error> for VectaDBError {
         VectaDBError::Internal(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    async fn render(err: VectaDBError) -> (StatusCode, serde_json::Value) {
+        let response = err.into_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_variants_map_to_status_and_body() {
+        let cases = vec![
+            (VectaDBError::Database("query failed".into()), StatusCode::INTERNAL_SERVER_ERROR, "DatabaseError"),
+            (VectaDBError::Embedding("provider down".into()), StatusCode::INTERNAL_SERVER_ERROR, "EmbeddingError"),
+            (VectaDBError::Validation("missing name".into()), StatusCode::BAD_REQUEST, "ValidationError"),
+            (VectaDBError::NotFound("entity 'x'".into()), StatusCode::NOT_FOUND, "NotFound"),
+            (VectaDBError::Unavailable("Database not connected".into()), StatusCode::SERVICE_UNAVAILABLE, "ServiceNotAvailable"),
+            (VectaDBError::BadRequest("bad cursor".into()), StatusCode::BAD_REQUEST, "BadRequest"),
+            (VectaDBError::Conflict("already exists".into()), StatusCode::CONFLICT, "Conflict"),
+            (VectaDBError::PayloadTooLarge("line too long".into()), StatusCode::PAYLOAD_TOO_LARGE, "PayloadTooLarge"),
+            (VectaDBError::Unprocessable("key reused".into()), StatusCode::UNPROCESSABLE_ENTITY, "UnprocessableEntity"),
+        ];
+
+        for (err, status, code) in cases {
+            let message = err.message();
+            let (got_status, body) = render(err).await;
+            assert_eq!(got_status, status, "status for {}", code);
+            assert_eq!(body["error"], code);
+            assert_eq!(body["message"], message);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dimension_mismatch_message() {
+        let (status, body) = render(VectaDBError::EmbeddingDimensionMismatch { expected: 384, got: 768 }).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "EmbeddingDimensionMismatch");
+        assert_eq!(
+            body["message"],
+            "Embedding dimension mismatch: collection expects 384, got 768; re-index the collection or check the embedding model"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_specific_code_keeps_status_and_message() {
+        let err = VectaDBError::NotFound("Entity 'x' not found".into()).with_code("EntityNotFound");
+        assert_eq!(err.to_string(), "Not found: Entity 'x' not found");
+
+        let (status, body) = render(err).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "EntityNotFound");
+        assert_eq!(body["message"], "Entity 'x' not found");
+    }
+
+    #[test]
+    fn test_error_pair_conversion() {
+        let (status, Json(body)) = <(StatusCode, Json<ErrorResponse>)>::from(VectaDBError::NotFound(
+            "Entity 'x' not found".into(),
+        ));
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body.message, "Entity 'x' not found");
+    }
+}
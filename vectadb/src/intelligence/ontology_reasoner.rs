@@ -1,8 +1,9 @@
 // Ontology-aware query reasoning and expansion
 
-use crate::ontology::schema::OntologySchema;
+use crate::ontology::schema::{Condition, OntologySchema, RuleType};
 use crate::error::{Result, VectaDBError};
 use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
 
 /// Expanded query with ontology-inferred information
 #[derive(Debug, Clone)]
@@ -50,24 +51,63 @@ pub enum InferenceReason {
 
     /// Subtype compatibility
     SubtypeInheritance,
+
+    /// Concluded by one of the schema's inference rules
+    Rule,
 }
 
 /// Ontology-aware reasoner
 pub struct OntologyReasoner {
     schema: OntologySchema,
+    /// Memoized `expand_query` results per entity type. The schema only
+    /// changes through `update_schema`, which clears it; a reasoner that is
+    /// swapped out takes its cache with it.
+    expansions: RwLock<HashMap<String, ExpandedQuery>>,
 }
 
 impl OntologyReasoner {
     /// Create a new reasoner with the given schema
     pub fn new(schema: OntologySchema) -> Self {
-        Self { schema }
+        Self {
+            schema,
+            expansions: RwLock::new(HashMap::new()),
+        }
     }
 
-    /// Expand a query to include subtypes and inferred relations
+    /// Expand a query to include subtypes and inferred relations. Results are
+    /// cached per entity type until the schema changes.
     pub fn expand_query(&self, entity_type: &str) -> Result<ExpandedQuery> {
+        if let Some(expanded) = self.expansions.read().unwrap().get(entity_type) {
+            return Ok(expanded.clone());
+        }
+
+        let expanded = self.compute_expansion(entity_type)?;
+        self.expansions
+            .write()
+            .unwrap()
+            .insert(entity_type.to_string(), expanded.clone());
+        Ok(expanded)
+    }
+
+    /// Expand every entity type in the schema ahead of the first query.
+    /// Returns the expansions keyed by entity type.
+    pub fn materialize(&self) -> HashMap<String, ExpandedQuery> {
+        for entity_type in self.schema.entity_types.keys() {
+            // Only unknown types fail, and these all come from the schema
+            let _ = self.expand_query(entity_type);
+        }
+        self.expansions.read().unwrap().clone()
+    }
+
+    /// Number of entity types with a cached expansion
+    pub fn cached_expansions(&self) -> usize {
+        self.expansions.read().unwrap().len()
+    }
+
+    fn compute_expansion(&self, entity_type: &str) -> Result<ExpandedQuery> {
         // Check if entity type exists
         if !self.schema.entity_types.contains_key(entity_type) {
-            return Err(VectaDBError::InvalidInput(format!(
+            return Err(VectaDBError::BadRequest(format!(
                 "Entity type '{}' not found in ontology",
                 entity_type
             )));
@@ -135,6 +175,68 @@ impl OntologyReasoner {
             }
         }
 
+        for relation in self.infer_rule_relations(entity_type) {
+            let known = inferred.iter().any(|r| {
+                r.relation_type == relation.relation_type
+                    && r.source_type == relation.source_type
+                    && r.target_type == relation.target_type
+            });
+            if !known {
+                inferred.push(relation);
+            }
+        }
+
+        inferred
+    }
+
+    /// Relations from `entity_type` concluded by the schema's inference rules.
+    /// Rule terms starting with `?` are variables and anything else names an
+    /// entity type. Conditions are matched against relation type signatures,
+    /// with domain and range widened to their subtypes, and a rule concludes
+    /// once for every consistent binding of its variables. Disjoint rules are
+    /// constraints and conclude nothing.
+    fn infer_rule_relations(&self, entity_type: &str) -> Vec<InferredRelation> {
+        let mut signatures = Vec::new();
+        for (relation_id, relation_type) in &self.schema.relation_types {
+            for source in self.schema.get_subtypes(&relation_type.domain) {
+                for target in self.schema.get_subtypes(&relation_type.range) {
+                    signatures.push([source.clone(), relation_id.clone(), target]);
+                }
+            }
+        }
+
+        let mut inferred: Vec<InferredRelation> = Vec::new();
+        for rule in &self.schema.rules {
+            if rule.rule_type == RuleType::Disjoint || rule.conditions.is_empty() {
+                continue;
+            }
+
+            for bindings in match_conditions(&rule.conditions, &signatures) {
+                let conclusion = &rule.conclusion;
+                let (Some(source), Some(target)) = (
+                    resolve_term(&conclusion.subject, &bindings),
+                    resolve_term(&conclusion.object, &bindings),
+                ) else {
+                    continue;
+                };
+                if !self.is_type_compatible(entity_type, source) {
+                    continue;
+                }
+
+                let duplicate = inferred.iter().any(|r| {
+                    r.relation_type == conclusion.predicate && r.target_type == target
+                });
+                if !duplicate {
+                    inferred.push(InferredRelation {
+                        relation_type: conclusion.predicate.clone(),
+                        source_type: entity_type.to_string(),
+                        target_type: target.to_string(),
+                        reason: InferenceReason::Rule,
+                    });
+                }
+            }
+        }
+
         inferred
     }
 
@@ -217,10 +319,11 @@ impl OntologyReasoner {
     pub fn update_schema(&mut self, schema: OntologySchema) -> Result<()> {
         // Validate new schema
         schema.validate().map_err(|e| {
-            VectaDBError::InvalidInput(format!("Invalid ontology schema: {}", e))
+            VectaDBError::BadRequest(format!("Invalid ontology schema: {}", e))
         })?;
 
         self.schema = schema;
+        self.expansions.write().unwrap().clear();
         Ok(())
     }
 
@@ -230,6 +333,63 @@ impl OntologyReasoner {
     }
 }
 
+/// Every assignment of rule variables under which all `conditions` hold
+/// among `facts`, each a subject, predicate and object
+fn match_conditions(
+    conditions: &[Condition],
+    facts: &[[String; 3]],
+) -> Vec<HashMap<String, String>> {
+    let mut matches = vec![HashMap::new()];
+
+    for condition in conditions {
+        let terms = [&condition.subject, &condition.predicate, &condition.object];
+        let mut extended = Vec::new();
+
+        for bindings in &matches {
+            for fact in facts {
+                let mut candidate = bindings.clone();
+                if terms
+                    .iter()
+                    .zip(fact)
+                    .all(|(term, value)| bind_term(term, value, &mut candidate))
+                {
+                    extended.push(candidate);
+                }
+            }
+        }
+
+        matches = extended;
+        if matches.is_empty() {
+            break;
+        }
+    }
+
+    matches
+}
+
+/// Match one term against a value, binding it if it is a free variable
+fn bind_term(term: &str, value: &str, bindings: &mut HashMap<String, String>) -> bool {
+    if !term.starts_with('?') {
+        return term == value;
+    }
+    match bindings.get(term) {
+        Some(bound) => bound == value,
+        None => {
+            bindings.insert(term.to_string(), value.to_string());
+            true
+        }
+    }
+}
+
+/// The value of a term: its binding for a variable, itself for a constant
+fn resolve_term<'a>(term: &'a str, bindings: &'a HashMap<String, String>) -> Option<&'a str> {
+    if term.starts_with('?') {
+        bindings.get(term).map(String::as_str)
+    } else {
+        Some(term)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -346,6 +506,81 @@ mod tests {
         assert!(symmetric_count > 0);
     }
 
+    #[test]
+    fn test_rule_inference() {
+        use crate::ontology::schema::{Conclusion, InferenceRule};
+
+        let condition = |subject: &str, predicate: &str, object: &str| Condition {
+            subject: subject.to_string(),
+            predicate: predicate.to_string(),
+            object: object.to_string(),
+        };
+
+        let mut schema = create_test_schema();
+        schema.add_entity_type(EntityType::new("Tool".to_string(), "Tool".to_string()));
+        schema.add_relation_type(RelationType::new(
+            "uses".to_string(),
+            "uses".to_string(),
+            "Task".to_string(),
+            "Tool".to_string(),
+        ));
+        // An agent depends on the tools its tasks use
+        schema.add_rule(InferenceRule {
+            id: "agent_depends_on_tool".to_string(),
+            rule_type: RuleType::PropertyChain,
+            description: "executes o uses -> depends_on".to_string(),
+            conditions: vec![
+                condition("?agent", "executes", "?task"),
+                condition("?task", "uses", "?tool"),
+            ],
+            conclusion: Conclusion {
+                subject: "?agent".to_string(),
+                predicate: "depends_on".to_string(),
+                object: "?tool".to_string(),
+            },
+        });
+        // Never matches: nothing executes a tool
+        schema.add_rule(InferenceRule {
+            id: "unmatched".to_string(),
+            rule_type: RuleType::Custom("test".to_string()),
+            description: "executes a tool".to_string(),
+            conditions: vec![condition("?agent", "executes", "Tool")],
+            conclusion: Conclusion {
+                subject: "?agent".to_string(),
+                predicate: "operates".to_string(),
+                object: "Tool".to_string(),
+            },
+        });
+        let reasoner = OntologyReasoner::new(schema);
+
+        let rule_relations = |entity_type: &str| -> Vec<InferredRelation> {
+            reasoner
+                .infer_relations(entity_type)
+                .into_iter()
+                .filter(|r| r.reason == InferenceReason::Rule)
+                .collect()
+        };
+
+        let agent = rule_relations("Agent");
+        assert_eq!(agent.len(), 1);
+        assert_eq!(agent[0].relation_type, "depends_on");
+        assert_eq!(agent[0].source_type, "Agent");
+        assert_eq!(agent[0].target_type, "Tool");
+
+        // Subtypes satisfy the condition too
+        let llm_agent = rule_relations("LLMAgent");
+        assert_eq!(llm_agent.len(), 1);
+        assert_eq!(llm_agent[0].source_type, "LLMAgent");
+
+        assert!(rule_relations("Task").is_empty());
+
+        let expanded = reasoner.expand_query("Agent").unwrap();
+        assert!(expanded
+            .inferred_relations
+            .iter()
+            .any(|r| r.relation_type == "depends_on" && r.reason == InferenceReason::Rule));
+    }
+
     #[test]
     fn test_get_compatible_relations() {
         let schema = create_test_schema();
@@ -391,6 +626,37 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_expand_query_is_cached_until_schema_update() {
+        let mut reasoner = OntologyReasoner::new(create_test_schema());
+        assert_eq!(reasoner.cached_expansions(), 0);
+
+        let first = reasoner.expand_query("Agent").unwrap();
+        let second = reasoner.expand_query("Agent").unwrap();
+        assert_eq!(first.expanded_types, second.expanded_types);
+        assert_eq!(reasoner.cached_expansions(), 1);
+
+        // Unknown types are not cached
+        assert!(reasoner.expand_query("UnknownType").is_err());
+        assert_eq!(reasoner.cached_expansions(), 1);
+
+        let materialized = reasoner.materialize();
+        assert_eq!(materialized.len(), 4);
+        assert_eq!(reasoner.cached_expansions(), 4);
+
+        let mut schema = create_test_schema();
+        schema.add_entity_type(
+            EntityType::new("ToolAgent".to_string(), "Tool Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        reasoner.update_schema(schema).unwrap();
+        assert_eq!(reasoner.cached_expansions(), 0);
+
+        let expanded = reasoner.expand_query("Agent").unwrap();
+        assert_eq!(expanded.expanded_types.len(), 4);
+        assert!(expanded.expanded_types.contains(&"ToolAgent".to_string()));
+    }
+
     #[test]
     fn test_update_schema() {
         let schema = create_test_schema();
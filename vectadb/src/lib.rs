@@ -10,3 +10,4 @@ pub mod intelligence;
 pub mod models;
 pub mod ontology;
 pub mod query;
+pub mod ttl_map;
@@ -10,6 +10,7 @@ mod intelligence;
 mod api;
 mod db;
 mod query;
+mod ttl_map;
 
 use config::Config;
 use error::Result;
@@ -51,15 +52,23 @@ async fn main() -> Result<()> {
 
     // Initialize database connections
     tracing::info!("Connecting to SurrealDB...");
+    let mut surreal_pending = false;
     let surreal = match db::SurrealDBClient::new(&config.database).await {
         Ok(client) => {
             tracing::info!("SurrealDB connected successfully");
             Some(Arc::new(client))
         }
-        Err(e) => {
-            warn!("Failed to connect to SurrealDB: {}. Continuing without database support.", e);
-            None
-        }
+        Err(e) => match db::SurrealDBClient::disconnected(&config.database) {
+            Ok(client) => {
+                warn!("Failed to connect to SurrealDB: {}. Retrying in the background.", e);
+                surreal_pending = true;
+                Some(Arc::new(client))
+            }
+            Err(_) => {
+                warn!("Failed to connect to SurrealDB: {}. Continuing without database support.", e);
+                None
+            }
+        },
     };
 
     tracing::info!("Connecting to Qdrant...");
@@ -91,55 +100,116 @@ async fn main() -> Result<()> {
     // Load ontology schema from database if available
     let reasoner = Arc::new(RwLock::new(None));
     if let Some(ref surreal_client) = surreal {
-        match surreal_client.get_schema().await {
-            Ok(Some(schema)) => {
-                tracing::info!("Loaded ontology schema from database");
-                let mut reasoner_guard = reasoner.write().await;
-                let r = intelligence::OntologyReasoner::new(schema);
-                tracing::info!("Ontology reasoner initialized with persisted schema");
-                *reasoner_guard = Some(r);
-            }
-            Ok(None) => {
-                tracing::info!("No ontology schema found in database");
-            }
-            Err(e) => {
-                warn!("Failed to load schema from database: {}", e);
-            }
+        if !surreal_pending {
+            api::handlers::load_persisted_schema(surreal_client, &reasoner).await;
         }
+        tokio::spawn(api::handlers::keep_surreal_connected(surreal_client.clone(), reasoner.clone(), surreal_pending));
     }
 
-    // Create API router with database support
-    let app = if surreal.is_some() && qdrant.is_some() && embedding_service.is_some() {
+    // Create API state with database support
+    let mut state = if surreal.is_some() && qdrant.is_some() && embedding_service.is_some() {
         tracing::info!("Creating API router with full database support");
-        let state = api::handlers::AppState::with_databases(
+        api::handlers::AppState::with_databases(
             reasoner.clone(),
             surreal.unwrap(),
             qdrant.unwrap(),
             embedding_service.unwrap(),
-        );
-        api::routes::create_router_with_state(state)
+        )
     } else {
         tracing::info!("Creating API router without database support (ontology-only mode)");
         let mut state = api::handlers::AppState::new();
         state.reasoner = reasoner;
-        api::routes::create_router_with_state(state)
+        state
     };
 
+    state.limits = config.limits.clone();
+    state.query = config.query.clone();
+    state.pricing = config.pricing.clone();
+    state.sampler = Arc::new(api::sampling::TraceSampler::new(config.analytics.sampling_rate));
+    state.cors = config.cors.clone();
+    state.timeouts = api::timeout::RequestTimeouts::from_config(&config.server);
+    state.idempotency = Arc::new(api::idempotency::IdempotencyStore::new(
+        std::time::Duration::from_secs(config.idempotency.ttl_secs),
+    ));
+    state.set_query_cache_ttl(std::time::Duration::from_secs(config.query.query_cache_ttl_secs));
+
+    if config.audit.enabled {
+        match &state.surreal {
+            Some(surreal) => {
+                tracing::info!("Audit logging enabled: creates, updates and deletes are recorded");
+                state.audit = Some(api::audit::AuditLogger::start(surreal.clone(), config.audit.queue_size));
+            }
+            None => warn!("Audit logging disabled: it needs SurrealDB"),
+        }
+    }
+
+    if config.database.qdrant.warmup_collections {
+        api::handlers::warm_collections(&state).await;
+    }
+
+    let retention = db::retention::RetentionPolicy::from_config(&config.analytics);
+    if let (Some(surreal), Some(policy)) = (&state.surreal, retention) {
+        tracing::info!("Retention enabled: purging events older than {} days", config.analytics.retention_days);
+        tokio::spawn(db::retention::enforce_retention(surreal.clone(), state.qdrant.clone(), policy));
+    }
+
+    if config.rate_limit.enabled {
+        tracing::info!(
+            "Rate limiting enabled: {} req/s, burst {}",
+            config.rate_limit.requests_per_second,
+            config.rate_limit.burst
+        );
+        state.rate_limiter = Some(Arc::new(api::rate_limit::RateLimiter::new(&config.rate_limit)));
+    }
+
+    if config.tenancy.enabled {
+        tracing::info!("Multi-tenancy enabled: requests must carry an X-Tenant-ID header");
+        state.tenants = Some(Arc::new(api::tenancy::TenantRegistry::new(
+            state.clone(),
+            config.database.clone(),
+            config.tenancy.allowed_tenants.clone(),
+            db::retention::RetentionPolicy::from_config(&config.analytics),
+        )));
+    }
+
+    if config.server.grpc_enabled {
+        if config.tenancy.enabled {
+            warn!("gRPC interface disabled: it does not support multi-tenancy");
+        } else {
+            let addr = format!("{}:{}", config.server.host, config.server.grpc_port);
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .map_err(|e| crate::error::VectaDBError::Internal(format!("Failed to bind to {}: {}", addr, e)))?;
+            tracing::info!("VectaDB gRPC server listening on {}", addr);
+            let grpc_state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = api::grpc::serve(grpc_state, listener, shutdown_signal()).await {
+                    tracing::error!("gRPC server error: {}", e);
+                }
+            });
+        }
+    }
+
+    let app = api::routes::create_router_with_state(state);
+
     // Start HTTP server
     let addr = format!("{}:{}", config.server.host, config.server.port);
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
-        .map_err(|e| crate::error::VectaDBError::Config(format!("Failed to bind to {}: {}", addr, e)))?;
+        .map_err(|e| crate::error::VectaDBError::Internal(format!("Failed to bind to {}: {}", addr, e)))?;
 
     tracing::info!("VectaDB API server listening on {}", addr);
     tracing::info!("VectaDB initialized successfully");
     tracing::info!("Press Ctrl+C to shutdown");
 
     // Run server with graceful shutdown
-    axum::serve(listener, app)
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
         .with_graceful_shutdown(shutdown_signal())
         .await
-        .map_err(|e| crate::error::VectaDBError::Config(format!("Server error: {}", e)))?;
+        .map_err(|e| crate::error::VectaDBError::Internal(format!("Server error: {}", e)))?;
 
     tracing::info!("Shutting down VectaDB...");
     Ok(())
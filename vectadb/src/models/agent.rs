@@ -1,9 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::{log::Log, task::Task, thought::Thought};
+
+/// Entity type agents are stored under
+pub const AGENT_ENTITY_TYPE: &str = "Agent";
+
+/// Symmetric relation between agents working together
+pub const COLLABORATES_RELATION: &str = "collaborates_with";
 
 /// Agent model - represents an AI agent in the system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Agent {
     /// Unique identifier (nanoid)
     pub id: String,
@@ -16,6 +26,7 @@ pub struct Agent {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 
     /// Creation timestamp
@@ -27,7 +38,7 @@ pub struct Agent {
 }
 
 /// Request to create a new agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateAgentRequest {
     /// Agent role (e.g., "researcher", "writer", "analyst")
     pub role: String,
@@ -37,26 +48,35 @@ pub struct CreateAgentRequest {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 }
 
 /// Agent with all related entities (tasks, thoughts, logs)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AgentWithRelations {
     #[serde(flatten)]
     pub agent: Agent,
 
     /// Related tasks
     #[serde(default)]
-    pub tasks: Vec<super::task::Task>,
+    pub tasks: Vec<Task>,
 
     /// Related thoughts
     #[serde(default)]
-    pub thoughts: Vec<super::thought::Thought>,
+    pub thoughts: Vec<Thought>,
 
     /// Related logs
     #[serde(default)]
-    pub logs: Vec<super::log::Log>,
+    pub logs: Vec<Log>,
+
+    /// IDs of the traces the agent ran
+    #[serde(default)]
+    pub trace_ids: Vec<String>,
+
+    /// Agents this agent collaborates with
+    #[serde(default)]
+    pub collaborators: Vec<Agent>,
 }
 
 impl Agent {
@@ -72,6 +92,21 @@ impl Agent {
         }
     }
 
+    /// Create an agent from an API request
+    pub fn from_request(request: CreateAgentRequest) -> Self {
+        Self::new(request.role, request.goal, request.metadata)
+    }
+
+    /// Entity properties for storing this agent (every field but the id)
+    pub fn to_properties(&self) -> HashMap<String, JsonValue> {
+        super::record_properties(self)
+    }
+
+    /// Rebuild an agent from a stored entity's id and properties
+    pub fn from_properties(id: &str, properties: &HashMap<String, JsonValue>) -> Option<Self> {
+        super::record_from_properties(id, properties)
+    }
+
     /// Build searchable text from agent metadata for embedding
     pub fn to_searchable_text(&self) -> String {
         let mut parts = vec![
@@ -135,4 +170,23 @@ mod tests {
         assert!(text.contains("skills"));
         assert!(text.contains("specialty"));
     }
+
+    #[test]
+    fn test_agent_properties_round_trip() {
+        let request = CreateAgentRequest {
+            role: "writer".to_string(),
+            goal: "draft the report".to_string(),
+            metadata: json!({"tone": "formal"}),
+        };
+        let agent = Agent::from_request(request);
+
+        let properties = agent.to_properties();
+        assert!(!properties.contains_key("id"));
+
+        let restored = Agent::from_properties(&agent.id, &properties).unwrap();
+        assert_eq!(restored.id, agent.id);
+        assert_eq!(restored.role, "writer");
+        assert_eq!(restored.metadata, json!({"tone": "formal"}));
+        assert_eq!(restored.created_at, agent.created_at);
+    }
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
+use crate::error::{Result, VectaDBError};
+
 /// Metadata for embedded items in vector database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingMetadata {
@@ -13,6 +15,57 @@ pub struct EmbeddingMetadata {
     /// Original metadata from the item
     #[serde(default)]
     pub metadata: JsonValue,
+
+    /// Embedding provider that produced the vectors
+    #[serde(default)]
+    pub provider: String,
+
+    /// Embedding model that produced the vectors
+    #[serde(default)]
+    pub model: String,
+
+    /// Vector dimension
+    #[serde(default)]
+    pub dimension: usize,
+}
+
+impl EmbeddingMetadata {
+    /// Metadata describing the vectors stored in a collection
+    pub fn for_collection(collection: &str, provider: &str, model: &str, dimension: usize) -> Self {
+        Self {
+            id: collection.to_string(),
+            item_type: "collection".to_string(),
+            metadata: JsonValue::Null,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            dimension,
+        }
+    }
+
+    /// Check that a vector of length `got` can be used against this collection
+    pub fn validate_dimension(&self, got: usize) -> Result<()> {
+        if got == self.dimension {
+            return Ok(());
+        }
+
+        Err(VectaDBError::BadRequest(format!(
+            "Query vector has dimension {} but collection '{}' holds {}-dimensional vectors from {}/{}; re-index the collection or switch providers",
+            got, self.id, self.dimension, self.provider, self.model
+        )))
+    }
+
+    /// Check that queries embedded by `provider` can be matched against this
+    /// collection. Collections recorded before providers were tracked pass.
+    pub fn validate_provider(&self, provider: &str) -> Result<()> {
+        if self.provider.is_empty() || self.provider == provider {
+            return Ok(());
+        }
+
+        Err(VectaDBError::BadRequest(format!(
+            "Collection '{}' holds vectors from {}/{} but queries for it are embedded by provider '{}'; re-index the collection or change the provider routing",
+            self.id, self.provider, self.model, provider
+        )))
+    }
 }
 
 /// Result from similarity search
@@ -84,6 +137,33 @@ mod tests {
         assert_eq!(req.limit, 10);
     }
 
+    #[test]
+    fn test_collection_dimension_match() {
+        let meta = EmbeddingMetadata::for_collection("Log", "openai", "text-embedding-3-small", 1536);
+        assert!(meta.validate_dimension(1536).is_ok());
+    }
+
+    #[test]
+    fn test_collection_dimension_mismatch() {
+        let meta = EmbeddingMetadata::for_collection("Log", "openai", "text-embedding-3-small", 1536);
+        let err = meta.validate_dimension(1024).unwrap_err();
+        assert!(matches!(err, VectaDBError::BadRequest(_)));
+        assert!(err.to_string().contains("1024"));
+        assert!(err.to_string().contains("1536"));
+    }
+
+    #[test]
+    fn test_collection_provider_mismatch() {
+        let meta = EmbeddingMetadata::for_collection("Code", "openai", "text-embedding-3-small", 1536);
+        assert!(meta.validate_provider("openai").is_ok());
+        let err = meta.validate_provider("cohere").unwrap_err();
+        assert!(matches!(err, VectaDBError::BadRequest(_)));
+        assert!(err.to_string().contains("cohere"));
+
+        let untracked = EmbeddingMetadata::for_collection("Code", "", "", 1536);
+        assert!(untracked.validate_provider("cohere").is_ok());
+    }
+
     #[test]
     fn test_similarity_search_request_builder() {
         let req = SimilaritySearchRequest::new("test query".to_string())
@@ -1,9 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Entity type logs are stored under
+pub const LOG_ENTITY_TYPE: &str = "Log";
 
 /// Log level enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum LogLevel {
     Debug,
@@ -32,7 +37,7 @@ impl std::fmt::Display for LogLevel {
 }
 
 /// Log model - represents a log entry from an agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Log {
     /// Unique identifier (nanoid)
     pub id: String,
@@ -53,6 +58,7 @@ pub struct Log {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 
     /// Timestamp
@@ -60,7 +66,7 @@ pub struct Log {
 }
 
 /// Request to create a new log
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateLogRequest {
     /// ID of the agent that generated this log
     pub agent_id: String,
@@ -78,6 +84,7 @@ pub struct CreateLogRequest {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 }
 
@@ -101,6 +108,27 @@ impl Log {
         }
     }
 
+    /// Create a log from an API request
+    pub fn from_request(request: CreateLogRequest) -> Self {
+        Self::new(
+            request.agent_id,
+            request.task_id,
+            request.level,
+            request.message,
+            request.metadata,
+        )
+    }
+
+    /// Entity properties for storing this log (every field but the id)
+    pub fn to_properties(&self) -> HashMap<String, JsonValue> {
+        super::record_properties(self)
+    }
+
+    /// Rebuild a log from a stored entity's id and properties
+    pub fn from_properties(id: &str, properties: &HashMap<String, JsonValue>) -> Option<Self> {
+        super::record_from_properties(id, properties)
+    }
+
     /// Check if this is an error-level log
     pub fn is_error(&self) -> bool {
         matches!(self.level, LogLevel::Error | LogLevel::Critical)
@@ -164,6 +192,31 @@ mod tests {
         assert!(critical_log.is_error());
     }
 
+    #[test]
+    fn test_log_properties_round_trip() {
+        let log = Log::new(
+            "agent123".to_string(),
+            Some("task456".to_string()),
+            LogLevel::Warning,
+            "Retrying request".to_string(),
+            json!({"attempt": 2}),
+        );
+
+        let properties = log.to_properties();
+        assert!(!properties.contains_key("id"));
+        assert_eq!(properties["level"], json!("WARNING"));
+
+        let restored = Log::from_properties(&log.id, &properties).unwrap();
+        assert_eq!(restored.id, log.id);
+        assert_eq!(restored.task_id, log.task_id);
+        assert_eq!(restored.level, LogLevel::Warning);
+        assert_eq!(restored.metadata, json!({"attempt": 2}));
+        assert_eq!(restored.timestamp, log.timestamp);
+
+        // Properties of some other entity are not a log
+        assert!(Log::from_properties("x", &HashMap::new()).is_none());
+    }
+
     #[test]
     fn test_log_level_display() {
         assert_eq!(LogLevel::Info.to_string(), "INFO");
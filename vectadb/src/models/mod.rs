@@ -6,12 +6,33 @@ pub mod embedding;
 
 // Re-export models for convenience (currently unused but may be needed by API layer)
 #[allow(unused_imports)]
-pub use agent::{Agent, CreateAgentRequest, AgentWithRelations};
+pub use agent::{Agent, CreateAgentRequest, AgentWithRelations, AGENT_ENTITY_TYPE, COLLABORATES_RELATION};
 #[allow(unused_imports)]
-pub use task::{Task, CreateTaskRequest, TaskWithRelations};
+pub use task::{Task, CreateTaskRequest, TaskStatus, TaskWithRelations, EXECUTES_RELATION, TASK_ENTITY_TYPE};
 #[allow(unused_imports)]
-pub use log::{Log, CreateLogRequest, LogLevel};
+pub use log::{Log, CreateLogRequest, LogLevel, LOG_ENTITY_TYPE};
 #[allow(unused_imports)]
-pub use thought::{Thought, CreateThoughtRequest};
+pub use thought::{sort_chain, Thought, CreateThoughtRequest, FOLLOWS_RELATION, THOUGHT_ENTITY_TYPE};
 #[allow(unused_imports)]
 pub use embedding::{EmbeddingMetadata, SimilarityResult};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Entity properties for a model stored as an entity (every field but the id)
+fn record_properties<T: Serialize>(record: &T) -> HashMap<String, JsonValue> {
+    let mut properties: HashMap<String, JsonValue> = match serde_json::to_value(record) {
+        Ok(JsonValue::Object(map)) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    properties.remove("id");
+    properties
+}
+
+/// Rebuild a model from a stored entity's id and properties
+fn record_from_properties<T: DeserializeOwned>(id: &str, properties: &HashMap<String, JsonValue>) -> Option<T> {
+    let mut map: serde_json::Map<String, JsonValue> = properties.clone().into_iter().collect();
+    map.insert("id".to_string(), JsonValue::String(id.to_string()));
+    serde_json::from_value(JsonValue::Object(map)).ok()
+}
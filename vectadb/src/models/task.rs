@@ -1,9 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::{agent::Agent, log::Log, thought::Thought};
+
+/// Entity type tasks are stored under
+pub const TASK_ENTITY_TYPE: &str = "Task";
+
+/// Relation from an agent to the tasks it executes
+pub const EXECUTES_RELATION: &str = "executes";
 
 /// Task status enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum TaskStatus {
     Pending,
@@ -19,8 +29,25 @@ impl Default for TaskStatus {
     }
 }
 
+impl TaskStatus {
+    /// Whether a task may move from this status to `next`: pending tasks
+    /// start or are cancelled, running tasks complete, fail or are cancelled
+    pub fn can_transition_to(self, next: TaskStatus) -> bool {
+        use TaskStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Running) | (Pending, Cancelled) | (Running, Completed) | (Running, Failed) | (Running, Cancelled)
+        )
+    }
+
+    /// Whether the task has finished and can't change status again
+    pub fn is_terminal(self) -> bool {
+        matches!(self, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled)
+    }
+}
+
 /// Task model - represents a task assigned to an agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Task {
     /// Unique identifier (nanoid)
     pub id: String,
@@ -41,6 +68,7 @@ pub struct Task {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 
     /// Creation timestamp
@@ -56,7 +84,7 @@ pub struct Task {
 }
 
 /// Request to create a new task
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateTaskRequest {
     /// ID of the agent this task belongs to
     pub agent_id: String,
@@ -70,22 +98,27 @@ pub struct CreateTaskRequest {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 }
 
 /// Task with all related entities (thoughts, logs)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TaskWithRelations {
     #[serde(flatten)]
     pub task: Task,
 
+    /// Agent that executes the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executed_by: Option<Agent>,
+
     /// Related thoughts
     #[serde(default)]
-    pub thoughts: Vec<super::thought::Thought>,
+    pub thoughts: Vec<Thought>,
 
     /// Related logs
     #[serde(default)]
-    pub logs: Vec<super::log::Log>,
+    pub logs: Vec<Log>,
 }
 
 impl Task {
@@ -104,6 +137,41 @@ impl Task {
         }
     }
 
+    /// Create a task from an API request
+    pub fn from_request(request: CreateTaskRequest) -> Self {
+        let mut task = Self::new(request.agent_id, request.name, request.metadata);
+        task.status = request.status;
+        task
+    }
+
+    /// Move the task to `status`, recording completion for finished tasks.
+    /// Returns false, leaving the task unchanged, if the transition isn't allowed.
+    pub fn transition_to(&mut self, status: TaskStatus) -> bool {
+        if !self.status.can_transition_to(status) {
+            return false;
+        }
+
+        match status {
+            TaskStatus::Completed => self.complete(),
+            TaskStatus::Failed => self.fail(),
+            _ => {
+                self.status = status;
+                self.updated_at = Some(Utc::now());
+            }
+        }
+        true
+    }
+
+    /// Entity properties for storing this task (every field but the id)
+    pub fn to_properties(&self) -> HashMap<String, JsonValue> {
+        super::record_properties(self)
+    }
+
+    /// Rebuild a task from a stored entity's id and properties
+    pub fn from_properties(id: &str, properties: &HashMap<String, JsonValue>) -> Option<Self> {
+        super::record_from_properties(id, properties)
+    }
+
     /// Mark task as completed and calculate duration
     pub fn complete(&mut self) {
         self.status = TaskStatus::Completed;
@@ -205,4 +273,28 @@ mod tests {
         assert!(task.completed_at.is_some());
         assert!(task.duration_ms.is_some());
     }
+
+    #[test]
+    fn test_status_transitions() {
+        let mut task = Task::new("agent123".to_string(), "test_task".to_string(), json!({}));
+
+        assert!(!task.transition_to(TaskStatus::Completed));
+        assert_eq!(task.status, TaskStatus::Pending);
+
+        assert!(task.transition_to(TaskStatus::Running));
+        assert!(task.updated_at.is_some());
+        assert!(!task.transition_to(TaskStatus::Pending));
+
+        assert!(task.transition_to(TaskStatus::Completed));
+        assert!(task.duration_ms.is_some());
+        assert!(task.status.is_terminal());
+        for next in [TaskStatus::Running, TaskStatus::Failed, TaskStatus::Cancelled] {
+            assert!(!task.transition_to(next));
+        }
+        assert_eq!(task.status, TaskStatus::Completed);
+
+        assert!(TaskStatus::Pending.can_transition_to(TaskStatus::Cancelled));
+        assert!(TaskStatus::Running.can_transition_to(TaskStatus::Failed));
+        assert!(!TaskStatus::Pending.can_transition_to(TaskStatus::Failed));
+    }
 }
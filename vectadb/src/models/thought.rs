@@ -1,9 +1,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Entity type thoughts are stored under
+pub const THOUGHT_ENTITY_TYPE: &str = "Thought";
+
+/// Relation linking a thought to the one before it in its chain
+pub const FOLLOWS_RELATION: &str = "follows";
 
 /// Thought model - represents a chain-of-thought entry from an agent
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Thought {
     /// Unique identifier (nanoid)
     pub id: String,
@@ -22,8 +30,13 @@ pub struct Thought {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sequence: Option<i32>,
 
+    /// ID of the trace whose reasoning chain this thought belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 
     /// Timestamp
@@ -31,7 +44,7 @@ pub struct Thought {
 }
 
 /// Request to create a new thought
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateThoughtRequest {
     /// ID of the agent that generated this thought
     pub agent_id: String,
@@ -49,6 +62,7 @@ pub struct CreateThoughtRequest {
 
     /// Additional metadata (flexible JSON)
     #[serde(default)]
+    #[schema(value_type = Object)]
     pub metadata: JsonValue,
 }
 
@@ -67,11 +81,35 @@ impl Thought {
             task_id,
             content,
             sequence,
+            trace_id: None,
             metadata,
             timestamp: Utc::now(),
         }
     }
 
+    /// Create a thought in `trace_id`'s chain from an API request
+    pub fn from_request(request: CreateThoughtRequest, trace_id: String) -> Self {
+        let mut thought = Self::new(
+            request.agent_id,
+            request.task_id,
+            request.content,
+            request.sequence,
+            request.metadata,
+        );
+        thought.trace_id = Some(trace_id);
+        thought
+    }
+
+    /// Entity properties for storing this thought (every field but the id)
+    pub fn to_properties(&self) -> HashMap<String, JsonValue> {
+        super::record_properties(self)
+    }
+
+    /// Rebuild a thought from a stored entity's id and properties
+    pub fn from_properties(id: &str, properties: &HashMap<String, JsonValue>) -> Option<Self> {
+        super::record_from_properties(id, properties)
+    }
+
     /// Build searchable text from thought for semantic analysis
     pub fn to_searchable_text(&self) -> String {
         if let Some(seq) = self.sequence {
@@ -82,6 +120,16 @@ impl Thought {
     }
 }
 
+/// Put a reasoning chain in order: by sequence number, then by time
+pub fn sort_chain(thoughts: &mut [Thought]) {
+    thoughts.sort_by(|a, b| {
+        a.sequence
+            .unwrap_or(i32::MAX)
+            .cmp(&b.sequence.unwrap_or(i32::MAX))
+            .then(a.timestamp.cmp(&b.timestamp))
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,4 +175,39 @@ mod tests {
         );
         assert_eq!(thought_without_seq.to_searchable_text(), "Analyzing data");
     }
+
+    #[test]
+    fn test_sort_chain() {
+        let step = |content: &str, sequence: Option<i32>| {
+            Thought::new("agent123".to_string(), None, content.to_string(), sequence, json!({}))
+        };
+        let mut chain = vec![
+            step("conclude", Some(3)),
+            step("unnumbered", None),
+            step("plan", Some(1)),
+            step("analyze", Some(2)),
+        ];
+
+        sort_chain(&mut chain);
+        let contents: Vec<&str> = chain.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, vec!["plan", "analyze", "conclude", "unnumbered"]);
+    }
+
+    #[test]
+    fn test_thought_properties_round_trip() {
+        let request = CreateThoughtRequest {
+            agent_id: "agent123".to_string(),
+            task_id: None,
+            content: "Check the cache first".to_string(),
+            sequence: Some(2),
+            metadata: json!({}),
+        };
+        let thought = Thought::from_request(request, "trace-1".to_string());
+
+        let restored = Thought::from_properties(&thought.id, &thought.to_properties()).unwrap();
+        assert_eq!(restored.id, thought.id);
+        assert_eq!(restored.trace_id.as_deref(), Some("trace-1"));
+        assert_eq!(restored.sequence, Some(2));
+        assert_eq!(restored.content, "Check the cache first");
+    }
 }
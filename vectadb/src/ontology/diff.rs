@@ -0,0 +1,472 @@
+// Schema diffing: what replacing the loaded ontology with a candidate would change
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::entity_type::{Cardinality, EntityType, PropertyDefinition};
+use super::relation_type::RelationType;
+use super::schema::OntologySchema;
+
+/// Differences between the current schema and a candidate schema
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SchemaDiff {
+    pub added_entity_types: Vec<String>,
+    pub removed_entity_types: Vec<String>,
+    pub modified_entity_types: Vec<EntityTypeChange>,
+    pub added_relation_types: Vec<String>,
+    pub removed_relation_types: Vec<String>,
+    pub modified_relation_types: Vec<RelationTypeChange>,
+
+    /// Whether existing data may stop validating or querying as before
+    pub breaking: bool,
+
+    /// Why the diff is breaking, one entry per breaking change
+    pub breaking_changes: Vec<String>,
+}
+
+/// Changes to an entity type present in both schemas
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EntityTypeChange {
+    pub id: String,
+    pub added_properties: Vec<String>,
+    pub removed_properties: Vec<String>,
+    pub modified_properties: Vec<PropertyChange>,
+
+    /// Changes to the type itself (label, parent)
+    pub changes: Vec<String>,
+}
+
+/// Changes to a property present in both versions of an entity type
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PropertyChange {
+    pub name: String,
+    pub changes: Vec<String>,
+}
+
+/// Changes to a relation type present in both schemas
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RelationTypeChange {
+    pub id: String,
+    pub changes: Vec<String>,
+}
+
+impl SchemaDiff {
+    /// Record that a removed entity or relation type still has `count` stored
+    /// records, which would be left without a type
+    pub fn flag_removed_type_in_use(&mut self, kind: &str, type_id: &str, count: u64) {
+        self.push_breaking(format!(
+            "Removed {} type '{}' is still used by {} stored record(s)",
+            kind, type_id, count
+        ));
+    }
+
+    fn push_breaking(&mut self, reason: String) {
+        self.breaking = true;
+        self.breaking_changes.push(reason);
+    }
+}
+
+/// Compare `current` against `candidate`. Removed types are not breaking on
+/// their own, since whether data still references them is only known to the
+/// caller; see [`SchemaDiff::flag_removed_type_in_use`].
+pub fn diff_schemas(current: &OntologySchema, candidate: &OntologySchema) -> SchemaDiff {
+    let mut diff = SchemaDiff {
+        added_entity_types: added_keys(&current.entity_types, &candidate.entity_types),
+        removed_entity_types: added_keys(&candidate.entity_types, &current.entity_types),
+        added_relation_types: added_keys(&current.relation_types, &candidate.relation_types),
+        removed_relation_types: added_keys(&candidate.relation_types, &current.relation_types),
+        ..SchemaDiff::default()
+    };
+
+    let mut common: Vec<&String> = current
+        .entity_types
+        .keys()
+        .filter(|id| candidate.entity_types.contains_key(*id))
+        .collect();
+    common.sort();
+    for id in common {
+        if let Some(change) = diff_entity_type(
+            &current.entity_types[id],
+            &candidate.entity_types[id],
+            &mut diff,
+        ) {
+            diff.modified_entity_types.push(change);
+        }
+    }
+
+    let mut common: Vec<&String> = current
+        .relation_types
+        .keys()
+        .filter(|id| candidate.relation_types.contains_key(*id))
+        .collect();
+    common.sort();
+    for id in common {
+        if let Some(change) = diff_relation_type(
+            &current.relation_types[id],
+            &candidate.relation_types[id],
+            &mut diff,
+        ) {
+            diff.modified_relation_types.push(change);
+        }
+    }
+
+    diff
+}
+
+/// Keys of `after` missing from `before`, sorted
+fn added_keys<V>(before: &HashMap<String, V>, after: &HashMap<String, V>) -> Vec<String> {
+    let mut keys: Vec<String> = after
+        .keys()
+        .filter(|key| !before.contains_key(*key))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys
+}
+
+fn diff_entity_type(
+    before: &EntityType,
+    after: &EntityType,
+    diff: &mut SchemaDiff,
+) -> Option<EntityTypeChange> {
+    let mut change = EntityTypeChange {
+        id: before.id.clone(),
+        added_properties: Vec::new(),
+        removed_properties: Vec::new(),
+        modified_properties: Vec::new(),
+        changes: Vec::new(),
+    };
+
+    if before.label != after.label {
+        change
+            .changes
+            .push(format!("label '{}' -> '{}'", before.label, after.label));
+    }
+    if before.parent != after.parent {
+        change.changes.push(format!(
+            "parent {} -> {}",
+            describe_optional(&before.parent),
+            describe_optional(&after.parent)
+        ));
+        diff.push_breaking(format!(
+            "Entity type '{}' changes parent, so subtype queries match it differently",
+            before.id
+        ));
+    }
+
+    let before_props: HashMap<&str, &PropertyDefinition> =
+        before.properties.iter().map(|p| (p.name.as_str(), p)).collect();
+    let after_props: HashMap<&str, &PropertyDefinition> =
+        after.properties.iter().map(|p| (p.name.as_str(), p)).collect();
+
+    for prop in &after.properties {
+        match before_props.get(prop.name.as_str()) {
+            None => {
+                change.added_properties.push(prop.name.clone());
+                if prop.required {
+                    diff.push_breaking(format!(
+                        "Entity type '{}' requires new property '{}'",
+                        before.id, prop.name
+                    ));
+                }
+            }
+            Some(old) => {
+                if let Some(prop_change) = diff_property(&before.id, old, prop, diff) {
+                    change.modified_properties.push(prop_change);
+                }
+            }
+        }
+    }
+    for prop in &before.properties {
+        if !after_props.contains_key(prop.name.as_str()) {
+            change.removed_properties.push(prop.name.clone());
+        }
+    }
+
+    change.added_properties.sort();
+    change.removed_properties.sort();
+    change.modified_properties.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let unchanged = change.added_properties.is_empty()
+        && change.removed_properties.is_empty()
+        && change.modified_properties.is_empty()
+        && change.changes.is_empty();
+    if unchanged {
+        None
+    } else {
+        Some(change)
+    }
+}
+
+fn diff_property(
+    type_id: &str,
+    before: &PropertyDefinition,
+    after: &PropertyDefinition,
+    diff: &mut SchemaDiff,
+) -> Option<PropertyChange> {
+    let mut changes = Vec::new();
+
+    if before.property_type != after.property_type {
+        changes.push(format!(
+            "type {:?} -> {:?}",
+            before.property_type, after.property_type
+        ));
+        diff.push_breaking(format!(
+            "Property '{}' of '{}' changes type from {:?} to {:?}",
+            after.name, type_id, before.property_type, after.property_type
+        ));
+    }
+    if before.required != after.required {
+        if after.required {
+            changes.push("now required".to_string());
+            diff.push_breaking(format!(
+                "Property '{}' of '{}' is now required",
+                after.name, type_id
+            ));
+        } else {
+            changes.push("no longer required".to_string());
+        }
+    }
+    if before.cardinality != after.cardinality {
+        changes.push(format!(
+            "cardinality {:?} -> {:?}",
+            before.cardinality, after.cardinality
+        ));
+        if allows_many(&before.cardinality) && !allows_many(&after.cardinality) {
+            diff.push_breaking(format!(
+                "Property '{}' of '{}' no longer allows multiple values",
+                after.name, type_id
+            ));
+        }
+    }
+    if before.description != after.description {
+        changes.push("description changed".to_string());
+    }
+    if before.embeddable != after.embeddable {
+        changes.push(format!("embeddable {} -> {}", before.embeddable, after.embeddable));
+    }
+    if before.default != after.default {
+        changes.push("default changed".to_string());
+    }
+    if before.derived != after.derived {
+        changes.push("derivation changed".to_string());
+    }
+    if before.unique != after.unique {
+        changes.push(format!("unique {} -> {}", before.unique, after.unique));
+        if after.unique {
+            diff.push_breaking(format!(
+                "Property '{}' of '{}' becomes unique",
+                after.name, type_id
+            ));
+        }
+    }
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(PropertyChange {
+            name: after.name.clone(),
+            changes,
+        })
+    }
+}
+
+fn diff_relation_type(
+    before: &RelationType,
+    after: &RelationType,
+    diff: &mut SchemaDiff,
+) -> Option<RelationTypeChange> {
+    let mut changes = Vec::new();
+
+    if before.label != after.label {
+        changes.push(format!("label '{}' -> '{}'", before.label, after.label));
+    }
+    if before.domain != after.domain {
+        changes.push(format!("domain '{}' -> '{}'", before.domain, after.domain));
+        diff.push_breaking(format!(
+            "Relation type '{}' changes domain from '{}' to '{}'",
+            before.id, before.domain, after.domain
+        ));
+    }
+    if before.range != after.range {
+        changes.push(format!("range '{}' -> '{}'", before.range, after.range));
+        diff.push_breaking(format!(
+            "Relation type '{}' changes range from '{}' to '{}'",
+            before.id, before.range, after.range
+        ));
+    }
+    if before.inverse != after.inverse {
+        changes.push(format!(
+            "inverse {} -> {}",
+            describe_optional(&before.inverse),
+            describe_optional(&after.inverse)
+        ));
+    }
+
+    let flags = [
+        ("transitive", before.transitive, after.transitive),
+        ("symmetric", before.symmetric, after.symmetric),
+        ("functional", before.functional, after.functional),
+        ("reflexive", before.reflexive, after.reflexive),
+    ];
+    for (flag, was, is) in flags {
+        if was != is {
+            changes.push(format!("{} {} -> {}", flag, was, is));
+        }
+    }
+    if !before.functional && after.functional {
+        diff.push_breaking(format!(
+            "Relation type '{}' becomes functional, allowing one target per source",
+            before.id
+        ));
+    }
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(RelationTypeChange {
+            id: before.id.clone(),
+            changes,
+        })
+    }
+}
+
+fn allows_many(cardinality: &Cardinality) -> bool {
+    matches!(cardinality, Cardinality::Many | Cardinality::OneOrMore)
+}
+
+fn describe_optional(parent: &Option<String>) -> String {
+    match parent {
+        Some(id) => format!("'{}'", id),
+        None => "none".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ontology::entity_type::PropertyType;
+
+    fn base_schema() -> OntologySchema {
+        let mut schema = OntologySchema::new("diff://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string())
+                .with_property(
+                    PropertyDefinition::new("name".to_string(), PropertyType::String).required(),
+                )
+                .with_property(PropertyDefinition::new(
+                    "model".to_string(),
+                    PropertyType::String,
+                )),
+        );
+        schema.add_entity_type(EntityType::new("Task".to_string(), "Task".to_string()));
+        schema.add_relation_type(RelationType::new(
+            "executes".to_string(),
+            "executes".to_string(),
+            "Agent".to_string(),
+            "Task".to_string(),
+        ));
+        schema
+    }
+
+    #[test]
+    fn test_identical_schemas_have_empty_diff() {
+        let diff = diff_schemas(&base_schema(), &base_schema());
+
+        assert!(diff.added_entity_types.is_empty());
+        assert!(diff.removed_entity_types.is_empty());
+        assert!(diff.modified_entity_types.is_empty());
+        assert!(diff.modified_relation_types.is_empty());
+        assert!(!diff.breaking);
+    }
+
+    #[test]
+    fn test_additive_changes_are_not_breaking() {
+        let mut candidate = base_schema();
+        candidate.entity_types.get_mut("Agent").unwrap().properties.push(
+            PropertyDefinition::new("temperature".to_string(), PropertyType::Number),
+        );
+        candidate.add_entity_type(EntityType::new("Tool".to_string(), "Tool".to_string()));
+        candidate.add_relation_type(RelationType::new(
+            "uses".to_string(),
+            "uses".to_string(),
+            "Agent".to_string(),
+            "Tool".to_string(),
+        ));
+
+        let diff = diff_schemas(&base_schema(), &candidate);
+
+        assert_eq!(diff.added_entity_types, vec!["Tool"]);
+        assert_eq!(diff.added_relation_types, vec!["uses"]);
+        assert_eq!(diff.modified_entity_types.len(), 1);
+        assert_eq!(diff.modified_entity_types[0].added_properties, vec!["temperature"]);
+        assert!(!diff.breaking, "unexpected: {:?}", diff.breaking_changes);
+    }
+
+    #[test]
+    fn test_property_changes_are_breaking() {
+        let mut candidate = base_schema();
+        let agent = candidate.entity_types.get_mut("Agent").unwrap();
+        agent.properties = vec![
+            // name: String -> Number
+            PropertyDefinition::new("name".to_string(), PropertyType::Number).required(),
+            // model: optional -> required
+            PropertyDefinition::new("model".to_string(), PropertyType::String).required(),
+            PropertyDefinition::new("owner".to_string(), PropertyType::String).required(),
+        ];
+
+        let diff = diff_schemas(&base_schema(), &candidate);
+
+        let agent = &diff.modified_entity_types[0];
+        assert_eq!(agent.id, "Agent");
+        assert_eq!(agent.added_properties, vec!["owner"]);
+        let modified: Vec<&str> = agent
+            .modified_properties
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(modified, vec!["model", "name"]);
+
+        assert!(diff.breaking);
+        assert_eq!(diff.breaking_changes.len(), 3);
+        assert!(diff
+            .breaking_changes
+            .iter()
+            .any(|c| c.contains("'model'") && c.contains("now required")));
+    }
+
+    #[test]
+    fn test_removed_types_are_breaking_only_when_in_use() {
+        let mut candidate = base_schema();
+        candidate.relation_types.remove("executes");
+        candidate.entity_types.remove("Task");
+
+        let mut diff = diff_schemas(&base_schema(), &candidate);
+
+        assert_eq!(diff.removed_entity_types, vec!["Task"]);
+        assert_eq!(diff.removed_relation_types, vec!["executes"]);
+        assert!(!diff.breaking);
+
+        diff.flag_removed_type_in_use("entity", "Task", 3);
+        assert!(diff.breaking);
+        assert!(diff.breaking_changes[0].contains("'Task'"));
+    }
+
+    #[test]
+    fn test_relation_changes() {
+        let mut candidate = base_schema();
+        candidate.add_entity_type(EntityType::new("Job".to_string(), "Job".to_string()));
+        let executes = candidate.relation_types.get_mut("executes").unwrap();
+        executes.range = "Job".to_string();
+        executes.transitive = true;
+
+        let diff = diff_schemas(&base_schema(), &candidate);
+
+        assert_eq!(diff.modified_relation_types.len(), 1);
+        assert_eq!(diff.modified_relation_types[0].changes.len(), 2);
+        assert!(diff.breaking);
+        assert_eq!(diff.breaking_changes.len(), 1);
+        assert!(diff.breaking_changes[0].contains("range"));
+    }
+}
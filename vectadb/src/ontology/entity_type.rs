@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
 
 /// Represents an ontology class/type
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +44,77 @@ pub struct PropertyDefinition {
     /// Optional description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Include this property in the embedding text. Once any property of a
+    /// type is marked, only marked properties are embedded.
+    #[serde(default)]
+    pub embeddable: bool,
+
+    /// No two live entities of the type may share a value for this property.
+    /// Only checked by validation against stored data.
+    #[serde(default)]
+    pub unique: bool,
+
+    /// Index the property in the entity store so filters on it avoid a
+    /// full scan. The index covers the property on every entity type.
+    #[serde(default)]
+    pub indexed: bool,
+
+    /// Full-text index the property so `POST /api/v1/entities/text-search`
+    /// can find entities by keyword. Meant for string properties.
+    #[serde(default)]
+    pub searchable: bool,
+
+    /// Value stored when an entity is created without this property. A
+    /// required property with a default can be left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+
+    /// Computed by the server from other properties on every create and
+    /// update, replacing any value sent by the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived: Option<Derivation>,
+}
+
+/// How a derived property is computed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", content = "of", rename_all = "snake_case")]
+pub enum Derivation {
+    /// Characters in a string, or items in an array
+    LengthOf(String),
+    /// Sum of numeric properties
+    SumOf(Vec<String>),
+}
+
+impl Derivation {
+    /// The derived value, or `None` when an input is missing or of the wrong kind
+    pub fn compute(&self, properties: &HashMap<String, JsonValue>) -> Option<JsonValue> {
+        match self {
+            Derivation::LengthOf(name) => match properties.get(name)? {
+                JsonValue::String(s) => Some(JsonValue::from(s.chars().count())),
+                JsonValue::Array(items) => Some(JsonValue::from(items.len())),
+                _ => None,
+            },
+            Derivation::SumOf(names) => {
+                let inputs: Vec<&serde_json::Number> = names
+                    .iter()
+                    .map(|name| properties.get(name)?.as_number())
+                    .collect::<Option<_>>()?;
+                // Stay integral when every input is, so token counts remain integers
+                let integers: Option<Vec<i64>> = inputs.iter().map(|n| n.as_i64()).collect();
+                match integers {
+                    Some(integers) => integers
+                        .into_iter()
+                        .try_fold(0i64, |sum, n| sum.checked_add(n))
+                        .map(JsonValue::from),
+                    None => {
+                        let sum: f64 = inputs.iter().filter_map(|n| n.as_f64()).sum();
+                        serde_json::Number::from_f64(sum).map(JsonValue::Number)
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Property type
@@ -142,6 +214,80 @@ impl EntityType {
         properties
     }
 
+    /// Fill in the default of every property, including inherited ones, that
+    /// `properties` leaves out. Values already present are never replaced, and
+    /// a subtype's default wins over its parent's. Returns the names filled in.
+    pub fn apply_defaults(
+        &self,
+        schema: &super::schema::OntologySchema,
+        properties: &mut HashMap<String, JsonValue>,
+    ) -> Vec<String> {
+        let mut applied = Vec::new();
+        for property in self.get_all_properties(schema) {
+            if let Some(default) = property.default {
+                if !properties.contains_key(&property.name) {
+                    properties.insert(property.name.clone(), default);
+                    applied.push(property.name);
+                }
+            }
+        }
+        applied
+    }
+
+    /// Compute every derived property, including inherited ones, from the
+    /// rest of `properties`. A derived property whose inputs are missing or of
+    /// the wrong kind is removed rather than left with a stale value. Returns
+    /// the names computed.
+    pub fn apply_derived(
+        &self,
+        schema: &super::schema::OntologySchema,
+        properties: &mut HashMap<String, JsonValue>,
+    ) -> Vec<String> {
+        let mut computed = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        for property in self.get_all_properties(schema) {
+            // A subtype's definition shadows its parent's
+            if !seen.insert(property.name.clone()) {
+                continue;
+            }
+            let Some(derivation) = &property.derived else {
+                continue;
+            };
+            match derivation.compute(properties) {
+                Some(value) => {
+                    properties.insert(property.name.clone(), value);
+                    computed.push(property.name);
+                }
+                None => {
+                    properties.remove(&property.name);
+                }
+            }
+        }
+        computed
+    }
+
+    /// Names of the properties, including inherited ones, marked embeddable
+    pub fn embeddable_properties(&self, schema: &super::schema::OntologySchema) -> Vec<String> {
+        self.get_all_properties(schema)
+            .into_iter()
+            .filter(|p| p.embeddable)
+            .map(|p| p.name)
+            .collect()
+    }
+
+    /// Embedding text template from this type's metadata, or the nearest
+    /// ancestor's
+    pub fn embedding_template<'a>(&self, schema: &'a super::schema::OntologySchema) -> Option<&'a str> {
+        schema.get_supertypes(&self.id).iter().find_map(|id| {
+            schema
+                .entity_types
+                .get(id)?
+                .metadata
+                .get(super::template::EMBEDDING_TEMPLATE_KEY)?
+                .as_str()
+        })
+    }
+
     /// Check if this type is a subtype of another
     pub fn is_subtype_of(&self, other_id: &str, schema: &super::schema::OntologySchema) -> bool {
         if self.id == other_id {
@@ -172,6 +318,12 @@ impl PropertyDefinition {
             required: false,
             cardinality: Cardinality::ZeroOrOne,
             description: None,
+            embeddable: false,
+            unique: false,
+            indexed: false,
+            searchable: false,
+            default: None,
+            derived: None,
         }
     }
 
@@ -188,6 +340,42 @@ impl PropertyDefinition {
         self
     }
 
+    /// Mark as part of the embedding text
+    pub fn embeddable(mut self) -> Self {
+        self.embeddable = true;
+        self
+    }
+
+    /// Mark as unique among entities of the type
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Mark as indexed in the entity store
+    pub fn indexed(mut self) -> Self {
+        self.indexed = true;
+        self
+    }
+
+    /// Mark as full-text searchable
+    pub fn searchable(mut self) -> Self {
+        self.searchable = true;
+        self
+    }
+
+    /// Set the value used when the property is left out on creation
+    pub fn with_default(mut self, default: JsonValue) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Compute the property from others instead of accepting it from clients
+    pub fn derived(mut self, derivation: Derivation) -> Self {
+        self.derived = Some(derivation);
+        self
+    }
+
     /// Add description
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
@@ -238,6 +426,97 @@ mod tests {
         assert!(!llm_agent.is_subtype_of("Task", &schema));
     }
 
+    #[test]
+    fn test_apply_defaults() {
+        let mut schema = OntologySchema::new("test".to_string(), "1.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("Task".to_string(), "Task".to_string())
+                .with_property(
+                    PropertyDefinition::new("status".to_string(), PropertyType::String)
+                        .required()
+                        .with_default(JsonValue::from("pending")),
+                )
+                .with_property(
+                    PropertyDefinition::new("priority".to_string(), PropertyType::Number)
+                        .with_default(JsonValue::from(3)),
+                )
+                .with_property(PropertyDefinition::new("title".to_string(), PropertyType::String)),
+        );
+        schema.add_entity_type(
+            EntityType::new("UrgentTask".to_string(), "Urgent Task".to_string())
+                .with_parent("Task".to_string())
+                .with_property(
+                    PropertyDefinition::new("priority".to_string(), PropertyType::Number)
+                        .with_default(JsonValue::from(1)),
+                ),
+        );
+
+        let task = &schema.entity_types["Task"];
+        let mut properties = HashMap::new();
+        properties.insert("priority".to_string(), JsonValue::from(5));
+        let mut applied = task.apply_defaults(&schema, &mut properties);
+        applied.sort();
+
+        // Explicit values win, and properties without a default stay absent
+        assert_eq!(applied, vec!["status".to_string()]);
+        assert_eq!(properties["status"], "pending");
+        assert_eq!(properties["priority"], 5);
+        assert!(!properties.contains_key("title"));
+
+        // A subtype's default overrides the inherited one
+        let mut properties = HashMap::new();
+        schema.entity_types["UrgentTask"].apply_defaults(&schema, &mut properties);
+        assert_eq!(properties["priority"], 1);
+        assert_eq!(properties["status"], "pending");
+    }
+
+    #[test]
+    fn test_apply_derived() {
+        let mut schema = OntologySchema::new("test".to_string(), "1.0".to_string());
+        schema.add_entity_type(
+            EntityType::new("LLMCall".to_string(), "LLM Call".to_string())
+                .with_property(PropertyDefinition::new("text".to_string(), PropertyType::String))
+                .with_property(
+                    PropertyDefinition::new("text_length".to_string(), PropertyType::Number)
+                        .derived(Derivation::LengthOf("text".to_string())),
+                )
+                .with_property(
+                    PropertyDefinition::new("total_tokens".to_string(), PropertyType::Number).derived(
+                        Derivation::SumOf(vec!["input_tokens".to_string(), "output_tokens".to_string()]),
+                    ),
+                ),
+        );
+        let call = &schema.entity_types["LLMCall"];
+
+        let mut properties = HashMap::new();
+        properties.insert("text".to_string(), JsonValue::from("héllo"));
+        properties.insert("input_tokens".to_string(), JsonValue::from(120));
+        properties.insert("output_tokens".to_string(), JsonValue::from(30));
+        // Client-sent values of derived properties are replaced
+        properties.insert("text_length".to_string(), JsonValue::from(999));
+
+        let mut computed = call.apply_derived(&schema, &mut properties);
+        computed.sort();
+        assert_eq!(computed, vec!["text_length".to_string(), "total_tokens".to_string()]);
+        assert_eq!(properties["text_length"], 5);
+        assert_eq!(properties["total_tokens"], 150);
+        assert!(properties["total_tokens"].is_i64());
+
+        // Missing inputs drop the derived value instead of keeping a stale one
+        properties.remove("output_tokens");
+        properties.insert("input_tokens".to_string(), JsonValue::from(1.5));
+        call.apply_derived(&schema, &mut properties);
+        assert!(!properties.contains_key("total_tokens"));
+
+        let mut floats = HashMap::new();
+        floats.insert("input_tokens".to_string(), JsonValue::from(1.5));
+        floats.insert("output_tokens".to_string(), JsonValue::from(2));
+        assert_eq!(
+            Derivation::SumOf(vec!["input_tokens".to_string(), "output_tokens".to_string()]).compute(&floats),
+            Some(JsonValue::from(3.5))
+        );
+    }
+
     #[test]
     fn test_property_types() {
         let string_prop = PropertyDefinition::new("name".to_string(), PropertyType::String);
@@ -0,0 +1,213 @@
+// JSON Schema documents for entity types, for generating typed clients and forms
+
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::HashSet;
+
+use super::entity_type::{Cardinality, Constraint, EntityType, PropertyDefinition, PropertyType};
+use super::schema::OntologySchema;
+
+/// Dialect of the emitted documents
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A JSON Schema document for the properties of an entity of `entity_type`,
+/// inherited ones included. Its constraints apply to every property of the
+/// matching kind, as the validator applies them. Unknown properties are
+/// allowed, as they are on create.
+pub fn entity_json_schema(schema: &OntologySchema, entity_type: &EntityType) -> JsonValue {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut seen = HashSet::new();
+
+    for property in entity_type.get_all_properties(schema) {
+        // A subtype's definition shadows its parent's
+        if !seen.insert(property.name.clone()) {
+            continue;
+        }
+        // Properties with a default or computed by the server can be left out
+        if property.required && property.default.is_none() && property.derived.is_none() {
+            required.push(JsonValue::String(property.name.clone()));
+        }
+        properties.insert(property.name.clone(), property_schema(&property, &entity_type.constraints));
+    }
+
+    let mut document = json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "title": entity_type.label,
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": true,
+    });
+    if !required.is_empty() {
+        document["required"] = JsonValue::Array(required);
+    }
+    document
+}
+
+fn property_schema(property: &PropertyDefinition, constraints: &[Constraint]) -> JsonValue {
+    let mut value = type_schema(&property.property_type);
+    for constraint in constraints {
+        apply_constraint(&mut value, constraint);
+    }
+
+    let mut schema = match property.cardinality {
+        Cardinality::One | Cardinality::ZeroOrOne => value,
+        Cardinality::Many => json!({ "type": "array", "items": value }),
+        Cardinality::OneOrMore => json!({ "type": "array", "items": value, "minItems": 1 }),
+    };
+
+    if let Some(description) = &property.description {
+        schema["description"] = json!(description);
+    }
+    if let Some(default) = &property.default {
+        schema["default"] = default.clone();
+    }
+    if property.derived.is_some() {
+        schema["readOnly"] = json!(true);
+    }
+    schema
+}
+
+fn type_schema(property_type: &PropertyType) -> JsonValue {
+    match property_type {
+        PropertyType::String => json!({ "type": "string" }),
+        PropertyType::Number => json!({ "type": "number" }),
+        PropertyType::Boolean => json!({ "type": "boolean" }),
+        PropertyType::DateTime => json!({ "type": "string", "format": "date-time" }),
+        // The ID of an entity; `x-reference` names its type for generators
+        PropertyType::Reference(target) => json!({ "type": "string", "x-reference": target }),
+        PropertyType::Embedding => json!({ "type": "array", "items": { "type": "number" } }),
+        PropertyType::Object => json!({ "type": "object" }),
+        PropertyType::Array(items) => json!({ "type": "array", "items": type_schema(items) }),
+    }
+}
+
+/// Add a type-level constraint to a value schema it applies to: ranges to
+/// numbers, everything else but custom rules to strings
+fn apply_constraint(schema: &mut JsonValue, constraint: &Constraint) {
+    let is = |kind: &str| schema["type"] == kind;
+    match constraint {
+        Constraint::ValueRange { min, max } if is("number") => {
+            schema["minimum"] = json!(min);
+            schema["maximum"] = json!(max);
+        }
+        Constraint::Pattern(pattern) if is("string") => {
+            schema["pattern"] = json!(pattern);
+        }
+        Constraint::Enum(values) if is("string") => {
+            schema["enum"] = json!(values);
+        }
+        Constraint::StringLength { min, max } if is("string") => {
+            if let Some(min) = min {
+                schema["minLength"] = json!(min);
+            }
+            if let Some(max) = max {
+                schema["maxLength"] = json!(max);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> OntologySchema {
+        let mut schema = OntologySchema::new("json-schema://test".to_string(), "1.0.0".to_string());
+        let mut created_at = PropertyDefinition::new("created_at".to_string(), PropertyType::DateTime);
+        created_at.description = Some("When the agent was registered".to_string());
+        schema.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string())
+                .with_property(PropertyDefinition::new("name".to_string(), PropertyType::String).required())
+                .with_property(created_at),
+        );
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string())
+                .with_parent("Agent".to_string())
+                .with_property(PropertyDefinition::new("model".to_string(), PropertyType::String).required())
+                .with_property(
+                    PropertyDefinition::new("tools".to_string(), PropertyType::Reference("Tool".to_string()))
+                        .with_cardinality(Cardinality::OneOrMore),
+                )
+                .with_property(
+                    PropertyDefinition::new("temperature".to_string(), PropertyType::Number)
+                        .required()
+                        .with_default(json!(0.7)),
+                )
+                .with_property(PropertyDefinition::new(
+                    "embedding".to_string(),
+                    PropertyType::Embedding,
+                ))
+                .with_constraint(Constraint::ValueRange { min: 0.0, max: 2.0 })
+                .with_constraint(Constraint::StringLength { min: Some(1), max: Some(64) }),
+        );
+        schema
+    }
+
+    #[test]
+    fn test_entity_json_schema_matches_known_document() {
+        let schema = schema();
+        let document = entity_json_schema(&schema, &schema.entity_types["LLMAgent"]);
+
+        let string = json!({ "type": "string", "minLength": 1, "maxLength": 64 });
+        let expected = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "LLM Agent",
+            "type": "object",
+            "properties": {
+                "model": string,
+                "tools": {
+                    "type": "array",
+                    "items": { "type": "string", "x-reference": "Tool", "minLength": 1, "maxLength": 64 },
+                    "minItems": 1
+                },
+                "temperature": { "type": "number", "minimum": 0.0, "maximum": 2.0, "default": 0.7 },
+                "embedding": { "type": "array", "items": { "type": "number" } },
+                "name": string,
+                "created_at": {
+                    "type": "string",
+                    "format": "date-time",
+                    "minLength": 1,
+                    "maxLength": 64,
+                    "description": "When the agent was registered"
+                }
+            },
+            "required": ["model", "name"],
+            "additionalProperties": true
+        });
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn test_conforming_instances_validate() {
+        let schema = schema();
+        let document = entity_json_schema(&schema, &schema.entity_types["LLMAgent"]);
+        let compiled = jsonschema::JSONSchema::options()
+            .with_draft(jsonschema::Draft::Draft202012)
+            .compile(&document)
+            .unwrap();
+
+        let instance = json!({
+            "name": "planner",
+            "model": "gpt-4o",
+            "tools": ["tool-1", "tool-2"],
+            "temperature": 1.2,
+            "created_at": "2025-03-01T12:00:00Z",
+            "team": "research"
+        });
+        assert!(compiled.is_valid(&instance));
+
+        for invalid in [
+            json!({ "name": "planner" }),
+            json!({ "name": "planner", "model": "gpt-4o", "temperature": 3 }),
+            json!({ "name": "planner", "model": "gpt-4o", "tools": [] }),
+            json!({ "name": "", "model": "gpt-4o" }),
+        ] {
+            assert!(!compiled.is_valid(&invalid), "{} should not validate", invalid);
+        }
+
+        // The parent's document doesn't carry the subtype's constraints
+        let agent = entity_json_schema(&schema, &schema.entity_types["Agent"]);
+        assert!(jsonschema::is_valid(&agent, &json!({ "name": "" })));
+    }
+}
@@ -0,0 +1,187 @@
+// Schema merging: apply a partial schema on top of the loaded ontology
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use super::entity_type::EntityType;
+use super::relation_type::RelationType;
+use super::schema::{InferenceRule, OntologySchema};
+
+/// Partial schema: only the types and rules to add or change
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaPatch {
+    /// New schema version; the current one is kept when absent
+    #[serde(default)]
+    pub version: Option<String>,
+
+    #[serde(default)]
+    pub entity_types: HashMap<String, EntityType>,
+
+    #[serde(default)]
+    pub relation_types: HashMap<String, RelationType>,
+
+    #[serde(default)]
+    pub rules: Vec<InferenceRule>,
+}
+
+/// What merging a patch changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SchemaMerge {
+    pub added_entity_types: Vec<String>,
+    pub updated_entity_types: Vec<String>,
+    pub added_relation_types: Vec<String>,
+    pub updated_relation_types: Vec<String>,
+    pub added_rules: Vec<String>,
+    pub updated_rules: Vec<String>,
+}
+
+/// Merge `patch` into a copy of `current`. Types and rules the patch doesn't
+/// mention are kept, and ones it repeats unchanged are left alone. Redefining
+/// an existing one differently is a conflict unless `overwrite` is set; the
+/// error lists every conflict. The merged schema is not validated.
+pub fn merge_schemas(
+    current: &OntologySchema,
+    patch: SchemaPatch,
+    overwrite: bool,
+) -> Result<(OntologySchema, SchemaMerge), Vec<String>> {
+    let mut merged = current.clone();
+    let mut summary = SchemaMerge::default();
+    let mut conflicts = Vec::new();
+
+    if let Some(version) = patch.version {
+        merged.version = version;
+    }
+
+    for entity_type in patch.entity_types.into_values() {
+        match current.entity_types.get(&entity_type.id) {
+            None => summary.added_entity_types.push(entity_type.id.clone()),
+            Some(existing) if same_definition(existing, &entity_type) => continue,
+            Some(_) if overwrite => summary.updated_entity_types.push(entity_type.id.clone()),
+            Some(_) => {
+                conflicts.push(format!("entity type '{}' is already defined differently", entity_type.id));
+                continue;
+            }
+        }
+        merged.add_entity_type(entity_type);
+    }
+
+    for relation_type in patch.relation_types.into_values() {
+        match current.relation_types.get(&relation_type.id) {
+            None => summary.added_relation_types.push(relation_type.id.clone()),
+            Some(existing) if same_definition(existing, &relation_type) => continue,
+            Some(_) if overwrite => summary.updated_relation_types.push(relation_type.id.clone()),
+            Some(_) => {
+                conflicts.push(format!(
+                    "relation type '{}' is already defined differently",
+                    relation_type.id
+                ));
+                continue;
+            }
+        }
+        merged.add_relation_type(relation_type);
+    }
+
+    for rule in patch.rules {
+        match merged.rules.iter().position(|r| r.id == rule.id) {
+            None => {
+                summary.added_rules.push(rule.id.clone());
+                merged.add_rule(rule);
+            }
+            Some(i) if same_definition(&merged.rules[i], &rule) => {}
+            Some(i) if overwrite => {
+                summary.updated_rules.push(rule.id.clone());
+                merged.rules[i] = rule;
+            }
+            Some(_) => conflicts.push(format!("rule '{}' is already defined differently", rule.id)),
+        }
+    }
+
+    if !conflicts.is_empty() {
+        conflicts.sort();
+        return Err(conflicts);
+    }
+
+    summary.added_entity_types.sort();
+    summary.updated_entity_types.sort();
+    summary.added_relation_types.sort();
+    summary.updated_relation_types.sort();
+
+    Ok((merged, summary))
+}
+
+/// Whether two definitions serialize the same. The type definitions don't
+/// implement `PartialEq`, and comparing through serde also catches fields
+/// added later.
+fn same_definition<T: Serialize>(a: &T, b: &T) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current() -> OntologySchema {
+        let mut schema = OntologySchema::new("merge://test".to_string(), "1.0.0".to_string());
+        schema.add_entity_type(EntityType::new("Agent".to_string(), "Agent".to_string()));
+        schema.add_entity_type(EntityType::new("Task".to_string(), "Task".to_string()));
+        schema.add_relation_type(RelationType::new(
+            "executes".to_string(),
+            "executes".to_string(),
+            "Agent".to_string(),
+            "Task".to_string(),
+        ));
+        schema
+    }
+
+    #[test]
+    fn test_merge_adds_types_and_keeps_the_rest() {
+        let mut patch = SchemaPatch {
+            version: Some("1.1.0".to_string()),
+            ..Default::default()
+        };
+        let tool = EntityType::new("Tool".to_string(), "Tool".to_string());
+        patch.entity_types.insert(tool.id.clone(), tool);
+        // Repeating an existing type unchanged is not an update
+        let agent = current().entity_types["Agent"].clone();
+        patch.entity_types.insert(agent.id.clone(), agent);
+        let uses = RelationType::new(
+            "uses".to_string(),
+            "uses".to_string(),
+            "Agent".to_string(),
+            "Tool".to_string(),
+        );
+        patch.relation_types.insert(uses.id.clone(), uses);
+
+        let (merged, summary) = merge_schemas(&current(), patch, false).unwrap();
+        assert_eq!(merged.version, "1.1.0");
+        assert_eq!(merged.namespace, "merge://test");
+        assert_eq!(merged.entity_types.len(), 3);
+        assert!(merged.entity_types.contains_key("Task"));
+        assert!(merged.relation_types.contains_key("executes"));
+        assert!(merged.relation_types.contains_key("uses"));
+        assert!(merged.validate().is_ok());
+
+        assert_eq!(summary.added_entity_types, vec!["Tool".to_string()]);
+        assert!(summary.updated_entity_types.is_empty());
+        assert_eq!(summary.added_relation_types, vec!["uses".to_string()]);
+    }
+
+    #[test]
+    fn test_merge_rejects_redefinition_unless_overwrite() {
+        let mut patch = SchemaPatch::default();
+        let task = EntityType::new("Task".to_string(), "Job".to_string());
+        patch.entity_types.insert(task.id.clone(), task);
+
+        let conflicts = merge_schemas(&current(), patch.clone(), false).unwrap_err();
+        assert_eq!(conflicts, vec!["entity type 'Task' is already defined differently".to_string()]);
+
+        let (merged, summary) = merge_schemas(&current(), patch, true).unwrap();
+        assert_eq!(merged.entity_types["Task"].label, "Job");
+        assert_eq!(summary.updated_entity_types, vec!["Task".to_string()]);
+        assert!(merged.entity_types.contains_key("Agent"));
+    }
+}
@@ -1,12 +1,19 @@
 // VectaDB Ontology Layer
 // Provides ontological semantics for entities and relations
 
+pub mod diff;
 pub mod entity_type;
+pub mod json_schema;
 pub mod relation_type;
 pub mod schema;
+pub mod template;
 pub mod validator;
 pub mod loader;
+pub mod merge;
 
+pub use diff::{diff_schemas, SchemaDiff};
+pub use json_schema::entity_json_schema;
 pub use schema::OntologySchema;
 pub use validator::OntologyValidator;
 pub use loader::OntologyLoader;
+pub use merge::{merge_schemas, SchemaMerge, SchemaPatch};
@@ -133,20 +133,42 @@ impl OntologySchema {
     /// Get all supertypes of an entity type (including itself)
     pub fn get_supertypes(&self, type_id: &str) -> Vec<String> {
         let mut supertypes = vec![type_id.to_string()];
+        supertypes.extend(self.get_ancestors(type_id));
+        supertypes
+    }
 
-        if let Some(entity_type) = self.entity_types.get(type_id) {
-            let mut current = entity_type;
-            while let Some(parent_id) = &current.parent {
-                supertypes.push(parent_id.clone());
-                if let Some(parent) = self.entity_types.get(parent_id) {
-                    current = parent;
-                } else {
-                    break;
-                }
+    /// Get the parent chain of an entity type, nearest first, up to its root.
+    /// Root types have no ancestors; a cycle stops the walk where it repeats.
+    pub fn get_ancestors(&self, type_id: &str) -> Vec<String> {
+        let mut ancestors = Vec::new();
+        let mut visited = std::collections::HashSet::from([type_id.to_string()]);
+
+        let mut current = self.entity_types.get(type_id);
+        while let Some(parent_id) = current.and_then(|t| t.parent.as_ref()) {
+            if !visited.insert(parent_id.clone()) {
+                break;
             }
+            ancestors.push(parent_id.clone());
+            current = self.entity_types.get(parent_id);
         }
 
-        supertypes
+        ancestors
+    }
+
+    /// The inverse of a relation type: the one it declares, or else one that
+    /// declares it as its inverse
+    pub fn inverse_of(&self, relation_type_id: &str) -> Option<&RelationType> {
+        let declared = self
+            .relation_types
+            .get(relation_type_id)?
+            .inverse
+            .as_ref()
+            .and_then(|inverse_id| self.relation_types.get(inverse_id));
+        declared.or_else(|| {
+            self.relation_types
+                .values()
+                .find(|other| other.inverse.as_deref() == Some(relation_type_id))
+        })
     }
 
     /// Validate the schema for consistency
@@ -296,6 +318,49 @@ mod tests {
         assert!(supertypes.contains(&"Agent".to_string()));
     }
 
+    #[test]
+    fn test_get_ancestors() {
+        let mut schema = OntologySchema::new("test".to_string(), "1.0".to_string());
+
+        schema.add_entity_type(EntityType::new("Agent".to_string(), "Agent".to_string()));
+        schema.add_entity_type(
+            EntityType::new("LLMAgent".to_string(), "LLM Agent".to_string())
+                .with_parent("Agent".to_string()),
+        );
+        schema.add_entity_type(
+            EntityType::new("ChatAgent".to_string(), "Chat Agent".to_string())
+                .with_parent("LLMAgent".to_string()),
+        );
+
+        assert_eq!(schema.get_ancestors("ChatAgent"), vec!["LLMAgent", "Agent"]);
+        assert_eq!(schema.get_ancestors("LLMAgent"), vec!["Agent"]);
+        assert!(schema.get_ancestors("Agent").is_empty());
+        assert!(schema.get_ancestors("Unknown").is_empty());
+
+        // A cycle ends the walk instead of looping forever
+        schema.add_entity_type(
+            EntityType::new("Agent".to_string(), "Agent".to_string())
+                .with_parent("ChatAgent".to_string()),
+        );
+        assert_eq!(schema.get_ancestors("ChatAgent"), vec!["LLMAgent", "Agent"]);
+    }
+
+    #[test]
+    fn test_inverse_of_either_side() {
+        let mut schema = OntologySchema::new("test".to_string(), "1.0".to_string());
+        let relation = |id: &str| {
+            RelationType::new(id.to_string(), id.to_string(), "Agent".to_string(), "Task".to_string())
+        };
+        schema.add_relation_type(relation("executes").with_inverse("executed_by".to_string()));
+        schema.add_relation_type(relation("executed_by"));
+        schema.add_relation_type(relation("mentions"));
+
+        assert_eq!(schema.inverse_of("executes").map(|r| r.id.as_str()), Some("executed_by"));
+        assert_eq!(schema.inverse_of("executed_by").map(|r| r.id.as_str()), Some("executes"));
+        assert!(schema.inverse_of("mentions").is_none());
+        assert!(schema.inverse_of("unknown").is_none());
+    }
+
     #[test]
     fn test_schema_validation() {
         let mut schema = OntologySchema::new("test".to_string(), "1.0".to_string());
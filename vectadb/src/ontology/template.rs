@@ -0,0 +1,105 @@
+// Embedding text templates declared in entity type metadata
+
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Entity type metadata key holding the embedding text template
+pub const EMBEDDING_TEMPLATE_KEY: &str = "embedding_template";
+
+/// Render an embedding text template such as `"{role}: {content}"` with an
+/// entity's properties. `{name|fallback}` renders `fallback` when the property
+/// is missing or null, plain `{name}` renders nothing. `{{` and `}}` are
+/// literal braces.
+pub fn render_embedding_template(template: &str, properties: &HashMap<String, JsonValue>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    // Unterminated placeholder: keep it as written
+                    output.push('{');
+                    output.push_str(&placeholder);
+                    break;
+                }
+
+                let (name, fallback) = match placeholder.split_once('|') {
+                    Some((name, fallback)) => (name.trim(), fallback),
+                    None => (placeholder.trim(), ""),
+                };
+                match properties.get(name) {
+                    Some(JsonValue::String(s)) => output.push_str(s),
+                    Some(JsonValue::Null) | None => output.push_str(fallback),
+                    Some(value) => output.push_str(&value.to_string()),
+                }
+            }
+            c => output.push(c),
+        }
+    }
+
+    output.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn properties(pairs: &[(&str, JsonValue)]) -> HashMap<String, JsonValue> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_present_fields() {
+        let props = properties(&[
+            ("role", json!("assistant")),
+            ("content", json!("Retrying the query")),
+            ("attempt", json!(2)),
+        ]);
+
+        assert_eq!(
+            render_embedding_template("{role}: {content} (attempt {attempt})", &props),
+            "assistant: Retrying the query (attempt 2)"
+        );
+    }
+
+    #[test]
+    fn test_render_absent_fields() {
+        let props = properties(&[("content", json!("Retrying")), ("role", JsonValue::Null)]);
+
+        assert_eq!(render_embedding_template("{role}: {content}", &props), ": Retrying");
+        assert_eq!(
+            render_embedding_template("{role|unknown}: {content} {tool|}", &props),
+            "unknown: Retrying"
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_and_unterminated_placeholder() {
+        let props = properties(&[("content", json!("x"))]);
+
+        assert_eq!(render_embedding_template("{{literal}} {content}", &props), "{literal} x");
+        assert_eq!(render_embedding_template("{content} {oops", &props), "x {oops");
+    }
+}
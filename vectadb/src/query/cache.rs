@@ -0,0 +1,192 @@
+// Query result cache: dashboards re-issue identical hybrid queries on every refresh
+
+use anyhow::Result;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::types::{HybridQuery, QueryResult};
+use crate::ttl_map::TtlMap;
+
+/// Most results held at once; beyond this, new results are not cached
+const MAX_ENTRIES: usize = 1000;
+
+/// Remembers each query's result for `ttl`. Writes do not invalidate
+/// entries, so a cached result may be up to `ttl` stale. A zero TTL disables
+/// caching.
+pub struct QueryCache {
+    ttl: Duration,
+    entries: Mutex<TtlMap<QueryResult>>,
+}
+
+impl QueryCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(TtlMap::new(ttl, MAX_ENTRIES)),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The cached result of `query` if it is still fresh, otherwise the
+    /// result of `execute`, which is cached when it succeeds. Cached results
+    /// come back with `metadata.cache_hit` set.
+    pub async fn get_or_execute<F, Fut>(
+        &self,
+        query: &HybridQuery,
+        execute: F,
+    ) -> Result<QueryResult>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<QueryResult>>,
+    {
+        if self.ttl.is_zero() {
+            return execute().await;
+        }
+
+        let key = cache_key(query);
+        if let Some(mut result) = self.get(&key, Instant::now()) {
+            result.metadata.cache_hit = true;
+            return Ok(result);
+        }
+
+        let result = execute().await?;
+        self.insert(key, result.clone(), Instant::now());
+        Ok(result)
+    }
+
+    fn get(&self, key: &str, now: Instant) -> Option<QueryResult> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(key, now).cloned()
+    }
+
+    fn insert(&self, key: String, result: QueryResult, now: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(key, result, now);
+    }
+}
+
+/// Canonical JSON of the query: object keys sorted, so map fields such as
+/// property filters compare equal whatever their iteration order, and search
+/// text whitespace-normalized, so queries differing only in spacing share an
+/// entry. The whole string is the key, so distinct queries never collide.
+fn cache_key(query: &HybridQuery) -> String {
+    let mut query = query.clone();
+    let vector_query = match &mut query {
+        HybridQuery::Vector(vq) => Some(vq),
+        HybridQuery::Combined(cq) => Some(&mut cq.vector_query),
+        HybridQuery::Graph(_) => None,
+    };
+    if let Some(vq) = vector_query {
+        vq.query_text = vq
+            .query_text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    let mut canonical = serde_json::to_value(&query).unwrap_or_default();
+    canonical.sort_all_objects();
+    canonical.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::types::QueryMetadata;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn vector_query(text: &str) -> HybridQuery {
+        serde_json::from_value(serde_json::json!({
+            "type": "Vector",
+            "entity_type": "Log",
+            "query_text": text,
+        }))
+        .unwrap()
+    }
+
+    /// Stands in for the databases: counts executions
+    async fn execute(calls: &AtomicUsize) -> Result<QueryResult> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Ok(QueryResult {
+            results: Vec::new(),
+            total_count: 0,
+            metadata: QueryMetadata {
+                execution_time_ms: 0,
+                vector_count: Some(0),
+                graph_count: None,
+                searched_types: None,
+                traversed_relations: None,
+                cache_hit: false,
+                limit: None,
+                explanation: None,
+                extra: HashMap::new(),
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_repeat_query_within_ttl_is_served_from_cache() {
+        let cache = QueryCache::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+
+        let first = cache
+            .get_or_execute(&vector_query("database errors"), || execute(&calls))
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_execute(&vector_query("  database   errors "), || execute(&calls))
+            .await
+            .unwrap();
+        assert!(!first.metadata.cache_hit);
+        assert!(second.metadata.cache_hit);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A different query executes
+        cache
+            .get_or_execute(&vector_query("cache misses"), || execute(&calls))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_cache_key_is_canonical() {
+        let sparse = |terms: serde_json::Value| -> HybridQuery {
+            serde_json::from_value(serde_json::json!({
+                "type": "Vector",
+                "entity_type": "Log",
+                "query_text": "timeout",
+                "sparse_vector": terms,
+            }))
+            .unwrap()
+        };
+
+        let forward = sparse(serde_json::json!({ "timeout": 0.8, "gateway": 0.3, "retry": 0.1 }));
+        let reversed = sparse(serde_json::json!({ "retry": 0.1, "gateway": 0.3, "timeout": 0.8 }));
+        assert_eq!(cache_key(&forward), cache_key(&reversed));
+
+        let reweighted = sparse(serde_json::json!({ "timeout": 0.8, "gateway": 0.3, "retry": 0.2 }));
+        assert_ne!(cache_key(&forward), cache_key(&reweighted));
+        assert_ne!(cache_key(&forward), cache_key(&vector_query("timeout")));
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_cache() {
+        let cache = QueryCache::new(Duration::ZERO);
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let result = cache
+                .get_or_execute(&vector_query("database errors"), || execute(&calls))
+                .await
+                .unwrap();
+            assert!(!result.metadata.cache_hit);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,17 +1,40 @@
 // Query coordinator for hybrid query execution
 
 use anyhow::{Context, Result};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, RwLock};
+use tracing::{debug, info, instrument, warn};
 
-use crate::db::{Entity, QdrantClient, SurrealDBClient};
+use crate::db::{CollectionMetaCache, Entity, QdrantClient, Relation, SurrealDBClient};
 use crate::embeddings::EmbeddingManager;
+use crate::error::VectaDBError;
 use crate::intelligence::OntologyReasoner;
+use super::cache::QueryCache;
+use super::keyword;
 use super::types::*;
 
+/// Keyword search ranks this many candidates per requested result
+const KEYWORD_CANDIDATE_FACTOR: usize = 20;
+
+/// Similarity hits of a vector query
+struct VectorHits {
+    /// Entity IDs with scores, best first
+    hits: Vec<(String, f32)>,
+    /// Types searched, after ontology expansion
+    search_types: Vec<String>,
+    /// Hits each type returned, in `search_types` order
+    per_type: Vec<usize>,
+}
+
+impl VectorHits {
+    fn candidates_per_type(&self) -> BTreeMap<String, usize> {
+        self.search_types.iter().cloned().zip(self.per_type.iter().copied()).collect()
+    }
+}
+
 /// Coordinator for executing hybrid queries combining vector search,
 /// graph traversal, and ontology reasoning
 pub struct QueryCoordinator {
@@ -19,6 +42,8 @@ pub struct QueryCoordinator {
     qdrant: Arc<QdrantClient>,
     reasoner: Arc<RwLock<Option<OntologyReasoner>>>,
     embedding_service: Arc<EmbeddingManager>,
+    collection_meta: Arc<CollectionMetaCache>,
+    cache: QueryCache,
 }
 
 impl QueryCoordinator {
@@ -28,29 +53,48 @@ impl QueryCoordinator {
         qdrant: Arc<QdrantClient>,
         reasoner: Arc<RwLock<Option<OntologyReasoner>>>,
         embedding_service: Arc<EmbeddingManager>,
+        collection_meta: Arc<CollectionMetaCache>,
     ) -> Self {
         Self {
             surreal,
             qdrant,
             reasoner,
             embedding_service,
+            collection_meta,
+            cache: QueryCache::new(Duration::ZERO),
         }
     }
 
-    /// Execute a hybrid query
+    /// Cache query results for `ttl`; zero disables the cache
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache = QueryCache::new(ttl);
+        self
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        self.cache.ttl()
+    }
+
+    /// Execute a hybrid query, or return its cached result
+    #[instrument(name = "hybrid_query", skip_all)]
     pub async fn execute(&self, query: &HybridQuery) -> Result<QueryResult> {
         let start_time = Instant::now();
 
-        let result = match query {
-            HybridQuery::Vector(vq) => self.execute_vector_query(vq).await?,
-            HybridQuery::Graph(gq) => self.execute_graph_query(gq).await?,
-            HybridQuery::Combined(cq) => self.execute_combined_query(cq).await?,
-        };
+        let mut result = self
+            .cache
+            .get_or_execute(query, || async {
+                match query {
+                    HybridQuery::Vector(vq) => self.execute_vector_query(vq).await,
+                    HybridQuery::Graph(gq) => self.execute_graph_query(gq).await,
+                    HybridQuery::Combined(cq) => self.execute_combined_query(cq).await,
+                }
+            })
+            .await?;
 
         // Add execution time
         let execution_time_ms = start_time.elapsed().as_millis() as u64;
-        let mut result = result;
         result.metadata.execution_time_ms = execution_time_ms;
+        result.metadata.limit = Some(query.limit());
 
         info!(
             "Query executed in {}ms, returned {} results",
@@ -62,92 +106,311 @@ impl QueryCoordinator {
     }
 
     // ============================================================================
-    // Vector Search
+    // Streaming
     // ============================================================================
 
-    /// Execute a pure vector similarity search
-    async fn execute_vector_query(&self, query: &VectorQuery) -> Result<QueryResult> {
-        debug!("Executing vector query for type: {}", query.entity_type);
-
-        // Generate query embedding
-        let query_vector = self
-            .embedding_service
-            .embed(&query.query_text)
-            .await
-            .context("Failed to generate query embedding")?;
+    /// Execute a hybrid query, sending results through `sender` as they are
+    /// found: vector hits first (best first), then graph-expanded entities.
+    ///
+    /// Results are not merged or re-ranked. Returns the final summary once all
+    /// results have been sent; stops early if the receiver is dropped.
+    #[instrument(name = "hybrid_query_stream", skip_all)]
+    pub async fn execute_streaming(
+        &self,
+        query: &HybridQuery,
+        sender: mpsc::Sender<ScoredResult>,
+    ) -> Result<StreamSummary> {
+        let start_time = Instant::now();
+        let mut sent = HashSet::new();
 
-        // Expand entity types if requested
-        let search_types = if query.expand_types {
-            self.expand_entity_types(&query.entity_type).await?
-        } else {
-            vec![query.entity_type.clone()]
+        let (vector_query, graph_query) = match query {
+            HybridQuery::Vector(vq) => (Some(vq), None),
+            HybridQuery::Graph(gq) => (None, Some(gq)),
+            HybridQuery::Combined(cq) => (Some(&cq.vector_query), cq.graph_query.as_ref()),
         };
 
-        debug!("Searching types: {:?}", search_types);
-
-        // Search across all types
-        let mut all_results: HashMap<String, f32> = HashMap::new();
+        let mut metadata = QueryMetadata {
+            execution_time_ms: 0,
+            vector_count: None,
+            graph_count: None,
+            searched_types: None,
+            traversed_relations: None,
+            cache_hit: false,
+            limit: Some(query.limit()),
+            explanation: None,
+            extra: HashMap::new(),
+        };
 
-        for entity_type in &search_types {
-            match self
-                .qdrant
-                .search_similar_with_scores(entity_type, query_vector.clone(), query.limit)
-                .await
-            {
-                Ok(results) => {
-                    for (entity_id, score) in results {
-                        // Apply score threshold
-                        if let Some(min_score) = query.min_score {
-                            if score < min_score {
-                                continue;
-                            }
-                        }
-                        all_results.insert(entity_id, score);
+        let mut candidates_per_type = BTreeMap::new();
+        if let Some(vq) = vector_query {
+            let vector_hits = self.vector_hits(vq).await?;
+            candidates_per_type = vector_hits.candidates_per_type();
+            let VectorHits { hits, search_types, .. } = vector_hits;
+            let mut count = 0;
+            if vq.count_only {
+                count = hits.len();
+            }
+            for (entity_id, score) in hits {
+                if vq.count_only || count >= vq.limit() {
+                    break;
+                }
+                if let Some(entity) = self.surreal.get_entity(&entity_id).await? {
+                    sent.insert(entity.id_string());
+                    if sender.send(vector_result(entity, score)).await.is_err() {
+                        return Ok(StreamSummary::new(sent.len(), metadata, start_time));
                     }
+                    count += 1;
                 }
-                Err(e) => {
-                    warn!("Failed to search in type {}: {}", entity_type, e);
+            }
+            metadata.vector_count = Some(count);
+            metadata.searched_types = Some(search_types);
+        }
+
+        if let Some(gq) = graph_query {
+            let graph_result = self.execute_graph_query(gq).await?;
+            // Count-only graph queries come back without results
+            let mut count = if gq.count_only { graph_result.total_count } else { 0 };
+            for result in graph_result.results {
+                if !sent.insert(result.entity.id_string()) {
+                    continue;
                 }
+                if sender.send(result).await.is_err() {
+                    break;
+                }
+                count += 1;
             }
+            metadata.graph_count = Some(count);
+            metadata.traversed_relations = graph_result.metadata.traversed_relations;
+        }
+
+        // Streamed results are not merged, only deduplicated
+        if query.explain() {
+            metadata.explanation = Some(QueryExplanation {
+                candidates_per_type,
+                vector_count: metadata.vector_count,
+                graph_count: metadata.graph_count,
+                merged_count: sent.len(),
+                merge_strategy: None,
+            });
+        }
+
+        let summary = StreamSummary::new(sent.len(), metadata, start_time);
+        info!(
+            "Streaming query executed in {}ms, sent {} results",
+            summary.metadata.execution_time_ms, summary.total_count
+        );
+
+        Ok(summary)
+    }
+
+    // ============================================================================
+    // Vector Search
+    // ============================================================================
+
+    /// Execute a pure vector similarity search
+    async fn execute_vector_query(&self, query: &VectorQuery) -> Result<QueryResult> {
+        debug!("Executing vector query for type: {}", query.entity_type);
+
+        let vector_hits = self.vector_hits(query).await?;
+        let explain = |vector_count: usize, merged_count: usize| {
+            query.explain.then(|| QueryExplanation {
+                candidates_per_type: vector_hits.candidates_per_type(),
+                vector_count: Some(vector_count),
+                merged_count,
+                ..Default::default()
+            })
+        };
+        let hits = &vector_hits.hits;
+        let search_types = vector_hits.search_types.clone();
+
+        let keyword = query.keyword.as_deref().map(str::trim).filter(|k| !k.is_empty());
+        if query.count_only && keyword.is_none() {
+            let mut result = count_result(hits.len(), Some(search_types), None);
+            result.metadata.explanation = explain(hits.len(), hits.len());
+            return Ok(result);
         }
 
         // Fetch entities from SurrealDB
         let mut scored_results = Vec::new();
-        for (entity_id, score) in all_results {
-            if let Some(entity) = self.surreal.get_entity(&entity_id).await? {
-                scored_results.push(ScoredResult {
-                    entity,
-                    score,
-                    source: ResultSource::Vector,
-                    explanation: Some(format!(
-                        "Vector similarity: {:.3}",
-                        score
-                    )),
-                });
+        for (entity_id, score) in hits {
+            if let Some(entity) = self.surreal.get_entity(entity_id).await? {
+                scored_results.push(vector_result(entity, *score));
             }
         }
 
         // Sort by score descending
         scored_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 
+        let vector_count = scored_results.len();
+        let mut keyword_count = None;
+        if let Some(keyword) = keyword {
+            let keyword_results = self.keyword_hits(query, keyword, &search_types).await?;
+            keyword_count = Some(keyword_results.len());
+            if !keyword_results.is_empty() {
+                scored_results = reciprocal_rank_fusion(scored_results, keyword_results);
+                scored_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+            }
+            if query.count_only {
+                let mut result = count_result(scored_results.len(), Some(search_types), None);
+                result.metadata.vector_count = Some(vector_count);
+                result.metadata.explanation = explain(vector_count, scored_results.len());
+                if let Some(count) = keyword_count {
+                    result.metadata.extra.insert("keyword_count".to_string(), count.to_string());
+                }
+                return Ok(result);
+            }
+        }
+
         // Apply limit
         let total_count = scored_results.len();
-        scored_results.truncate(query.limit);
+        let explanation = explain(vector_count, total_count);
+        scored_results.truncate(query.limit());
+
+        let mut extra = HashMap::new();
+        if let Some(count) = keyword_count {
+            extra.insert("keyword_count".to_string(), count.to_string());
+        }
 
         Ok(QueryResult {
             results: scored_results,
             total_count,
             metadata: QueryMetadata {
                 execution_time_ms: 0, // Will be filled by caller
-                vector_count: Some(total_count),
+                vector_count: Some(vector_count),
                 graph_count: None,
                 searched_types: Some(search_types),
                 traversed_relations: None,
-                extra: HashMap::new(),
+                cache_hit: false,
+                limit: None,
+                explanation,
+                extra,
             },
         })
     }
 
+    /// Entities matching `keyword` literally, best first: an entity whose ID is
+    /// the keyword, then BM25 over the text of entities containing its terms
+    async fn keyword_hits(
+        &self,
+        query: &VectorQuery,
+        keyword: &str,
+        search_types: &[String],
+    ) -> Result<Vec<ScoredResult>> {
+        let terms = keyword::query_terms(keyword);
+        let mut ids = vec![keyword.to_string()];
+        ids.extend(keyword.split_whitespace().map(str::to_string));
+
+        let time_range = query.time_range();
+        let candidates: Vec<Entity> = self
+            .surreal
+            .keyword_candidates(search_types, &ids, &terms, query.limit() * KEYWORD_CANDIDATE_FACTOR)
+            .await?
+            .into_iter()
+            .filter(|entity| time_range.contains(entity.created_at.0))
+            .collect();
+
+        let documents: Vec<(String, String)> = candidates
+            .iter()
+            .map(|entity| (entity.id_string(), keyword::property_text(&entity.properties)))
+            .collect();
+        let ranked = keyword::rank(keyword, &documents);
+        let top = ranked.first().map(|(_, score)| *score).unwrap_or(1.0);
+
+        let mut candidates: Vec<Option<Entity>> = candidates.into_iter().map(Some).collect();
+        Ok(ranked
+            .into_iter()
+            .take(query.limit())
+            .filter_map(|(index, score)| {
+                candidates[index].take().map(|entity| ScoredResult {
+                    entity,
+                    score: score / top,
+                    source: ResultSource::Keyword,
+                    explanation: Some(format!("Keyword match for '{}'", keyword)),
+                })
+            })
+            .collect())
+    }
+
+    /// Run the similarity search, returning entity IDs with scores (best first)
+    /// and the entity types that were searched
+    async fn vector_hits(&self, query: &VectorQuery) -> Result<VectorHits> {
+        let time_range = query.time_range();
+        time_range.validate()?;
+
+        // Sparse vectors are stored with the main embedding only
+        let sparse_terms: Option<HashMap<String, f32>> = query
+            .sparse_vector
+            .as_ref()
+            .filter(|terms| !terms.is_empty())
+            .map(|terms| terms.iter().map(|(term, weight)| (term.clone(), *weight)).collect());
+        if sparse_terms.is_some() && query.vector_name.is_some() {
+            return Err(VectaDBError::BadRequest(
+                "sparse_vector can't be combined with vector_name".to_string(),
+            )
+            .into());
+        }
+
+        // Expand entity types if requested
+        let search_types = if query.expand_types {
+            self.expand_entity_types(&query.entity_type).await?
+        } else {
+            vec![query.entity_type.clone()]
+        };
+
+        debug!("Searching types: {:?}", search_types);
+
+        // Each type is searched with a vector from the provider that indexed it
+        let type_vectors = self
+            .embedding_service
+            .embed_for_types(&search_types, &query.query_text)
+            .await
+            .context("Failed to generate query embedding")?;
+
+        // Fail clearly if a collection was built by another provider or with a different dimension
+        for entity_type in &search_types {
+            if let Some(meta) = self.collection_meta.get(&self.surreal, entity_type).await? {
+                meta.validate_provider(self.embedding_service.provider_for(entity_type))?;
+                meta.validate_dimension(type_vectors[entity_type].len())?;
+            }
+        }
+
+        // Search across all types concurrently
+        let candidates = per_type_candidates(query.limit(), query.candidate_multiplier, search_types.len());
+        let (hits, per_type) = search_types_concurrently(&search_types, query.min_score, |entity_type| {
+            let query_vector = type_vectors[entity_type].clone();
+            let sparse_terms = sparse_terms.as_ref();
+            let vector_name = query.vector_name.as_deref();
+            let time_range = &time_range;
+            async move {
+                match (sparse_terms, vector_name) {
+                    (Some(terms), _) => {
+                        self.qdrant
+                            .search_hybrid(entity_type, query_vector, terms, candidates, time_range)
+                            .await
+                    }
+                    // Named vectors are stored on the entity's point beside its main embedding
+                    (None, Some(name)) => {
+                        self.qdrant
+                            .search_named_vector(entity_type, name, query_vector, candidates, time_range)
+                            .await
+                    }
+                    (None, None) => {
+                        self.qdrant
+                            .search_similar_with_scores(entity_type, query_vector, candidates, time_range)
+                            .await
+                    }
+                }
+            }
+        })
+        .await;
+
+        Ok(VectorHits {
+            hits,
+            search_types,
+            per_type,
+        })
+    }
+
     // ============================================================================
     // Graph Traversal
     // ============================================================================
@@ -168,6 +431,24 @@ impl QueryCoordinator {
 
         debug!("Traversing relations: {:?}", relation_types);
 
+        let time_range = query.time_range();
+        time_range.validate()?;
+        let explain = |graph_count: usize| {
+            query.explain.then(|| QueryExplanation {
+                graph_count: Some(graph_count),
+                merged_count: graph_count,
+                ..Default::default()
+            })
+        };
+
+        // Time filtering needs each entity's created_at, so only unbounded counts skip hydration
+        if query.count_only && !time_range.is_bounded() {
+            let count = self.count_reachable(query, &relation_types).await?;
+            let mut result = count_result(count, None, Some(relation_types));
+            result.metadata.explanation = explain(count);
+            return Ok(result);
+        }
+
         // Perform traversal based on direction
         let entities = match query.direction {
             TraversalDirection::Outgoing => {
@@ -194,15 +475,22 @@ impl QueryCoordinator {
         let mut seen = HashSet::new();
         let mut unique_entities = Vec::new();
         for entity in entities {
-            if seen.insert(entity.id.clone()) {
+            if seen.insert(entity.id.clone()) && time_range.contains(entity.created_at.0) {
                 unique_entities.push(entity);
             }
         }
 
+        if query.count_only {
+            let mut result = count_result(unique_entities.len(), None, Some(relation_types));
+            result.metadata.explanation = explain(unique_entities.len());
+            return Ok(result);
+        }
+
         // Convert to scored results (graph results don't have similarity scores)
         let total_count = unique_entities.len();
         let scored_results: Vec<ScoredResult> = unique_entities
             .into_iter()
+            .take(query.limit())
             .enumerate()
             .map(|(i, entity)| {
                 // Score based on inverse of distance from start (closer = higher score)
@@ -225,11 +513,124 @@ impl QueryCoordinator {
                 graph_count: Some(total_count),
                 searched_types: None,
                 traversed_relations: Some(relation_types),
+                cache_hit: false,
+                limit: None,
+                explanation: explain(total_count),
                 extra: HashMap::new(),
             },
         })
     }
 
+    /// Count entities reachable within the query depth without fetching them
+    async fn count_reachable(&self, query: &GraphQuery, relation_types: &[String]) -> Result<usize> {
+        let start = &query.start_entity_id;
+        let mut reachable = HashSet::new();
+
+        if query.direction != TraversalDirection::Incoming {
+            reachable.extend(
+                reachable_ids(start, query.depth, |id| self.neighbor_ids(id, relation_types, false))
+                    .await?,
+            );
+        }
+        if query.direction != TraversalDirection::Outgoing {
+            reachable.extend(
+                reachable_ids(start, query.depth, |id| self.neighbor_ids(id, relation_types, true))
+                    .await?,
+            );
+        }
+
+        Ok(reachable.len())
+    }
+
+    /// IDs of live entities one hop away along the given relation types (all
+    /// if empty). Like the traversal, this skips tombstoned and missing
+    /// endpoints, so counts match the entities a full query returns.
+    async fn neighbor_ids(
+        &self,
+        entity_id: String,
+        relation_types: &[String],
+        incoming: bool,
+    ) -> Result<Vec<String>> {
+        let types: Vec<Option<&str>> = if relation_types.is_empty() {
+            vec![None]
+        } else {
+            relation_types.iter().map(|t| Some(t.as_str())).collect()
+        };
+
+        let mut ids = Vec::new();
+        for rel_type in types {
+            if incoming {
+                let relations = self.surreal.get_incoming_relations(&entity_id, rel_type).await?;
+                ids.extend(relations.into_iter().map(|r| r.source_id));
+            } else {
+                let relations = self.surreal.get_outgoing_relations(&entity_id, rel_type).await?;
+                ids.extend(relations.into_iter().map(|r| r.target_id));
+            }
+        }
+
+        let live = self.surreal.existing_entity_ids(&ids).await?;
+        ids.retain(|id| live.contains(id));
+        Ok(ids)
+    }
+
+    /// Extract the neighborhood of an entity: every entity within `depth` hops
+    /// along `direction` and the relations connecting them, capped at `max_nodes`
+    pub async fn subgraph(
+        &self,
+        start_id: &str,
+        depth: usize,
+        direction: TraversalDirection,
+        relation_type: Option<&str>,
+        max_nodes: usize,
+    ) -> Result<Subgraph> {
+        let (node_ids, mut edges, truncated) = walk_subgraph(start_id, depth, max_nodes, |id| {
+            self.adjacent_relations(id, direction, relation_type)
+        })
+        .await?;
+
+        let mut nodes = Vec::with_capacity(node_ids.len());
+        for id in &node_ids {
+            if let Some(entity) = self.surreal.get_entity(id).await? {
+                nodes.push(entity);
+            }
+        }
+
+        // Relations can outlive their endpoints; keep only edges between returned nodes
+        let present: HashSet<String> = nodes.iter().map(|n| n.id_string()).collect();
+        edges.retain(|r| present.contains(&r.source_id) && present.contains(&r.target_id));
+
+        debug!(
+            "Subgraph of {}: {} nodes, {} edges (truncated: {})",
+            start_id,
+            nodes.len(),
+            edges.len(),
+            truncated
+        );
+
+        Ok(Subgraph {
+            nodes,
+            edges,
+            truncated,
+        })
+    }
+
+    /// Relations touching an entity in the given direction
+    async fn adjacent_relations(
+        &self,
+        entity_id: String,
+        direction: TraversalDirection,
+        relation_type: Option<&str>,
+    ) -> Result<Vec<Relation>> {
+        let mut relations = Vec::new();
+        if direction != TraversalDirection::Incoming {
+            relations.extend(self.surreal.get_outgoing_relations(&entity_id, relation_type).await?);
+        }
+        if direction != TraversalDirection::Outgoing {
+            relations.extend(self.surreal.get_incoming_relations(&entity_id, relation_type).await?);
+        }
+        Ok(relations)
+    }
+
     /// Traverse outgoing edges
     async fn traverse_outgoing(
         &self,
@@ -366,24 +767,37 @@ impl QueryCoordinator {
     async fn execute_combined_query(&self, query: &CombinedQuery) -> Result<QueryResult> {
         debug!("Executing combined query with strategy: {:?}", query.merge_strategy);
 
+        // Merging needs the entities, so sub-queries always hydrate
+        let mut vector_query = query.vector_query.clone();
+        vector_query.count_only = false;
+        vector_query.explain = query.explain;
+        let mut count_only = query.vector_query.count_only;
+
         // Execute vector search
-        let vector_result = self.execute_vector_query(&query.vector_query).await?;
+        let vector_result = self.execute_vector_query(&vector_query).await?;
 
         // If no graph query, return vector results
         let graph_result = if let Some(ref graph_query) = query.graph_query {
-            Some(self.execute_graph_query(graph_query).await?)
+            let mut graph_query = graph_query.clone();
+            count_only |= graph_query.count_only;
+            graph_query.count_only = false;
+            Some(self.execute_graph_query(&graph_query).await?)
         } else {
             None
         };
 
         // Merge results based on strategy
-        let merged = self.merge_results(
+        let mut merged = self.merge_results(
             vector_result,
             graph_result,
             query.merge_strategy,
-            query.vector_query.limit,
+            query.vector_query.limit(),
         );
 
+        if count_only {
+            merged.results.clear();
+        }
+
         Ok(merged)
     }
 
@@ -399,6 +813,13 @@ impl QueryCoordinator {
             Some(r) => r,
             None => return vector_result,
         };
+        let vector_count = vector_result.results.len();
+        let graph_count = graph_result.results.len();
+        let candidates_per_type = vector_result
+            .metadata
+            .explanation
+            .as_ref()
+            .map(|explanation| explanation.candidates_per_type.clone());
 
         let mut merged_results = match strategy {
             MergeStrategy::Union => self.merge_union(vector_result.results, graph_result.results),
@@ -406,7 +827,7 @@ impl QueryCoordinator {
                 self.merge_intersection(vector_result.results, graph_result.results)
             }
             MergeStrategy::RankFusion => {
-                self.merge_rank_fusion(vector_result.results, graph_result.results)
+                reciprocal_rank_fusion(vector_result.results, graph_result.results)
             }
             MergeStrategy::VectorPriority => {
                 self.merge_vector_priority(vector_result.results, graph_result.results)
@@ -429,9 +850,20 @@ impl QueryCoordinator {
             graph_count: graph_result.metadata.graph_count,
             searched_types: vector_result.metadata.searched_types,
             traversed_relations: graph_result.metadata.traversed_relations,
+            cache_hit: false,
+            limit: None,
+            explanation: None,
             extra: HashMap::new(),
         };
         metadata.extra.insert("merge_strategy".to_string(), format!("{:?}", strategy));
+        // Only explained vector queries carry per-type candidates
+        metadata.explanation = candidates_per_type.map(|candidates_per_type| QueryExplanation {
+            candidates_per_type,
+            vector_count: Some(vector_count),
+            graph_count: Some(graph_count),
+            merged_count: total_count,
+            merge_strategy: Some(strategy),
+        });
 
         QueryResult {
             results: merged_results,
@@ -489,48 +921,6 @@ impl QueryCoordinator {
             .collect()
     }
 
-    /// Rank fusion merge: combine using reciprocal rank fusion
-    fn merge_rank_fusion(
-        &self,
-        vector_results: Vec<ScoredResult>,
-        graph_results: Vec<ScoredResult>,
-    ) -> Vec<ScoredResult> {
-        let mut scores: HashMap<String, f32> = HashMap::new();
-        let mut entities: HashMap<String, Entity> = HashMap::new();
-
-        // Reciprocal Rank Fusion constant
-        const K: f32 = 60.0;
-
-        // Add vector ranks
-        for (rank, result) in vector_results.iter().enumerate() {
-            let rrf_score = 1.0 / (K + rank as f32 + 1.0);
-            let entity_id = result.entity.id_string();
-            scores.insert(entity_id.clone(), rrf_score);
-            entities.insert(entity_id, result.entity.clone());
-        }
-
-        // Add graph ranks
-        for (rank, result) in graph_results.iter().enumerate() {
-            let rrf_score = 1.0 / (K + rank as f32 + 1.0);
-            let entity_id = result.entity.id_string();
-            scores
-                .entry(entity_id.clone())
-                .and_modify(|s| *s += rrf_score)
-                .or_insert(rrf_score);
-            entities.insert(entity_id, result.entity.clone());
-        }
-
-        scores
-            .into_iter()
-            .map(|(entity_id, score)| ScoredResult {
-                entity: entities.get(&entity_id).unwrap().clone(),
-                score,
-                source: ResultSource::Hybrid,
-                explanation: Some("Ranked by reciprocal rank fusion".to_string()),
-            })
-            .collect()
-    }
-
     /// Vector priority: filter vector results by graph connectivity
     fn merge_vector_priority(
         &self,
@@ -628,6 +1018,214 @@ impl QueryCoordinator {
     }
 }
 
+/// Result of a `count_only` query: the count without any entities
+fn count_result(
+    count: usize,
+    searched_types: Option<Vec<String>>,
+    traversed_relations: Option<Vec<String>>,
+) -> QueryResult {
+    QueryResult {
+        results: Vec::new(),
+        total_count: count,
+        metadata: QueryMetadata {
+            execution_time_ms: 0,
+            vector_count: searched_types.as_ref().map(|_| count),
+            graph_count: traversed_relations.as_ref().map(|_| count),
+            searched_types,
+            traversed_relations,
+            cache_hit: false,
+            limit: None,
+            explanation: None,
+            extra: HashMap::new(),
+        },
+    }
+}
+
+/// Breadth-first walk over entity IDs up to `depth` hops, where `neighbors`
+/// returns the IDs one hop away. The start entity is not included.
+async fn reachable_ids<F, Fut>(start_id: &str, depth: usize, mut neighbors: F) -> Result<HashSet<String>>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<Vec<String>>>,
+{
+    let mut seen = HashSet::from([start_id.to_string()]);
+    let mut current_level = vec![start_id.to_string()];
+
+    for _ in 0..depth {
+        let mut next_level = Vec::new();
+        for entity_id in current_level {
+            for neighbor in neighbors(entity_id).await? {
+                if seen.insert(neighbor.clone()) {
+                    next_level.push(neighbor);
+                }
+            }
+        }
+        if next_level.is_empty() {
+            break;
+        }
+        current_level = next_level;
+    }
+
+    seen.remove(start_id);
+    Ok(seen)
+}
+
+/// Breadth-first walk that collects both entity IDs and the relations crossed,
+/// where `relations` returns the relations touching an entity. Returns the node
+/// IDs in discovery order (start first), the deduplicated edges between them,
+/// and whether `max_nodes` cut the walk short.
+async fn walk_subgraph<F, Fut>(
+    start_id: &str,
+    depth: usize,
+    max_nodes: usize,
+    mut relations: F,
+) -> Result<(Vec<String>, Vec<Relation>, bool)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<Vec<Relation>>>,
+{
+    let mut nodes = vec![start_id.to_string()];
+    let mut seen = HashSet::from([start_id.to_string()]);
+    let mut edges = Vec::new();
+    let mut seen_edges = HashSet::new();
+    let mut truncated = false;
+    let mut current_level = vec![start_id.to_string()];
+
+    for _ in 0..depth {
+        let mut next_level = Vec::new();
+        for entity_id in current_level {
+            for relation in relations(entity_id.clone()).await? {
+                let other = if relation.source_id == entity_id {
+                    &relation.target_id
+                } else {
+                    &relation.source_id
+                };
+                if !seen.contains(other) {
+                    if nodes.len() >= max_nodes {
+                        truncated = true;
+                        continue;
+                    }
+                    seen.insert(other.clone());
+                    nodes.push(other.clone());
+                    next_level.push(other.clone());
+                }
+                if seen_edges.insert(relation.id_string()) {
+                    edges.push(relation);
+                }
+            }
+        }
+        if next_level.is_empty() {
+            break;
+        }
+        current_level = next_level;
+    }
+
+    Ok((nodes, edges, truncated))
+}
+
+/// Combine two ranked result lists using reciprocal rank fusion
+fn reciprocal_rank_fusion(first: Vec<ScoredResult>, second: Vec<ScoredResult>) -> Vec<ScoredResult> {
+    let mut scores: HashMap<String, f32> = HashMap::new();
+    let mut entities: HashMap<String, Entity> = HashMap::new();
+
+    // Reciprocal Rank Fusion constant
+    const K: f32 = 60.0;
+
+    // Add ranks from the first list
+    for (rank, result) in first.iter().enumerate() {
+        let rrf_score = 1.0 / (K + rank as f32 + 1.0);
+        let entity_id = result.entity.id_string();
+        scores.insert(entity_id.clone(), rrf_score);
+        entities.insert(entity_id, result.entity.clone());
+    }
+
+    // Add ranks from the second list
+    for (rank, result) in second.iter().enumerate() {
+        let rrf_score = 1.0 / (K + rank as f32 + 1.0);
+        let entity_id = result.entity.id_string();
+        scores
+            .entry(entity_id.clone())
+            .and_modify(|s| *s += rrf_score)
+            .or_insert(rrf_score);
+        entities.insert(entity_id, result.entity.clone());
+    }
+
+    scores
+        .into_iter()
+        .map(|(entity_id, score)| ScoredResult {
+            entity: entities.get(&entity_id).unwrap().clone(),
+            score,
+            source: ResultSource::Hybrid,
+            explanation: Some("Ranked by reciprocal rank fusion".to_string()),
+        })
+        .collect()
+}
+
+/// Run `search` for every type at once and merge the hits, best first.
+/// Hits below `min_score` are dropped; an entity found under several types
+/// keeps the score from the last of them. A failed type is logged and skipped.
+async fn search_types_concurrently<'a, F, Fut>(
+    search_types: &'a [String],
+    min_score: Option<f32>,
+    search: F,
+) -> (Vec<(String, f32)>, Vec<usize>)
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<Vec<(String, f32)>>>,
+{
+    let searches = search_types.iter().map(|entity_type| search(entity_type));
+    let outcomes = futures::future::join_all(searches).await;
+
+    let mut all_results: HashMap<String, f32> = HashMap::new();
+    let mut per_type = Vec::with_capacity(search_types.len());
+    for (entity_type, outcome) in search_types.iter().zip(outcomes) {
+        let mut count = 0;
+        match outcome {
+            Ok(results) => {
+                for (entity_id, score) in results {
+                    // Apply score threshold
+                    if let Some(min_score) = min_score {
+                        if score < min_score {
+                            continue;
+                        }
+                    }
+                    all_results.insert(entity_id, score);
+                    count += 1;
+                }
+            }
+            Err(e) => {
+                warn!("Failed to search in type {}: {}", entity_type, e);
+            }
+        }
+        per_type.push(count);
+    }
+
+    let mut hits: Vec<(String, f32)> = all_results.into_iter().collect();
+    hits.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    (hits, per_type)
+}
+
+/// How many hits each type's search fetches: a single type needs only
+/// `limit`, while a multi-type search over-fetches so the global top results
+/// survive the merge
+fn per_type_candidates(limit: usize, multiplier: usize, type_count: usize) -> usize {
+    if type_count > 1 {
+        limit.saturating_mul(multiplier.max(1))
+    } else {
+        limit
+    }
+}
+
+/// Wrap an entity found by similarity search as a scored result
+fn vector_result(entity: Entity, score: f32) -> ScoredResult {
+    ScoredResult {
+        entity,
+        score,
+        source: ResultSource::Vector,
+        explanation: Some(format!("Vector similarity: {:.3}", score)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -637,4 +1235,345 @@ mod tests {
         // Test that merge strategies are correctly defined
         assert_eq!(MergeStrategy::default(), MergeStrategy::RankFusion);
     }
+
+    #[test]
+    fn test_keyword_match_surfaces_low_similarity_entity() {
+        let result = |name: &str, score: f32, source: ResultSource| {
+            let mut entity = Entity::new("Incident".to_string(), HashMap::new());
+            entity.id = surrealdb::sql::Thing::from(("entity".to_string(), name.to_string()));
+            ScoredResult {
+                entity,
+                score,
+                source,
+                explanation: None,
+            }
+        };
+
+        // The pasted ID is the least similar vector hit, but the only keyword hit
+        let vector: Vec<ScoredResult> = [("a", 0.92), ("b", 0.9), ("c", 0.88), ("d", 0.85), ("inc42", 0.2)]
+            .into_iter()
+            .map(|(id, score)| result(id, score, ResultSource::Vector))
+            .collect();
+        let keyword = vec![result("inc42", 1.0, ResultSource::Keyword)];
+
+        let mut fused = reciprocal_rank_fusion(vector, keyword);
+        fused.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        assert_eq!(fused.len(), 5);
+        assert_eq!(fused[0].entity.id_string(), "inc42");
+        assert_eq!(fused[1].entity.id_string(), "a");
+    }
+
+    #[tokio::test]
+    async fn test_search_types_concurrently_merges_all_types() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let types: Vec<String> = ["Agent", "Planner", "Executor", "Broken"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let queried = Mutex::new(Vec::new());
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        // Mock Qdrant: each type holds one hit, and "Planner" also finds the shared entity
+        let search = |entity_type: &str| {
+            queried.lock().unwrap().push(entity_type.to_string());
+            let entity_type = entity_type.to_string();
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                match entity_type.as_str() {
+                    "Broken" => Err(anyhow::anyhow!("collection missing")),
+                    "Agent" => Ok(vec![("a1".to_string(), 0.9), ("shared".to_string(), 0.4)]),
+                    "Planner" => Ok(vec![("p1".to_string(), 0.3), ("shared".to_string(), 0.7)]),
+                    _ => Ok(vec![("e1".to_string(), 0.8)]),
+                }
+            }
+        };
+
+        let (hits, per_type) = search_types_concurrently(&types, Some(0.5), search).await;
+        assert_eq!(queried.lock().unwrap().len(), types.len());
+        assert_eq!(per_type, vec![1, 1, 1, 0]);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), types.len());
+        assert_eq!(
+            hits,
+            vec![("a1".to_string(), 0.9), ("e1".to_string(), 0.8), ("shared".to_string(), 0.7)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_candidate_multiplier_keeps_top_results_from_one_type() {
+        let types = vec!["Planner".to_string(), "Executor".to_string()];
+        // Planner holds every top hit, but its second-best entity was deleted
+        let planner = [("p1", 0.95), ("p2", 0.9), ("p3", 0.85), ("p4", 0.8)];
+        let executor = [("e1", 0.4), ("e2", 0.3), ("e3", 0.2)];
+        let deleted = "p2";
+        let limit = 3;
+
+        let top = |multiplier: usize| {
+            let candidates = per_type_candidates(limit, multiplier, types.len());
+            let types = &types;
+            async move {
+                let (hits, _) = search_types_concurrently(types, None, |entity_type| {
+                    let stored: &[(&str, f32)] = if entity_type == "Planner" { &planner } else { &executor };
+                    let found: Vec<(String, f32)> = stored
+                        .iter()
+                        .take(candidates)
+                        .map(|(id, score)| (id.to_string(), *score))
+                        .collect();
+                    async move { Ok(found) }
+                })
+                .await;
+                // Hydration drops the deleted entity, then the merge is truncated
+                hits.into_iter()
+                    .filter(|(id, _)| id != deleted)
+                    .take(limit)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(top(1).await, vec!["p1", "p3", "e1"]);
+        assert_eq!(top(3).await, vec!["p1", "p3", "p4"]);
+        assert_eq!(per_type_candidates(limit, 3, 1), limit);
+    }
+
+    #[tokio::test]
+    async fn test_reachable_ids_respects_depth_and_cycles() {
+        let edges: HashMap<&str, Vec<&str>> = HashMap::from([
+            ("a", vec!["b", "c"]),
+            ("b", vec!["d", "a"]),
+            ("c", vec!["d"]),
+            ("d", vec!["e"]),
+        ]);
+        let neighbors = |id: String| {
+            let ids = edges
+                .get(id.as_str())
+                .map(|ns| ns.iter().map(|n| n.to_string()).collect())
+                .unwrap_or_default();
+            async move { Ok(ids) }
+        };
+
+        let one_hop = reachable_ids("a", 1, neighbors).await.unwrap();
+        assert_eq!(one_hop, HashSet::from(["b".to_string(), "c".to_string()]));
+
+        let two_hops = reachable_ids("a", 2, neighbors).await.unwrap();
+        assert_eq!(two_hops.len(), 3);
+        assert!(!two_hops.contains("a"));
+
+        let all = reachable_ids("a", 10, neighbors).await.unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_walk_subgraph_collects_nodes_and_edges() {
+        // a -> b -> c -> d, e -> a, b -> e
+        let graph: Vec<Relation> = [("a", "b"), ("b", "c"), ("c", "d"), ("e", "a"), ("b", "e")]
+            .iter()
+            .map(|(s, t)| Relation::new("next".to_string(), s.to_string(), t.to_string(), HashMap::new()))
+            .collect();
+        let edge_set = |edges: &[Relation]| -> HashSet<(String, String)> {
+            edges.iter().map(|r| (r.source_id.clone(), r.target_id.clone())).collect()
+        };
+        let pairs = |items: &[(&str, &str)]| -> HashSet<(String, String)> {
+            items.iter().map(|(s, t)| (s.to_string(), t.to_string())).collect()
+        };
+        let adjacent = |direction: TraversalDirection| {
+            let graph = &graph;
+            move |id: String| {
+                let relations: Vec<Relation> = graph
+                    .iter()
+                    .filter(|r| {
+                        (direction != TraversalDirection::Incoming && r.source_id == id)
+                            || (direction != TraversalDirection::Outgoing && r.target_id == id)
+                    })
+                    .cloned()
+                    .collect();
+                async move { Ok(relations) }
+            }
+        };
+
+        let (nodes, edges, truncated) = walk_subgraph("a", 2, 100, adjacent(TraversalDirection::Both))
+            .await
+            .unwrap();
+        assert_eq!(nodes[0], "a");
+        assert_eq!(
+            nodes.iter().cloned().collect::<HashSet<_>>(),
+            HashSet::from(["a", "b", "c", "e"].map(String::from))
+        );
+        assert_eq!(edges.len(), 4, "each edge is listed once");
+        assert_eq!(
+            edge_set(&edges),
+            pairs(&[("a", "b"), ("b", "c"), ("e", "a"), ("b", "e")])
+        );
+        assert!(!truncated);
+
+        let (nodes, edges, _) = walk_subgraph("a", 2, 100, adjacent(TraversalDirection::Outgoing))
+            .await
+            .unwrap();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(edge_set(&edges), pairs(&[("a", "b"), ("b", "c"), ("b", "e")]));
+
+        let (nodes, edges, truncated) = walk_subgraph("a", 2, 2, adjacent(TraversalDirection::Both))
+            .await
+            .unwrap();
+        assert_eq!(nodes, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(edge_set(&edges), pairs(&[("a", "b")]));
+        assert!(truncated);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires SurrealDB, Qdrant and the local embedding model
+    async fn test_count_only_matches_full_query() {
+        use crate::config::{DatabaseConfig, EmbeddingConfig, LongTextStrategy, QdrantConfig, SurrealDBConfig};
+        use crate::db::Relation;
+
+        let qdrant_config = QdrantConfig {
+            url: "http://localhost:6333".to_string(),
+            api_key: None,
+            collection_prefix: "test_".to_string(),
+            warmup_collections: false,
+            collection: Default::default(),
+        };
+        let db_config = DatabaseConfig {
+            surrealdb: SurrealDBConfig {
+                endpoint: "ws://localhost:8000".to_string(),
+                namespace: "test".to_string(),
+                database: "test".to_string(),
+                username: "root".to_string(),
+                password: "root".to_string(),
+                pool_size: 1,
+            },
+            qdrant: qdrant_config.clone(),
+        };
+        let surreal = Arc::new(SurrealDBClient::new(&db_config).await.unwrap());
+        let qdrant = Arc::new(QdrantClient::new(&qdrant_config).await.unwrap());
+        let embeddings = Arc::new(
+            EmbeddingManager::new(EmbeddingConfig {
+                model: "all-MiniLM-L6-v2".to_string(),
+                dim: 384,
+                provider: "local".to_string(),
+                plugin_config_dir: "./config/embeddings".to_string(),
+                fallback_to_local: false,
+                fallback_providers: Vec::new(),
+                type_providers: HashMap::new(),
+                verify_dim: true,
+                max_concurrent_requests: 8,
+                max_input_chars: 16_000,
+                long_text_strategy: LongTextStrategy::Truncate,
+                normalize: false,
+            })
+            .await
+            .unwrap(),
+        );
+        let coordinator = QueryCoordinator::new(
+            surreal.clone(),
+            qdrant.clone(),
+            Arc::new(RwLock::new(None)),
+            embeddings.clone(),
+            Arc::new(CollectionMetaCache::default()),
+        );
+
+        // A chain of three CountTest entities with embeddings
+        let _ = qdrant.delete_collection("CountTest").await;
+        qdrant.create_collection("CountTest", 384).await.unwrap();
+        let mut ids = Vec::new();
+        for text in ["database timeout", "database connection refused", "cache miss"] {
+            let mut properties = HashMap::new();
+            properties.insert("message".to_string(), serde_json::json!(text));
+            let id = surreal
+                .create_entity(&Entity::new("CountTest".to_string(), properties))
+                .await
+                .unwrap();
+            let embedding = embeddings.embed(text).await.unwrap();
+            qdrant
+                .upsert_embedding("CountTest", &id, embedding, chrono::Utc::now())
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        for pair in ids.windows(2) {
+            let relation = Relation::new("next".to_string(), pair[0].clone(), pair[1].clone(), HashMap::new());
+            surreal.create_relation(&relation).await.unwrap();
+        }
+
+        let vector = |count_only| {
+            HybridQuery::Vector(VectorQuery {
+                entity_type: "CountTest".to_string(),
+                query_text: "database errors".to_string(),
+                keyword: None,
+                vector_name: None,
+                sparse_vector: None,
+                limit: Some(10),
+                expand_types: false,
+                candidate_multiplier: 3,
+                min_score: None,
+                start_time: None,
+                end_time: None,
+                count_only,
+                explain: false,
+            })
+        };
+        let graph = |count_only| {
+            HybridQuery::Graph(GraphQuery {
+                start_entity_id: ids[0].clone(),
+                relation_types: vec!["next".to_string()],
+                depth: 3,
+                expand_relations: false,
+                direction: TraversalDirection::Outgoing,
+                limit: None,
+                start_time: None,
+                end_time: None,
+                count_only,
+                explain: false,
+            })
+        };
+
+        for make in [&vector as &dyn Fn(bool) -> HybridQuery, &graph] {
+            let full = coordinator.execute(&make(false)).await.unwrap();
+            let counted = coordinator.execute(&make(true)).await.unwrap();
+            assert_eq!(counted.total_count, full.results.len());
+            assert!(counted.results.is_empty());
+            assert!(full.metadata.explanation.is_none());
+        }
+
+        // An explained combined query reports per-stage counts that add up
+        let (HybridQuery::Vector(vector_query), HybridQuery::Graph(graph_query)) = (vector(false), graph(false)) else {
+            unreachable!()
+        };
+        let combined = HybridQuery::Combined(CombinedQuery {
+            vector_query,
+            graph_query: Some(graph_query),
+            merge_strategy: MergeStrategy::Union,
+            explain: true,
+        });
+        let result = coordinator.execute(&combined).await.unwrap();
+        let explanation = result.metadata.explanation.expect("explain fills in the explanation");
+        assert_eq!(explanation.candidates_per_type.keys().collect::<Vec<_>>(), vec!["CountTest"]);
+        let vector_count = explanation.vector_count.unwrap();
+        let graph_count = explanation.graph_count.unwrap();
+        assert!(vector_count <= explanation.candidates_per_type["CountTest"]);
+        assert_eq!(graph_count, ids.len() - 1);
+        assert!(explanation.merged_count >= vector_count.max(graph_count));
+        assert!(explanation.merged_count <= vector_count + graph_count);
+        assert_eq!(explanation.merged_count, result.total_count);
+        assert_eq!(explanation.merge_strategy, Some(MergeStrategy::Union));
+
+        // A soft-deleted neighbour is neither returned nor counted
+        surreal.delete_entity(&ids[2]).await.unwrap();
+        let full = coordinator.execute(&graph(false)).await.unwrap();
+        let counted = coordinator.execute(&graph(true)).await.unwrap();
+        assert_eq!(full.results.len(), 1);
+        assert_eq!(counted.total_count, 1);
+
+        // Cleanup
+        for id in &ids {
+            let _ = surreal.purge_entity(id).await;
+        }
+        let _ = qdrant.delete_collection("CountTest").await;
+    }
 }
@@ -0,0 +1,137 @@
+// Keyword scoring for hybrid retrieval: catches exact IDs and codes that embeddings blur
+
+use std::collections::{HashMap, HashSet};
+
+/// At most this many query terms are matched
+pub const MAX_TERMS: usize = 8;
+
+/// BM25 term-frequency saturation
+const K1: f32 = 1.2;
+
+/// BM25 document-length normalization
+const B: f32 = 0.75;
+
+/// Lowercased alphanumeric terms of `text`; `_` and `-` stay inside terms so
+/// identifiers and error codes like `ERR_TIMEOUT-42` remain whole
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Distinct terms of a keyword query, capped at `MAX_TERMS`
+pub fn query_terms(keyword: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokenize(keyword)
+        .into_iter()
+        .filter(|t| seen.insert(t.clone()))
+        .take(MAX_TERMS)
+        .collect()
+}
+
+/// Searchable text of an entity's properties: every string, number and
+/// boolean value, however deeply nested
+pub fn property_text(properties: &HashMap<String, serde_json::Value>) -> String {
+    fn collect(value: &serde_json::Value, parts: &mut Vec<String>) {
+        match value {
+            serde_json::Value::String(s) => parts.push(s.clone()),
+            serde_json::Value::Number(n) => parts.push(n.to_string()),
+            serde_json::Value::Bool(b) => parts.push(b.to_string()),
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect(v, parts)),
+            serde_json::Value::Object(map) => map.values().for_each(|v| collect(v, parts)),
+            serde_json::Value::Null => {}
+        }
+    }
+
+    let mut parts = Vec::new();
+    for value in properties.values() {
+        collect(value, &mut parts);
+    }
+    parts.join(" ")
+}
+
+/// Score `documents` (ID and text) against `keyword` with BM25, returning
+/// `(index, score)` pairs best first. A document whose ID is the keyword
+/// itself outranks every text match. Documents matching nothing are dropped.
+pub fn rank(keyword: &str, documents: &[(String, String)]) -> Vec<(usize, f32)> {
+    let keyword = keyword.trim();
+    let terms = query_terms(keyword);
+    let docs: Vec<Vec<String>> = documents.iter().map(|(_, text)| tokenize(text)).collect();
+    if docs.is_empty() {
+        return Vec::new();
+    }
+
+    let avg_len = docs.iter().map(Vec::len).sum::<usize>() as f32 / docs.len() as f32;
+    let doc_freq: HashMap<&str, usize> = terms
+        .iter()
+        .map(|term| (term.as_str(), docs.iter().filter(|d| d.contains(term)).count()))
+        .collect();
+    let n = docs.len() as f32;
+
+    let mut scored: Vec<(usize, f32)> = Vec::new();
+    for (index, doc) in docs.iter().enumerate() {
+        let mut score = 0.0;
+        for term in &terms {
+            let tf = doc.iter().filter(|t| *t == term).count() as f32;
+            if tf == 0.0 {
+                continue;
+            }
+            let df = doc_freq[term.as_str()] as f32;
+            let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+            let norm = 1.0 - B + B * doc.len() as f32 / avg_len.max(1.0);
+            score += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+        }
+
+        // An exact ID match beats any amount of text overlap
+        if documents[index].0 == keyword {
+            score += 1000.0;
+        }
+        if score > 0.0 {
+            scored.push((index, score));
+        }
+    }
+
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then(a.0.cmp(&b.0)));
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn docs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(id, text)| (id.to_string(), text.to_string())).collect()
+    }
+
+    #[test]
+    fn test_tokenize_keeps_identifiers_whole() {
+        assert_eq!(
+            tokenize("Failed: ERR_TIMEOUT-42 (retry 3)"),
+            vec!["failed", "err_timeout-42", "retry", "3"]
+        );
+        assert_eq!(query_terms("db db DB cache"), vec!["db", "cache"]);
+    }
+
+    #[test]
+    fn test_rank_prefers_rare_terms_and_exact_ids() {
+        let documents = docs(&[
+            ("a1", "database connection refused"),
+            ("a2", "database timeout while reading database pages"),
+            ("a3", "ERR_TIMEOUT-42 raised by the cache"),
+            ("a4", "unrelated text"),
+        ]);
+
+        // The rare code outranks the common word
+        let ranked = rank("database ERR_TIMEOUT-42", &documents);
+        assert_eq!(ranked[0].0, 2);
+        assert!(ranked.iter().all(|(i, _)| *i != 3));
+
+        // An entity ID pasted verbatim comes first even without text overlap
+        let ranked = rank("a4", &documents);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].0, 3);
+
+        assert!(rank("nothing matches", &documents).is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 // Query execution module
 
+pub mod cache;
 pub mod coordinator;
+pub mod keyword;
 pub mod types;
 
 pub use coordinator::QueryCoordinator;
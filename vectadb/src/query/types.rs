@@ -1,12 +1,16 @@
 // Query types for hybrid query execution
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::db::Entity;
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+
+use crate::config::QueryConfig;
+use crate::db::{Entity, Relation, TimeRange};
 
 /// Hybrid query request combining multiple search strategies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum HybridQuery {
     /// Pure vector similarity search
@@ -19,8 +23,43 @@ pub enum HybridQuery {
     Combined(CombinedQuery),
 }
 
+impl HybridQuery {
+    /// Whether per-stage counts were asked for
+    pub fn explain(&self) -> bool {
+        match self {
+            HybridQuery::Vector(vq) => vq.explain,
+            HybridQuery::Graph(gq) => gq.explain,
+            HybridQuery::Combined(cq) => cq.explain,
+        }
+    }
+
+    /// Most results the query returns
+    pub fn limit(&self) -> usize {
+        match self {
+            HybridQuery::Vector(vq) => vq.limit(),
+            HybridQuery::Graph(gq) => gq.limit(),
+            HybridQuery::Combined(cq) => cq.vector_query.limit(),
+        }
+    }
+
+    /// Resolve every result limit in the query against the server's default
+    /// and maximum, returning the limit applied to the final results
+    pub fn apply_limits(&mut self, config: &QueryConfig) -> usize {
+        match self {
+            HybridQuery::Vector(vq) => *vq.limit.insert(config.limit(vq.limit)),
+            HybridQuery::Graph(gq) => *gq.limit.insert(config.limit(gq.limit)),
+            HybridQuery::Combined(cq) => {
+                if let Some(gq) = cq.graph_query.as_mut() {
+                    gq.limit = Some(config.limit(gq.limit));
+                }
+                *cq.vector_query.limit.insert(config.limit(cq.vector_query.limit))
+            }
+        }
+    }
+}
+
 /// Vector similarity search query
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VectorQuery {
     /// Entity type to search
     pub entity_type: String,
@@ -28,21 +67,69 @@ pub struct VectorQuery {
     /// Query text to embed and search
     pub query_text: String,
 
-    /// Maximum number of results
-    #[serde(default = "default_limit")]
-    pub limit: usize,
+    /// Keywords matched literally (entity IDs, error codes) and fused with
+    /// the vector results by reciprocal rank fusion
+    #[serde(default)]
+    pub keyword: Option<String>,
+
+    /// Search this named vector instead of the entities' main embedding
+    #[serde(default)]
+    pub vector_name: Option<String>,
+
+    /// Term weights of the query (SPLADE, BM25). When given, entities' sparse
+    /// vectors are searched alongside their embeddings and Qdrant fuses the
+    /// two rankings by reciprocal rank fusion
+    #[serde(default)]
+    pub sparse_vector: Option<BTreeMap<String, f32>>,
+
+    /// Maximum number of results; the server's default limit when absent
+    #[serde(default)]
+    pub limit: Option<usize>,
 
     /// Expand to include subtypes using ontology
     #[serde(default)]
     pub expand_types: bool,
 
+    /// When several types are searched, each fetches `limit * candidate_multiplier`
+    /// candidates before the global merge, so the true top results survive
+    /// hits dropped after it (tombstoned entities, fusion). Higher values
+    /// improve recall at the cost of larger searches and more hydration.
+    #[serde(default = "default_candidate_multiplier")]
+    pub candidate_multiplier: usize,
+
     /// Minimum similarity score threshold
     #[serde(default)]
     pub min_score: Option<f32>,
+
+    /// Only match vectors stamped at or after this time
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+
+    /// Only match vectors stamped before this time
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+
+    /// Only return `total_count`, skipping entity hydration
+    #[serde(default)]
+    pub count_only: bool,
+
+    /// Report per-stage counts in `metadata.explanation`
+    #[serde(default)]
+    pub explain: bool,
+}
+
+impl VectorQuery {
+    pub fn time_range(&self) -> TimeRange {
+        TimeRange::new(self.start_time, self.end_time)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or_else(default_limit)
+    }
 }
 
 /// Graph traversal query
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GraphQuery {
     /// Starting entity ID
     pub start_entity_id: String,
@@ -62,10 +149,40 @@ pub struct GraphQuery {
     /// Direction of traversal
     #[serde(default)]
     pub direction: TraversalDirection,
+
+    /// Maximum number of results, nearest first; the server's default limit when absent
+    #[serde(default)]
+    pub limit: Option<usize>,
+
+    /// Only return entities created at or after this time
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+
+    /// Only return entities created before this time
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+
+    /// Only return `total_count`, skipping entity hydration
+    #[serde(default)]
+    pub count_only: bool,
+
+    /// Report per-stage counts in `metadata.explanation`
+    #[serde(default)]
+    pub explain: bool,
+}
+
+impl GraphQuery {
+    pub fn time_range(&self) -> TimeRange {
+        TimeRange::new(self.start_time, self.end_time)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or_else(default_limit)
+    }
 }
 
 /// Combined vector and graph query
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CombinedQuery {
     /// Vector search component
     pub vector_query: VectorQuery,
@@ -76,10 +193,14 @@ pub struct CombinedQuery {
     /// How to merge results
     #[serde(default)]
     pub merge_strategy: MergeStrategy,
+
+    /// Report per-stage counts in `metadata.explanation`
+    #[serde(default)]
+    pub explain: bool,
 }
 
 /// Direction for graph traversal
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub enum TraversalDirection {
     /// Follow outgoing edges
     Outgoing,
@@ -98,7 +219,7 @@ impl Default for TraversalDirection {
 }
 
 /// Strategy for merging multiple result sets
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub enum MergeStrategy {
     /// Union of all results (deduplicated)
     Union,
@@ -123,7 +244,7 @@ impl Default for MergeStrategy {
 }
 
 /// Query execution result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryResult {
     /// Matching entities with scores
     pub results: Vec<ScoredResult>,
@@ -136,7 +257,7 @@ pub struct QueryResult {
 }
 
 /// Entity with relevance score
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ScoredResult {
     /// The entity
     pub entity: Entity,
@@ -153,7 +274,7 @@ pub struct ScoredResult {
 }
 
 /// Source of a query result
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub enum ResultSource {
     /// From vector similarity search
     Vector,
@@ -161,12 +282,15 @@ pub enum ResultSource {
     /// From graph traversal
     Graph,
 
+    /// From keyword matching
+    Keyword,
+
     /// From both sources (hybrid)
     Hybrid,
 }
 
 /// Query execution metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryMetadata {
     /// Total execution time in milliseconds
     pub execution_time_ms: u64,
@@ -187,16 +311,89 @@ pub struct QueryMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub traversed_relations: Option<Vec<String>>,
 
+    /// Served from the query cache rather than executed
+    #[serde(default)]
+    pub cache_hit: bool,
+
+    /// Result limit applied, after the server's default and maximum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+
+    /// Per-stage counts, for queries run with `explain`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<QueryExplanation>,
+
     /// Additional metadata
     #[serde(flatten)]
     pub extra: HashMap<String, String>,
 }
 
+/// How a query arrived at its results, stage by stage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct QueryExplanation {
+    /// Vector hits each searched type returned (after `min_score`), before
+    /// hits found in several types are deduplicated
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub candidates_per_type: BTreeMap<String, usize>,
+
+    /// Vector results going into the merge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vector_count: Option<usize>,
+
+    /// Graph results going into the merge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph_count: Option<usize>,
+
+    /// Results after merging, before the limit is applied
+    pub merged_count: usize,
+
+    /// Strategy used to merge vector and graph results, if both ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<MergeStrategy>,
+}
+
+/// Final summary sent after all results of a streamed query
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamSummary {
+    /// Number of results sent
+    pub total_count: usize,
+
+    /// Query execution metadata
+    pub metadata: QueryMetadata,
+}
+
+impl StreamSummary {
+    pub fn new(total_count: usize, mut metadata: QueryMetadata, start_time: std::time::Instant) -> Self {
+        metadata.execution_time_ms = start_time.elapsed().as_millis() as u64;
+        Self {
+            total_count,
+            metadata,
+        }
+    }
+}
+
+/// Entities and relations within a number of hops of a start entity
+#[derive(Debug, Clone)]
+pub struct Subgraph {
+    /// Entities reached, starting with the start entity
+    pub nodes: Vec<Entity>,
+
+    /// Relations between the returned entities, each listed once
+    pub edges: Vec<Relation>,
+
+    /// Whether the node limit stopped the walk before it was exhausted
+    pub truncated: bool,
+}
+
 // Default values
 fn default_limit() -> usize {
     10
 }
 
+fn default_candidate_multiplier() -> usize {
+    3
+}
+
 fn default_depth() -> usize {
     2
 }
@@ -213,9 +410,10 @@ mod tests {
         }"#;
 
         let query: VectorQuery = serde_json::from_str(json).unwrap();
-        assert_eq!(query.limit, 10);
+        assert_eq!(query.limit(), 10);
         assert!(!query.expand_types);
         assert!(query.min_score.is_none());
+        assert!(!query.count_only);
     }
 
     #[test]
@@ -230,6 +428,40 @@ mod tests {
         assert!(!query.expand_relations);
     }
 
+    #[test]
+    fn test_apply_limits_defaults_and_clamps() {
+        let config = QueryConfig {
+            default_limit: 25,
+            max_limit: 100,
+            ..Default::default()
+        };
+        let parse = |json: &str| -> HybridQuery { serde_json::from_str(json).unwrap() };
+
+        // Absent limits take the configured default
+        let mut query = parse(r#"{"type": "Vector", "entity_type": "Agent", "query_text": "test"}"#);
+        assert_eq!(query.apply_limits(&config), 25);
+
+        // Oversized and zero limits are clamped
+        let mut query = parse(r#"{"type": "Graph", "start_entity_id": "a", "limit": 1000000}"#);
+        assert_eq!(query.apply_limits(&config), 100);
+        let HybridQuery::Graph(graph) = &query else { unreachable!() };
+        assert_eq!(graph.limit, Some(100));
+
+        let mut query = parse(r#"{"type": "Vector", "entity_type": "Agent", "query_text": "test", "limit": 0}"#);
+        assert_eq!(query.apply_limits(&config), 1);
+
+        // Both halves of a combined query are bounded
+        let mut query = parse(
+            r#"{"type": "Combined",
+                "vector_query": {"entity_type": "Agent", "query_text": "test", "limit": 500},
+                "graph_query": {"start_entity_id": "a"}}"#,
+        );
+        assert_eq!(query.apply_limits(&config), 100);
+        let HybridQuery::Combined(combined) = &query else { unreachable!() };
+        assert_eq!(combined.vector_query.limit, Some(100));
+        assert_eq!(combined.graph_query.as_ref().unwrap().limit, Some(25));
+    }
+
     #[test]
     fn test_merge_strategy_default() {
         let strategy = MergeStrategy::default();
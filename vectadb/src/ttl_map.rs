@@ -0,0 +1,117 @@
+// Bounded map whose entries expire: backs the idempotency store and the query cache
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+struct Entry<V> {
+    value: V,
+    expires_at: Instant,
+}
+
+/// Map of string keys to values that expire `ttl` after they are inserted,
+/// holding at most `capacity` live entries. Every entry lives for the same
+/// `ttl`, so insertion order is expiry order: each insert evicts the expired
+/// keys at the front of a queue instead of scanning the whole map. Not
+/// synchronized; callers keep it behind their own lock.
+pub struct TtlMap<V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<String, Entry<V>>,
+    /// Keys with the expiry they were inserted with, oldest first. A key
+    /// inserted again leaves its earlier item behind, skipped on eviction.
+    expiries: VecDeque<(Instant, String)>,
+}
+
+impl<V> TtlMap<V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: HashMap::new(),
+            expiries: VecDeque::new(),
+        }
+    }
+
+    /// The value for `key`, unless it is missing or expired
+    pub fn get(&self, key: &str, now: Instant) -> Option<&V> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| &entry.value)
+    }
+
+    /// Store `value` under `key` until `now + ttl`, replacing any earlier
+    /// value. Returns `false`, storing nothing, when the map already holds
+    /// `capacity` live entries and `key` is not one of them.
+    pub fn insert(&mut self, key: String, value: V, now: Instant) -> bool {
+        self.evict_expired(now);
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            return false;
+        }
+
+        let expires_at = now + self.ttl;
+        self.expiries.push_back((expires_at, key.clone()));
+        self.entries.insert(key, Entry { value, expires_at });
+        true
+    }
+
+    fn evict_expired(&mut self, now: Instant) {
+        while self.expiries.front().is_some_and(|(expires_at, _)| *expires_at <= now) {
+            let Some((expires_at, key)) = self.expiries.pop_front() else {
+                break;
+            };
+            if self.entries.get(&key).is_some_and(|entry| entry.expires_at == expires_at) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let start = Instant::now();
+        let mut map = TtlMap::new(Duration::from_secs(60), 10);
+
+        assert!(map.insert("a".to_string(), 1, start));
+        assert_eq!(map.get("a", start + Duration::from_secs(59)), Some(&1));
+        assert_eq!(map.get("a", start + Duration::from_secs(60)), None);
+        assert_eq!(map.get("b", start), None);
+    }
+
+    #[test]
+    fn test_full_map_refuses_new_keys_until_one_expires() {
+        let start = Instant::now();
+        let mut map = TtlMap::new(Duration::from_secs(60), 2);
+        map.insert("a".to_string(), 1, start);
+        map.insert("b".to_string(), 2, start + Duration::from_secs(30));
+
+        // Live keys can still be replaced
+        assert!(!map.insert("c".to_string(), 3, start + Duration::from_secs(31)));
+        assert!(map.insert("b".to_string(), 20, start + Duration::from_secs(31)));
+        assert_eq!(map.get("b", start + Duration::from_secs(31)), Some(&20));
+
+        // Once "a" expires there is room again
+        let later = start + Duration::from_secs(60);
+        assert!(map.insert("c".to_string(), 3, later));
+        assert_eq!(map.get("c", later), Some(&3));
+        assert_eq!(map.get("a", later), None);
+        assert!(!map.insert("d".to_string(), 4, later));
+    }
+
+    #[test]
+    fn test_reinserted_key_keeps_its_new_expiry() {
+        let start = Instant::now();
+        let mut map = TtlMap::new(Duration::from_secs(60), 10);
+        map.insert("a".to_string(), 1, start);
+        map.insert("a".to_string(), 2, start + Duration::from_secs(30));
+
+        // Evicting the first insert's expiry leaves the second in place
+        let later = start + Duration::from_secs(61);
+        map.insert("b".to_string(), 3, later);
+        assert_eq!(map.get("a", later), Some(&2));
+    }
+}
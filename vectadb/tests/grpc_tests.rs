@@ -0,0 +1,87 @@
+// Integration tests for the VectaDB gRPC interface
+
+use std::sync::Arc;
+
+use vectadb::api::grpc::{self, proto::vecta_db_client::VectaDbClient, proto::Event};
+use vectadb::api::handlers::AppState;
+use vectadb::config::{DatabaseConfig, QdrantConfig, SurrealDBConfig};
+use vectadb::db::{EventFilter, SurrealDBClient};
+
+fn test_database() -> DatabaseConfig {
+    DatabaseConfig {
+        surrealdb: SurrealDBConfig {
+            endpoint: "ws://localhost:8000".to_string(),
+            namespace: "test".to_string(),
+            database: "test".to_string(),
+            username: "root".to_string(),
+            password: "root".to_string(),
+            pool_size: 1,
+        },
+        qdrant: QdrantConfig {
+            url: "http://localhost:6333".to_string(),
+            api_key: None,
+            collection_prefix: "test_".to_string(),
+            warmup_collections: false,
+            collection: Default::default(),
+        },
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires SurrealDB running
+async fn test_stream_events_are_stored() {
+    let surreal = Arc::new(SurrealDBClient::new(&test_database()).await.unwrap());
+    let mut state = AppState::new();
+    state.surreal = Some(surreal.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(grpc::serve(state, listener, std::future::pending()));
+
+    let mut client = VectaDbClient::connect(format!("http://{}", addr)).await.unwrap();
+    let trace_id = format!("grpc-test-{}", uuid::Uuid::new_v4());
+    let events: Vec<Event> = (0..3)
+        .map(|i| Event {
+            trace_id: Some(trace_id.clone()),
+            timestamp_ms: 1_767_787_200_000 + i,
+            event_type: Some("tool_call".to_string()),
+            agent_id: Some("grpc-agent".to_string()),
+            properties_json: serde_json::json!({ "step": i }).to_string(),
+            ..Default::default()
+        })
+        .chain(std::iter::once(Event {
+            trace_id: Some(trace_id.clone()),
+            properties_json: "not json".to_string(),
+            ..Default::default()
+        }))
+        .collect();
+
+    let summary = client
+        .ingest_events(futures::stream::iter(events))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(summary.ingested, 3);
+    assert_eq!(summary.failed, 1);
+    assert_eq!(summary.trace_ids, vec![trace_id.clone()]);
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.errors[0].index, 3);
+
+    let filter = EventFilter {
+        trace_id: Some(trace_id),
+        ..Default::default()
+    };
+    let stored = surreal.list_events(&filter, None, 0, 10).await.unwrap();
+    let mut steps: Vec<i64> = stored
+        .iter()
+        .map(|event| event["properties"]["step"].as_i64().unwrap())
+        .collect();
+    steps.sort();
+    assert_eq!(steps, vec![0, 1, 2]);
+
+    let ids: Vec<String> = stored
+        .iter()
+        .map(|event| event["id"].as_str().unwrap().to_string())
+        .collect();
+    let _ = surreal.delete_events(&ids).await;
+}